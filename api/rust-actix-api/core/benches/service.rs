@@ -0,0 +1,241 @@
+//! Service operations at 1k and 100k todos on each storage backend, for
+//! before/after numbers on performance work. Run with `make bench`, or
+//! `cargo bench -p spicy-todo-core -- get_all/journal` for a subset.
+//!
+//! `concurrent_list` compares copied and shared reads under parallel load,
+//! and prints how many allocations one list takes each way.
+
+use chrono::{Duration, NaiveDate, TimeZone, Utc};
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+use spicy_todo_core::models::{Priority, Todo, TodoCreate};
+use spicy_todo_core::{snapshot, Deadline, JournaledStore, TodoService};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use uuid::Uuid;
+
+/// The system allocator, counting allocations.
+struct CountingAlloc;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+/// Allocations made while running `f`, by any thread.
+fn allocations<T>(f: impl FnOnce() -> T) -> usize {
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    drop(f());
+    ALLOCATIONS.load(Ordering::Relaxed) - before
+}
+
+/// Readers listing the todos at once in `concurrent_list`.
+const READERS: usize = 4;
+
+const SIZES: [usize; 2] = [1_000, 100_000];
+const WORDS: [&str; 8] = [
+    "report",
+    "groceries",
+    "invoice",
+    "dentist",
+    "deploy",
+    "review",
+    "garden",
+    "taxes",
+];
+
+#[derive(Debug, Clone, Copy)]
+enum Backend {
+    Memory,
+    Journal,
+    Events,
+}
+
+impl Backend {
+    const ALL: [Backend; 3] = [Backend::Memory, Backend::Journal, Backend::Events];
+
+    fn name(self) -> &'static str {
+        match self {
+            Backend::Memory => "memory",
+            Backend::Journal => "journal",
+            Backend::Events => "events",
+        }
+    }
+
+    /// A service on this backend holding `todos`, keeping its files in
+    /// `dir`.
+    fn open(self, todos: &[Todo], dir: &Path) -> TodoService {
+        match self {
+            Backend::Memory => {
+                let service = TodoService::new_empty();
+                service.import(todos.to_vec());
+                service
+            }
+            Backend::Journal => {
+                // Loaded from a snapshot rather than journaled one by one,
+                // which would sync 100k times.
+                std::fs::create_dir_all(dir).unwrap();
+                snapshot::save(&dir.join("snapshot.json"), todos).unwrap();
+                let store = JournaledStore::open(dir, 1000).unwrap();
+                TodoService::with_store(Box::new(store))
+            }
+            Backend::Events => {
+                let service = TodoService::event_sourced(dir.join("events.jsonl")).unwrap();
+                service.import(todos.to_vec());
+                service
+            }
+        }
+    }
+}
+
+fn todos(count: usize) -> Vec<Todo> {
+    let created = Utc.with_ymd_and_hms(2024, 1, 1, 9, 0, 0).unwrap();
+    let today = NaiveDate::from_ymd_opt(2024, 6, 10).unwrap();
+    (0..count)
+        .map(|i| Todo {
+            id: Uuid::new_v4(),
+            text: format!("{} {} #{}", WORDS[i % WORDS.len()], i, WORDS[i % 3]),
+            priority: match i % 3 {
+                0 => Priority::Low,
+                1 => Priority::Medium,
+                _ => Priority::High,
+            },
+            completed: i % 4 == 0,
+            due_date: (i % 2 == 0)
+                .then(|| (today + Duration::days(i as i64 % 60 - 30)).to_string()),
+            reminder_time: None,
+            recurrence: None,
+            recurrence_end: None,
+            remaining_occurrences: None,
+            estimate_minutes: Some(15 + (i % 8) as u32 * 15),
+            location: None,
+            color: None,
+            icon: None,
+            pinned: false,
+            rollover_count: 0,
+            rolled_over_to: None,
+            created_at: created + Duration::seconds(i as i64),
+            updated_at: created + Duration::seconds(i as i64),
+        })
+        .collect()
+}
+
+fn input() -> TodoCreate {
+    TodoCreate {
+        text: "Benchmark todo".to_string(),
+        priority: Some(Priority::Medium),
+        due_date: Some("2024-06-10".to_string()),
+        estimate_minutes: Some(30),
+        ..Default::default()
+    }
+}
+
+fn temp_dir() -> PathBuf {
+    std::env::temp_dir().join(format!("spicy-bench-{}", Uuid::new_v4()))
+}
+
+fn service_operations(c: &mut Criterion) {
+    for size in SIZES {
+        let todos = todos(size);
+        for backend in Backend::ALL {
+            let dir = temp_dir();
+            let service = backend.open(&todos, &dir);
+            let id = BenchmarkId::new(backend.name(), size);
+
+            let mut group = c.benchmark_group("get_all");
+            if size > 10_000 {
+                group.sample_size(10);
+            }
+            group.bench_with_input(id.clone(), &service, |b, service| {
+                b.iter(|| service.get_all(None, None, None))
+            });
+            group.finish();
+
+            let mut group = c.benchmark_group("get_stats");
+            group.bench_with_input(id.clone(), &service, |b, service| {
+                b.iter(|| service.get_stats())
+            });
+            group.finish();
+
+            let mut group = c.benchmark_group("search");
+            if size > 10_000 {
+                group.sample_size(10);
+            }
+            group.bench_with_input(id.clone(), &service, |b, service| {
+                b.iter(|| {
+                    service.get_all(
+                        Some("active".to_string()),
+                        Some("invoice".to_string()),
+                        Some("high".to_string()),
+                    )
+                })
+            });
+            group.finish();
+
+            // Last, since it grows the collection the others read.
+            let mut group = c.benchmark_group("create");
+            group.bench_with_input(id, &service, |b, service| {
+                b.iter_batched(input, |input| service.create(input), BatchSize::SmallInput)
+            });
+            group.finish();
+
+            drop(service);
+            let _ = std::fs::remove_dir_all(&dir);
+        }
+    }
+}
+
+/// `GET /api/todos` without filters, as the handler reads it before and
+/// after sharing todos with the store, with `READERS` lists at a time.
+fn concurrent_list(c: &mut Criterion) {
+    for size in SIZES {
+        let service = Backend::Memory.open(&todos(size), &temp_dir());
+        let copied = || service.get_all_until(None, None, None, &Deadline::unbounded());
+        let shared = || service.get_all_shared_until(None, None, None, &Deadline::unbounded());
+        println!(
+            "concurrent_list/{}: {} allocations copied, {} shared",
+            size,
+            allocations(copied),
+            allocations(shared)
+        );
+
+        let mut group = c.benchmark_group("concurrent_list");
+        if size > 10_000 {
+            group.sample_size(10);
+        }
+        group.bench_function(BenchmarkId::new("copied", size), |b| {
+            b.iter(|| {
+                thread::scope(|scope| {
+                    for _ in 0..READERS {
+                        scope.spawn(copied);
+                    }
+                })
+            })
+        });
+        group.bench_function(BenchmarkId::new("shared", size), |b| {
+            b.iter(|| {
+                thread::scope(|scope| {
+                    for _ in 0..READERS {
+                        scope.spawn(shared);
+                    }
+                })
+            })
+        });
+        group.finish();
+    }
+}
+
+criterion_group!(benches, service_operations, concurrent_list);
+criterion_main!(benches);
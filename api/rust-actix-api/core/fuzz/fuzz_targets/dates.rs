@@ -0,0 +1,22 @@
+//! `cargo +nightly fuzz run dates` from `core/`.
+
+#![no_main]
+
+use chrono::NaiveDate;
+use libfuzzer_sys::fuzz_target;
+use spicy_todo_core::dates;
+
+fuzz_target!(|phrase: &str| {
+    // A leap day and a year end, where date arithmetic goes wrong first.
+    for today in [
+        NaiveDate::from_ymd_opt(2024, 2, 29).unwrap(),
+        NaiveDate::from_ymd_opt(2024, 12, 31).unwrap(),
+    ] {
+        if let Some(date) = dates::parse_date(phrase, today) {
+            let mut due_date = Some(date.to_string());
+            dates::normalize_due_date(&mut due_date, today).unwrap();
+            assert_eq!(due_date, Some(date.to_string()));
+        }
+    }
+    let _ = dates::parse_time(phrase);
+});
@@ -0,0 +1,16 @@
+//! `cargo +nightly fuzz run quick_add` from `core/`.
+
+#![no_main]
+
+use chrono::NaiveDate;
+use libfuzzer_sys::fuzz_target;
+use spicy_todo_core::quick_add;
+
+fuzz_target!(|line: &str| {
+    let today = NaiveDate::from_ymd_opt(2024, 6, 10).unwrap();
+    let parsed = quick_add::parse(line, today);
+    // Parsing only takes words out; it never makes text up.
+    for word in parsed.text.split_whitespace() {
+        assert!(line.contains(word), "{:?} not in {:?}", word, line);
+    }
+});
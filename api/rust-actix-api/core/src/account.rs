@@ -0,0 +1,158 @@
+use crate::cascade::CascadePolicy;
+use crate::deadline::{Deadline, DeadlineExceeded};
+use crate::events::{Event, EventFilter, EventType};
+use crate::models::{todo_id, Todo};
+use crate::rollover::MissedOccurrence;
+use crate::service::TodoService;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use uuid::Uuid;
+
+/// Everything the service holds about one user: the todos they created
+/// that still exist, with their missed occurrences, and every event they
+/// caused.
+#[derive(Debug, Serialize)]
+pub struct AccountExport {
+    pub user: String,
+    #[serde(rename = "exportedAt")]
+    pub exported_at: DateTime<Utc>,
+    pub todos: Vec<Todo>,
+    #[serde(rename = "missedOccurrences")]
+    pub missed_occurrences: Vec<MissedOccurrence>,
+    pub history: Vec<Event>,
+}
+
+/// What erasing a user did.
+#[derive(Debug, Default, PartialEq, Serialize)]
+pub struct AccountDeletion {
+    /// Their todos, deleted with their missed occurrences and with the
+    /// payload of their history redacted.
+    #[serde(rename = "deletedTodos")]
+    pub deleted_todos: Vec<String>,
+    /// Events that no longer name them as the actor.
+    #[serde(rename = "anonymizedEvents")]
+    pub anonymized_events: usize,
+}
+
+impl TodoService {
+    /// Ids of the todos `user` created that are still in the store.
+    pub(crate) fn created_by(&self, user: &str) -> Vec<Uuid> {
+        let filter = EventFilter {
+            event_types: vec![EventType::Created],
+            actor: Some(user.to_string()),
+            ..Default::default()
+        };
+        self.events()
+            .search(&filter)
+            .into_iter()
+            .map(|event| todo_id(&event.todo_id))
+            .filter(|id| self.store().get(*id).is_some())
+            .collect()
+    }
+
+    pub fn export_account(&self, user: &str) -> AccountExport {
+        let ids = self.created_by(user);
+        let todos = ids.iter().filter_map(|id| self.store().get(*id)).collect();
+        let missed_occurrences = self
+            .missed_log()
+            .iter()
+            .filter(|missed| ids.contains(&todo_id(&missed.todo_id)))
+            .cloned()
+            .collect();
+        let history = self.events().search(&EventFilter {
+            actor: Some(user.to_string()),
+            ..Default::default()
+        });
+        AccountExport {
+            user: user.to_string(),
+            exported_at: Utc::now(),
+            todos,
+            missed_occurrences,
+            history,
+        }
+    }
+
+    /// Erases `user`, all under one write lock: deletes the todos they
+    /// created as a delete cascading to everything would, then takes their
+    /// name off every event left, including the ones the deletes appended.
+    /// Todos others created keep the changes `user` made to them.
+    pub fn delete_account_until(
+        &self,
+        user: &str,
+        deadline: &Deadline,
+    ) -> Result<AccountDeletion, DeadlineExceeded> {
+        let everything = CascadePolicy {
+            missed_occurrences: true,
+            history: true,
+        };
+        let guard = self.write_lock(deadline)?;
+        let deleted_todos: Vec<String> = self
+            .created_by(user)
+            .into_iter()
+            .filter(|id| self.delete_cascading_locked(*id, &everything).is_some())
+            .map(|id| id.to_string())
+            .collect();
+        let anonymized_events = self.events().anonymize(user);
+        drop(guard);
+        if !deleted_todos.is_empty() || anonymized_events > 0 {
+            self.bump_version();
+        }
+        Ok(AccountDeletion {
+            deleted_todos,
+            anonymized_events,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cascade::REDACTED_TEXT;
+    use crate::context::RequestContext;
+    use crate::models::TodoCreate;
+
+    fn create_as(service: &TodoService, user: &str, text: &str) -> Todo {
+        let context = RequestContext {
+            user: Some(user.to_string()),
+            ..Default::default()
+        };
+        context.sync_scope(|| {
+            service.create(TodoCreate {
+                text: text.to_string(),
+                ..Default::default()
+            })
+        })
+    }
+
+    #[test]
+    fn test_exports_and_erases_one_user() {
+        let service = TodoService::new_empty();
+        let mine = create_as(&service, "alice", "See the doctor");
+        let theirs = create_as(&service, "bob", "Water the plants");
+        RequestContext {
+            user: Some("alice".to_string()),
+            ..Default::default()
+        }
+        .sync_scope(|| service.toggle(theirs.id));
+
+        let export = service.export_account("alice");
+        assert_eq!(export.todos.len(), 1);
+        assert_eq!(export.todos[0].id, mine.id);
+        assert_eq!(export.history.len(), 2);
+
+        let deletion = service
+            .delete_account_until("alice", &Deadline::unbounded())
+            .unwrap();
+        assert_eq!(deletion.deleted_todos, vec![mine.id.to_string()]);
+        assert!(service.get_by_id(mine.id).is_none());
+        assert!(service.get_by_id(theirs.id).unwrap().completed);
+        let events = service.events().all();
+        assert!(events.iter().all(|event| event.actor.as_deref() != Some("alice")));
+        assert!(events
+            .iter()
+            .filter(|event| event.todo_id == mine.id.to_string())
+            .all(|event| event.todo.text == REDACTED_TEXT));
+        assert!(service.export_account("alice").history.is_empty());
+        assert_eq!(service.export_account("bob").todos.len(), 1);
+    }
+}
@@ -0,0 +1,121 @@
+use crate::events::{Event, EventFilter, EventType};
+use crate::service::TodoService;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+/// Longest todo text quoted in a message; longer text is cut with `…`.
+const MAX_QUOTED_CHARS: usize = 60;
+
+/// One line of the activity feed: an event told as a sentence, such as
+/// `You completed 'Write report'`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ActivityItem {
+    pub sequence: u64,
+    pub timestamp: DateTime<Utc>,
+    pub actor: Option<String>,
+    pub action: EventType,
+    #[serde(rename = "todoId")]
+    pub todo_id: String,
+    pub message: String,
+}
+
+impl TodoService {
+    /// Recent activity, newest first, as `viewer` reads it: their own
+    /// events are "You", others' are by name, and events without an actor
+    /// are "Someone". Removed children are not shown; the delete of their
+    /// todo already is.
+    pub fn activity(&self, viewer: Option<&str>) -> Vec<ActivityItem> {
+        let mut events = self.events().search(&EventFilter::default());
+        events.retain(|event| event.event_type != EventType::ChildRemoved);
+        events
+            .iter()
+            .rev()
+            .map(|event| ActivityItem {
+                sequence: event.sequence,
+                timestamp: event.timestamp,
+                actor: event.actor.clone(),
+                action: event.event_type,
+                todo_id: event.todo_id.clone(),
+                message: describe(event, viewer),
+            })
+            .collect()
+    }
+}
+
+fn describe(event: &Event, viewer: Option<&str>) -> String {
+    let who = match event.actor.as_deref() {
+        Some(actor) if viewer == Some(actor) => "You",
+        Some(actor) => actor,
+        None => "Someone",
+    };
+    let verb = match event.event_type {
+        EventType::Created => "added",
+        EventType::Updated => "updated",
+        EventType::Completed => "completed",
+        EventType::Reopened => "reopened",
+        EventType::Deleted => "deleted",
+        EventType::Escalated => "escalated",
+        EventType::Snoozed => "snoozed",
+        EventType::ChildRemoved => "removed part of",
+    };
+    format!("{} {} '{}'", who, verb, quoted(&event.todo.text))
+}
+
+fn quoted(text: &str) -> String {
+    let text = text.trim();
+    if text.chars().count() <= MAX_QUOTED_CHARS {
+        return text.to_string();
+    }
+    let cut: String = text.chars().take(MAX_QUOTED_CHARS - 1).collect();
+    format!("{}…", cut.trim_end())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::RequestContext;
+    use crate::models::TodoCreate;
+    use uuid::Uuid;
+
+    fn create_as(service: &TodoService, user: Option<&str>, text: &str) -> Uuid {
+        let context = RequestContext {
+            user: user.map(str::to_string),
+            ..Default::default()
+        };
+        context.sync_scope(|| {
+            service
+                .create(TodoCreate {
+                    text: text.to_string(),
+                    ..Default::default()
+                })
+                .id
+        })
+    }
+
+    #[test]
+    fn test_tells_events_newest_first() {
+        let service = TodoService::new_empty();
+        let report = create_as(&service, Some("bob"), "Write report");
+        create_as(&service, Some("alice"), "Deploy v2");
+        create_as(&service, None, &"x".repeat(100));
+        RequestContext {
+            user: Some("bob".to_string()),
+            ..Default::default()
+        }
+        .sync_scope(|| service.toggle(report));
+
+        let messages: Vec<String> = service
+            .activity(Some("bob"))
+            .into_iter()
+            .map(|item| item.message)
+            .collect();
+        assert_eq!(messages[0], "You completed 'Write report'");
+        assert_eq!(messages[1], format!("Someone added '{}…'", "x".repeat(59)));
+        assert_eq!(messages[2], "alice added 'Deploy v2'");
+        assert_eq!(messages[3], "You added 'Write report'");
+        assert_eq!(
+            service.activity(None)[0].message,
+            "bob completed 'Write report'"
+        );
+    }
+}
@@ -0,0 +1,104 @@
+use crate::events::{Event, EventFilter, EventType};
+use crate::service::TodoService;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Who did what to which todo, and when: an event without the todo it
+/// carried, so the audit trail can be read and archived without the text.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub sequence: u64,
+    pub timestamp: DateTime<Utc>,
+    pub actor: Option<String>,
+    pub action: EventType,
+    #[serde(rename = "todoId")]
+    pub todo_id: String,
+    #[serde(rename = "requestId", default, skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub workspace: Option<String>,
+}
+
+impl From<&Event> for AuditEntry {
+    fn from(event: &Event) -> Self {
+        AuditEntry {
+            sequence: event.sequence,
+            timestamp: event.timestamp,
+            actor: event.actor.clone(),
+            action: event.event_type,
+            todo_id: event.todo_id.clone(),
+            request_id: event.request_id.clone(),
+            workspace: event.workspace.clone(),
+        }
+    }
+}
+
+impl TodoService {
+    /// The audit entries matching `filter`, oldest first.
+    pub fn audit(&self, filter: &EventFilter) -> Vec<AuditEntry> {
+        self.events()
+            .search(filter)
+            .iter()
+            .map(AuditEntry::from)
+            .collect()
+    }
+
+    /// Drops the history recorded before `cutoff` once `archive` has taken
+    /// it as audit entries. Returns how many were dropped. Fails on an
+    /// event-sourced service, whose history is the todos themselves.
+    pub fn prune_audit_before(
+        &self,
+        cutoff: DateTime<Utc>,
+        archive: &mut dyn FnMut(&[AuditEntry]) -> Result<(), String>,
+    ) -> Result<usize, String> {
+        self.events().prune_before(cutoff, &mut |events| {
+            archive(&events.iter().map(AuditEntry::from).collect::<Vec<_>>())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::TodoCreate;
+    use uuid::Uuid;
+
+    fn create(service: &TodoService, text: &str) -> Uuid {
+        service
+            .create(TodoCreate {
+                text: text.to_string(),
+                ..Default::default()
+            })
+            .id
+    }
+
+    #[test]
+    fn test_prunes_old_entries_and_keeps_sequences() {
+        let service = TodoService::new_empty();
+        let old = create(&service, "Old");
+        service.toggle(old);
+        let cutoff = Utc::now();
+        let new = create(&service, "New");
+
+        let failing = service.prune_audit_before(cutoff, &mut |_| Err("disk full".to_string()));
+        assert!(failing.is_err());
+        assert_eq!(service.audit(&EventFilter::default()).len(), 3);
+
+        let mut archived = Vec::new();
+        let pruned = service.prune_audit_before(cutoff, &mut |entries| {
+            archived.extend_from_slice(entries);
+            Ok(())
+        });
+        assert_eq!(pruned, Ok(2));
+        assert_eq!(archived[1].action, EventType::Completed);
+        let kept = service.audit(&EventFilter::default());
+        assert_eq!(kept.len(), 1);
+        assert_eq!((kept[0].sequence, &kept[0].todo_id), (3, &new.to_string()));
+
+        service.toggle(new);
+        assert_eq!(service.sequence(), 4);
+        assert!(service.changes_since(1, None).is_err());
+        assert_eq!(service.changes_since(2, None).unwrap().changes.len(), 2);
+        assert_eq!(service.changes_since(3, None).unwrap().changes.len(), 1);
+    }
+}
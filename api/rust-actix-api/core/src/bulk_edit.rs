@@ -0,0 +1,279 @@
+use crate::deadline::{Deadline, DeadlineExceeded};
+use crate::events::EventType;
+use crate::models::{Todo, TodoUpdate};
+use crate::service::TodoService;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use uuid::Uuid;
+
+/// Which todos a bulk edit touches: the same filters as the todo list,
+/// optionally narrowed to specific ids.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct BulkFilter {
+    pub filter: Option<String>,
+    pub search: Option<String>,
+    pub priority: Option<String>,
+    pub ids: Option<Vec<String>>,
+}
+
+/// Body of `POST /api/todos/bulk-edit/preview`.
+#[derive(Debug, Deserialize)]
+pub struct BulkEditRequest {
+    #[serde(default)]
+    pub filter: BulkFilter,
+    pub changes: TodoUpdate,
+}
+
+/// One field's value before and after the edit, as it appears in the todo
+/// JSON.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FieldChange {
+    pub from: Value,
+    pub to: Value,
+}
+
+/// What a bulk edit would do to one todo.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TodoDiff {
+    pub id: Uuid,
+    pub text: String,
+    /// When the todo last changed. Applying is refused if it has changed
+    /// since the preview.
+    #[serde(rename = "updatedAt")]
+    pub updated_at: DateTime<Utc>,
+    /// Keyed by JSON field name.
+    pub changes: BTreeMap<String, FieldChange>,
+}
+
+/// The result of applying a previewed bulk edit.
+#[derive(Debug)]
+pub enum BulkEditOutcome {
+    Applied(Vec<Todo>),
+    /// These todos changed or disappeared after the preview; nothing was
+    /// applied.
+    Stale(Vec<Uuid>),
+}
+
+/// Whether `changes` sets no field at all.
+pub fn is_empty(changes: &TodoUpdate) -> bool {
+    match serde_json::to_value(changes) {
+        Ok(Value::Object(fields)) => fields.values().all(Value::is_null),
+        _ => true,
+    }
+}
+
+/// The fields `changes` would alter on `todo`, or `None` when it would
+/// leave the todo as it is.
+pub fn diff(todo: &Todo, changes: &TodoUpdate) -> Option<TodoDiff> {
+    let mut edited = todo.clone();
+    changes.clone().apply_to(&mut edited);
+    let (Ok(Value::Object(before)), Ok(Value::Object(mut after))) =
+        (serde_json::to_value(todo), serde_json::to_value(&edited))
+    else {
+        return None;
+    };
+    let mut fields = BTreeMap::new();
+    for (name, from) in before {
+        let to = after.remove(&name).unwrap_or(Value::Null);
+        if from != to {
+            fields.insert(name, FieldChange { from, to });
+        }
+    }
+    // Fields the todo didn't serialize before, such as a first location.
+    for (name, to) in after {
+        fields.insert(
+            name,
+            FieldChange {
+                from: Value::Null,
+                to,
+            },
+        );
+    }
+    (!fields.is_empty()).then(|| TodoDiff {
+        id: todo.id,
+        text: todo.text.clone(),
+        updated_at: todo.updated_at,
+        changes: fields,
+    })
+}
+
+impl TodoService {
+    /// What `request` would change, todo by todo, in list order. Todos the
+    /// filter matches but the changes would leave alone are not included.
+    pub fn bulk_edit_preview_until(
+        &self,
+        request: &BulkEditRequest,
+        deadline: &Deadline,
+    ) -> Result<Vec<TodoDiff>, DeadlineExceeded> {
+        let filter = &request.filter;
+        let todos = self.get_all_until(
+            filter.filter.clone(),
+            filter.search.clone(),
+            filter.priority.clone(),
+            deadline,
+        )?;
+        Ok(todos
+            .iter()
+            .filter(|todo| {
+                filter
+                    .ids
+                    .as_ref()
+                    .is_none_or(|ids| ids.contains(&todo.id.to_string()))
+            })
+            .filter_map(|todo| diff(todo, &request.changes))
+            .collect())
+    }
+
+    /// Applies `changes` to exactly the previewed todos, all or nothing:
+    /// if any of them changed since `previewed` was computed, none is
+    /// touched.
+    pub fn bulk_edit_apply_until(
+        &self,
+        previewed: &[TodoDiff],
+        changes: &TodoUpdate,
+        deadline: &Deadline,
+    ) -> Result<BulkEditOutcome, DeadlineExceeded> {
+        let guard = self.write_lock(deadline)?;
+        let stale: Vec<Uuid> = previewed
+            .iter()
+            .filter(|diff| {
+                self.store()
+                    .get(diff.id)
+                    .is_none_or(|todo| todo.updated_at != diff.updated_at)
+            })
+            .map(|diff| diff.id)
+            .collect();
+        if !stale.is_empty() {
+            return Ok(BulkEditOutcome::Stale(stale));
+        }
+
+        let now = Utc::now();
+        let mut applied = Vec::new();
+        for diff in previewed {
+            let mut was_completed = false;
+            let updated = self.store().update(diff.id, &mut |todo| {
+                was_completed = todo.completed;
+                changes.clone().apply_to(todo);
+                todo.updated_at = now;
+            });
+            let Some(updated) = updated else { continue };
+            let event_type = match (was_completed, updated.completed) {
+                (false, true) => EventType::Completed,
+                (true, false) => EventType::Reopened,
+                _ => EventType::Updated,
+            };
+            self.record(event_type, &updated);
+            applied.push(updated);
+        }
+        drop(guard);
+        if !applied.is_empty() {
+            self.bump_version();
+        }
+        Ok(BulkEditOutcome::Applied(applied))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Priority, TodoCreate};
+
+    fn create(service: &TodoService, text: &str, priority: Priority) -> Todo {
+        service.create(TodoCreate {
+            text: text.to_string(),
+            priority: Some(priority),
+            ..Default::default()
+        })
+    }
+
+    fn request(priority: &str, changes: TodoUpdate) -> BulkEditRequest {
+        BulkEditRequest {
+            filter: BulkFilter {
+                priority: Some(priority.to_string()),
+                ..BulkFilter::default()
+            },
+            changes,
+        }
+    }
+
+    #[test]
+    fn test_preview_lists_only_real_changes() {
+        let service = TodoService::new_empty();
+        let rent = create(&service, "Pay rent", Priority::Low);
+        create(&service, "Water plants", Priority::Medium);
+        let changes = TodoUpdate {
+            priority: Some(Priority::High),
+            due_date: Some("2024-06-30".to_string()),
+            ..TodoUpdate::default()
+        };
+        assert!(!is_empty(&changes));
+        assert!(is_empty(&TodoUpdate::default()));
+
+        let preview = service
+            .bulk_edit_preview_until(&request("low", changes), &Deadline::unbounded())
+            .unwrap();
+        assert_eq!(preview.len(), 1);
+        assert_eq!(preview[0].id.to_string(), rent.id.to_string());
+        assert_eq!(
+            preview[0].changes.keys().collect::<Vec<_>>(),
+            vec!["dueDate", "priority"]
+        );
+        assert_eq!(preview[0].changes["priority"].from, "low");
+        assert_eq!(preview[0].changes["priority"].to, "high");
+
+        // Setting what is already there is not a change.
+        let same = TodoUpdate {
+            priority: Some(Priority::Low),
+            ..TodoUpdate::default()
+        };
+        assert!(diff(&rent, &same).is_none());
+    }
+
+    #[test]
+    fn test_apply_is_all_or_nothing() {
+        let service = TodoService::new_empty();
+        let rent = create(&service, "Pay rent", Priority::Low);
+        let bins = create(&service, "Take out bins", Priority::Low);
+        let changes = TodoUpdate {
+            completed: Some(true),
+            ..TodoUpdate::default()
+        };
+        let preview = service
+            .bulk_edit_preview_until(&request("low", changes.clone()), &Deadline::unbounded())
+            .unwrap();
+        assert_eq!(preview.len(), 2);
+
+        service.update(
+            bins.id,
+            TodoUpdate {
+                text: Some("Take out the bins".to_string()),
+                ..TodoUpdate::default()
+            },
+        );
+        let version = service.collection_version().version;
+        match service
+            .bulk_edit_apply_until(&preview, &changes, &Deadline::unbounded())
+            .unwrap()
+        {
+            BulkEditOutcome::Stale(ids) => assert_eq!(ids, vec![bins.id]),
+            BulkEditOutcome::Applied(_) => panic!("applied a stale preview"),
+        }
+        assert!(!service.get_by_id(rent.id).unwrap().completed);
+        assert_eq!(service.collection_version().version, version);
+
+        let preview = service
+            .bulk_edit_preview_until(&request("low", changes.clone()), &Deadline::unbounded())
+            .unwrap();
+        match service
+            .bulk_edit_apply_until(&preview, &changes, &Deadline::unbounded())
+            .unwrap()
+        {
+            BulkEditOutcome::Applied(todos) => assert_eq!(todos.len(), 2),
+            BulkEditOutcome::Stale(ids) => panic!("stale: {:?}", ids),
+        }
+        assert!(service.get_by_id(rent.id).unwrap().completed);
+        assert_eq!(service.get_stats().completed, 2);
+    }
+}
@@ -0,0 +1,228 @@
+use crate::cascade::{CascadePolicy, CascadeReport};
+use crate::deadline::Deadline;
+use crate::events::{Event, EventFilter, EventType};
+use crate::models::Todo;
+use crate::rollover::MissedOccurrence;
+use crate::service::TodoService;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Bumped when the bundle layout changes incompatibly.
+pub const BUNDLE_FORMAT: u32 = 1;
+
+/// A todo with everything recorded about it, for moving it between
+/// instances.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TodoBundle {
+    pub format: u32,
+    #[serde(rename = "exportedAt")]
+    pub exported_at: DateTime<Utc>,
+    pub todo: Todo,
+    /// The todo's events on the exporting instance, oldest first.
+    #[serde(default)]
+    pub history: Vec<Event>,
+    #[serde(default, rename = "missedOccurrences")]
+    pub missed_occurrences: Vec<MissedOccurrence>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum BundleError {
+    UnsupportedFormat(u32),
+    /// A todo with the bundle's id is already here.
+    Exists(String),
+    Invalid(String),
+}
+
+impl std::fmt::Display for BundleError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BundleError::UnsupportedFormat(format) => write!(
+                f,
+                "Unsupported bundle format {}: expected {}",
+                format, BUNDLE_FORMAT
+            ),
+            BundleError::Exists(id) => write!(f, "Todo {} already exists", id),
+            BundleError::Invalid(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl TodoService {
+    /// Bundles the todo `id` with its history and missed occurrences.
+    pub fn export_todo(&self, id: Uuid) -> Option<TodoBundle> {
+        let todo = self.get_by_id(id)?;
+        let history = self.events().search(&EventFilter {
+            todo_id: Some(id.to_string()),
+            ..Default::default()
+        });
+        Some(TodoBundle {
+            format: BUNDLE_FORMAT,
+            exported_at: Utc::now(),
+            todo,
+            history,
+            missed_occurrences: self.missed_occurrences(id),
+        })
+    }
+
+    /// Restores a bundle from another instance, keeping the todo's id and
+    /// timestamps. Event sequences are local to each instance, so the
+    /// bundled history is not replayed: the import is recorded as a single
+    /// `Created` event, like any other import.
+    pub fn import_todo(&self, bundle: TodoBundle) -> Result<Todo, BundleError> {
+        if bundle.format != BUNDLE_FORMAT {
+            return Err(BundleError::UnsupportedFormat(bundle.format));
+        }
+        let todo = bundle.todo;
+        if todo.text.trim().is_empty() {
+            return Err(BundleError::Invalid("Bundled todo needs text".to_string()));
+        }
+        if bundle
+            .missed_occurrences
+            .iter()
+            .any(|missed| missed.todo_id != todo.id.to_string())
+        {
+            return Err(BundleError::Invalid(
+                "Missed occurrences must belong to the bundled todo".to_string(),
+            ));
+        }
+
+        let guard = self
+            .write_lock(&Deadline::unbounded())
+            .unwrap_or_else(|e| unreachable!("unbounded deadline exceeded: {}", e));
+        if self.store().get(todo.id).is_some() {
+            return Err(BundleError::Exists(todo.id.to_string()));
+        }
+        self.store().insert(todo.clone());
+        self.record(EventType::Created, &todo);
+        self.missed_log().extend(bundle.missed_occurrences);
+        drop(guard);
+        self.bump_version();
+        Ok(todo)
+    }
+
+    /// Deletes the todo in `bundle` once another instance has taken it over.
+    /// Returns `None`, leaving the todo alone, if it changed or went away
+    /// since the export, so no edit made meanwhile is lost.
+    pub fn hand_off(&self, bundle: &TodoBundle, policy: &CascadePolicy) -> Option<CascadeReport> {
+        let exported = bundle.history.last().map(|event| event.sequence);
+        let guard = self
+            .write_lock(&Deadline::unbounded())
+            .unwrap_or_else(|e| unreachable!("unbounded deadline exceeded: {}", e));
+        let current = self.events().latest_for(&bundle.todo.id.to_string());
+        if current.map(|event| event.sequence) != exported {
+            return None;
+        }
+        let report = self.delete_cascading_locked(bundle.todo.id, policy);
+        drop(guard);
+        if report.is_some() {
+            self.bump_version();
+        }
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Recurrence, TodoCreate};
+    use crate::rollover::RolloverMode;
+    use chrono::NaiveDate;
+
+    fn exported() -> TodoBundle {
+        let source = TodoService::new_empty();
+        let todo = source.create(TodoCreate {
+            text: "Stretch".to_string(),
+            due_date: Some("2024-06-09".to_string()),
+            recurrence: Some(Recurrence::Daily),
+            ..Default::default()
+        });
+        source.toggle(todo.id);
+        source.toggle(todo.id);
+        let today = NaiveDate::from_ymd_opt(2024, 6, 10).unwrap();
+        source.roll_over(today, RolloverMode::MarkMissed);
+        source.export_todo(todo.id).unwrap()
+    }
+
+    #[test]
+    fn test_export_includes_history_and_missed() {
+        let bundle = exported();
+        let types: Vec<EventType> = bundle.history.iter().map(|e| e.event_type).collect();
+        assert_eq!(
+            types,
+            vec![
+                EventType::Created,
+                EventType::Completed,
+                EventType::Reopened,
+                EventType::Updated
+            ]
+        );
+        assert_eq!(bundle.missed_occurrences.len(), 1);
+        assert_eq!(bundle.todo.due_date.as_deref(), Some("2024-06-10"));
+        assert!(TodoService::new_empty().export_todo(Uuid::nil()).is_none());
+    }
+
+    #[test]
+    fn test_import_round_trips_through_json() {
+        let bundle = exported();
+        let json = serde_json::to_string(&bundle).unwrap();
+        let target = TodoService::new_empty();
+
+        let imported = target
+            .import_todo(serde_json::from_str(&json).unwrap())
+            .unwrap();
+        assert_eq!(imported.id, bundle.todo.id);
+        assert_eq!(imported.created_at, bundle.todo.created_at);
+        assert_eq!(target.missed_occurrences(imported.id).len(), 1);
+        assert_eq!(target.sequence(), 1);
+        assert_eq!(target.get_stats().total, 1);
+
+        assert_eq!(
+            target.import_todo(bundle.clone()).unwrap_err(),
+            BundleError::Exists(bundle.todo.id.to_string())
+        );
+        assert_eq!(target.sequence(), 1);
+    }
+
+    #[test]
+    fn test_hand_off_deletes_only_unchanged_todos() {
+        let service = TodoService::new_empty();
+        let create = |text: &str| {
+            service.create(TodoCreate {
+                text: text.to_string(),
+                ..Default::default()
+            })
+        };
+        let moved = create("Moved");
+        let edited = create("Edited meanwhile");
+        let moved_bundle = service.export_todo(moved.id).unwrap();
+        let edited_bundle = service.export_todo(edited.id).unwrap();
+        service.toggle(edited.id);
+
+        let policy = CascadePolicy::default();
+        assert!(service.hand_off(&moved_bundle, &policy).is_some());
+        assert!(service.get_by_id(moved.id).is_none());
+        assert!(service.hand_off(&moved_bundle, &policy).is_none());
+        assert!(service.hand_off(&edited_bundle, &policy).is_none());
+        assert!(service.get_by_id(edited.id).is_some());
+    }
+
+    #[test]
+    fn test_import_rejects_bad_bundles() {
+        let target = TodoService::new_empty();
+        let mut future = exported();
+        future.format = BUNDLE_FORMAT + 1;
+        assert_eq!(
+            target.import_todo(future).unwrap_err(),
+            BundleError::UnsupportedFormat(BUNDLE_FORMAT + 1)
+        );
+
+        let mut foreign = exported();
+        foreign.missed_occurrences[0].todo_id = "someone-else".to_string();
+        assert!(matches!(
+            target.import_todo(foreign),
+            Err(BundleError::Invalid(_))
+        ));
+        assert_eq!(target.sequence(), 0);
+    }
+}
@@ -0,0 +1,283 @@
+use crate::deadline::{Deadline, DeadlineExceeded};
+use crate::events::EventType;
+use crate::models::Todo;
+use crate::service::TodoService;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use uuid::Uuid;
+
+/// Text left in place of a deleted todo's text when its history is redacted.
+pub const REDACTED_TEXT: &str = "[redacted]";
+
+/// Kinds of records hanging off a todo that a delete can take with it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ChildKind {
+    /// A missed occurrence recorded by the rollover, keyed by its due date.
+    MissedOccurrence,
+    /// A past event of the todo, keyed by its sequence. The event stays in
+    /// the log so cursors keep working; only its payload is redacted.
+    History,
+}
+
+/// A child removed by a cascading delete.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChildRef {
+    pub kind: ChildKind,
+    pub id: String,
+}
+
+/// Which children a delete removes along with the todo. Children it keeps
+/// stay readable after the todo is gone.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CascadePolicy {
+    pub missed_occurrences: bool,
+    /// Off by default: the event history is what the audit trail, the
+    /// changes feed and webhook replays are built on.
+    pub history: bool,
+}
+
+impl Default for CascadePolicy {
+    fn default() -> Self {
+        CascadePolicy {
+            missed_occurrences: true,
+            history: false,
+        }
+    }
+}
+
+impl FromStr for CascadePolicy {
+    type Err = String;
+
+    /// A comma-separated list of the children to remove (`missed`,
+    /// `history`), or `none`.
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let mut policy = CascadePolicy {
+            missed_occurrences: false,
+            history: false,
+        };
+        for part in value.split(',').map(|part| part.trim().to_lowercase()) {
+            match part.as_str() {
+                "missed" => policy.missed_occurrences = true,
+                "history" => policy.history = true,
+                "none" | "" => {}
+                other => {
+                    return Err(format!(
+                        "Invalid cascade target '{}': expected missed, history or none",
+                        other
+                    ))
+                }
+            }
+        }
+        Ok(policy)
+    }
+}
+
+/// What a cascading delete removed besides the todo.
+#[derive(Debug, Default, PartialEq, Serialize)]
+pub struct CascadeReport {
+    #[serde(rename = "missedOccurrences")]
+    pub missed_occurrences: usize,
+    /// Past events whose payload was redacted.
+    pub history: usize,
+}
+
+impl TodoService {
+    /// Permanently deletes a todo and the children `policy` selects, all
+    /// under one write lock. Each removed child gets a `ChildRemoved` event,
+    /// ahead of the todo's own `Deleted` event. Returns `None` if there is no
+    /// such todo.
+    pub fn delete_cascading_until(
+        &self,
+        id: Uuid,
+        policy: &CascadePolicy,
+        deadline: &Deadline,
+    ) -> Result<Option<CascadeReport>, DeadlineExceeded> {
+        let guard = self.write_lock(deadline)?;
+        let report = self.delete_cascading_locked(id, policy);
+        drop(guard);
+        if report.is_some() {
+            self.bump_version();
+        }
+        Ok(report)
+    }
+
+    /// `delete_cascading_until` for callers already holding the write lock.
+    /// Leaves bumping the version to them.
+    pub(crate) fn delete_cascading_locked(
+        &self,
+        id: Uuid,
+        policy: &CascadePolicy,
+    ) -> Option<CascadeReport> {
+        let mut todo = self.store().remove(id)?;
+        let id = id.to_string();
+        let mut report = CascadeReport::default();
+        if policy.history {
+            todo = redacted(&todo);
+        }
+
+        if policy.missed_occurrences {
+            let mut log = self.missed_log();
+            let (removed, kept) = log.drain(..).partition(|missed| missed.todo_id == id);
+            *log = kept;
+            drop(log);
+            for missed in removed {
+                let child = ChildRef {
+                    kind: ChildKind::MissedOccurrence,
+                    id: missed.due_date.format("%Y-%m-%d").to_string(),
+                };
+                self.events().append_child(&todo, child);
+                report.missed_occurrences += 1;
+            }
+        }
+        if policy.history {
+            for sequence in self.events().redact(&id, &redacted) {
+                let child = ChildRef {
+                    kind: ChildKind::History,
+                    id: sequence.to_string(),
+                };
+                self.events().append_child(&todo, child);
+                report.history += 1;
+            }
+        }
+
+        self.record(EventType::Deleted, &todo);
+        Some(report)
+    }
+}
+
+/// `todo` with everything a user wrote removed, keeping what the stats and
+/// the audit trail need.
+fn redacted(todo: &Todo) -> Todo {
+    Todo {
+        text: REDACTED_TEXT.to_string(),
+        due_date: None,
+        reminder_time: None,
+        ..todo.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Recurrence, TodoCreate};
+    use crate::rollover::RolloverMode;
+    use chrono::NaiveDate;
+
+    fn create(service: &TodoService, text: &str) -> Uuid {
+        service
+            .create(TodoCreate {
+                text: text.to_string(),
+                due_date: Some("2024-06-08".to_string()),
+                recurrence: Some(Recurrence::Daily),
+                ..Default::default()
+            })
+            .id
+    }
+
+    fn missed_service() -> (TodoService, Uuid, Uuid) {
+        let service = TodoService::new_empty();
+        let doomed = create(&service, "Secret plans");
+        let other = create(&service, "Water plants");
+        let today = NaiveDate::from_ymd_opt(2024, 6, 10).unwrap();
+        service.roll_over(today, RolloverMode::MarkMissed);
+        (service, doomed, other)
+    }
+
+    #[test]
+    fn test_policy_parsing() {
+        assert_eq!(
+            "history, missed".parse(),
+            Ok(CascadePolicy {
+                missed_occurrences: true,
+                history: true
+            })
+        );
+        assert_eq!(
+            "none".parse(),
+            Ok(CascadePolicy {
+                missed_occurrences: false,
+                history: false
+            })
+        );
+        assert!("comments".parse::<CascadePolicy>().is_err());
+    }
+
+    #[test]
+    fn test_default_policy_removes_missed_occurrences() {
+        let (service, doomed, other) = missed_service();
+        let before = service.sequence();
+
+        let report = service
+            .delete_cascading_until(doomed, &CascadePolicy::default(), &Deadline::unbounded())
+            .unwrap();
+        assert_eq!(
+            report,
+            Some(CascadeReport {
+                missed_occurrences: 2,
+                history: 0
+            })
+        );
+        assert!(service.missed_occurrences(doomed).is_empty());
+        assert_eq!(service.missed_occurrences(other).len(), 2);
+
+        let events = service.events().all();
+        let types: Vec<EventType> = events[before as usize..]
+            .iter()
+            .map(|event| event.event_type)
+            .collect();
+        assert_eq!(
+            types,
+            vec![
+                EventType::ChildRemoved,
+                EventType::ChildRemoved,
+                EventType::Deleted
+            ]
+        );
+        assert_eq!(
+            events[before as usize].child,
+            Some(ChildRef {
+                kind: ChildKind::MissedOccurrence,
+                id: "2024-06-08".to_string()
+            })
+        );
+        // Only the tombstone shows up in the changes feed.
+        let feed = service.changes_since(before, None).unwrap();
+        assert_eq!(feed.changes.len(), 1);
+        assert!(feed.changes[0].deleted);
+        assert_eq!(feed.cursor, service.sequence());
+    }
+
+    #[test]
+    fn test_history_policy_redacts_past_events() {
+        let (service, doomed, other) = missed_service();
+        let policy = CascadePolicy {
+            missed_occurrences: false,
+            history: true,
+        };
+
+        let report = service
+            .delete_cascading_until(doomed, &policy, &Deadline::unbounded())
+            .unwrap()
+            .unwrap();
+        assert_eq!(report.history, 2);
+        assert_eq!(service.missed_occurrences(doomed).len(), 2);
+        for event in service.events().all() {
+            if event.todo_id == doomed.to_string() {
+                assert_eq!(event.todo.text, REDACTED_TEXT);
+                assert_eq!(event.todo.due_date, None);
+            } else {
+                assert_ne!(event.todo.text, REDACTED_TEXT);
+            }
+        }
+        assert!(service.get_by_id(other).is_some());
+        assert_eq!(service.get_stats().total, 1);
+
+        assert_eq!(
+            service
+                .delete_cascading_until(doomed, &policy, &Deadline::unbounded())
+                .unwrap(),
+            None
+        );
+    }
+}
@@ -0,0 +1,141 @@
+use crate::events::{EventCursor, EventType};
+use crate::models::{Todo, DEFAULT_PAGE_LIMIT, MAX_PAGE_LIMIT};
+use crate::service::TodoService;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+/// One mutation in the changes feed. Deletions are tombstones: `deleted` is
+/// set and `todo` is omitted.
+#[derive(Debug, Clone, Serialize)]
+pub struct Change {
+    pub sequence: u64,
+    pub id: String,
+    pub deleted: bool,
+    #[serde(rename = "changedAt")]
+    pub changed_at: DateTime<Utc>,
+    /// State right after this change.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub todo: Option<Todo>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ChangeFeed {
+    pub changes: Vec<Change>,
+    /// Pass back as `since` to continue after the last change returned.
+    pub cursor: u64,
+    /// Sequence number of the most recent mutation on the server.
+    pub latest: u64,
+    #[serde(rename = "hasMore")]
+    pub has_more: bool,
+}
+
+impl TodoService {
+    /// Sequence number of the most recent mutation; 0 before any.
+    pub fn sequence(&self) -> u64 {
+        self.events().latest_sequence()
+    }
+
+    /// Mutations after sequence `since`, oldest first. Fails when `since` is
+    /// beyond this server's history (e.g. the cursor predates a restart) or
+    /// before what retention kept, in which case the caller has to re-fetch
+    /// everything.
+    pub fn changes_since(&self, since: u64, limit: Option<usize>) -> Result<ChangeFeed, String> {
+        let latest = self.sequence();
+        if since > latest {
+            return Err(format!(
+                "Cursor {} is ahead of the latest sequence {}; re-fetch all todos",
+                since, latest
+            ));
+        }
+        let pruned = self.events().pruned_through();
+        if since < pruned {
+            return Err(format!(
+                "Cursor {} predates the retained history, which starts after {}; \
+                 re-fetch all todos",
+                since, pruned
+            ));
+        }
+        let limit = limit.unwrap_or(DEFAULT_PAGE_LIMIT).clamp(1, MAX_PAGE_LIMIT);
+        let mut events = self.events().since(&EventCursor::Sequence(since));
+        let has_more = events.len() > limit;
+        events.truncate(limit);
+
+        // Child removals leave the todo as it was, so they aren't changes;
+        // the cursor still moves past them.
+        let cursor = events.last().map_or(since, |event| event.sequence);
+        let changes: Vec<Change> = events
+            .into_iter()
+            .filter(|event| event.event_type != EventType::ChildRemoved)
+            .map(|event| {
+                let deleted = event.event_type == EventType::Deleted;
+                Change {
+                    sequence: event.sequence,
+                    id: event.todo_id,
+                    deleted,
+                    changed_at: event.timestamp,
+                    todo: (!deleted).then_some(event.todo),
+                }
+            })
+            .collect();
+        Ok(ChangeFeed {
+            changes,
+            cursor,
+            latest,
+            has_more,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::TodoCreate;
+
+    fn create(service: &TodoService, text: &str) -> Todo {
+        service.create(TodoCreate {
+            text: text.to_string(),
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_changes_since_includes_tombstones() {
+        let service = TodoService::new_empty();
+        let first = create(&service, "First");
+        let second = create(&service, "Second");
+        service.toggle(first.id);
+        service.delete(second.id);
+
+        let feed = service.changes_since(1, None).unwrap();
+        assert_eq!(feed.latest, 4);
+        assert_eq!(feed.cursor, 4);
+        assert!(!feed.has_more);
+        let sequences: Vec<u64> = feed.changes.iter().map(|c| c.sequence).collect();
+        assert_eq!(sequences, vec![2, 3, 4]);
+        assert!(feed.changes[1].todo.as_ref().unwrap().completed);
+        assert!(feed.changes[2].deleted);
+        assert!(feed.changes[2].todo.is_none());
+        assert_eq!(feed.changes[2].id.to_string(), second.id.to_string());
+    }
+
+    #[test]
+    fn test_changes_since_pages_by_cursor() {
+        let service = TodoService::new_empty();
+        for i in 0..5 {
+            create(&service, &format!("Todo {}", i));
+        }
+        let page = service.changes_since(0, Some(2)).unwrap();
+        assert_eq!(page.cursor, 2);
+        assert!(page.has_more);
+
+        let rest = service.changes_since(page.cursor, Some(10)).unwrap();
+        assert_eq!(rest.changes.len(), 3);
+        assert!(!rest.has_more);
+
+        let caught_up = service.changes_since(rest.cursor, None).unwrap();
+        assert!(caught_up.changes.is_empty());
+        assert_eq!(caught_up.cursor, 5);
+
+        assert!(service.changes_since(6, None).is_err());
+    }
+}
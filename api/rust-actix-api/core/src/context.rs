@@ -0,0 +1,71 @@
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+
+tokio::task_local! {
+    static CURRENT: RequestContext;
+}
+
+/// What caused a change: the request, and who made it from where. Every
+/// event appended while a context is in scope carries it, so each mutation
+/// can be traced back to the request that made it, or that set off the
+/// script that made it.
+///
+/// The context is ambient rather than passed to each `TodoService` call:
+/// the HTTP layer sets it once around the whole request.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RequestContext {
+    #[serde(rename = "requestId", skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+    /// Becomes the events' `actor`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub workspace: Option<String>,
+}
+
+impl RequestContext {
+    /// Runs `future` with this as the current context.
+    pub async fn scope<F: Future>(self, future: F) -> F::Output {
+        CURRENT.scope(self, future).await
+    }
+
+    /// Runs `f` with this as the current context, e.g. on a blocking thread
+    /// the request handed work to.
+    pub fn sync_scope<R>(self, f: impl FnOnce() -> R) -> R {
+        CURRENT.sync_scope(self, f)
+    }
+
+    /// The context in scope; `None` outside any, such as in scheduled jobs.
+    pub fn current() -> Option<RequestContext> {
+        CURRENT.try_with(RequestContext::clone).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::TodoCreate;
+    use crate::service::TodoService;
+
+    #[test]
+    fn test_events_carry_context_in_scope() {
+        let service = TodoService::new_empty();
+        let create = || TodoCreate {
+            text: "Pay rent".to_string(),
+            ..Default::default()
+        };
+        let context = RequestContext {
+            request_id: Some("req-1".to_string()),
+            user: Some("alice".to_string()),
+            workspace: None,
+        };
+        context.clone().sync_scope(|| service.create(create()));
+        service.create(create());
+
+        let events = service.events().all();
+        assert_eq!(events[0].context(), context);
+        assert_eq!(events[0].actor.as_deref(), Some("alice"));
+        assert_eq!(events[1].context(), RequestContext::default());
+        assert_eq!(RequestContext::current(), None);
+    }
+}
@@ -0,0 +1,192 @@
+use crate::deadline::{Deadline, DeadlineExceeded};
+use crate::events::Event;
+use crate::models::{Priority, Todo, TodoStats, WeekStart};
+use crate::service::TodoService;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+
+/// Todos per section, and events in the activity list, unless asked for
+/// fewer or more.
+pub const DEFAULT_SECTION_LIMIT: usize = 10;
+pub const MAX_SECTION_LIMIT: usize = 100;
+
+/// Query of `GET /api/dashboard`.
+#[derive(Debug, Default, Deserialize)]
+pub struct DashboardQuery {
+    pub limit: Option<usize>,
+}
+
+/// Everything the frontend's first screen shows, so it loads with one
+/// request. Each list is capped at the section limit; `stats` has the full
+/// counts.
+#[derive(Debug, Serialize)]
+pub struct Dashboard {
+    pub date: NaiveDate,
+    /// Active todos due today, those with a reminder first, by time.
+    #[serde(rename = "dueToday")]
+    pub due_today: Vec<Todo>,
+    /// Active todos due before today, longest overdue first.
+    pub overdue: Vec<Todo>,
+    /// Active todos by priority, highest first, then by due date.
+    #[serde(rename = "topPriority")]
+    pub top_priority: Vec<Todo>,
+    pub stats: TodoStats,
+    /// Newest first.
+    #[serde(rename = "recentActivity")]
+    pub recent_activity: Vec<Event>,
+}
+
+fn due(todo: &Todo) -> Option<NaiveDate> {
+    todo.due_date
+        .as_deref()
+        .and_then(|due| NaiveDate::parse_from_str(due, "%Y-%m-%d").ok())
+}
+
+fn rank(priority: &Priority) -> u8 {
+    match priority {
+        Priority::Low => 0,
+        Priority::Medium => 1,
+        Priority::High => 2,
+    }
+}
+
+impl Dashboard {
+    /// Sorts `todos` into the sections. Completed and hidden todos are left
+    /// out of every section.
+    pub fn build(
+        todos: &[Todo],
+        today: NaiveDate,
+        stats: TodoStats,
+        recent_activity: Vec<Event>,
+        limit: usize,
+    ) -> Self {
+        let active: Vec<&Todo> = todos
+            .iter()
+            .filter(|todo| !todo.completed && todo.hidden_state().is_none())
+            .collect();
+        let select = |keep: &dyn Fn(&Todo) -> bool| -> Vec<Todo> {
+            active
+                .iter()
+                .filter(|todo| keep(todo))
+                .map(|todo| (*todo).clone())
+                .collect()
+        };
+
+        let mut due_today = select(&|todo| due(todo) == Some(today));
+        due_today.sort_by_key(|todo| {
+            (
+                todo.reminder_time.is_none(),
+                todo.reminder_time.clone(),
+                Reverse(rank(&todo.priority)),
+                todo.id,
+            )
+        });
+        due_today.truncate(limit);
+
+        let mut overdue = select(&|todo| due(todo).is_some_and(|due| due < today));
+        overdue.sort_by_key(|todo| (due(todo), todo.id));
+        overdue.truncate(limit);
+
+        let mut top_priority = select(&|_| true);
+        top_priority.sort_by_key(|todo| {
+            (
+                Reverse(rank(&todo.priority)),
+                due(todo).is_none(),
+                due(todo),
+                todo.id,
+            )
+        });
+        top_priority.truncate(limit);
+
+        Dashboard {
+            date: today,
+            due_today,
+            overdue,
+            top_priority,
+            stats,
+            recent_activity,
+        }
+    }
+}
+
+impl TodoService {
+    /// The dashboard for `today`; see `Dashboard::build`.
+    pub fn dashboard_until(
+        &self,
+        today: NaiveDate,
+        week_start: WeekStart,
+        limit: usize,
+        deadline: &Deadline,
+    ) -> Result<Dashboard, DeadlineExceeded> {
+        let todos = self.get_all_until(Some("active".to_string()), None, None, deadline)?;
+        let stats = self.get_stats_on_until(today, week_start, deadline, false)?;
+        let recent = self.events().recent(limit);
+        Ok(Dashboard::build(&todos, today, stats, recent, limit))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::todo_id;
+    use chrono::{Duration, Utc};
+
+    fn today() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 6, 10).unwrap()
+    }
+
+    fn todo(id: &str, priority: Priority, due_in: Option<i64>, reminder: Option<&str>) -> Todo {
+        Todo {
+            id: todo_id(id),
+            text: format!("Todo {}", id),
+            priority,
+            completed: false,
+            due_date: due_in.map(|days| (today() + Duration::days(days)).to_string()),
+            reminder_time: reminder.map(str::to_string),
+            recurrence: None,
+            recurrence_end: None,
+            remaining_occurrences: None,
+            estimate_minutes: None,
+            location: None,
+            color: None,
+            icon: None,
+            pinned: false,
+            rollover_count: 0,
+            rolled_over_to: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_builds_sections() {
+        let mut done = todo("done", Priority::High, Some(0), None);
+        done.completed = true;
+        let todos = vec![
+            todo("today-late", Priority::Medium, Some(0), Some("17:00")),
+            todo("today-early", Priority::Low, Some(0), Some("08:00")),
+            todo("today-anytime", Priority::High, Some(0), None),
+            todo("last-week", Priority::Low, Some(-7), None),
+            todo("yesterday", Priority::Medium, Some(-1), None),
+            todo("someday", Priority::High, None, None),
+            done,
+        ];
+        let stats = TodoService::new_empty().get_stats();
+        let ids = |todos: &[Todo]| todos.iter().map(|t| t.id).collect::<Vec<_>>();
+
+        let dashboard = Dashboard::build(&todos, today(), stats, Vec::new(), 3);
+        assert_eq!(
+            ids(&dashboard.due_today),
+            ["today-early", "today-late", "today-anytime"].map(todo_id)
+        );
+        assert_eq!(
+            ids(&dashboard.overdue),
+            ["last-week", "yesterday"].map(todo_id)
+        );
+        assert_eq!(
+            ids(&dashboard.top_priority),
+            ["today-anytime", "someday", "yesterday"].map(todo_id)
+        );
+    }
+}
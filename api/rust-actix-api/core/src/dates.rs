@@ -0,0 +1,167 @@
+use chrono::{Datelike, Days, Months, NaiveDate, NaiveTime, Weekday};
+
+const WEEKDAYS: [(&str, Weekday); 7] = [
+    ("monday", Weekday::Mon),
+    ("tuesday", Weekday::Tue),
+    ("wednesday", Weekday::Wed),
+    ("thursday", Weekday::Thu),
+    ("friday", Weekday::Fri),
+    ("saturday", Weekday::Sat),
+    ("sunday", Weekday::Sun),
+];
+
+/// Resolves a date phrase relative to `today`. Understands ISO dates,
+/// `today`, `tomorrow`, a weekday (the next one on or after today),
+/// `next <weekday>` (strictly after today), `next week`, `next month` and
+/// `in N days|weeks|months`. Case-insensitive.
+pub fn parse_date(phrase: &str, today: NaiveDate) -> Option<NaiveDate> {
+    let phrase = phrase.trim().to_lowercase();
+    if let Ok(date) = NaiveDate::parse_from_str(&phrase, "%Y-%m-%d") {
+        return Some(date);
+    }
+    let words: Vec<&str> = phrase.split_whitespace().collect();
+    match words.as_slice() {
+        ["today"] => Some(today),
+        ["tomorrow"] => today.succ_opt(),
+        ["next", "week"] => today.checked_add_days(Days::new(7)),
+        ["next", "month"] => today.checked_add_months(Months::new(1)),
+        ["next", day] => Some(on_or_after(today.succ_opt()?, weekday(day)?)),
+        [day] => Some(on_or_after(today, weekday(day)?)),
+        ["in", count, unit] => {
+            let count: u32 = count.parse().ok()?;
+            match *unit {
+                "day" | "days" => today.checked_add_days(Days::new(count.into())),
+                "week" | "weeks" => today.checked_add_days(Days::new(u64::from(count) * 7)),
+                "month" | "months" => today.checked_add_months(Months::new(count)),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Rewrites a client-supplied due date as `YYYY-MM-DD`, resolving phrases
+/// like `next friday` against `today`. Blank values are left alone.
+pub fn normalize_due_date(due_date: &mut Option<String>, today: NaiveDate) -> Result<(), String> {
+    let Some(value) = due_date.as_deref().filter(|value| !value.trim().is_empty()) else {
+        return Ok(());
+    };
+    let date = parse_date(value, today).ok_or_else(|| {
+        format!(
+            "Invalid due date '{}': expected YYYY-MM-DD or a phrase like 'in 3 days'",
+            value
+        )
+    })?;
+    *due_date = Some(date.format("%Y-%m-%d").to_string());
+    Ok(())
+}
+
+/// Parses a time of day: `9am`, `9:30pm`, `noon`, `midnight` or 24-hour
+/// `21:00`.
+pub fn parse_time(word: &str) -> Option<NaiveTime> {
+    let word = word.trim().to_lowercase();
+    match word.as_str() {
+        "noon" => return NaiveTime::from_hms_opt(12, 0, 0),
+        "midnight" => return NaiveTime::from_hms_opt(0, 0, 0),
+        _ => {}
+    }
+    let (clock, offset) = if let Some(clock) = word.strip_suffix("am") {
+        (clock, Some(0))
+    } else if let Some(clock) = word.strip_suffix("pm") {
+        (clock, Some(12))
+    } else {
+        (word.as_str(), None)
+    };
+    let (hour, minute) = match clock.split_once(':') {
+        Some((hour, minute)) if minute.len() == 2 => (hour, minute.parse().ok()?),
+        Some(_) => return None,
+        // A bare number is only a time with am/pm, else it is just a number.
+        None if offset.is_some() => (clock, 0),
+        None => return None,
+    };
+    let hour: u32 = hour.parse().ok()?;
+    let hour = match offset {
+        Some(_) if !(1..=12).contains(&hour) => return None,
+        Some(offset) => hour % 12 + offset,
+        None => hour,
+    };
+    NaiveTime::from_hms_opt(hour, minute, 0)
+}
+
+fn weekday(word: &str) -> Option<Weekday> {
+    WEEKDAYS
+        .iter()
+        .find(|(name, _)| *name == word)
+        .map(|(_, weekday)| *weekday)
+}
+
+fn on_or_after(date: NaiveDate, weekday: Weekday) -> NaiveDate {
+    let ahead = (7 + weekday.num_days_from_monday() - date.weekday().num_days_from_monday()) % 7;
+    date + Days::new(ahead.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(value: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(value, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn test_parse_date_phrases() {
+        // A Monday.
+        let today = date("2024-06-10");
+        let parse = |phrase| parse_date(phrase, today);
+        assert_eq!(parse("2024-07-01"), Some(date("2024-07-01")));
+        assert_eq!(parse("Today"), Some(today));
+        assert_eq!(parse("tomorrow"), Some(date("2024-06-11")));
+        assert_eq!(parse("friday"), Some(date("2024-06-14")));
+        assert_eq!(parse("monday"), Some(today));
+        assert_eq!(parse("next monday"), Some(date("2024-06-17")));
+        assert_eq!(parse("next  Friday"), Some(date("2024-06-14")));
+        assert_eq!(parse("next week"), Some(date("2024-06-17")));
+        assert_eq!(parse("next month"), Some(date("2024-07-10")));
+        assert_eq!(parse("in 3 days"), Some(date("2024-06-13")));
+        assert_eq!(parse("in 1 week"), Some(date("2024-06-17")));
+        assert_eq!(parse("in 2 months"), Some(date("2024-08-10")));
+        assert_eq!(parse("someday"), None);
+        assert_eq!(parse("in a while"), None);
+        assert_eq!(parse("2024-13-01"), None);
+    }
+
+    #[test]
+    fn test_normalize_due_date() {
+        let today = date("2024-06-10");
+        let normalize = |value: Option<&str>| {
+            let mut due_date = value.map(str::to_string);
+            normalize_due_date(&mut due_date, today).map(|()| due_date)
+        };
+        assert_eq!(
+            normalize(Some("next friday")),
+            Ok(Some("2024-06-14".to_string()))
+        );
+        assert_eq!(
+            normalize(Some("2024-12-31")),
+            Ok(Some("2024-12-31".to_string()))
+        );
+        assert_eq!(normalize(Some("")), Ok(Some(String::new())));
+        assert_eq!(normalize(None), Ok(None));
+        assert!(normalize(Some("whenever")).is_err());
+    }
+
+    #[test]
+    fn test_parse_time() {
+        let time = |h, m| NaiveTime::from_hms_opt(h, m, 0);
+        assert_eq!(parse_time("9am"), time(9, 0));
+        assert_eq!(parse_time("12am"), time(0, 0));
+        assert_eq!(parse_time("12pm"), time(12, 0));
+        assert_eq!(parse_time("9:30PM"), time(21, 30));
+        assert_eq!(parse_time("21:05"), time(21, 5));
+        assert_eq!(parse_time("noon"), time(12, 0));
+        assert_eq!(parse_time("9"), None);
+        assert_eq!(parse_time("13pm"), None);
+        assert_eq!(parse_time("9:5"), None);
+        assert_eq!(parse_time("25:00"), None);
+    }
+}
@@ -0,0 +1,130 @@
+use serde::Serialize;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// A time budget for one operation, threaded from the caller through the
+/// service down to storage so a slow lock or query fails fast with a
+/// description of where the time went instead of queueing indefinitely.
+#[derive(Debug)]
+pub struct Deadline {
+    started: Instant,
+    budget: Option<Duration>,
+    stages: Mutex<Vec<StageTiming>>,
+}
+
+/// How far into the budget a stage finished.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StageTiming {
+    pub stage: &'static str,
+    #[serde(rename = "elapsedMs")]
+    pub elapsed_ms: f64,
+}
+
+/// The budget ran out during `stage`. Carries the stages that did complete.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DeadlineExceeded {
+    pub stage: &'static str,
+    #[serde(rename = "budgetMs")]
+    pub budget_ms: f64,
+    #[serde(rename = "elapsedMs")]
+    pub elapsed_ms: f64,
+    pub completed: Vec<StageTiming>,
+}
+
+impl std::fmt::Display for DeadlineExceeded {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "deadline of {:.0}ms exceeded during {} after {:.1}ms",
+            self.budget_ms, self.stage, self.elapsed_ms
+        )
+    }
+}
+
+impl Deadline {
+    /// No budget: every check passes and locks are waited on indefinitely.
+    pub fn unbounded() -> Self {
+        Deadline {
+            started: Instant::now(),
+            budget: None,
+            stages: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub fn within(budget: Duration) -> Self {
+        Deadline {
+            budget: Some(budget),
+            ..Self::unbounded()
+        }
+    }
+
+    pub fn is_bounded(&self) -> bool {
+        self.budget.is_some()
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    /// Time left, or `None` when unbounded. Zero once expired.
+    pub fn remaining(&self) -> Option<Duration> {
+        self.budget
+            .map(|budget| budget.saturating_sub(self.started.elapsed()))
+    }
+
+    /// Fails if the budget is already spent, attributing it to `stage`.
+    pub fn check(&self, stage: &'static str) -> Result<(), DeadlineExceeded> {
+        match self.remaining() {
+            Some(remaining) if remaining.is_zero() => Err(self.exceeded(stage)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Records that `stage` finished now.
+    pub fn complete(&self, stage: &'static str) {
+        self.stages.lock().unwrap().push(StageTiming {
+            stage,
+            elapsed_ms: millis(self.started.elapsed()),
+        });
+    }
+
+    pub fn exceeded(&self, stage: &'static str) -> DeadlineExceeded {
+        DeadlineExceeded {
+            stage,
+            budget_ms: self.budget.map(millis).unwrap_or_default(),
+            elapsed_ms: millis(self.started.elapsed()),
+            completed: self.stages.lock().unwrap().clone(),
+        }
+    }
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unbounded_never_expires() {
+        let deadline = Deadline::unbounded();
+        assert!(deadline.remaining().is_none());
+        assert!(deadline.check("anything").is_ok());
+    }
+
+    #[test]
+    fn test_exceeded_reports_completed_stages() {
+        let deadline = Deadline::within(Duration::from_millis(10));
+        deadline.check("start").unwrap();
+        deadline.complete("start");
+        std::thread::sleep(Duration::from_millis(15));
+
+        let exceeded = deadline.check("query").unwrap_err();
+        assert_eq!(exceeded.stage, "query");
+        assert_eq!(exceeded.budget_ms, 10.0);
+        assert!(exceeded.elapsed_ms >= 10.0);
+        assert_eq!(exceeded.completed.len(), 1);
+        assert_eq!(exceeded.completed[0].stage, "start");
+    }
+}
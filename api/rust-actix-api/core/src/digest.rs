@@ -0,0 +1,282 @@
+use crate::locale::Locale;
+use crate::models::{Priority, Todo};
+use chrono::{Days, NaiveDate, NaiveTime};
+use serde::Serialize;
+
+/// Default look-ahead of the `upcoming` section, in days.
+pub const DEFAULT_DAYS: u32 = 7;
+
+/// Active todos with a due date, grouped by how soon they are due.
+#[derive(Debug, Serialize)]
+pub struct Agenda {
+    pub date: NaiveDate,
+    pub overdue: Vec<Todo>,
+    #[serde(rename = "dueToday")]
+    pub due_today: Vec<Todo>,
+    /// Due within `days` after `date`.
+    pub upcoming: Vec<Todo>,
+    pub days: u32,
+}
+
+/// How `Agenda::render_plain_text` lays out its output.
+#[derive(Debug, Clone, Copy)]
+pub struct PlainTextOptions {
+    pub locale: Locale,
+    /// Wraps lines at this many characters, continuing items with a two
+    /// space indent. `0` leaves lines unwrapped.
+    pub width: usize,
+    pub show_priority: bool,
+}
+
+impl Default for PlainTextOptions {
+    fn default() -> Self {
+        PlainTextOptions {
+            locale: Locale::default(),
+            width: 0,
+            show_priority: true,
+        }
+    }
+}
+
+impl Agenda {
+    /// Builds the agenda of `todos` for `today`. Completed and hidden todos,
+    /// and those without a parseable due date, are left out. Each section is
+    /// ordered by due date, then highest priority first.
+    pub fn build(todos: &[Todo], today: NaiveDate, days: u32) -> Self {
+        let horizon = today
+            .checked_add_days(Days::new(days.into()))
+            .unwrap_or(NaiveDate::MAX);
+        let mut due: Vec<(NaiveDate, &Todo)> = todos
+            .iter()
+            .filter(|todo| !todo.completed && todo.hidden_state().is_none())
+            .filter_map(|todo| {
+                let due = NaiveDate::parse_from_str(todo.due_date.as_deref()?, "%Y-%m-%d").ok()?;
+                Some((due, todo))
+            })
+            .filter(|(due, _)| *due <= horizon)
+            .collect();
+        due.sort_by(|a, b| {
+            a.0.cmp(&b.0)
+                .then(b.1.priority.weight().cmp(&a.1.priority.weight()))
+                .then(a.1.text.cmp(&b.1.text))
+        });
+
+        let mut agenda = Agenda {
+            date: today,
+            overdue: Vec::new(),
+            due_today: Vec::new(),
+            upcoming: Vec::new(),
+            days,
+        };
+        for (due, todo) in due {
+            let section = match due.cmp(&today) {
+                std::cmp::Ordering::Less => &mut agenda.overdue,
+                std::cmp::Ordering::Equal => &mut agenda.due_today,
+                std::cmp::Ordering::Greater => &mut agenda.upcoming,
+            };
+            section.push(todo.clone());
+        }
+        agenda
+    }
+
+    /// Renders the agenda as plain sentences, without markdown, tables or
+    /// emoji, so it reads well over SMS and in screen readers.
+    pub fn render_plain_text(&self, options: &PlainTextOptions) -> String {
+        let mut lines = wrap(
+            &format!("Agenda for {}", options.locale.format_date(self.date)),
+            options.width,
+        );
+        let upcoming = format!("Due in the next {} {}", self.days, plural(self.days, "day"));
+        let sections = [
+            ("Overdue", &self.overdue),
+            ("Due today", &self.due_today),
+            (upcoming.as_str(), &self.upcoming),
+        ];
+        for (title, todos) in sections {
+            lines.push(String::new());
+            let count = todos.len() as u32;
+            let heading = format!("{}: {} {}.", title, count, plural(count, "item"));
+            lines.extend(wrap(&heading, options.width));
+            for (number, todo) in todos.iter().enumerate() {
+                let item = format!("{}. {}", number + 1, describe(todo, options));
+                lines.extend(wrap(&item, options.width));
+            }
+        }
+        lines.join("\n") + "\n"
+    }
+}
+
+fn describe(todo: &Todo, options: &PlainTextOptions) -> String {
+    let mut sentence = sentence(&todo.text);
+    if let Some(due) = todo.due_date.as_deref() {
+        sentence.push_str(&format!(" Due {}", options.locale.format_due_date(due)));
+        if let Some(time) = todo.reminder_time.as_deref() {
+            let time = NaiveTime::parse_from_str(time, "%H:%M").map_or_else(
+                |_| time.to_string(),
+                |time| options.locale.format_time(time),
+            );
+            sentence.push_str(&format!(" at {}", time));
+        }
+        sentence.push('.');
+    }
+    if options.show_priority {
+        let priority = match todo.priority {
+            Priority::Low => "Low",
+            Priority::Medium => "Medium",
+            Priority::High => "High",
+        };
+        sentence.push_str(&format!(" {} priority.", priority));
+    }
+    sentence
+}
+
+/// `text` ending in a full stop, so screen readers pause between fields.
+fn sentence(text: &str) -> String {
+    let text = text.trim();
+    if text.ends_with(['.', '!', '?']) {
+        text.to_string()
+    } else {
+        format!("{}.", text)
+    }
+}
+
+fn plural(count: u32, noun: &str) -> String {
+    if count == 1 {
+        noun.to_string()
+    } else {
+        format!("{}s", noun)
+    }
+}
+
+/// Greedy word wrap. Words longer than `width` get a line to themselves.
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    if width == 0 {
+        return vec![text.to_string()];
+    }
+    let mut lines: Vec<String> = Vec::new();
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        if line.is_empty() {
+            line.push_str(word);
+        } else if line.chars().count() + 1 + word.chars().count() <= width {
+            line.push(' ');
+            line.push_str(word);
+        } else {
+            lines.push(std::mem::take(&mut line));
+            line = format!("  {}", word);
+        }
+    }
+    lines.push(line);
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::todo_id;
+    use chrono::Utc;
+
+    fn todo(text: &str, due: Option<&str>, priority: Priority) -> Todo {
+        Todo {
+            id: todo_id(text),
+            text: text.to_string(),
+            priority,
+            completed: false,
+            due_date: due.map(str::to_string),
+            reminder_time: None,
+            recurrence: None,
+            recurrence_end: None,
+            remaining_occurrences: None,
+            estimate_minutes: None,
+            location: None,
+            color: None,
+            icon: None,
+            pinned: false,
+            rollover_count: 0,
+            rolled_over_to: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    fn today() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 6, 10).unwrap()
+    }
+
+    fn texts(todos: &[Todo]) -> Vec<&str> {
+        todos.iter().map(|todo| todo.text.as_str()).collect()
+    }
+
+    #[test]
+    fn test_build_groups_and_orders_active_due_todos() {
+        let mut done = todo("Done", Some("2024-06-10"), Priority::High);
+        done.completed = true;
+        let todos = vec![
+            todo("Later", Some("2024-06-20"), Priority::High),
+            todo("Soon low", Some("2024-06-12"), Priority::Low),
+            todo("Soon high", Some("2024-06-12"), Priority::High),
+            todo("Today", Some("2024-06-10"), Priority::Medium),
+            todo("Late", Some("2024-06-01"), Priority::Medium),
+            todo("Undated", None, Priority::High),
+            done,
+        ];
+
+        let agenda = Agenda::build(&todos, today(), DEFAULT_DAYS);
+        assert_eq!(texts(&agenda.overdue), vec!["Late"]);
+        assert_eq!(texts(&agenda.due_today), vec!["Today"]);
+        assert_eq!(texts(&agenda.upcoming), vec!["Soon high", "Soon low"]);
+    }
+
+    #[test]
+    fn test_render_plain_text() {
+        let mut today_todo = todo("Call the bank", Some("2024-06-10"), Priority::High);
+        today_todo.reminder_time = Some("14:30".to_string());
+        let todos = vec![
+            today_todo,
+            todo("Renew passport!", Some("2024-06-11"), Priority::Low),
+        ];
+        let agenda = Agenda::build(&todos, today(), 1);
+        let options = PlainTextOptions {
+            locale: "de".parse().unwrap(),
+            ..PlainTextOptions::default()
+        };
+        assert_eq!(
+            agenda.render_plain_text(&options),
+            "Agenda for 10.06.2024\n\
+             \n\
+             Overdue: 0 items.\n\
+             \n\
+             Due today: 1 item.\n\
+             1. Call the bank. Due 10.06.2024 at 14:30. High priority.\n\
+             \n\
+             Due in the next 1 day: 1 item.\n\
+             1. Renew passport! Due 11.06.2024. Low priority.\n"
+        );
+    }
+
+    #[test]
+    fn test_render_wraps_and_hides_priority() {
+        let todos = vec![todo(
+            "Pick up the dry cleaning on the way home",
+            Some("2024-06-10"),
+            Priority::Medium,
+        )];
+        let options = PlainTextOptions {
+            locale: "en-US".parse().unwrap(),
+            width: 20,
+            show_priority: false,
+        };
+        let text = Agenda::build(&todos, today(), 0).render_plain_text(&options);
+        let item: Vec<&str> = text.lines().skip(5).take(4).collect();
+        assert_eq!(
+            item,
+            vec![
+                "1. Pick up the dry",
+                "  cleaning on the",
+                "  way home. Due",
+                "  6/10/2024.",
+            ]
+        );
+        assert!(text.lines().all(|line| line.chars().count() <= 20));
+    }
+}
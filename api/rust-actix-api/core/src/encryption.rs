@@ -0,0 +1,288 @@
+use crate::deadline::{Deadline, DeadlineExceeded};
+use crate::models::Todo;
+use crate::storage::{LockStatsSnapshot, TodoStore};
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Nonce};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use std::io;
+use std::time::Duration;
+use uuid::Uuid;
+
+/// Leads sealed text, so text written before encryption was turned on can
+/// be told apart and read as is.
+pub const SEALED_PREFIX: &str = "enc:v1:";
+const NONCE_LEN: usize = 12;
+
+/// AES-256-GCM for todo text on its way to disk, with a random nonce per
+/// write. Sealed text is `SEALED_PREFIX` and the base64 of
+/// `nonce || ciphertext+tag`.
+#[derive(Clone)]
+pub struct TextCipher(Aes256Gcm);
+
+impl TextCipher {
+    /// Parses a 256-bit key given as 64 hex characters.
+    pub fn from_hex(hex: &str) -> Result<Self, String> {
+        let invalid = || "Encryption key must be 64 hex characters (256 bits)".to_string();
+        let hex = hex.trim();
+        if hex.len() != 64 || !hex.is_ascii() {
+            return Err(invalid());
+        }
+        let bytes = (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16))
+            .collect::<Result<Vec<u8>, _>>()
+            .map_err(|_| invalid())?;
+        Aes256Gcm::new_from_slice(&bytes)
+            .map(TextCipher)
+            .map_err(|e| e.to_string())
+    }
+
+    pub fn seal(&self, text: &str) -> String {
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .0
+            .encrypt(&nonce, text.as_bytes())
+            .expect("AES-GCM encryption of an in-memory buffer cannot fail");
+        let mut sealed = nonce.to_vec();
+        sealed.extend_from_slice(&ciphertext);
+        format!("{}{}", SEALED_PREFIX, STANDARD.encode(sealed))
+    }
+
+    /// The text `seal` sealed. Text without `SEALED_PREFIX` is returned as
+    /// is.
+    pub fn open(&self, text: &str) -> Result<String, String> {
+        let Some(encoded) = text.strip_prefix(SEALED_PREFIX) else {
+            return Ok(text.to_string());
+        };
+        let sealed = STANDARD
+            .decode(encoded)
+            .ok()
+            .filter(|sealed| sealed.len() > NONCE_LEN)
+            .ok_or("Sealed text is malformed")?;
+        let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+        let plaintext = self
+            .0
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| "Text could not be decrypted; wrong key or corrupted")?;
+        String::from_utf8(plaintext).map_err(|e| e.to_string())
+    }
+
+    /// `todo` with its text sealed.
+    pub fn seal_todo(&self, todo: &Todo) -> Todo {
+        Todo {
+            text: self.seal(&todo.text),
+            ..todo.clone()
+        }
+    }
+
+    /// `todo` with its text opened.
+    pub fn open_todo(&self, todo: Todo) -> Result<Todo, String> {
+        let text = self
+            .open(&todo.text)
+            .map_err(|e| format!("todo {}: {}", todo.id, e))?;
+        Ok(Todo { text, ..todo })
+    }
+
+    /// `open_todo` for todos already checked to open; one that stops
+    /// opening is logged and left sealed rather than failing the read.
+    fn open_checked(&self, todo: Todo) -> Todo {
+        match self.open(&todo.text) {
+            Ok(text) => Todo { text, ..todo },
+            Err(e) => {
+                log::warn!("Reading todo {}: {}", todo.id, e);
+                todo
+            }
+        }
+    }
+}
+
+/// A backend that only ever sees sealed todo text. Everything above it,
+/// from the service to the API, sees plain text; everything below, from
+/// the journal on disk to a database, sees ciphertext.
+pub struct EncryptedStore {
+    inner: Box<dyn TodoStore>,
+    cipher: TextCipher,
+}
+
+impl EncryptedStore {
+    /// Wraps `inner`, checking that every todo it already holds opens with
+    /// `cipher`, so a wrong key fails at boot rather than on some read.
+    pub fn new(inner: Box<dyn TodoStore>, cipher: TextCipher) -> io::Result<Self> {
+        for todo in inner.all() {
+            cipher
+                .open_todo(todo)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        }
+        Ok(EncryptedStore { inner, cipher })
+    }
+}
+
+impl TodoStore for EncryptedStore {
+    fn all(&self) -> Vec<Todo> {
+        self.inner
+            .all()
+            .into_iter()
+            .map(|todo| self.cipher.open_checked(todo))
+            .collect()
+    }
+
+    fn get(&self, id: Uuid) -> Option<Todo> {
+        self.inner
+            .get(id)
+            .map(|todo| self.cipher.open_checked(todo))
+    }
+
+    fn insert(&self, todo: Todo) {
+        self.inner.insert(self.cipher.seal_todo(&todo));
+    }
+
+    fn update(&self, id: Uuid, apply: &mut dyn FnMut(&mut Todo)) -> Option<Todo> {
+        self.inner
+            .update(id, &mut |todo| {
+                let mut opened = self.cipher.open_checked(todo.clone());
+                apply(&mut opened);
+                *todo = self.cipher.seal_todo(&opened);
+            })
+            .map(|todo| self.cipher.open_checked(todo))
+    }
+
+    fn remove(&self, id: Uuid) -> Option<Todo> {
+        self.inner
+            .remove(id)
+            .map(|todo| self.cipher.open_checked(todo))
+    }
+
+    fn remove_where(&self, predicate: &dyn Fn(&Todo) -> bool) -> Vec<Todo> {
+        self.inner
+            .remove_where(&|todo| predicate(&self.cipher.open_checked(todo.clone())))
+            .into_iter()
+            .map(|todo| self.cipher.open_checked(todo))
+            .collect()
+    }
+
+    fn count(&self) -> usize {
+        self.inner.count()
+    }
+
+    fn ids(&self) -> Vec<Uuid> {
+        self.inner.ids()
+    }
+
+    fn get_many(&self, ids: &[Uuid]) -> Vec<Todo> {
+        self.inner
+            .get_many(ids)
+            .into_iter()
+            .map(|todo| self.cipher.open_checked(todo))
+            .collect()
+    }
+
+    fn all_until(&self, deadline: &Deadline) -> Result<Vec<Todo>, DeadlineExceeded> {
+        Ok(self
+            .inner
+            .all_until(deadline)?
+            .into_iter()
+            .map(|todo| self.cipher.open_checked(todo))
+            .collect())
+    }
+
+    fn get_until(&self, id: Uuid, deadline: &Deadline) -> Result<Option<Todo>, DeadlineExceeded> {
+        Ok(self
+            .inner
+            .get_until(id, deadline)?
+            .map(|todo| self.cipher.open_checked(todo)))
+    }
+
+    fn ping(&self, timeout: Duration) -> Result<(), String> {
+        self.inner.ping(timeout)
+    }
+
+    fn lock_stats(&self) -> Option<LockStatsSnapshot> {
+        self.inner.lock_stats()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{TodoCreate, TodoUpdate};
+    use crate::service::TodoService;
+    use crate::storage::InMemoryStore;
+    use std::sync::Arc;
+
+    fn cipher() -> TextCipher {
+        TextCipher::from_hex(&"ab".repeat(32)).unwrap()
+    }
+
+    /// Lets the test look under the encrypted store.
+    struct Shared(Arc<InMemoryStore>);
+
+    impl TodoStore for Shared {
+        fn all(&self) -> Vec<Todo> {
+            self.0.all()
+        }
+        fn get(&self, id: Uuid) -> Option<Todo> {
+            self.0.get(id)
+        }
+        fn insert(&self, todo: Todo) {
+            self.0.insert(todo)
+        }
+        fn update(&self, id: Uuid, apply: &mut dyn FnMut(&mut Todo)) -> Option<Todo> {
+            self.0.update(id, apply)
+        }
+        fn remove(&self, id: Uuid) -> Option<Todo> {
+            self.0.remove(id)
+        }
+        fn remove_where(&self, predicate: &dyn Fn(&Todo) -> bool) -> Vec<Todo> {
+            self.0.remove_where(predicate)
+        }
+        fn count(&self) -> usize {
+            self.0.count()
+        }
+        fn ping(&self, timeout: Duration) -> Result<(), String> {
+            self.0.ping(timeout)
+        }
+    }
+
+    #[test]
+    fn test_seals_and_opens_text() {
+        let cipher = cipher();
+        let sealed = cipher.seal("Pay rent");
+        assert!(sealed.starts_with(SEALED_PREFIX));
+        assert_ne!(sealed, cipher.seal("Pay rent"));
+        assert_eq!(cipher.open(&sealed).unwrap(), "Pay rent");
+        assert_eq!(
+            cipher.open("Written in the clear").unwrap(),
+            "Written in the clear"
+        );
+
+        let other = TextCipher::from_hex(&"cd".repeat(32)).unwrap();
+        assert!(other.open(&sealed).is_err());
+        assert!(TextCipher::from_hex("abcd").is_err());
+    }
+
+    #[test]
+    fn test_store_only_sees_ciphertext() {
+        let raw = Arc::new(InMemoryStore::new());
+        let store = EncryptedStore::new(Box::new(Shared(raw.clone())), cipher()).unwrap();
+        let service = TodoService::with_store(Box::new(store));
+        let todo = service.create(TodoCreate {
+            text: "See the doctor".to_string(),
+            ..Default::default()
+        });
+        assert_eq!(todo.text, "See the doctor");
+        assert!(raw.get(todo.id).unwrap().text.starts_with(SEALED_PREFIX));
+
+        let update = TodoUpdate {
+            text: Some("See the dentist".to_string()),
+            ..Default::default()
+        };
+        service.update(todo.id, update).unwrap();
+        assert!(raw.get(todo.id).unwrap().text.starts_with(SEALED_PREFIX));
+        let found = service.get_all(None, Some("dentist".to_string()), None);
+        assert_eq!(found[0].text, "See the dentist");
+
+        let wrong = TextCipher::from_hex(&"cd".repeat(32)).unwrap();
+        assert!(EncryptedStore::new(Box::new(Shared(raw)), wrong).is_err());
+    }
+}
@@ -0,0 +1,360 @@
+use crate::encryption::TextCipher;
+use crate::events::{Event, EventLog, EventType, RETAINED_EVENTS};
+use crate::journal::{append_lines, read_lines};
+use crate::models::todo_id;
+use crate::read_model::StatsReadModel;
+use crate::service::TodoService;
+use crate::storage::{InMemoryStore, TodoStore};
+use std::borrow::Cow;
+use std::fs::{self, File, OpenOptions};
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Append-only file of domain events, one JSON object per line. In event
+/// sourcing mode this is the only thing persisted; the todos are rebuilt
+/// from it at startup.
+pub(crate) struct EventFile {
+    path: PathBuf,
+    file: File,
+    /// Last write failure, cleared by the next successful write.
+    error: Option<String>,
+    /// Seals the todo text of the events written, when set.
+    cipher: Option<TextCipher>,
+}
+
+impl EventFile {
+    /// `events` as they are written to the file.
+    fn sealed<'a>(&self, events: &'a [Event]) -> Cow<'a, [Event]> {
+        match &self.cipher {
+            Some(cipher) => Cow::Owned(
+                events
+                    .iter()
+                    .map(|event| Event {
+                        todo: cipher.seal_todo(&event.todo),
+                        ..event.clone()
+                    })
+                    .collect(),
+            ),
+            None => Cow::Borrowed(events),
+        }
+    }
+
+    pub(crate) fn append(&mut self, event: &Event) {
+        let sealed = self.sealed(std::slice::from_ref(event));
+        match append_lines(&mut self.file, &sealed) {
+            Ok(()) => self.error = None,
+            Err(e) => {
+                log::error!("Event store write to {} failed: {}", self.path.display(), e);
+                self.error = Some(e.to_string());
+            }
+        }
+    }
+
+    /// Replaces the whole file with `events`, e.g. after history was
+    /// redacted. The new file is written aside and renamed into place so a
+    /// crash leaves one version or the other.
+    pub(crate) fn rewrite(&mut self, events: &[Event]) {
+        match self.replace(events) {
+            Ok(()) => self.error = None,
+            Err(e) => {
+                log::error!(
+                    "Event store rewrite of {} failed: {}",
+                    self.path.display(),
+                    e
+                );
+                self.error = Some(e.to_string());
+            }
+        }
+    }
+
+    fn replace(&mut self, events: &[Event]) -> io::Result<()> {
+        let mut tmp_name = self.path.as_os_str().to_owned();
+        tmp_name.push(".tmp");
+        let tmp_path = PathBuf::from(tmp_name);
+        let mut tmp = File::create(&tmp_path)?;
+        append_lines(&mut tmp, &self.sealed(events))?;
+        fs::rename(&tmp_path, &self.path)?;
+        self.file = OpenOptions::new().append(true).open(&self.path)?;
+        Ok(())
+    }
+
+    /// The first `count` events in the file, to rewrite along with those
+    /// the log keeps in memory. A failure to read them is recorded like a
+    /// failed write.
+    pub(crate) fn history(&mut self, count: u64) -> Option<Vec<Event>> {
+        if count == 0 {
+            return Some(Vec::new());
+        }
+        let read = read_events(&self.path, self.cipher.as_ref()).and_then(|mut events| {
+            if (events.len() as u64) < count {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("expected {} events but found {}", count, events.len()),
+                ));
+            }
+            events.truncate(count as usize);
+            Ok(events)
+        });
+        match read {
+            Ok(events) => Some(events),
+            Err(e) => {
+                log::error!("Event store read of {} failed: {}", self.path.display(), e);
+                self.error = Some(e.to_string());
+                None
+            }
+        }
+    }
+
+    pub(crate) fn check(&self) -> Result<(), String> {
+        match &self.error {
+            Some(error) => Err(format!("event store write failed: {}", error)),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Reads the events in the file at `path`, opening their text with
+/// `cipher` when it was sealed.
+fn read_events(path: &Path, cipher: Option<&TextCipher>) -> io::Result<Vec<Event>> {
+    let mut events: Vec<Event> = if path.exists() {
+        read_lines(path)?
+    } else {
+        Vec::new()
+    };
+    if let Some(cipher) = cipher {
+        for event in &mut events {
+            event.todo = cipher
+                .open_todo(event.todo.clone())
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        }
+    }
+    Ok(events)
+}
+
+/// Folds `events` into the state of the collection they describe.
+pub fn project(events: &[Event]) -> InMemoryStore {
+    let store = InMemoryStore::new();
+    for event in events {
+        match event.event_type {
+            EventType::Created
+            | EventType::Updated
+            | EventType::Completed
+            | EventType::Reopened
+            | EventType::Escalated
+            | EventType::Snoozed => store.insert(event.todo.clone()),
+            EventType::Deleted => {
+                store.remove(todo_id(&event.todo_id));
+            }
+            EventType::ChildRemoved => {}
+        }
+    }
+    store
+}
+
+impl TodoService {
+    /// Opens a service whose source of truth is the event file at `path`.
+    /// The todos are a projection of the events, and the restored log keeps
+    /// serving the event history and changes feed across restarts.
+    pub fn event_sourced(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::open_event_sourced(path.as_ref(), None, RETAINED_EVENTS)
+    }
+
+    /// `event_sourced`, with the todo text in the file sealed by `cipher`.
+    /// Events written before encryption was turned on are read as they are.
+    pub fn event_sourced_encrypted(path: impl AsRef<Path>, cipher: TextCipher) -> io::Result<Self> {
+        Self::open_event_sourced(path.as_ref(), Some(cipher), RETAINED_EVENTS)
+    }
+
+    fn open_event_sourced(
+        path: &Path,
+        cipher: Option<TextCipher>,
+        retained: usize,
+    ) -> io::Result<Self> {
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)?;
+        }
+        let events = read_events(path, cipher.as_ref())?;
+        // Cursors index the log by sequence, so a gap would corrupt every
+        // read after it.
+        if let Some((index, event)) = events
+            .iter()
+            .enumerate()
+            .find(|(index, event)| event.sequence != *index as u64 + 1)
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "{}: expected event {} but found {}",
+                    path.display(),
+                    index + 1,
+                    event.sequence
+                ),
+            ));
+        }
+
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let store = project(&events);
+        let stats = StatsReadModel::from_todos(&store.all());
+        let log = EventLog::restore(
+            events,
+            EventFile {
+                path: path.to_path_buf(),
+                file,
+                error: None,
+                cipher,
+            },
+            retained,
+        );
+        Ok(TodoService::from_parts(Box::new(store), log, stats))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cascade::CascadePolicy;
+    use crate::deadline::Deadline;
+    use crate::models::{TodoCreate, TodoUpdate};
+    use uuid::Uuid;
+
+    struct TempFile(PathBuf);
+
+    impl TempFile {
+        fn new() -> Self {
+            let dir = std::env::temp_dir().join(format!("spicy-events-{}", uuid::Uuid::new_v4()));
+            TempFile(dir.join("events.jsonl"))
+        }
+    }
+
+    impl Drop for TempFile {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(self.0.parent().unwrap());
+        }
+    }
+
+    fn create(service: &TodoService, text: &str) -> Uuid {
+        service
+            .create(TodoCreate {
+                text: text.to_string(),
+                ..Default::default()
+            })
+            .id
+    }
+
+    #[test]
+    fn test_state_is_rebuilt_from_events() {
+        let file = TempFile::new();
+        let service = TodoService::event_sourced(&file.0).unwrap();
+        let kept = create(&service, "Kept");
+        let dropped = create(&service, "Dropped");
+        service.toggle(kept);
+        service.update(
+            kept,
+            TodoUpdate {
+                text: Some("Kept and renamed".to_string()),
+                ..Default::default()
+            },
+        );
+        service.delete(dropped);
+        let version = service.collection_version().version;
+        drop(service);
+
+        let reopened = TodoService::event_sourced(&file.0).unwrap();
+        let todos = reopened.get_all(None, None, None);
+        assert_eq!(todos.len(), 1);
+        assert_eq!(todos[0].text, "Kept and renamed");
+        assert!(todos[0].completed);
+        assert_eq!(reopened.collection_version().version, version);
+        assert_eq!(reopened.sequence(), 5);
+        assert_eq!(reopened.changes_since(3, None).unwrap().changes.len(), 2);
+
+        create(&reopened, "After restart");
+        assert_eq!(reopened.sequence(), 6);
+        drop(reopened);
+        assert_eq!(TodoService::event_sourced(&file.0).unwrap().sequence(), 6);
+    }
+
+    #[test]
+    fn test_redacted_history_is_persisted() {
+        let file = TempFile::new();
+        let service = TodoService::event_sourced(&file.0).unwrap();
+        let kept = create(&service, "Kept");
+        let secret = create(&service, "Secret");
+        let policy = CascadePolicy {
+            missed_occurrences: true,
+            history: true,
+        };
+        service
+            .delete_cascading_until(secret, &policy, &Deadline::unbounded())
+            .unwrap();
+        create(&service, "After redaction");
+        drop(service);
+
+        assert!(!fs::read_to_string(&file.0).unwrap().contains("Secret"));
+        let reopened = TodoService::event_sourced(&file.0).unwrap();
+        assert_eq!(reopened.sequence(), 5);
+        assert_eq!(reopened.get_all(None, None, None).len(), 2);
+        assert!(reopened.get_by_id(kept).is_some());
+    }
+
+    #[test]
+    fn test_keeps_the_latest_events_in_memory() {
+        let file = TempFile::new();
+        let service = TodoService::open_event_sourced(&file.0, None, 10).unwrap();
+        let secret = create(&service, "Secret");
+        for i in 0..20 {
+            create(&service, &format!("Todo {}", i));
+        }
+        let events = service.events().all();
+        assert!(events.len() <= 11);
+        assert_eq!(events.last().unwrap().sequence, 21);
+        assert_eq!(service.events().pruned_through(), events[0].sequence - 1);
+
+        // Redaction still reaches the events only the file holds.
+        let policy = CascadePolicy {
+            missed_occurrences: true,
+            history: true,
+        };
+        service
+            .delete_cascading_until(secret, &policy, &Deadline::unbounded())
+            .unwrap();
+        drop(service);
+
+        assert!(!fs::read_to_string(&file.0).unwrap().contains("Secret"));
+        let reopened = TodoService::open_event_sourced(&file.0, None, 10).unwrap();
+        assert_eq!(reopened.sequence(), 23);
+        assert_eq!(reopened.get_all(None, None, None).len(), 20);
+        assert!(reopened.events().all().len() <= 11);
+    }
+
+    #[test]
+    fn test_encrypted_file_holds_no_text() {
+        let file = TempFile::new();
+        let cipher = TextCipher::from_hex(&"ab".repeat(32)).unwrap();
+        let service = TodoService::event_sourced_encrypted(&file.0, cipher.clone()).unwrap();
+        let id = create(&service, "See the doctor");
+        drop(service);
+
+        assert!(!fs::read_to_string(&file.0).unwrap().contains("doctor"));
+        let reopened = TodoService::event_sourced_encrypted(&file.0, cipher).unwrap();
+        assert_eq!(reopened.get_by_id(id).unwrap().text, "See the doctor");
+        assert_eq!(reopened.events().recent(1)[0].todo.text, "See the doctor");
+        let wrong = TextCipher::from_hex(&"cd".repeat(32)).unwrap();
+        assert!(TodoService::event_sourced_encrypted(&file.0, wrong).is_err());
+    }
+
+    #[test]
+    fn test_rejects_sequence_gap() {
+        let file = TempFile::new();
+        let service = TodoService::event_sourced(&file.0).unwrap();
+        create(&service, "One");
+        create(&service, "Two");
+        drop(service);
+
+        let contents = fs::read_to_string(&file.0).unwrap();
+        let second = contents.lines().nth(1).unwrap();
+        fs::write(&file.0, format!("{}\n", second)).unwrap();
+        let error = TodoService::event_sourced(&file.0).err().unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+}
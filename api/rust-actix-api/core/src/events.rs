@@ -0,0 +1,507 @@
+use crate::cascade::ChildRef;
+use crate::context::RequestContext;
+use crate::event_store::EventFile;
+use crate::models::Todo;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+/// Capacity of the in-process broadcast channel. Slow subscribers that fall
+/// further behind than this skip ahead and can catch up from the log.
+const BROADCAST_CAPACITY: usize = 1024;
+/// Most events a log on an event file keeps in memory. The file holds
+/// them all; older ones read as pruned.
+pub const RETAINED_EVENTS: usize = 100_000;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub enum EventType {
+    #[serde(rename = "todo.created")]
+    Created,
+    #[serde(rename = "todo.updated")]
+    Updated,
+    #[serde(rename = "todo.completed")]
+    Completed,
+    #[serde(rename = "todo.reopened")]
+    Reopened,
+    #[serde(rename = "todo.deleted")]
+    Deleted,
+    /// Priority raised a level for being overdue, by an escalation
+    /// policy; see `policy::PolicyAction::EscalatePriority`.
+    #[serde(rename = "todo.escalated")]
+    Escalated,
+    /// Due date put off by `POST /api/todos/{id}/snooze`.
+    #[serde(rename = "todo.snoozed")]
+    Snoozed,
+    /// Something hanging off the todo was removed along with it; see
+    /// `Event::child`. Leaves the todo itself untouched.
+    #[serde(rename = "todo.child.removed")]
+    ChildRemoved,
+}
+
+/// A domain event describing a single mutation of the todo collection.
+/// `todo` is the state after the change, or the last known state for deletions.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Event {
+    pub id: String,
+    pub sequence: u64,
+    #[serde(rename = "type")]
+    pub event_type: EventType,
+    #[serde(rename = "todoId")]
+    pub todo_id: String,
+    /// Who caused the event, when known.
+    pub actor: Option<String>,
+    /// The request that caused the event; see `RequestContext`.
+    #[serde(rename = "requestId", default, skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub workspace: Option<String>,
+    pub timestamp: DateTime<Utc>,
+    pub todo: Todo,
+    /// The removed child, for `ChildRemoved` events.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub child: Option<ChildRef>,
+}
+
+impl Event {
+    /// The context the event was appended in, for carrying it on to what
+    /// the event sets off.
+    pub fn context(&self) -> RequestContext {
+        RequestContext {
+            request_id: self.request_id.clone(),
+            user: self.actor.clone(),
+            workspace: self.workspace.clone(),
+        }
+    }
+}
+
+/// Criteria for searching the event log. Unset fields match everything;
+/// the time range is inclusive.
+#[derive(Debug, Clone, Default)]
+pub struct EventFilter {
+    pub event_types: Vec<EventType>,
+    pub todo_id: Option<String>,
+    pub actor: Option<String>,
+    pub request_id: Option<String>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+}
+
+impl EventFilter {
+    pub fn matches(&self, event: &Event) -> bool {
+        (self.event_types.is_empty() || self.event_types.contains(&event.event_type))
+            && self.todo_id.as_ref().is_none_or(|id| *id == event.todo_id)
+            && self
+                .actor
+                .as_ref()
+                .is_none_or(|actor| event.actor.as_ref() == Some(actor))
+            && self
+                .request_id
+                .as_ref()
+                .is_none_or(|id| event.request_id.as_ref() == Some(id))
+            && self.from.is_none_or(|from| event.timestamp >= from)
+            && self.to.is_none_or(|to| event.timestamp <= to)
+    }
+}
+
+/// Position in the event log to read from: either an event sequence number
+/// or a point in time (RFC 3339). Both are exclusive.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EventCursor {
+    Sequence(u64),
+    Timestamp(DateTime<Utc>),
+}
+
+impl FromStr for EventCursor {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        if let Ok(sequence) = value.parse::<u64>() {
+            return Ok(EventCursor::Sequence(sequence));
+        }
+        DateTime::parse_from_rfc3339(value)
+            .map(|timestamp| EventCursor::Timestamp(timestamp.with_timezone(&Utc)))
+            .map_err(|_| {
+                format!(
+                    "Invalid cursor '{}': expected a sequence number or RFC 3339 timestamp",
+                    value
+                )
+            })
+    }
+}
+
+/// Append-only log of domain events that also fans new events out to live
+/// subscribers (webhook dispatcher, etc.). In-memory unless opened on an
+/// event file, in which case every event is persisted as it is appended
+/// and only the latest are kept in memory.
+pub struct EventLog {
+    events: Mutex<Vec<Event>>,
+    /// Sequence of the last event pruned from the front of `events`; only
+    /// changed under the `events` lock.
+    pruned: AtomicU64,
+    sender: broadcast::Sender<Event>,
+    file: Option<Mutex<EventFile>>,
+    /// Most events kept in memory, for a log whose file keeps the rest.
+    window: Option<usize>,
+}
+
+impl EventLog {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(BROADCAST_CAPACITY);
+        EventLog {
+            events: Mutex::new(Vec::new()),
+            pruned: AtomicU64::new(0),
+            sender,
+            file: None,
+            window: None,
+        }
+    }
+
+    /// A log that continues `events` (as read back from `file`) and
+    /// persists new events to it, keeping the latest `window` in memory.
+    pub(crate) fn restore(events: Vec<Event>, file: EventFile, window: usize) -> Self {
+        let (sender, _) = broadcast::channel(BROADCAST_CAPACITY);
+        let log = EventLog {
+            events: Mutex::new(events),
+            pruned: AtomicU64::new(0),
+            sender,
+            file: Some(Mutex::new(file)),
+            window: Some(window),
+        };
+        log.trim(&mut log.events.lock().unwrap());
+        log
+    }
+
+    pub fn append(&self, event_type: EventType, todo: &Todo) -> Event {
+        self.push(event_type, todo, None)
+    }
+
+    /// Appends a `ChildRemoved` event for `child` of `todo`.
+    pub fn append_child(&self, todo: &Todo, child: ChildRef) -> Event {
+        self.push(EventType::ChildRemoved, todo, Some(child))
+    }
+
+    fn push(&self, event_type: EventType, todo: &Todo, child: Option<ChildRef>) -> Event {
+        let context = RequestContext::current().unwrap_or_default();
+        let mut events = self.events.lock().unwrap();
+        let event = Event {
+            id: Uuid::new_v4().to_string(),
+            sequence: self.pruned.load(Ordering::Relaxed) + events.len() as u64 + 1,
+            event_type,
+            todo_id: todo.id.to_string(),
+            actor: context.user,
+            request_id: context.request_id,
+            workspace: context.workspace,
+            timestamp: Utc::now(),
+            todo: todo.clone(),
+            child,
+        };
+        if let Some(file) = &self.file {
+            // Written under the log lock so the file keeps sequence order.
+            file.lock().unwrap().append(&event);
+        }
+        events.push(event.clone());
+        self.trim(&mut events);
+        // No receivers is fine; the event is still in the log.
+        let _ = self.sender.send(event.clone());
+        event
+    }
+
+    /// Drops the oldest events from memory once a log with a window holds
+    /// a tenth more than it, back down to the window, so that not every
+    /// append shifts the whole log.
+    fn trim(&self, events: &mut Vec<Event>) {
+        let Some(window) = self.window else {
+            return;
+        };
+        if events.len() <= window + window / 10 {
+            return;
+        }
+        let cut = events.len() - window;
+        self.pruned.store(events[cut - 1].sequence, Ordering::Relaxed);
+        events.drain(..cut);
+    }
+
+    /// Applies `edit` to every event, in memory and on disk, those only the
+    /// event file still holds included. Returns the sequences of the events
+    /// it changed.
+    fn edit(&self, edit: &mut dyn FnMut(&mut Event) -> bool) -> Vec<u64> {
+        let mut events = self.events.lock().unwrap();
+        let Some(file) = &self.file else {
+            return edit_each(&mut events, edit);
+        };
+        let mut file = file.lock().unwrap();
+        let pruned = self.pruned.load(Ordering::Relaxed);
+        let Some(mut history) = file.history(pruned) else {
+            return Vec::new();
+        };
+        history.append(&mut events);
+        let edited = edit_each(&mut history, edit);
+        if !edited.is_empty() {
+            file.rewrite(&history);
+        }
+        *events = history.split_off(pruned as usize);
+        edited
+    }
+
+    /// Rewrites the payload of every event for `todo_id` with `redact`, in
+    /// memory and on disk. Returns the sequences of the rewritten events.
+    pub fn redact(&self, todo_id: &str, redact: &dyn Fn(&Todo) -> Todo) -> Vec<u64> {
+        self.edit(&mut |event| {
+            if event.todo_id != todo_id {
+                return false;
+            }
+            event.todo = redact(&event.todo);
+            true
+        })
+    }
+
+    /// Clears `actor` from every event it caused, in memory and on disk.
+    /// Returns how many events named it.
+    pub fn anonymize(&self, actor: &str) -> usize {
+        self.edit(&mut |event| {
+            if event.actor.as_deref() != Some(actor) {
+                return false;
+            }
+            event.actor = None;
+            true
+        })
+        .len()
+    }
+
+    /// Drops the events recorded before `cutoff`, handing them to `archive`
+    /// first; if it fails, nothing is dropped. Returns how many were.
+    /// Sequences carry on where they were, and cursors from before the cut
+    /// read as too old. An event file is the source of truth for the todos,
+    /// so a log on one is never pruned.
+    pub fn prune_before(
+        &self,
+        cutoff: DateTime<Utc>,
+        archive: &mut dyn FnMut(&[Event]) -> Result<(), String>,
+    ) -> Result<usize, String> {
+        if self.file.is_some() {
+            return Err("The event store's history is its data and cannot be pruned".to_string());
+        }
+        let mut events = self.events.lock().unwrap();
+        let cut = events.partition_point(|event| event.timestamp < cutoff);
+        if cut == 0 {
+            return Ok(0);
+        }
+        archive(&events[..cut])?;
+        self.pruned.store(events[cut - 1].sequence, Ordering::Relaxed);
+        events.drain(..cut);
+        Ok(cut)
+    }
+
+    /// Sequence of the last event pruned, or 0 if none was. Reading on
+    /// from before it would miss events.
+    pub fn pruned_through(&self) -> u64 {
+        let _events = self.events.lock().unwrap();
+        self.pruned.load(Ordering::Relaxed)
+    }
+
+    pub fn check(&self, timeout: std::time::Duration) -> Result<(), String> {
+        drop(crate::storage::lock_within(&self.events, timeout)?);
+        match &self.file {
+            Some(file) => crate::storage::lock_within(file, timeout)?.check(),
+            None => Ok(()),
+        }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<Event> {
+        self.sender.subscribe()
+    }
+
+    #[cfg(test)]
+    pub fn all(&self) -> Vec<Event> {
+        self.events.lock().unwrap().clone()
+    }
+
+    /// The last `limit` events, newest first.
+    pub fn recent(&self, limit: usize) -> Vec<Event> {
+        let events = self.events.lock().unwrap();
+        events.iter().rev().take(limit).cloned().collect()
+    }
+
+    /// Timestamp of the most recent event.
+    pub fn latest_timestamp(&self) -> Option<DateTime<Utc>> {
+        self.events.lock().unwrap().last().map(|event| event.timestamp)
+    }
+
+    /// Sequence of the most recent event, or 0 for an empty log.
+    pub fn latest_sequence(&self) -> u64 {
+        let events = self.events.lock().unwrap();
+        self.pruned.load(Ordering::Relaxed) + events.len() as u64
+    }
+
+    /// The most recent event for `todo_id`. Its sequence doubles as the
+    /// todo's version.
+    pub fn latest_for(&self, todo_id: &str) -> Option<Event> {
+        self.events
+            .lock()
+            .unwrap()
+            .iter()
+            .rev()
+            .find(|event| event.todo_id == todo_id)
+            .cloned()
+    }
+
+    /// Returns the events matching `filter`, oldest first.
+    pub fn search(&self, filter: &EventFilter) -> Vec<Event> {
+        self.events
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|event| filter.matches(event))
+            .cloned()
+            .collect()
+    }
+
+    /// Returns the events recorded after `cursor`, oldest first.
+    pub fn since(&self, cursor: &EventCursor) -> Vec<Event> {
+        let events = self.events.lock().unwrap();
+        match cursor {
+            EventCursor::Sequence(sequence) => {
+                // Sequences are 1-based and contiguous, so past the pruned
+                // ones they index the log directly.
+                let pruned = self.pruned.load(Ordering::Relaxed);
+                let start = (sequence.saturating_sub(pruned) as usize).min(events.len());
+                events[start..].to_vec()
+            }
+            EventCursor::Timestamp(timestamp) => events
+                .iter()
+                .filter(|event| event.timestamp > *timestamp)
+                .cloned()
+                .collect(),
+        }
+    }
+}
+
+fn edit_each(events: &mut [Event], edit: &mut dyn FnMut(&mut Event) -> bool) -> Vec<u64> {
+    events
+        .iter_mut()
+        .filter_map(|event| edit(event).then_some(event.sequence))
+        .collect()
+}
+
+impl Default for EventLog {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{todo_id, Priority};
+
+    fn sample_todo() -> Todo {
+        Todo {
+            id: todo_id("todo-1"),
+            text: "Test".to_string(),
+            priority: Priority::High,
+            completed: false,
+            due_date: None,
+            reminder_time: None,
+            recurrence: None,
+            recurrence_end: None,
+            remaining_occurrences: None,
+            estimate_minutes: None,
+            location: None,
+            color: None,
+            icon: None,
+            pinned: false,
+            rollover_count: 0,
+            rolled_over_to: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_append_assigns_sequence_and_broadcasts() {
+        let log = EventLog::new();
+        let mut rx = log.subscribe();
+
+        let first = log.append(EventType::Created, &sample_todo());
+        let second = log.append(EventType::Completed, &sample_todo());
+
+        assert_eq!(first.sequence, 1);
+        assert_eq!(second.sequence, 2);
+        assert_eq!(log.all().len(), 2);
+        assert_eq!(rx.try_recv().unwrap().id, first.id);
+        assert_eq!(rx.try_recv().unwrap().id, second.id);
+    }
+
+    #[test]
+    fn test_event_type_serialization() {
+        let json = serde_json::to_string(&EventType::Completed).unwrap();
+        assert_eq!(json, "\"todo.completed\"");
+    }
+
+    #[test]
+    fn test_since_cursor() {
+        let log = EventLog::new();
+        log.append(EventType::Created, &sample_todo());
+        let second = log.append(EventType::Updated, &sample_todo());
+        log.append(EventType::Deleted, &sample_todo());
+
+        let after_first = log.since(&EventCursor::Sequence(1));
+        assert_eq!(after_first.len(), 2);
+        assert_eq!(after_first[0].id, second.id);
+        assert!(log.since(&EventCursor::Sequence(99)).is_empty());
+
+        let by_time = log.since(&EventCursor::Timestamp(second.timestamp));
+        assert_eq!(by_time.len(), 1);
+        assert_eq!(by_time[0].event_type, EventType::Deleted);
+    }
+
+    #[test]
+    fn test_cursor_parsing() {
+        assert_eq!("42".parse::<EventCursor>(), Ok(EventCursor::Sequence(42)));
+        assert!(matches!(
+            "2024-01-01T00:00:00Z".parse::<EventCursor>(),
+            Ok(EventCursor::Timestamp(_))
+        ));
+        assert!("yesterday".parse::<EventCursor>().is_err());
+    }
+
+    #[test]
+    fn test_search_filters() {
+        let log = EventLog::new();
+        let mut other = sample_todo();
+        other.id = todo_id("todo-2");
+
+        let created = log.append(EventType::Created, &sample_todo());
+        log.append(EventType::Created, &other);
+        let completed = log.append(EventType::Completed, &sample_todo());
+
+        let by_todo = log.search(&EventFilter {
+            todo_id: Some(todo_id("todo-1").to_string()),
+            ..Default::default()
+        });
+        assert_eq!(by_todo.len(), 2);
+
+        let by_type = log.search(&EventFilter {
+            event_types: vec![EventType::Completed],
+            ..Default::default()
+        });
+        assert_eq!(by_type.len(), 1);
+        assert_eq!(by_type[0].id, completed.id);
+
+        let by_time = log.search(&EventFilter {
+            to: Some(created.timestamp),
+            ..Default::default()
+        });
+        assert_eq!(by_time[0].id, created.id);
+
+        let by_actor = log.search(&EventFilter {
+            actor: Some("alice".to_string()),
+            ..Default::default()
+        });
+        assert!(by_actor.is_empty());
+    }
+}
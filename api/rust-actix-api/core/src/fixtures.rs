@@ -0,0 +1,260 @@
+use crate::models::{Priority, TodoCreate};
+use chrono::{Duration, Utc};
+use std::fs;
+use std::path::Path;
+
+/// Names of the built-in fixture sets accepted by the seed endpoint.
+pub const FIXTURE_SETS: &[&str] = &["default", "demo"];
+
+/// Most todos `generate` makes in one call.
+pub const MAX_GENERATED: usize = 100_000;
+
+const VERBS: [&str; 10] = [
+    "Call", "Email", "Review", "Buy", "Book", "Fix", "Plan", "Pay", "Clean", "Write",
+];
+const OBJECTS: [&str; 12] = [
+    "the landlord",
+    "quarterly report",
+    "groceries",
+    "dentist appointment",
+    "flights to Lisbon",
+    "leaking tap",
+    "team offsite",
+    "electricity bill",
+    "garage",
+    "blog post",
+    "birthday present",
+    "car insurance",
+];
+const TAGS: [&str; 5] = ["work", "home", "errands", "health", "finance"];
+
+/// Returns the todos for a built-in fixture set. Due dates are relative to
+/// today so seeded data always has something overdue, due, and upcoming.
+pub fn builtin(name: &str) -> Option<Vec<TodoCreate>> {
+    match name {
+        "default" => Some(default_set()),
+        "demo" => Some(demo_set()),
+        _ => None,
+    }
+}
+
+/// Reads a fixture file: a JSON array of todos in the same shape as the
+/// create endpoint accepts.
+pub fn load_file(path: &Path) -> Result<Vec<TodoCreate>, String> {
+    let contents = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read fixture file {}: {}", path.display(), e))?;
+    let todos: Vec<TodoCreate> = serde_json::from_str(&contents)
+        .map_err(|e| format!("Invalid fixture file {}: {}", path.display(), e))?;
+    if todos.iter().any(|todo| todo.text.trim().is_empty()) {
+        return Err(format!(
+            "Invalid fixture file {}: todo text is required",
+            path.display()
+        ));
+    }
+    Ok(todos)
+}
+
+/// Small deterministic generator (xorshift64*), so a load test can be
+/// repeated with the same data.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    /// Uniform in `0..bound`.
+    fn below(&mut self, bound: u64) -> u64 {
+        self.next() % bound
+    }
+
+    /// True `percent` times in a hundred.
+    fn chance(&mut self, percent: u64) -> bool {
+        self.below(100) < percent
+    }
+}
+
+/// `count` synthetic todos for load testing, the same for the same `seed`:
+/// a mix of priorities (mostly low and medium), tags, completion, and due
+/// dates from a month ago to two months out, some with reminders and
+/// estimates.
+pub fn generate(count: usize, seed: u64) -> Vec<TodoCreate> {
+    // Zero is xorshift's one fixed point.
+    let mut rng = Rng(seed.max(1));
+    let today = Utc::now().date_naive();
+    (0..count.min(MAX_GENERATED))
+        .map(|i| {
+            let mut text = format!(
+                "{} {}",
+                VERBS[rng.below(VERBS.len() as u64) as usize],
+                OBJECTS[rng.below(OBJECTS.len() as u64) as usize]
+            );
+            if rng.chance(60) {
+                text.push_str(&format!(" #{}", TAGS[rng.below(TAGS.len() as u64) as usize]));
+            }
+            if rng.chance(10) {
+                text.push_str(&format!(" ({})", i + 1));
+            }
+            let priority = match rng.below(10) {
+                0..=3 => Priority::Low,
+                4..=7 => Priority::Medium,
+                _ => Priority::High,
+            };
+            let due_date =
+                rng.chance(70).then(|| today + Duration::days(rng.below(91) as i64 - 30));
+            let reminder_time = due_date
+                .filter(|_| rng.chance(30))
+                .map(|_| format!("{:02}:{:02}", 7 + rng.below(13), rng.below(4) * 15));
+            TodoCreate {
+                text,
+                priority: Some(priority),
+                completed: Some(rng.chance(30)),
+                due_date: due_date.map(|date| date.to_string()),
+                reminder_time,
+                estimate_minutes: rng.chance(50).then(|| 15 * (1 + rng.below(8) as u32)),
+                ..Default::default()
+            }
+        })
+        .collect()
+}
+
+fn todo(
+    text: &str,
+    priority: Priority,
+    completed: bool,
+    due_in_days: Option<i64>,
+    reminder_time: Option<&str>,
+) -> TodoCreate {
+    let today = Utc::now().date_naive();
+    TodoCreate {
+        text: text.to_string(),
+        priority: Some(priority),
+        completed: Some(completed),
+        due_date: due_in_days.map(|days| (today + Duration::days(days)).to_string()),
+        reminder_time: reminder_time.map(|time| time.to_string()),
+        ..Default::default()
+    }
+}
+
+fn default_set() -> Vec<TodoCreate> {
+    vec![
+        todo(
+            "Learn Rust programming language",
+            Priority::High,
+            false,
+            Some(1),
+            Some("09:00"),
+        ),
+        todo(
+            "Build blazingly fast API with Actix-web",
+            Priority::High,
+            true,
+            Some(-1),
+            Some("14:30"),
+        ),
+        todo(
+            "Master async/await in Rust",
+            Priority::Medium,
+            false,
+            Some(7),
+            Some("16:00"),
+        ),
+    ]
+}
+
+fn demo_set() -> Vec<TodoCreate> {
+    let mut todos = default_set();
+    todos.extend(vec![
+        todo(
+            "Buy chili peppers for the hot sauce",
+            Priority::Medium,
+            false,
+            Some(0),
+            Some("18:00"),
+        ),
+        todo("Renew passport", Priority::High, false, Some(-3), None),
+        todo(
+            "Call the landlord about the heating",
+            Priority::High,
+            false,
+            Some(2),
+            Some("10:00"),
+        ),
+        todo("Water the plants", Priority::Low, true, None, None),
+        todo(
+            "Read 'The Rust Programming Language' chapter 16",
+            Priority::Low,
+            false,
+            Some(5),
+            None,
+        ),
+        todo(
+            "Plan weekend hike",
+            Priority::Low,
+            false,
+            Some(4),
+            Some("20:00"),
+        ),
+        todo(
+            "Submit expense report",
+            Priority::Medium,
+            false,
+            Some(-1),
+            Some("09:30"),
+        ),
+    ]);
+    todos
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_sets() {
+        for name in FIXTURE_SETS {
+            assert!(builtin(name).is_some(), "missing fixture set {}", name);
+        }
+        assert_eq!(builtin("default").unwrap().len(), 3);
+        assert!(builtin("demo").unwrap().len() > 3);
+        assert!(builtin("production").is_none());
+    }
+
+    #[test]
+    fn test_generate_is_repeatable() {
+        let todos = generate(500, 42);
+        assert_eq!(todos.len(), 500);
+        assert_eq!(todos[0].text, generate(1, 42)[0].text);
+        assert_ne!(todos[0].text, generate(1, 43)[0].text);
+        assert!(todos.iter().all(|todo| !todo.text.trim().is_empty()));
+        for priority in [Priority::Low, Priority::Medium, Priority::High] {
+            assert!(todos.iter().any(|todo| todo.priority == Some(priority.clone())));
+        }
+        assert!(todos.iter().any(|todo| todo.due_date.is_none()));
+        assert!(todos.iter().any(|todo| todo.reminder_time.is_some()));
+        assert!(todos.iter().any(|todo| todo.text.contains('#')));
+        assert_eq!(generate(MAX_GENERATED + 1, 1).len(), MAX_GENERATED);
+    }
+
+    #[test]
+    fn test_load_file() {
+        let path = std::env::temp_dir().join(format!("fixtures-{}.json", uuid::Uuid::new_v4()));
+        fs::write(
+            &path,
+            r#"[{"text": "From file", "priority": "high", "dueDate": "2030-01-01"}]"#,
+        )
+        .unwrap();
+
+        let todos = load_file(&path).unwrap();
+        assert_eq!(todos.len(), 1);
+        assert_eq!(todos[0].text, "From file");
+        assert_eq!(todos[0].priority, Some(Priority::High));
+
+        fs::write(&path, r#"{"not": "an array"}"#).unwrap();
+        assert!(load_file(&path).is_err());
+        fs::remove_file(&path).unwrap();
+    }
+}
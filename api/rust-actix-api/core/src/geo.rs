@@ -0,0 +1,58 @@
+use crate::models::Todo;
+use serde::Serialize;
+
+/// Mean Earth radius used by `distance_meters`.
+const EARTH_RADIUS_METERS: f64 = 6_371_008.8;
+
+/// Great-circle distance between two points in decimal degrees, by the
+/// haversine formula. Accurate to about 0.5%, plenty for "near me".
+pub fn distance_meters(from: (f64, f64), to: (f64, f64)) -> f64 {
+    let (lat1, lng1) = (from.0.to_radians(), from.1.to_radians());
+    let (lat2, lng2) = (to.0.to_radians(), to.1.to_radians());
+    let a = ((lat2 - lat1) / 2.0).sin().powi(2)
+        + lat1.cos() * lat2.cos() * ((lng2 - lng1) / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_METERS * a.sqrt().min(1.0).asin()
+}
+
+/// A todo found by a nearby query, with how far away it is.
+#[derive(Debug, Clone, Serialize)]
+pub struct NearbyTodo {
+    #[serde(flatten)]
+    pub todo: Todo,
+    #[serde(rename = "distanceMeters")]
+    pub distance_meters: f64,
+}
+
+/// The todos in `todos` with coordinates within `radius_meters` of
+/// `center`, nearest first.
+pub fn within(todos: Vec<Todo>, center: (f64, f64), radius_meters: f64) -> Vec<NearbyTodo> {
+    let mut nearby: Vec<NearbyTodo> = todos
+        .into_iter()
+        .filter_map(|todo| {
+            let point = todo.location.as_ref()?.coordinates()?;
+            let distance_meters = distance_meters(center, point);
+            (distance_meters <= radius_meters).then_some(NearbyTodo {
+                todo,
+                distance_meters,
+            })
+        })
+        .collect();
+    nearby.sort_by(|a, b| a.distance_meters.total_cmp(&b.distance_meters));
+    nearby
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_distance_meters() {
+        let paris = (48.8566, 2.3522);
+        let london = (51.5074, -0.1278);
+        let distance = distance_meters(paris, london);
+        assert!((distance - 343_500.0).abs() < 1_000.0, "{}", distance);
+        assert_eq!(distance_meters(paris, paris), 0.0);
+        let antipode = distance_meters((0.0, 0.0), (0.0, 180.0));
+        assert!((antipode - std::f64::consts::PI * EARTH_RADIUS_METERS).abs() < 1.0);
+    }
+}
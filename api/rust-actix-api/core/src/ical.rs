@@ -0,0 +1,548 @@
+//! Todos as iCalendar VTODO components (RFC 5545), for CalDAV clients.
+//! Only the fields both sides understand are mapped; everything else a
+//! client sends, such as alarms or categories, is dropped.
+
+use crate::deadline::{Deadline, DeadlineExceeded};
+use crate::events::EventType;
+use crate::models::{Priority, Recurrence, RecurrenceEnd, Todo};
+use crate::service::TodoService;
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
+use uuid::Uuid;
+
+pub const PRODUCT_ID: &str = "-//Spicy Todo//CalDAV//EN";
+/// Longest content line in octets, not counting the line break.
+const MAX_LINE_OCTETS: usize = 75;
+
+/// The todo fields a VTODO carries.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VTodo {
+    pub uid: Option<String>,
+    pub summary: String,
+    pub priority: Priority,
+    pub completed: bool,
+    pub due_date: Option<String>,
+    pub reminder_time: Option<String>,
+    pub recurrence: Option<Recurrence>,
+    pub recurrence_end: Option<RecurrenceEnd>,
+}
+
+/// What a conditional write expects of the current resource.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Precondition {
+    None,
+    /// `If-None-Match: *`: only create.
+    Absent,
+    /// `If-Match`: only overwrite this version.
+    Matches(String),
+}
+
+#[derive(Debug)]
+pub enum PutOutcome {
+    Created(Todo),
+    Updated(Todo),
+    PreconditionFailed,
+}
+
+/// Entity tag of one todo's calendar resource; changes whenever the todo
+/// does.
+pub fn etag(todo: &Todo) -> String {
+    format!("\"{}\"", todo.updated_at.timestamp_micros())
+}
+
+impl VTodo {
+    /// Replaces the mapped fields of `todo`, as a PUT replaces the whole
+    /// resource. Estimates and locations have no VTODO property and are
+    /// kept.
+    pub fn apply_to(self, todo: &mut Todo) {
+        todo.text = self.summary;
+        todo.priority = self.priority;
+        todo.completed = self.completed;
+        todo.due_date = self.due_date;
+        todo.reminder_time = self.reminder_time;
+        todo.recurrence = self.recurrence;
+        todo.recurrence_end = self.recurrence_end;
+        todo.reset_remaining_occurrences();
+    }
+}
+
+fn escape_text(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            ';' => escaped.push_str("\\;"),
+            ',' => escaped.push_str("\\,"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn unescape_text(value: &str) -> String {
+    let mut unescaped = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n' | 'N') => unescaped.push('\n'),
+            Some(c) => unescaped.push(c),
+            None => unescaped.push('\\'),
+        }
+    }
+    unescaped
+}
+
+/// Appends `line` folded into content lines of at most 75 octets, never
+/// splitting a UTF-8 character.
+fn push_line(out: &mut String, line: &str) {
+    let mut rest = line;
+    let mut limit = MAX_LINE_OCTETS;
+    while rest.len() > limit {
+        let mut split = limit;
+        while !rest.is_char_boundary(split) {
+            split -= 1;
+        }
+        out.push_str(&rest[..split]);
+        out.push_str("\r\n ");
+        rest = &rest[split..];
+        // The leading space of a continuation counts towards its length.
+        limit = MAX_LINE_OCTETS - 1;
+    }
+    out.push_str(rest);
+    out.push_str("\r\n");
+}
+
+fn format_timestamp(timestamp: DateTime<Utc>) -> String {
+    timestamp.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+fn due_date(todo: &Todo) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(todo.due_date.as_deref()?, "%Y-%m-%d").ok()
+}
+
+/// The todo as a VCALENDAR holding one VTODO, with CRLF line breaks.
+pub fn to_ics(todo: &Todo) -> String {
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        format!("PRODID:{}", PRODUCT_ID),
+        "BEGIN:VTODO".to_string(),
+        format!("UID:{}", todo.id),
+        format!("DTSTAMP:{}", format_timestamp(todo.updated_at)),
+        format!("CREATED:{}", format_timestamp(todo.created_at)),
+        format!("LAST-MODIFIED:{}", format_timestamp(todo.updated_at)),
+        format!("SUMMARY:{}", escape_text(&todo.text)),
+        format!(
+            "PRIORITY:{}",
+            match todo.priority {
+                Priority::High => 1,
+                Priority::Medium => 5,
+                Priority::Low => 9,
+            }
+        ),
+    ];
+    if let Some(due) = due_date(todo) {
+        let time = todo
+            .reminder_time
+            .as_deref()
+            .and_then(|time| NaiveTime::parse_from_str(time, "%H:%M").ok());
+        lines.push(match time {
+            // Floating time: the reminder has no time zone either.
+            Some(time) => format!("DUE:{}", due.and_time(time).format("%Y%m%dT%H%M%S")),
+            None => format!("DUE;VALUE=DATE:{}", due.format("%Y%m%d")),
+        });
+    }
+    if todo.completed {
+        lines.push("STATUS:COMPLETED".to_string());
+        lines.push(format!("COMPLETED:{}", format_timestamp(todo.updated_at)));
+    } else {
+        lines.push("STATUS:NEEDS-ACTION".to_string());
+    }
+    if let Some(recurrence) = todo.recurrence {
+        let mut rule = format!(
+            "RRULE:FREQ={}",
+            match recurrence {
+                Recurrence::Daily => "DAILY",
+                Recurrence::Weekly => "WEEKLY",
+                Recurrence::Monthly => "MONTHLY",
+            }
+        );
+        match (todo.recurrence_end, todo.remaining_occurrences) {
+            // COUNT includes the occurrence DUE points at, so the series
+            // stays right after occurrences have been completed.
+            (Some(RecurrenceEnd::AfterOccurrences(_)), Some(remaining)) => {
+                rule.push_str(&format!(";COUNT={}", remaining + 1));
+            }
+            (Some(RecurrenceEnd::Until(until)), _) => {
+                rule.push_str(&format!(";UNTIL={}", until.format("%Y%m%d")));
+            }
+            _ => {}
+        }
+        lines.push(rule);
+    }
+    lines.push("END:VTODO".to_string());
+    lines.push("END:VCALENDAR".to_string());
+
+    let mut ics = String::new();
+    for line in &lines {
+        push_line(&mut ics, line);
+    }
+    ics
+}
+
+/// One content line: name, parameters and value.
+struct Property<'a> {
+    name: String,
+    params: Vec<(String, &'a str)>,
+    value: &'a str,
+}
+
+impl Property<'_> {
+    fn param(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(param, _)| param == name)
+            .map(|(_, value)| value.trim_matches('"'))
+    }
+}
+
+/// Splits a content line at the first `:` outside a quoted parameter.
+fn parse_property(line: &str) -> Option<Property<'_>> {
+    let mut quoted = false;
+    let colon = line.char_indices().find_map(|(i, c)| match c {
+        '"' => {
+            quoted = !quoted;
+            None
+        }
+        ':' if !quoted => Some(i),
+        _ => None,
+    })?;
+    let (head, value) = (&line[..colon], &line[colon + 1..]);
+    let mut parts = head.split(';');
+    let name = parts.next()?.trim().to_ascii_uppercase();
+    let params = parts
+        .filter_map(|param| param.split_once('='))
+        .map(|(key, value)| (key.trim().to_ascii_uppercase(), value))
+        .collect();
+    Some(Property {
+        name,
+        params,
+        value,
+    })
+}
+
+/// A DATE or DATE-TIME value as a due date and, for date-times, a
+/// reminder time. Times are taken as written, whatever their zone.
+fn parse_due(value: &str) -> Result<(String, Option<String>), String> {
+    let invalid = || format!("Invalid DUE '{}'", value);
+    let (date, time) = match value.split_once('T') {
+        Some((date, time)) => (date, Some(time.trim_end_matches('Z'))),
+        None => (value, None),
+    };
+    let date = NaiveDate::parse_from_str(date, "%Y%m%d").map_err(|_| invalid())?;
+    let time = time
+        .map(|time| NaiveTime::parse_from_str(time, "%H%M%S").map_err(|_| invalid()))
+        .transpose()?;
+    Ok((
+        date.format("%Y-%m-%d").to_string(),
+        time.map(|time| time.format("%H:%M").to_string()),
+    ))
+}
+
+/// The RRULEs a todo can repeat by: daily, weekly or monthly, optionally
+/// bounded by COUNT or UNTIL.
+fn parse_rrule(value: &str) -> Result<(Recurrence, Option<RecurrenceEnd>), String> {
+    let unsupported = || {
+        format!(
+            "Unsupported RRULE '{}': only DAILY, WEEKLY and MONTHLY with COUNT or UNTIL repeat",
+            value
+        )
+    };
+    let mut recurrence = None;
+    let mut end = None;
+    for part in value.split(';').filter(|part| !part.is_empty()) {
+        let (key, value) = part.split_once('=').ok_or_else(unsupported)?;
+        match (key.to_ascii_uppercase().as_str(), value) {
+            ("FREQ", "DAILY") => recurrence = Some(Recurrence::Daily),
+            ("FREQ", "WEEKLY") => recurrence = Some(Recurrence::Weekly),
+            ("FREQ", "MONTHLY") => recurrence = Some(Recurrence::Monthly),
+            ("INTERVAL", "1") | ("WKST", _) => {}
+            ("COUNT", count) => {
+                let count = count.parse().map_err(|_| unsupported())?;
+                end = Some(RecurrenceEnd::AfterOccurrences(count));
+            }
+            ("UNTIL", until) => {
+                let date = until.split_once('T').map_or(until, |(date, _)| date);
+                let until = NaiveDate::parse_from_str(date, "%Y%m%d").map_err(|_| unsupported())?;
+                end = Some(RecurrenceEnd::Until(until));
+            }
+            _ => return Err(unsupported()),
+        }
+    }
+    Ok((recurrence.ok_or_else(unsupported)?, end))
+}
+
+/// Reads the first VTODO of an iCalendar object. Properties of nested
+/// components such as VALARM are ignored.
+pub fn parse(ics: &str) -> Result<VTodo, String> {
+    let unfolded = ics
+        .replace("\r\n ", "")
+        .replace("\r\n\t", "")
+        .replace("\n ", "")
+        .replace("\n\t", "");
+    let mut vtodo: Option<VTodo> = None;
+    // Components open inside the VTODO, itself included.
+    let mut depth = 0;
+    let mut status = None;
+    let mut completed_at = false;
+    for line in unfolded.lines().map(|line| line.trim_end_matches('\r')) {
+        let Some(property) = parse_property(line) else {
+            continue;
+        };
+        match property.name.as_str() {
+            "BEGIN" if depth > 0 => depth += 1,
+            "BEGIN" if vtodo.is_none() && property.value.eq_ignore_ascii_case("VTODO") => {
+                depth = 1;
+                vtodo = Some(VTodo {
+                    uid: None,
+                    summary: String::new(),
+                    priority: Priority::default(),
+                    completed: false,
+                    due_date: None,
+                    reminder_time: None,
+                    recurrence: None,
+                    recurrence_end: None,
+                });
+            }
+            "END" if depth > 0 => depth -= 1,
+            _ if depth != 1 => {}
+            "UID" => vtodo.as_mut().unwrap().uid = Some(unescape_text(property.value)),
+            "SUMMARY" => vtodo.as_mut().unwrap().summary = unescape_text(property.value),
+            "PRIORITY" => {
+                vtodo.as_mut().unwrap().priority = match property.value.trim().parse::<u8>() {
+                    Ok(1..=4) => Priority::High,
+                    Ok(6..=9) => Priority::Low,
+                    Ok(_) => Priority::Medium,
+                    Err(_) => return Err(format!("Invalid PRIORITY '{}'", property.value)),
+                }
+            }
+            "DUE" => {
+                let (date, time) = parse_due(property.value.trim())?;
+                let vtodo = vtodo.as_mut().unwrap();
+                vtodo.due_date = Some(date);
+                // An all-day DUE has no reminder time, even if the value
+                // parameter is missing.
+                if property.param("VALUE") != Some("DATE") {
+                    vtodo.reminder_time = time;
+                }
+            }
+            "STATUS" => status = Some(property.value.trim().to_ascii_uppercase()),
+            "COMPLETED" => completed_at = true,
+            "RRULE" => {
+                let (recurrence, end) = parse_rrule(property.value.trim())?;
+                let vtodo = vtodo.as_mut().unwrap();
+                vtodo.recurrence = Some(recurrence);
+                vtodo.recurrence_end = end;
+            }
+            _ => {}
+        }
+    }
+    let mut vtodo = vtodo.ok_or("Calendar data has no VTODO")?;
+    vtodo.completed = match status.as_deref() {
+        Some(status) => status == "COMPLETED",
+        None => completed_at,
+    };
+    Ok(vtodo)
+}
+
+impl TodoService {
+    /// Creates or replaces the todo `id` from a VTODO, checking
+    /// `precondition` against the current version under the write lock.
+    pub fn put_vtodo_until(
+        &self,
+        id: Uuid,
+        vtodo: VTodo,
+        precondition: &Precondition,
+        deadline: &Deadline,
+    ) -> Result<PutOutcome, DeadlineExceeded> {
+        let guard = self.write_lock(deadline)?;
+        let current = self.store().get(id);
+        let allowed = match (precondition, &current) {
+            (Precondition::None, _) => true,
+            (Precondition::Absent, current) => current.is_none(),
+            (Precondition::Matches(expected), Some(todo)) => &etag(todo) == expected,
+            (Precondition::Matches(_), None) => false,
+        };
+        if !allowed {
+            return Ok(PutOutcome::PreconditionFailed);
+        }
+
+        let now = Utc::now();
+        let outcome = match current {
+            Some(current) => {
+                let mut vtodo = Some(vtodo);
+                let updated = self.store().update(id, &mut |todo| {
+                    let Some(vtodo) = vtodo.take() else { return };
+                    vtodo.apply_to(todo);
+                    todo.updated_at = now;
+                });
+                let Some(updated) = updated else {
+                    return Ok(PutOutcome::PreconditionFailed);
+                };
+                let event_type = match (current.completed, updated.completed) {
+                    (false, true) => EventType::Completed,
+                    (true, false) => EventType::Reopened,
+                    _ => EventType::Updated,
+                };
+                self.record(event_type, &updated);
+                PutOutcome::Updated(updated)
+            }
+            None => {
+                let mut todo = Todo {
+                    id,
+                    text: String::new(),
+                    priority: Priority::default(),
+                    completed: false,
+                    due_date: None,
+                    reminder_time: None,
+                    recurrence: None,
+                    recurrence_end: None,
+                    remaining_occurrences: None,
+                    estimate_minutes: None,
+                    location: None,
+                    color: None,
+                    icon: None,
+                    pinned: false,
+                    rollover_count: 0,
+                    rolled_over_to: None,
+                    created_at: now,
+                    updated_at: now,
+                };
+                vtodo.apply_to(&mut todo);
+                self.store().insert(todo.clone());
+                self.record(EventType::Created, &todo);
+                PutOutcome::Created(todo)
+            }
+        };
+        drop(guard);
+        self.bump_version();
+        Ok(outcome)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{todo_id, TodoCreate};
+
+    fn vtodo(summary: &str) -> VTodo {
+        VTodo {
+            uid: None,
+            summary: summary.to_string(),
+            priority: Priority::Medium,
+            completed: false,
+            due_date: None,
+            reminder_time: None,
+            recurrence: None,
+            recurrence_end: None,
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let service = TodoService::new_empty();
+        let todo = service.create(TodoCreate {
+            text: "Pay rent; then, call the bank about a very long standing issue".to_string(),
+            priority: Some(Priority::High),
+            due_date: Some("2024-06-10".to_string()),
+            reminder_time: Some("09:30".to_string()),
+            recurrence: Some(Recurrence::Monthly),
+            recurrence_end: Some(RecurrenceEnd::AfterOccurrences(3)),
+            ..Default::default()
+        });
+        let ics = to_ics(&todo);
+        assert!(ics.contains("\r\nDUE:20240610T093000\r\n"));
+        assert!(ics.contains("\r\nRRULE:FREQ=MONTHLY;COUNT=3\r\n"));
+        assert!(ics.lines().all(|line| line.len() <= MAX_LINE_OCTETS + 1));
+
+        let parsed = parse(&ics).unwrap();
+        assert_eq!(parsed.uid.as_deref(), Some(todo.id.to_string().as_str()));
+        assert_eq!(parsed.summary, todo.text);
+        assert_eq!(parsed.priority, Priority::High);
+        assert_eq!(parsed.due_date.as_deref(), Some("2024-06-10"));
+        assert_eq!(parsed.reminder_time.as_deref(), Some("09:30"));
+        assert_eq!(parsed.recurrence, Some(Recurrence::Monthly));
+        assert_eq!(
+            parsed.recurrence_end,
+            Some(RecurrenceEnd::AfterOccurrences(3))
+        );
+        assert!(!parsed.completed);
+    }
+
+    #[test]
+    fn test_parse_client_vtodo() {
+        let ics = "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nBEGIN:VTODO\r\nUID:abc@tasks.org\r\n\
+                   SUMMARY:Water\r\n  plants\\, ferns\r\nPRIORITY:7\r\nDUE;VALUE=DATE:20240612\r\n\
+                   STATUS:COMPLETED\r\nX-APPLE-SORT-ORDER:3\r\nBEGIN:VALARM\r\n\
+                   SUMMARY:Alarm\r\nEND:VALARM\r\nEND:VTODO\r\nEND:VCALENDAR\r\n";
+        let parsed = parse(ics).unwrap();
+        assert_eq!(parsed.uid.as_deref(), Some("abc@tasks.org"));
+        assert_eq!(parsed.summary, "Water plants, ferns");
+        assert_eq!(parsed.priority, Priority::Low);
+        assert_eq!(parsed.due_date.as_deref(), Some("2024-06-12"));
+        assert_eq!(parsed.reminder_time, None);
+        assert!(parsed.completed);
+
+        assert!(parse("BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nEND:VEVENT\r\nEND:VCALENDAR").is_err());
+        let weekdays = ics.replace("STATUS:COMPLETED", "RRULE:FREQ=WEEKLY;BYDAY=MO,TU");
+        assert!(parse(&weekdays)
+            .unwrap_err()
+            .starts_with("Unsupported RRULE"));
+    }
+
+    #[test]
+    fn test_put_checks_preconditions() {
+        let service = TodoService::new_empty();
+        let deadline = Deadline::unbounded();
+        let id = todo_id("abc");
+        let created = match service
+            .put_vtodo_until(id, vtodo("Pay rent"), &Precondition::Absent, &deadline)
+            .unwrap()
+        {
+            PutOutcome::Created(todo) => todo,
+            outcome => panic!("{:?}", outcome),
+        };
+        assert_eq!(created.id, id);
+        assert_eq!(service.get_by_id(id).unwrap().text, "Pay rent");
+        assert!(matches!(
+            service
+                .put_vtodo_until(id, vtodo("Again"), &Precondition::Absent, &deadline)
+                .unwrap(),
+            PutOutcome::PreconditionFailed
+        ));
+
+        let stale = Precondition::Matches("\"1\"".to_string());
+        assert!(matches!(
+            service
+                .put_vtodo_until(id, vtodo("Stale"), &stale, &deadline)
+                .unwrap(),
+            PutOutcome::PreconditionFailed
+        ));
+        let current = Precondition::Matches(etag(&created));
+        let mut done = vtodo("Pay rent");
+        done.completed = true;
+        match service
+            .put_vtodo_until(id, done, &current, &deadline)
+            .unwrap()
+        {
+            PutOutcome::Updated(todo) => assert!(todo.completed),
+            outcome => panic!("{:?}", outcome),
+        }
+        assert_eq!(service.get_stats().completed, 1);
+    }
+}
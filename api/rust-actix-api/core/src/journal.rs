@@ -0,0 +1,403 @@
+use crate::deadline::{Deadline, DeadlineExceeded};
+use crate::models::Todo;
+use crate::snapshot;
+use crate::storage::{lock_within, InMemoryStore, LockStatsSnapshot, TodoStore};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use uuid::Uuid;
+
+const JOURNAL_FILE: &str = "journal.jsonl";
+const SNAPSHOT_FILE: &str = "snapshot.json";
+
+/// One line of the journal.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+enum Record {
+    Put { todo: Box<Todo> },
+    Delete { id: Uuid },
+}
+
+/// In-memory store made durable with a write-ahead journal.
+///
+/// Every mutation is appended to `journal.jsonl` and synced before the call
+/// returns. Once the journal holds `compact_every` records, the full state is
+/// written to `snapshot.json` and the journal is truncated. On open, the
+/// snapshot is loaded and the journal replayed on top of it.
+pub struct JournaledStore {
+    todos: InMemoryStore,
+    /// Held across each mutation so journal order matches apply order.
+    journal: Mutex<Journal>,
+}
+
+struct Journal {
+    dir: PathBuf,
+    file: File,
+    records: usize,
+    compact_every: usize,
+    /// Last write failure, cleared by the next successful write. Reported
+    /// through `ping` so readiness degrades while writes aren't durable.
+    error: Option<String>,
+}
+
+impl JournaledStore {
+    /// Opens or creates the journal in `dir` and restores its state.
+    pub fn open(dir: impl AsRef<Path>, compact_every: usize) -> io::Result<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(&dir)?;
+
+        let todos = InMemoryStore::new();
+        for todo in snapshot::load(&dir.join(SNAPSHOT_FILE))? {
+            todos.insert(todo);
+        }
+
+        let journal_path = dir.join(JOURNAL_FILE);
+        let records = if journal_path.exists() {
+            replay(&journal_path, &todos)?
+        } else {
+            0
+        };
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&journal_path)?;
+
+        let store = JournaledStore {
+            todos,
+            journal: Mutex::new(Journal {
+                dir,
+                file,
+                records,
+                compact_every: compact_every.max(1),
+                error: None,
+            }),
+        };
+        let mut journal = store.journal.lock().unwrap();
+        if journal.records >= journal.compact_every {
+            journal.compact(&store.todos.all())?;
+        }
+        drop(journal);
+        Ok(store)
+    }
+
+    /// Writes a snapshot and truncates the journal now.
+    pub fn compact(&self) -> io::Result<()> {
+        let mut journal = self.journal.lock().unwrap();
+        journal.compact(&self.todos.all())
+    }
+
+    fn write(&self, journal: &mut Journal, records: &[Record]) {
+        if records.is_empty() {
+            return;
+        }
+        let result = journal.append(records).and_then(|()| {
+            if journal.records >= journal.compact_every {
+                journal.compact(&self.todos.all())
+            } else {
+                Ok(())
+            }
+        });
+        match result {
+            Ok(()) => journal.error = None,
+            Err(e) => {
+                log::error!("Journal write to {} failed: {}", journal.dir.display(), e);
+                journal.error = Some(e.to_string());
+            }
+        }
+    }
+}
+
+impl Journal {
+    fn append(&mut self, records: &[Record]) -> io::Result<()> {
+        append_lines(&mut self.file, records)?;
+        self.records += records.len();
+        Ok(())
+    }
+
+    /// Replaces the snapshot atomically, then truncates the journal. A crash
+    /// in between only means replaying records the snapshot already has.
+    fn compact(&mut self, todos: &[Todo]) -> io::Result<()> {
+        snapshot::save(&self.dir.join(SNAPSHOT_FILE), todos)?;
+        self.file.set_len(0)?;
+        self.file.sync_all()?;
+        self.records = 0;
+        Ok(())
+    }
+}
+
+/// Applies the journal at `path` to `todos` and returns the number of
+/// records.
+fn replay(path: &Path, todos: &InMemoryStore) -> io::Result<usize> {
+    let records: Vec<Record> = read_lines(path)?;
+    let count = records.len();
+    for record in records {
+        match record {
+            Record::Put { todo } => todos.insert(*todo),
+            Record::Delete { id } => {
+                todos.remove(id);
+            }
+        }
+    }
+    Ok(count)
+}
+
+/// Reads a JSON-lines file written by appending one record per write. A torn
+/// final line from a crash mid-write is truncated away; corruption anywhere
+/// else is an error.
+pub(crate) fn read_lines<T: DeserializeOwned>(path: &Path) -> io::Result<Vec<T>> {
+    let contents = fs::read(path)?;
+    let mut records = Vec::new();
+    let mut offset = 0;
+    for line in contents.split_inclusive(|byte| *byte == b'\n') {
+        let torn = !line.ends_with(b"\n");
+        match (torn, serde_json::from_slice::<T>(line)) {
+            (false, Ok(record)) => records.push(record),
+            (false, Err(e)) if offset + line.len() < contents.len() => {
+                return Err(invalid_data(path, e))
+            }
+            _ => {
+                log::warn!("Dropping incomplete last record of {}", path.display());
+                OpenOptions::new()
+                    .write(true)
+                    .open(path)?
+                    .set_len(offset as u64)?;
+                break;
+            }
+        }
+        offset += line.len();
+    }
+    Ok(records)
+}
+
+/// Appends `records` as JSON lines and syncs them to disk.
+pub(crate) fn append_lines<T: Serialize>(file: &mut File, records: &[T]) -> io::Result<()> {
+    let mut buffer = Vec::new();
+    for record in records {
+        serde_json::to_writer(&mut buffer, record)?;
+        buffer.push(b'\n');
+    }
+    file.write_all(&buffer)?;
+    file.sync_data()
+}
+
+fn invalid_data(path: &Path, error: serde_json::Error) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("{}: {}", path.display(), error),
+    )
+}
+
+impl TodoStore for JournaledStore {
+    fn all(&self) -> Vec<Todo> {
+        self.todos.all()
+    }
+
+    fn get(&self, id: Uuid) -> Option<Todo> {
+        self.todos.get(id)
+    }
+
+    fn insert(&self, todo: Todo) {
+        let mut journal = self.journal.lock().unwrap();
+        self.todos.insert(todo.clone());
+        self.write(
+            &mut journal,
+            &[Record::Put {
+                todo: Box::new(todo),
+            }],
+        );
+    }
+
+    fn update(&self, id: Uuid, apply: &mut dyn FnMut(&mut Todo)) -> Option<Todo> {
+        let mut journal = self.journal.lock().unwrap();
+        let todo = self.todos.update(id, apply)?;
+        self.write(
+            &mut journal,
+            &[Record::Put {
+                todo: Box::new(todo.clone()),
+            }],
+        );
+        Some(todo)
+    }
+
+    fn remove(&self, id: Uuid) -> Option<Todo> {
+        let mut journal = self.journal.lock().unwrap();
+        let todo = self.todos.remove(id)?;
+        self.write(&mut journal, &[Record::Delete { id: todo.id }]);
+        Some(todo)
+    }
+
+    fn remove_where(&self, predicate: &dyn Fn(&Todo) -> bool) -> Vec<Todo> {
+        let mut journal = self.journal.lock().unwrap();
+        let removed = self.todos.remove_where(predicate);
+        let records: Vec<Record> = removed
+            .iter()
+            .map(|todo| Record::Delete { id: todo.id })
+            .collect();
+        self.write(&mut journal, &records);
+        removed
+    }
+
+    fn count(&self) -> usize {
+        self.todos.count()
+    }
+
+    fn ids(&self) -> Vec<Uuid> {
+        self.todos.ids()
+    }
+
+    fn get_many(&self, ids: &[Uuid]) -> Vec<Todo> {
+        self.todos.get_many(ids)
+    }
+
+    fn all_until(&self, deadline: &Deadline) -> Result<Vec<Todo>, DeadlineExceeded> {
+        self.todos.all_until(deadline)
+    }
+
+    fn all_shared_until(&self, deadline: &Deadline) -> Result<Vec<Arc<Todo>>, DeadlineExceeded> {
+        self.todos.all_shared_until(deadline)
+    }
+
+    fn get_until(&self, id: Uuid, deadline: &Deadline) -> Result<Option<Todo>, DeadlineExceeded> {
+        self.todos.get_until(id, deadline)
+    }
+
+    fn ping(&self, timeout: Duration) -> Result<(), String> {
+        self.todos.ping(timeout)?;
+        let journal = lock_within(&self.journal, timeout)?;
+        match &journal.error {
+            Some(error) => Err(format!("journal write failed: {}", error)),
+            None => Ok(()),
+        }
+    }
+
+    fn lock_stats(&self) -> Option<LockStatsSnapshot> {
+        self.todos.lock_stats()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{todo_id, Priority};
+    use chrono::Utc;
+
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new() -> Self {
+            TempDir(std::env::temp_dir().join(format!("spicy-journal-{}", uuid::Uuid::new_v4())))
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn todo(id: &str) -> Todo {
+        Todo {
+            id: todo_id(id),
+            text: format!("Todo {}", id),
+            priority: Priority::Medium,
+            completed: false,
+            due_date: None,
+            reminder_time: None,
+            recurrence: None,
+            recurrence_end: None,
+            remaining_occurrences: None,
+            estimate_minutes: None,
+            location: None,
+            color: None,
+            icon: None,
+            pinned: false,
+            rollover_count: 0,
+            rolled_over_to: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    fn key(id: &str) -> Uuid {
+        todo_id(id)
+    }
+
+    fn texts(store: &JournaledStore) -> Vec<String> {
+        let mut texts: Vec<String> = store.all().into_iter().map(|t| t.text).collect();
+        texts.sort();
+        texts
+    }
+
+    #[test]
+    fn test_replays_mutations_after_reopen() {
+        let dir = TempDir::new();
+        let store = JournaledStore::open(&dir.0, 100).unwrap();
+        store.insert(todo("a"));
+        store.insert(todo("b"));
+        store.insert(todo("c"));
+        store.update(key("a"), &mut |t| t.completed = true);
+        store.remove(key("b"));
+        store.remove_where(&|t| t.id == todo_id("c"));
+        drop(store);
+
+        let reopened = JournaledStore::open(&dir.0, 100).unwrap();
+        assert_eq!(texts(&reopened), vec!["Todo a"]);
+        assert!(reopened.get(key("a")).unwrap().completed);
+    }
+
+    #[test]
+    fn test_compacts_into_snapshot() {
+        let dir = TempDir::new();
+        let store = JournaledStore::open(&dir.0, 3).unwrap();
+        store.insert(todo("a"));
+        store.insert(todo("b"));
+        store.insert(todo("c"));
+        assert_eq!(fs::metadata(dir.0.join(JOURNAL_FILE)).unwrap().len(), 0);
+        assert!(dir.0.join(SNAPSHOT_FILE).exists());
+
+        store.remove(key("a"));
+        drop(store);
+        let reopened = JournaledStore::open(&dir.0, 3).unwrap();
+        assert_eq!(texts(&reopened), vec!["Todo b", "Todo c"]);
+    }
+
+    #[test]
+    fn test_drops_torn_last_record() {
+        let dir = TempDir::new();
+        let store = JournaledStore::open(&dir.0, 100).unwrap();
+        store.insert(todo("a"));
+        drop(store);
+        let mut file = OpenOptions::new()
+            .append(true)
+            .open(dir.0.join(JOURNAL_FILE))
+            .unwrap();
+        file.write_all(br#"{"op":"put","todo":{"id":"b""#).unwrap();
+        drop(file);
+
+        let reopened = JournaledStore::open(&dir.0, 100).unwrap();
+        assert_eq!(texts(&reopened), vec!["Todo a"]);
+        reopened.insert(todo("c"));
+        drop(reopened);
+        assert_eq!(
+            texts(&JournaledStore::open(&dir.0, 100).unwrap()),
+            vec!["Todo a", "Todo c"]
+        );
+    }
+
+    #[test]
+    fn test_rejects_corrupt_journal() {
+        let dir = TempDir::new();
+        fs::create_dir_all(&dir.0).unwrap();
+        fs::write(
+            dir.0.join(JOURNAL_FILE),
+            "garbage\n{\"op\":\"delete\",\"id\":\"a\"}\n",
+        )
+        .unwrap();
+        let error = JournaledStore::open(&dir.0, 100).err().unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+}
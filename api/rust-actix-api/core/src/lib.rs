@@ -0,0 +1,50 @@
+//! Domain core of the Spicy Todo API: models, the todo service, its event
+//! log and pluggable storage. Has no HTTP dependencies, so it can be
+//! embedded directly in other Rust programs. Storage failures it recovers
+//! from are reported through the `log` facade, for the embedding program
+//! to route.
+
+pub mod account;
+pub mod activity;
+pub mod audit;
+pub mod bulk_edit;
+pub mod bundle;
+pub mod cascade;
+pub mod changes;
+pub mod context;
+pub mod dashboard;
+pub mod dates;
+pub mod deadline;
+pub mod digest;
+pub mod encryption;
+pub mod event_store;
+pub mod events;
+pub mod fixtures;
+pub mod geo;
+pub mod ical;
+pub mod journal;
+pub mod locale;
+pub mod models;
+pub mod plan;
+pub mod policy;
+pub mod query;
+pub mod quick_add;
+pub mod read_model;
+pub mod report;
+pub mod rollover;
+pub mod service;
+#[cfg(test)]
+mod service_properties_test;
+pub mod snapshot;
+pub mod snooze;
+pub mod storage;
+pub mod suggest;
+pub mod sync;
+pub mod triage;
+pub mod weekly;
+
+pub use context::RequestContext;
+pub use deadline::{Deadline, DeadlineExceeded};
+pub use journal::JournaledStore;
+pub use service::TodoService;
+pub use storage::{InMemoryStore, TodoStore};
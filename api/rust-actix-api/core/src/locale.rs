@@ -0,0 +1,196 @@
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
+use std::str::FromStr;
+
+/// How dates and times are written for people, in exports, digests and
+/// reports. The JSON API always uses ISO 8601 regardless.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Locale {
+    pub tag: &'static str,
+    date: &'static str,
+    time: &'static str,
+}
+
+/// Supported locales. A tag not listed falls back to the first entry with
+/// the same language, so `de-AT` formats like `de-DE`.
+const LOCALES: &[Locale] = &[
+    Locale {
+        tag: "iso",
+        date: "%Y-%m-%d",
+        time: "%H:%M",
+    },
+    Locale {
+        tag: "en-US",
+        date: "%-m/%-d/%Y",
+        time: "%-I:%M %p",
+    },
+    Locale {
+        tag: "en-GB",
+        date: "%d/%m/%Y",
+        time: "%H:%M",
+    },
+    Locale {
+        tag: "de-DE",
+        date: "%d.%m.%Y",
+        time: "%H:%M",
+    },
+    Locale {
+        tag: "fr-FR",
+        date: "%d/%m/%Y",
+        time: "%H:%M",
+    },
+    Locale {
+        tag: "es-ES",
+        date: "%d/%m/%Y",
+        time: "%H:%M",
+    },
+    Locale {
+        tag: "it-IT",
+        date: "%d/%m/%Y",
+        time: "%H:%M",
+    },
+    Locale {
+        tag: "nl-NL",
+        date: "%d-%m-%Y",
+        time: "%H:%M",
+    },
+    Locale {
+        tag: "ja-JP",
+        date: "%Y/%m/%d",
+        time: "%H:%M",
+    },
+];
+
+impl Locale {
+    /// ISO 8601, the same as the JSON API.
+    pub const ISO: Locale = LOCALES[0];
+
+    /// Every supported tag, for error messages and feature detection.
+    pub fn tags() -> Vec<&'static str> {
+        LOCALES.iter().map(|locale| locale.tag).collect()
+    }
+
+    /// Picks the most preferred supported locale from an `Accept-Language`
+    /// header.
+    pub fn negotiate(accept_language: &str) -> Option<Locale> {
+        let mut ranges: Vec<(f32, &str)> = accept_language
+            .split(',')
+            .filter_map(|range| {
+                let mut parts = range.split(';');
+                let tag = parts.next()?.trim();
+                let quality = parts
+                    .find_map(|param| param.trim().strip_prefix("q="))
+                    .map_or(Some(1.0), |q| q.parse().ok())?;
+                Some((quality, tag))
+            })
+            .filter(|(quality, _)| *quality > 0.0)
+            .collect();
+        // Stable, so equal weights keep the client's order.
+        ranges.sort_by(|a, b| b.0.total_cmp(&a.0));
+        ranges.into_iter().find_map(|(_, tag)| tag.parse().ok())
+    }
+
+    pub fn format_date(&self, date: NaiveDate) -> String {
+        date.format(self.date).to_string()
+    }
+
+    pub fn format_time(&self, time: NaiveTime) -> String {
+        time.format(self.time).to_string()
+    }
+
+    /// Date and time of `at`, in UTC.
+    pub fn format_timestamp(&self, at: DateTime<Utc>) -> String {
+        format!(
+            "{} {} UTC",
+            self.format_date(at.date_naive()),
+            self.format_time(at.time())
+        )
+    }
+
+    /// Formats a stored `dueDate`, passing through values that aren't ISO
+    /// dates unchanged.
+    pub fn format_due_date(&self, due_date: &str) -> String {
+        match NaiveDate::parse_from_str(due_date, "%Y-%m-%d") {
+            Ok(date) => self.format_date(date),
+            Err(_) => due_date.to_string(),
+        }
+    }
+}
+
+impl Default for Locale {
+    fn default() -> Self {
+        Locale::ISO
+    }
+}
+
+impl FromStr for Locale {
+    type Err = String;
+
+    /// A BCP 47 tag such as `de-DE`, `de` or `en_GB`, case-insensitive.
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let tag = value.trim().replace('_', "-");
+        let language = tag.split('-').next().unwrap_or_default();
+        LOCALES
+            .iter()
+            .find(|locale| locale.tag.eq_ignore_ascii_case(&tag))
+            .or_else(|| {
+                LOCALES.iter().find(|locale| {
+                    locale
+                        .tag
+                        .split('-')
+                        .next()
+                        .is_some_and(|known| known.eq_ignore_ascii_case(language))
+                })
+            })
+            .copied()
+            .ok_or_else(|| {
+                format!(
+                    "Unsupported locale '{}': expected one of {}",
+                    value,
+                    Locale::tags().join(", ")
+                )
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn locale(tag: &str) -> Locale {
+        tag.parse().unwrap()
+    }
+
+    #[test]
+    fn test_formats_dates_per_locale() {
+        let date = NaiveDate::from_ymd_opt(2024, 3, 5).unwrap();
+        assert_eq!(Locale::default().format_date(date), "2024-03-05");
+        assert_eq!(locale("en-US").format_date(date), "3/5/2024");
+        assert_eq!(locale("en-GB").format_date(date), "05/03/2024");
+        assert_eq!(locale("de-DE").format_date(date), "05.03.2024");
+        assert_eq!(locale("ja-JP").format_date(date), "2024/03/05");
+
+        let at = date.and_hms_opt(14, 7, 0).unwrap().and_utc();
+        assert_eq!(locale("en-US").format_timestamp(at), "3/5/2024 2:07 PM UTC");
+        assert_eq!(locale("de").format_timestamp(at), "05.03.2024 14:07 UTC");
+        assert_eq!(locale("de").format_due_date("2024-03-05"), "05.03.2024");
+        assert_eq!(locale("de").format_due_date("soon"), "soon");
+    }
+
+    #[test]
+    fn test_parses_tags_with_language_fallback() {
+        assert_eq!(locale("EN_gb").tag, "en-GB");
+        assert_eq!(locale("de-AT").tag, "de-DE");
+        assert_eq!(locale("en").tag, "en-US");
+        assert!("xx-YY".parse::<Locale>().is_err());
+    }
+
+    #[test]
+    fn test_negotiates_accept_language() {
+        let negotiated = Locale::negotiate("xx;q=1.0, fr-CH;q=0.8, en-GB;q=0.9");
+        assert_eq!(negotiated.map(|locale| locale.tag), Some("en-GB"));
+        let negotiated = Locale::negotiate("de, en;q=0");
+        assert_eq!(negotiated.map(|locale| locale.tag), Some("de-DE"));
+        assert_eq!(Locale::negotiate("xx, en;q=0"), None);
+        assert_eq!(Locale::negotiate(""), None);
+    }
+}
@@ -0,0 +1,1473 @@
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chrono::{DateTime, Datelike, Days, Months, NaiveDate, Utc, Weekday};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use sha2::{Digest, Sha256};
+use std::borrow::Borrow;
+use std::str::FromStr;
+use std::sync::Arc;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Priority {
+    Low,
+    #[default]
+    Medium,
+    High,
+}
+
+impl Priority {
+    /// Relative size of a todo for weighted figures such as
+    /// `TodoStats::weighted_completion_rate`.
+    pub fn weight(&self) -> u32 {
+        match self {
+            Priority::Low => 1,
+            Priority::Medium => 2,
+            Priority::High => 4,
+        }
+    }
+
+    /// One level up, or `None` from high.
+    pub fn raised(&self) -> Option<Priority> {
+        match self {
+            Priority::Low => Some(Priority::Medium),
+            Priority::Medium => Some(Priority::High),
+            Priority::High => None,
+        }
+    }
+}
+
+/// The first day of the week for "this week" figures.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum WeekStart {
+    #[default]
+    Monday,
+    Sunday,
+    Saturday,
+}
+
+impl WeekStart {
+    /// The first and last day of the week `date` falls in.
+    pub fn week_of(&self, date: NaiveDate) -> (NaiveDate, NaiveDate) {
+        let first = match self {
+            WeekStart::Monday => Weekday::Mon,
+            WeekStart::Sunday => Weekday::Sun,
+            WeekStart::Saturday => Weekday::Sat,
+        };
+        let back = (date.weekday().num_days_from_monday() + 7 - first.num_days_from_monday()) % 7;
+        let start = date - Days::new(u64::from(back));
+        (start, start + Days::new(6))
+    }
+}
+
+/// The id a todo from elsewhere gets here: its own when that is a UUID,
+/// else one derived from it. The same id always gives the same UUID, so
+/// importing or syncing a todo again finds the earlier copy.
+pub fn todo_id(value: &str) -> Uuid {
+    let value = value.trim();
+    Uuid::parse_str(value).unwrap_or_else(|_| {
+        let digest = Sha256::digest(value.as_bytes());
+        let mut bytes = [0; 16];
+        bytes.copy_from_slice(&digest[..16]);
+        uuid::Builder::from_custom_bytes(bytes).into_uuid()
+    })
+}
+
+/// Reads ids written before they were UUIDs, such as imported ones, as
+/// `todo_id` maps them.
+fn deserialize_todo_id<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Uuid, D::Error> {
+    String::deserialize(deserializer).map(|id| todo_id(&id))
+}
+
+/// Writes the id as text in every format; binary ones such as MessagePack
+/// would otherwise get the UUID's 16 bytes.
+fn serialize_todo_id<S: Serializer>(id: &Uuid, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(id)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Todo {
+    #[serde(
+        serialize_with = "serialize_todo_id",
+        deserialize_with = "deserialize_todo_id"
+    )]
+    pub id: Uuid,
+    pub text: String,
+    pub priority: Priority,
+    pub completed: bool,
+    #[serde(rename = "dueDate")]
+    pub due_date: Option<String>,
+    #[serde(rename = "reminderTime")]
+    pub reminder_time: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recurrence: Option<Recurrence>,
+    /// When the recurrence stops; unset repeats forever.
+    #[serde(
+        rename = "recurrenceEnd",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub recurrence_end: Option<RecurrenceEnd>,
+    /// Occurrences left after the current one, for todos with a
+    /// `recurrenceEnd`. Maintained by the server.
+    #[serde(
+        rename = "remainingOccurrences",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub remaining_occurrences: Option<u32>,
+    /// How long the todo is expected to take, for workload stats.
+    #[serde(
+        rename = "estimateMinutes",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub estimate_minutes: Option<u32>,
+    /// Where the todo gets done, for `GET /api/todos/nearby`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub location: Option<Location>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<Color>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub icon: Option<Icon>,
+    /// Pinned todos list first.
+    #[serde(default)]
+    pub pinned: bool,
+    /// Times the overdue rollover carried the todo over to a new day.
+    #[serde(rename = "rolloverCount", default)]
+    pub rollover_count: u32,
+    /// The copy the overdue rollover made of this todo, in copy mode.
+    #[serde(
+        rename = "rolledOverTo",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub rolled_over_to: Option<String>,
+    #[serde(rename = "createdAt")]
+    pub created_at: DateTime<Utc>,
+    #[serde(rename = "updatedAt")]
+    pub updated_at: DateTime<Utc>,
+}
+
+/// How a todo repeats. Its due date is the current occurrence; once that has
+/// passed, the rollover job moves it to the next one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Recurrence {
+    Daily,
+    Weekly,
+    /// Same day of the month, or the month's last day when it is shorter.
+    Monthly,
+}
+
+impl Recurrence {
+    /// The occurrence after the one due on `date`.
+    pub fn next_after(self, date: NaiveDate) -> NaiveDate {
+        let next = match self {
+            Recurrence::Daily => date.checked_add_days(Days::new(1)),
+            Recurrence::Weekly => date.checked_add_days(Days::new(7)),
+            Recurrence::Monthly => date.checked_add_months(Months::new(1)),
+        };
+        next.unwrap_or(NaiveDate::MAX)
+    }
+}
+
+/// Most occurrences a recurrence end condition can allow, and the most
+/// `remainingOccurrences` counts for an `until` date.
+pub const MAX_OCCURRENCES: u32 = 1000;
+
+/// When a recurring todo stops repeating.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RecurrenceEnd {
+    /// This many occurrences in all, counting the one due when the
+    /// condition was set.
+    AfterOccurrences(u32),
+    /// No occurrence after this date.
+    Until(NaiveDate),
+}
+
+/// Checks a client-supplied `recurrenceEnd`.
+pub fn validate_recurrence_end(end: Option<&RecurrenceEnd>) -> Result<(), String> {
+    match end {
+        Some(RecurrenceEnd::AfterOccurrences(count)) if *count == 0 || *count > MAX_OCCURRENCES => {
+            Err(format!(
+                "recurrenceEnd.afterOccurrences must be between 1 and {}",
+                MAX_OCCURRENCES
+            ))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// States that take a todo out of the everyday list. Stats count these
+/// separately and leave them out of `total` and `active` unless asked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HiddenState {
+    Archived,
+    Trashed,
+    Deferred,
+}
+
+impl Todo {
+    fn due(&self) -> Option<NaiveDate> {
+        NaiveDate::parse_from_str(self.due_date.as_deref()?, "%Y-%m-%d").ok()
+    }
+
+    /// The occurrence after the current one, or `None` for one-off todos
+    /// and series that have ended.
+    pub fn next_occurrence(&self) -> Option<NaiveDate> {
+        let next = self.recurrence?.next_after(self.due()?);
+        match self.recurrence_end {
+            Some(RecurrenceEnd::AfterOccurrences(_)) if self.remaining_occurrences == Some(0) => {
+                None
+            }
+            Some(RecurrenceEnd::Until(until)) if next > until => None,
+            _ => Some(next),
+        }
+    }
+
+    /// Moves the todo to its next occurrence, returning it, unless the
+    /// series has ended.
+    pub fn advance(&mut self) -> Option<NaiveDate> {
+        let next = self.next_occurrence()?;
+        self.due_date = Some(next.format("%Y-%m-%d").to_string());
+        match self.recurrence_end {
+            Some(RecurrenceEnd::AfterOccurrences(_)) => {
+                self.remaining_occurrences = self.remaining_occurrences.map(|n| n - 1);
+            }
+            Some(RecurrenceEnd::Until(_)) => self.reset_remaining_occurrences(),
+            None => {}
+        }
+        Some(next)
+    }
+
+    /// Counts `remainingOccurrences` afresh from the current due date.
+    pub fn reset_remaining_occurrences(&mut self) {
+        self.remaining_occurrences = match (self.recurrence, self.recurrence_end, self.due()) {
+            (Some(_), Some(RecurrenceEnd::AfterOccurrences(count)), Some(_)) => Some(count - 1),
+            (Some(recurrence), Some(RecurrenceEnd::Until(until)), Some(mut due)) => {
+                let mut remaining = 0;
+                while remaining < MAX_OCCURRENCES {
+                    due = recurrence.next_after(due);
+                    if due > until {
+                        break;
+                    }
+                    remaining += 1;
+                }
+                Some(remaining)
+            }
+            _ => None,
+        };
+    }
+
+    /// No todo can be archived, trashed or deferred yet, so this is always
+    /// `None`; those features report their state here.
+    pub fn hidden_state(&self) -> Option<HiddenState> {
+        None
+    }
+}
+
+/// A place: coordinates in decimal degrees (WGS 84), a name such as
+/// `Hardware store`, or both. Only todos with coordinates show up in
+/// nearby queries.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Location {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lat: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lng: Option<f64>,
+}
+
+impl Location {
+    pub fn coordinates(&self) -> Option<(f64, f64)> {
+        Some((self.lat?, self.lng?))
+    }
+}
+
+const MAX_LOCATION_NAME_LEN: usize = 100;
+
+/// Checks a client-supplied `location`.
+pub fn validate_location(location: Option<&Location>) -> Result<(), String> {
+    let Some(location) = location else {
+        return Ok(());
+    };
+    if let Some(name) = &location.name {
+        if name.trim().is_empty() || name.len() > MAX_LOCATION_NAME_LEN {
+            return Err(format!(
+                "location.name must be 1 to {} characters",
+                MAX_LOCATION_NAME_LEN
+            ));
+        }
+    }
+    match (location.lat, location.lng) {
+        (Some(lat), Some(lng)) => validate_coordinates(lat, lng),
+        (None, None) if location.name.is_some() => Ok(()),
+        (None, None) => Err("location needs a name or lat and lng".to_string()),
+        _ => Err("location.lat and location.lng must be given together".to_string()),
+    }
+}
+
+/// Checks a latitude and longitude in decimal degrees.
+pub fn validate_coordinates(lat: f64, lng: f64) -> Result<(), String> {
+    if !(-90.0..=90.0).contains(&lat) {
+        return Err("lat must be between -90 and 90".to_string());
+    }
+    if !(-180.0..=180.0).contains(&lng) {
+        return Err("lng must be between -180 and 180".to_string());
+    }
+    Ok(())
+}
+
+/// A color from the palette every client can render, for tinting a todo
+/// or a list.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Color {
+    Red,
+    Orange,
+    Yellow,
+    Green,
+    Teal,
+    Blue,
+    Purple,
+    Pink,
+    Gray,
+}
+
+impl Color {
+    pub const ALL: [Color; 9] = [
+        Color::Red,
+        Color::Orange,
+        Color::Yellow,
+        Color::Green,
+        Color::Teal,
+        Color::Blue,
+        Color::Purple,
+        Color::Pink,
+        Color::Gray,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Color::Red => "red",
+            Color::Orange => "orange",
+            Color::Yellow => "yellow",
+            Color::Green => "green",
+            Color::Teal => "teal",
+            Color::Blue => "blue",
+            Color::Purple => "purple",
+            Color::Pink => "pink",
+            Color::Gray => "gray",
+        }
+    }
+}
+
+impl FromStr for Color {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let value = value.trim().to_lowercase();
+        Color::ALL
+            .into_iter()
+            .find(|color| color.as_str() == value)
+            .ok_or_else(|| {
+                let names: Vec<&str> = Color::ALL.iter().map(Color::as_str).collect();
+                format!("Unknown color '{}': expected {}", value, names.join(", "))
+            })
+    }
+}
+
+/// An icon from the set the clients ship, by name. Free-form emoji are
+/// not taken, so what a client is asked to draw is always known to it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Icon {
+    Pepper,
+    Fire,
+    Star,
+    Pin,
+    Home,
+    Work,
+    Cart,
+    Book,
+    Idea,
+    Heart,
+    Bell,
+    Calendar,
+    Fitness,
+    Money,
+    Travel,
+    Party,
+}
+
+impl Icon {
+    pub const ALL: [Icon; 16] = [
+        Icon::Pepper,
+        Icon::Fire,
+        Icon::Star,
+        Icon::Pin,
+        Icon::Home,
+        Icon::Work,
+        Icon::Cart,
+        Icon::Book,
+        Icon::Idea,
+        Icon::Heart,
+        Icon::Bell,
+        Icon::Calendar,
+        Icon::Fitness,
+        Icon::Money,
+        Icon::Travel,
+        Icon::Party,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Icon::Pepper => "pepper",
+            Icon::Fire => "fire",
+            Icon::Star => "star",
+            Icon::Pin => "pin",
+            Icon::Home => "home",
+            Icon::Work => "work",
+            Icon::Cart => "cart",
+            Icon::Book => "book",
+            Icon::Idea => "idea",
+            Icon::Heart => "heart",
+            Icon::Bell => "bell",
+            Icon::Calendar => "calendar",
+            Icon::Fitness => "fitness",
+            Icon::Money => "money",
+            Icon::Travel => "travel",
+            Icon::Party => "party",
+        }
+    }
+
+    /// The emoji clients without their own artwork draw.
+    pub fn emoji(&self) -> &'static str {
+        match self {
+            Icon::Pepper => "🌶️",
+            Icon::Fire => "🔥",
+            Icon::Star => "⭐",
+            Icon::Pin => "📌",
+            Icon::Home => "🏠",
+            Icon::Work => "💼",
+            Icon::Cart => "🛒",
+            Icon::Book => "📚",
+            Icon::Idea => "💡",
+            Icon::Heart => "❤️",
+            Icon::Bell => "🔔",
+            Icon::Calendar => "📅",
+            Icon::Fitness => "💪",
+            Icon::Money => "💰",
+            Icon::Travel => "✈️",
+            Icon::Party => "🎉",
+        }
+    }
+}
+
+impl FromStr for Icon {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let value = value.trim().to_lowercase();
+        Icon::ALL
+            .into_iter()
+            .find(|icon| icon.as_str() == value)
+            .ok_or_else(|| {
+                let names: Vec<&str> = Icon::ALL.iter().map(Icon::as_str).collect();
+                format!("Unknown icon '{}': expected {}", value, names.join(", "))
+            })
+    }
+}
+
+/// Longest estimate a todo can carry: one week.
+pub const MAX_ESTIMATE_MINUTES: u32 = 7 * 24 * 60;
+
+/// Checks a client-supplied `estimateMinutes`.
+pub fn validate_estimate(estimate_minutes: Option<u32>) -> Result<(), String> {
+    match estimate_minutes {
+        Some(minutes) if minutes == 0 || minutes > MAX_ESTIMATE_MINUTES => Err(format!(
+            "estimateMinutes must be between 1 and {}",
+            MAX_ESTIMATE_MINUTES
+        )),
+        _ => Ok(()),
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct TodoCreate {
+    pub text: String,
+    pub priority: Option<Priority>,
+    pub completed: Option<bool>,
+    #[serde(rename = "dueDate")]
+    pub due_date: Option<String>,
+    #[serde(rename = "reminderTime")]
+    pub reminder_time: Option<String>,
+    pub recurrence: Option<Recurrence>,
+    #[serde(rename = "recurrenceEnd")]
+    pub recurrence_end: Option<RecurrenceEnd>,
+    #[serde(rename = "estimateMinutes")]
+    pub estimate_minutes: Option<u32>,
+    pub location: Option<Location>,
+    pub color: Option<Color>,
+    pub icon: Option<Icon>,
+    pub pinned: Option<bool>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TodoUpdate {
+    pub text: Option<String>,
+    pub priority: Option<Priority>,
+    pub completed: Option<bool>,
+    #[serde(rename = "dueDate")]
+    pub due_date: Option<String>,
+    #[serde(rename = "reminderTime")]
+    pub reminder_time: Option<String>,
+    pub recurrence: Option<Recurrence>,
+    #[serde(rename = "recurrenceEnd")]
+    pub recurrence_end: Option<RecurrenceEnd>,
+    #[serde(rename = "estimateMinutes")]
+    pub estimate_minutes: Option<u32>,
+    pub location: Option<Location>,
+    pub color: Option<Color>,
+    pub icon: Option<Icon>,
+    pub pinned: Option<bool>,
+}
+
+impl TodoUpdate {
+    /// Copies the fields that are set onto `todo`, recounting its remaining
+    /// occurrences when the series changed. Does not touch timestamps.
+    pub fn apply_to(self, todo: &mut Todo) {
+        let recount = self.recurrence_end.is_some()
+            || (matches!(todo.recurrence_end, Some(RecurrenceEnd::Until(_)))
+                && (self.due_date.is_some() || self.recurrence.is_some()));
+        if let Some(text) = self.text {
+            todo.text = text;
+        }
+        if let Some(priority) = self.priority {
+            todo.priority = priority;
+        }
+        if let Some(completed) = self.completed {
+            todo.completed = completed;
+        }
+        if let Some(due_date) = self.due_date {
+            todo.due_date = Some(due_date);
+        }
+        if let Some(reminder_time) = self.reminder_time {
+            todo.reminder_time = Some(reminder_time);
+        }
+        if let Some(recurrence) = self.recurrence {
+            todo.recurrence = Some(recurrence);
+        }
+        if let Some(recurrence_end) = self.recurrence_end {
+            todo.recurrence_end = Some(recurrence_end);
+        }
+        if let Some(estimate_minutes) = self.estimate_minutes {
+            todo.estimate_minutes = Some(estimate_minutes);
+        }
+        if let Some(location) = self.location {
+            todo.location = Some(location);
+        }
+        if let Some(color) = self.color {
+            todo.color = Some(color);
+        }
+        if let Some(icon) = self.icon {
+            todo.icon = Some(icon);
+        }
+        if let Some(pinned) = self.pinned {
+            todo.pinned = pinned;
+        }
+        if recount {
+            todo.reset_remaining_occurrences();
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct TodoStats {
+    pub total: usize,
+    pub active: usize,
+    pub completed: usize,
+    #[serde(rename = "completionRate")]
+    pub completion_rate: f64,
+    /// Completion rate with each todo weighted by `Priority::weight`, so one
+    /// high priority item counts as much as four low priority ones.
+    #[serde(rename = "weightedCompletionRate")]
+    pub weighted_completion_rate: f64,
+    #[serde(rename = "priorityBreakdown")]
+    pub priority_breakdown: std::collections::HashMap<String, usize>,
+    #[serde(rename = "overdueCount")]
+    pub overdue_count: usize,
+    #[serde(rename = "dueTodayCount")]
+    pub due_today_count: usize,
+    #[serde(rename = "upcomingCount")]
+    pub upcoming_count: usize,
+    /// Active todos due in the current week, which begins on `week_start`.
+    #[serde(rename = "dueThisWeekCount")]
+    pub due_this_week_count: usize,
+    #[serde(rename = "weekStart")]
+    pub week_start: WeekStart,
+    /// Sum of `estimateMinutes` over active todos.
+    #[serde(rename = "estimatedOutstandingMinutes")]
+    pub estimated_outstanding_minutes: u64,
+    /// Estimated minutes of active todos due on each of the next seven
+    /// days, starting today.
+    pub workload: Vec<DayWorkload>,
+    /// Set by `with_capacity`.
+    #[serde(
+        rename = "dailyCapacityMinutes",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub daily_capacity_minutes: Option<u32>,
+    /// Days in `workload` over the daily capacity.
+    #[serde(rename = "overCommittedDays")]
+    pub over_committed_days: Vec<NaiveDate>,
+    /// Hidden todos, counted whether or not `include_hidden` was set.
+    pub archived: usize,
+    pub trashed: usize,
+    pub deferred: usize,
+    pub pinned: usize,
+    /// Todos the overdue rollover carried over at least once.
+    #[serde(rename = "rolledOverCount")]
+    pub rolled_over: usize,
+}
+
+impl TodoStats {
+    /// Flags the days whose scheduled workload exceeds `capacity_minutes`.
+    pub fn with_capacity(mut self, capacity_minutes: u32) -> Self {
+        self.daily_capacity_minutes = Some(capacity_minutes);
+        for day in &mut self.workload {
+            day.over_committed = day.minutes > u64::from(capacity_minutes);
+        }
+        self.over_committed_days = self
+            .workload
+            .iter()
+            .filter(|day| day.over_committed)
+            .map(|day| day.date)
+            .collect();
+        self
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DayWorkload {
+    pub date: NaiveDate,
+    pub minutes: u64,
+    #[serde(rename = "overCommitted")]
+    pub over_committed: bool,
+}
+
+/// Body of `POST /api/todos/quick`.
+#[derive(Debug, Deserialize)]
+pub struct QuickAddRequest {
+    pub text: String,
+}
+
+/// Body of `POST /api/todos/{id}/snooze`: a preset (`tomorrow`,
+/// `next-week`, `+Nd`) or a `YYYY-MM-DD` date.
+#[derive(Debug, Default, Deserialize)]
+pub struct SnoozeRequest {
+    pub preset: Option<String>,
+    pub date: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct StatsQuery {
+    /// Count archived, trashed and deferred todos in the totals as well.
+    #[serde(default)]
+    pub include_hidden: bool,
+    /// Daily capacity in minutes for the over-commitment warning,
+    /// overriding the server default.
+    pub capacity: Option<u32>,
+}
+
+/// Query of `POST /api/todos/complete`: the list filters of `TodoQuery`,
+/// and a hashtag the todos must have.
+#[derive(Debug, Default, Deserialize)]
+pub struct CompleteQuery {
+    pub filter: Option<String>,
+    pub search: Option<String>,
+    pub priority: Option<String>,
+    pub q: Option<String>,
+    pub tag: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TodoQuery {
+    pub filter: Option<String>,
+    pub search: Option<String>,
+    pub priority: Option<String>,
+    pub sort: Option<SortField>,
+    pub order: Option<SortOrder>,
+    /// An expression in the `query` module's filter language, applied on
+    /// top of the other filters.
+    pub q: Option<String>,
+    /// Setting any of these switches the list to a paginated response.
+    pub limit: Option<usize>,
+    pub offset: Option<usize>,
+    /// A previous page's `nextCursor`, or empty for the first page, to page
+    /// by cursor instead of offset.
+    pub cursor: Option<String>,
+    /// Only todos with this color or icon.
+    pub color: Option<String>,
+    pub icon: Option<String>,
+}
+
+impl TodoQuery {
+    pub fn is_paginated(&self) -> bool {
+        self.limit.is_some() || self.offset.is_some() || self.cursor.is_some()
+    }
+
+    /// Whether any of the parameters a saved list preference covers were sent.
+    pub fn has_view_params(&self) -> bool {
+        self.filter.is_some()
+            || self.priority.is_some()
+            || self.sort.is_some()
+            || self.order.is_some()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SortField {
+    CreatedAt,
+    UpdatedAt,
+    DueDate,
+    Priority,
+    Text,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    #[default]
+    Asc,
+    Desc,
+}
+
+/// A todo's place under a `SortField`, before ties are broken by id.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SortKey {
+    Time(DateTime<Utc>),
+    /// Whether the due date is missing, so those sort last, and the date.
+    Due(bool, Option<String>),
+    Rank(u8),
+    Text(String),
+}
+
+impl SortField {
+    pub fn key(self, todo: &Todo) -> SortKey {
+        match self {
+            SortField::CreatedAt => SortKey::Time(todo.created_at),
+            SortField::UpdatedAt => SortKey::Time(todo.updated_at),
+            SortField::DueDate => SortKey::Due(todo.due_date.is_none(), todo.due_date.clone()),
+            SortField::Priority => SortKey::Rank(priority_rank(&todo.priority)),
+            SortField::Text => SortKey::Text(todo.text.to_lowercase()),
+        }
+    }
+
+    /// Sorts `todos` by this field, breaking ties by id. Todos without a due
+    /// date sort after those with one.
+    pub fn sort<T: Borrow<Todo>>(self, todos: &mut [T], order: SortOrder) {
+        todos.sort_by(|a, b| {
+            let (a, b) = (a.borrow(), b.borrow());
+            let ordering = self.key(a).cmp(&self.key(b)).then_with(|| a.id.cmp(&b.id));
+            match order {
+                SortOrder::Asc => ordering,
+                SortOrder::Desc => ordering.reverse(),
+            }
+        });
+    }
+}
+
+/// Where a cursor-paginated list stopped: the sort it was read in, and the
+/// sort key and id of the last todo returned. Unlike an offset, it still
+/// points at the right place after todos are added or removed before it.
+/// Clients get it base64url-encoded and should treat it as opaque.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Cursor {
+    pub sort: SortField,
+    pub order: SortOrder,
+    key: SortKey,
+    id: Uuid,
+}
+
+impl Cursor {
+    pub fn after(todo: &Todo, sort: SortField, order: SortOrder) -> Self {
+        Cursor {
+            sort,
+            order,
+            key: sort.key(todo),
+            id: todo.id,
+        }
+    }
+
+    pub fn encode(&self) -> String {
+        let json = serde_json::to_vec(self).expect("cursors serialize");
+        URL_SAFE_NO_PAD.encode(json)
+    }
+
+    pub fn decode(encoded: &str) -> Result<Self, String> {
+        URL_SAFE_NO_PAD
+            .decode(encoded)
+            .ok()
+            .and_then(|json| serde_json::from_slice(&json).ok())
+            .ok_or_else(|| "Invalid cursor".to_string())
+    }
+
+    /// Whether `todo` sorts after the cursor, on a later page.
+    pub fn precedes(&self, todo: &Todo) -> bool {
+        let ordering = self
+            .key
+            .cmp(&self.sort.key(todo))
+            .then_with(|| self.id.cmp(&todo.id));
+        match self.order {
+            SortOrder::Asc => ordering.is_lt(),
+            SortOrder::Desc => ordering.is_gt(),
+        }
+    }
+}
+
+fn priority_rank(priority: &Priority) -> u8 {
+    match priority {
+        Priority::Low => 0,
+        Priority::Medium => 1,
+        Priority::High => 2,
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct SeedRequest {
+    /// Name of a built-in fixture set; defaults to "default".
+    pub fixture: Option<String>,
+    /// Explicit todos to seed instead of a built-in set.
+    pub todos: Option<Vec<TodoCreate>>,
+    /// Wipe existing todos before seeding.
+    pub replace: Option<bool>,
+}
+
+/// Query of `POST /api/admin/generate`.
+#[derive(Debug, Default, Deserialize)]
+pub struct GenerateQuery {
+    /// How many todos to make; see `fixtures::MAX_GENERATED`.
+    pub count: Option<usize>,
+    /// Makes the same todos for the same seed; random when unset.
+    pub seed: Option<u64>,
+}
+
+pub const DEFAULT_PAGE_LIMIT: usize = 50;
+pub const MAX_PAGE_LIMIT: usize = 500;
+
+/// A page of results from a larger collection.
+#[derive(Debug, Serialize)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub total: usize,
+    pub limit: usize,
+    pub offset: usize,
+    #[serde(rename = "hasMore")]
+    pub has_more: bool,
+    /// Where the next page starts, for pages read by cursor.
+    #[serde(rename = "nextCursor", skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+}
+
+impl<T> Page<T> {
+    /// Slices `items` with the requested window; `limit` is clamped to
+    /// `MAX_PAGE_LIMIT` and defaults to `DEFAULT_PAGE_LIMIT`.
+    pub fn from_vec(items: Vec<T>, limit: Option<usize>, offset: Option<usize>) -> Self {
+        let total = items.len();
+        let limit = limit.unwrap_or(DEFAULT_PAGE_LIMIT).clamp(1, MAX_PAGE_LIMIT);
+        let offset = offset.unwrap_or(0);
+        let items: Vec<T> = items.into_iter().skip(offset).take(limit).collect();
+        let has_more = offset + items.len() < total;
+        Page {
+            items,
+            total,
+            limit,
+            offset,
+            has_more,
+            next_cursor: None,
+        }
+    }
+}
+
+impl<T: Borrow<Todo>> Page<T> {
+    /// The todos after `cursor`, or the first ones without one. `todos` must
+    /// already be sorted the way the cursor says. `offset` reports how many
+    /// were skipped.
+    pub fn after_cursor(
+        todos: Vec<T>,
+        sort: SortField,
+        order: SortOrder,
+        cursor: Option<&Cursor>,
+        limit: Option<usize>,
+    ) -> Self {
+        let total = todos.len();
+        let limit = limit.unwrap_or(DEFAULT_PAGE_LIMIT).clamp(1, MAX_PAGE_LIMIT);
+        let offset = cursor.map_or(0, |cursor| {
+            todos
+                .iter()
+                .take_while(|todo| !cursor.precedes((*todo).borrow()))
+                .count()
+        });
+        let items: Vec<T> = todos.into_iter().skip(offset).take(limit).collect();
+        let has_more = offset + items.len() < total;
+        let next_cursor = items
+            .last()
+            .filter(|_| has_more)
+            .map(|last| Cursor::after(last.borrow(), sort, order).encode());
+        Page {
+            items,
+            total,
+            limit,
+            offset,
+            has_more,
+            next_cursor,
+        }
+    }
+}
+
+/// Aggregates over every todo matching a list query, not just the page.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ListMeta {
+    pub matching: usize,
+    pub active: usize,
+    pub completed: usize,
+    pub overdue: usize,
+}
+
+impl ListMeta {
+    /// Overdue counts active todos due before `today`, as in `TodoStats`.
+    pub fn from_todos<T: Borrow<Todo>>(todos: &[T], today: NaiveDate) -> Self {
+        let completed = todos.iter().filter(|t| (*t).borrow().completed).count();
+        let overdue = todos
+            .iter()
+            .map(|t| -> &Todo { t.borrow() })
+            .filter(|t| !t.completed)
+            .filter_map(|t| t.due_date.as_deref())
+            .filter_map(|due| NaiveDate::parse_from_str(due, "%Y-%m-%d").ok())
+            .filter(|due| *due < today)
+            .count();
+        ListMeta {
+            matching: todos.len(),
+            active: todos.len() - completed,
+            completed,
+            overdue,
+        }
+    }
+}
+
+/// Paginated `GET /api/todos` response.
+#[derive(Debug, Serialize)]
+pub struct TodoPage {
+    #[serde(flatten)]
+    pub page: Page<Arc<Todo>>,
+    pub meta: ListMeta,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct EventLogQuery {
+    /// Comma-separated event types, e.g. `todo.created,todo.completed`.
+    #[serde(rename = "type")]
+    pub event_type: Option<String>,
+    #[serde(rename = "todoId")]
+    pub todo_id: Option<String>,
+    pub actor: Option<String>,
+    #[serde(rename = "requestId")]
+    pub request_id: Option<String>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    pub limit: Option<usize>,
+    pub offset: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AuditQuery {
+    pub actor: Option<String>,
+    /// Comma-separated event types, as for the event log.
+    pub action: Option<String>,
+    #[serde(rename = "todoId")]
+    pub todo_id: Option<String>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    pub limit: Option<usize>,
+    pub offset: Option<usize>,
+}
+
+/// Query of `GET /api/activity`.
+#[derive(Debug, Deserialize)]
+pub struct ActivityQuery {
+    pub limit: Option<usize>,
+    pub offset: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReplayQuery {
+    /// Event sequence number or RFC 3339 timestamp; omitted replays everything.
+    pub since: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ChangesQuery {
+    /// Sequence number from a previous response's `cursor`; 0 starts from the beginning.
+    pub since: Option<u64>,
+    pub limit: Option<usize>,
+}
+
+/// Query of `GET /api/todos/nearby`.
+#[derive(Debug, Deserialize)]
+pub struct NearbyQuery {
+    pub lat: f64,
+    pub lng: f64,
+    /// Search radius in meters; defaults to 1 km.
+    pub radius: Option<f64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct DigestQuery {
+    /// Look-ahead of the upcoming section in days; defaults to a week.
+    pub days: Option<u32>,
+    /// `json` (the default) or `text`.
+    pub format: Option<String>,
+    /// Locale tag for dates in the text format; falls back to
+    /// `Accept-Language`, then ISO.
+    pub locale: Option<String>,
+    /// Line width for the text format; 0 or unset leaves lines unwrapped.
+    pub width: Option<usize>,
+    /// Whether the text format mentions priorities; defaults to true.
+    pub priority: Option<bool>,
+}
+
+/// Stable, machine-readable reason for an error response, sent as `code`
+/// next to the human `error` message. Clients should branch on the code:
+/// messages may be reworded or translated, codes are not.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ErrorCode {
+    /// A field, header or query parameter is missing or invalid.
+    ValidationFailed,
+    /// A todo id in the path is not a UUID.
+    InvalidId,
+    /// The todo text is empty.
+    TextRequired,
+    /// The todo text is over 500 characters.
+    TextTooLong,
+    /// The endpoint needs `X-Client-Id`.
+    ClientIdRequired,
+    /// A token is missing or wrong.
+    Unauthorized,
+    Forbidden,
+    NotFound,
+    TodoNotFound,
+    /// The feature behind the endpoint is not set up on this server.
+    NotConfigured,
+    Conflict,
+    /// The todo changed since the client read it.
+    TodoChanged,
+    /// A bulk edit preview has expired or the todos changed since.
+    PreviewExpired,
+    Gone,
+    PreconditionFailed,
+    /// Moderation refused the todo text.
+    ContentRejected,
+    RateLimited,
+    InternalError,
+    NotImplemented,
+    /// A server this one relies on, such as a transfer peer, failed.
+    UpstreamFailed,
+    Unavailable,
+    /// The request's `X-Request-Timeout` or deadline ran out.
+    DeadlineExceeded,
+}
+
+impl ErrorCode {
+    pub const ALL: [ErrorCode; 22] = [
+        ErrorCode::ValidationFailed,
+        ErrorCode::InvalidId,
+        ErrorCode::TextRequired,
+        ErrorCode::TextTooLong,
+        ErrorCode::ClientIdRequired,
+        ErrorCode::Unauthorized,
+        ErrorCode::Forbidden,
+        ErrorCode::NotFound,
+        ErrorCode::TodoNotFound,
+        ErrorCode::NotConfigured,
+        ErrorCode::Conflict,
+        ErrorCode::TodoChanged,
+        ErrorCode::PreviewExpired,
+        ErrorCode::Gone,
+        ErrorCode::PreconditionFailed,
+        ErrorCode::ContentRejected,
+        ErrorCode::RateLimited,
+        ErrorCode::InternalError,
+        ErrorCode::NotImplemented,
+        ErrorCode::UpstreamFailed,
+        ErrorCode::Unavailable,
+        ErrorCode::DeadlineExceeded,
+    ];
+
+    /// The code for an error of `status` that has no more specific one.
+    pub fn for_status(status: u16) -> Self {
+        match status {
+            401 => ErrorCode::Unauthorized,
+            403 => ErrorCode::Forbidden,
+            404 => ErrorCode::NotFound,
+            409 => ErrorCode::Conflict,
+            410 => ErrorCode::Gone,
+            412 => ErrorCode::PreconditionFailed,
+            429 => ErrorCode::RateLimited,
+            501 => ErrorCode::NotImplemented,
+            502 => ErrorCode::UpstreamFailed,
+            503 => ErrorCode::Unavailable,
+            504 => ErrorCode::DeadlineExceeded,
+            500..=599 => ErrorCode::InternalError,
+            _ => ErrorCode::ValidationFailed,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_priority_default() {
+        let priority = Priority::default();
+        assert_eq!(priority, Priority::Medium);
+    }
+
+    #[test]
+    fn test_priority_serialization() {
+        let priority = Priority::High;
+        let json = serde_json::to_string(&priority).unwrap();
+        assert_eq!(json, "\"high\"");
+    }
+
+    #[test]
+    fn test_todo_creation() {
+        let todo = Todo {
+            id: todo_id("test-id"),
+            text: "Test Todo".to_string(),
+            priority: Priority::High,
+            completed: false,
+            due_date: Some("2024-12-31".to_string()),
+            reminder_time: Some("10:00".to_string()),
+            recurrence: None,
+            recurrence_end: None,
+            remaining_occurrences: None,
+            estimate_minutes: None,
+            location: None,
+            color: None,
+            icon: None,
+            pinned: false,
+            rollover_count: 0,
+            rolled_over_to: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+
+        assert_eq!(todo.id, todo_id("test-id"));
+        assert_eq!(todo.text, "Test Todo");
+        assert_eq!(todo.priority, Priority::High);
+        assert!(!todo.completed);
+    }
+
+    #[test]
+    fn test_todo_serialization() {
+        let todo = Todo {
+            id: todo_id("test-id"),
+            text: "Test".to_string(),
+            priority: Priority::Low,
+            completed: false,
+            due_date: None,
+            reminder_time: None,
+            recurrence: None,
+            recurrence_end: None,
+            remaining_occurrences: None,
+            estimate_minutes: None,
+            location: None,
+            color: None,
+            icon: None,
+            pinned: false,
+            rollover_count: 0,
+            rolled_over_to: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+
+        let json = serde_json::to_string(&todo).unwrap();
+        assert!(json.contains(&format!("\"id\":\"{}\"", todo.id)));
+        assert!(json.contains("\"text\":\"Test\""));
+        assert!(json.contains("\"priority\":\"low\""));
+
+        let legacy = json.replace(&todo.id.to_string(), "test-id");
+        let read: Todo = serde_json::from_str(&legacy).unwrap();
+        assert_eq!(read.id, todo.id);
+    }
+
+    #[test]
+    fn test_todo_id_keeps_uuids_and_maps_the_rest() {
+        let uuid = Uuid::new_v4();
+        assert_eq!(todo_id(&uuid.to_string()), uuid);
+        assert_eq!(todo_id(&uuid.to_string().to_uppercase()), uuid);
+        assert_eq!(todo_id("py-1"), todo_id(" py-1 "));
+        assert_ne!(todo_id("py-1"), todo_id("py-2"));
+    }
+
+    #[test]
+    fn test_page_from_vec() {
+        let page = Page::from_vec((1..=10).collect(), Some(4), Some(8));
+        assert_eq!(page.items, vec![9, 10]);
+        assert_eq!(page.total, 10);
+        assert!(!page.has_more);
+
+        let page = Page::from_vec((1..=10).collect::<Vec<i32>>(), Some(0), None);
+        assert_eq!(page.limit, 1);
+        assert!(page.has_more);
+    }
+
+    #[test]
+    fn test_sort_field() {
+        let todo = |id: &str, priority: Priority, due: Option<&str>| Todo {
+            id: todo_id(id),
+            text: id.to_uppercase(),
+            priority,
+            completed: false,
+            due_date: due.map(str::to_string),
+            reminder_time: None,
+            recurrence: None,
+            recurrence_end: None,
+            remaining_occurrences: None,
+            estimate_minutes: None,
+            location: None,
+            color: None,
+            icon: None,
+            pinned: false,
+            rollover_count: 0,
+            rolled_over_to: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+        let mut todos = vec![
+            todo("a", Priority::Low, None),
+            todo("b", Priority::High, Some("2024-02-01")),
+            todo("c", Priority::Medium, Some("2024-01-01")),
+        ];
+        let ids = |todos: &[Todo]| todos.iter().map(|t| t.id).collect::<Vec<_>>();
+
+        SortField::DueDate.sort(&mut todos, SortOrder::Asc);
+        assert_eq!(ids(&todos), ["c", "b", "a"].map(todo_id));
+        SortField::Priority.sort(&mut todos, SortOrder::Desc);
+        assert_eq!(ids(&todos), ["b", "c", "a"].map(todo_id));
+        SortField::Text.sort(&mut todos, SortOrder::Asc);
+        assert_eq!(ids(&todos), ["a", "b", "c"].map(todo_id));
+    }
+
+    #[test]
+    fn test_cursor_pages_survive_inserts() {
+        let todo = |id: &str, priority: Priority| Todo {
+            id: todo_id(id),
+            text: id.to_string(),
+            priority,
+            completed: false,
+            due_date: None,
+            reminder_time: None,
+            recurrence: None,
+            recurrence_end: None,
+            remaining_occurrences: None,
+            estimate_minutes: None,
+            location: None,
+            color: None,
+            icon: None,
+            pinned: false,
+            rollover_count: 0,
+            rolled_over_to: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+        let sorted = |mut todos: Vec<Todo>| {
+            SortField::Priority.sort(&mut todos, SortOrder::Desc);
+            todos
+        };
+        let ids = |page: &Page<Todo>| page.items.iter().map(|t| t.id).collect::<Vec<_>>();
+        let mut todos = vec![
+            todo("a", Priority::High),
+            todo("b", Priority::Medium),
+            todo("c", Priority::Medium),
+            todo("d", Priority::Low),
+        ];
+
+        let first = Page::after_cursor(
+            sorted(todos.clone()),
+            SortField::Priority,
+            SortOrder::Desc,
+            None,
+            Some(2),
+        );
+        assert_eq!(ids(&first), ["a", "b"].map(todo_id));
+        let cursor = Cursor::decode(first.next_cursor.as_deref().unwrap()).unwrap();
+        assert_eq!(cursor.sort, SortField::Priority);
+
+        // A todo added ahead of the cursor does not push "b" onto page two.
+        todos.push(todo("e", Priority::High));
+        let second = Page::after_cursor(
+            sorted(todos),
+            SortField::Priority,
+            SortOrder::Desc,
+            Some(&cursor),
+            Some(2),
+        );
+        assert_eq!(ids(&second), ["c", "d"].map(todo_id));
+        assert_eq!(second.offset, 3);
+        assert!(!second.has_more);
+        assert!(second.next_cursor.is_none());
+
+        assert!(Cursor::decode("not a cursor").is_err());
+    }
+
+    #[test]
+    fn test_list_meta_from_todos() {
+        let today = NaiveDate::from_ymd_opt(2024, 6, 15).unwrap();
+        let todo = |completed: bool, due: Option<&str>| Todo {
+            id: todo_id("id"),
+            text: "Test".to_string(),
+            priority: Priority::Medium,
+            completed,
+            due_date: due.map(str::to_string),
+            reminder_time: None,
+            recurrence: None,
+            recurrence_end: None,
+            remaining_occurrences: None,
+            estimate_minutes: None,
+            location: None,
+            color: None,
+            icon: None,
+            pinned: false,
+            rollover_count: 0,
+            rolled_over_to: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+        let todos = vec![
+            todo(false, Some("2024-06-14")),
+            todo(true, Some("2024-06-01")),
+            todo(false, Some("2024-06-15")),
+            todo(false, None),
+        ];
+        let meta = ListMeta::from_todos(&todos, today);
+        assert_eq!(
+            meta,
+            ListMeta {
+                matching: 4,
+                active: 3,
+                completed: 1,
+                overdue: 1,
+            }
+        );
+    }
+
+    #[test]
+    fn test_validate_location() {
+        let location = |name: Option<&str>, lat: Option<f64>, lng: Option<f64>| Location {
+            name: name.map(str::to_string),
+            lat,
+            lng,
+        };
+        assert!(validate_location(None).is_ok());
+        assert!(validate_location(Some(&location(Some("Bakery"), None, None))).is_ok());
+        assert!(validate_location(Some(&location(None, Some(48.85), Some(2.35)))).is_ok());
+        assert!(validate_location(Some(&location(None, None, None))).is_err());
+        assert!(validate_location(Some(&location(None, Some(48.85), None))).is_err());
+        assert!(validate_location(Some(&location(None, Some(91.0), Some(0.0)))).is_err());
+        assert!(validate_location(Some(&location(Some(" "), Some(0.0), Some(0.0)))).is_err());
+    }
+
+    #[test]
+    fn test_validate_estimate() {
+        assert!(validate_estimate(None).is_ok());
+        assert!(validate_estimate(Some(90)).is_ok());
+        assert!(validate_estimate(Some(0)).is_err());
+        assert!(validate_estimate(Some(MAX_ESTIMATE_MINUTES + 1)).is_err());
+    }
+
+    #[test]
+    fn test_validate_recurrence_end() {
+        let end: RecurrenceEnd = serde_json::from_str(r#"{"afterOccurrences": 3}"#).unwrap();
+        assert_eq!(end, RecurrenceEnd::AfterOccurrences(3));
+        let end: RecurrenceEnd = serde_json::from_str(r#"{"until": "2024-06-30"}"#).unwrap();
+        assert!(validate_recurrence_end(Some(&end)).is_ok());
+        assert!(validate_recurrence_end(Some(&RecurrenceEnd::AfterOccurrences(0))).is_err());
+        assert!(
+            validate_recurrence_end(Some(&RecurrenceEnd::AfterOccurrences(MAX_OCCURRENCES + 1)))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_advance_stops_at_recurrence_end() {
+        let mut todo = Todo {
+            id: todo_id("test-id"),
+            text: "Water plants".to_string(),
+            priority: Priority::Medium,
+            completed: false,
+            due_date: Some("2024-06-10".to_string()),
+            reminder_time: None,
+            recurrence: Some(Recurrence::Weekly),
+            recurrence_end: Some(RecurrenceEnd::AfterOccurrences(3)),
+            remaining_occurrences: None,
+            estimate_minutes: None,
+            location: None,
+            color: None,
+            icon: None,
+            pinned: false,
+            rollover_count: 0,
+            rolled_over_to: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+        todo.reset_remaining_occurrences();
+        assert_eq!(todo.remaining_occurrences, Some(2));
+        assert_eq!(todo.advance().unwrap().to_string(), "2024-06-17");
+        assert_eq!(todo.advance().unwrap().to_string(), "2024-06-24");
+        assert_eq!(todo.remaining_occurrences, Some(0));
+        assert_eq!(todo.advance(), None);
+        assert_eq!(todo.due_date.as_deref(), Some("2024-06-24"));
+
+        // Changing the end condition recounts from the current occurrence.
+        let update = TodoUpdate {
+            recurrence_end: Some(RecurrenceEnd::Until(
+                NaiveDate::from_ymd_opt(2024, 7, 10).unwrap(),
+            )),
+            ..Default::default()
+        };
+        update.apply_to(&mut todo);
+        assert_eq!(todo.remaining_occurrences, Some(2));
+        todo.advance();
+        todo.advance();
+        assert_eq!(todo.due_date.as_deref(), Some("2024-07-08"));
+        assert_eq!(todo.remaining_occurrences, Some(0));
+        assert_eq!(todo.advance(), None);
+        let json = serde_json::to_value(&todo).unwrap();
+        assert_eq!(json["recurrenceEnd"]["until"], "2024-07-10");
+        assert_eq!(json["remainingOccurrences"], 0);
+    }
+
+    #[test]
+    fn test_error_codes_are_screaming_snake_case() {
+        let codes = serde_json::to_value(ErrorCode::ALL).unwrap();
+        assert_eq!(codes[0], "VALIDATION_FAILED");
+        assert_eq!(
+            serde_json::to_value(ErrorCode::TodoNotFound).unwrap(),
+            "TODO_NOT_FOUND"
+        );
+        assert_eq!(ErrorCode::for_status(404), ErrorCode::NotFound);
+        assert_eq!(ErrorCode::for_status(422), ErrorCode::ValidationFailed);
+        assert_eq!(ErrorCode::for_status(500), ErrorCode::InternalError);
+    }
+}
@@ -0,0 +1,406 @@
+use crate::deadline::{Deadline, DeadlineExceeded};
+use crate::models::Todo;
+use crate::service::TodoService;
+use chrono::{NaiveDate, NaiveTime};
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+
+/// Minutes assumed for todos without `estimateMinutes`.
+pub const DEFAULT_ESTIMATE_MINUTES: u32 = 30;
+/// How far ahead todos are pulled in to fill spare time, in days.
+pub const DEFAULT_LOOKAHEAD_DAYS: u32 = 7;
+/// Largest capacity the planner works with, a full day.
+pub const MAX_CAPACITY_MINUTES: u32 = 24 * 60;
+
+/// Query of `GET /api/plan/today`.
+#[derive(Debug, Default, Deserialize)]
+pub struct PlanQuery {
+    /// Minutes available today, overriding `DAILY_CAPACITY_MINUTES`.
+    pub capacity: Option<u32>,
+    /// How far ahead to look for todos to fill spare time.
+    pub lookahead: Option<u32>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct PlanOptions {
+    pub capacity_minutes: u32,
+    pub lookahead_days: u32,
+    pub default_estimate_minutes: u32,
+}
+
+impl PlanOptions {
+    pub fn new(capacity_minutes: u32) -> Self {
+        PlanOptions {
+            capacity_minutes: capacity_minutes.min(MAX_CAPACITY_MINUTES),
+            lookahead_days: DEFAULT_LOOKAHEAD_DAYS,
+            default_estimate_minutes: DEFAULT_ESTIMATE_MINUTES,
+        }
+    }
+}
+
+/// Why a todo is in the plan.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Urgency {
+    Overdue,
+    DueToday,
+    /// Due within the lookahead, done early to free up later days.
+    Upcoming,
+    /// No due date; fills whatever time is left.
+    Anytime,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PlannedTodo {
+    pub todo: Todo,
+    pub urgency: Urgency,
+    pub minutes: u32,
+    /// The todo has no estimate, so `minutes` is the default.
+    #[serde(rename = "estimateAssumed")]
+    pub estimate_assumed: bool,
+    /// Minutes of planned work before this one starts.
+    #[serde(rename = "startsAfterMinutes")]
+    pub starts_after_minutes: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum WarningCode {
+    /// Overdue and due-today work does not fit in the day.
+    OverCapacity,
+    /// A single todo takes longer than the whole day.
+    TooLong,
+    /// Some minutes are guesses.
+    Unestimated,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PlanWarning {
+    pub code: WarningCode,
+    pub message: String,
+    #[serde(rename = "todoIds")]
+    pub todo_ids: Vec<String>,
+}
+
+/// What to work on today, in order.
+#[derive(Debug, Clone, Serialize)]
+pub struct DayPlan {
+    pub date: NaiveDate,
+    #[serde(rename = "capacityMinutes")]
+    pub capacity_minutes: u32,
+    #[serde(rename = "plannedMinutes")]
+    pub planned_minutes: u32,
+    pub agenda: Vec<PlannedTodo>,
+    /// Overdue and due-today todos that did not fit. Upcoming and undated
+    /// todos that did not fit are simply left for another day.
+    pub overflow: Vec<PlannedTodo>,
+    pub warnings: Vec<PlanWarning>,
+}
+
+struct Candidate<'a> {
+    todo: &'a Todo,
+    urgency: Urgency,
+    due: Option<NaiveDate>,
+    time: Option<NaiveTime>,
+    minutes: u32,
+}
+
+impl Candidate<'_> {
+    fn planned(&self, starts_after_minutes: u32) -> PlannedTodo {
+        PlannedTodo {
+            todo: self.todo.clone(),
+            urgency: self.urgency,
+            minutes: self.minutes,
+            estimate_assumed: self.todo.estimate_minutes.is_none(),
+            starts_after_minutes,
+        }
+    }
+
+    /// What doing an optional todo today is worth: its priority weight,
+    /// scaled up the closer it is due. Undated todos count as due just
+    /// past the lookahead.
+    fn value(&self, today: NaiveDate, lookahead_days: u32) -> u64 {
+        let days_left = self.due.map_or(i64::from(lookahead_days) + 1, |due| {
+            (due - today).num_days()
+        });
+        let closeness = (i64::from(lookahead_days) + 2 - days_left).max(1) as u64;
+        u64::from(self.todo.priority.weight()) * closeness
+    }
+}
+
+/// Picks the optional todos worth the most that fit in `minutes`, as a
+/// 0/1 knapsack over whole minutes. Returns their indexes in `candidates`.
+fn fill(candidates: &[(u64, u32)], minutes: u32) -> Vec<usize> {
+    let capacity = minutes as usize;
+    let mut best = vec![0u64; capacity + 1];
+    let mut taken = vec![vec![false; capacity + 1]; candidates.len()];
+    for (i, &(value, cost)) in candidates.iter().enumerate() {
+        let cost = cost as usize;
+        if cost > capacity {
+            continue;
+        }
+        for room in (cost..=capacity).rev() {
+            if best[room - cost] + value > best[room] {
+                best[room] = best[room - cost] + value;
+                taken[i][room] = true;
+            }
+        }
+    }
+    let mut chosen = Vec::new();
+    let mut room = capacity;
+    for i in (0..candidates.len()).rev() {
+        if taken[i][room] {
+            chosen.push(i);
+            room -= candidates[i].1 as usize;
+        }
+    }
+    chosen.reverse();
+    chosen
+}
+
+impl DayPlan {
+    /// Plans `today` from `todos`. Overdue and due-today todos come first,
+    /// highest priority first and then by reminder time, as long as they
+    /// fit. Whatever time is left goes to the most valuable mix of todos
+    /// due within the lookahead and undated ones, ordered by due date.
+    pub fn build(todos: &[Todo], today: NaiveDate, options: &PlanOptions) -> Self {
+        let horizon = today + chrono::Duration::days(options.lookahead_days.into());
+        let mut candidates: Vec<Candidate> = todos
+            .iter()
+            .filter(|todo| !todo.completed && todo.hidden_state().is_none())
+            .filter_map(|todo| {
+                let due = todo
+                    .due_date
+                    .as_deref()
+                    .and_then(|due| NaiveDate::parse_from_str(due, "%Y-%m-%d").ok());
+                let urgency = match due {
+                    Some(due) if due < today => Urgency::Overdue,
+                    Some(due) if due == today => Urgency::DueToday,
+                    Some(due) if due <= horizon => Urgency::Upcoming,
+                    Some(_) => return None,
+                    None => Urgency::Anytime,
+                };
+                Some(Candidate {
+                    todo,
+                    urgency,
+                    due,
+                    time: todo
+                        .reminder_time
+                        .as_deref()
+                        .and_then(|time| NaiveTime::parse_from_str(time, "%H:%M").ok()),
+                    minutes: todo
+                        .estimate_minutes
+                        .unwrap_or(options.default_estimate_minutes),
+                })
+            })
+            .collect();
+        candidates.sort_by_key(|candidate| {
+            (
+                candidate.urgency > Urgency::DueToday,
+                Reverse(candidate.todo.priority.weight()),
+                candidate.time.is_none(),
+                candidate.time,
+                candidate.due,
+                candidate.todo.created_at,
+            )
+        });
+        let (required, optional): (Vec<Candidate>, Vec<Candidate>) = candidates
+            .into_iter()
+            .partition(|candidate| candidate.urgency <= Urgency::DueToday);
+
+        let mut plan = DayPlan {
+            date: today,
+            capacity_minutes: options.capacity_minutes,
+            planned_minutes: 0,
+            agenda: Vec::new(),
+            overflow: Vec::new(),
+            warnings: Vec::new(),
+        };
+        for candidate in &required {
+            if plan.planned_minutes + candidate.minutes <= options.capacity_minutes {
+                plan.agenda.push(candidate.planned(plan.planned_minutes));
+                plan.planned_minutes += candidate.minutes;
+            } else {
+                plan.overflow.push(candidate.planned(0));
+            }
+        }
+
+        let values: Vec<(u64, u32)> = optional
+            .iter()
+            .map(|candidate| {
+                (
+                    candidate.value(today, options.lookahead_days),
+                    candidate.minutes,
+                )
+            })
+            .collect();
+        let mut chosen: Vec<&Candidate> =
+            fill(&values, options.capacity_minutes - plan.planned_minutes)
+                .into_iter()
+                .map(|i| &optional[i])
+                .collect();
+        chosen.sort_by_key(|candidate| {
+            (
+                candidate.due.is_none(),
+                candidate.due,
+                Reverse(candidate.todo.priority.weight()),
+                candidate.todo.created_at,
+            )
+        });
+        for candidate in chosen {
+            plan.agenda.push(candidate.planned(plan.planned_minutes));
+            plan.planned_minutes += candidate.minutes;
+        }
+
+        plan.warn(&required, options);
+        plan
+    }
+
+    fn warn(&mut self, required: &[Candidate], options: &PlanOptions) {
+        if !self.overflow.is_empty() {
+            let required_minutes: u32 = required.iter().map(|candidate| candidate.minutes).sum();
+            self.warnings.push(PlanWarning {
+                code: WarningCode::OverCapacity,
+                message: format!(
+                    "Overdue and due-today todos need {} minutes but the day has {}; {} did not fit",
+                    required_minutes,
+                    options.capacity_minutes,
+                    self.overflow.len()
+                ),
+                todo_ids: self.overflow.iter().map(|planned| planned.todo.id.to_string()).collect(),
+            });
+        }
+        let too_long: Vec<String> = required
+            .iter()
+            .filter(|candidate| candidate.minutes > options.capacity_minutes)
+            .map(|candidate| candidate.todo.id.to_string())
+            .collect();
+        if !too_long.is_empty() {
+            self.warnings.push(PlanWarning {
+                code: WarningCode::TooLong,
+                message: format!(
+                    "{} todo(s) take longer than the whole day; consider splitting them",
+                    too_long.len()
+                ),
+                todo_ids: too_long,
+            });
+        }
+        let unestimated: Vec<String> = self
+            .agenda
+            .iter()
+            .chain(&self.overflow)
+            .filter(|planned| planned.estimate_assumed)
+            .map(|planned| planned.todo.id.to_string())
+            .collect();
+        if !unestimated.is_empty() {
+            self.warnings.push(PlanWarning {
+                code: WarningCode::Unestimated,
+                message: format!(
+                    "{} todo(s) have no estimate; assumed {} minutes each",
+                    unestimated.len(),
+                    options.default_estimate_minutes
+                ),
+                todo_ids: unestimated,
+            });
+        }
+    }
+}
+
+impl TodoService {
+    /// Plans `today` from the current todos; see `DayPlan::build`.
+    pub fn plan_day_until(
+        &self,
+        today: NaiveDate,
+        options: &PlanOptions,
+        deadline: &Deadline,
+    ) -> Result<DayPlan, DeadlineExceeded> {
+        let todos = self.get_all_until(Some("active".to_string()), None, None, deadline)?;
+        Ok(DayPlan::build(&todos, today, options))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{todo_id, Priority};
+    use chrono::{Duration, Utc};
+    use uuid::Uuid;
+
+    fn today() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 6, 10).unwrap()
+    }
+
+    fn todo(id: &str, priority: Priority, due_in: Option<i64>, minutes: Option<u32>) -> Todo {
+        Todo {
+            id: todo_id(id),
+            text: format!("Todo {}", id),
+            priority,
+            completed: false,
+            due_date: due_in.map(|days| (today() + Duration::days(days)).to_string()),
+            reminder_time: None,
+            recurrence: None,
+            recurrence_end: None,
+            remaining_occurrences: None,
+            estimate_minutes: minutes,
+            location: None,
+            color: None,
+            icon: None,
+            pinned: false,
+            rollover_count: 0,
+            rolled_over_to: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    fn ids(planned: &[PlannedTodo]) -> Vec<Uuid> {
+        planned.iter().map(|planned| planned.todo.id).collect()
+    }
+
+    #[test]
+    fn test_due_work_first_then_best_fill() {
+        let todos = vec![
+            todo("someday", Priority::Low, None, Some(60)),
+            todo("friday", Priority::High, Some(4), Some(90)),
+            todo("today-low", Priority::Low, Some(0), Some(60)),
+            todo("overdue-high", Priority::High, Some(-2), Some(120)),
+            todo("tomorrow", Priority::Medium, Some(1), Some(120)),
+            todo("next-month", Priority::High, Some(30), Some(10)),
+        ];
+        let plan = DayPlan::build(&todos, today(), &PlanOptions::new(360));
+        // 180 minutes left after the required work: the 120 minute
+        // tomorrow todo alone is worth less than friday's plus someday's.
+        assert_eq!(
+            ids(&plan.agenda),
+            ["overdue-high", "today-low", "friday", "someday"].map(todo_id)
+        );
+        assert_eq!(plan.planned_minutes, 330);
+        assert_eq!(plan.agenda[1].starts_after_minutes, 120);
+        assert!(plan.overflow.is_empty());
+        assert!(plan.warnings.is_empty());
+    }
+
+    #[test]
+    fn test_warns_when_due_work_overflows() {
+        let todos = vec![
+            todo("a", Priority::High, Some(0), Some(300)),
+            todo("b", Priority::Medium, Some(-1), None),
+            todo("c", Priority::Low, Some(0), Some(200)),
+            todo("d", Priority::Low, Some(0), Some(600)),
+        ];
+        let plan = DayPlan::build(&todos, today(), &PlanOptions::new(480));
+        assert_eq!(ids(&plan.agenda), ["a", "b"].map(todo_id));
+        assert_eq!(ids(&plan.overflow), ["c", "d"].map(todo_id));
+        let codes: Vec<WarningCode> = plan.warnings.iter().map(|warning| warning.code).collect();
+        assert_eq!(
+            codes,
+            vec![
+                WarningCode::OverCapacity,
+                WarningCode::TooLong,
+                WarningCode::Unestimated
+            ]
+        );
+        assert_eq!(plan.warnings[1].todo_ids, vec![todo_id("d").to_string()]);
+        assert_eq!(plan.warnings[2].todo_ids, vec![todo_id("b").to_string()]);
+    }
+}
@@ -0,0 +1,212 @@
+use crate::deadline::Deadline;
+use crate::events::{EventFilter, EventType};
+use crate::models::{Priority, Todo};
+use crate::service::TodoService;
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Longest a policy can be set to wait, about ten years.
+pub const MAX_AFTER_DAYS: u32 = 3650;
+
+/// What a policy does to the todos it selects. Archiving and emptying the
+/// trash wait on todos having those states; see `Todo::hidden_state`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PolicyAction {
+    /// Delete completed todos left unchanged for the wait.
+    DeleteCompleted,
+    /// Raise active todos a level of priority for every wait they are
+    /// overdue, up to high. Each step is a `todo.escalated` event, so the
+    /// todo's history shows it.
+    EscalatePriority,
+}
+
+/// An action and how many days a todo waits before it applies, e.g.
+/// "delete completed todos after 30 days".
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PolicyRule {
+    pub action: PolicyAction,
+    #[serde(rename = "afterDays")]
+    pub after_days: u32,
+}
+
+impl PolicyRule {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.after_days == 0 || self.after_days > MAX_AFTER_DAYS {
+            return Err(format!("afterDays must be 1 to {}", MAX_AFTER_DAYS));
+        }
+        Ok(())
+    }
+
+    /// Whether the rule applies to `todo` at `now`. Completed todos keep no
+    /// completion time, so their last change stands in for it.
+    pub fn selects(&self, todo: &Todo, now: DateTime<Utc>) -> bool {
+        let after_days = i64::from(self.after_days);
+        match self.action {
+            PolicyAction::DeleteCompleted => {
+                todo.completed && (now - todo.updated_at).num_days() >= after_days
+            }
+            PolicyAction::EscalatePriority => {
+                !todo.completed
+                    && todo.priority != Priority::High
+                    && days_overdue(todo, now.date_naive()).is_some_and(|days| days >= after_days)
+            }
+        }
+    }
+}
+
+fn days_overdue(todo: &Todo, today: NaiveDate) -> Option<i64> {
+    let due = NaiveDate::parse_from_str(todo.due_date.as_deref()?, "%Y-%m-%d").ok()?;
+    Some((today - due).num_days())
+}
+
+impl TodoService {
+    /// Applies `rule` to every todo it selects at `now`, recording the
+    /// change of each. Returns how many todos it changed.
+    pub fn apply_policy(&self, rule: &PolicyRule, now: DateTime<Utc>) -> usize {
+        let guard = self
+            .write_lock(&Deadline::unbounded())
+            .unwrap_or_else(|e| unreachable!("unbounded deadline exceeded: {}", e));
+        let affected = match rule.action {
+            PolicyAction::DeleteCompleted => {
+                let removed = self.store().remove_where(&|todo| rule.selects(todo, now));
+                for todo in &removed {
+                    self.record(EventType::Deleted, todo);
+                }
+                removed.len()
+            }
+            PolicyAction::EscalatePriority => {
+                let mut escalated = 0;
+                for todo in self.store().all() {
+                    if !rule.selects(&todo, now) || !self.escalation_due(rule, &todo, now) {
+                        continue;
+                    }
+                    let updated = self.store().update(todo.id, &mut |todo| {
+                        if let Some(raised) = todo.priority.raised() {
+                            todo.priority = raised;
+                            todo.updated_at = now;
+                        }
+                    });
+                    if let Some(updated) = updated {
+                        self.record(EventType::Escalated, &updated);
+                        escalated += 1;
+                    }
+                }
+                escalated
+            }
+        };
+        drop(guard);
+        if affected > 0 {
+            self.bump_version();
+        }
+        affected
+    }
+
+    /// Whether `todo` is overdue by more waits than it was escalated for
+    /// since it became due on its current date. Moving the due date starts
+    /// the count over.
+    fn escalation_due(&self, rule: &PolicyRule, todo: &Todo, now: DateTime<Utc>) -> bool {
+        let Some(days) = days_overdue(todo, now.date_naive()) else {
+            return false;
+        };
+        let steps = days / i64::from(rule.after_days);
+        let filter = EventFilter {
+            event_types: vec![EventType::Escalated],
+            todo_id: Some(todo.id.to_string()),
+            ..Default::default()
+        };
+        let taken = self
+            .events()
+            .search(&filter)
+            .iter()
+            .filter(|event| event.todo.due_date == todo.due_date)
+            .count();
+        steps > taken as i64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::TodoCreate;
+    use chrono::Duration;
+
+    fn create(service: &TodoService, text: &str, due: Option<NaiveDate>, completed: bool) {
+        service.create(TodoCreate {
+            text: text.to_string(),
+            priority: Some(Priority::Low),
+            completed: Some(completed),
+            due_date: due.map(|due| due.to_string()),
+            ..Default::default()
+        });
+    }
+
+    #[test]
+    fn test_escalates_todos_overdue_long_enough() {
+        let service = TodoService::new_empty();
+        let today = Utc::now().date_naive();
+        create(
+            &service,
+            "Three days late",
+            Some(today - Duration::days(3)),
+            false,
+        );
+        create(
+            &service,
+            "One day late",
+            Some(today - Duration::days(1)),
+            false,
+        );
+        create(&service, "Done late", Some(today - Duration::days(5)), true);
+        let rule = PolicyRule {
+            action: PolicyAction::EscalatePriority,
+            after_days: 3,
+        };
+
+        assert_eq!(service.apply_policy(&rule, Utc::now()), 1);
+        let medium = service.get_all(None, None, Some("medium".to_string()));
+        assert_eq!(medium.len(), 1);
+        assert_eq!(medium[0].text, "Three days late");
+        // One level per three days overdue, so nothing more until day six.
+        assert_eq!(service.apply_policy(&rule, Utc::now()), 0);
+        let later = Utc::now() + Duration::days(3);
+        assert_eq!(service.apply_policy(&rule, later), 2);
+        let high = service.get_all(None, None, Some("high".to_string()));
+        assert_eq!(high.len(), 1);
+        assert_eq!(high[0].text, "Three days late");
+
+        let history = service.events().search(&EventFilter {
+            event_types: vec![EventType::Escalated],
+            todo_id: Some(high[0].id.to_string()),
+            ..Default::default()
+        });
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[1].todo.priority, Priority::High);
+    }
+
+    #[test]
+    fn test_deletes_completed_todos_after_the_wait() {
+        let service = TodoService::new_empty();
+        create(&service, "Done", None, true);
+        create(&service, "Open", None, false);
+        let rule = PolicyRule {
+            action: PolicyAction::DeleteCompleted,
+            after_days: 30,
+        };
+
+        assert_eq!(service.apply_policy(&rule, Utc::now()), 0);
+        assert_eq!(
+            service.apply_policy(&rule, Utc::now() + Duration::days(30)),
+            1
+        );
+        let todos = service.get_all(None, None, None);
+        assert_eq!(todos.len(), 1);
+        assert_eq!(todos[0].text, "Open");
+        assert!(PolicyRule {
+            after_days: 0,
+            ..rule
+        }
+        .validate()
+        .is_err());
+    }
+}
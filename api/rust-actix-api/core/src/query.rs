@@ -0,0 +1,495 @@
+//! The `q` filter language of `GET /api/todos`, for conditions the fixed
+//! parameters can't express:
+//!
+//! ```text
+//! priority:high AND (tag:work OR overdue:true) AND text~"report"
+//! ```
+//!
+//! Conditions are `field:value`, `text~value` (contains) or `due<date`
+//! style comparisons, and a bare word searches the text. They combine with
+//! `NOT`, `AND` and `OR`, binding in that order, and parentheses;
+//! conditions side by side are ANDed.
+
+use crate::models::{Priority, Todo};
+use crate::quick_add;
+use chrono::NaiveDate;
+use nom::branch::alt;
+use nom::bytes::complete::{escaped_transform, is_not, tag, tag_no_case, take_while1};
+use nom::character::complete::{char, multispace0, multispace1};
+use nom::combinator::{all_consuming, eof, map, not, opt, peek, value};
+use nom::multi::many0;
+use nom::sequence::{delimited, pair, preceded, terminated, tuple};
+use nom::IResult;
+
+/// Longest `q` accepted.
+pub const MAX_QUERY_LEN: usize = 500;
+/// Deepest nesting of parentheses, so a query can't make the parser
+/// recurse deeply.
+pub const MAX_NESTING: usize = 16;
+
+/// A parsed `q`, ready to be matched against todos.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Query {
+    And(Box<Query>, Box<Query>),
+    Or(Box<Query>, Box<Query>),
+    Not(Box<Query>),
+    Condition(Condition),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Condition {
+    Priority(Priority),
+    Completed(bool),
+    /// Active and due before today.
+    Overdue(bool),
+    /// A `#tag` in the text.
+    Tag(String),
+    /// Case-insensitive substring of the text.
+    Text(String),
+    Due(Comparison, Option<NaiveDate>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Eq,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl Comparison {
+    fn symbol(self) -> &'static str {
+        match self {
+            Comparison::Eq => ":",
+            Comparison::Lt => "<",
+            Comparison::Le => "<=",
+            Comparison::Gt => ">",
+            Comparison::Ge => ">=",
+        }
+    }
+}
+
+/// A condition as written, before its field and value are checked.
+#[derive(Debug, Clone, PartialEq)]
+struct Term {
+    field: Option<String>,
+    op: Op,
+    value: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Op {
+    Compare(Comparison),
+    Contains,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    And(Box<Node>, Box<Node>),
+    Or(Box<Node>, Box<Node>),
+    Not(Box<Node>),
+    Term(Term),
+}
+
+fn keyword<'a>(word: &'static str) -> impl FnMut(&'a str) -> IResult<&'a str, &'a str> {
+    terminated(
+        tag_no_case(word),
+        peek(alt((multispace1, tag("("), tag(")"), eof))),
+    )
+}
+
+fn is_word_char(c: char) -> bool {
+    !c.is_whitespace() && !matches!(c, '(' | ')' | '"' | ':' | '~' | '<' | '>')
+}
+
+fn word(input: &str) -> IResult<&str, &str> {
+    take_while1(is_word_char)(input)
+}
+
+fn quoted(input: &str) -> IResult<&str, String> {
+    delimited(
+        char('"'),
+        map(
+            opt(escaped_transform(
+                is_not("\"\\"),
+                '\\',
+                alt((value("\"", tag("\"")), value("\\", tag("\\")))),
+            )),
+            Option::unwrap_or_default,
+        ),
+        char('"'),
+    )(input)
+}
+
+fn term_value(input: &str) -> IResult<&str, String> {
+    alt((quoted, map(word, str::to_string)))(input)
+}
+
+fn op(input: &str) -> IResult<&str, Op> {
+    alt((
+        value(Op::Compare(Comparison::Le), tag("<=")),
+        value(Op::Compare(Comparison::Ge), tag(">=")),
+        value(Op::Compare(Comparison::Lt), tag("<")),
+        value(Op::Compare(Comparison::Gt), tag(">")),
+        value(Op::Compare(Comparison::Eq), tag(":")),
+        value(Op::Contains, tag("~")),
+    ))(input)
+}
+
+fn term(input: &str) -> IResult<&str, Node> {
+    let field_term = map(tuple((word, op, term_value)), |(field, op, value)| Term {
+        field: Some(field.to_lowercase()),
+        op,
+        value,
+    });
+    let bare = map(
+        preceded(
+            not(alt((keyword("AND"), keyword("OR"), keyword("NOT")))),
+            term_value,
+        ),
+        |value| Term {
+            field: None,
+            op: Op::Contains,
+            value,
+        },
+    );
+    map(alt((field_term, bare)), Node::Term)(input)
+}
+
+fn atom(input: &str) -> IResult<&str, Node> {
+    alt((
+        delimited(
+            pair(char('('), multispace0),
+            or_expr,
+            pair(multispace0, char(')')),
+        ),
+        term,
+    ))(input)
+}
+
+fn not_expr(input: &str) -> IResult<&str, Node> {
+    alt((
+        map(
+            preceded(pair(keyword("NOT"), multispace0), not_expr),
+            |node| Node::Not(Box::new(node)),
+        ),
+        atom,
+    ))(input)
+}
+
+fn and_expr(input: &str) -> IResult<&str, Node> {
+    let (input, first) = not_expr(input)?;
+    let separator = alt((
+        map(delimited(multispace0, keyword("AND"), multispace0), |_| ()),
+        map(
+            terminated(multispace1, not(peek(alt((keyword("OR"), tag(")")))))),
+            |_| (),
+        ),
+    ));
+    let (input, rest) = many0(preceded(separator, not_expr))(input)?;
+    Ok((input, fold(first, rest, Node::And)))
+}
+
+fn or_expr(input: &str) -> IResult<&str, Node> {
+    let (input, first) = and_expr(input)?;
+    let (input, rest) = many0(preceded(
+        delimited(multispace0, keyword("OR"), multispace0),
+        and_expr,
+    ))(input)?;
+    Ok((input, fold(first, rest, Node::Or)))
+}
+
+fn fold(first: Node, rest: Vec<Node>, join: fn(Box<Node>, Box<Node>) -> Node) -> Node {
+    rest.into_iter()
+        .fold(first, |left, right| join(Box::new(left), Box::new(right)))
+}
+
+fn boolean(field: &str, value: &str) -> Result<bool, String> {
+    match value.to_lowercase().as_str() {
+        "true" | "yes" => Ok(true),
+        "false" | "no" => Ok(false),
+        _ => Err(format!("{} must be true or false, not '{}'", field, value)),
+    }
+}
+
+fn compile_term(term: Term) -> Result<Condition, String> {
+    let Term { field, op, value } = term;
+    let Some(field) = field else {
+        return Ok(Condition::Text(value.to_lowercase()));
+    };
+    let condition = match (field.as_str(), op) {
+        ("text", Op::Contains | Op::Compare(Comparison::Eq)) => {
+            Condition::Text(value.to_lowercase())
+        }
+        ("priority", Op::Compare(Comparison::Eq)) => match value.to_lowercase().as_str() {
+            "low" => Condition::Priority(Priority::Low),
+            "medium" => Condition::Priority(Priority::Medium),
+            "high" => Condition::Priority(Priority::High),
+            _ => return Err(format!("Unknown priority '{}'", value)),
+        },
+        ("completed", Op::Compare(Comparison::Eq)) => {
+            Condition::Completed(boolean("completed", &value)?)
+        }
+        ("overdue", Op::Compare(Comparison::Eq)) => Condition::Overdue(boolean("overdue", &value)?),
+        ("tag", Op::Compare(Comparison::Eq)) => tag_condition(&value)?,
+        ("due", Op::Compare(comparison)) => {
+            let date = match value.to_lowercase().as_str() {
+                "none" if comparison == Comparison::Eq => None,
+                _ => Some(
+                    NaiveDate::parse_from_str(&value, "%Y-%m-%d")
+                        .map_err(|_| format!("due must be a YYYY-MM-DD date, not '{}'", value))?,
+                ),
+            };
+            Condition::Due(comparison, date)
+        }
+        ("text" | "priority" | "completed" | "overdue" | "tag" | "due", op) => {
+            let symbol = match op {
+                Op::Compare(comparison) => comparison.symbol(),
+                Op::Contains => "~",
+            };
+            return Err(format!("'{}' can't be used with {}", symbol, field));
+        }
+        _ => return Err(format!("Unknown query field '{}'", field)),
+    };
+    Ok(condition)
+}
+
+fn compile(node: Node) -> Result<Query, String> {
+    Ok(match node {
+        Node::And(left, right) => Query::And(Box::new(compile(*left)?), Box::new(compile(*right)?)),
+        Node::Or(left, right) => Query::Or(Box::new(compile(*left)?), Box::new(compile(*right)?)),
+        Node::Not(inner) => Query::Not(Box::new(compile(*inner)?)),
+        Node::Term(term) => Query::Condition(compile_term(term)?),
+    })
+}
+
+/// How deeply `input` nests parentheses, not counting those in quotes.
+fn nesting(input: &str) -> usize {
+    let (mut depth, mut deepest, mut quoted, mut escaped) = (0usize, 0, false, false);
+    for c in input.chars() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if quoted => escaped = true,
+            '"' => quoted = !quoted,
+            '(' if !quoted => {
+                depth += 1;
+                deepest = deepest.max(depth);
+            }
+            ')' if !quoted => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+    }
+    deepest
+}
+
+fn tag_condition(value: &str) -> Result<Condition, String> {
+    let tag = value.trim_start_matches('#').to_lowercase();
+    if tag.is_empty() {
+        return Err("tag must not be empty".to_string());
+    }
+    Ok(Condition::Tag(tag))
+}
+
+impl Query {
+    /// The query `tag:<tag>`, for endpoints taking a tag parameter.
+    pub fn tag(tag: &str) -> Result<Self, String> {
+        tag_condition(tag).map(Query::Condition)
+    }
+
+    /// Both `self` and `other`.
+    pub fn and(self, other: Query) -> Self {
+        Query::And(Box::new(self), Box::new(other))
+    }
+
+    pub fn parse(input: &str) -> Result<Self, String> {
+        if input.len() > MAX_QUERY_LEN {
+            return Err(format!("q must be at most {} characters", MAX_QUERY_LEN));
+        }
+        if nesting(input) > MAX_NESTING {
+            return Err(format!(
+                "q can nest parentheses at most {} deep",
+                MAX_NESTING
+            ));
+        }
+        let mut parser = all_consuming(delimited(multispace0, or_expr, multispace0));
+        match parser(input) {
+            Ok((_, node)) => compile(node),
+            Err(nom::Err::Error(e) | nom::Err::Failure(e)) => {
+                let at = e.input.chars().take(20).collect::<String>();
+                if at.is_empty() {
+                    Err("Invalid query: unexpected end".to_string())
+                } else {
+                    Err(format!("Invalid query near '{}'", at))
+                }
+            }
+            Err(nom::Err::Incomplete(_)) => Err("Invalid query: unexpected end".to_string()),
+        }
+    }
+
+    /// Whether which todos match changes with the day, as `overdue:` does.
+    pub fn is_date_relative(&self) -> bool {
+        match self {
+            Query::And(left, right) | Query::Or(left, right) => {
+                left.is_date_relative() || right.is_date_relative()
+            }
+            Query::Not(inner) => inner.is_date_relative(),
+            Query::Condition(condition) => matches!(condition, Condition::Overdue(_)),
+        }
+    }
+
+    pub fn matches(&self, todo: &Todo, today: NaiveDate) -> bool {
+        match self {
+            Query::And(left, right) => left.matches(todo, today) && right.matches(todo, today),
+            Query::Or(left, right) => left.matches(todo, today) || right.matches(todo, today),
+            Query::Not(inner) => !inner.matches(todo, today),
+            Query::Condition(condition) => condition.matches(todo, today),
+        }
+    }
+}
+
+impl Condition {
+    fn matches(&self, todo: &Todo, today: NaiveDate) -> bool {
+        let due = || {
+            todo.due_date
+                .as_deref()
+                .and_then(|due| NaiveDate::parse_from_str(due, "%Y-%m-%d").ok())
+        };
+        match self {
+            Condition::Priority(priority) => todo.priority == *priority,
+            Condition::Completed(completed) => todo.completed == *completed,
+            Condition::Overdue(overdue) => {
+                let is_overdue = !todo.completed && due().is_some_and(|due| due < today);
+                is_overdue == *overdue
+            }
+            Condition::Tag(tag) => quick_add::parse(&todo.text, today).tags.contains(tag),
+            Condition::Text(text) => todo.text.to_lowercase().contains(text),
+            Condition::Due(comparison, date) => match (due(), date) {
+                (None, None) => true,
+                (Some(due), Some(date)) => match comparison {
+                    Comparison::Eq => due == *date,
+                    Comparison::Lt => due < *date,
+                    Comparison::Le => due <= *date,
+                    Comparison::Gt => due > *date,
+                    Comparison::Ge => due >= *date,
+                },
+                _ => false,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::todo_id;
+    use chrono::Utc;
+
+    fn todo(text: &str, priority: Priority, due: Option<&str>) -> Todo {
+        Todo {
+            id: todo_id(text),
+            text: text.to_string(),
+            priority,
+            completed: false,
+            due_date: due.map(str::to_string),
+            reminder_time: None,
+            recurrence: None,
+            recurrence_end: None,
+            remaining_occurrences: None,
+            estimate_minutes: None,
+            location: None,
+            color: None,
+            icon: None,
+            pinned: false,
+            rollover_count: 0,
+            rolled_over_to: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_parses_precedence_and_grouping() {
+        let query =
+            Query::parse("priority:high AND (tag:work OR overdue:true) AND text~\"report\"")
+                .unwrap();
+        let and = |l: Query, r: Query| Query::And(Box::new(l), Box::new(r));
+        let or = |l: Query, r: Query| Query::Or(Box::new(l), Box::new(r));
+        let expected = and(
+            and(
+                Query::Condition(Condition::Priority(Priority::High)),
+                or(
+                    Query::Condition(Condition::Tag("work".to_string())),
+                    Query::Condition(Condition::Overdue(true)),
+                ),
+            ),
+            Query::Condition(Condition::Text("report".to_string())),
+        );
+        assert_eq!(query, expected);
+        assert!(query.is_date_relative());
+        assert!(!Query::parse("tag:work due<2024-06-10")
+            .unwrap()
+            .is_date_relative());
+
+        // AND binds tighter than OR, and side by side means AND.
+        assert_eq!(
+            Query::parse("milk OR eggs bread").unwrap(),
+            or(
+                Query::Condition(Condition::Text("milk".to_string())),
+                and(
+                    Query::Condition(Condition::Text("eggs".to_string())),
+                    Query::Condition(Condition::Text("bread".to_string())),
+                ),
+            )
+        );
+        assert_eq!(
+            Query::parse("not completed:true").unwrap(),
+            Query::Not(Box::new(Query::Condition(Condition::Completed(true))))
+        );
+    }
+
+    #[test]
+    fn test_rejects_bad_queries() {
+        for bad in [
+            "",
+            "priority:urgent",
+            "colour:red",
+            "(tag:work",
+            "tag:work)",
+            "due<soon",
+            "priority~high",
+            "tag:work AND",
+        ] {
+            assert!(Query::parse(bad).is_err(), "{}", bad);
+        }
+        assert!(Query::parse(&"a ".repeat(MAX_QUERY_LEN)).is_err());
+        let deep = format!(
+            "{}a{}",
+            "(".repeat(MAX_NESTING + 1),
+            ")".repeat(MAX_NESTING + 1)
+        );
+        assert!(Query::parse(&deep).is_err());
+        assert!(Query::parse("\"((((\" OR ((a))").is_ok());
+    }
+
+    #[test]
+    fn test_matches_todos() {
+        let today = NaiveDate::from_ymd_opt(2024, 6, 10).unwrap();
+        let query =
+            Query::parse("priority:high AND (tag:work OR overdue:true) AND text~\"report\"")
+                .unwrap();
+        let report = todo("Quarterly report #work", Priority::High, None);
+        let late = todo("Expense report", Priority::High, Some("2024-06-01"));
+        let low = todo("Weekly report #work", Priority::Low, None);
+        let other = todo("Expense report", Priority::High, Some("2024-06-20"));
+        assert!(query.matches(&report, today));
+        assert!(query.matches(&late, today));
+        assert!(!query.matches(&low, today));
+        assert!(!query.matches(&other, today));
+
+        let due = Query::parse("due>=2024-06-10 OR due:none").unwrap();
+        assert!(due.matches(&report, today));
+        assert!(!due.matches(&late, today));
+        assert!(due.matches(&other, today));
+    }
+}
@@ -0,0 +1,160 @@
+use crate::dates::{parse_date, parse_time};
+use crate::models::{Priority, TodoCreate};
+use chrono::{NaiveDate, NaiveTime};
+use serde::Serialize;
+
+/// Longest date phrase `parse_date` understands, in words (`in 3 days`).
+const MAX_DATE_WORDS: usize = 3;
+
+/// Words dropped when they introduce a date or time: "due friday", "at 9am".
+const LEADING_WORDS: [&str; 4] = ["at", "on", "by", "due"];
+
+/// What `parse` read from a quick-add line.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct QuickAdd {
+    /// The line without the parsed date, time and priority. Hashtags stay
+    /// in the text, since todos have no separate tags.
+    pub text: String,
+    pub priority: Option<Priority>,
+    #[serde(rename = "dueDate")]
+    pub due_date: Option<NaiveDate>,
+    #[serde(rename = "reminderTime")]
+    pub reminder_time: Option<NaiveTime>,
+    pub tags: Vec<String>,
+}
+
+impl QuickAdd {
+    pub fn into_create(self) -> TodoCreate {
+        TodoCreate {
+            text: self.text,
+            priority: self.priority,
+            due_date: self
+                .due_date
+                .map(|date| date.format("%Y-%m-%d").to_string()),
+            reminder_time: self
+                .reminder_time
+                .map(|time| time.format("%H:%M").to_string()),
+            ..Default::default()
+        }
+    }
+}
+
+/// Reads a line like `Pay rent tomorrow 9am !high #finance`. The first date
+/// phrase, time and `!low`/`!medium`/`!high` marker are taken; anything
+/// else, including repeats, stays in the text.
+pub fn parse(line: &str, today: NaiveDate) -> QuickAdd {
+    let words: Vec<&str> = line.split_whitespace().collect();
+    let mut parsed = QuickAdd {
+        text: String::new(),
+        priority: None,
+        due_date: None,
+        reminder_time: None,
+        tags: Vec::new(),
+    };
+    let mut kept: Vec<&str> = Vec::new();
+    let mut i = 0;
+    while i < words.len() {
+        let word = words[i];
+        if let Some(tag) = word.strip_prefix('#').filter(|tag| !tag.is_empty()) {
+            parsed.tags.push(tag.to_lowercase());
+        }
+        if parsed.priority.is_none() {
+            if let Some(priority) = word.strip_prefix('!').and_then(priority) {
+                parsed.priority = Some(priority);
+                i += 1;
+                continue;
+            }
+        }
+        if parsed.reminder_time.is_none() {
+            if let Some(time) = parse_time(word) {
+                parsed.reminder_time = Some(time);
+                drop_leading_word(&mut kept);
+                i += 1;
+                continue;
+            }
+        }
+        if parsed.due_date.is_none() {
+            let longest = MAX_DATE_WORDS.min(words.len() - i);
+            let found = (1..=longest).rev().find_map(|len| {
+                parse_date(&words[i..i + len].join(" "), today).map(|date| (date, len))
+            });
+            if let Some((date, len)) = found {
+                parsed.due_date = Some(date);
+                drop_leading_word(&mut kept);
+                i += len;
+                continue;
+            }
+        }
+        kept.push(word);
+        i += 1;
+    }
+    parsed.text = kept.join(" ");
+    parsed
+}
+
+fn priority(name: &str) -> Option<Priority> {
+    match name.to_lowercase().as_str() {
+        "low" => Some(Priority::Low),
+        "medium" | "med" => Some(Priority::Medium),
+        "high" => Some(Priority::High),
+        _ => None,
+    }
+}
+
+fn drop_leading_word(kept: &mut Vec<&str>) {
+    if kept
+        .last()
+        .is_some_and(|word| LEADING_WORDS.contains(&word.to_lowercase().as_str()))
+    {
+        kept.pop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn today() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 6, 10).unwrap()
+    }
+
+    #[test]
+    fn test_parses_date_time_priority_and_tags() {
+        let parsed = parse("Pay rent tomorrow 9am !high #finance", today());
+        assert_eq!(
+            parsed,
+            QuickAdd {
+                text: "Pay rent #finance".to_string(),
+                priority: Some(Priority::High),
+                due_date: NaiveDate::from_ymd_opt(2024, 6, 11),
+                reminder_time: NaiveTime::from_hms_opt(9, 0, 0),
+                tags: vec!["finance".to_string()],
+            }
+        );
+
+        let create = parsed.into_create();
+        assert_eq!(create.due_date.as_deref(), Some("2024-06-11"));
+        assert_eq!(create.reminder_time.as_deref(), Some("09:00"));
+    }
+
+    #[test]
+    fn test_drops_words_introducing_date_and_time() {
+        let parsed = parse("Dentist on next friday at 2:30pm", today());
+        assert_eq!(parsed.text, "Dentist");
+        assert_eq!(parsed.due_date, NaiveDate::from_ymd_opt(2024, 6, 14));
+        assert_eq!(parsed.reminder_time, NaiveTime::from_hms_opt(14, 30, 0));
+
+        let parsed = parse("Submit report due in 3 days", today());
+        assert_eq!(parsed.text, "Submit report");
+        assert_eq!(parsed.due_date, NaiveDate::from_ymd_opt(2024, 6, 13));
+    }
+
+    #[test]
+    fn test_leaves_unparsed_words_alone() {
+        let parsed = parse("Buy 3 eggs !urgent #Shop #home today tomorrow", today());
+        assert_eq!(parsed.text, "Buy 3 eggs !urgent #Shop #home tomorrow");
+        assert_eq!(parsed.priority, None);
+        assert_eq!(parsed.due_date, Some(today()));
+        assert_eq!(parsed.tags, vec!["shop", "home"]);
+    }
+}
@@ -0,0 +1,411 @@
+use crate::events::EventType;
+use crate::models::{DayWorkload, HiddenState, Priority, Todo, TodoStats, WeekStart};
+use chrono::{Duration, NaiveDate};
+use std::collections::{BTreeMap, HashMap};
+
+/// Days covered by `TodoStats::workload`, starting today.
+const WORKLOAD_DAYS: i64 = 7;
+
+/// The parts of a todo the stats depend on. Kept per todo so its old
+/// contribution can be taken back out when it changes or is deleted.
+#[derive(Debug, Clone, PartialEq)]
+struct Entry {
+    priority: Priority,
+    completed: bool,
+    due: Option<NaiveDate>,
+    estimate_minutes: Option<u32>,
+    hidden: Option<HiddenState>,
+    pinned: bool,
+    rolled_over: bool,
+}
+
+impl Entry {
+    fn of(todo: &Todo) -> Self {
+        Entry {
+            priority: todo.priority.clone(),
+            completed: todo.completed,
+            due: todo
+                .due_date
+                .as_deref()
+                .and_then(|due| NaiveDate::parse_from_str(due, "%Y-%m-%d").ok()),
+            estimate_minutes: todo.estimate_minutes,
+            hidden: todo.hidden_state(),
+            pinned: todo.pinned,
+            rolled_over: todo.rollover_count > 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+struct Counters {
+    total: usize,
+    completed: usize,
+    pinned: usize,
+    rolled_over: usize,
+    low: usize,
+    medium: usize,
+    high: usize,
+    weight: u64,
+    completed_weight: u64,
+    /// Active todos per due date. Date buckets are relative to today, so
+    /// they are summed from this at read time.
+    active_due: BTreeMap<NaiveDate, usize>,
+    /// Estimated minutes of every active todo.
+    outstanding_minutes: u64,
+    /// Estimated minutes of active todos per due date.
+    active_due_minutes: BTreeMap<NaiveDate, u64>,
+}
+
+impl Counters {
+    fn apply(&mut self, entry: &Entry, add: bool) {
+        let step = |count: &mut usize| {
+            if add {
+                *count += 1
+            } else {
+                *count -= 1
+            }
+        };
+        let weight = entry.priority.weight() as u64;
+        step(&mut self.total);
+        if entry.pinned {
+            step(&mut self.pinned);
+        }
+        if entry.rolled_over {
+            step(&mut self.rolled_over);
+        }
+        match entry.priority {
+            Priority::Low => step(&mut self.low),
+            Priority::Medium => step(&mut self.medium),
+            Priority::High => step(&mut self.high),
+        }
+        if add {
+            self.weight += weight;
+        } else {
+            self.weight -= weight;
+        }
+        if entry.completed {
+            step(&mut self.completed);
+            if add {
+                self.completed_weight += weight;
+            } else {
+                self.completed_weight -= weight;
+            }
+        } else {
+            let minutes = u64::from(entry.estimate_minutes.unwrap_or(0));
+            if add {
+                self.outstanding_minutes += minutes;
+            } else {
+                self.outstanding_minutes -= minutes;
+            }
+            if let Some(due) = entry.due {
+                let count = self.active_due.entry(due).or_default();
+                step(count);
+                if *count == 0 {
+                    self.active_due.remove(&due);
+                }
+                if minutes > 0 {
+                    let scheduled = self.active_due_minutes.entry(due).or_default();
+                    if add {
+                        *scheduled += minutes;
+                    } else {
+                        *scheduled -= minutes;
+                    }
+                    if *scheduled == 0 {
+                        self.active_due_minutes.remove(&due);
+                    }
+                }
+            }
+        }
+    }
+
+    fn merge(&mut self, other: &Counters) {
+        self.total += other.total;
+        self.completed += other.completed;
+        self.pinned += other.pinned;
+        self.rolled_over += other.rolled_over;
+        self.low += other.low;
+        self.medium += other.medium;
+        self.high += other.high;
+        self.weight += other.weight;
+        self.completed_weight += other.completed_weight;
+        for (due, count) in &other.active_due {
+            *self.active_due.entry(*due).or_default() += count;
+        }
+        self.outstanding_minutes += other.outstanding_minutes;
+        for (due, minutes) in &other.active_due_minutes {
+            *self.active_due_minutes.entry(*due).or_default() += minutes;
+        }
+    }
+
+    fn due_between(&self, from: NaiveDate, to: NaiveDate) -> usize {
+        self.active_due
+            .range(from..=to)
+            .map(|(_, count)| count)
+            .sum()
+    }
+}
+
+/// Stats kept up to date as todos change, so reading them costs nothing
+/// proportional to the collection and never touches the store. Fed by
+/// `TodoService::record` alongside every event.
+#[derive(Debug, Default, PartialEq)]
+pub struct StatsReadModel {
+    entries: HashMap<String, Entry>,
+    visible: Counters,
+    /// Archived, trashed and deferred todos, only in the totals on request.
+    hidden: Counters,
+    archived: usize,
+    trashed: usize,
+    deferred: usize,
+}
+
+impl StatsReadModel {
+    /// Builds the model from scratch, e.g. for todos restored at startup.
+    pub fn from_todos(todos: &[Todo]) -> Self {
+        let mut model = StatsReadModel::default();
+        for todo in todos {
+            model.apply(EventType::Created, todo);
+        }
+        model
+    }
+
+    /// Folds in one mutation; `todo` is the state after it.
+    pub fn apply(&mut self, event_type: EventType, todo: &Todo) {
+        if let Some(old) = self.entries.remove(&todo.id.to_string()) {
+            self.count(&old, false);
+        }
+        if event_type != EventType::Deleted {
+            let entry = Entry::of(todo);
+            self.count(&entry, true);
+            self.entries.insert(todo.id.to_string(), entry);
+        }
+    }
+
+    fn count(&mut self, entry: &Entry, add: bool) {
+        let counters = match entry.hidden {
+            Some(state) => {
+                let count = match state {
+                    HiddenState::Archived => &mut self.archived,
+                    HiddenState::Trashed => &mut self.trashed,
+                    HiddenState::Deferred => &mut self.deferred,
+                };
+                if add {
+                    *count += 1
+                } else {
+                    *count -= 1
+                }
+                &mut self.hidden
+            }
+            None => &mut self.visible,
+        };
+        counters.apply(entry, add);
+    }
+
+    /// The stats as of `today`. Archived, trashed and deferred todos are
+    /// always counted on their own; `include_hidden` also counts them in
+    /// every other figure.
+    pub fn stats(
+        &self,
+        today: NaiveDate,
+        week_start: WeekStart,
+        include_hidden: bool,
+    ) -> TodoStats {
+        let merged;
+        let counters = if include_hidden {
+            let mut all = self.visible.clone();
+            all.merge(&self.hidden);
+            merged = all;
+            &merged
+        } else {
+            &self.visible
+        };
+
+        let rate = |part: f64, whole: f64| {
+            if whole > 0.0 {
+                (part / whole) * 100.0
+            } else {
+                0.0
+            }
+        };
+        let priority_breakdown = HashMap::from([
+            ("low".to_string(), counters.low),
+            ("medium".to_string(), counters.medium),
+            ("high".to_string(), counters.high),
+        ]);
+        let overdue_count = counters
+            .active_due
+            .range(..today)
+            .map(|(_, count)| count)
+            .sum();
+        let (week_first, week_last) = week_start.week_of(today);
+
+        TodoStats {
+            total: counters.total,
+            active: counters.total - counters.completed,
+            completed: counters.completed,
+            completion_rate: rate(counters.completed as f64, counters.total as f64),
+            weighted_completion_rate: rate(
+                counters.completed_weight as f64,
+                counters.weight as f64,
+            ),
+            priority_breakdown,
+            overdue_count,
+            due_today_count: counters.due_between(today, today),
+            upcoming_count: counters
+                .due_between(today + Duration::days(1), today + Duration::days(7)),
+            due_this_week_count: counters.due_between(week_first, week_last),
+            week_start,
+            estimated_outstanding_minutes: counters.outstanding_minutes,
+            workload: (0..WORKLOAD_DAYS)
+                .map(|offset| {
+                    let date = today + Duration::days(offset);
+                    DayWorkload {
+                        date,
+                        minutes: counters.active_due_minutes.get(&date).copied().unwrap_or(0),
+                        over_committed: false,
+                    }
+                })
+                .collect(),
+            daily_capacity_minutes: None,
+            over_committed_days: Vec::new(),
+            archived: self.archived,
+            trashed: self.trashed,
+            deferred: self.deferred,
+            pinned: counters.pinned,
+            rolled_over: counters.rolled_over,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::todo_id;
+    use chrono::Utc;
+
+    fn todo(id: &str, priority: Priority, completed: bool, due: Option<NaiveDate>) -> Todo {
+        Todo {
+            id: todo_id(id),
+            text: format!("Todo {}", id),
+            priority,
+            completed,
+            due_date: due.map(|due| due.format("%Y-%m-%d").to_string()),
+            reminder_time: None,
+            recurrence: None,
+            recurrence_end: None,
+            remaining_occurrences: None,
+            estimate_minutes: None,
+            location: None,
+            color: None,
+            icon: None,
+            pinned: false,
+            rollover_count: 0,
+            rolled_over_to: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_buckets_by_due_date() {
+        let today = NaiveDate::from_ymd_opt(2024, 6, 10).unwrap();
+        let model = StatsReadModel::from_todos(&[
+            todo("a", Priority::High, false, today.pred_opt()),
+            todo("b", Priority::Low, false, Some(today)),
+            todo(
+                "c",
+                Priority::Low,
+                false,
+                today.checked_add_days(chrono::Days::new(7)),
+            ),
+            todo(
+                "d",
+                Priority::Low,
+                false,
+                today.checked_add_days(chrono::Days::new(8)),
+            ),
+            todo("e", Priority::Medium, true, today.pred_opt()),
+        ]);
+
+        let stats = model.stats(today, WeekStart::Monday, false);
+        assert_eq!((stats.total, stats.active, stats.completed), (5, 4, 1));
+        assert_eq!(stats.overdue_count, 1);
+        assert_eq!(stats.due_today_count, 1);
+        assert_eq!(stats.upcoming_count, 1);
+        // 2024-06-10 is a Monday: "a" fell in the previous week, unless it
+        // starts on Sunday.
+        assert_eq!(stats.due_this_week_count, 1);
+        let sunday = model.stats(today, WeekStart::Sunday, false);
+        assert_eq!(sunday.due_this_week_count, 2);
+        assert_eq!(stats.priority_breakdown["low"], 3);
+        assert!((stats.weighted_completion_rate - 2.0 / 9.0 * 100.0).abs() < 0.01);
+
+        // The same counters read a day later move "b" into overdue.
+        let tomorrow = model.stats(today.succ_opt().unwrap(), WeekStart::Monday, false);
+        assert_eq!(tomorrow.overdue_count, 2);
+        assert_eq!(tomorrow.due_today_count, 0);
+    }
+
+    #[test]
+    fn test_apply_replaces_previous_contribution() {
+        let today = NaiveDate::from_ymd_opt(2024, 6, 10).unwrap();
+        let mut model = StatsReadModel::default();
+        model.apply(
+            EventType::Created,
+            &todo("a", Priority::High, false, Some(today)),
+        );
+        model.apply(EventType::Created, &todo("b", Priority::Low, false, None));
+        model.apply(
+            EventType::Completed,
+            &todo("a", Priority::High, true, Some(today)),
+        );
+        model.apply(
+            EventType::Updated,
+            &todo("b", Priority::Medium, false, Some(today)),
+        );
+        model.apply(
+            EventType::Deleted,
+            &todo("b", Priority::Medium, false, Some(today)),
+        );
+
+        assert_eq!(
+            model,
+            StatsReadModel::from_todos(&[todo("a", Priority::High, true, Some(today))])
+        );
+        assert!(model.visible.active_due.is_empty());
+    }
+
+    #[test]
+    fn test_workload_from_estimates() {
+        let today = NaiveDate::from_ymd_opt(2024, 6, 10).unwrap();
+        let estimated = |id: &str, completed: bool, due: Option<NaiveDate>, minutes: u32| Todo {
+            estimate_minutes: Some(minutes),
+            ..todo(id, Priority::Medium, completed, due)
+        };
+        let mut model = StatsReadModel::from_todos(&[
+            estimated("a", false, Some(today), 300),
+            estimated("b", false, Some(today), 240),
+            estimated("c", false, today.succ_opt(), 60),
+            estimated("d", false, None, 45),
+            estimated("e", true, Some(today), 600),
+        ]);
+
+        let stats = model
+            .stats(today, WeekStart::Monday, false)
+            .with_capacity(480);
+        assert_eq!(stats.estimated_outstanding_minutes, 645);
+        assert_eq!(stats.workload.len(), 7);
+        assert_eq!(stats.workload[0].minutes, 540);
+        assert_eq!(stats.workload[1].minutes, 60);
+        assert_eq!(stats.over_committed_days, vec![today]);
+        assert!(stats.workload[0].over_committed);
+
+        model.apply(EventType::Deleted, &estimated("b", false, Some(today), 240));
+        let stats = model
+            .stats(today, WeekStart::Monday, false)
+            .with_capacity(480);
+        assert_eq!(stats.estimated_outstanding_minutes, 405);
+        assert!(stats.over_committed_days.is_empty());
+        assert_eq!(stats.daily_capacity_minutes, Some(480));
+    }
+}
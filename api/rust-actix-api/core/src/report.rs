@@ -0,0 +1,301 @@
+use crate::events::{EventFilter, EventType};
+use crate::models::{Priority, WeekStart};
+use crate::quick_add;
+use crate::service::TodoService;
+use chrono::{Datelike, Days, Months, NaiveDate};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::str::FromStr;
+
+/// Query of `GET /api/todos/stats/report`.
+#[derive(Debug, Default, Deserialize)]
+pub struct ReportQuery {
+    /// `csv` (the default) or `prometheus`.
+    pub format: Option<String>,
+    /// `month` (the default) or `week`.
+    pub period: Option<String>,
+    /// A day in the period to report on; defaults to today.
+    pub date: Option<NaiveDate>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ReportFormat {
+    /// Sections of comma-separated rows, for spreadsheets.
+    #[default]
+    Csv,
+    /// The Prometheus text format, for node_exporter's textfile collector.
+    Prometheus,
+}
+
+impl FromStr for ReportFormat {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_lowercase().as_str() {
+            "csv" => Ok(ReportFormat::Csv),
+            "prometheus" => Ok(ReportFormat::Prometheus),
+            other => Err(format!(
+                "Invalid report format '{}': expected csv or prometheus",
+                other
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ReportPeriod {
+    Week,
+    #[default]
+    Month,
+}
+
+impl FromStr for ReportPeriod {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_lowercase().as_str() {
+            "week" => Ok(ReportPeriod::Week),
+            "month" => Ok(ReportPeriod::Month),
+            other => Err(format!(
+                "Invalid report period '{}': expected week or month",
+                other
+            )),
+        }
+    }
+}
+
+impl ReportPeriod {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReportPeriod::Week => "week",
+            ReportPeriod::Month => "month",
+        }
+    }
+
+    /// The first and last day of the period `date` falls in.
+    pub fn range(&self, date: NaiveDate, week_start: WeekStart) -> (NaiveDate, NaiveDate) {
+        match self {
+            ReportPeriod::Week => week_start.week_of(date),
+            ReportPeriod::Month => {
+                let first = date.with_day(1).unwrap_or(date);
+                let last = first
+                    .checked_add_months(Months::new(1))
+                    .and_then(|next| next.checked_sub_days(Days::new(1)))
+                    .unwrap_or(date);
+                (first, last)
+            }
+        }
+    }
+}
+
+/// Completions over a period, counted from the history: a todo completed,
+/// reopened and completed again counts twice. Priority and tags are as the
+/// todo was when completed. History already pruned is not counted.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StatsReport {
+    pub period: ReportPeriod,
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    /// Every day of the period, days without completions included.
+    pub per_day: BTreeMap<NaiveDate, usize>,
+    pub by_priority: BTreeMap<String, usize>,
+    pub by_tag: BTreeMap<String, usize>,
+}
+
+impl StatsReport {
+    pub fn completed(&self) -> usize {
+        self.per_day.values().sum()
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("date,completed\n");
+        for (date, count) in &self.per_day {
+            let _ = writeln!(csv, "{},{}", date, count);
+        }
+        csv.push_str("\npriority,completed\n");
+        for (priority, count) in &self.by_priority {
+            let _ = writeln!(csv, "{},{}", priority, count);
+        }
+        csv.push_str("\ntag,completed\n");
+        for (tag, count) in &self.by_tag {
+            let _ = writeln!(csv, "{},{}", csv_field(tag), count);
+        }
+        csv
+    }
+
+    pub fn to_prometheus(&self) -> String {
+        let period = format!(
+            "period=\"{}\",from=\"{}\",to=\"{}\"",
+            self.period.as_str(),
+            self.from,
+            self.to
+        );
+        let mut text = String::new();
+        let mut family = |name: &str, help: &str, samples: Vec<(String, usize)>| {
+            let _ = writeln!(text, "# HELP {} {}", name, help);
+            let _ = writeln!(text, "# TYPE {} gauge", name);
+            for (labels, value) in samples {
+                let _ = writeln!(text, "{}{{{}{}}} {}", name, period, labels, value);
+            }
+        };
+        family(
+            "spicy_todo_report_completed",
+            "Todos completed in the period.",
+            vec![(String::new(), self.completed())],
+        );
+        family(
+            "spicy_todo_report_completed_by_day",
+            "Todos completed per day of the period.",
+            self.per_day
+                .iter()
+                .map(|(date, count)| (format!(",date=\"{}\"", date), *count))
+                .collect(),
+        );
+        family(
+            "spicy_todo_report_completed_by_priority",
+            "Todos completed in the period per priority.",
+            self.by_priority
+                .iter()
+                .map(|(priority, count)| (format!(",priority=\"{}\"", priority), *count))
+                .collect(),
+        );
+        family(
+            "spicy_todo_report_completed_by_tag",
+            "Todos completed in the period per tag.",
+            self.by_tag
+                .iter()
+                .map(|(tag, count)| (format!(",tag=\"{}\"", label_value(tag)), *count))
+                .collect(),
+        );
+        text
+    }
+}
+
+/// Quotes a CSV field when it needs it.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Escapes a Prometheus label value.
+fn label_value(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+impl TodoService {
+    /// The completions of the `period` that `date` falls in, by UTC day.
+    pub fn stats_report(
+        &self,
+        period: ReportPeriod,
+        date: NaiveDate,
+        week_start: WeekStart,
+    ) -> StatsReport {
+        let (from, to) = period.range(date, week_start);
+        let mut report = StatsReport {
+            period,
+            from,
+            to,
+            per_day: from
+                .iter_days()
+                .take_while(|day| *day <= to)
+                .map(|day| (day, 0))
+                .collect(),
+            by_priority: ["low", "medium", "high"]
+                .into_iter()
+                .map(|priority| (priority.to_string(), 0))
+                .collect(),
+            by_tag: BTreeMap::new(),
+        };
+        let filter = EventFilter {
+            event_types: vec![EventType::Completed],
+            ..Default::default()
+        };
+        for event in self.events().search(&filter) {
+            let day = event.timestamp.date_naive();
+            let Some(count) = report.per_day.get_mut(&day) else {
+                continue;
+            };
+            *count += 1;
+            let priority = match event.todo.priority {
+                Priority::Low => "low",
+                Priority::Medium => "medium",
+                Priority::High => "high",
+            };
+            *report.by_priority.entry(priority.to_string()).or_default() += 1;
+            for tag in quick_add::parse(&event.todo.text, day).tags {
+                *report.by_tag.entry(tag).or_default() += 1;
+            }
+        }
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::TodoCreate;
+    use chrono::Utc;
+
+    fn date(value: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(value, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn test_periods() {
+        let day = date("2024-02-14");
+        assert_eq!(
+            ReportPeriod::Month.range(day, WeekStart::Monday),
+            (date("2024-02-01"), date("2024-02-29"))
+        );
+        assert_eq!(
+            ReportPeriod::Week.range(day, WeekStart::Sunday),
+            (date("2024-02-11"), date("2024-02-17"))
+        );
+        assert!("year".parse::<ReportPeriod>().is_err());
+        assert_eq!("CSV".parse(), Ok(ReportFormat::Csv));
+    }
+
+    #[test]
+    fn test_counts_completions_by_day_priority_and_tag() {
+        let service = TodoService::new_empty();
+        for (text, priority) in [
+            ("Send invoices #work", Priority::High),
+            ("Review \"Q3\" #work #finance", Priority::Low),
+            ("Water the plants", Priority::Low),
+        ] {
+            let todo = service.create(TodoCreate {
+                text: text.to_string(),
+                priority: Some(priority),
+                ..Default::default()
+            });
+            service.toggle(todo.id);
+        }
+        let today = Utc::now().date_naive();
+
+        let report = service.stats_report(ReportPeriod::Week, today, WeekStart::Monday);
+        assert_eq!(report.per_day.len(), 7);
+        assert_eq!(report.per_day[&today], 3);
+        assert_eq!(report.by_priority["low"], 2);
+        assert_eq!(report.by_priority["medium"], 0);
+        assert_eq!(report.by_tag["work"], 2);
+
+        let csv = report.to_csv();
+        assert!(csv.starts_with("date,completed\n"));
+        assert!(csv.contains(&format!("\n{},3\n", today)));
+        assert!(csv.ends_with("tag,completed\nfinance,1\nwork,2\n"));
+        let text = report.to_prometheus();
+        assert!(text.contains("# TYPE spicy_todo_report_completed gauge\n"));
+        assert!(text.contains("priority=\"high\"} 1\n"));
+
+        let earlier = today - Days::new(40);
+        let report = service.stats_report(ReportPeriod::Month, earlier, WeekStart::Monday);
+        assert_eq!(report.completed(), 0);
+    }
+}
@@ -0,0 +1,382 @@
+use crate::deadline::Deadline;
+use crate::events::EventType;
+use crate::models::Todo;
+use crate::service::TodoService;
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use uuid::Uuid;
+
+/// What happens to a recurring todo whose occurrence passed unfinished.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RolloverMode {
+    /// Move it to the current occurrence as if nothing was missed.
+    #[default]
+    CarryOver,
+    /// Move it to the current occurrence and record every skipped one as
+    /// missed.
+    MarkMissed,
+}
+
+impl FromStr for RolloverMode {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_lowercase().as_str() {
+            "carry-over" => Ok(RolloverMode::CarryOver),
+            "mark-missed" => Ok(RolloverMode::MarkMissed),
+            other => Err(format!(
+                "Invalid rollover mode '{}': expected carry-over or mark-missed",
+                other
+            )),
+        }
+    }
+}
+
+/// What the opt-in overdue rollover does with a one-off todo whose due
+/// date passed unfinished.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverdueRollover {
+    /// Move its due date to today.
+    Move,
+    /// Leave it as a record of the missed day and carry a copy due today;
+    /// the original points at the copy in `rolledOverTo`.
+    Copy,
+}
+
+impl OverdueRollover {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OverdueRollover::Move => "move",
+            OverdueRollover::Copy => "copy",
+        }
+    }
+}
+
+impl FromStr for OverdueRollover {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_lowercase().as_str() {
+            "move" => Ok(OverdueRollover::Move),
+            "copy" => Ok(OverdueRollover::Copy),
+            other => Err(format!(
+                "Invalid overdue rollover '{}': expected move or copy",
+                other
+            )),
+        }
+    }
+}
+
+/// An occurrence of a recurring todo that passed without being completed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MissedOccurrence {
+    #[serde(rename = "todoId")]
+    pub todo_id: String,
+    #[serde(rename = "dueDate")]
+    pub due_date: NaiveDate,
+    #[serde(rename = "recordedAt")]
+    pub recorded_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Default, PartialEq, Serialize)]
+pub struct RolloverReport {
+    /// Todos moved to a new due date.
+    pub rolled: usize,
+    /// Occurrences recorded as missed; always 0 when carrying over.
+    pub missed: usize,
+}
+
+impl TodoService {
+    /// Moves every incomplete recurring todo due before `today` to its first
+    /// occurrence on or after `today`. A series whose end condition runs out
+    /// first stops at its last occurrence and stays overdue. Todos without a
+    /// parseable due date are left alone.
+    pub fn roll_over(&self, today: NaiveDate, mode: RolloverMode) -> RolloverReport {
+        let mut report = RolloverReport::default();
+        let guard = self
+            .write_lock(&Deadline::unbounded())
+            .unwrap_or_else(|e| unreachable!("unbounded deadline exceeded: {}", e));
+        let now = Utc::now();
+        let mut missed = Vec::new();
+        for todo in self.store().all() {
+            let Some(mut occurrence) = overdue_occurrence(&todo, today) else {
+                continue;
+            };
+            let mut series = todo.clone();
+            let mut skipped = Vec::new();
+            while occurrence < today {
+                let Some(next) = series.advance() else { break };
+                skipped.push(occurrence);
+                occurrence = next;
+            }
+            if skipped.is_empty() {
+                continue;
+            }
+            let rolled = self.store().update(todo.id, &mut |todo| {
+                todo.due_date = series.due_date.clone();
+                todo.remaining_occurrences = series.remaining_occurrences;
+                todo.updated_at = now;
+            });
+            let Some(rolled) = rolled else { continue };
+            self.record(EventType::Updated, &rolled);
+            report.rolled += 1;
+            if mode == RolloverMode::MarkMissed {
+                report.missed += skipped.len();
+                missed.extend(skipped.into_iter().map(|due_date| MissedOccurrence {
+                    todo_id: rolled.id.to_string(),
+                    due_date,
+                    recorded_at: now,
+                }));
+            }
+        }
+        self.missed_log().extend(missed);
+        drop(guard);
+        if report.rolled > 0 {
+            self.bump_version();
+        }
+        report
+    }
+
+    /// Carries every incomplete one-off todo due before `today` over to
+    /// `today`, counting the rollover in its `rolloverCount`. Recurring
+    /// todos are left to `roll_over`, and a todo already copied is not
+    /// copied again, so running twice a day changes nothing. Returns how
+    /// many todos were carried over.
+    pub fn roll_over_overdue(&self, today: NaiveDate, mode: OverdueRollover) -> usize {
+        let guard = self
+            .write_lock(&Deadline::unbounded())
+            .unwrap_or_else(|e| unreachable!("unbounded deadline exceeded: {}", e));
+        let now = Utc::now();
+        let due_today = today.format("%Y-%m-%d").to_string();
+        let mut rolled = 0;
+        for todo in self.store().all() {
+            if todo.recurrence.is_some() || todo.rolled_over_to.is_some() {
+                continue;
+            }
+            if overdue_date(&todo, today).is_none() {
+                continue;
+            }
+            let carried = match mode {
+                OverdueRollover::Move => self.store().update(todo.id, &mut |todo| {
+                    todo.due_date = Some(due_today.clone());
+                    todo.rollover_count += 1;
+                    todo.updated_at = now;
+                }),
+                OverdueRollover::Copy => {
+                    let copy = Todo {
+                        id: Uuid::new_v4(),
+                        due_date: Some(due_today.clone()),
+                        rollover_count: todo.rollover_count + 1,
+                        created_at: now,
+                        updated_at: now,
+                        ..todo.clone()
+                    };
+                    self.store().insert(copy.clone());
+                    self.record(EventType::Created, &copy);
+                    self.store().update(todo.id, &mut |todo| {
+                        todo.rolled_over_to = Some(copy.id.to_string());
+                        todo.updated_at = now;
+                    })
+                }
+            };
+            if let Some(carried) = carried {
+                self.record(EventType::Updated, &carried);
+                rolled += 1;
+            }
+        }
+        drop(guard);
+        if rolled > 0 {
+            self.bump_version();
+        }
+        rolled
+    }
+
+    /// Recorded missed occurrences of `todo_id`, oldest first.
+    pub fn missed_occurrences(&self, todo_id: Uuid) -> Vec<MissedOccurrence> {
+        let todo_id = todo_id.to_string();
+        self.missed_log()
+            .iter()
+            .filter(|missed| missed.todo_id == todo_id)
+            .cloned()
+            .collect()
+    }
+}
+
+/// The due date of `todo` if it is a recurring todo whose current
+/// occurrence passed unfinished.
+fn overdue_occurrence(todo: &Todo, today: NaiveDate) -> Option<NaiveDate> {
+    todo.recurrence?;
+    overdue_date(todo, today)
+}
+
+/// The due date of `todo` if it passed unfinished.
+fn overdue_date(todo: &Todo, today: NaiveDate) -> Option<NaiveDate> {
+    if todo.completed {
+        return None;
+    }
+    let due = NaiveDate::parse_from_str(todo.due_date.as_deref()?, "%Y-%m-%d").ok()?;
+    (due < today).then_some(due)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{todo_id, Recurrence, RecurrenceEnd, TodoCreate};
+
+    fn date(value: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(value, "%Y-%m-%d").unwrap()
+    }
+
+    fn create(
+        service: &TodoService,
+        due: &str,
+        recurrence: Option<Recurrence>,
+        completed: bool,
+    ) -> Uuid {
+        service
+            .create(TodoCreate {
+                text: format!("Due {}", due),
+                completed: Some(completed),
+                due_date: Some(due.to_string()),
+                recurrence,
+                ..Default::default()
+            })
+            .id
+    }
+
+    #[test]
+    fn test_mode_parsing() {
+        assert_eq!("carry-over".parse(), Ok(RolloverMode::CarryOver));
+        assert_eq!("Mark-Missed".parse(), Ok(RolloverMode::MarkMissed));
+        assert!("skip".parse::<RolloverMode>().is_err());
+    }
+
+    #[test]
+    fn test_carry_over_moves_to_current_occurrence() {
+        let service = TodoService::new_empty();
+        let daily = create(&service, "2024-06-07", Some(Recurrence::Daily), false);
+        let weekly = create(&service, "2024-06-03", Some(Recurrence::Weekly), false);
+        let monthly = create(&service, "2024-01-31", Some(Recurrence::Monthly), false);
+        let done = create(&service, "2024-06-07", Some(Recurrence::Daily), true);
+        let one_off = create(&service, "2024-06-07", None, false);
+        let current = create(&service, "2024-06-10", Some(Recurrence::Daily), false);
+
+        let report = service.roll_over(date("2024-06-10"), RolloverMode::CarryOver);
+        assert_eq!(
+            report,
+            RolloverReport {
+                rolled: 3,
+                missed: 0
+            }
+        );
+
+        let due = |id: Uuid| service.get_by_id(id).unwrap().due_date.unwrap();
+        assert_eq!(due(daily), "2024-06-10");
+        assert_eq!(due(weekly), "2024-06-10");
+        assert_eq!(due(monthly), "2024-06-29");
+        assert_eq!(due(done), "2024-06-07");
+        assert_eq!(due(one_off), "2024-06-07");
+        assert_eq!(due(current), "2024-06-10");
+        assert!(service.missed_occurrences(daily).is_empty());
+    }
+
+    #[test]
+    fn test_mark_missed_records_each_skipped_occurrence() {
+        let service = TodoService::new_empty();
+        let daily = create(&service, "2024-06-07", Some(Recurrence::Daily), false);
+        let version = service.collection_version().version;
+
+        let report = service.roll_over(date("2024-06-10"), RolloverMode::MarkMissed);
+        assert_eq!(
+            report,
+            RolloverReport {
+                rolled: 1,
+                missed: 3
+            }
+        );
+        let missed: Vec<NaiveDate> = service
+            .missed_occurrences(daily)
+            .into_iter()
+            .map(|missed| missed.due_date)
+            .collect();
+        assert_eq!(
+            missed,
+            vec![date("2024-06-07"), date("2024-06-08"), date("2024-06-09")]
+        );
+        assert_eq!(service.collection_version().version, version + 1);
+
+        // Nothing left to roll on the same day.
+        assert_eq!(
+            service.roll_over(date("2024-06-10"), RolloverMode::MarkMissed),
+            RolloverReport::default()
+        );
+        assert_eq!(service.collection_version().version, version + 1);
+    }
+
+    #[test]
+    fn test_series_stops_at_end_condition() {
+        let service = TodoService::new_empty();
+        let todo = service.create(TodoCreate {
+            text: "Take antibiotics".to_string(),
+            due_date: Some("2024-06-07".to_string()),
+            recurrence: Some(Recurrence::Daily),
+            recurrence_end: Some(RecurrenceEnd::AfterOccurrences(3)),
+            ..Default::default()
+        });
+        assert_eq!(todo.remaining_occurrences, Some(2));
+
+        let report = service.roll_over(date("2024-06-12"), RolloverMode::MarkMissed);
+        assert_eq!(
+            report,
+            RolloverReport {
+                rolled: 1,
+                missed: 2
+            }
+        );
+        let rolled = service.get_by_id(todo.id).unwrap();
+        assert_eq!(rolled.due_date.as_deref(), Some("2024-06-09"));
+        assert_eq!(rolled.remaining_occurrences, Some(0));
+
+        // The last occurrence stays overdue instead of rolling again.
+        assert_eq!(
+            service.roll_over(date("2024-06-13"), RolloverMode::MarkMissed),
+            RolloverReport::default()
+        );
+    }
+
+    #[test]
+    fn test_overdue_rollover_moves_or_copies_one_off_todos() {
+        let service = TodoService::new_empty();
+        let late = create(&service, "2024-06-07", None, false);
+        let done = create(&service, "2024-06-07", None, true);
+        let daily = create(&service, "2024-06-07", Some(Recurrence::Daily), false);
+        let today = date("2024-06-10");
+
+        assert_eq!(service.roll_over_overdue(today, OverdueRollover::Move), 1);
+        let moved = service.get_by_id(late).unwrap();
+        assert_eq!(moved.due_date.as_deref(), Some("2024-06-10"));
+        assert_eq!(moved.rollover_count, 1);
+        assert_eq!(service.get_by_id(done).unwrap().rollover_count, 0);
+        assert_eq!(service.get_by_id(daily).unwrap().rollover_count, 0);
+
+        let tomorrow = date("2024-06-11");
+        assert_eq!(
+            service.roll_over_overdue(tomorrow, OverdueRollover::Copy),
+            1
+        );
+        assert_eq!(
+            service.roll_over_overdue(tomorrow, OverdueRollover::Copy),
+            0
+        );
+        let original = service.get_by_id(late).unwrap();
+        assert_eq!(original.due_date.as_deref(), Some("2024-06-10"));
+        let copy = service
+            .get_by_id(todo_id(&original.rolled_over_to.unwrap()))
+            .unwrap();
+        assert_eq!(copy.due_date.as_deref(), Some("2024-06-11"));
+        assert_eq!(copy.rollover_count, 2);
+        assert_eq!(copy.text, original.text);
+        assert_eq!(service.get_stats().rolled_over, 2);
+    }
+}
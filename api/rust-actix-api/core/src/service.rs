@@ -0,0 +1,1082 @@
+use crate::cascade::CascadePolicy;
+use crate::deadline::{Deadline, DeadlineExceeded};
+use crate::events::{EventLog, EventType};
+use crate::geo::{self, NearbyTodo};
+use crate::models::{Priority, Todo, TodoCreate, TodoStats, TodoUpdate, WeekStart};
+use crate::query::Query;
+use crate::read_model::StatsReadModel;
+use crate::rollover::MissedOccurrence;
+use crate::storage::{InMemoryStore, LockStats, LockStatsSnapshot, TodoStore};
+use serde::Serialize;
+use chrono::{DateTime, NaiveDate, Utc};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+use uuid::Uuid;
+
+/// Monotonic version of the todo collection, bumped on every mutation.
+/// Used to answer conditional GETs without re-serializing the list.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CollectionVersion {
+    pub version: u64,
+    pub last_modified: DateTime<Utc>,
+}
+
+impl CollectionVersion {
+    /// Builds an opaque entity tag for a view of the collection. The
+    /// `variant` distinguishes differently filtered views of the same version.
+    pub fn etag<H: Hash>(&self, variant: &H) -> String {
+        let mut hasher = DefaultHasher::new();
+        self.version.hash(&mut hasher);
+        self.last_modified.timestamp_nanos_opt().hash(&mut hasher);
+        variant.hash(&mut hasher);
+        format!("{:016x}", hasher.finish())
+    }
+}
+
+pub struct TodoService {
+    store: Box<dyn TodoStore>,
+    /// Serializes mutations so the store, the event log and the version
+    /// always agree on ordering.
+    write_lock: Mutex<()>,
+    write_lock_stats: LockStats,
+    version: Mutex<CollectionVersion>,
+    events: EventLog,
+    /// Maintained by `record`, so reading stats never scans the store.
+    stats: Mutex<StatsReadModel>,
+    /// Occurrences of recurring todos recorded as missed by `roll_over`.
+    missed: Mutex<Vec<MissedOccurrence>>,
+}
+
+/// Wait statistics for the locks on the request path.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct LockDiagnostics {
+    /// The storage backend's own lock, if it has one.
+    pub store: Option<LockStatsSnapshot>,
+    /// The service-level lock serializing mutations.
+    pub writes: LockStatsSnapshot,
+}
+
+impl Default for TodoService {
+    fn default() -> Self {
+        Self::new_empty()
+    }
+}
+
+impl TodoService {
+    /// An in-memory instance pre-populated with the default fixtures.
+    pub fn new() -> Self {
+        let service = TodoService::new_empty();
+        service.seed(crate::fixtures::builtin("default").unwrap_or_default());
+        service
+    }
+
+    pub fn new_empty() -> Self {
+        Self::from_parts(
+            Box::new(InMemoryStore::new()),
+            EventLog::new(),
+            StatsReadModel::default(),
+        )
+    }
+
+    /// Builds a service on top of a custom storage backend.
+    pub fn with_store(store: Box<dyn TodoStore>) -> Self {
+        let stats = StatsReadModel::from_todos(&store.all());
+        Self::from_parts(store, EventLog::new(), stats)
+    }
+
+    /// Builds a service whose event log may already hold history, e.g. one
+    /// restored from disk. The version starts at the latest event; `stats`
+    /// must describe what `store` already holds.
+    pub(crate) fn from_parts(
+        store: Box<dyn TodoStore>,
+        events: EventLog,
+        stats: StatsReadModel,
+    ) -> Self {
+        let version = CollectionVersion {
+            version: events.latest_sequence(),
+            last_modified: events.latest_timestamp().unwrap_or_else(Utc::now),
+        };
+        TodoService {
+            store,
+            write_lock: Mutex::new(()),
+            write_lock_stats: LockStats::default(),
+            version: Mutex::new(version),
+            events,
+            stats: Mutex::new(stats),
+            missed: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub fn events(&self) -> &EventLog {
+        &self.events
+    }
+
+    /// Verifies the storage backend is usable within `timeout`.
+    pub fn check_storage(&self, timeout: Duration) -> Result<(), String> {
+        self.store.ping(timeout)
+    }
+
+    pub fn lock_diagnostics(&self) -> LockDiagnostics {
+        LockDiagnostics {
+            store: self.store.lock_stats(),
+            writes: self.write_lock_stats.snapshot(),
+        }
+    }
+
+    pub fn collection_version(&self) -> CollectionVersion {
+        *self.version.lock().unwrap()
+    }
+
+    pub(crate) fn store(&self) -> &dyn TodoStore {
+        self.store.as_ref()
+    }
+
+    /// Appends the event for a mutation and folds it into the stats read
+    /// model. Every mutation goes through here, under the write lock.
+    pub(crate) fn record(&self, event_type: EventType, todo: &Todo) {
+        self.events.append(event_type, todo);
+        self.stats.lock().unwrap().apply(event_type, todo);
+    }
+
+    pub(crate) fn missed_log(&self) -> MutexGuard<'_, Vec<MissedOccurrence>> {
+        self.missed.lock().unwrap()
+    }
+
+    pub(crate) fn bump_version(&self) {
+        let mut version = self.version.lock().unwrap();
+        version.version += 1;
+        version.last_modified = Utc::now();
+    }
+
+    pub fn get_all(&self, filter: Option<String>, search: Option<String>, priority: Option<String>) -> Vec<Todo> {
+        unbounded(self.get_all_until(filter, search, priority, &Deadline::unbounded()))
+    }
+
+    pub fn get_all_until(
+        &self,
+        filter: Option<String>,
+        search: Option<String>,
+        priority: Option<String>,
+        deadline: &Deadline,
+    ) -> Result<Vec<Todo>, DeadlineExceeded> {
+        let todos = self.get_all_shared_until(filter, search, priority, deadline)?;
+        Ok(todos.into_iter().map(Arc::unwrap_or_clone).collect())
+    }
+
+    /// `get_all_until`, sharing the todos with the store instead of copying
+    /// each one, for read paths that only serialize them.
+    pub fn get_all_shared_until(
+        &self,
+        filter: Option<String>,
+        search: Option<String>,
+        priority: Option<String>,
+        deadline: &Deadline,
+    ) -> Result<Vec<Arc<Todo>>, DeadlineExceeded> {
+        let mut filtered = self.store.all_shared_until(deadline)?;
+
+        // Apply filters
+        if let Some(f) = filter {
+            filtered = match f.as_str() {
+                "active" => filtered.into_iter().filter(|t| !t.completed).collect(),
+                "completed" => filtered.into_iter().filter(|t| t.completed).collect(),
+                _ => filtered,
+            };
+        }
+
+        if let Some(s) = search {
+            let search_lower = s.to_lowercase();
+            filtered.retain(|t| t.text.to_lowercase().contains(&search_lower));
+        }
+
+        if let Some(p) = priority {
+            let priority_lower = p.to_lowercase();
+            filtered.retain(|t| match &t.priority {
+                Priority::Low => priority_lower == "low",
+                Priority::Medium => priority_lower == "medium",
+                Priority::High => priority_lower == "high",
+            });
+        }
+
+        // Pinned first; the sort is stable, so this is all it changes.
+        filtered.sort_by_key(|t| !t.pinned);
+
+        deadline.check("filter")?;
+        deadline.complete("filter");
+        Ok(filtered)
+    }
+
+    /// `get_all_until`, narrowed further by a `q` expression. `today` decides
+    /// what is overdue.
+    pub fn get_matching_until(
+        &self,
+        filter: Option<String>,
+        search: Option<String>,
+        priority: Option<String>,
+        query: &Query,
+        today: NaiveDate,
+        deadline: &Deadline,
+    ) -> Result<Vec<Todo>, DeadlineExceeded> {
+        let todos =
+            self.get_matching_shared_until(filter, search, priority, query, today, deadline)?;
+        Ok(todos.into_iter().map(Arc::unwrap_or_clone).collect())
+    }
+
+    /// `get_matching_until`, sharing the todos like `get_all_shared_until`.
+    pub fn get_matching_shared_until(
+        &self,
+        filter: Option<String>,
+        search: Option<String>,
+        priority: Option<String>,
+        query: &Query,
+        today: NaiveDate,
+        deadline: &Deadline,
+    ) -> Result<Vec<Arc<Todo>>, DeadlineExceeded> {
+        let mut todos = self.get_all_shared_until(filter, search, priority, deadline)?;
+        todos.retain(|todo| query.matches(todo, today));
+        deadline.check("query")?;
+        deadline.complete("query");
+        Ok(todos)
+    }
+
+    /// Active todos within `radius_meters` of `(lat, lng)`, nearest first.
+    /// Todos without coordinates are never nearby.
+    pub fn nearby(&self, lat: f64, lng: f64, radius_meters: f64) -> Vec<NearbyTodo> {
+        unbounded(self.nearby_until(lat, lng, radius_meters, &Deadline::unbounded()))
+    }
+
+    pub fn nearby_until(
+        &self,
+        lat: f64,
+        lng: f64,
+        radius_meters: f64,
+        deadline: &Deadline,
+    ) -> Result<Vec<NearbyTodo>, DeadlineExceeded> {
+        let active: Vec<Todo> = self
+            .store
+            .all_until(deadline)?
+            .into_iter()
+            .filter(|todo| !todo.completed && todo.hidden_state().is_none())
+            .collect();
+        let nearby = geo::within(active, (lat, lng), radius_meters);
+        deadline.check("filter")?;
+        deadline.complete("filter");
+        Ok(nearby)
+    }
+
+    pub fn get_by_id(&self, id: Uuid) -> Option<Todo> {
+        self.store.get(id)
+    }
+
+    pub fn get_by_id_until(
+        &self,
+        id: Uuid,
+        deadline: &Deadline,
+    ) -> Result<Option<Todo>, DeadlineExceeded> {
+        self.store.get_until(id, deadline)
+    }
+
+    /// Ids of every todo, sorted, for exports that read the todos in pages
+    /// with `get_many` rather than all at once.
+    pub fn todo_ids(&self) -> Vec<Uuid> {
+        let mut ids = self.store.ids();
+        ids.sort_unstable();
+        ids
+    }
+
+    /// The todos with `ids` that still exist, in the order of `ids`.
+    pub fn get_many(&self, ids: &[Uuid]) -> Vec<Todo> {
+        self.store.get_many(ids)
+    }
+
+    pub fn create(&self, input: TodoCreate) -> Todo {
+        unbounded(self.create_until(input, &Deadline::unbounded()))
+    }
+
+    /// Mutations only give up before they take effect: once the write lock
+    /// is held the change is applied regardless of the remaining budget.
+    pub fn create_until(
+        &self,
+        input: TodoCreate,
+        deadline: &Deadline,
+    ) -> Result<Todo, DeadlineExceeded> {
+        let now = Utc::now();
+        let mut todo = Todo {
+            id: Uuid::new_v4(),
+            text: input.text,
+            priority: input.priority.unwrap_or_default(),
+            completed: input.completed.unwrap_or(false),
+            due_date: input.due_date,
+            reminder_time: input.reminder_time,
+            recurrence: input.recurrence,
+            recurrence_end: input.recurrence_end,
+            remaining_occurrences: None,
+            estimate_minutes: input.estimate_minutes,
+            location: input.location,
+            color: input.color,
+            icon: input.icon,
+            pinned: input.pinned.unwrap_or(false),
+            rollover_count: 0,
+            rolled_over_to: None,
+            created_at: now,
+            updated_at: now,
+        };
+        todo.reset_remaining_occurrences();
+
+        let guard = self.write_lock(deadline)?;
+        self.store.insert(todo.clone());
+        self.record(EventType::Created, &todo);
+        drop(guard);
+        self.bump_version();
+        Ok(todo)
+    }
+
+    pub fn update(&self, id: Uuid, input: TodoUpdate) -> Option<Todo> {
+        unbounded(self.update_until(id, input, &Deadline::unbounded()))
+    }
+
+    pub fn update_until(
+        &self,
+        id: Uuid,
+        input: TodoUpdate,
+        deadline: &Deadline,
+    ) -> Result<Option<Todo>, DeadlineExceeded> {
+        let guard = self.write_lock(deadline)?;
+        let mut was_completed = false;
+        let mut input = Some(input);
+        let updated = self.store.update(id, &mut |todo| {
+            let Some(input) = input.take() else { return };
+            was_completed = todo.completed;
+            input.apply_to(todo);
+            todo.updated_at = Utc::now();
+        });
+        let Some(updated) = updated else {
+            return Ok(None);
+        };
+        let event_type = match (was_completed, updated.completed) {
+            (false, true) => EventType::Completed,
+            (true, false) => EventType::Reopened,
+            _ => EventType::Updated,
+        };
+        self.record(event_type, &updated);
+        drop(guard);
+        self.bump_version();
+        Ok(Some(updated))
+    }
+
+    pub fn delete(&self, id: Uuid) -> bool {
+        unbounded(self.delete_until(id, &Deadline::unbounded()))
+    }
+
+    /// Deletes with the default `CascadePolicy`.
+    pub fn delete_until(&self, id: Uuid, deadline: &Deadline) -> Result<bool, DeadlineExceeded> {
+        let report = self.delete_cascading_until(id, &CascadePolicy::default(), deadline)?;
+        Ok(report.is_some())
+    }
+
+    pub fn toggle(&self, id: Uuid) -> Option<Todo> {
+        unbounded(self.toggle_until(id, &Deadline::unbounded()))
+    }
+
+    pub fn toggle_until(
+        &self,
+        id: Uuid,
+        deadline: &Deadline,
+    ) -> Result<Option<Todo>, DeadlineExceeded> {
+        let guard = self.write_lock(deadline)?;
+        let toggled = self.store.update(id, &mut |todo| {
+            todo.completed = !todo.completed;
+            todo.updated_at = Utc::now();
+        });
+        let Some(toggled) = toggled else {
+            return Ok(None);
+        };
+        let event_type = if toggled.completed {
+            EventType::Completed
+        } else {
+            EventType::Reopened
+        };
+        self.record(event_type, &toggled);
+        drop(guard);
+        self.bump_version();
+        Ok(Some(toggled))
+    }
+
+    pub fn toggle_pin(&self, id: Uuid) -> Option<Todo> {
+        unbounded(self.toggle_pin_until(id, &Deadline::unbounded()))
+    }
+
+    pub fn toggle_pin_until(
+        &self,
+        id: Uuid,
+        deadline: &Deadline,
+    ) -> Result<Option<Todo>, DeadlineExceeded> {
+        let guard = self.write_lock(deadline)?;
+        let toggled = self.store.update(id, &mut |todo| {
+            todo.pinned = !todo.pinned;
+            todo.updated_at = Utc::now();
+        });
+        let Some(toggled) = toggled else {
+            return Ok(None);
+        };
+        self.record(EventType::Updated, &toggled);
+        drop(guard);
+        self.bump_version();
+        Ok(Some(toggled))
+    }
+
+    pub fn get_stats(&self) -> TodoStats {
+        unbounded(self.get_stats_until(&Deadline::unbounded(), false))
+    }
+
+    /// Reads the incrementally maintained stats; see `StatsReadModel`.
+    pub fn get_stats_until(
+        &self,
+        deadline: &Deadline,
+        include_hidden: bool,
+    ) -> Result<TodoStats, DeadlineExceeded> {
+        let today = Utc::now().date_naive();
+        self.get_stats_on_until(today, WeekStart::default(), deadline, include_hidden)
+    }
+
+    /// Stats as seen on `today`, for clients whose day or week differs from
+    /// the server's.
+    pub fn get_stats_on_until(
+        &self,
+        today: NaiveDate,
+        week_start: WeekStart,
+        deadline: &Deadline,
+        include_hidden: bool,
+    ) -> Result<TodoStats, DeadlineExceeded> {
+        deadline.check("stats")?;
+        Ok(self
+            .stats
+            .lock()
+            .unwrap()
+            .stats(today, week_start, include_hidden))
+    }
+
+    /// Bulk-creates todos, e.g. from a fixture set. Returns the created todos.
+    pub fn seed(&self, inputs: Vec<TodoCreate>) -> Vec<Todo> {
+        inputs.into_iter().map(|input| self.create(input)).collect()
+    }
+
+    /// Inserts todos exactly as given, keeping their ids and timestamps, e.g.
+    /// when migrating from another instance. Todos whose id already exists
+    /// are skipped so re-running an import is harmless. Returns the inserted.
+    pub fn import(&self, todos: Vec<Todo>) -> Vec<Todo> {
+        let guard = self.write_lock_stats.lock(&self.write_lock);
+        let mut imported = Vec::new();
+        for todo in todos {
+            if self.store.get(todo.id).is_some() {
+                continue;
+            }
+            self.store.insert(todo.clone());
+            self.record(EventType::Created, &todo);
+            imported.push(todo);
+        }
+        drop(guard);
+        if !imported.is_empty() {
+            self.bump_version();
+        }
+        imported
+    }
+
+    /// Removes every todo. Returns how many were deleted.
+    pub fn reset(&self) -> usize {
+        let guard = self.write_lock_stats.lock(&self.write_lock);
+        let removed = self.store.remove_where(&|_| true);
+        for todo in &removed {
+            self.record(EventType::Deleted, todo);
+        }
+        drop(guard);
+        if !removed.is_empty() {
+            self.bump_version();
+        }
+        removed.len()
+    }
+
+    /// Replaces the whole collection with `todos` in one write, e.g. when
+    /// rolling back to a backup. Returns (removed, restored) counts.
+    pub fn replace_all(&self, todos: Vec<Todo>) -> (usize, usize) {
+        let guard = self.write_lock_stats.lock(&self.write_lock);
+        let removed = self.store.remove_where(&|_| true);
+        for todo in &removed {
+            self.record(EventType::Deleted, todo);
+        }
+        let restored = todos.len();
+        for todo in todos {
+            self.store.insert(todo.clone());
+            self.record(EventType::Created, &todo);
+        }
+        drop(guard);
+        if !removed.is_empty() || restored > 0 {
+            self.bump_version();
+        }
+        (removed.len(), restored)
+    }
+
+    pub fn clear_completed(&self) {
+        unbounded(self.clear_completed_until(&Deadline::unbounded()))
+    }
+
+    pub fn clear_completed_until(&self, deadline: &Deadline) -> Result<(), DeadlineExceeded> {
+        let guard = self.write_lock(deadline)?;
+        let removed = self.store.remove_where(&|todo| todo.completed);
+        for todo in &removed {
+            self.record(EventType::Deleted, todo);
+        }
+        drop(guard);
+        if !removed.is_empty() {
+            self.bump_version();
+        }
+        Ok(())
+    }
+
+    /// Completes every active todo the list filters and `query` select,
+    /// the way `clear_completed_until` deletes the completed ones. Returns
+    /// how many were completed.
+    pub fn complete_matching_until(
+        &self,
+        filter: Option<String>,
+        search: Option<String>,
+        priority: Option<String>,
+        query: Option<&Query>,
+        today: NaiveDate,
+        deadline: &Deadline,
+    ) -> Result<usize, DeadlineExceeded> {
+        let guard = self.write_lock(deadline)?;
+        let selected = match query {
+            Some(query) => {
+                self.get_matching_until(filter, search, priority, query, today, deadline)?
+            }
+            None => self.get_all_until(filter, search, priority, deadline)?,
+        };
+        let mut completed = 0;
+        for todo in selected.iter().filter(|todo| !todo.completed) {
+            let updated = self.store.update(todo.id, &mut |todo| {
+                todo.completed = true;
+                todo.updated_at = Utc::now();
+            });
+            if let Some(updated) = updated {
+                self.record(EventType::Completed, &updated);
+                completed += 1;
+            }
+        }
+        drop(guard);
+        if completed > 0 {
+            self.bump_version();
+        }
+        Ok(completed)
+    }
+
+    pub(crate) fn write_lock(
+        &self,
+        deadline: &Deadline,
+    ) -> Result<MutexGuard<'_, ()>, DeadlineExceeded> {
+        self.write_lock_stats.lock_until(&self.write_lock, deadline, "write lock")
+    }
+}
+
+/// Unwraps the result of an operation run without a budget, which cannot
+/// exceed it.
+fn unbounded<T>(result: Result<T, DeadlineExceeded>) -> T {
+    result.unwrap_or_else(|e| unreachable!("unbounded deadline exceeded: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_new_service() {
+        let service = TodoService::new();
+        let todos = service.get_all(None, None, None);
+        assert!(!todos.is_empty(), "Service should have sample data");
+    }
+
+    #[test]
+    fn test_create_todo() {
+        let service = TodoService::new_empty();
+        
+        let input = TodoCreate {
+            text: "Test Todo".to_string(),
+            priority: Some(Priority::High),
+            completed: Some(false),
+            ..Default::default()
+        };
+
+        let todo = service.create(input);
+        
+        assert_eq!(todo.text, "Test Todo");
+        assert_eq!(todo.priority, Priority::High);
+        assert!(!todo.completed);
+        assert!(!todo.id.to_string().is_empty());
+    }
+
+    #[test]
+    fn test_create_todo_with_defaults() {
+        let service = TodoService::new_empty();
+        
+        let input = TodoCreate {
+            text: "Test".to_string(),
+            ..Default::default()
+        };
+
+        let todo = service.create(input);
+        
+        assert_eq!(todo.priority, Priority::Medium);
+        assert!(!todo.completed);
+    }
+
+    #[test]
+    fn test_get_by_id() {
+        let service = TodoService::new_empty();
+        let created = service.create(TodoCreate {
+            text: "Test".to_string(),
+            ..Default::default()
+        });
+
+        let found = service.get_by_id(created.id);
+        assert!(found.is_some());
+        assert_eq!(found.unwrap().id, created.id);
+
+        let not_found = service.get_by_id(Uuid::nil());
+        assert!(not_found.is_none());
+    }
+
+    #[test]
+    fn test_get_all_with_filters() {
+        let service = TodoService::new_empty();
+        
+        service.create(TodoCreate {
+            text: "Active Todo".to_string(),
+            priority: Some(Priority::High),
+            completed: Some(false),
+            ..Default::default()
+        });
+
+        service.create(TodoCreate {
+            text: "Completed Todo".to_string(),
+            priority: Some(Priority::Low),
+            completed: Some(true),
+            ..Default::default()
+        });
+
+        // Test filter
+        let active = service.get_all(Some("active".to_string()), None, None);
+        assert_eq!(active.len(), 1);
+
+        let completed = service.get_all(Some("completed".to_string()), None, None);
+        assert_eq!(completed.len(), 1);
+
+        // Test priority filter
+        let high = service.get_all(None, None, Some("high".to_string()));
+        assert_eq!(high.len(), 1);
+
+        // Test search
+        let search = service.get_all(None, Some("Active".to_string()), None);
+        assert_eq!(search.len(), 1);
+    }
+
+    #[test]
+    fn test_update_todo() {
+        let service = TodoService::new_empty();
+        let created = service.create(TodoCreate {
+            text: "Original".to_string(),
+            ..Default::default()
+        });
+
+        let update = TodoUpdate {
+            text: Some("Updated".to_string()),
+            priority: Some(Priority::High),
+            completed: Some(true),
+            ..Default::default()
+        };
+
+        let updated = service.update(created.id, update);
+        assert!(updated.is_some());
+        
+        let todo = updated.unwrap();
+        assert_eq!(todo.text, "Updated");
+        assert_eq!(todo.priority, Priority::High);
+        assert!(todo.completed);
+    }
+
+    #[test]
+    fn test_update_nonexistent() {
+        let service = TodoService::new_empty();
+        
+        let update = TodoUpdate {
+            text: Some("Updated".to_string()),
+            ..Default::default()
+        };
+
+        let result = service.update(Uuid::nil(), update);
+        assert!(result.is_none());
+    }
+
+    #[test]
+    fn test_delete_todo() {
+        let service = TodoService::new_empty();
+        let created = service.create(TodoCreate {
+            text: "To Delete".to_string(),
+            ..Default::default()
+        });
+
+        let deleted = service.delete(created.id);
+        assert!(deleted);
+
+        let found = service.get_by_id(created.id);
+        assert!(found.is_none());
+    }
+
+    #[test]
+    fn test_delete_nonexistent() {
+        let service = TodoService::new_empty();
+        let deleted = service.delete(Uuid::nil());
+        assert!(!deleted);
+    }
+
+    #[test]
+    fn test_toggle_todo() {
+        let service = TodoService::new_empty();
+        let created = service.create(TodoCreate {
+            text: "To Toggle".to_string(),
+            completed: Some(false),
+            ..Default::default()
+        });
+
+        let toggled = service.toggle(created.id);
+        assert!(toggled.is_some());
+        assert!(toggled.unwrap().completed);
+
+        let toggled_again = service.toggle(created.id);
+        assert!(!toggled_again.unwrap().completed);
+    }
+
+    #[test]
+    fn test_get_stats() {
+        let service = TodoService::new_empty();
+        
+        service.create(TodoCreate {
+            text: "Todo 1".to_string(),
+            priority: Some(Priority::High),
+            completed: Some(false),
+            ..Default::default()
+        });
+
+        service.create(TodoCreate {
+            text: "Todo 2".to_string(),
+            priority: Some(Priority::High),
+            completed: Some(true),
+            ..Default::default()
+        });
+
+        service.create(TodoCreate {
+            text: "Todo 3".to_string(),
+            priority: Some(Priority::Low),
+            completed: Some(false),
+            ..Default::default()
+        });
+
+        let stats = service.get_stats();
+        
+        assert_eq!(stats.total, 3);
+        assert_eq!(stats.active, 2);
+        assert_eq!(stats.completed, 1);
+        assert!((stats.completion_rate - 33.33).abs() < 0.1);
+        assert!((stats.weighted_completion_rate - 44.44).abs() < 0.1);
+        assert_eq!(*stats.priority_breakdown.get("high").unwrap(), 2);
+        assert_eq!(*stats.priority_breakdown.get("low").unwrap(), 1);
+    }
+
+    #[test]
+    fn test_stats_read_model_tracks_mutations() {
+        let service = TodoService::new();
+        let ids: Vec<Uuid> = service
+            .get_all(None, None, None)
+            .into_iter()
+            .map(|t| t.id)
+            .collect();
+        service.toggle(ids[0]);
+        service.update(
+            ids[1],
+            TodoUpdate {
+                priority: Some(Priority::Low),
+                due_date: Some(Utc::now().date_naive().to_string()),
+                ..Default::default()
+            },
+        );
+        service.delete(ids[2]);
+        service.clear_completed();
+        service.create(TodoCreate {
+            text: "Late addition".to_string(),
+            priority: Some(Priority::High),
+            completed: Some(true),
+            ..Default::default()
+        });
+
+        let rebuilt = StatsReadModel::from_todos(&service.get_all(None, None, None));
+        assert_eq!(*service.stats.lock().unwrap(), rebuilt);
+
+        service.reset();
+        assert_eq!(*service.stats.lock().unwrap(), StatsReadModel::default());
+    }
+
+    #[test]
+    fn test_clear_completed() {
+        let service = TodoService::new_empty();
+        
+        service.create(TodoCreate {
+            text: "Active".to_string(),
+            completed: Some(false),
+            ..Default::default()
+        });
+
+        service.create(TodoCreate {
+            text: "Completed".to_string(),
+            completed: Some(true),
+            ..Default::default()
+        });
+
+        service.clear_completed();
+        
+        let todos = service.get_all(None, None, None);
+        assert_eq!(todos.len(), 1);
+        assert!(!todos[0].completed);
+    }
+
+    #[test]
+    fn test_complete_matching() {
+        let service = TodoService::new_empty();
+        for (text, priority) in [
+            ("Send report #work", Priority::High),
+            ("Book flights #work", Priority::Low),
+            ("Water plants #home", Priority::High),
+        ] {
+            service.create(TodoCreate {
+                text: text.to_string(),
+                priority: Some(priority),
+                ..Default::default()
+            });
+        }
+        let today = Utc::now().date_naive();
+        let work = Query::tag("work").unwrap();
+
+        let completed = service
+            .complete_matching_until(
+                None,
+                None,
+                Some("high".to_string()),
+                Some(&work),
+                today,
+                &Deadline::unbounded(),
+            )
+            .unwrap();
+        assert_eq!(completed, 1);
+        let done = service.get_all(Some("completed".to_string()), None, None);
+        assert_eq!(done.len(), 1);
+        assert_eq!(done[0].text, "Send report #work");
+        assert_eq!(service.get_stats().completed, 1);
+
+        // Already completed todos are not counted again.
+        let completed = service
+            .complete_matching_until(None, None, None, Some(&work), today, &Deadline::unbounded())
+            .unwrap();
+        assert_eq!(completed, 1);
+        assert_eq!(service.get_all(Some("active".to_string()), None, None).len(), 1);
+    }
+
+    #[test]
+    fn test_collection_version_bumps_on_mutation() {
+        let service = TodoService::new_empty();
+        let initial = service.collection_version();
+
+        let created = service.create(TodoCreate {
+            text: "Versioned".to_string(),
+            ..Default::default()
+        });
+        let after_create = service.collection_version();
+        assert_eq!(after_create.version, initial.version + 1);
+        assert_ne!(after_create.etag(&()), initial.etag(&()));
+
+        // Reads and no-op mutations leave the version alone
+        service.get_all(None, None, None);
+        service.delete(Uuid::nil());
+        service.clear_completed();
+        assert_eq!(service.collection_version(), after_create);
+
+        service.toggle(created.id);
+        assert_eq!(service.collection_version().version, initial.version + 2);
+    }
+
+    #[test]
+    fn test_mutations_emit_events() {
+        let service = TodoService::new_empty();
+        let created = service.create(TodoCreate {
+            text: "Evented".to_string(),
+            ..Default::default()
+        });
+        service.update(created.id, TodoUpdate {
+            completed: Some(true),
+            ..Default::default()
+        });
+        service.toggle(created.id);
+        service.toggle(created.id);
+        service.clear_completed();
+
+        let types: Vec<EventType> = service.events().all().iter().map(|e| e.event_type).collect();
+        assert_eq!(
+            types,
+            vec![
+                EventType::Created,
+                EventType::Completed,
+                EventType::Reopened,
+                EventType::Completed,
+                EventType::Deleted,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_check_storage_delegates_to_store() {
+        let service = TodoService::new_empty();
+        assert!(service.check_storage(Duration::from_millis(50)).is_ok());
+    }
+
+    #[test]
+    fn test_replace_all() {
+        let service = TodoService::new();
+        let existing = service.get_all(None, None, None).len();
+        let mut replacement = service.get_all(None, None, None);
+        replacement.truncate(1);
+        replacement[0].text = "Restored".to_string();
+
+        assert_eq!(service.replace_all(replacement.clone()), (existing, 1));
+        let todos = service.get_all(None, None, None);
+        assert_eq!(todos.len(), 1);
+        assert_eq!(todos[0].text, "Restored");
+        assert_eq!(todos[0].id, replacement[0].id);
+    }
+
+    #[test]
+    fn test_seed_and_reset() {
+        let service = TodoService::new_empty();
+        let seeded = service.seed(crate::fixtures::builtin("demo").unwrap());
+        assert_eq!(service.get_all(None, None, None).len(), seeded.len());
+
+        assert_eq!(service.reset(), seeded.len());
+        assert!(service.get_all(None, None, None).is_empty());
+        assert_eq!(service.reset(), 0);
+    }
+
+    #[test]
+    fn test_lock_diagnostics_count_acquisitions() {
+        let service = TodoService::new_empty();
+        service.create(TodoCreate {
+            text: "Counted".to_string(),
+            ..Default::default()
+        });
+        service.get_all(None, None, None);
+
+        let locks = service.lock_diagnostics();
+        assert_eq!(locks.writes.acquisitions, 1);
+        assert_eq!(locks.writes.contended, 0);
+        assert_eq!(locks.store.unwrap().acquisitions, 2);
+    }
+
+    #[test]
+    fn test_deadline_bounds_write_lock_wait() {
+        let service = TodoService::new_empty();
+        let held = service.write_lock.lock().unwrap();
+
+        let deadline = Deadline::within(Duration::from_millis(20));
+        let exceeded = service
+            .create_until(
+                TodoCreate {
+                    text: "Too late".to_string(),
+                    ..Default::default()
+                },
+                &deadline,
+            )
+            .unwrap_err();
+        assert_eq!(exceeded.stage, "write lock");
+        drop(held);
+
+        // Nothing was applied
+        assert!(service.get_all(None, None, None).is_empty());
+        assert_eq!(service.collection_version().version, 0);
+
+        let deadline = Deadline::within(Duration::from_secs(5));
+        let todos = service.get_all_until(None, None, None, &deadline).unwrap();
+        assert!(todos.is_empty());
+        let stages: Vec<&str> = deadline
+            .exceeded("done")
+            .completed
+            .iter()
+            .map(|stage| stage.stage)
+            .collect();
+        assert_eq!(stages, vec!["store lock", "store query", "filter"]);
+
+        let deadline = Deadline::within(Duration::from_secs(5));
+        assert!(service.get_by_id_until(Uuid::nil(), &deadline).unwrap().is_none());
+        let stages: Vec<&str> = deadline
+            .exceeded("done")
+            .completed
+            .iter()
+            .map(|stage| stage.stage)
+            .collect();
+        assert_eq!(stages, vec!["store lock", "store query"]);
+    }
+
+    #[test]
+    fn test_import_keeps_ids_and_skips_existing() {
+        let service = TodoService::new_empty();
+        let source = TodoService::new();
+        let todos = source.get_all(None, None, None);
+
+        let imported = service.import(todos.clone());
+        assert_eq!(imported.len(), todos.len());
+        let copy = service.get_by_id(todos[0].id).unwrap();
+        assert_eq!(copy.created_at, todos[0].created_at);
+
+        assert!(service.import(todos).is_empty());
+        assert_eq!(service.collection_version().version, 1);
+    }
+
+    #[test]
+    fn test_nearby_filters_by_radius() {
+        let service = TodoService::new_empty();
+        let errand = |text: &str, lat: Option<f64>, lng: Option<f64>| {
+            service.create(TodoCreate {
+                text: text.to_string(),
+                location: Some(crate::models::Location {
+                    name: Some(text.to_string()),
+                    lat,
+                    lng,
+                }),
+                ..Default::default()
+            })
+        };
+        let bakery = errand("Bakery", Some(48.8570), Some(2.3530));
+        let pharmacy = errand("Pharmacy", Some(48.8600), Some(2.3500));
+        errand("Airport", Some(49.0097), Some(2.5479));
+        errand("Somewhere", None, None);
+        service.toggle(pharmacy.id);
+
+        let nearby = service.nearby(48.8566, 2.3522, 2_000.0);
+        assert_eq!(nearby.len(), 1);
+        assert_eq!(nearby[0].todo.id, bakery.id);
+        assert!(nearby[0].distance_meters < 100.0);
+
+        service.toggle(pharmacy.id);
+        let ids: Vec<String> = service
+            .nearby(48.8566, 2.3522, 2_000.0)
+            .into_iter()
+            .map(|found| found.todo.id.to_string())
+            .collect();
+        assert_eq!(ids, vec![bakery.id.to_string(), pharmacy.id.to_string()]);
+    }
+}
@@ -0,0 +1,165 @@
+//! Property tests: arbitrary sequences of service operations, checking the
+//! invariants after every step.
+
+use crate::models::{Priority, TodoCreate, TodoUpdate};
+use crate::service::TodoService;
+use proptest::prelude::*;
+use std::collections::BTreeSet;
+use uuid::Uuid;
+
+#[derive(Debug, Clone)]
+enum Op {
+    Create {
+        priority: Priority,
+        completed: bool,
+    },
+    /// Operations on an existing todo pick it by index, modulo the count.
+    Toggle(usize),
+    SetPriority(usize, Priority),
+    Delete(usize),
+    ClearCompleted,
+}
+
+fn priority() -> impl Strategy<Value = Priority> {
+    prop_oneof![
+        Just(Priority::Low),
+        Just(Priority::Medium),
+        Just(Priority::High)
+    ]
+}
+
+fn op() -> impl Strategy<Value = Op> {
+    prop_oneof![
+        3 => (priority(), any::<bool>())
+            .prop_map(|(priority, completed)| Op::Create { priority, completed }),
+        2 => any::<usize>().prop_map(Op::Toggle),
+        1 => (any::<usize>(), priority()).prop_map(|(i, priority)| Op::SetPriority(i, priority)),
+        1 => any::<usize>().prop_map(Op::Delete),
+        1 => Just(Op::ClearCompleted),
+    ]
+}
+
+fn nth_id(service: &TodoService, i: usize) -> Option<Uuid> {
+    let mut ids: Vec<Uuid> = service
+        .get_all(None, None, None)
+        .into_iter()
+        .map(|todo| todo.id)
+        .collect();
+    ids.sort();
+    (!ids.is_empty()).then(|| ids.swap_remove(i % ids.len()))
+}
+
+fn apply(service: &TodoService, op: &Op) {
+    match op {
+        Op::Create {
+            priority,
+            completed,
+        } => {
+            service.create(TodoCreate {
+                text: "Generated".to_string(),
+                priority: Some(priority.clone()),
+                completed: Some(*completed),
+                ..Default::default()
+            });
+        }
+        Op::Toggle(i) => {
+            if let Some(id) = nth_id(service, *i) {
+                service.toggle(id);
+            }
+        }
+        Op::SetPriority(i, priority) => {
+            if let Some(id) = nth_id(service, *i) {
+                service.update(
+                    id,
+                    TodoUpdate {
+                        priority: Some(priority.clone()),
+                        ..Default::default()
+                    },
+                );
+            }
+        }
+        Op::Delete(i) => {
+            if let Some(id) = nth_id(service, *i) {
+                service.delete(id);
+            }
+        }
+        Op::ClearCompleted => service.clear_completed(),
+    }
+}
+
+/// The incrementally kept stats agree with counting the todos afresh.
+fn assert_stats_consistent(service: &TodoService) -> Result<(), TestCaseError> {
+    let todos = service.get_all(None, None, None);
+    let stats = service.get_stats();
+    let completed = todos.iter().filter(|todo| todo.completed).count();
+    prop_assert_eq!(stats.total, todos.len());
+    prop_assert_eq!(stats.completed, completed);
+    prop_assert_eq!(stats.active, todos.len() - completed);
+    for (name, priority) in [
+        ("low", Priority::Low),
+        ("medium", Priority::Medium),
+        ("high", Priority::High),
+    ] {
+        let count = todos
+            .iter()
+            .filter(|todo| todo.priority == priority)
+            .count();
+        prop_assert_eq!(
+            stats.priority_breakdown.get(name).copied().unwrap_or(0),
+            count
+        );
+    }
+    Ok(())
+}
+
+proptest! {
+    #[test]
+    fn stats_stay_consistent(ops in prop::collection::vec(op(), 0..60)) {
+        let service = TodoService::new_empty();
+        for op in &ops {
+            apply(&service, op);
+            assert_stats_consistent(&service)?;
+        }
+    }
+
+    #[test]
+    fn toggle_is_an_involution(ops in prop::collection::vec(op(), 1..30), pick in any::<usize>()) {
+        let service = TodoService::new_empty();
+        for op in &ops {
+            apply(&service, op);
+        }
+        let Some(id) = nth_id(&service, pick) else { return Ok(()) };
+        let before = service.get_by_id(id).unwrap();
+        service.toggle(id);
+        let toggled = service.get_by_id(id).unwrap();
+        prop_assert_eq!(toggled.completed, !before.completed);
+        service.toggle(id);
+        let after = service.get_by_id(id).unwrap();
+        prop_assert_eq!(
+            (after.completed, &after.text, &after.priority),
+            (before.completed, &before.text, &before.priority)
+        );
+        assert_stats_consistent(&service)?;
+    }
+
+    #[test]
+    fn clear_completed_removes_exactly_the_completed(ops in prop::collection::vec(op(), 0..40)) {
+        let service = TodoService::new_empty();
+        for op in &ops {
+            apply(&service, op);
+        }
+        let active: BTreeSet<String> = service
+            .get_all(Some("active".to_string()), None, None)
+            .into_iter()
+            .map(|todo| todo.id.to_string())
+            .collect();
+        service.clear_completed();
+        let left: BTreeSet<String> = service
+            .get_all(None, None, None)
+            .into_iter()
+            .map(|todo| todo.id.to_string())
+            .collect();
+        prop_assert_eq!(left, active);
+        assert_stats_consistent(&service)?;
+    }
+}
@@ -0,0 +1,78 @@
+use crate::models::Todo;
+use std::fs::{self, File};
+use std::io;
+use std::path::Path;
+
+/// Reads a snapshot written by `save`. A missing file is an empty snapshot.
+pub fn load(path: &Path) -> io::Result<Vec<Todo>> {
+    let bytes = match fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+    serde_json::from_slice(&bytes).map_err(|e| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{}: {}", path.display(), e),
+        )
+    })
+}
+
+/// Writes `todos` as a JSON array, replacing `path` atomically so a crash
+/// mid-write leaves the previous snapshot intact.
+pub fn save(path: &Path, todos: &[Todo]) -> io::Result<()> {
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)?;
+    }
+    let mut tmp_name = path.as_os_str().to_owned();
+    tmp_name.push(".tmp");
+    let tmp_path = Path::new(&tmp_name);
+
+    let mut tmp = File::create(tmp_path)?;
+    serde_json::to_writer(&mut tmp, todos)?;
+    tmp.sync_all()?;
+    fs::rename(tmp_path, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{todo_id, Priority};
+    use chrono::Utc;
+
+    #[test]
+    fn test_save_and_load() {
+        let dir = std::env::temp_dir().join(format!("spicy-snapshot-{}", uuid::Uuid::new_v4()));
+        let path = dir.join("nested").join("todos.json");
+        assert!(load(&path).unwrap().is_empty());
+
+        let todo = Todo {
+            id: todo_id("a"),
+            text: "Persisted".to_string(),
+            priority: Priority::High,
+            completed: false,
+            due_date: None,
+            reminder_time: None,
+            recurrence: None,
+            recurrence_end: None,
+            remaining_occurrences: None,
+            estimate_minutes: None,
+            location: None,
+            color: None,
+            icon: None,
+            pinned: false,
+            rollover_count: 0,
+            rolled_over_to: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+        save(&path, &[todo]).unwrap();
+        let loaded = load(&path).unwrap();
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(loaded[0].text, "Persisted");
+
+        fs::write(&path, "not json").unwrap();
+        assert_eq!(load(&path).unwrap_err().kind(), io::ErrorKind::InvalidData);
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,152 @@
+use crate::deadline::{Deadline, DeadlineExceeded};
+use crate::events::EventType;
+use crate::models::{SnoozeRequest, Todo, TodoUpdate};
+use crate::service::TodoService;
+use chrono::{Days, NaiveDate, Utc};
+use uuid::Uuid;
+
+/// Longest `+Nd` preset.
+pub const MAX_SNOOZE_DAYS: u32 = 365;
+
+/// How far a todo is put off.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Snooze {
+    Tomorrow,
+    NextWeek,
+    /// `+Nd`: N days from today.
+    Days(u32),
+    /// A given date, which must be after today.
+    Until(NaiveDate),
+}
+
+impl Snooze {
+    /// Parses a preset: `tomorrow`, `next-week` or `+Nd`.
+    pub fn preset(value: &str) -> Result<Self, String> {
+        let value = value.trim().to_lowercase();
+        let days = value
+            .strip_prefix('+')
+            .and_then(|rest| rest.strip_suffix('d'))
+            .map(|count| count.parse::<u32>());
+        match (value.as_str(), days) {
+            ("tomorrow", _) => Ok(Snooze::Tomorrow),
+            ("next-week", _) => Ok(Snooze::NextWeek),
+            (_, Some(Ok(days))) if (1..=MAX_SNOOZE_DAYS).contains(&days) => Ok(Snooze::Days(days)),
+            _ => Err(format!(
+                "Invalid snooze preset '{}': expected tomorrow, next-week or +Nd up to +{}d",
+                value, MAX_SNOOZE_DAYS
+            )),
+        }
+    }
+
+    /// The snooze a `POST /api/todos/{id}/snooze` body asks for: a preset
+    /// or a date, not both.
+    pub fn from_request(request: &SnoozeRequest) -> Result<Self, String> {
+        match (&request.preset, &request.date) {
+            (Some(preset), None) => Snooze::preset(preset),
+            (None, Some(date)) => NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d")
+                .map(Snooze::Until)
+                .map_err(|_| format!("Invalid snooze date '{}': expected YYYY-MM-DD", date)),
+            _ => Err("Snooze needs either a preset or a date".to_string()),
+        }
+    }
+
+    /// The new due date when snoozing on `today`.
+    pub fn due_date(self, today: NaiveDate) -> Result<NaiveDate, String> {
+        let date = match self {
+            Snooze::Tomorrow => today.checked_add_days(Days::new(1)),
+            Snooze::NextWeek => today.checked_add_days(Days::new(7)),
+            Snooze::Days(days) => today.checked_add_days(Days::new(days.into())),
+            Snooze::Until(date) => Some(date),
+        };
+        date.filter(|date| *date > today)
+            .ok_or_else(|| "A todo can only be snoozed to a date after today".to_string())
+    }
+}
+
+impl TodoService {
+    /// Moves the todo's due date to `due`, recorded as a `todo.snoozed`
+    /// event. Its reminder is due date at reminder time, so a pending one
+    /// moves along with it.
+    pub fn snooze_until(
+        &self,
+        id: Uuid,
+        due: NaiveDate,
+        deadline: &Deadline,
+    ) -> Result<Option<Todo>, DeadlineExceeded> {
+        let guard = self.write_lock(deadline)?;
+        let snoozed = self.store().update(id, &mut |todo| {
+            let update = TodoUpdate {
+                due_date: Some(due.format("%Y-%m-%d").to_string()),
+                ..Default::default()
+            };
+            update.apply_to(todo);
+            todo.updated_at = Utc::now();
+        });
+        let Some(snoozed) = snoozed else {
+            return Ok(None);
+        };
+        self.record(EventType::Snoozed, &snoozed);
+        drop(guard);
+        self.bump_version();
+        Ok(Some(snoozed))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::EventFilter;
+    use crate::models::TodoCreate;
+
+    fn date(value: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(value, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn test_resolves_presets_and_dates() {
+        let today = date("2026-10-16");
+        let due = |snooze: Result<Snooze, String>| snooze.and_then(|s| s.due_date(today));
+        assert_eq!(due(Snooze::preset("tomorrow")), Ok(date("2026-10-17")));
+        assert_eq!(due(Snooze::preset("Next-Week")), Ok(date("2026-10-23")));
+        assert_eq!(due(Snooze::preset("+3d")), Ok(date("2026-10-19")));
+        assert!(Snooze::preset("+0d").is_err());
+        assert!(Snooze::preset("later").is_err());
+
+        let request = |preset: Option<&str>, date: Option<&str>| SnoozeRequest {
+            preset: preset.map(str::to_string),
+            date: date.map(str::to_string),
+        };
+        let until = Snooze::from_request(&request(None, Some("2026-11-01")));
+        assert_eq!(due(until), Ok(date("2026-11-01")));
+        assert!(due(Snooze::from_request(&request(None, Some("2026-10-16")))).is_err());
+        assert!(Snooze::from_request(&request(Some("tomorrow"), Some("2026-11-01"))).is_err());
+        assert!(Snooze::from_request(&request(None, None)).is_err());
+    }
+
+    #[test]
+    fn test_snooze_moves_due_date_and_is_recorded() {
+        let service = TodoService::new_empty();
+        let todo = service.create(TodoCreate {
+            text: "Call the bank".to_string(),
+            due_date: Some("2026-10-16".to_string()),
+            reminder_time: Some("09:00".to_string()),
+            ..Default::default()
+        });
+
+        let snoozed = service
+            .snooze_until(todo.id, date("2026-10-19"), &Deadline::unbounded())
+            .unwrap()
+            .unwrap();
+        assert_eq!(snoozed.due_date.as_deref(), Some("2026-10-19"));
+        assert_eq!(snoozed.reminder_time.as_deref(), Some("09:00"));
+        let filter = EventFilter {
+            event_types: vec![EventType::Snoozed],
+            ..Default::default()
+        };
+        assert_eq!(service.events().search(&filter).len(), 1);
+        assert!(service
+            .snooze_until(Uuid::nil(), date("2026-10-19"), &Deadline::unbounded())
+            .unwrap()
+            .is_none());
+    }
+}
@@ -0,0 +1,428 @@
+use crate::deadline::{Deadline, DeadlineExceeded};
+use crate::models::Todo;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, TryLockError};
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+/// Persistence backend for todos.
+///
+/// `TodoService` serializes all writes and handles events and versioning,
+/// so a backend only needs each individual call to be atomic.
+pub trait TodoStore: Send + Sync {
+    fn all(&self) -> Vec<Todo>;
+
+    fn get(&self, id: Uuid) -> Option<Todo>;
+
+    fn insert(&self, todo: Todo);
+
+    /// Applies `apply` to the todo with `id` in place and returns the result.
+    fn update(&self, id: Uuid, apply: &mut dyn FnMut(&mut Todo)) -> Option<Todo>;
+
+    fn remove(&self, id: Uuid) -> Option<Todo>;
+
+    /// Removes every todo matching `predicate` and returns them.
+    fn remove_where(&self, predicate: &dyn Fn(&Todo) -> bool) -> Vec<Todo>;
+
+    fn count(&self) -> usize;
+
+    /// Ids of every todo, in no particular order. Lets a caller walk a
+    /// large store a page at a time with `get_many` instead of cloning all
+    /// of it at once.
+    fn ids(&self) -> Vec<Uuid> {
+        self.all().into_iter().map(|todo| todo.id).collect()
+    }
+
+    /// The todos with `ids` that exist, in the order of `ids`.
+    fn get_many(&self, ids: &[Uuid]) -> Vec<Todo> {
+        ids.iter().filter_map(|id| self.get(*id)).collect()
+    }
+
+    /// `all`, giving up once `deadline` passes. Backends that can block
+    /// should override this to bound their waits by the remaining budget.
+    fn all_until(&self, deadline: &Deadline) -> Result<Vec<Todo>, DeadlineExceeded> {
+        deadline.check("store query")?;
+        let todos = self.all();
+        deadline.complete("store query");
+        Ok(todos)
+    }
+
+    /// `all_until`, sharing the todos with the store instead of copying
+    /// them, for read paths that only serialize what they get. The default
+    /// copies once into fresh `Arc`s; backends that keep todos behind one
+    /// hand out theirs.
+    fn all_shared_until(&self, deadline: &Deadline) -> Result<Vec<Arc<Todo>>, DeadlineExceeded> {
+        Ok(self
+            .all_until(deadline)?
+            .into_iter()
+            .map(Arc::new)
+            .collect())
+    }
+
+    /// `get`, giving up once `deadline` passes.
+    fn get_until(&self, id: Uuid, deadline: &Deadline) -> Result<Option<Todo>, DeadlineExceeded> {
+        deadline.check("store query")?;
+        let todo = self.get(id);
+        deadline.complete("store query");
+        Ok(todo)
+    }
+
+    /// Verifies the backend is usable within `timeout`, for readiness checks.
+    fn ping(&self, timeout: Duration) -> Result<(), String>;
+
+    /// Lock wait statistics, for backends that guard their data with a lock.
+    fn lock_stats(&self) -> Option<LockStatsSnapshot> {
+        None
+    }
+}
+
+/// Polls `mutex` until it can be locked or `timeout` elapses, so a wedged
+/// lock reports as an error instead of hanging the caller.
+pub fn lock_within<T>(mutex: &Mutex<T>, timeout: Duration) -> Result<MutexGuard<'_, T>, String> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        match mutex.try_lock() {
+            Ok(guard) => return Ok(guard),
+            Err(TryLockError::Poisoned(_)) => return Err("lock poisoned".to_string()),
+            Err(TryLockError::WouldBlock) if Instant::now() >= deadline => {
+                return Err(format!(
+                    "lock not acquired within {}ms",
+                    timeout.as_millis()
+                ))
+            }
+            Err(TryLockError::WouldBlock) => std::thread::sleep(Duration::from_millis(1)),
+        }
+    }
+}
+
+/// Counts how often a lock is taken and how long callers wait for it.
+/// Uncontended acquisitions are counted but not timed.
+#[derive(Debug, Default)]
+pub struct LockStats {
+    acquisitions: AtomicU64,
+    contended: AtomicU64,
+    total_wait_nanos: AtomicU64,
+    max_wait_nanos: AtomicU64,
+}
+
+/// Point-in-time view of a `LockStats`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct LockStatsSnapshot {
+    pub acquisitions: u64,
+    pub contended: u64,
+    #[serde(rename = "totalWaitMs")]
+    pub total_wait_ms: f64,
+    #[serde(rename = "maxWaitMs")]
+    pub max_wait_ms: f64,
+    /// Mean wait of the contended acquisitions.
+    #[serde(rename = "meanWaitMs")]
+    pub mean_wait_ms: f64,
+}
+
+impl LockStats {
+    /// Locks `mutex`, recording whether the caller had to wait and for how long.
+    pub fn lock<'a, T>(&self, mutex: &'a Mutex<T>) -> MutexGuard<'a, T> {
+        self.acquisitions.fetch_add(1, Ordering::Relaxed);
+        match mutex.try_lock() {
+            Ok(guard) => guard,
+            Err(TryLockError::Poisoned(poisoned)) => panic!("{}", poisoned),
+            Err(TryLockError::WouldBlock) => {
+                let start = Instant::now();
+                let guard = mutex.lock().unwrap();
+                let waited = start.elapsed().as_nanos() as u64;
+                self.contended.fetch_add(1, Ordering::Relaxed);
+                self.total_wait_nanos.fetch_add(waited, Ordering::Relaxed);
+                self.max_wait_nanos.fetch_max(waited, Ordering::Relaxed);
+                guard
+            }
+        }
+    }
+
+    /// Like `lock`, but waits no longer than `deadline` allows. A timeout is
+    /// attributed to `stage`.
+    pub fn lock_until<'a, T>(
+        &self,
+        mutex: &'a Mutex<T>,
+        deadline: &Deadline,
+        stage: &'static str,
+    ) -> Result<MutexGuard<'a, T>, DeadlineExceeded> {
+        let Some(remaining) = deadline.remaining() else {
+            let guard = self.lock(mutex);
+            deadline.complete(stage);
+            return Ok(guard);
+        };
+        deadline.check(stage)?;
+        self.acquisitions.fetch_add(1, Ordering::Relaxed);
+        let start = Instant::now();
+        let guard = match mutex.try_lock() {
+            Ok(guard) => guard,
+            Err(TryLockError::Poisoned(poisoned)) => panic!("{}", poisoned),
+            Err(TryLockError::WouldBlock) => {
+                let acquired = lock_within(mutex, remaining);
+                let waited = start.elapsed().as_nanos() as u64;
+                self.contended.fetch_add(1, Ordering::Relaxed);
+                self.total_wait_nanos.fetch_add(waited, Ordering::Relaxed);
+                self.max_wait_nanos.fetch_max(waited, Ordering::Relaxed);
+                acquired.map_err(|_| deadline.exceeded(stage))?
+            }
+        };
+        deadline.complete(stage);
+        Ok(guard)
+    }
+
+    pub fn snapshot(&self) -> LockStatsSnapshot {
+        let contended = self.contended.load(Ordering::Relaxed);
+        let total_wait_ms = self.total_wait_nanos.load(Ordering::Relaxed) as f64 / 1e6;
+        LockStatsSnapshot {
+            acquisitions: self.acquisitions.load(Ordering::Relaxed),
+            contended,
+            total_wait_ms,
+            max_wait_ms: self.max_wait_nanos.load(Ordering::Relaxed) as f64 / 1e6,
+            mean_wait_ms: if contended > 0 {
+                total_wait_ms / contended as f64
+            } else {
+                0.0
+            },
+        }
+    }
+}
+
+/// The default backend: a mutex-guarded map that lives for the process.
+/// Todos are kept behind an `Arc`, so reads can share them and a write
+/// copies only a todo that a reader still holds.
+#[derive(Default)]
+pub struct InMemoryStore {
+    todos: Mutex<HashMap<Uuid, Arc<Todo>>>,
+    lock_stats: LockStats,
+}
+
+impl InMemoryStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<Uuid, Arc<Todo>>> {
+        self.lock_stats.lock(&self.todos)
+    }
+}
+
+impl TodoStore for InMemoryStore {
+    fn all(&self) -> Vec<Todo> {
+        self.lock().values().map(|todo| Todo::clone(todo)).collect()
+    }
+
+    fn get(&self, id: Uuid) -> Option<Todo> {
+        self.lock().get(&id).map(|todo| Todo::clone(todo))
+    }
+
+    fn insert(&self, todo: Todo) {
+        self.lock().insert(todo.id, Arc::new(todo));
+    }
+
+    fn update(&self, id: Uuid, apply: &mut dyn FnMut(&mut Todo)) -> Option<Todo> {
+        let mut todos = self.lock();
+        let todo = Arc::make_mut(todos.get_mut(&id)?);
+        apply(todo);
+        Some(todo.clone())
+    }
+
+    fn remove(&self, id: Uuid) -> Option<Todo> {
+        self.lock().remove(&id).map(Arc::unwrap_or_clone)
+    }
+
+    fn remove_where(&self, predicate: &dyn Fn(&Todo) -> bool) -> Vec<Todo> {
+        let mut todos = self.lock();
+        let ids: Vec<Uuid> = todos
+            .values()
+            .filter(|todo| predicate(todo))
+            .map(|todo| todo.id)
+            .collect();
+        ids.iter()
+            .filter_map(|id| todos.remove(id))
+            .map(Arc::unwrap_or_clone)
+            .collect()
+    }
+
+    fn count(&self) -> usize {
+        self.lock().len()
+    }
+
+    fn ids(&self) -> Vec<Uuid> {
+        self.lock().keys().copied().collect()
+    }
+
+    fn get_many(&self, ids: &[Uuid]) -> Vec<Todo> {
+        let todos = self.lock();
+        ids.iter()
+            .filter_map(|id| todos.get(id))
+            .map(|todo| Todo::clone(todo))
+            .collect()
+    }
+
+    fn all_until(&self, deadline: &Deadline) -> Result<Vec<Todo>, DeadlineExceeded> {
+        let todos = self
+            .lock_stats
+            .lock_until(&self.todos, deadline, "store lock")?;
+        let all = todos.values().map(|todo| Todo::clone(todo)).collect();
+        drop(todos);
+        deadline.complete("store query");
+        Ok(all)
+    }
+
+    fn all_shared_until(&self, deadline: &Deadline) -> Result<Vec<Arc<Todo>>, DeadlineExceeded> {
+        let todos = self
+            .lock_stats
+            .lock_until(&self.todos, deadline, "store lock")?;
+        let all = todos.values().cloned().collect();
+        drop(todos);
+        deadline.complete("store query");
+        Ok(all)
+    }
+
+    fn get_until(&self, id: Uuid, deadline: &Deadline) -> Result<Option<Todo>, DeadlineExceeded> {
+        let todos = self
+            .lock_stats
+            .lock_until(&self.todos, deadline, "store lock")?;
+        let todo = todos.get(&id).map(|todo| Todo::clone(todo));
+        drop(todos);
+        deadline.complete("store query");
+        Ok(todo)
+    }
+
+    fn ping(&self, timeout: Duration) -> Result<(), String> {
+        lock_within(&self.todos, timeout).map(|todos| {
+            let _ = todos.len();
+        })
+    }
+
+    fn lock_stats(&self) -> Option<LockStatsSnapshot> {
+        Some(self.lock_stats.snapshot())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{todo_id, Priority};
+    use chrono::Utc;
+
+    fn todo(id: &str, completed: bool) -> Todo {
+        Todo {
+            id: todo_id(id),
+            text: format!("Todo {}", id),
+            priority: Priority::Medium,
+            completed,
+            due_date: None,
+            reminder_time: None,
+            recurrence: None,
+            recurrence_end: None,
+            remaining_occurrences: None,
+            estimate_minutes: None,
+            location: None,
+            color: None,
+            icon: None,
+            pinned: false,
+            rollover_count: 0,
+            rolled_over_to: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_in_memory_store_crud() {
+        let store = InMemoryStore::new();
+        store.insert(todo("a", false));
+        store.insert(todo("b", true));
+        assert_eq!(store.count(), 2);
+
+        let updated = store.update(todo_id("a"), &mut |todo| todo.text = "Renamed".to_string());
+        assert_eq!(updated.unwrap().text, "Renamed");
+        assert!(store.update(Uuid::nil(), &mut |_| {}).is_none());
+
+        let removed = store.remove_where(&|todo| todo.completed);
+        assert_eq!(removed.len(), 1);
+        assert_eq!(removed[0].id, todo_id("b"));
+
+        assert!(store.remove(todo_id("a")).is_some());
+        assert!(store.get(todo_id("a")).is_none());
+        assert!(store.all().is_empty());
+    }
+
+    #[test]
+    fn test_update_leaves_shared_todos_alone() {
+        let store = InMemoryStore::new();
+        store.insert(todo("a", false));
+        let shared = store.all_shared_until(&Deadline::unbounded()).unwrap();
+
+        store.update(todo_id("a"), &mut |todo| todo.completed = true);
+        assert!(!shared[0].completed);
+        let fresh = store.all_shared_until(&Deadline::unbounded()).unwrap();
+        assert!(fresh[0].completed);
+    }
+
+    #[test]
+    fn test_get_many_skips_missing_ids() {
+        let store = InMemoryStore::new();
+        store.insert(todo("a", false));
+        store.insert(todo("b", false));
+        let mut ids = store.ids();
+        ids.sort();
+        assert_eq!(ids.len(), 2);
+
+        let wanted = [todo_id("b"), todo_id("missing"), todo_id("a")];
+        let found: Vec<_> = store.get_many(&wanted).into_iter().map(|t| t.id).collect();
+        assert_eq!(found, [todo_id("b"), todo_id("a")]);
+    }
+
+    #[test]
+    fn test_ping_times_out_on_held_lock() {
+        let store = InMemoryStore::new();
+        assert!(store.ping(Duration::from_millis(50)).is_ok());
+
+        let _held = store.todos.lock().unwrap();
+        let result = store.ping(Duration::from_millis(20));
+        assert!(result.unwrap_err().contains("not acquired"));
+    }
+
+    #[test]
+    fn test_lock_stats_record_contention() {
+        let stats = LockStats::default();
+        let mutex = std::sync::Arc::new(Mutex::new(0));
+
+        drop(stats.lock(&mutex));
+        let (locked_tx, locked_rx) = std::sync::mpsc::channel();
+        let holder = {
+            let mutex = mutex.clone();
+            std::thread::spawn(move || {
+                let _held = mutex.lock().unwrap();
+                locked_tx.send(()).unwrap();
+                std::thread::sleep(Duration::from_millis(20));
+            })
+        };
+        locked_rx.recv().unwrap();
+        drop(stats.lock(&mutex));
+        holder.join().unwrap();
+
+        let snapshot = stats.snapshot();
+        assert_eq!(snapshot.acquisitions, 2);
+        assert_eq!(snapshot.contended, 1);
+        assert!(snapshot.max_wait_ms >= 10.0);
+        assert_eq!(snapshot.mean_wait_ms, snapshot.total_wait_ms);
+    }
+
+    #[test]
+    fn test_all_until_gives_up_on_held_lock() {
+        let store = InMemoryStore::new();
+        store.insert(todo("a", false));
+        assert_eq!(store.all_until(&Deadline::unbounded()).unwrap().len(), 1);
+
+        let _held = store.todos.lock().unwrap();
+        let deadline = Deadline::within(Duration::from_millis(20));
+        let exceeded = store.all_until(&deadline).unwrap_err();
+        assert_eq!(exceeded.stage, "store lock");
+        assert_eq!(store.lock_stats().unwrap().contended, 1);
+    }
+}
@@ -0,0 +1,159 @@
+use crate::events::{EventFilter, EventType};
+use crate::models::todo_id;
+use crate::service::TodoService;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::HashSet;
+
+/// How many suggestions of each kind to return.
+const MAX_SUGGESTIONS: usize = 5;
+/// Shortest missing id worth prefix-matching; anything shorter matches too much.
+const MIN_PREFIX_LEN: usize = 4;
+
+/// Hints for an id that was not found: existing ids it is probably a typo or
+/// truncation of, and deleted ids it matches.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct IdSuggestions {
+    pub similar: Vec<String>,
+    #[serde(rename = "recentlyDeleted")]
+    pub recently_deleted: Vec<DeletedId>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DeletedId {
+    pub id: String,
+    pub text: String,
+    #[serde(rename = "deletedAt")]
+    pub deleted_at: DateTime<Utc>,
+}
+
+impl IdSuggestions {
+    pub fn is_empty(&self) -> bool {
+        self.similar.is_empty() && self.recently_deleted.is_empty()
+    }
+}
+
+impl TodoService {
+    /// Looks for ids close to `missing` among existing todos and tombstones
+    /// in the event log. Closest first; deletions most recent first.
+    pub fn suggest_ids(&self, missing: &str) -> IdSuggestions {
+        let mut similar: Vec<(usize, String)> = self
+            .store()
+            .all()
+            .into_iter()
+            .map(|todo| todo.id.to_string())
+            .filter_map(|id| closeness(missing, &id).map(|score| (score, id)))
+            .collect();
+        similar.sort();
+        similar.truncate(MAX_SUGGESTIONS);
+
+        let deletions = self.events().search(&EventFilter {
+            event_types: vec![EventType::Deleted],
+            ..Default::default()
+        });
+        let mut seen = HashSet::new();
+        let recently_deleted = deletions
+            .into_iter()
+            .rev()
+            .filter(|event| seen.insert(event.todo_id.clone()))
+            .filter(|event| self.store().get(todo_id(&event.todo_id)).is_none())
+            .filter(|event| closeness(missing, &event.todo_id).is_some())
+            .take(MAX_SUGGESTIONS)
+            .map(|event| DeletedId {
+                id: event.todo_id,
+                text: event.todo.text,
+                deleted_at: event.timestamp,
+            })
+            .collect();
+
+        IdSuggestions {
+            similar: similar.into_iter().map(|(_, id)| id).collect(),
+            recently_deleted,
+        }
+    }
+}
+
+/// Lower is closer; `None` when `candidate` is not a plausible match. A
+/// truncated id scores by how much was cut off, a typo by its edit distance.
+fn closeness(missing: &str, candidate: &str) -> Option<usize> {
+    if missing == candidate {
+        return Some(0);
+    }
+    let (shorter, longer) = if missing.len() <= candidate.len() {
+        (missing, candidate)
+    } else {
+        (candidate, missing)
+    };
+    if shorter.len() >= MIN_PREFIX_LEN && longer.starts_with(shorter) {
+        return Some(longer.len() - shorter.len());
+    }
+    let max_distance = (missing.chars().count() / 8).max(1);
+    let distance = levenshtein(missing, candidate);
+    (distance <= max_distance).then_some(distance)
+}
+
+fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+    for (i, ca) in a.chars().enumerate() {
+        current[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::TodoCreate;
+
+    fn create(service: &TodoService, text: &str) -> String {
+        service
+            .create(TodoCreate {
+                text: text.to_string(),
+                ..Default::default()
+            })
+            .id
+            .to_string()
+    }
+
+    #[test]
+    fn test_levenshtein() {
+        assert_eq!(levenshtein("kitten", "sitting"), 3);
+        assert_eq!(levenshtein("", "abc"), 3);
+        assert_eq!(levenshtein("same", "same"), 0);
+    }
+
+    #[test]
+    fn test_suggests_truncated_and_typoed_ids() {
+        let service = TodoService::new_empty();
+        let id = create(&service, "Target");
+        create(&service, "Other");
+
+        let truncated = service.suggest_ids(&id[..8]);
+        assert_eq!(truncated.similar, vec![id.clone()]);
+
+        let mut typo = id.clone();
+        typo.replace_range(0..1, if id.starts_with('a') { "b" } else { "a" });
+        assert_eq!(service.suggest_ids(&typo).similar, vec![id.clone()]);
+
+        assert!(service.suggest_ids("nothing-like-it").is_empty());
+    }
+
+    #[test]
+    fn test_suggests_deleted_ids() {
+        let service = TodoService::new_empty();
+        let id = create(&service, "Gone");
+        service.delete(id.parse().unwrap());
+
+        let suggestions = service.suggest_ids(&id);
+        assert!(suggestions.similar.is_empty());
+        assert_eq!(suggestions.recently_deleted.len(), 1);
+        assert_eq!(suggestions.recently_deleted[0].text, "Gone");
+    }
+}
@@ -0,0 +1,516 @@
+use crate::dates::normalize_due_date;
+use crate::deadline::Deadline;
+use crate::events::{EventCursor, EventType};
+use crate::models::{
+    todo_id, validate_estimate, validate_location, validate_recurrence_end, Todo, TodoUpdate,
+};
+use crate::service::TodoService;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+const MAX_TEXT_LEN: usize = 500;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SyncOp {
+    Create,
+    Update,
+    Delete,
+}
+
+/// One change made on a client while offline.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncChange {
+    pub op: SyncOp,
+    /// Client-generated for creates. One that is not a UUID stands for the
+    /// UUID [`todo_id`] derives from it, which the todo is stored under.
+    pub id: String,
+    /// All fields for creates, the changed ones for updates.
+    #[serde(default)]
+    pub fields: TodoUpdate,
+    /// When the change was made on the client; decides conflicts.
+    #[serde(rename = "clientTimestamp")]
+    pub client_timestamp: DateTime<Utc>,
+    /// Version of the todo the client last saw (`Todo` versions are event
+    /// sequence numbers). Unset for creates.
+    #[serde(rename = "baseVersion", default)]
+    pub base_version: Option<u64>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct SyncRequest {
+    /// Cursor from the previous sync; unset on first sync.
+    #[serde(default)]
+    pub cursor: Option<u64>,
+    #[serde(default)]
+    pub changes: Vec<SyncChange>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Resolution {
+    ClientWins,
+    ServerWins,
+}
+
+/// Record of a change that raced a server-side modification, and which side
+/// last-writer-wins picked.
+#[derive(Debug, Clone, Serialize)]
+pub struct Conflict {
+    pub id: String,
+    pub op: SyncOp,
+    pub resolution: Resolution,
+    #[serde(rename = "baseVersion")]
+    pub base_version: Option<u64>,
+    #[serde(rename = "serverVersion")]
+    pub server_version: u64,
+    #[serde(rename = "clientTimestamp")]
+    pub client_timestamp: DateTime<Utc>,
+    #[serde(rename = "serverTimestamp")]
+    pub server_timestamp: DateTime<Utc>,
+    /// Server state before resolution; `None` if it had been deleted.
+    pub server: Option<Todo>,
+}
+
+/// Why a change was not applied. Core leaves the wording to the caller,
+/// which knows the client's language.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SyncErrorCode {
+    IdRequired,
+    TextRequired,
+    TextTooLong,
+    /// An update for a todo the server never had.
+    NotFound,
+    /// A field failed its check; `error` says which.
+    Invalid,
+    /// Refused by the caller's content moderation before it reached core.
+    Disallowed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SyncError {
+    pub id: String,
+    pub code: SyncErrorCode,
+    /// Set for `Invalid`; the caller words the other codes.
+    pub error: Option<String>,
+}
+
+impl SyncError {
+    fn new(id: String, code: SyncErrorCode) -> Self {
+        SyncError {
+            id,
+            code,
+            error: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SyncResponse {
+    /// Pass back as `cursor` on the next sync.
+    pub cursor: u64,
+    /// The cursor is not in this server's history (e.g. after a restart, or
+    /// older than retention kept); the client should replace its local
+    /// state with `upserted`.
+    #[serde(rename = "fullResync")]
+    pub full_resync: bool,
+    /// Ids of the client changes that took effect.
+    pub applied: Vec<String>,
+    pub conflicts: Vec<Conflict>,
+    pub errors: Vec<SyncError>,
+    /// Current state of every todo changed since the cursor.
+    pub upserted: Vec<Todo>,
+    /// Ids of todos deleted since the cursor.
+    pub deleted: Vec<String>,
+}
+
+enum Outcome {
+    Applied,
+    Conflict(Box<Conflict>),
+    Error(SyncError),
+}
+
+impl TodoService {
+    /// Applies a client's offline changes and returns everything that changed
+    /// on the server since the client's cursor. The batch runs under the
+    /// write lock so no other write interleaves with it.
+    pub fn sync(&self, request: SyncRequest) -> SyncResponse {
+        let now = Utc::now();
+        let mut applied = Vec::new();
+        let mut conflicts = Vec::new();
+        let mut errors = Vec::new();
+
+        let guard = self
+            .write_lock(&Deadline::unbounded())
+            .unwrap_or_else(|e| unreachable!("unbounded deadline exceeded: {}", e));
+        let mut changed = false;
+        for change in request.changes {
+            let id = change.id.clone();
+            let (outcome, wrote) = self.apply_change(change, now);
+            changed |= wrote;
+            match outcome {
+                Outcome::Applied => applied.push(id),
+                Outcome::Conflict(conflict) => {
+                    if conflict.resolution == Resolution::ClientWins {
+                        applied.push(id);
+                    }
+                    conflicts.push(*conflict);
+                }
+                Outcome::Error(error) => errors.push(error),
+            }
+        }
+        drop(guard);
+        if changed {
+            self.bump_version();
+        }
+
+        let latest = self.events().latest_sequence();
+        let pruned = self.events().pruned_through();
+        let full_resync = request
+            .cursor
+            .is_none_or(|cursor| cursor > latest || cursor < pruned);
+        let (upserted, deleted) = if full_resync {
+            (self.store().all(), Vec::new())
+        } else {
+            self.delta_since(request.cursor.unwrap_or(0))
+        };
+
+        SyncResponse {
+            cursor: latest,
+            full_resync,
+            applied,
+            conflicts,
+            errors,
+            upserted,
+            deleted,
+        }
+    }
+
+    fn delta_since(&self, cursor: u64) -> (Vec<Todo>, Vec<String>) {
+        let events = self.events().since(&EventCursor::Sequence(cursor));
+        let mut seen = HashSet::new();
+        let mut upserted = Vec::new();
+        let mut deleted = Vec::new();
+        // Newest first, so each todo is reported once in its latest state
+        for event in events.iter().rev() {
+            if !seen.insert(event.todo_id.clone()) {
+                continue;
+            }
+            match self.store().get(todo_id(&event.todo_id)) {
+                Some(todo) => upserted.push(todo),
+                None => deleted.push(event.todo_id.clone()),
+            }
+        }
+        upserted.reverse();
+        deleted.reverse();
+        (upserted, deleted)
+    }
+
+    /// Returns the outcome and whether the store was written.
+    fn apply_change(&self, mut change: SyncChange, now: DateTime<Utc>) -> (Outcome, bool) {
+        if let Err(error) = validate(&mut change, now) {
+            return (Outcome::Error(error), false);
+        }
+        // Client clocks can run ahead; never let a change claim the future.
+        let written_at = change.client_timestamp.min(now);
+        let id = todo_id(&change.id);
+        let current = self.store().get(id);
+        let last_event = self.events().latest_for(&id.to_string());
+        let server_version = last_event.as_ref().map_or(0, |event| event.sequence);
+
+        let raced = match change.base_version {
+            Some(base) => server_version > base,
+            // A create for an id the server already has, seeded or synced
+            None => last_event.is_some() || current.is_some(),
+        };
+        let mut conflict = None;
+        if raced {
+            let server_timestamp = match (&current, &last_event) {
+                (Some(todo), _) => todo.updated_at,
+                (None, Some(event)) => event.timestamp,
+                (None, None) => now,
+            };
+            let resolution = if written_at > server_timestamp {
+                Resolution::ClientWins
+            } else {
+                Resolution::ServerWins
+            };
+            let record = Conflict {
+                id: change.id.clone(),
+                op: change.op,
+                resolution,
+                base_version: change.base_version,
+                server_version,
+                client_timestamp: change.client_timestamp,
+                server_timestamp,
+                server: current.clone(),
+            };
+            if resolution == Resolution::ServerWins {
+                return (Outcome::Conflict(Box::new(record)), false);
+            }
+            conflict = Some(record);
+        }
+        let outcome = || match conflict {
+            Some(record) => Outcome::Conflict(Box::new(record)),
+            None => Outcome::Applied,
+        };
+
+        match (change.op, current) {
+            (SyncOp::Delete, Some(todo)) => {
+                self.store().remove(todo.id);
+                self.record(EventType::Deleted, &todo);
+                (outcome(), true)
+            }
+            // Already gone: deleting is idempotent
+            (SyncOp::Delete, None) => (outcome(), false),
+            (_, Some(mut todo)) => {
+                let was_completed = todo.completed;
+                change.fields.apply_to(&mut todo);
+                todo.updated_at = written_at;
+                self.store().insert(todo.clone());
+                let event_type = match (was_completed, todo.completed) {
+                    (false, true) => EventType::Completed,
+                    (true, false) => EventType::Reopened,
+                    _ => EventType::Updated,
+                };
+                self.record(event_type, &todo);
+                (outcome(), true)
+            }
+            (op, None) => {
+                // A create, or an update that won against a server-side
+                // delete, which restores the todo's last known state.
+                let mut todo = match (op, last_event) {
+                    (SyncOp::Update, Some(event)) => event.todo,
+                    (SyncOp::Update, None) => {
+                        let error = SyncError::new(change.id, SyncErrorCode::NotFound);
+                        return (Outcome::Error(error), false);
+                    }
+                    _ => Todo {
+                        id,
+                        text: String::new(),
+                        priority: Default::default(),
+                        completed: false,
+                        due_date: None,
+                        reminder_time: None,
+                        recurrence: None,
+                        recurrence_end: None,
+                        remaining_occurrences: None,
+                        estimate_minutes: None,
+                        location: None,
+                        color: None,
+                        icon: None,
+                        pinned: false,
+                        rollover_count: 0,
+                        rolled_over_to: None,
+                        created_at: written_at,
+                        updated_at: written_at,
+                    },
+                };
+                change.fields.apply_to(&mut todo);
+                todo.updated_at = written_at;
+                self.store().insert(todo.clone());
+                self.record(EventType::Created, &todo);
+                (outcome(), true)
+            }
+        }
+    }
+}
+
+/// Checks a change and normalizes its due date in place.
+fn validate(change: &mut SyncChange, now: DateTime<Utc>) -> Result<(), SyncError> {
+    let code = if change.id.trim().is_empty() {
+        Some(SyncErrorCode::IdRequired)
+    } else {
+        match (&change.op, &change.fields.text) {
+            (SyncOp::Create, None) => Some(SyncErrorCode::TextRequired),
+            (_, Some(text)) if text.trim().is_empty() => Some(SyncErrorCode::TextRequired),
+            (_, Some(text)) if text.len() > MAX_TEXT_LEN => Some(SyncErrorCode::TextTooLong),
+            _ => None,
+        }
+    };
+    if let Some(code) = code {
+        return Err(SyncError::new(change.id.clone(), code));
+    }
+    let fields = &mut change.fields;
+    validate_estimate(fields.estimate_minutes)
+        .and_then(|()| validate_location(fields.location.as_ref()))
+        .and_then(|()| validate_recurrence_end(fields.recurrence_end.as_ref()))
+        .and_then(|()| normalize_due_date(&mut fields.due_date, now.date_naive()))
+        .map_err(|error| SyncError {
+            id: change.id.clone(),
+            code: SyncErrorCode::Invalid,
+            error: Some(error),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::TodoCreate;
+    use chrono::Duration;
+
+    fn change(op: SyncOp, id: &str, text: Option<&str>, at: DateTime<Utc>) -> SyncChange {
+        SyncChange {
+            op,
+            id: id.to_string(),
+            fields: TodoUpdate {
+                text: text.map(str::to_string),
+                ..Default::default()
+            },
+            client_timestamp: at,
+            base_version: None,
+        }
+    }
+
+    fn create_input(text: &str) -> TodoCreate {
+        TodoCreate {
+            text: text.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_first_sync_applies_creates_and_returns_everything() {
+        let service = TodoService::new_empty();
+        service.create(create_input("Server side"));
+
+        let response = service.sync(SyncRequest {
+            cursor: None,
+            changes: vec![change(
+                SyncOp::Create,
+                "client-1",
+                Some("Offline"),
+                Utc::now(),
+            )],
+        });
+        assert!(response.full_resync);
+        assert_eq!(response.applied, vec!["client-1"]);
+        assert_eq!(response.upserted.len(), 2);
+        assert_eq!(response.cursor, 2);
+        assert!(service.get_by_id(todo_id("client-1")).is_some());
+    }
+
+    #[test]
+    fn test_delta_since_cursor() {
+        let service = TodoService::new_empty();
+        let kept = service.create(create_input("Kept"));
+        let gone = service.create(create_input("Gone"));
+        let cursor = service.events().latest_sequence();
+
+        service.toggle(kept.id);
+        service.delete(gone.id);
+        let fresh = service.create(create_input("Fresh"));
+
+        let response = service.sync(SyncRequest {
+            cursor: Some(cursor),
+            changes: Vec::new(),
+        });
+        assert!(!response.full_resync);
+        let upserted: Vec<String> = response.upserted.iter().map(|t| t.id.to_string()).collect();
+        assert_eq!(upserted, vec![kept.id.to_string(), fresh.id.to_string()]);
+        assert_eq!(response.deleted, vec![gone.id.to_string()]);
+    }
+
+    #[test]
+    fn test_conflicting_update_uses_last_writer_wins() {
+        let service = TodoService::new_empty();
+        let todo = service.create(create_input("Original"));
+        let base = service.events().latest_sequence();
+        let stale = Utc::now() - Duration::minutes(5);
+        service.update(
+            todo.id,
+            TodoUpdate {
+                text: Some("Server edit".to_string()),
+                ..Default::default()
+            },
+        );
+
+        // Client edit made before the server's: server wins
+        let mut older = change(
+            SyncOp::Update,
+            &todo.id.to_string(),
+            Some("Old client edit"),
+            stale,
+        );
+        older.base_version = Some(base);
+        let response = service.sync(SyncRequest {
+            cursor: Some(base),
+            changes: vec![older],
+        });
+        assert!(response.applied.is_empty());
+        assert_eq!(response.conflicts[0].resolution, Resolution::ServerWins);
+        assert_eq!(service.get_by_id(todo.id).unwrap().text, "Server edit");
+
+        // Client edit made after it: client wins, still recorded
+        let mut newer = change(
+            SyncOp::Update,
+            &todo.id.to_string(),
+            Some("New client edit"),
+            Utc::now() + Duration::seconds(1),
+        );
+        newer.base_version = Some(base);
+        let response = service.sync(SyncRequest {
+            cursor: Some(base),
+            changes: vec![newer],
+        });
+        assert_eq!(response.applied, vec![todo.id.to_string()]);
+        assert_eq!(response.conflicts[0].resolution, Resolution::ClientWins);
+        assert_eq!(
+            response.conflicts[0].server.as_ref().unwrap().text,
+            "Server edit"
+        );
+        assert_eq!(service.get_by_id(todo.id).unwrap().text, "New client edit");
+    }
+
+    #[test]
+    fn test_update_after_server_delete_restores_when_newer() {
+        let service = TodoService::new_empty();
+        let todo = service.create(create_input("Doomed"));
+        let base = service.events().latest_sequence();
+        service.delete(todo.id);
+
+        let mut edit = change(
+            SyncOp::Update,
+            &todo.id.to_string(),
+            Some("Saved"),
+            Utc::now(),
+        );
+        edit.base_version = Some(base);
+        let response = service.sync(SyncRequest {
+            cursor: Some(base),
+            changes: vec![edit],
+        });
+        assert_eq!(response.conflicts[0].resolution, Resolution::ClientWins);
+        assert!(response.conflicts[0].server.is_none());
+        assert_eq!(service.get_by_id(todo.id).unwrap().text, "Saved");
+    }
+
+    #[test]
+    fn test_invalid_changes_are_reported() {
+        let service = TodoService::new_empty();
+        let response = service.sync(SyncRequest {
+            cursor: Some(0),
+            changes: vec![
+                change(SyncOp::Create, "no-text", None, Utc::now()),
+                change(SyncOp::Update, "missing", Some("x"), Utc::now()),
+                change(SyncOp::Delete, "missing", None, Utc::now()),
+            ],
+        });
+        let errors: Vec<_> = response
+            .errors
+            .iter()
+            .map(|e| (e.id.as_str(), e.code))
+            .collect();
+        assert_eq!(
+            errors,
+            vec![
+                ("no-text", SyncErrorCode::TextRequired),
+                ("missing", SyncErrorCode::NotFound)
+            ]
+        );
+        assert_eq!(response.applied, vec!["missing"]);
+        assert_eq!(service.collection_version().version, 0);
+    }
+}
@@ -0,0 +1,237 @@
+use crate::models::{Priority, Todo};
+use crate::quick_add;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+/// Words that make a todo urgent.
+const URGENT_WORDS: [&str; 8] = [
+    "urgent",
+    "asap",
+    "immediately",
+    "critical",
+    "important",
+    "emergency",
+    "overdue",
+    "deadline",
+];
+
+/// Words that make a todo something for whenever.
+const SOMEDAY_WORDS: [&str; 6] = [
+    "someday",
+    "maybe",
+    "eventually",
+    "whenever",
+    "idea",
+    "optional",
+];
+
+/// Tags suggested for todos mentioning any of the words.
+const TAG_WORDS: [(&str, &[&str]); 7] = [
+    (
+        "finance",
+        &[
+            "pay",
+            "bill",
+            "bills",
+            "rent",
+            "tax",
+            "taxes",
+            "invoice",
+            "bank",
+            "budget",
+            "insurance",
+        ],
+    ),
+    (
+        "shopping",
+        &["buy", "order", "groceries", "grocery", "shop", "purchase"],
+    ),
+    (
+        "health",
+        &[
+            "doctor",
+            "dentist",
+            "gym",
+            "pharmacy",
+            "prescription",
+            "workout",
+            "checkup",
+        ],
+    ),
+    (
+        "work",
+        &[
+            "meeting",
+            "report",
+            "client",
+            "presentation",
+            "slides",
+            "standup",
+            "review",
+        ],
+    ),
+    (
+        "home",
+        &[
+            "clean", "laundry", "dishes", "vacuum", "repair", "garden", "trash",
+        ],
+    ),
+    ("calls", &["call", "phone", "ring"]),
+    ("errands", &["pick", "drop", "return", "post", "collect"]),
+];
+
+/// Days before the due date at which a todo is suggested high priority.
+const URGENT_WITHIN_DAYS: i64 = 1;
+
+/// Suggested changes to a todo. Nothing here has been applied.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Suggestion {
+    /// Only set when it differs from the todo's priority.
+    pub priority: Option<Priority>,
+    /// Tags for the todo, not counting hashtags its text already has.
+    pub tags: Vec<String>,
+    /// `YYYY-MM-DD`, only for todos without a due date.
+    #[serde(rename = "dueDate")]
+    pub due_date: Option<String>,
+    /// Why, one line per suggested field.
+    pub reasons: Vec<String>,
+}
+
+fn words(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// Suggests a priority, tags and due date from a todo's text, without a
+/// language model. Date phrases are read relative to the day the todo was
+/// created, since that is when "tomorrow" was written.
+pub fn suggest(todo: &Todo, today: NaiveDate) -> Suggestion {
+    let mut suggestion = Suggestion::default();
+    let words = words(&todo.text);
+    let parsed = quick_add::parse(&todo.text, todo.created_at.date_naive());
+
+    if todo.due_date.is_none() {
+        if let Some(date) = parsed.due_date {
+            suggestion.due_date = Some(date.format("%Y-%m-%d").to_string());
+            suggestion
+                .reasons
+                .push(format!("The text mentions a date: {}", date));
+        }
+    }
+
+    let due = todo
+        .due_date
+        .as_deref()
+        .and_then(|date| NaiveDate::parse_from_str(date, "%Y-%m-%d").ok())
+        .or(parsed.due_date);
+    let urgent_word = words
+        .iter()
+        .find(|word| URGENT_WORDS.contains(&word.as_str()));
+    let someday_word = words
+        .iter()
+        .find(|word| SOMEDAY_WORDS.contains(&word.as_str()));
+    let (priority, reason) = if let Some(priority) = parsed.priority {
+        (priority, "The text has a priority marker".to_string())
+    } else if let Some(word) = urgent_word {
+        (Priority::High, format!("The text says '{}'", word))
+    } else if let Some(days) = due
+        .map(|due| (due - today).num_days())
+        .filter(|days| !todo.completed && *days <= URGENT_WITHIN_DAYS)
+    {
+        let when = match days {
+            days if days < 0 => "overdue",
+            0 => "due today",
+            _ => "due tomorrow",
+        };
+        (Priority::High, format!("It is {}", when))
+    } else if let Some(word) = someday_word {
+        (Priority::Low, format!("The text says '{}'", word))
+    } else {
+        (todo.priority.clone(), String::new())
+    };
+    if priority != todo.priority {
+        suggestion.priority = Some(priority);
+        suggestion.reasons.push(reason);
+    }
+
+    for (tag, tag_words) in TAG_WORDS {
+        let matched = words.iter().find(|word| tag_words.contains(&word.as_str()));
+        if let Some(word) = matched {
+            if !parsed.tags.iter().any(|existing| existing == tag) {
+                suggestion.tags.push(tag.to_string());
+                suggestion
+                    .reasons
+                    .push(format!("'{}' suggests #{}", word, tag));
+            }
+        }
+    }
+    suggestion
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::todo_id;
+    use chrono::{TimeZone, Utc};
+
+    fn todo(text: &str) -> Todo {
+        let created_at = Utc.with_ymd_and_hms(2024, 6, 10, 9, 0, 0).unwrap();
+        Todo {
+            id: todo_id("1"),
+            text: text.to_string(),
+            priority: Priority::Medium,
+            completed: false,
+            due_date: None,
+            reminder_time: None,
+            recurrence: None,
+            recurrence_end: None,
+            remaining_occurrences: None,
+            estimate_minutes: None,
+            location: None,
+            color: None,
+            icon: None,
+            pinned: false,
+            rollover_count: 0,
+            rolled_over_to: None,
+            created_at,
+            updated_at: created_at,
+        }
+    }
+
+    fn today() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 6, 10).unwrap()
+    }
+
+    #[test]
+    fn test_suggests_from_text() {
+        let suggestion = suggest(&todo("Pay rent friday urgent"), today());
+        assert_eq!(suggestion.priority, Some(Priority::High));
+        assert_eq!(suggestion.due_date.as_deref(), Some("2024-06-14"));
+        assert_eq!(suggestion.tags, vec!["finance"]);
+        assert_eq!(suggestion.reasons.len(), 3);
+
+        let suggestion = suggest(&todo("Maybe learn the banjo someday"), today());
+        assert_eq!(suggestion.priority, Some(Priority::Low));
+        assert_eq!(suggestion.due_date, None);
+        assert!(suggestion.tags.is_empty());
+    }
+
+    #[test]
+    fn test_leaves_settled_fields_alone() {
+        let mut settled = todo("Call the dentist tomorrow");
+        settled.due_date = Some("2024-06-20".to_string());
+        let suggestion = suggest(&settled, today());
+        assert_eq!(suggestion.due_date, None);
+        assert_eq!(suggestion.priority, None);
+        assert_eq!(suggestion.tags, vec!["health", "calls"]);
+
+        // Read against the creation day, "tomorrow" is now overdue.
+        let later = NaiveDate::from_ymd_opt(2024, 6, 12).unwrap();
+        let suggestion = suggest(&todo("Call the dentist tomorrow"), later);
+        assert_eq!(suggestion.due_date.as_deref(), Some("2024-06-11"));
+        assert_eq!(suggestion.priority, Some(Priority::High));
+        assert!(suggestion.reasons.contains(&"It is overdue".to_string()));
+    }
+}
@@ -0,0 +1,174 @@
+use crate::events::{EventFilter, EventType};
+use crate::models::Todo;
+use crate::service::TodoService;
+use chrono::{Days, NaiveDate};
+use serde::Serialize;
+use std::collections::BTreeSet;
+
+/// Days covered by a weekly summary, looking back and ahead alike.
+const WEEK_DAYS: u64 = 7;
+
+/// One user's week: what they got done from `from` to `to`, what they left
+/// open past its due date, and what comes due in the seven days ahead.
+/// Only todos the user created are counted as theirs.
+#[derive(Debug, Clone, Serialize)]
+pub struct WeeklySummary {
+    pub user: String,
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    /// Todos they completed in the week, whoever created them.
+    pub completed: usize,
+    /// Their open todos already overdue, ordered by due date.
+    #[serde(rename = "carriedOver")]
+    pub carried_over: Vec<Todo>,
+    /// Their open todos due in the coming week, ordered by due date.
+    pub upcoming: Vec<Todo>,
+}
+
+impl WeeklySummary {
+    /// Nothing done and nothing open: not worth sending.
+    pub fn is_empty(&self) -> bool {
+        self.completed == 0 && self.carried_over.is_empty() && self.upcoming.is_empty()
+    }
+
+    pub fn render_plain_text(&self) -> String {
+        let mut lines = vec![
+            format!("Week of {} to {} for {}", self.from, self.to, self.user),
+            String::new(),
+            format!("Completed: {}.", self.completed),
+        ];
+        for (title, todos) in [
+            ("Carried over", &self.carried_over),
+            ("Due this week", &self.upcoming),
+        ] {
+            lines.push(String::new());
+            lines.push(format!("{}: {}.", title, todos.len()));
+            for todo in todos {
+                let due = todo.due_date.as_deref().unwrap_or_default();
+                lines.push(format!("- {} (due {})", todo.text.trim(), due));
+            }
+        }
+        lines.join("\n") + "\n"
+    }
+}
+
+fn due_date(todo: &Todo) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(todo.due_date.as_deref()?, "%Y-%m-%d").ok()
+}
+
+impl TodoService {
+    /// Everyone who ever caused an event, in name order: the users a weekly
+    /// summary can be written for.
+    pub fn weekly_recipients(&self) -> Vec<String> {
+        let users: BTreeSet<String> = self
+            .events()
+            .search(&EventFilter::default())
+            .into_iter()
+            .filter_map(|event| event.actor)
+            .collect();
+        users.into_iter().collect()
+    }
+
+    /// `user`'s summary of the week ending the day before `today`.
+    pub fn weekly_summary(&self, user: &str, today: NaiveDate) -> WeeklySummary {
+        let from = today
+            .checked_sub_days(Days::new(WEEK_DAYS))
+            .unwrap_or(today);
+        let to = today.pred_opt().unwrap_or(today);
+        let horizon = today
+            .checked_add_days(Days::new(WEEK_DAYS))
+            .unwrap_or(NaiveDate::MAX);
+        let filter = EventFilter {
+            event_types: vec![EventType::Completed],
+            actor: Some(user.to_string()),
+            ..Default::default()
+        };
+        let completed = self
+            .events()
+            .search(&filter)
+            .iter()
+            .filter(|event| (from..=to).contains(&event.timestamp.date_naive()))
+            .count();
+
+        let mut open: Vec<(NaiveDate, Todo)> = self
+            .created_by(user)
+            .iter()
+            .filter_map(|id| self.store().get(*id))
+            .filter(|todo| !todo.completed && todo.hidden_state().is_none())
+            .filter_map(|todo| Some((due_date(&todo)?, todo)))
+            .filter(|(due, _)| *due < horizon)
+            .collect();
+        open.sort_by(|a, b| a.0.cmp(&b.0).then_with(|| a.1.text.cmp(&b.1.text)));
+        let (carried_over, upcoming): (Vec<_>, Vec<_>) =
+            open.into_iter().partition(|(due, _)| *due < today);
+        WeeklySummary {
+            user: user.to_string(),
+            from,
+            to,
+            completed,
+            carried_over: carried_over.into_iter().map(|(_, todo)| todo).collect(),
+            upcoming: upcoming.into_iter().map(|(_, todo)| todo).collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::RequestContext;
+    use crate::models::TodoCreate;
+    use chrono::Utc;
+    use uuid::Uuid;
+
+    fn create_as(service: &TodoService, user: &str, text: &str, due: NaiveDate) -> Uuid {
+        let context = RequestContext {
+            user: Some(user.to_string()),
+            ..Default::default()
+        };
+        context.sync_scope(|| {
+            service
+                .create(TodoCreate {
+                    text: text.to_string(),
+                    due_date: Some(due.format("%Y-%m-%d").to_string()),
+                    ..Default::default()
+                })
+                .id
+        })
+    }
+
+    #[test]
+    fn test_summarizes_one_users_week() {
+        let service = TodoService::new_empty();
+        let today = Utc::now().date_naive();
+        let day = |offset: i64| today + chrono::Duration::days(offset);
+        let done = create_as(&service, "alice", "Send invoices", day(-2));
+        create_as(&service, "alice", "Renew passport", day(-3));
+        create_as(&service, "alice", "Book dentist", day(2));
+        create_as(&service, "alice", "Plan holiday", day(30));
+        create_as(&service, "bob", "Water the plants", day(1));
+        RequestContext {
+            user: Some("alice".to_string()),
+            ..Default::default()
+        }
+        .sync_scope(|| service.toggle(done));
+
+        assert_eq!(service.weekly_recipients(), vec!["alice", "bob"]);
+        // Completed today, so it counts in next week's summary.
+        let tomorrow = day(1);
+        let summary = service.weekly_summary("alice", tomorrow);
+        assert_eq!(summary.completed, 1);
+        assert_eq!(summary.carried_over.len(), 1);
+        assert_eq!(summary.carried_over[0].text, "Renew passport");
+        assert_eq!(summary.upcoming.len(), 1);
+        assert_eq!(summary.upcoming[0].text, "Book dentist");
+        assert_eq!(service.weekly_summary("alice", today).completed, 0);
+
+        let text = summary.render_plain_text();
+        assert!(text.contains("Completed: 1.\n"));
+        assert!(text.contains(&format!(
+            "Due this week: 1.\n- Book dentist (due {})\n",
+            day(2)
+        )));
+        assert!(service.weekly_summary("carol", tomorrow).is_empty());
+    }
+}
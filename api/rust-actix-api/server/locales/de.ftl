@@ -0,0 +1,87 @@
+todo-not-found = Aufgabe nicht gefunden
+todo-id-invalid = Die Aufgaben-ID muss eine UUID sein
+todo-text-required = Der Aufgabentext ist erforderlich
+todo-text-too-long = Der Aufgabentext muss kürzer als 500 Zeichen sein
+todo-text-invalid = Der Aufgabentext ist erforderlich und muss kürzer als 500 Zeichen sein
+todo-text-disallowed = Der Aufgabentext enthält unzulässige Inhalte
+todo-changed = Die Aufgabe wurde seit dem Abruf geändert
+todo-deleted = Aufgabe gelöscht
+todo-transferred = Aufgabe übertragen
+todo-transfer-conflict = Die Aufgabe wurde während der Übertragung geändert; sie bleibt hier, die Gegenstelle hat die ältere Kopie
+completed-cleared = Erledigte Aufgaben entfernt
+client-header-required = Der Header { $header } ist erforderlich
+deadline-exceeded = Frist der Anfrage überschritten
+bulk-edit-empty = changes muss mindestens ein Feld setzen
+bulk-edit-stale = Aufgaben wurden seit der Vorschau geändert; bitte die Änderung erneut prüfen
+bulk-edit-preview-missing = Vorschau nicht gefunden oder abgelaufen; bitte die Änderung erneut prüfen
+preference-not-found = Keine gespeicherte Einstellung für diesen Client
+push-subscription-not-found = Kein Push-Abonnement für diesen Client
+sms-subscription-not-found = Kein SMS-Abonnement für diesen Client
+sms-not-configured = SMS-Benachrichtigungen sind nicht eingerichtet
+sms-code-sent = Bestätigungscode gesendet
+web-push-not-configured = Web Push ist nicht eingerichtet
+web-push-subscription-not-found = Kein Abonnement für diesen Endpunkt
+reminder-sent-recently = Die Erinnerung wurde kürzlich schon gesendet
+reminder-undelivered = Kein Kanal hat die Erinnerung zugestellt
+webhook-not-found = Webhook nicht gefunden
+webhook-deleted = Webhook gelöscht
+notifier-not-found = Benachrichtiger nicht gefunden
+notifier-deleted = Benachrichtiger gelöscht
+script-not-found = Skript nicht gefunden
+script-deleted = Skript gelöscht
+view-not-found = Ansicht nicht gefunden
+view-deleted = Ansicht gelöscht
+policy-not-found = Richtlinie nicht gefunden
+policy-deleted = Richtlinie gelöscht
+feed-token-invalid = Feed-Token ungültig oder fehlt
+admin-token-invalid = Admin-Token ungültig oder fehlt
+admin-disabled = Die Admin-API ist deaktiviert
+auth-required = Anmeldung erforderlich
+auth-csrf-invalid = CSRF-Token fehlt oder ist ungültig
+auth-login-failed = Benutzername oder Passwort ungültig
+auth-session-disabled = Die Anmeldung per Sitzung ist nicht aktiviert
+auth-logged-out = Abgemeldet
+auth-provider-unknown = Unbekannter Anmeldeanbieter
+auth-state-invalid = Ungültiger oder abgelaufener Anmeldestatus
+auth-sign-in-cancelled = Die Anmeldung wurde abgebrochen oder abgelehnt
+auth-sign-in-refused = Dieses Konto darf sich hier nicht anmelden
+auth-account-exists = Ein Konto mit dieser E-Mail-Adresse existiert bereits; melde dich dort an, um dieses zu verknüpfen
+auth-provider-failed = Der Anmeldeanbieter konnte deine Identität nicht bestätigen
+account-deleted = Konto gelöscht
+state-reset = Zustand zurückgesetzt
+replay-started = Wiedergabe gestartet
+email-not-configured = E-Mail-Empfang ist nicht eingerichtet
+backups-not-configured = Backups sind nicht eingerichtet
+backup-not-found = Backup nicht gefunden
+mcp-session-not-found = MCP-Sitzung nicht gefunden
+mcp-too-many-sessions = Zu viele MCP-Sitzungen
+cursor-invalid = Ungültiger Cursor
+cursor-with-offset = cursor und offset können nicht kombiniert werden
+cursor-sort-mismatch = Der Cursor wurde für eine andere Sortierung ausgegeben
+sync-id-required = Die Änderungs-ID ist erforderlich
+response-encode-failed = Die Antwort konnte nicht kodiert werden: { $error }
+feed-limit-range = limit muss zwischen 1 und { $max } liegen
+nearby-radius-range = radius muss zwischen 0 und { $max } Metern liegen
+transfer-peer-unknown = Unbekannte Gegenstelle „{ $peer }“
+digest-days-range = days darf höchstens { $max } sein
+digest-format-invalid = Ungültiges Format „{ $format }“: erwartet json oder text
+plan-capacity-range = capacity darf höchstens { $max } Minuten sein
+plan-lookahead-range = lookahead darf höchstens { $max } Tage sein
+plan-date-invalid = Ungültiges Datum „{ $date }“: erwartet JJJJ-MM-TT oder today
+plan-todo-not-planned = Die Aufgabe ist für diesen Tag nicht eingeplant
+dashboard-limit-range = limit darf höchstens { $max } sein
+homeassistant-data-invalid = Ungültige Daten für { $service }: { $error }
+homeassistant-service-unknown = Unbekannter Dienst „{ $service }“
+event-type-unknown = Unbekannter Ereignistyp „{ $name }“
+session-start-failed = Die Sitzung konnte nicht gestartet werden
+fixture-unknown = Unbekannter Beispielsatz „{ $name }“
+todos-seeded = { $count } Aufgaben angelegt
+todos-generated = { $count } Aufgaben erzeugt
+profiling-disabled = Profiling ist deaktiviert
+profiling-not-built = Dieser Build wurde ohne das Feature `profiling` kompiliert
+profiling-busy = Es wird bereits ein Profil aufgezeichnet
+profiling-seconds-range = seconds muss zwischen 1 und { $max } liegen
+profiling-frequency-range = frequency muss zwischen 1 und { $max } liegen
+email-duplicate = Bereits empfangen
+email-subject-empty = Leerer Betreff
+caldav-resource-invalid = Kalenderressourcen heißen {"{"}id{"}"}.ics
@@ -0,0 +1,90 @@
+# English is the source catalog, used for any message another locale
+# lacks. Handlers name messages by id, through `i18n::message`.
+
+todo-not-found = Todo not found
+todo-id-invalid = Todo id must be a UUID
+todo-text-required = Todo text is required
+todo-text-too-long = Todo text must be less than 500 characters
+todo-text-invalid = Todo text is required and must be less than 500 characters
+todo-text-disallowed = Todo text contains disallowed content
+todo-changed = The todo has changed since it was fetched
+todo-deleted = Todo deleted successfully
+todo-transferred = Todo transferred
+todo-transfer-conflict = Todo changed during the transfer; kept here, the peer has the earlier copy
+completed-cleared = Completed todos cleared
+client-header-required = { $header } header is required
+deadline-exceeded = Request deadline exceeded
+bulk-edit-empty = changes must set at least one field
+bulk-edit-stale = Todos changed since the preview; preview the edit again
+bulk-edit-preview-missing = Preview not found or expired; preview the edit again
+preference-not-found = No saved preference for this client
+push-subscription-not-found = No push subscription for this client
+sms-subscription-not-found = No SMS subscription for this client
+sms-not-configured = SMS notifications are not configured
+sms-code-sent = Verification code sent
+web-push-not-configured = Web Push is not configured
+web-push-subscription-not-found = No subscription for this endpoint
+reminder-sent-recently = Reminder already sent recently
+reminder-undelivered = No channel delivered the reminder
+webhook-not-found = Webhook not found
+webhook-deleted = Webhook deleted successfully
+notifier-not-found = Notifier not found
+notifier-deleted = Notifier deleted successfully
+script-not-found = Script not found
+script-deleted = Script deleted successfully
+view-not-found = View not found
+view-deleted = View deleted successfully
+policy-not-found = Policy not found
+policy-deleted = Policy deleted successfully
+feed-token-invalid = Invalid or missing feed token
+admin-token-invalid = Invalid or missing admin token
+admin-disabled = Admin API is disabled
+auth-required = Authentication required
+auth-csrf-invalid = Missing or invalid CSRF token
+auth-login-failed = Invalid username or password
+auth-session-disabled = Session login is not enabled
+auth-logged-out = Logged out
+auth-provider-unknown = Unknown sign-in provider
+auth-state-invalid = Invalid or expired sign-in state
+auth-sign-in-cancelled = Sign-in was cancelled or refused
+auth-sign-in-refused = This account may not sign in here
+auth-account-exists = An account with this email exists; sign in to it to link this one
+auth-provider-failed = The sign-in provider could not confirm who you are
+account-deleted = Account deleted
+state-reset = State reset
+replay-started = Replay started
+email-not-configured = Email ingestion is not configured
+backups-not-configured = Backups are not configured
+backup-not-found = Backup not found
+mcp-session-not-found = MCP session not found
+mcp-too-many-sessions = Too many MCP sessions
+cursor-invalid = Invalid cursor
+cursor-with-offset = cursor and offset cannot be combined
+cursor-sort-mismatch = The cursor was issued for a different sort
+sync-id-required = Change id is required
+response-encode-failed = Failed to encode response: { $error }
+feed-limit-range = limit must be between 1 and { $max }
+nearby-radius-range = radius must be between 0 and { $max } meters
+transfer-peer-unknown = Unknown peer '{ $peer }'
+digest-days-range = days must be at most { $max }
+digest-format-invalid = Invalid format '{ $format }': expected json or text
+plan-capacity-range = capacity must be at most { $max } minutes
+plan-lookahead-range = lookahead must be at most { $max } days
+plan-date-invalid = Invalid date '{ $date }': expected YYYY-MM-DD or today
+plan-todo-not-planned = Todo is not planned for that day
+dashboard-limit-range = limit must be at most { $max }
+homeassistant-data-invalid = Invalid { $service } data: { $error }
+homeassistant-service-unknown = Unknown service '{ $service }'
+event-type-unknown = Unknown event type '{ $name }'
+session-start-failed = Could not start the session
+fixture-unknown = Unknown fixture set '{ $name }'
+todos-seeded = Seeded { $count } todos
+todos-generated = Generated { $count } todos
+profiling-disabled = Profiling is disabled
+profiling-not-built = This build was compiled without the `profiling` feature
+profiling-busy = A profile is already being captured
+profiling-seconds-range = seconds must be between 1 and { $max }
+profiling-frequency-range = frequency must be between 1 and { $max }
+email-duplicate = Already received
+email-subject-empty = Empty subject
+caldav-resource-invalid = Calendar resources are named {"{"}id{"}"}.ics
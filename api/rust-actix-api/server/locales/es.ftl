@@ -0,0 +1,87 @@
+todo-not-found = Tarea no encontrada
+todo-id-invalid = El identificador de la tarea debe ser un UUID
+todo-text-required = El texto de la tarea es obligatorio
+todo-text-too-long = El texto de la tarea debe tener menos de 500 caracteres
+todo-text-invalid = El texto de la tarea es obligatorio y debe tener menos de 500 caracteres
+todo-text-disallowed = El texto de la tarea contiene contenido no permitido
+todo-changed = La tarea ha cambiado desde que se obtuvo
+todo-deleted = Tarea eliminada correctamente
+todo-transferred = Tarea transferida
+todo-transfer-conflict = La tarea cambió durante la transferencia; se conserva aquí y el otro servidor tiene la copia anterior
+completed-cleared = Tareas completadas eliminadas
+client-header-required = Se requiere la cabecera { $header }
+deadline-exceeded = Se superó el plazo de la solicitud
+bulk-edit-empty = changes debe modificar al menos un campo
+bulk-edit-stale = Las tareas cambiaron desde la vista previa; vuelve a previsualizar la edición
+bulk-edit-preview-missing = Vista previa no encontrada o caducada; vuelve a previsualizar la edición
+preference-not-found = No hay ninguna preferencia guardada para este cliente
+push-subscription-not-found = No hay ninguna suscripción push para este cliente
+sms-subscription-not-found = No hay ninguna suscripción SMS para este cliente
+sms-not-configured = Las notificaciones SMS no están configuradas
+sms-code-sent = Código de verificación enviado
+web-push-not-configured = Web Push no está configurado
+web-push-subscription-not-found = No hay ninguna suscripción para este endpoint
+reminder-sent-recently = El recordatorio ya se envió hace poco
+reminder-undelivered = Ningún canal entregó el recordatorio
+webhook-not-found = Webhook no encontrado
+webhook-deleted = Webhook eliminado correctamente
+notifier-not-found = Notificador no encontrado
+notifier-deleted = Notificador eliminado correctamente
+script-not-found = Script no encontrado
+script-deleted = Script eliminado correctamente
+view-not-found = Vista no encontrada
+view-deleted = Vista eliminada
+policy-not-found = Política no encontrada
+policy-deleted = Política eliminada
+feed-token-invalid = Token del feed no válido o ausente
+admin-token-invalid = Token de administración no válido o ausente
+admin-disabled = La API de administración está desactivada
+auth-required = Se requiere autenticación
+auth-csrf-invalid = Token CSRF ausente o no válido
+auth-login-failed = Usuario o contraseña no válidos
+auth-session-disabled = El inicio de sesión por sesión no está activado
+auth-logged-out = Sesión cerrada
+auth-provider-unknown = Proveedor de inicio de sesión desconocido
+auth-state-invalid = Estado de inicio de sesión no válido o caducado
+auth-sign-in-cancelled = El inicio de sesión se canceló o fue rechazado
+auth-sign-in-refused = Esta cuenta no puede iniciar sesión aquí
+auth-account-exists = Ya existe una cuenta con este correo; inicia sesión en ella para vincular esta
+auth-provider-failed = El proveedor de inicio de sesión no pudo confirmar tu identidad
+account-deleted = Cuenta eliminada
+state-reset = Estado restablecido
+replay-started = Reproducción iniciada
+email-not-configured = La recepción de correo no está configurada
+backups-not-configured = Las copias de seguridad no están configuradas
+backup-not-found = Copia de seguridad no encontrada
+mcp-session-not-found = Sesión MCP no encontrada
+mcp-too-many-sessions = Demasiadas sesiones MCP
+cursor-invalid = Cursor no válido
+cursor-with-offset = cursor y offset no se pueden combinar
+cursor-sort-mismatch = El cursor se emitió para otra ordenación
+sync-id-required = El identificador del cambio es obligatorio
+response-encode-failed = No se pudo codificar la respuesta: { $error }
+feed-limit-range = limit debe estar entre 1 y { $max }
+nearby-radius-range = radius debe estar entre 0 y { $max } metros
+transfer-peer-unknown = Par desconocido «{ $peer }»
+digest-days-range = days debe ser como máximo { $max }
+digest-format-invalid = Formato no válido «{ $format }»: se esperaba json o text
+plan-capacity-range = capacity debe ser como máximo { $max } minutos
+plan-lookahead-range = lookahead debe ser como máximo { $max } días
+plan-date-invalid = Fecha no válida «{ $date }»: se esperaba AAAA-MM-DD o today
+plan-todo-not-planned = La tarea no está planificada para ese día
+dashboard-limit-range = limit debe ser como máximo { $max }
+homeassistant-data-invalid = Datos de { $service } no válidos: { $error }
+homeassistant-service-unknown = Servicio desconocido «{ $service }»
+event-type-unknown = Tipo de evento desconocido «{ $name }»
+session-start-failed = No se pudo iniciar la sesión
+fixture-unknown = Conjunto de ejemplo desconocido «{ $name }»
+todos-seeded = { $count } tareas creadas
+todos-generated = { $count } tareas generadas
+profiling-disabled = El perfilado está desactivado
+profiling-not-built = Esta versión se compiló sin la función `profiling`
+profiling-busy = Ya se está capturando un perfil
+profiling-seconds-range = seconds debe estar entre 1 y { $max }
+profiling-frequency-range = frequency debe estar entre 1 y { $max }
+email-duplicate = Ya recibido
+email-subject-empty = Asunto vacío
+caldav-resource-invalid = Los recursos del calendario se llaman {"{"}id{"}"}.ics
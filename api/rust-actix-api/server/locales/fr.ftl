@@ -0,0 +1,87 @@
+todo-not-found = Tâche introuvable
+todo-id-invalid = L’identifiant de la tâche doit être un UUID
+todo-text-required = Le texte de la tâche est obligatoire
+todo-text-too-long = Le texte de la tâche doit faire moins de 500 caractères
+todo-text-invalid = Le texte de la tâche est obligatoire et doit faire moins de 500 caractères
+todo-text-disallowed = Le texte de la tâche contient du contenu interdit
+todo-changed = La tâche a changé depuis sa récupération
+todo-deleted = Tâche supprimée
+todo-transferred = Tâche transférée
+todo-transfer-conflict = La tâche a changé pendant le transfert ; elle reste ici et le pair a la copie précédente
+completed-cleared = Tâches terminées supprimées
+client-header-required = L’en-tête { $header } est obligatoire
+deadline-exceeded = Délai de la requête dépassé
+bulk-edit-empty = changes doit modifier au moins un champ
+bulk-edit-stale = Des tâches ont changé depuis l’aperçu ; prévisualisez de nouveau la modification
+bulk-edit-preview-missing = Aperçu introuvable ou expiré ; prévisualisez de nouveau la modification
+preference-not-found = Aucune préférence enregistrée pour ce client
+push-subscription-not-found = Aucun abonnement push pour ce client
+sms-subscription-not-found = Aucun abonnement SMS pour ce client
+sms-not-configured = Les notifications SMS ne sont pas configurées
+sms-code-sent = Code de vérification envoyé
+web-push-not-configured = Web Push n’est pas configuré
+web-push-subscription-not-found = Aucun abonnement pour ce point de terminaison
+reminder-sent-recently = Le rappel a déjà été envoyé récemment
+reminder-undelivered = Aucun canal n'a remis le rappel
+webhook-not-found = Webhook introuvable
+webhook-deleted = Webhook supprimé
+notifier-not-found = Notificateur introuvable
+notifier-deleted = Notificateur supprimé
+script-not-found = Script introuvable
+script-deleted = Script supprimé
+view-not-found = Vue introuvable
+view-deleted = Vue supprimée
+policy-not-found = Règle introuvable
+policy-deleted = Règle supprimée
+feed-token-invalid = Jeton de flux invalide ou manquant
+admin-token-invalid = Jeton d’administration invalide ou manquant
+admin-disabled = L’API d’administration est désactivée
+auth-required = Authentification requise
+auth-csrf-invalid = Jeton CSRF manquant ou invalide
+auth-login-failed = Nom d’utilisateur ou mot de passe invalide
+auth-session-disabled = La connexion par session n’est pas activée
+auth-logged-out = Déconnecté
+auth-provider-unknown = Fournisseur de connexion inconnu
+auth-state-invalid = État de connexion invalide ou expiré
+auth-sign-in-cancelled = La connexion a été annulée ou refusée
+auth-sign-in-refused = Ce compte ne peut pas se connecter ici
+auth-account-exists = Un compte avec cet e-mail existe déjà ; connectez-vous à celui-ci pour lier ce compte
+auth-provider-failed = Le fournisseur de connexion n’a pas pu confirmer votre identité
+account-deleted = Compte supprimé
+state-reset = État réinitialisé
+replay-started = Rejeu démarré
+email-not-configured = La réception d’e-mails n’est pas configurée
+backups-not-configured = Les sauvegardes ne sont pas configurées
+backup-not-found = Sauvegarde introuvable
+mcp-session-not-found = Session MCP introuvable
+mcp-too-many-sessions = Trop de sessions MCP
+cursor-invalid = Curseur invalide
+cursor-with-offset = cursor et offset ne peuvent pas être combinés
+cursor-sort-mismatch = Le curseur a été émis pour un autre tri
+sync-id-required = L’identifiant de la modification est obligatoire
+response-encode-failed = Impossible d’encoder la réponse : { $error }
+feed-limit-range = limit doit être compris entre 1 et { $max }
+nearby-radius-range = radius doit être compris entre 0 et { $max } mètres
+transfer-peer-unknown = Pair inconnu « { $peer } »
+digest-days-range = days doit être au plus { $max }
+digest-format-invalid = Format invalide « { $format } » : json ou text attendu
+plan-capacity-range = capacity doit être au plus { $max } minutes
+plan-lookahead-range = lookahead doit être au plus { $max } jours
+plan-date-invalid = Date invalide « { $date } » : AAAA-MM-JJ ou today attendu
+plan-todo-not-planned = La tâche n’est pas prévue ce jour-là
+dashboard-limit-range = limit doit être au plus { $max }
+homeassistant-data-invalid = Données { $service } invalides : { $error }
+homeassistant-service-unknown = Service inconnu « { $service } »
+event-type-unknown = Type d’événement inconnu « { $name } »
+session-start-failed = Impossible de démarrer la session
+fixture-unknown = Jeu d’exemples inconnu « { $name } »
+todos-seeded = { $count } tâches créées
+todos-generated = { $count } tâches générées
+profiling-disabled = Le profilage est désactivé
+profiling-not-built = Cette version a été compilée sans la fonctionnalité `profiling`
+profiling-busy = Un profil est déjà en cours de capture
+profiling-seconds-range = seconds doit être compris entre 1 et { $max }
+profiling-frequency-range = frequency doit être compris entre 1 et { $max }
+email-duplicate = Déjà reçu
+email-subject-empty = Objet vide
+caldav-resource-invalid = Les ressources du calendrier s’appellent {"{"}id{"}"}.ics
@@ -0,0 +1,310 @@
+use crate::config::Config;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use spicy_todo_core::models::{Todo, MAX_ESTIMATE_MINUTES, MAX_OCCURRENCES};
+use uuid::Uuid;
+
+/// What an action operates on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Resource {
+    /// The todo list as a whole.
+    Todos,
+    /// One todo, whose id fills `{id}` in the path.
+    Todo,
+}
+
+/// One operation a command palette can offer, with enough of its request
+/// shape to build a form for it.
+#[derive(Debug, Clone, Serialize)]
+pub struct Action {
+    pub id: &'static str,
+    pub title: &'static str,
+    pub method: &'static str,
+    pub path: &'static str,
+    pub resource: Resource,
+    /// Values the todo's fields must have for the action to apply, e.g.
+    /// only open todos can be completed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub when: Option<Value>,
+    /// JSON Schema of the query string.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub query: Option<Value>,
+    /// JSON Schema of the request body.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub body: Option<Value>,
+}
+
+/// Query of `GET /api/actions`.
+#[derive(Debug, Default, Deserialize)]
+pub struct ActionsQuery {
+    pub resource: Option<Resource>,
+    /// Only the actions that apply to this todo.
+    pub id: Option<Uuid>,
+}
+
+impl Action {
+    fn new(
+        id: &'static str,
+        title: &'static str,
+        method: &'static str,
+        path: &'static str,
+        resource: Resource,
+    ) -> Self {
+        Action {
+            id,
+            title,
+            method,
+            path,
+            resource,
+            when: None,
+            query: None,
+            body: None,
+        }
+    }
+
+    fn when(mut self, when: Value) -> Self {
+        self.when = Some(when);
+        self
+    }
+
+    fn query(mut self, query: Value) -> Self {
+        self.query = Some(query);
+        self
+    }
+
+    fn body(mut self, body: Value) -> Self {
+        self.body = Some(body);
+        self
+    }
+
+    /// Whether this is a todo action whose `when` matches `todo`.
+    pub fn applies_to(&self, todo: &Todo) -> bool {
+        if self.resource != Resource::Todo {
+            return false;
+        }
+        let Some(Value::Object(when)) = &self.when else {
+            return true;
+        };
+        let todo = serde_json::to_value(todo).unwrap_or_default();
+        when.iter().all(|(field, value)| &todo[field] == value)
+    }
+}
+
+pub fn object(properties: Value, required: &[&str]) -> Value {
+    json!({
+        "type": "object",
+        "properties": properties,
+        "required": required
+    })
+}
+
+/// The writable todo fields, as accepted by create and update.
+pub fn todo_fields() -> Value {
+    json!({
+        "text": { "type": "string", "minLength": 1, "maxLength": 500 },
+        "priority": { "enum": ["low", "medium", "high"] },
+        "completed": { "type": "boolean" },
+        "dueDate": {
+            "type": "string",
+            "description": "YYYY-MM-DD, or a phrase such as 'next friday'"
+        },
+        "reminderTime": { "type": "string", "pattern": "^[0-2][0-9]:[0-5][0-9]$" },
+        "recurrence": { "enum": ["daily", "weekly", "monthly"] },
+        "recurrenceEnd": {
+            "oneOf": [
+                object(
+                    json!({ "afterOccurrences": {
+                        "type": "integer", "minimum": 1, "maximum": MAX_OCCURRENCES
+                    }}),
+                    &["afterOccurrences"],
+                ),
+                object(
+                    json!({ "until": { "type": "string", "format": "date" } }),
+                    &["until"],
+                ),
+            ]
+        },
+        "estimateMinutes": { "type": "integer", "minimum": 1, "maximum": MAX_ESTIMATE_MINUTES },
+        "location": object(
+            json!({
+                "name": { "type": "string" },
+                "lat": { "type": "number", "minimum": -90, "maximum": 90 },
+                "lng": { "type": "number", "minimum": -180, "maximum": 180 }
+            }),
+            &[],
+        )
+    })
+}
+
+/// Every action this server offers with `config`. Kept in step with
+/// `routes::configure_routes` by `test_actions_match_routes`.
+pub fn catalog(config: &Config) -> Vec<Action> {
+    use Resource::{Todo, Todos};
+
+    let mut actions = vec![
+        Action::new("todo.create", "New todo", "POST", "/api/todos", Todos)
+            .body(object(todo_fields(), &["text"])),
+        Action::new(
+            "todo.quickAdd",
+            "Quick add",
+            "POST",
+            "/api/todos/quick",
+            Todos,
+        )
+        .body(object(
+            json!({ "text": {
+                "type": "string",
+                "minLength": 1,
+                "description": "e.g. 'Pay rent tomorrow 9am !high #home'"
+            }}),
+            &["text"],
+        )),
+        Action::new(
+            "todos.clearCompleted",
+            "Clear completed todos",
+            "DELETE",
+            "/api/todos/completed",
+            Todos,
+        ),
+        Action::new(
+            "todos.bulkEdit.preview",
+            "Bulk edit",
+            "POST",
+            "/api/todos/bulk-edit/preview",
+            Todos,
+        )
+        .body(object(
+            json!({
+                "filter": object(
+                    json!({
+                        "filter": { "enum": ["all", "active", "completed"] },
+                        "search": { "type": "string" },
+                        "priority": { "enum": ["low", "medium", "high"] },
+                        "ids": { "type": "array", "items": { "type": "string" } }
+                    }),
+                    &[],
+                ),
+                "changes": object(todo_fields(), &[])
+            }),
+            &["changes"],
+        )),
+        Action::new(
+            "todos.bulkEdit.apply",
+            "Apply bulk edit",
+            "POST",
+            "/api/todos/bulk-edit/apply",
+            Todos,
+        )
+        .body(object(json!({ "token": { "type": "string" } }), &["token"])),
+        Action::new("todos.digest", "Agenda", "GET", "/api/todos/digest", Todos).query(object(
+            json!({
+                "days": { "type": "integer", "minimum": 0 },
+                "format": { "enum": ["json", "text"] }
+            }),
+            &[],
+        )),
+        Action::new(
+            "todos.nearby",
+            "Todos nearby",
+            "GET",
+            "/api/todos/nearby",
+            Todos,
+        )
+        .query(object(
+            json!({
+                "lat": { "type": "number", "minimum": -90, "maximum": 90 },
+                "lng": { "type": "number", "minimum": -180, "maximum": 180 },
+                "radius": { "type": "number", "exclusiveMinimum": 0, "maximum": 100000 }
+            }),
+            &["lat", "lng"],
+        )),
+        Action::new(
+            "todos.stats",
+            "Statistics",
+            "GET",
+            "/api/todos/stats/summary",
+            Todos,
+        ),
+        Action::new(
+            "todo.complete",
+            "Complete",
+            "PATCH",
+            "/api/todos/{id}/toggle",
+            Todo,
+        )
+        .when(json!({ "completed": false })),
+        Action::new(
+            "todo.reopen",
+            "Reopen",
+            "PATCH",
+            "/api/todos/{id}/toggle",
+            Todo,
+        )
+        .when(json!({ "completed": true })),
+        Action::new("todo.update", "Edit", "PUT", "/api/todos/{id}", Todo)
+            .body(object(todo_fields(), &[])),
+        Action::new("todo.delete", "Delete", "DELETE", "/api/todos/{id}", Todo),
+        Action::new(
+            "todo.missed",
+            "Missed occurrences",
+            "GET",
+            "/api/todos/{id}/missed",
+            Todo,
+        ),
+        Action::new(
+            "todo.export",
+            "Export",
+            "GET",
+            "/api/todos/{id}/export",
+            Todo,
+        ),
+    ];
+    if !config.transfer_peers.is_empty() {
+        let peers: Vec<&String> = config.transfer_peers.keys().collect();
+        actions.push(
+            Action::new(
+                "todo.transfer",
+                "Send to another instance",
+                "POST",
+                "/api/todos/{id}/transfer",
+                Todo,
+            )
+            .body(object(json!({ "peer": { "enum": peers } }), &["peer"])),
+        );
+    }
+    actions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spicy_todo_core::models::TodoCreate;
+    use spicy_todo_core::TodoService;
+
+    #[test]
+    fn test_applies_to_matches_when() {
+        let service = TodoService::new_empty();
+        let todo = service.create(TodoCreate {
+            text: "Pay rent".to_string(),
+            ..Default::default()
+        });
+        let ids = |todo: &Todo| -> Vec<&str> {
+            catalog(&Config::default())
+                .into_iter()
+                .filter(|action| action.applies_to(todo))
+                .map(|action| action.id)
+                .collect()
+        };
+        let open = ids(&todo);
+        assert!(open.contains(&"todo.complete"));
+        assert!(!open.contains(&"todo.reopen"));
+        assert!(!open.contains(&"todo.create"));
+        assert!(!open.contains(&"todo.transfer"));
+
+        let done = service.toggle(todo.id).unwrap();
+        let closed = ids(&done);
+        assert!(closed.contains(&"todo.reopen"));
+        assert!(!closed.contains(&"todo.complete"));
+    }
+}
@@ -0,0 +1,97 @@
+use crate::config::AuditRetentionSettings;
+use crate::scheduler::{Outcome, Schedule, Scheduler};
+use actix_web::web;
+use chrono::{Duration as ChronoDuration, Utc};
+use spicy_todo_core::audit::AuditEntry;
+use spicy_todo_core::TodoService;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+
+/// Prunes audit entries older than the retention at startup and after
+/// every UTC midnight, archiving them first when an archive is configured.
+/// Every replica keeps its own history, so every replica prunes it.
+pub fn schedule(
+    scheduler: &mut Scheduler,
+    service: web::Data<TodoService>,
+    settings: AuditRetentionSettings,
+) {
+    scheduler.register("audit-retention", Schedule::Daily, move || {
+        let (service, settings) = (service.clone(), settings.clone());
+        async move {
+            let pruned = web::block(move || prune(&service, &settings))
+                .await
+                .map_err(|e| e.to_string())??;
+            if pruned == 0 {
+                return Ok(Outcome::Skipped);
+            }
+            println!("🧾 Pruned {} audit entries past retention", pruned);
+            Ok(Outcome::Done)
+        }
+    });
+}
+
+/// Prunes what `settings` says is too old. Returns how many entries went.
+pub fn prune(service: &TodoService, settings: &AuditRetentionSettings) -> Result<usize, String> {
+    let max_age = ChronoDuration::from_std(settings.max_age).map_err(|e| e.to_string())?;
+    let now = Utc::now();
+    service.prune_audit_before(now - max_age, &mut |entries| {
+        let Some(dir) = &settings.archive_dir else {
+            return Ok(());
+        };
+        let path = dir.join(format!("audit-{}.jsonl", now.format("%Y-%m-%d")));
+        archive(&path, entries).map_err(|e| format!("archive to {}: {}", path.display(), e))
+    })
+}
+
+/// Appends `entries` to `path`, one JSON object a line.
+fn archive(path: &Path, entries: &[AuditEntry]) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut lines = Vec::new();
+    for entry in entries {
+        serde_json::to_writer(&mut lines, entry)?;
+        lines.push(b'\n');
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    file.write_all(&lines)?;
+    file.sync_all()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spicy_todo_core::events::EventFilter;
+    use spicy_todo_core::models::TodoCreate;
+    use std::time::Duration;
+
+    #[test]
+    fn test_prune_archives_before_dropping() {
+        let dir = std::env::temp_dir().join(format!("spicy-audit-{}", uuid::Uuid::new_v4()));
+        let service = TodoService::new_empty();
+        service.create(TodoCreate {
+            text: "Rotate the keys".to_string(),
+            ..Default::default()
+        });
+        let keep = AuditRetentionSettings {
+            max_age: Duration::from_secs(3600),
+            archive_dir: Some(dir.clone()),
+        };
+        assert_eq!(prune(&service, &keep), Ok(0));
+        assert!(!dir.exists());
+
+        let drop_all = AuditRetentionSettings {
+            max_age: Duration::ZERO,
+            ..keep
+        };
+        assert_eq!(prune(&service, &drop_all), Ok(1));
+        assert!(service.audit(&EventFilter::default()).is_empty());
+        let archived = fs::read_dir(&dir).unwrap().next().unwrap().unwrap().path();
+        let line = fs::read_to_string(archived).unwrap();
+        let entry: AuditEntry = serde_json::from_str(line.trim()).unwrap();
+        assert_eq!(entry.sequence, 1);
+        assert!(!line.contains("Rotate the keys"));
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,349 @@
+use crate::config::{AuthSettings, Config};
+use crate::errors::ApiError;
+use crate::handlers::constant_time_eq;
+use crate::i18n;
+use crate::oidc::Oidc;
+use actix_session::config::{CookieContentSecurity, PersistentSession};
+use actix_session::storage::CookieSessionStore;
+use actix_session::{SessionExt, SessionMiddleware};
+use actix_web::body::{BoxBody, MessageBody};
+use actix_web::cookie::{time, Key, SameSite};
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::{header, Method, StatusCode};
+use actix_web::middleware::Next;
+use actix_web::{web, HttpResponse};
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+use serde::Deserialize;
+use spicy_todo_core::RequestContext;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// Cookie carrying the session, encrypted with the session key.
+pub const SESSION_COOKIE: &str = "spicy_session";
+/// Request header echoing the session's CSRF token on mutating requests.
+pub const CSRF_HEADER: &str = "x-csrf-token";
+/// Session keys.
+pub const USER_KEY: &str = "user";
+pub const CSRF_KEY: &str = "csrf";
+
+/// How requests to the API prove who sent them.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AuthMode {
+    /// Anyone can use the API, as before auth existed.
+    #[default]
+    Off,
+    /// `Authorization: Bearer` with one of the configured tokens.
+    Token,
+    /// A browser session from `POST /api/auth/login`, in a cookie.
+    Session,
+}
+
+impl FromStr for AuthMode {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_lowercase().as_str() {
+            "off" => Ok(AuthMode::Off),
+            "token" => Ok(AuthMode::Token),
+            "session" => Ok(AuthMode::Session),
+            other => Err(format!(
+                "Invalid auth mode '{}': expected off, token or session",
+                other
+            )),
+        }
+    }
+}
+
+impl AuthMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuthMode::Off => "off",
+            AuthMode::Token => "token",
+            AuthMode::Session => "session",
+        }
+    }
+
+    /// How the todo endpoints authenticate, as capabilities report it.
+    pub fn scheme(&self) -> &'static str {
+        match self {
+            AuthMode::Off => "none",
+            AuthMode::Token => "bearer",
+            AuthMode::Session => "session",
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LoginRequest {
+    pub username: String,
+    pub password: String,
+}
+
+/// The configured auth mode with what it checks against: the tokens, or
+/// the users and the key their session cookies are sealed with.
+pub struct Auth {
+    mode: AuthMode,
+    tokens: Vec<String>,
+    /// Name to Argon2 PHC hash.
+    users: BTreeMap<String, String>,
+    key: Key,
+    session_ttl: Duration,
+    cookie_secure: bool,
+    oidc: Oidc,
+}
+
+impl Auth {
+    pub fn from_settings(settings: &AuthSettings) -> Result<Self, String> {
+        let users = match &settings.users_file {
+            Some(path) => load_users(path)?,
+            None => BTreeMap::new(),
+        };
+        let oidc = Oidc::from_settings(settings.oidc.as_ref())?;
+        match settings.mode {
+            AuthMode::Token if settings.tokens.is_empty() => {
+                return Err("AUTH_MODE=token needs AUTH_TOKENS".to_string())
+            }
+            AuthMode::Session if users.is_empty() && !oidc.is_enabled() => {
+                return Err(
+                    "AUTH_MODE=session needs users in AUTH_USERS_FILE or an OIDC provider"
+                        .to_string(),
+                )
+            }
+            AuthMode::Off | AuthMode::Token if oidc.is_enabled() => {
+                return Err("OIDC sign-in needs AUTH_MODE=session".to_string())
+            }
+            _ => {}
+        }
+        let key = match &settings.session_key {
+            Some(hex_key) => {
+                let bytes = hex::decode(hex_key.trim())
+                    .ok()
+                    .filter(|bytes| bytes.len() >= 64)
+                    .ok_or("AUTH_SESSION_KEY must be at least 128 hex characters")?;
+                Key::from(&bytes)
+            }
+            None => Key::generate(),
+        };
+        Ok(Auth {
+            mode: settings.mode,
+            tokens: settings.tokens.clone(),
+            users,
+            key,
+            session_ttl: settings.session_ttl,
+            cookie_secure: settings.cookie_secure,
+            oidc,
+        })
+    }
+
+    pub fn mode(&self) -> AuthMode {
+        self.mode
+    }
+
+    pub fn oidc(&self) -> &Oidc {
+        &self.oidc
+    }
+
+    /// Whether `name` is in the users file.
+    pub fn has_user(&self, name: &str) -> bool {
+        self.users.contains_key(name)
+    }
+
+    /// Seals sessions into an encrypted cookie that expires `session_ttl`
+    /// after the last request that changed it.
+    pub fn session_middleware(&self) -> SessionMiddleware<CookieSessionStore> {
+        let ttl = time::Duration::seconds(self.session_ttl.as_secs() as i64);
+        SessionMiddleware::builder(CookieSessionStore::default(), self.key.clone())
+            .cookie_name(SESSION_COOKIE.to_string())
+            .cookie_content_security(CookieContentSecurity::Private)
+            .cookie_http_only(true)
+            .cookie_same_site(SameSite::Lax)
+            .cookie_secure(self.cookie_secure)
+            .session_lifecycle(PersistentSession::default().session_ttl(ttl))
+            .build()
+    }
+
+    /// Whether `password` is `username`'s. Slow by design; keep it off the
+    /// workers.
+    pub fn check_password(&self, username: &str, password: &str) -> bool {
+        let Some(hash) = self.users.get(username) else {
+            return false;
+        };
+        PasswordHash::new(hash).is_ok_and(|hash| {
+            Argon2::default()
+                .verify_password(password.as_bytes(), &hash)
+                .is_ok()
+        })
+    }
+
+    fn accepts_token(&self, token: &str) -> bool {
+        self.tokens
+            .iter()
+            .any(|expected| constant_time_eq(token.as_bytes(), expected.as_bytes()))
+    }
+}
+
+/// Reads a users file: `name:hash` lines with Argon2 PHC hashes, as
+/// `spicy-todo-rust-api hash-password` prints them. Blank lines and `#`
+/// comments are skipped.
+pub fn load_users(path: &Path) -> Result<BTreeMap<String, String>, String> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| format!("Cannot read {}: {}", path.display(), e))?;
+    let mut users = BTreeMap::new();
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (name, hash) = line
+            .split_once(':')
+            .filter(|(name, hash)| !name.is_empty() && PasswordHash::new(hash).is_ok())
+            .ok_or_else(|| {
+                format!(
+                    "{} line {}: expected name:argon2-hash",
+                    path.display(),
+                    i + 1
+                )
+            })?;
+        users.insert(name.to_string(), hash.to_string());
+    }
+    Ok(users)
+}
+
+/// An Argon2id PHC hash of `password`, for a users file.
+pub fn hash_password(password: &str) -> Result<String, String> {
+    let salt = SaltString::encode_b64(&rand::random::<[u8; 16]>()).map_err(|e| e.to_string())?;
+    Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|e| e.to_string())
+}
+
+pub fn new_csrf_token() -> String {
+    hex::encode(rand::random::<[u8; 32]>())
+}
+
+/// Paths that are open in every mode: login itself, discovery, and the
+/// endpoints checking their own token or signature. The feed only checks
+/// one when `FEED_TOKEN` is set, so without it the feed needs the usual
+/// auth.
+fn is_exempt(path: &str, feed_token: bool) -> bool {
+    let path = ["/api/v1", "/api/v2"]
+        .iter()
+        .find_map(|prefix| path.strip_prefix(prefix))
+        .map_or(path.to_string(), |rest| format!("/api{}", rest));
+    let protected = path == "/api" || path.starts_with("/api/") || path.starts_with("/dav");
+    !protected
+        || ["/api/auth/", "/api/admin/", "/api/ingest/"]
+            .iter()
+            .any(|prefix| path.starts_with(prefix))
+        || ["/api/capabilities", "/api/conformance"].contains(&path.as_str())
+        || (feed_token && path == "/api/todos/feed.atom")
+}
+
+fn unauthorized() -> HttpResponse {
+    ApiError::status(StatusCode::UNAUTHORIZED, i18n::message("auth-required")).into_response()
+}
+
+/// Turns away API requests without a valid token or session, as the
+/// configured mode asks. In session mode, requests that change anything
+/// must also send the session's CSRF token in `X-CSRF-Token`; the cookie
+/// alone would be sent by any site that makes the browser post here.
+/// The session's user becomes the request context's.
+pub async fn require_auth(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<BoxBody>, actix_web::Error> {
+    let mode = req
+        .app_data::<web::Data<Auth>>()
+        .map_or(AuthMode::Off, |auth| auth.mode());
+    let feed_token = req
+        .app_data::<web::Data<Config>>()
+        .is_some_and(|config| config.feed_token.is_some());
+    let exempt = req.method() == Method::OPTIONS || is_exempt(req.path(), feed_token);
+    if mode == AuthMode::Off || exempt {
+        return Ok(next.call(req).await?.map_into_boxed_body());
+    }
+
+    let auth = req.app_data::<web::Data<Auth>>().cloned().unwrap();
+    if mode == AuthMode::Token {
+        let token = req
+            .headers()
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "));
+        if !token.is_some_and(|token| auth.accepts_token(token)) {
+            return Ok(req.into_response(unauthorized()));
+        }
+        return Ok(next.call(req).await?.map_into_boxed_body());
+    }
+
+    let session = req.get_session();
+    let Ok(Some(user)) = session.get::<String>(USER_KEY) else {
+        return Ok(req.into_response(unauthorized()));
+    };
+    let safe = matches!(*req.method(), Method::GET | Method::HEAD);
+    if !safe {
+        let expected = session.get::<String>(CSRF_KEY).ok().flatten();
+        let sent = req
+            .headers()
+            .get(CSRF_HEADER)
+            .and_then(|value| value.to_str().ok());
+        let valid = matches!((expected, sent), (Some(expected), Some(sent))
+            if constant_time_eq(expected.as_bytes(), sent.as_bytes()));
+        if !valid {
+            let response =
+                ApiError::status(StatusCode::FORBIDDEN, i18n::message("auth-csrf-invalid"));
+            return Ok(req.into_response(response.into_response()));
+        }
+    }
+    let context = RequestContext {
+        user: Some(user),
+        ..RequestContext::current().unwrap_or_default()
+    };
+    Ok(context.scope(next.call(req)).await?.map_into_boxed_body())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exempts_login_and_self_checked_endpoints() {
+        assert!(is_exempt("/api/auth/login", false));
+        assert!(is_exempt("/api/v2/admin/seed", false));
+        assert!(is_exempt("/health", false));
+        assert!(!is_exempt("/api/todos", false));
+        assert!(!is_exempt("/api/v1/todos/1", false));
+        assert!(!is_exempt("/dav/todos/", false));
+        // The feed is open only when it has a token of its own.
+        assert!(is_exempt("/api/todos/feed.atom", true));
+        assert!(!is_exempt("/api/todos/feed.atom", false));
+        assert!(!is_exempt("/api/v1/todos/feed.atom", false));
+    }
+
+    #[test]
+    fn test_checks_hashed_passwords() {
+        let hash = hash_password("hunter2").unwrap();
+        let path = std::env::temp_dir().join(format!("users-{}", uuid::Uuid::new_v4()));
+        std::fs::write(&path, format!("# team\nalice:{}\n", hash)).unwrap();
+        let settings = AuthSettings {
+            mode: AuthMode::Session,
+            users_file: Some(path.clone()),
+            ..Default::default()
+        };
+        let auth = Auth::from_settings(&settings).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(auth.check_password("alice", "hunter2"));
+        assert!(!auth.check_password("alice", "hunter3"));
+        assert!(!auth.check_password("bob", "hunter2"));
+        assert!(Auth::from_settings(&AuthSettings {
+            mode: AuthMode::Token,
+            ..Default::default()
+        })
+        .is_err());
+    }
+}
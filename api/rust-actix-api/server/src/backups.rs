@@ -0,0 +1,471 @@
+// Without the `backups` feature there is no transport to hand keys and
+// bodies to; configuring backups then fails at startup.
+#![cfg_attr(not(any(feature = "backups", test)), allow(unused_variables))]
+
+use crate::config::{BackupSettings, Config};
+use crate::plugins::{Kind, Plugin};
+use crate::scheduler::{Outcome, Schedule, Scheduler};
+use actix_web::web;
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Nonce};
+use serde::Serialize;
+use spicy_todo_core::models::Todo;
+use spicy_todo_core::TodoService;
+use std::cell::Cell;
+use std::collections::BTreeSet;
+use std::rc::Rc;
+use std::time::Duration;
+
+/// Leads every encrypted backup so a wrong object fails fast on restore.
+const MAGIC: &[u8; 4] = b"STB1";
+const NONCE_LEN: usize = 12;
+const KEY_SUFFIX: &str = ".json.enc";
+/// Ids of erased todos, next to the backups, which still hold them until
+/// they age out. Restores leave these todos out.
+const ERASED_NAME: &str = "erased.json.enc";
+
+/// AES-256-GCM with a random nonce per backup. Sealed layout:
+/// `MAGIC || nonce || ciphertext+tag`.
+pub struct Cipher(Aes256Gcm);
+
+impl Cipher {
+    /// Parses a 256-bit key given as 64 hex characters.
+    pub fn from_hex(hex: &str) -> Result<Self, String> {
+        let hex = hex.trim();
+        if hex.len() != 64 || !hex.is_ascii() {
+            return Err("Encryption key must be 64 hex characters (256 bits)".to_string());
+        }
+        let bytes = (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16))
+            .collect::<Result<Vec<u8>, _>>()
+            .map_err(|_| "Encryption key must be 64 hex characters (256 bits)".to_string())?;
+        Aes256Gcm::new_from_slice(&bytes)
+            .map(Cipher)
+            .map_err(|e| e.to_string())
+    }
+
+    pub fn seal(&self, plaintext: &[u8]) -> Vec<u8> {
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .0
+            .encrypt(&nonce, plaintext)
+            .expect("AES-GCM encryption of an in-memory buffer cannot fail");
+        let mut sealed = Vec::with_capacity(MAGIC.len() + NONCE_LEN + ciphertext.len());
+        sealed.extend_from_slice(MAGIC);
+        sealed.extend_from_slice(&nonce);
+        sealed.extend_from_slice(&ciphertext);
+        sealed
+    }
+
+    pub fn open(&self, sealed: &[u8]) -> Result<Vec<u8>, String> {
+        let body = sealed
+            .strip_prefix(MAGIC)
+            .filter(|body| body.len() > NONCE_LEN)
+            .ok_or("Not an encrypted spicy-todo backup")?;
+        let (nonce, ciphertext) = body.split_at(NONCE_LEN);
+        self.0
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| "Backup could not be decrypted; wrong key or corrupted".to_string())
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BackupInfo {
+    pub key: String,
+    pub size: u64,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum BackupError {
+    NotFound,
+    Failed(String),
+}
+
+impl std::fmt::Display for BackupError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BackupError::NotFound => write!(f, "Backup not found"),
+            BackupError::Failed(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+enum Target {
+    #[cfg(feature = "backups")]
+    S3(Box<s3::Bucket>),
+    #[cfg(test)]
+    Memory(std::sync::Mutex<std::collections::BTreeMap<String, Vec<u8>>>),
+}
+
+/// Encrypted JSON snapshots of the collection in an S3-compatible bucket.
+/// Keys embed the UTC creation time, so they sort oldest to newest.
+pub struct Backups {
+    target: Target,
+    prefix: String,
+    retention: usize,
+    cipher: Cipher,
+}
+
+impl Backups {
+    pub fn from_settings(settings: &BackupSettings) -> Result<Self, String> {
+        let key = settings
+            .encryption_key
+            .as_deref()
+            .ok_or("BACKUP_ENCRYPTION_KEY is required when backups are enabled")?;
+        Ok(Backups {
+            target: s3_target(settings)?,
+            prefix: settings.prefix.clone(),
+            retention: settings.retention.max(1),
+            cipher: Cipher::from_hex(key)?,
+        })
+    }
+
+    #[cfg(test)]
+    pub fn in_memory(retention: usize) -> Self {
+        Backups {
+            target: Target::Memory(Default::default()),
+            prefix: "backups/".to_string(),
+            retention,
+            cipher: Cipher::from_hex(&"ab".repeat(32)).unwrap(),
+        }
+    }
+
+    /// Whether `key` names one of this instance's backups.
+    pub fn owns(&self, key: &str) -> bool {
+        key.strip_prefix(&self.prefix)
+            .is_some_and(|name| name.starts_with("todos-") && name.ends_with(KEY_SUFFIX))
+    }
+
+    /// Uploads the current collection and returns the new backup's key.
+    pub async fn backup_now(&self, service: &TodoService) -> Result<String, BackupError> {
+        let todos = service.get_all(None, None, None);
+        let json = serde_json::to_vec(&todos).map_err(|e| BackupError::Failed(e.to_string()))?;
+        let key = format!(
+            "{}todos-{}{}",
+            self.prefix,
+            chrono::Utc::now().format("%Y%m%dT%H%M%S%.3fZ"),
+            KEY_SUFFIX
+        );
+        self.put(&key, self.cipher.seal(&json)).await?;
+        Ok(key)
+    }
+
+    /// Backups newest first.
+    pub async fn list(&self) -> Result<Vec<BackupInfo>, BackupError> {
+        let mut backups: Vec<BackupInfo> = self
+            .list_objects()
+            .await?
+            .into_iter()
+            .filter(|backup| self.owns(&backup.key))
+            .collect();
+        backups.sort_by(|a, b| b.key.cmp(&a.key));
+        Ok(backups)
+    }
+
+    /// Deletes all but the newest `retention` backups. Returns deleted keys.
+    pub async fn prune(&self) -> Result<Vec<String>, BackupError> {
+        let expired: Vec<String> = self
+            .list()
+            .await?
+            .into_iter()
+            .skip(self.retention)
+            .map(|backup| backup.key)
+            .collect();
+        for key in &expired {
+            self.delete(key).await?;
+        }
+        Ok(expired)
+    }
+
+    /// Downloads and decrypts a backup, leaving out erased todos.
+    pub async fn fetch(&self, key: &str) -> Result<Vec<Todo>, BackupError> {
+        if !self.owns(key) {
+            return Err(BackupError::NotFound);
+        }
+        let sealed = self.get(key).await?;
+        let json = self.cipher.open(&sealed).map_err(BackupError::Failed)?;
+        let todos: Vec<Todo> =
+            serde_json::from_slice(&json).map_err(|e| BackupError::Failed(e.to_string()))?;
+        let erased = self.erased().await?;
+        Ok(todos
+            .into_iter()
+            .filter(|todo| !erased.contains(&todo.id.to_string()))
+            .collect())
+    }
+
+    async fn erased(&self) -> Result<BTreeSet<String>, BackupError> {
+        let key = format!("{}{}", self.prefix, ERASED_NAME);
+        let sealed = match self.get(&key).await {
+            Ok(sealed) => sealed,
+            Err(BackupError::NotFound) => return Ok(BTreeSet::new()),
+            Err(e) => return Err(e),
+        };
+        let json = self.cipher.open(&sealed).map_err(BackupError::Failed)?;
+        serde_json::from_slice(&json).map_err(|e| BackupError::Failed(e.to_string()))
+    }
+
+    /// Records `ids` as erased, so no restore brings them back. The backups
+    /// themselves are left alone; they are encrypted and age out with the
+    /// retention.
+    pub async fn erase(&self, ids: &[String]) -> Result<(), BackupError> {
+        let mut erased = self.erased().await?;
+        erased.extend(ids.iter().cloned());
+        let json = serde_json::to_vec(&erased).map_err(|e| BackupError::Failed(e.to_string()))?;
+        let key = format!("{}{}", self.prefix, ERASED_NAME);
+        self.put(&key, self.cipher.seal(&json)).await
+    }
+
+    async fn put(&self, key: &str, body: Vec<u8>) -> Result<(), BackupError> {
+        match self.target {
+            #[cfg(feature = "backups")]
+            Target::S3(ref bucket) => bucket
+                .put_object(key, &body)
+                .await
+                .map(|_| ())
+                .map_err(s3_error),
+            #[cfg(test)]
+            Target::Memory(ref objects) => {
+                objects.lock().unwrap().insert(key.to_string(), body);
+                Ok(())
+            }
+        }
+    }
+
+    async fn get(&self, key: &str) -> Result<Vec<u8>, BackupError> {
+        match self.target {
+            #[cfg(feature = "backups")]
+            Target::S3(ref bucket) => bucket
+                .get_object(key)
+                .await
+                .map(|response| response.to_vec())
+                .map_err(s3_error),
+            #[cfg(test)]
+            Target::Memory(ref objects) => objects
+                .lock()
+                .unwrap()
+                .get(key)
+                .cloned()
+                .ok_or(BackupError::NotFound),
+        }
+    }
+
+    async fn list_objects(&self) -> Result<Vec<BackupInfo>, BackupError> {
+        match self.target {
+            #[cfg(feature = "backups")]
+            Target::S3(ref bucket) => Ok(bucket
+                .list(self.prefix.clone(), None)
+                .await
+                .map_err(s3_error)?
+                .into_iter()
+                .flat_map(|page| page.contents)
+                .map(|object| BackupInfo {
+                    key: object.key,
+                    size: object.size,
+                })
+                .collect()),
+            #[cfg(test)]
+            Target::Memory(ref objects) => Ok(objects
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|(key, body)| BackupInfo {
+                    key: key.clone(),
+                    size: body.len() as u64,
+                })
+                .collect()),
+        }
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), BackupError> {
+        match self.target {
+            #[cfg(feature = "backups")]
+            Target::S3(ref bucket) => bucket
+                .delete_object(key)
+                .await
+                .map(|_| ())
+                .map_err(s3_error),
+            #[cfg(test)]
+            Target::Memory(ref objects) => {
+                objects.lock().unwrap().remove(key);
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(feature = "backups")]
+fn s3_target(settings: &BackupSettings) -> Result<Target, String> {
+    let region = match &settings.endpoint {
+        Some(endpoint) => s3::Region::Custom {
+            region: settings.region.clone(),
+            endpoint: endpoint.clone(),
+        },
+        None => settings
+            .region
+            .parse()
+            .map_err(|e| format!("Invalid BACKUP_S3_REGION: {}", e))?,
+    };
+    let credentials = s3::creds::Credentials::new(
+        settings.access_key.as_deref(),
+        settings.secret_key.as_deref(),
+        None,
+        None,
+        None,
+    )
+    .map_err(|e| format!("Invalid backup credentials: {}", e))?;
+    let bucket = s3::Bucket::new(&settings.bucket, region, credentials)
+        .map_err(|e| format!("Invalid backup bucket: {}", e))?;
+    // MinIO and most self-hosted S3 implementations need path-style URLs
+    let bucket = if settings.endpoint.is_some() {
+        bucket.with_path_style()
+    } else {
+        bucket
+    };
+    Ok(Target::S3(bucket))
+}
+
+#[cfg(not(feature = "backups"))]
+fn s3_target(_settings: &BackupSettings) -> Result<Target, String> {
+    Err(
+        "Backups are configured but this build was compiled without the `backups` feature"
+            .to_string(),
+    )
+}
+
+#[cfg(feature = "backups")]
+fn s3_error(error: s3::error::S3Error) -> BackupError {
+    match error {
+        s3::error::S3Error::HttpFailWithBody(404, _) => BackupError::NotFound,
+        other => BackupError::Failed(other.to_string()),
+    }
+}
+
+/// Backs up every `interval` while the collection has changed since the last
+/// backup, pruning old backups after each.
+pub fn schedule(
+    scheduler: &mut Scheduler,
+    backups: web::Data<Backups>,
+    service: web::Data<TodoService>,
+    interval: Duration,
+) {
+    let backed_up_version = Rc::new(Cell::new(None));
+    scheduler.register_exclusive("backup", Schedule::Every(interval), move || {
+        let (backups, service) = (backups.clone(), service.clone());
+        let backed_up_version = backed_up_version.clone();
+        async move {
+            let version = service.collection_version().version;
+            if backed_up_version.get() == Some(version) {
+                return Ok(Outcome::Skipped);
+            }
+            let key = backups
+                .backup_now(&service)
+                .await
+                .map_err(|e| format!("upload: {}", e))?;
+            backed_up_version.set(Some(version));
+            println!("💾 Uploaded backup {}", key);
+            if let Err(e) = backups.prune().await {
+                eprintln!("Backup pruning failed: {}", e);
+            }
+            Ok(Outcome::Done)
+        }
+    });
+}
+
+pub struct BackupsPlugin;
+
+impl Plugin for BackupsPlugin {
+    fn name(&self) -> &'static str {
+        "backups"
+    }
+
+    fn kind(&self) -> Kind {
+        Kind::Backup
+    }
+
+    fn description(&self) -> &'static str {
+        "Uploads snapshots of the todo list to S3-compatible storage"
+    }
+
+    fn feature(&self) -> Option<&'static str> {
+        Some("backups")
+    }
+
+    fn compiled(&self) -> bool {
+        cfg!(feature = "backups")
+    }
+
+    fn enabled(&self, config: &Config) -> bool {
+        config.backup.is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spicy_todo_core::models::TodoCreate;
+
+    #[test]
+    fn test_cipher_round_trip() {
+        let cipher = Cipher::from_hex(&"0f".repeat(32)).unwrap();
+        let sealed = cipher.seal(b"[]");
+        assert!(sealed.starts_with(MAGIC));
+        assert_ne!(sealed, cipher.seal(b"[]"), "nonces must differ");
+        assert_eq!(cipher.open(&sealed).unwrap(), b"[]");
+
+        let mut tampered = sealed.clone();
+        *tampered.last_mut().unwrap() ^= 1;
+        assert!(cipher.open(&tampered).is_err());
+        let other = Cipher::from_hex(&"f0".repeat(32)).unwrap();
+        assert!(other.open(&sealed).is_err());
+        assert!(cipher.open(b"plain json").is_err());
+    }
+
+    #[test]
+    fn test_cipher_rejects_bad_keys() {
+        assert!(Cipher::from_hex("abcd").is_err());
+        assert!(Cipher::from_hex(&"zz".repeat(32)).is_err());
+    }
+
+    #[actix_web::test]
+    async fn test_backup_prune_and_fetch() {
+        let backups = Backups::in_memory(2);
+        let service = TodoService::new_empty();
+        service.create(TodoCreate {
+            text: "Back me up".to_string(),
+            ..Default::default()
+        });
+
+        let mut keys = Vec::new();
+        for _ in 0..3 {
+            keys.push(backups.backup_now(&service).await.unwrap());
+            tokio::time::sleep(Duration::from_millis(2)).await;
+        }
+        assert!(keys.iter().all(|key| backups.owns(key)));
+
+        assert_eq!(backups.prune().await.unwrap(), vec![keys[0].clone()]);
+        let listed: Vec<String> = backups
+            .list()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|b| b.key)
+            .collect();
+        assert_eq!(listed, vec![keys[2].clone(), keys[1].clone()]);
+
+        let todos = backups.fetch(&keys[2]).await.unwrap();
+        assert_eq!(todos[0].text, "Back me up");
+        assert_eq!(
+            backups.fetch(&keys[0]).await.err(),
+            Some(BackupError::NotFound)
+        );
+        assert_eq!(
+            backups.fetch("elsewhere/todos.json").await.err(),
+            Some(BackupError::NotFound)
+        );
+
+        backups.erase(&[todos[0].id.to_string()]).await.unwrap();
+        assert!(backups.fetch(&keys[2]).await.unwrap().is_empty());
+        assert_eq!(backups.list().await.unwrap().len(), 2);
+    }
+}
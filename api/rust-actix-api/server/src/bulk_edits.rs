@@ -0,0 +1,121 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use spicy_todo_core::bulk_edit::TodoDiff;
+use spicy_todo_core::models::TodoUpdate;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+use uuid::Uuid;
+
+/// How long a preview token can be applied.
+pub const PREVIEW_TTL: Duration = Duration::from_secs(10 * 60);
+/// Most previews kept at once; the one closest to expiry makes room.
+const MAX_PREVIEWS: usize = 100;
+
+/// A computed bulk edit waiting to be applied.
+pub struct Preview {
+    pub changes: TodoUpdate,
+    pub todos: Vec<TodoDiff>,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Response of `POST /api/todos/bulk-edit/preview`.
+#[derive(Debug, Serialize)]
+pub struct PreviewResponse {
+    pub token: String,
+    #[serde(rename = "expiresAt")]
+    pub expires_at: DateTime<Utc>,
+    pub count: usize,
+    pub todos: Vec<TodoDiff>,
+}
+
+/// Body of `POST /api/todos/bulk-edit/apply`.
+#[derive(Debug, Deserialize)]
+pub struct ApplyRequest {
+    pub token: String,
+}
+
+/// Previews by token. Each token can be applied once.
+pub struct BulkEditPreviews {
+    ttl: Duration,
+    previews: Mutex<HashMap<String, Preview>>,
+}
+
+impl BulkEditPreviews {
+    pub fn new(ttl: Duration) -> Self {
+        BulkEditPreviews {
+            ttl,
+            previews: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Stores a preview of `changes` to `todos`, returning its token.
+    pub fn insert(
+        &self,
+        changes: TodoUpdate,
+        todos: Vec<TodoDiff>,
+        now: DateTime<Utc>,
+    ) -> PreviewResponse {
+        let mut previews = self.previews.lock().unwrap();
+        previews.retain(|_, preview| preview.expires_at > now);
+        if previews.len() >= MAX_PREVIEWS {
+            let oldest = previews
+                .iter()
+                .min_by_key(|(_, preview)| preview.expires_at)
+                .map(|(token, _)| token.clone());
+            if let Some(token) = oldest {
+                previews.remove(&token);
+            }
+        }
+
+        let ttl = chrono::Duration::from_std(self.ttl).unwrap_or(chrono::Duration::MAX);
+        let expires_at = now
+            .checked_add_signed(ttl)
+            .unwrap_or(DateTime::<Utc>::MAX_UTC);
+        let response = PreviewResponse {
+            token: Uuid::new_v4().simple().to_string(),
+            expires_at,
+            count: todos.len(),
+            todos: todos.clone(),
+        };
+        previews.insert(
+            response.token.clone(),
+            Preview {
+                changes,
+                todos,
+                expires_at,
+            },
+        );
+        response
+    }
+
+    /// Removes and returns the preview for `token` unless it has expired.
+    pub fn take(&self, token: &str, now: DateTime<Utc>) -> Option<Preview> {
+        self.previews
+            .lock()
+            .unwrap()
+            .remove(token)
+            .filter(|preview| preview.expires_at > now)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tokens_are_single_use_and_expire() {
+        let previews = BulkEditPreviews::new(Duration::from_secs(60));
+        let now = Utc::now();
+        let first = previews.insert(TodoUpdate::default(), Vec::new(), now);
+        let second = previews.insert(TodoUpdate::default(), Vec::new(), now);
+        assert_eq!(first.expires_at, now + chrono::Duration::seconds(60));
+
+        assert!(previews.take(&first.token, now).is_some());
+        assert!(previews.take(&first.token, now).is_none());
+        assert!(previews
+            .take(&second.token, now + chrono::Duration::seconds(60))
+            .is_none());
+        assert!(previews.take("unknown", now).is_none());
+    }
+}
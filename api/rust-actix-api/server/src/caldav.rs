@@ -0,0 +1,409 @@
+use crate::config::Config;
+use crate::plugins::{Kind, Plugin};
+use actix_web::http::Method;
+use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use spicy_todo_core::ical;
+use spicy_todo_core::models::{self, Todo};
+use spicy_todo_core::service::CollectionVersion;
+use uuid::Uuid;
+
+/// The principal and calendar home: this server has one user and one
+/// calendar.
+pub const ROOT: &str = "/dav/";
+/// The calendar holding every todo, one `{id}.ics` resource each.
+pub const COLLECTION: &str = "/dav/todos/";
+/// `DAV` response header: WebDAV classes 1 and 3 plus calendar-access.
+pub const COMPLIANCE: &str = "1, 3, calendar-access";
+pub const ALLOW: &str = "OPTIONS, GET, PUT, DELETE, PROPFIND, REPORT";
+pub const ICS_CONTENT_TYPE: &str = "text/calendar; charset=utf-8; component=VTODO";
+pub const XML_CONTENT_TYPE: &str = "application/xml; charset=utf-8";
+
+const DAV: &str = "DAV:";
+const CALDAV: &str = "urn:ietf:params:xml:ns:caldav";
+const CALENDARSERVER: &str = "http://calendarserver.org/ns/";
+
+/// Bytes of a todo id that stay as they are in its href.
+const HREF_SAFE: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'_')
+    .remove(b'.')
+    .remove(b'~')
+    .remove(b'@');
+
+/// A property name: namespace and local name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PropName {
+    pub namespace: String,
+    pub name: String,
+}
+
+impl PropName {
+    fn new(namespace: &str, name: &str) -> Self {
+        PropName {
+            namespace: namespace.to_string(),
+            name: name.to_string(),
+        }
+    }
+}
+
+/// Which properties a PROPFIND or REPORT asks for.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Props {
+    /// No body, `allprop` or `propname`.
+    All,
+    Named(Vec<PropName>),
+}
+
+/// The REPORTs this server answers.
+#[derive(Debug, PartialEq)]
+pub enum Report {
+    /// Every todo, unless the filter asks for another component type.
+    CalendarQuery { props: Props, todos: bool },
+    /// The todos at these hrefs.
+    CalendarMultiget { props: Props, hrefs: Vec<String> },
+}
+
+/// What a DAV resource is.
+pub enum Resource<'a> {
+    Root,
+    Collection(&'a CollectionVersion),
+    Todo(&'a Todo),
+}
+
+pub fn propfind() -> Method {
+    Method::from_bytes(b"PROPFIND").expect("PROPFIND is a valid method")
+}
+
+pub fn report() -> Method {
+    Method::from_bytes(b"REPORT").expect("REPORT is a valid method")
+}
+
+/// The `Depth` header, where anything but 0 lists children too.
+pub fn lists_children(depth: Option<&str>) -> bool {
+    depth.is_none_or(|depth| depth.trim() != "0")
+}
+
+pub fn href(id: &str) -> String {
+    format!("{}{}.ics", COLLECTION, utf8_percent_encode(id, HREF_SAFE))
+}
+
+/// The todo id a resource name or href points at, if it is one of the
+/// collection's `.ics` resources. Absolute URLs are accepted as well. A
+/// name that is not a UUID, such as a client's own UID, stands for the id
+/// `models::todo_id` derives from it.
+pub fn todo_id(href: &str) -> Option<Uuid> {
+    let path = match href.find("://") {
+        Some(scheme) => &href[href[scheme + 3..].find('/')? + scheme + 3..],
+        None => href,
+    };
+    let name = path.strip_prefix(COLLECTION).unwrap_or(path);
+    let name = name.strip_suffix(".ics")?;
+    let id = percent_decode_str(name).decode_utf8().ok()?;
+    (!id.is_empty() && !id.contains('/')).then(|| models::todo_id(&id))
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+fn parse_props(element: roxmltree::Node) -> Props {
+    let Some(prop) = element
+        .children()
+        .find(|child| child.has_tag_name((DAV, "prop")))
+    else {
+        return Props::All;
+    };
+    Props::Named(
+        prop.children()
+            .filter(|child| child.is_element())
+            .map(|child| {
+                let name = child.tag_name();
+                PropName::new(name.namespace().unwrap_or(""), name.name())
+            })
+            .collect(),
+    )
+}
+
+/// Reads a PROPFIND body; an empty one asks for all properties.
+pub fn parse_propfind(body: &str) -> Result<Props, String> {
+    if body.trim().is_empty() {
+        return Ok(Props::All);
+    }
+    let document =
+        roxmltree::Document::parse(body).map_err(|e| format!("Invalid PROPFIND body: {}", e))?;
+    let root = document.root_element();
+    if !root.has_tag_name((DAV, "propfind")) {
+        return Err("Expected a DAV:propfind body".to_string());
+    }
+    Ok(parse_props(root))
+}
+
+/// Reads a REPORT body. Other reports, such as sync-collection, are
+/// refused so clients fall back to comparing etags.
+pub fn parse_report(body: &str) -> Result<Report, String> {
+    let document =
+        roxmltree::Document::parse(body).map_err(|e| format!("Invalid REPORT body: {}", e))?;
+    let root = document.root_element();
+    let props = parse_props(root);
+    if root.has_tag_name((CALDAV, "calendar-multiget")) {
+        let hrefs = root
+            .children()
+            .filter(|child| child.has_tag_name((DAV, "href")))
+            .filter_map(|href| href.text())
+            .map(|href| href.trim().to_string())
+            .collect();
+        return Ok(Report::CalendarMultiget { props, hrefs });
+    }
+    if root.has_tag_name((CALDAV, "calendar-query")) {
+        // Other filters, such as time ranges, are not applied: clients
+        // get a superset and filter it themselves.
+        let todos = root
+            .descendants()
+            .filter(|node| node.has_tag_name((CALDAV, "comp-filter")))
+            .filter_map(|filter| filter.attribute("name"))
+            .filter(|name| !name.eq_ignore_ascii_case("VCALENDAR"))
+            .all(|name| name.eq_ignore_ascii_case("VTODO"));
+        return Ok(Report::CalendarQuery { props, todos });
+    }
+    Err(format!("Unsupported REPORT {}", root.tag_name().name()))
+}
+
+impl Resource<'_> {
+    /// Properties `allprop` returns. calendar-data is only sent when asked
+    /// for by name.
+    fn all_props(&self) -> Vec<PropName> {
+        let names: &[(&str, &str)] = match self {
+            Resource::Root => &[
+                (DAV, "resourcetype"),
+                (DAV, "displayname"),
+                (DAV, "current-user-principal"),
+                (DAV, "principal-URL"),
+                (CALDAV, "calendar-home-set"),
+            ],
+            Resource::Collection(_) => &[
+                (DAV, "resourcetype"),
+                (DAV, "displayname"),
+                (DAV, "getetag"),
+                (DAV, "current-user-principal"),
+                (DAV, "current-user-privilege-set"),
+                (DAV, "supported-report-set"),
+                (CALDAV, "supported-calendar-component-set"),
+                (CALENDARSERVER, "getctag"),
+            ],
+            Resource::Todo(_) => &[
+                (DAV, "resourcetype"),
+                (DAV, "getetag"),
+                (DAV, "getcontenttype"),
+                (DAV, "current-user-privilege-set"),
+            ],
+        };
+        names
+            .iter()
+            .map(|(namespace, name)| PropName::new(namespace, name))
+            .collect()
+    }
+
+    /// The property's content as XML, or `None` if the resource doesn't
+    /// have it.
+    fn prop(&self, prop: &PropName) -> Option<String> {
+        let principal = format!("<d:href>{}</d:href>", ROOT);
+        let privileges = ["read", "write", "write-content", "bind", "unbind"]
+            .iter()
+            .map(|privilege| format!("<d:privilege><d:{}/></d:privilege>", privilege))
+            .collect::<String>();
+        let value = match (self, prop.namespace.as_str(), prop.name.as_str()) {
+            (Resource::Root, DAV, "resourcetype") => "<d:collection/>".to_string(),
+            (Resource::Root, DAV, "displayname") => "Spicy Todo".to_string(),
+            (Resource::Root, DAV, "principal-URL") => principal,
+            (Resource::Root, CALDAV, "calendar-home-set") => principal,
+            (Resource::Root | Resource::Collection(_), DAV, "current-user-principal") => {
+                principal
+            }
+            (Resource::Collection(_), DAV, "resourcetype") => {
+                "<d:collection/><c:calendar/>".to_string()
+            }
+            (Resource::Collection(_), DAV, "displayname") => "Todos".to_string(),
+            (Resource::Collection(version), DAV, "getetag") => {
+                format!("\"{}\"", version.etag(&"caldav"))
+            }
+            (Resource::Collection(version), CALENDARSERVER, "getctag") => {
+                format!("\"{}\"", version.etag(&"caldav"))
+            }
+            (Resource::Collection(_), DAV, "supported-report-set") => [
+                "<d:supported-report><d:report><c:calendar-query/></d:report></d:supported-report>",
+                "<d:supported-report><d:report><c:calendar-multiget/></d:report></d:supported-report>",
+            ]
+            .concat(),
+            (Resource::Collection(_), CALDAV, "supported-calendar-component-set") => {
+                "<c:comp name=\"VTODO\"/>".to_string()
+            }
+            (Resource::Collection(_) | Resource::Todo(_), DAV, "current-user-privilege-set") => {
+                privileges
+            }
+            (Resource::Todo(_), DAV, "resourcetype") => String::new(),
+            (Resource::Todo(todo), DAV, "getetag") => escape(&ical::etag(todo)),
+            (Resource::Todo(_), DAV, "getcontenttype") => ICS_CONTENT_TYPE.to_string(),
+            (Resource::Todo(todo), CALDAV, "calendar-data") => escape(&ical::to_ics(todo)),
+            _ => return None,
+        };
+        Some(value)
+    }
+}
+
+/// The element for `prop` wrapping `content`, using the prefixes declared
+/// on the multistatus and declaring any other namespace inline.
+fn element(prop: &PropName, content: &str) -> String {
+    let (tag, declaration) = match prop.namespace.as_str() {
+        DAV => (format!("d:{}", prop.name), String::new()),
+        CALDAV => (format!("c:{}", prop.name), String::new()),
+        CALENDARSERVER => (format!("cs:{}", prop.name), String::new()),
+        namespace => (
+            format!("x:{}", prop.name),
+            format!(" xmlns:x=\"{}\"", escape(namespace).replace('"', "&quot;")),
+        ),
+    };
+    if content.is_empty() {
+        format!("<{}{}/>", tag, declaration)
+    } else {
+        format!("<{}{}>{}</{}>", tag, declaration, content, tag)
+    }
+}
+
+fn propstat(props: &str, status: &str) -> String {
+    format!(
+        "<d:propstat><d:prop>{}</d:prop><d:status>HTTP/1.1 {}</d:status></d:propstat>",
+        props, status
+    )
+}
+
+/// A `207 Multi-Status` body, built one response at a time.
+#[derive(Default)]
+pub struct Multistatus {
+    responses: String,
+}
+
+impl Multistatus {
+    pub fn new() -> Self {
+        Multistatus::default()
+    }
+
+    /// The requested properties of `resource`, found ones under 200 and
+    /// the rest under 404.
+    pub fn push(&mut self, href: &str, resource: &Resource, props: &Props) {
+        let names = match props {
+            Props::All => resource.all_props(),
+            Props::Named(names) => names.clone(),
+        };
+        let mut found = String::new();
+        let mut missing = String::new();
+        for name in &names {
+            match resource.prop(name) {
+                Some(content) => found.push_str(&element(name, &content)),
+                None => missing.push_str(&element(name, "")),
+            }
+        }
+        let mut response = format!("<d:response><d:href>{}</d:href>", escape(href));
+        if !found.is_empty() {
+            response.push_str(&propstat(&found, "200 OK"));
+        }
+        if !missing.is_empty() {
+            response.push_str(&propstat(&missing, "404 Not Found"));
+        }
+        response.push_str("</d:response>");
+        self.responses.push_str(&response);
+    }
+
+    pub fn push_not_found(&mut self, href: &str) {
+        self.responses.push_str(&format!(
+            "<d:response><d:href>{}</d:href><d:status>HTTP/1.1 404 Not Found</d:status>\
+             </d:response>",
+            escape(href)
+        ));
+    }
+
+    pub fn finish(self) -> String {
+        format!(
+            "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<d:multistatus xmlns:d=\"{}\" \
+             xmlns:c=\"{}\" xmlns:cs=\"{}\">{}</d:multistatus>\n",
+            DAV, CALDAV, CALENDARSERVER, self.responses
+        )
+    }
+}
+
+pub struct CaldavPlugin;
+
+impl Plugin for CaldavPlugin {
+    fn name(&self) -> &'static str {
+        "caldav"
+    }
+
+    fn kind(&self) -> Kind {
+        Kind::Sync
+    }
+
+    fn description(&self) -> &'static str {
+        "Serves todos to CalDAV clients as VTODOs"
+    }
+
+    fn enabled(&self, _config: &Config) -> bool {
+        true
+    }
+
+    fn details(&self, _config: &Config) -> Option<serde_json::Value> {
+        Some(serde_json::json!({ "collection": COLLECTION }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hrefs() {
+        assert_eq!(href("abc@tasks.org"), "/dav/todos/abc@tasks.org.ics");
+        assert_eq!(href("a b/c"), "/dav/todos/a%20b%2Fc.ics");
+        let mapped = |name: &str| Some(models::todo_id(name));
+        assert_eq!(todo_id("/dav/todos/a%20b.ics"), mapped("a b"));
+        assert_eq!(
+            todo_id("https://todo.example/dav/todos/abc.ics"),
+            mapped("abc")
+        );
+        assert_eq!(todo_id("abc.ics"), mapped("abc"));
+        assert_eq!(todo_id("/dav/todos/"), None);
+        assert_eq!(todo_id("/dav/todos/a%2Fb.ics"), None);
+    }
+
+    #[test]
+    fn test_parse_report() {
+        let multiget = r#"<?xml version="1.0"?>
+            <c:calendar-multiget xmlns:d="DAV:" xmlns:c="urn:ietf:params:xml:ns:caldav">
+              <d:prop><d:getetag/><c:calendar-data/></d:prop>
+              <d:href>/dav/todos/abc.ics</d:href>
+            </c:calendar-multiget>"#;
+        assert_eq!(
+            parse_report(multiget).unwrap(),
+            Report::CalendarMultiget {
+                props: Props::Named(vec![
+                    PropName::new(DAV, "getetag"),
+                    PropName::new(CALDAV, "calendar-data"),
+                ]),
+                hrefs: vec!["/dav/todos/abc.ics".to_string()],
+            }
+        );
+
+        let events = r#"<C:calendar-query xmlns:D="DAV:" xmlns:C="urn:ietf:params:xml:ns:caldav">
+              <D:prop><D:getetag/></D:prop>
+              <C:filter><C:comp-filter name="VCALENDAR">
+                <C:comp-filter name="VEVENT"/>
+              </C:comp-filter></C:filter>
+            </C:calendar-query>"#;
+        assert!(matches!(
+            parse_report(events).unwrap(),
+            Report::CalendarQuery { todos: false, .. }
+        ));
+        assert!(parse_report(r#"<d:sync-collection xmlns:d="DAV:"/>"#).is_err());
+    }
+}
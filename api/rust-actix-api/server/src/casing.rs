@@ -0,0 +1,995 @@
+use crate::errors::{self, ApiError};
+use actix_web::body::{BoxBody, MessageBody};
+use actix_web::dev::{Payload, ServiceRequest, ServiceResponse};
+use actix_web::error::JsonPayloadError;
+use actix_web::http::header::{self, HeaderValue};
+use actix_web::middleware::Next;
+use actix_web::{web, FromRequest, HttpRequest};
+use futures_util::future::LocalBoxFuture;
+use serde::de::{self, DeserializeOwned, DeserializeSeed, Visitor};
+use serde::ser;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt;
+use std::ops::{Deref, DerefMut};
+use std::sync::{Mutex, OnceLock};
+
+/// Request header choosing the key case of JSON bodies.
+pub const CASE_HEADER: &str = "x-json-case";
+/// Query parameter doing the same, for clients that cannot set headers.
+pub const CASE_PARAM: &str = "case";
+
+/// How the field names of JSON and MessagePack bodies are written. The
+/// models serialize as camelCase; snake_case renames their fields as they
+/// are written and read, leaving the keys of data maps as they are.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum KeyCase {
+    Camel,
+    Snake,
+}
+
+impl KeyCase {
+    pub const NAMES: [&'static str; 2] = ["camelCase", "snake_case"];
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim() {
+            "camelCase" | "camel" => Some(KeyCase::Camel),
+            "snake_case" | "snake" => Some(KeyCase::Snake),
+            _ => None,
+        }
+    }
+}
+
+tokio::task_local! {
+    static CASE: KeyCase;
+}
+
+/// The key case of the request being handled; camelCase outside of one.
+pub fn current() -> KeyCase {
+    CASE.try_with(|case| *case).unwrap_or(KeyCase::Camel)
+}
+
+pub fn snake_case(key: &str) -> String {
+    let mut snake = String::with_capacity(key.len() + 4);
+    for c in key.chars() {
+        if c.is_ascii_uppercase() {
+            snake.push('_');
+            snake.push(c.to_ascii_lowercase());
+        } else {
+            snake.push(c);
+        }
+    }
+    snake
+}
+
+pub fn camel_case(key: &str) -> String {
+    let mut camel = String::with_capacity(key.len());
+    let mut upper = false;
+    for c in key.chars() {
+        match c {
+            '_' if !camel.is_empty() => upper = true,
+            c if upper => {
+                camel.push(c.to_ascii_uppercase());
+                upper = false;
+            }
+            c => camel.push(c),
+        }
+    }
+    camel
+}
+
+/// The snake_case name of a struct field. Serializers take field names as
+/// `&'static str`, so each renamed one is leaked once; there are only as
+/// many as the models declare.
+fn snake_field(name: &'static str) -> &'static str {
+    static NAMES: OnceLock<Mutex<HashMap<&'static str, &'static str>>> = OnceLock::new();
+    if !name.bytes().any(|b| b.is_ascii_uppercase()) {
+        return name;
+    }
+    let mut names = NAMES.get_or_init(Default::default).lock().unwrap();
+    names
+        .entry(name)
+        .or_insert_with(|| Box::leak(snake_case(name).into_boxed_str()))
+}
+
+/// The key case a request asked for, by header or else query parameter;
+/// camelCase when it asked for neither.
+pub fn requested(req: &HttpRequest) -> Result<KeyCase, String> {
+    let from_header = req
+        .headers()
+        .get(CASE_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string);
+    let from_query = || {
+        web::Query::<HashMap<String, String>>::from_query(req.query_string())
+            .ok()?
+            .remove(CASE_PARAM)
+    };
+    match from_header.or_else(from_query) {
+        Some(value) => KeyCase::parse(&value).ok_or_else(|| {
+            format!(
+                "Unknown JSON case '{}'; expected one of {}",
+                value,
+                KeyCase::NAMES.join(", ")
+            )
+        }),
+        None => Ok(KeyCase::Camel),
+    }
+}
+
+/// Handles each request in the key case it asked for, which `Cased`
+/// responses and `Json` bodies are then written and read in. `/api/v2` is
+/// snake_case already and is left alone.
+pub async fn negotiate_case(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<BoxBody>, actix_web::Error> {
+    if req.path().starts_with("/api/v2/") {
+        return Ok(next.call(req).await?.map_into_boxed_body());
+    }
+    let case = match requested(req.request()) {
+        Ok(case) => case,
+        Err(e) => {
+            return Ok(req.into_response(ApiError::bad_request(e).into_response()));
+        }
+    };
+
+    let mut response = CASE
+        .scope(case, next.call(req))
+        .await?
+        .map_into_boxed_body();
+    response
+        .headers_mut()
+        .append(header::VARY, HeaderValue::from_static(CASE_HEADER));
+    Ok(response)
+}
+
+/// A response body written in the key case of the current request. Struct
+/// fields are renamed, and so are the keys of maps standing in for a body,
+/// such as `json!` objects; maps held in a struct field are data and keep
+/// their keys.
+pub struct Cased<T>(pub T);
+
+impl<T: Serialize> Serialize for Cased<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match current() {
+            KeyCase::Camel => self.0.serialize(serializer),
+            KeyCase::Snake => self.0.serialize(Snake::new(serializer, false)),
+        }
+    }
+}
+
+/// A named value outside of any model struct, such as an error detail,
+/// written in the current key case as a struct field would be.
+pub fn field(name: &str, value: impl Serialize) -> serde_json::Result<(String, Value)> {
+    match current() {
+        KeyCase::Camel => Ok((name.to_string(), serde_json::to_value(value)?)),
+        KeyCase::Snake => {
+            let value = value.serialize(Snake::new(serde_json::value::Serializer, true))?;
+            Ok((snake_case(name), value))
+        }
+    }
+}
+
+/// Writes through to `inner` with struct fields renamed to snake_case.
+/// `data` is set below a struct field, where map keys are left alone.
+struct Snake<S> {
+    inner: S,
+    data: bool,
+}
+
+impl<S> Snake<S> {
+    fn new(inner: S, data: bool) -> Self {
+        Snake { inner, data }
+    }
+}
+
+/// A value to write through `Snake`.
+struct Renamed<'a, T: ?Sized> {
+    value: &'a T,
+    data: bool,
+}
+
+impl<T: Serialize + ?Sized> Serialize for Renamed<'_, T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.value.serialize(Snake::new(serializer, self.data))
+    }
+}
+
+macro_rules! forward_scalars {
+    ($($method:ident($ty:ty)),* $(,)?) => {
+        $(
+            fn $method(self, v: $ty) -> Result<S::Ok, S::Error> {
+                self.inner.$method(v)
+            }
+        )*
+    };
+}
+
+impl<S: Serializer> Serializer for Snake<S> {
+    type Ok = S::Ok;
+    type Error = S::Error;
+    type SerializeSeq = Snake<S::SerializeSeq>;
+    type SerializeTuple = Snake<S::SerializeTuple>;
+    type SerializeTupleStruct = Snake<S::SerializeTupleStruct>;
+    type SerializeTupleVariant = Snake<S::SerializeTupleVariant>;
+    type SerializeMap = SnakeMap<S::SerializeMap>;
+    type SerializeStruct = Snake<S::SerializeStruct>;
+    type SerializeStructVariant = Snake<S::SerializeStructVariant>;
+
+    forward_scalars!(
+        serialize_bool(bool),
+        serialize_i8(i8),
+        serialize_i16(i16),
+        serialize_i32(i32),
+        serialize_i64(i64),
+        serialize_i128(i128),
+        serialize_u8(u8),
+        serialize_u16(u16),
+        serialize_u32(u32),
+        serialize_u64(u64),
+        serialize_u128(u128),
+        serialize_f32(f32),
+        serialize_f64(f64),
+        serialize_char(char),
+        serialize_str(&str),
+        serialize_bytes(&[u8]),
+        serialize_unit_struct(&'static str),
+    );
+
+    fn serialize_none(self) -> Result<S::Ok, S::Error> {
+        self.inner.serialize_none()
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<S::Ok, S::Error> {
+        self.inner.serialize_some(&Renamed {
+            value,
+            data: self.data,
+        })
+    }
+
+    fn serialize_unit(self) -> Result<S::Ok, S::Error> {
+        self.inner.serialize_unit()
+    }
+
+    fn serialize_unit_variant(
+        self,
+        name: &'static str,
+        index: u32,
+        variant: &'static str,
+    ) -> Result<S::Ok, S::Error> {
+        self.inner.serialize_unit_variant(name, index, variant)
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        name: &'static str,
+        value: &T,
+    ) -> Result<S::Ok, S::Error> {
+        let value = Renamed {
+            value,
+            data: self.data,
+        };
+        self.inner.serialize_newtype_struct(name, &value)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        name: &'static str,
+        index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<S::Ok, S::Error> {
+        let value = Renamed {
+            value,
+            data: self.data,
+        };
+        self.inner
+            .serialize_newtype_variant(name, index, variant, &value)
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq, S::Error> {
+        Ok(Snake::new(self.inner.serialize_seq(len)?, self.data))
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple, S::Error> {
+        Ok(Snake::new(self.inner.serialize_tuple(len)?, self.data))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleStruct, S::Error> {
+        Ok(Snake::new(
+            self.inner.serialize_tuple_struct(name, len)?,
+            self.data,
+        ))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        name: &'static str,
+        index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleVariant, S::Error> {
+        let inner = self
+            .inner
+            .serialize_tuple_variant(name, index, variant, len)?;
+        Ok(Snake::new(inner, self.data))
+    }
+
+    /// Structs with flattened fields are written as maps of unknown
+    /// length, and are renamed like any other struct.
+    fn serialize_map(self, len: Option<usize>) -> Result<Self::SerializeMap, S::Error> {
+        let inner = self.inner.serialize_map(len)?;
+        Ok(match len {
+            None => SnakeMap {
+                inner,
+                rename_keys: true,
+                data: true,
+            },
+            Some(_) => SnakeMap {
+                inner,
+                rename_keys: !self.data,
+                data: self.data,
+            },
+        })
+    }
+
+    fn serialize_struct(
+        self,
+        name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStruct, S::Error> {
+        Ok(Snake::new(self.inner.serialize_struct(name, len)?, true))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        name: &'static str,
+        index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStructVariant, S::Error> {
+        let inner = self
+            .inner
+            .serialize_struct_variant(name, index, variant, len)?;
+        Ok(Snake::new(inner, true))
+    }
+
+    fn is_human_readable(&self) -> bool {
+        self.inner.is_human_readable()
+    }
+}
+
+impl<S: ser::SerializeSeq> ser::SerializeSeq for Snake<S> {
+    type Ok = S::Ok;
+    type Error = S::Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), S::Error> {
+        self.inner.serialize_element(&Renamed {
+            value,
+            data: self.data,
+        })
+    }
+
+    fn end(self) -> Result<S::Ok, S::Error> {
+        self.inner.end()
+    }
+}
+
+impl<S: ser::SerializeTuple> ser::SerializeTuple for Snake<S> {
+    type Ok = S::Ok;
+    type Error = S::Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), S::Error> {
+        self.inner.serialize_element(&Renamed {
+            value,
+            data: self.data,
+        })
+    }
+
+    fn end(self) -> Result<S::Ok, S::Error> {
+        self.inner.end()
+    }
+}
+
+impl<S: ser::SerializeTupleStruct> ser::SerializeTupleStruct for Snake<S> {
+    type Ok = S::Ok;
+    type Error = S::Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), S::Error> {
+        self.inner.serialize_field(&Renamed {
+            value,
+            data: self.data,
+        })
+    }
+
+    fn end(self) -> Result<S::Ok, S::Error> {
+        self.inner.end()
+    }
+}
+
+impl<S: ser::SerializeTupleVariant> ser::SerializeTupleVariant for Snake<S> {
+    type Ok = S::Ok;
+    type Error = S::Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), S::Error> {
+        self.inner.serialize_field(&Renamed {
+            value,
+            data: self.data,
+        })
+    }
+
+    fn end(self) -> Result<S::Ok, S::Error> {
+        self.inner.end()
+    }
+}
+
+/// A map written through `Snake`, renaming its keys unless they are data.
+struct SnakeMap<S> {
+    inner: S,
+    rename_keys: bool,
+    data: bool,
+}
+
+impl<S: ser::SerializeMap> ser::SerializeMap for SnakeMap<S> {
+    type Ok = S::Ok;
+    type Error = S::Error;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), S::Error> {
+        if self.rename_keys {
+            if let Ok(Value::String(key)) = key.serialize(serde_json::value::Serializer) {
+                return self.inner.serialize_key(&snake_case(&key));
+            }
+        }
+        self.inner.serialize_key(key)
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), S::Error> {
+        self.inner.serialize_value(&Renamed {
+            value,
+            data: self.data,
+        })
+    }
+
+    fn end(self) -> Result<S::Ok, S::Error> {
+        self.inner.end()
+    }
+}
+
+impl<S: ser::SerializeStruct> ser::SerializeStruct for Snake<S> {
+    type Ok = S::Ok;
+    type Error = S::Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), S::Error> {
+        self.inner
+            .serialize_field(snake_field(key), &Renamed { value, data: true })
+    }
+
+    fn skip_field(&mut self, key: &'static str) -> Result<(), S::Error> {
+        self.inner.skip_field(snake_field(key))
+    }
+
+    fn end(self) -> Result<S::Ok, S::Error> {
+        self.inner.end()
+    }
+}
+
+impl<S: ser::SerializeStructVariant> ser::SerializeStructVariant for Snake<S> {
+    type Ok = S::Ok;
+    type Error = S::Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), S::Error> {
+        self.inner
+            .serialize_field(snake_field(key), &Renamed { value, data: true })
+    }
+
+    fn skip_field(&mut self, key: &'static str) -> Result<(), S::Error> {
+        self.inner.skip_field(snake_field(key))
+    }
+
+    fn end(self) -> Result<S::Ok, S::Error> {
+        self.inner.end()
+    }
+}
+
+/// A JSON request body read in the key case of the current request. Works
+/// as `web::Json` does, with the same limits and errors.
+#[derive(Debug)]
+pub struct Json<T>(pub T);
+
+impl<T> Json<T> {
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> Deref for Json<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for Json<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+
+impl<T: DeserializeOwned + 'static> FromRequest for Json<T> {
+    type Error = actix_web::Error;
+    type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        if current() == KeyCase::Camel {
+            let body = web::Json::<T>::from_request(req, payload);
+            return Box::pin(async move { Ok(Json(body.await?.into_inner())) });
+        }
+        let body = web::Json::<Value>::from_request(req, payload);
+        let req = req.clone();
+        Box::pin(async move {
+            let value = body.await?.into_inner();
+            T::deserialize(Input::new(value, None))
+                .map(Json)
+                .map_err(|e| errors::extractor_error(JsonPayloadError::Deserialize(e), &req))
+        })
+    }
+}
+
+/// Reads through `inner`, naming struct fields in camelCase before the
+/// struct sees them. `key_of` is set when `inner` is a map key, to the
+/// fields of the struct it belongs to; those of flattened structs are
+/// unknown.
+struct Input<D> {
+    inner: D,
+    key_of: Option<Option<&'static [&'static str]>>,
+}
+
+impl<D> Input<D> {
+    fn new(inner: D, key_of: Option<Option<&'static [&'static str]>>) -> Self {
+        Input { inner, key_of }
+    }
+}
+
+/// The camelCase field `key` names among `fields`, or `key` itself when it
+/// names none of them.
+fn camel_field(key: String, fields: Option<&'static [&'static str]>) -> String {
+    match fields {
+        Some(fields) if fields.contains(&key.as_str()) => key,
+        Some(fields) => {
+            let camel = camel_case(&key);
+            if fields.contains(&camel.as_str()) {
+                camel
+            } else {
+                key
+            }
+        }
+        None => camel_case(&key),
+    }
+}
+
+macro_rules! forward_deserialize {
+    ($($method:ident),* $(,)?) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, D::Error> {
+                self.inner.$method(Visit::new(visitor, None))
+            }
+        )*
+    };
+}
+
+impl<'de, D: Deserializer<'de>> Deserializer<'de> for Input<D> {
+    type Error = D::Error;
+
+    forward_deserialize!(
+        deserialize_any,
+        deserialize_bool,
+        deserialize_i8,
+        deserialize_i16,
+        deserialize_i32,
+        deserialize_i64,
+        deserialize_i128,
+        deserialize_u8,
+        deserialize_u16,
+        deserialize_u32,
+        deserialize_u64,
+        deserialize_u128,
+        deserialize_f32,
+        deserialize_f64,
+        deserialize_char,
+        deserialize_str,
+        deserialize_string,
+        deserialize_bytes,
+        deserialize_byte_buf,
+        deserialize_option,
+        deserialize_unit,
+        deserialize_seq,
+        deserialize_map,
+        deserialize_ignored_any,
+    );
+
+    /// Struct fields are read as identifiers, while the keys of data maps
+    /// are read as strings and keep their case.
+    fn deserialize_identifier<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, D::Error> {
+        match self.key_of {
+            Some(fields) => {
+                visitor.visit_string(camel_field(String::deserialize(self.inner)?, fields))
+            }
+            None => self.inner.deserialize_identifier(Visit::new(visitor, None)),
+        }
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, D::Error> {
+        self.inner
+            .deserialize_unit_struct(name, Visit::new(visitor, None))
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, D::Error> {
+        self.inner
+            .deserialize_newtype_struct(name, Visit::new(visitor, None))
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(
+        self,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, D::Error> {
+        self.inner.deserialize_tuple(len, Visit::new(visitor, None))
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, D::Error> {
+        self.inner
+            .deserialize_tuple_struct(name, len, Visit::new(visitor, None))
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, D::Error> {
+        self.inner
+            .deserialize_struct(name, fields, Visit::new(visitor, Some(fields)))
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, D::Error> {
+        self.inner
+            .deserialize_enum(name, variants, Visit::new(visitor, None))
+    }
+
+    fn is_human_readable(&self) -> bool {
+        self.inner.is_human_readable()
+    }
+}
+
+/// Hands what `Input` read on to `inner`, reading nested values through
+/// `Input` too. `fields` are set when `inner` visits a struct.
+struct Visit<V> {
+    inner: V,
+    fields: Option<&'static [&'static str]>,
+}
+
+impl<V> Visit<V> {
+    fn new(inner: V, fields: Option<&'static [&'static str]>) -> Self {
+        Visit { inner, fields }
+    }
+}
+
+macro_rules! forward_visits {
+    ($($method:ident($ty:ty)),* $(,)?) => {
+        $(
+            fn $method<E: de::Error>(self, v: $ty) -> Result<V::Value, E> {
+                self.inner.$method(v)
+            }
+        )*
+    };
+}
+
+impl<'de, V: Visitor<'de>> Visitor<'de> for Visit<V> {
+    type Value = V::Value;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        self.inner.expecting(formatter)
+    }
+
+    forward_visits!(
+        visit_bool(bool),
+        visit_i8(i8),
+        visit_i16(i16),
+        visit_i32(i32),
+        visit_i64(i64),
+        visit_i128(i128),
+        visit_u8(u8),
+        visit_u16(u16),
+        visit_u32(u32),
+        visit_u64(u64),
+        visit_u128(u128),
+        visit_f32(f32),
+        visit_f64(f64),
+        visit_char(char),
+        visit_str(&str),
+        visit_borrowed_str(&'de str),
+        visit_string(String),
+        visit_bytes(&[u8]),
+        visit_borrowed_bytes(&'de [u8]),
+        visit_byte_buf(Vec<u8>),
+    );
+
+    fn visit_none<E: de::Error>(self) -> Result<V::Value, E> {
+        self.inner.visit_none()
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<V::Value, E> {
+        self.inner.visit_unit()
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<V::Value, D::Error> {
+        self.inner.visit_some(Input::new(deserializer, None))
+    }
+
+    fn visit_newtype_struct<D: Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> Result<V::Value, D::Error> {
+        self.inner
+            .visit_newtype_struct(Input::new(deserializer, None))
+    }
+
+    fn visit_seq<A: de::SeqAccess<'de>>(self, seq: A) -> Result<V::Value, A::Error> {
+        self.inner.visit_seq(Access {
+            inner: seq,
+            fields: None,
+        })
+    }
+
+    fn visit_map<A: de::MapAccess<'de>>(self, map: A) -> Result<V::Value, A::Error> {
+        self.inner.visit_map(Access {
+            inner: map,
+            fields: self.fields,
+        })
+    }
+
+    fn visit_enum<A: de::EnumAccess<'de>>(self, data: A) -> Result<V::Value, A::Error> {
+        self.inner.visit_enum(Access {
+            inner: data,
+            fields: None,
+        })
+    }
+}
+
+/// Reads the parts of a sequence, map or enum through `Input`; `fields`
+/// are those of the struct a map is read into.
+struct Access<A> {
+    inner: A,
+    fields: Option<&'static [&'static str]>,
+}
+
+/// Deserializes a seed's value through `Input`.
+struct Seed<T> {
+    inner: T,
+    key_of: Option<Option<&'static [&'static str]>>,
+}
+
+impl<'de, T: DeserializeSeed<'de>> DeserializeSeed<'de> for Seed<T> {
+    type Value = T::Value;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<T::Value, D::Error> {
+        self.inner
+            .deserialize(Input::new(deserializer, self.key_of))
+    }
+}
+
+fn seed<T>(inner: T) -> Seed<T> {
+    Seed {
+        inner,
+        key_of: None,
+    }
+}
+
+impl<'de, A: de::SeqAccess<'de>> de::SeqAccess<'de> for Access<A> {
+    type Error = A::Error;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        element: T,
+    ) -> Result<Option<T::Value>, A::Error> {
+        self.inner.next_element_seed(seed(element))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        self.inner.size_hint()
+    }
+}
+
+impl<'de, A: de::MapAccess<'de>> de::MapAccess<'de> for Access<A> {
+    type Error = A::Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        key: K,
+    ) -> Result<Option<K::Value>, A::Error> {
+        let key = Seed {
+            inner: key,
+            key_of: Some(self.fields),
+        };
+        self.inner.next_key_seed(key)
+    }
+
+    fn next_value_seed<T: DeserializeSeed<'de>>(&mut self, value: T) -> Result<T::Value, A::Error> {
+        self.inner.next_value_seed(seed(value))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        self.inner.size_hint()
+    }
+}
+
+impl<'de, A: de::EnumAccess<'de>> de::EnumAccess<'de> for Access<A> {
+    type Error = A::Error;
+    type Variant = Access<A::Variant>;
+
+    fn variant_seed<T: DeserializeSeed<'de>>(
+        self,
+        variant: T,
+    ) -> Result<(T::Value, Self::Variant), A::Error> {
+        let (value, variant) = self.inner.variant_seed(variant)?;
+        Ok((
+            value,
+            Access {
+                inner: variant,
+                fields: None,
+            },
+        ))
+    }
+}
+
+impl<'de, A: de::VariantAccess<'de>> de::VariantAccess<'de> for Access<A> {
+    type Error = A::Error;
+
+    fn unit_variant(self) -> Result<(), A::Error> {
+        self.inner.unit_variant()
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, value: T) -> Result<T::Value, A::Error> {
+        self.inner.newtype_variant_seed(seed(value))
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, len: usize, visitor: V) -> Result<V::Value, A::Error> {
+        self.inner.tuple_variant(len, Visit::new(visitor, None))
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, A::Error> {
+        self.inner
+            .struct_variant(fields, Visit::new(visitor, Some(fields)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    struct Report {
+        due_date: String,
+        by_tag: HashMap<String, u32>,
+        #[serde(flatten)]
+        rule: Rule,
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Rule {
+        #[serde(rename = "afterDays")]
+        after_days: u32,
+    }
+
+    fn report() -> Report {
+        Report {
+            due_date: "2024-06-10".to_string(),
+            by_tag: HashMap::from([("workOutside".to_string(), 2)]),
+            rule: Rule { after_days: 30 },
+        }
+    }
+
+    #[test]
+    fn test_keys_round_trip_between_cases() {
+        assert_eq!(snake_case("recurrenceEnd"), "recurrence_end");
+        assert_eq!(camel_case("recurrence_end"), "recurrenceEnd");
+        assert_eq!(camel_case("_id"), "_id");
+    }
+
+    #[derive(Serialize)]
+    #[serde(rename_all = "camelCase")]
+    struct Page {
+        has_more: bool,
+        items: Vec<Report>,
+    }
+
+    #[tokio::test]
+    async fn test_renames_struct_fields_but_not_data_keys() {
+        let page = Page {
+            has_more: true,
+            items: vec![report()],
+        };
+        let written = CASE
+            .scope(KeyCase::Snake, async {
+                serde_json::to_value(Cased(&page)).unwrap()
+            })
+            .await;
+        assert_eq!(
+            written,
+            json!({
+                "has_more": true,
+                "items": [{
+                    "due_date": "2024-06-10",
+                    "by_tag": { "workOutside": 2 },
+                    "after_days": 30
+                }]
+            })
+        );
+        let envelope = CASE
+            .scope(KeyCase::Snake, async {
+                serde_json::to_value(Cased(json!({ "nextCursor": null }))).unwrap()
+            })
+            .await;
+        assert_eq!(envelope, json!({ "next_cursor": null }));
+        assert_eq!(serde_json::to_value(Cased(&page)).unwrap()["hasMore"], true);
+    }
+
+    #[test]
+    fn test_reads_snake_case_fields_into_structs() {
+        let body =
+            json!({ "due_date": "2024-06-10", "by_tag": { "work_outside": 2 }, "after_days": 30 });
+        let read = Report::deserialize(Input::new(body, None)).unwrap();
+        assert_eq!(read.due_date, "2024-06-10");
+        assert_eq!(
+            read.by_tag,
+            HashMap::from([("work_outside".to_string(), 2)])
+        );
+        assert_eq!(read.rule.after_days, 30);
+    }
+
+    #[test]
+    fn test_parses_case_names() {
+        assert_eq!(KeyCase::parse("snake_case"), Some(KeyCase::Snake));
+        assert_eq!(KeyCase::parse("camel"), Some(KeyCase::Camel));
+        assert_eq!(KeyCase::parse("kebab-case"), None);
+    }
+}
@@ -0,0 +1,1156 @@
+use crate::auth::AuthMode;
+use crate::moderation::ModerationMode;
+use crate::oidc;
+use crate::plugins::{Kind, Plugin};
+use crate::sms::RateLimits;
+use crate::transfer;
+use chrono_tz::Tz;
+use spicy_todo_core::cascade::CascadePolicy;
+use spicy_todo_core::encryption::{EncryptedStore, TextCipher};
+use spicy_todo_core::models::TodoCreate;
+use spicy_todo_core::rollover::{OverdueRollover, RolloverMode};
+use spicy_todo_core::{fixtures, snapshot};
+use spicy_todo_core::{InMemoryStore, JournaledStore, TodoService, TodoStore};
+use std::collections::BTreeMap;
+use std::env;
+use std::net::IpAddr;
+use std::path::PathBuf;
+use std::time::Duration;
+
+const DEFAULT_COMPACT_EVERY: usize = 1000;
+const DEFAULT_SNAPSHOT_INTERVAL_SECS: usize = 30;
+const DEFAULT_BACKUP_INTERVAL_SECS: usize = 3600;
+const DEFAULT_BACKUP_RETENTION: usize = 24;
+const DEFAULT_DAILY_CAPACITY_MINUTES: usize = 8 * 60;
+const DEFAULT_SMS_PER_NUMBER_PER_HOUR: usize = 3;
+const DEFAULT_SMS_PER_DAY: usize = 50;
+const DEFAULT_GEOFENCE_RADIUS_METERS: usize = 150;
+const DEFAULT_GEOFENCE_COOLDOWN_SECS: usize = 3600;
+const DEFAULT_IMAP_POLL_SECS: usize = 60;
+const DEFAULT_SCHEDULER_LEASE_TTL_SECS: usize = 300;
+const DEFAULT_SESSION_TTL_SECS: usize = 12 * 3600;
+const DEFAULT_HSTS_MAX_AGE_SECS: usize = 365 * 24 * 3600;
+const DEFAULT_DEMO_RESET_MINUTES: usize = 30;
+const DEFAULT_DEMO_MAX_TODOS: usize = 100;
+const DEFAULT_DEMO_WRITES_PER_MINUTE: usize = 30;
+const DEFAULT_KEEP_ALIVE_SECS: usize = 5;
+const DEFAULT_CLIENT_REQUEST_TIMEOUT_MS: usize = 5000;
+const DEFAULT_CLIENT_DISCONNECT_TIMEOUT_MS: usize = 1000;
+const DEFAULT_BACKLOG: usize = 1024;
+/// Enough for the web frontend, which loads nothing from other origins.
+pub const DEFAULT_CONTENT_SECURITY_POLICY: &str = "default-src 'self'; img-src 'self' data:; \
+    style-src 'self' 'unsafe-inline'; object-src 'none'; base-uri 'self'; \
+    form-action 'self'; frame-ancestors 'none'";
+
+/// Runtime configuration, read from environment variables at startup.
+#[derive(Debug, Clone)]
+pub struct Config {
+    /// Token required by `/api/admin/*` endpoints. When unset, the admin API
+    /// is disabled entirely.
+    pub admin_token: Option<String>,
+    /// Who may use the rest of the API. Read from `AUTH_*` variables; open
+    /// to anyone unless `AUTH_MODE` is set.
+    pub auth: AuthSettings,
+    /// Seed the store at startup (`SEED_SAMPLE_DATA`). Off by default so
+    /// production never boots with fake todos.
+    pub seed_sample_data: bool,
+    /// JSON file with the todos to seed (`SEED_FIXTURE_FILE`). When unset,
+    /// the built-in set named by `seed_fixture` is used.
+    pub seed_fixture_file: Option<PathBuf>,
+    /// Built-in fixture set used when no file is given (`SEED_FIXTURE`).
+    pub seed_fixture: String,
+    /// Allow `/debug/pprof/*` for admins (`ENABLE_PROFILING`). Only has an
+    /// effect in builds with the `profiling` feature.
+    pub profiling_enabled: bool,
+    /// Include did-you-mean id suggestions in todo 404s
+    /// (`SUGGEST_MISSING_IDS`). Meant for integration work; it reveals ids.
+    pub suggest_missing_ids: bool,
+    /// Directory for the write-ahead journal (`JOURNAL_DIR`). When set,
+    /// todos survive restarts; when unset they live only in memory.
+    pub journal_dir: Option<PathBuf>,
+    /// Journal records between snapshot compactions (`JOURNAL_COMPACT_EVERY`).
+    pub journal_compact_every: usize,
+    /// File the whole collection is periodically written to and loaded from
+    /// at boot (`SNAPSHOT_PATH`). A simpler alternative to the journal.
+    pub snapshot_path: Option<PathBuf>,
+    /// Seconds between snapshots (`SNAPSHOT_INTERVAL_SECS`).
+    pub snapshot_interval: Duration,
+    /// Scheduled encrypted backups to S3, enabled by `BACKUP_S3_BUCKET`.
+    pub backup: Option<BackupSettings>,
+    /// Event file for event sourcing mode (`EVENT_STORE_PATH`). Every domain
+    /// event is persisted and the todos are rebuilt from them at boot, so the
+    /// event history and changes feed survive restarts too.
+    pub event_store_path: Option<PathBuf>,
+    /// Key sealing todo text in the journal, snapshot and event store, as
+    /// 64 hex characters (`TODO_ENCRYPTION_KEY`). Text written before it was
+    /// set is still read; losing it loses the text.
+    pub encryption_key: Option<String>,
+    /// Daily pruning of the audit trail, enabled by `AUDIT_RETENTION_DAYS`.
+    /// Not for the event store, whose events are the todos.
+    pub audit_retention: Option<AuditRetentionSettings>,
+    /// What the nightly rollover does with missed occurrences of recurring
+    /// todos (`ROLLOVER_MODE`: `carry-over` or `mark-missed`).
+    pub rollover_mode: RolloverMode,
+    /// Carries overdue one-off todos over to today each midnight; enabled
+    /// by `OVERDUE_ROLLOVER`.
+    pub overdue_rollover: Option<OverdueRolloverSettings>,
+    /// What deleting a todo takes with it (`DELETE_CASCADE`: a comma-separated
+    /// list of `missed` and `history`, or `none`). Defaults to `missed`.
+    pub delete_cascade: CascadePolicy,
+    /// Other instances todos can be handed off to, by name
+    /// (`TRANSFER_PEERS`: `name=url` pairs, comma-separated).
+    pub transfer_peers: BTreeMap<String, String>,
+    /// Urgent reminders by SMS, enabled by `SMS_ACCOUNT_SID`.
+    pub sms: Option<SmsSettings>,
+    /// Reminders, digests and bot commands in a Matrix room, enabled by
+    /// `MATRIX_HOMESERVER`.
+    pub matrix: Option<MatrixSettings>,
+    /// Bot commands in Telegram chats, enabled by `TELEGRAM_BOT_TOKEN`.
+    pub telegram: Option<TelegramSettings>,
+    /// Browser push notifications, enabled by `VAPID_PRIVATE_KEY`.
+    pub web_push: Option<WebPushSettings>,
+    /// Todos from the emails arriving in a mailbox, enabled by `IMAP_HOST`.
+    pub imap: Option<ImapSettings>,
+    /// Mailgun's webhook signing key (`EMAIL_WEBHOOK_SIGNING_KEY`). Without
+    /// it, `POST /api/ingest/email` is disabled.
+    pub email_webhook_signing_key: Option<String>,
+    /// Required as `?token=` on `GET /api/todos/feed.atom` when set
+    /// (`FEED_TOKEN`); otherwise the feed is public like the rest of the API.
+    pub feed_token: Option<String>,
+    /// Language model behind `POST /api/todos/{id}/suggest`, enabled by
+    /// `SUGGEST_LLM_URL`. Without it, suggestions come from local
+    /// heuristics.
+    pub suggest_llm: Option<LlmSettings>,
+    /// Screening of todo text on create and update. Read from
+    /// `MODERATION_*` variables; off unless `MODERATION_MODE` is set.
+    pub moderation: ModerationSettings,
+    /// How close a reported position must be to a todo's location for a
+    /// geo-trigger to count (`GEOFENCE_RADIUS_METERS`).
+    pub geofence_radius_meters: u32,
+    /// Least time between two location reminders for the same todo
+    /// (`GEOFENCE_COOLDOWN_SECS`).
+    pub geofence_cooldown: Duration,
+    /// Estimated minutes of work a day can take before stats flag it as
+    /// over-committed (`DAILY_CAPACITY_MINUTES`).
+    pub daily_capacity_minutes: u32,
+    /// Lease file shared by the replicas of a deployment
+    /// (`SCHEDULER_LEASE_PATH`). When set, jobs that must run once, such as
+    /// reminders and policies, run only on the replica holding the lease.
+    pub scheduler_lease_path: Option<PathBuf>,
+    /// How long the lease outlives its holder's last check
+    /// (`SCHEDULER_LEASE_TTL_SECS`). Longer than a minute, since reminders
+    /// renew it that often.
+    pub scheduler_lease_ttl: Duration,
+    /// Headers hardening every response. Read from `SECURITY_HEADERS` and
+    /// the variables below it; on by default.
+    pub security_headers: SecurityHeaderSettings,
+    /// Public playground mode, enabled by `DEMO_MODE`: todos are wiped on a
+    /// timer and capped, writes are rate limited, and webhooks and chat
+    /// notifiers are off.
+    pub demo: Option<DemoSettings>,
+    /// Worker threads, keep-alive and connection timeouts of the HTTP
+    /// server. Read from `SERVER_*` variables.
+    pub server: ServerSettings,
+}
+
+/// Where and how often to upload backups. Read from `BACKUP_*` variables.
+#[derive(Debug, Clone)]
+#[cfg_attr(not(feature = "backups"), allow(dead_code))]
+pub struct BackupSettings {
+    pub bucket: String,
+    /// Custom endpoint for S3-compatible services such as MinIO or R2
+    /// (`BACKUP_S3_ENDPOINT`). Unset means AWS.
+    pub endpoint: Option<String>,
+    pub region: String,
+    pub access_key: Option<String>,
+    pub secret_key: Option<String>,
+    /// Key prefix for backup objects (`BACKUP_PREFIX`).
+    pub prefix: String,
+    pub interval: Duration,
+    /// How many backups to keep (`BACKUP_RETENTION`).
+    pub retention: usize,
+    /// 64 hex characters (`BACKUP_ENCRYPTION_KEY`); required.
+    pub encryption_key: Option<String>,
+}
+
+/// Twilio-compatible SMS gateway and send limits. Read from `SMS_*`
+/// variables.
+#[derive(Debug, Clone)]
+pub struct SmsSettings {
+    /// API base URL (`SMS_GATEWAY_URL`); defaults to Twilio's.
+    pub gateway_url: String,
+    pub account_sid: String,
+    /// Required (`SMS_AUTH_TOKEN`).
+    pub auth_token: Option<String>,
+    /// Sender number in E.164 (`SMS_FROM`); required.
+    pub from: Option<String>,
+    /// `SMS_MAX_PER_NUMBER_PER_HOUR` and `SMS_MAX_PER_DAY`.
+    pub limits: RateLimits,
+}
+
+/// Matrix bot account and room. Read from `MATRIX_*` variables.
+#[derive(Debug, Clone)]
+pub struct MatrixSettings {
+    pub homeserver: String,
+    /// The bot account's token (`MATRIX_ACCESS_TOKEN`); required.
+    pub access_token: Option<String>,
+    /// Room id, not alias (`MATRIX_ROOM_ID`); required. The bot must
+    /// already be joined.
+    pub room_id: Option<String>,
+    /// Post the agenda digest every UTC midnight (`MATRIX_DAILY_DIGEST`).
+    pub daily_digest: bool,
+}
+
+/// Telegram bot token and the chats it answers. Read from `TELEGRAM_*`
+/// variables.
+#[derive(Debug, Clone)]
+pub struct TelegramSettings {
+    /// Bot API base URL (`TELEGRAM_API_URL`); defaults to Telegram's, but
+    /// can point at a self-hosted Bot API server.
+    pub api_url: String,
+    pub token: String,
+    /// Comma-separated chat ids (`TELEGRAM_ALLOWED_CHATS`); required.
+    pub allowed_chats: Option<String>,
+}
+
+/// Mailbox to turn emails into todos from. Read from `IMAP_*` variables.
+#[derive(Debug, Clone)]
+pub struct ImapSettings {
+    pub host: String,
+    /// `IMAP_PORT`; defaults to 993, or 143 without TLS.
+    pub port: u16,
+    /// Implicit TLS (`IMAP_TLS`), on by default. Turn it off only for a
+    /// local bridge such as Proton Mail Bridge.
+    pub tls: bool,
+    /// Required (`IMAP_USERNAME`).
+    pub username: Option<String>,
+    /// Required (`IMAP_PASSWORD`); an app password where the provider
+    /// offers them.
+    pub password: Option<String>,
+    /// Folder to read (`IMAP_MAILBOX`); defaults to `INBOX`.
+    pub mailbox: String,
+    /// `IMAP_POLL_SECS`.
+    pub interval: Duration,
+}
+
+/// How todo text is moderated. Read from `MODERATION_*` variables.
+#[derive(Debug, Clone, Default)]
+pub struct ModerationSettings {
+    /// `off`, `reject` or `mask` (`MODERATION_MODE`).
+    pub mode: ModerationMode,
+    /// Words to screen besides the built-in list (`MODERATION_WORDS`,
+    /// comma-separated).
+    pub words: Vec<String>,
+    /// More words, one per line (`MODERATION_WORDS_FILE`).
+    pub words_file: Option<PathBuf>,
+    /// A moderation service consulted after the word list
+    /// (`MODERATION_URL`).
+    pub url: Option<String>,
+    pub api_key: Option<String>,
+}
+
+/// How long the audit trail is kept. Read from `AUDIT_*` variables.
+#[derive(Debug, Clone)]
+pub struct AuditRetentionSettings {
+    /// Age past which entries are pruned (`AUDIT_RETENTION_DAYS`).
+    pub max_age: Duration,
+    /// Where pruned entries are archived first, as JSON lines in a file per
+    /// day (`AUDIT_ARCHIVE_DIR`). Without it they are dropped.
+    pub archive_dir: Option<PathBuf>,
+}
+
+/// The opt-in rollover of overdue todos. Read from `OVERDUE_ROLLOVER*`
+/// variables.
+#[derive(Debug, Clone, Copy)]
+pub struct OverdueRolloverSettings {
+    /// Whether todos are moved or copied (`OVERDUE_ROLLOVER`: `move` or
+    /// `copy`).
+    pub mode: OverdueRollover,
+    /// Whose midnight counts (`OVERDUE_ROLLOVER_TIMEZONE`, an IANA name).
+    /// Defaults to UTC.
+    pub timezone: Tz,
+}
+
+impl OverdueRolloverSettings {
+    fn from_env() -> Option<Self> {
+        let mode = non_empty_var("OVERDUE_ROLLOVER")?
+            .parse()
+            .map_err(|e| eprintln!("Ignoring OVERDUE_ROLLOVER: {}", e))
+            .ok()?;
+        let timezone = non_empty_var("OVERDUE_ROLLOVER_TIMEZONE")
+            .and_then(|name| {
+                let timezone = name.trim().parse().ok();
+                if timezone.is_none() {
+                    eprintln!("Ignoring OVERDUE_ROLLOVER_TIMEZONE: unknown '{}'", name);
+                }
+                timezone
+            })
+            .unwrap_or(Tz::UTC);
+        Some(OverdueRolloverSettings { mode, timezone })
+    }
+}
+
+/// The public playground. Read from `DEMO_*` variables.
+#[derive(Debug, Clone)]
+pub struct DemoSettings {
+    /// How often every todo is wiped (`DEMO_RESET_MINUTES`). The startup
+    /// fixtures are seeded again, so pair it with `SEED_SAMPLE_DATA`.
+    pub reset_interval: Duration,
+    /// Most todos the demo holds (`DEMO_MAX_TODOS`); adding more is refused
+    /// until the next reset.
+    pub max_todos: usize,
+    /// Writes each client address may make in a minute
+    /// (`DEMO_WRITES_PER_MINUTE`).
+    pub writes_per_minute: usize,
+    /// Shown in the root endpoint's payload (`DEMO_BANNER`).
+    pub banner: String,
+    /// Proxies whose `X-Forwarded-For` is believed when counting writes
+    /// per client (`DEMO_TRUSTED_PROXIES`, comma-separated addresses).
+    /// Everyone else is counted by the address they connect from.
+    pub trusted_proxies: Vec<IpAddr>,
+}
+
+impl DemoSettings {
+    fn from_env() -> Self {
+        let reset_minutes = usize_var("DEMO_RESET_MINUTES", DEFAULT_DEMO_RESET_MINUTES).max(1);
+        DemoSettings {
+            reset_interval: Duration::from_secs(reset_minutes as u64 * 60),
+            max_todos: usize_var("DEMO_MAX_TODOS", DEFAULT_DEMO_MAX_TODOS),
+            writes_per_minute: usize_var("DEMO_WRITES_PER_MINUTE", DEFAULT_DEMO_WRITES_PER_MINUTE)
+                .max(1),
+            banner: non_empty_var("DEMO_BANNER").unwrap_or_else(|| demo_banner(reset_minutes)),
+            trusted_proxies: non_empty_var("DEMO_TRUSTED_PROXIES")
+                .map(|value| {
+                    value
+                        .split(',')
+                        .filter_map(|address| {
+                            let parsed = address.trim().parse().ok();
+                            if parsed.is_none() {
+                                eprintln!("Ignoring DEMO_TRUSTED_PROXIES entry '{}'", address);
+                            }
+                            parsed
+                        })
+                        .collect()
+                })
+                .unwrap_or_default(),
+        }
+    }
+}
+
+fn demo_banner(reset_minutes: usize) -> String {
+    format!(
+        "Public demo: todos are shared with everyone and reset every {} minutes.",
+        reset_minutes
+    )
+}
+
+impl Default for DemoSettings {
+    fn default() -> Self {
+        DemoSettings {
+            reset_interval: Duration::from_secs(DEFAULT_DEMO_RESET_MINUTES as u64 * 60),
+            max_todos: DEFAULT_DEMO_MAX_TODOS,
+            writes_per_minute: DEFAULT_DEMO_WRITES_PER_MINUTE,
+            banner: demo_banner(DEFAULT_DEMO_RESET_MINUTES),
+            trusted_proxies: Vec::new(),
+        }
+    }
+}
+
+/// Tuning of the HTTP server itself. Read from `SERVER_*` variables; the
+/// defaults are Actix's own.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ServerSettings {
+    /// Worker threads (`SERVER_WORKERS`). Defaults to the CPUs available to
+    /// the process, which honors a container's CPU quota.
+    pub workers: usize,
+    /// How long an idle connection stays open for the next request
+    /// (`SERVER_KEEP_ALIVE_SECS`). `0` closes it after every response.
+    pub keep_alive: Duration,
+    /// How long a client has to send a request's headers before getting a
+    /// 408 (`SERVER_CLIENT_REQUEST_TIMEOUT_MS`). `0` waits forever.
+    pub client_request_timeout: Duration,
+    /// How long a closing connection has to shut down cleanly
+    /// (`SERVER_CLIENT_DISCONNECT_TIMEOUT_MS`). `0` waits forever.
+    pub client_disconnect_timeout: Duration,
+    /// Connections that may wait to be accepted before more are refused
+    /// (`SERVER_BACKLOG`).
+    pub backlog: u32,
+}
+
+impl ServerSettings {
+    fn from_env() -> Self {
+        let defaults = ServerSettings::default();
+        ServerSettings {
+            workers: usize_var("SERVER_WORKERS", defaults.workers).max(1),
+            keep_alive: Duration::from_secs(usize_var(
+                "SERVER_KEEP_ALIVE_SECS",
+                DEFAULT_KEEP_ALIVE_SECS,
+            ) as u64),
+            client_request_timeout: Duration::from_millis(usize_var(
+                "SERVER_CLIENT_REQUEST_TIMEOUT_MS",
+                DEFAULT_CLIENT_REQUEST_TIMEOUT_MS,
+            ) as u64),
+            client_disconnect_timeout: Duration::from_millis(usize_var(
+                "SERVER_CLIENT_DISCONNECT_TIMEOUT_MS",
+                DEFAULT_CLIENT_DISCONNECT_TIMEOUT_MS,
+            ) as u64),
+            backlog: usize_var("SERVER_BACKLOG", DEFAULT_BACKLOG)
+                .try_into()
+                .unwrap_or(u32::MAX),
+        }
+    }
+
+    /// The effective settings on one line, for the startup log.
+    pub fn summary(&self) -> String {
+        let millis = |timeout: Duration| match timeout.as_millis() {
+            0 => "none".to_string(),
+            ms => format!("{}ms", ms),
+        };
+        let keep_alive = match self.keep_alive.as_secs() {
+            0 => "off".to_string(),
+            secs => format!("{}s", secs),
+        };
+        format!(
+            "{} workers, keep-alive {}, request timeout {}, disconnect timeout {}, backlog {}",
+            self.workers,
+            keep_alive,
+            millis(self.client_request_timeout),
+            millis(self.client_disconnect_timeout),
+            self.backlog
+        )
+    }
+}
+
+impl Default for ServerSettings {
+    fn default() -> Self {
+        ServerSettings {
+            workers: std::thread::available_parallelism().map_or(1, |cpus| cpus.get()),
+            keep_alive: Duration::from_secs(DEFAULT_KEEP_ALIVE_SECS as u64),
+            client_request_timeout: Duration::from_millis(DEFAULT_CLIENT_REQUEST_TIMEOUT_MS as u64),
+            client_disconnect_timeout: Duration::from_millis(
+                DEFAULT_CLIENT_DISCONNECT_TIMEOUT_MS as u64,
+            ),
+            backlog: DEFAULT_BACKLOG as u32,
+        }
+    }
+}
+
+/// Response headers that browsers and security scanners look for.
+#[derive(Debug, Clone)]
+pub struct SecurityHeaderSettings {
+    /// Send them at all (`SECURITY_HEADERS`). Turn off when a proxy in
+    /// front already does.
+    pub enabled: bool,
+    /// `Content-Security-Policy` (`CONTENT_SECURITY_POLICY`). The default
+    /// allows only this origin.
+    pub content_security_policy: String,
+    /// `Referrer-Policy` (`REFERRER_POLICY`).
+    pub referrer_policy: String,
+    /// `Strict-Transport-Security` max-age, sent on requests that came over
+    /// HTTPS, directly or per `X-Forwarded-Proto` (`HSTS_MAX_AGE_SECS`).
+    /// Zero leaves the header out.
+    pub hsts_max_age: Duration,
+}
+
+impl Default for SecurityHeaderSettings {
+    fn default() -> Self {
+        SecurityHeaderSettings {
+            enabled: true,
+            content_security_policy: DEFAULT_CONTENT_SECURITY_POLICY.to_string(),
+            referrer_policy: "no-referrer".to_string(),
+            hsts_max_age: Duration::from_secs(DEFAULT_HSTS_MAX_AGE_SECS as u64),
+        }
+    }
+}
+
+impl SecurityHeaderSettings {
+    fn from_env() -> Self {
+        let defaults = SecurityHeaderSettings::default();
+        SecurityHeaderSettings {
+            enabled: bool_var("SECURITY_HEADERS", true),
+            content_security_policy: non_empty_var("CONTENT_SECURITY_POLICY")
+                .unwrap_or(defaults.content_security_policy),
+            referrer_policy: non_empty_var("REFERRER_POLICY").unwrap_or(defaults.referrer_policy),
+            hsts_max_age: Duration::from_secs(
+                usize_var("HSTS_MAX_AGE_SECS", DEFAULT_HSTS_MAX_AGE_SECS) as u64,
+            ),
+        }
+    }
+}
+
+/// How API requests authenticate. Read from `AUTH_*` variables.
+#[derive(Debug, Clone)]
+pub struct AuthSettings {
+    /// `off`, `token` or `session` (`AUTH_MODE`).
+    pub mode: AuthMode,
+    /// Bearer tokens accepted in token mode (`AUTH_TOKENS`,
+    /// comma-separated).
+    pub tokens: Vec<String>,
+    /// Who can log in in session mode (`AUTH_USERS_FILE`): `name:hash`
+    /// lines with Argon2 hashes.
+    pub users_file: Option<PathBuf>,
+    /// 128 hex characters sealing the session cookies
+    /// (`AUTH_SESSION_KEY`). Without it a key is generated at boot, and
+    /// every session ends with the process.
+    pub session_key: Option<String>,
+    /// Idle time before a session expires (`AUTH_SESSION_TTL_SECS`).
+    pub session_ttl: Duration,
+    /// Send the session cookie over HTTPS only (`AUTH_COOKIE_SECURE`). On
+    /// by default; turn it off for plain-HTTP development only.
+    pub cookie_secure: bool,
+    /// Sign-in with Google or GitHub in session mode, enabled by
+    /// `OIDC_REDIRECT_URL`.
+    pub oidc: Option<OidcSettings>,
+}
+
+/// Sign-in through OAuth2/OpenID Connect providers. Read from `OIDC_*`
+/// variables.
+#[derive(Debug, Clone)]
+pub struct OidcSettings {
+    /// Where providers send the browser back: this server's public
+    /// `/api/auth/callback` URL, as registered with each provider.
+    pub redirect_url: String,
+    /// By name, from `OIDC_GOOGLE_CLIENT_ID` and `OIDC_GOOGLE_CLIENT_SECRET`,
+    /// and the same for GitHub.
+    pub providers: BTreeMap<String, OidcProviderSettings>,
+    /// Email domains whose first sign-in creates an account
+    /// (`OIDC_ALLOWED_DOMAINS`, comma-separated). Others can only sign in
+    /// with an identity linked while signed in some other way.
+    pub allowed_domains: Vec<String>,
+    /// Where linked identities are kept across restarts
+    /// (`OIDC_ACCOUNTS_FILE`).
+    pub accounts_file: Option<PathBuf>,
+    /// Where the browser lands once signed in (`OIDC_POST_LOGIN_URL`).
+    pub post_login_url: String,
+}
+
+/// One provider's client registration and endpoints.
+#[derive(Debug, Clone)]
+pub struct OidcProviderSettings {
+    pub client_id: String,
+    pub client_secret: String,
+    pub authorize_url: String,
+    pub token_url: String,
+    /// OpenID Connect's userinfo endpoint, or GitHub's `/user`.
+    pub userinfo_url: String,
+    pub scope: String,
+}
+
+impl Default for AuthSettings {
+    fn default() -> Self {
+        AuthSettings {
+            mode: AuthMode::Off,
+            tokens: Vec::new(),
+            users_file: None,
+            session_key: None,
+            session_ttl: Duration::from_secs(DEFAULT_SESSION_TTL_SECS as u64),
+            cookie_secure: true,
+            oidc: None,
+        }
+    }
+}
+
+impl AuthSettings {
+    fn from_env() -> Self {
+        AuthSettings {
+            mode: non_empty_var("AUTH_MODE")
+                .and_then(|value| {
+                    value
+                        .parse()
+                        .map_err(|e| eprintln!("Ignoring AUTH_MODE: {}", e))
+                        .ok()
+                })
+                .unwrap_or_default(),
+            tokens: non_empty_var("AUTH_TOKENS")
+                .map(|value| {
+                    value
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|token| !token.is_empty())
+                        .map(str::to_string)
+                        .collect()
+                })
+                .unwrap_or_default(),
+            users_file: non_empty_var("AUTH_USERS_FILE").map(PathBuf::from),
+            session_key: non_empty_var("AUTH_SESSION_KEY"),
+            session_ttl: Duration::from_secs(
+                usize_var("AUTH_SESSION_TTL_SECS", DEFAULT_SESSION_TTL_SECS).max(1) as u64,
+            ),
+            cookie_secure: bool_var("AUTH_COOKIE_SECURE", true),
+            oidc: non_empty_var("OIDC_REDIRECT_URL").map(OidcSettings::from_env),
+        }
+    }
+}
+
+impl OidcSettings {
+    fn from_env(redirect_url: String) -> Self {
+        let providers = oidc::PROVIDERS
+            .iter()
+            .filter_map(|&name| {
+                let prefix = format!("OIDC_{}", name.to_uppercase());
+                let client_id = non_empty_var(&format!("{}_CLIENT_ID", prefix))?;
+                let client_secret = non_empty_var(&format!("{}_CLIENT_SECRET", prefix))?;
+                let settings = oidc::builtin(name, client_id, client_secret)?;
+                Some((name.to_string(), settings))
+            })
+            .collect();
+        OidcSettings {
+            redirect_url,
+            providers,
+            allowed_domains: non_empty_var("OIDC_ALLOWED_DOMAINS")
+                .map(|value| {
+                    value
+                        .split(',')
+                        .map(|domain| domain.trim().to_lowercase())
+                        .filter(|domain| !domain.is_empty())
+                        .collect()
+                })
+                .unwrap_or_default(),
+            accounts_file: non_empty_var("OIDC_ACCOUNTS_FILE").map(PathBuf::from),
+            post_login_url: non_empty_var("OIDC_POST_LOGIN_URL").unwrap_or_else(|| "/".to_string()),
+        }
+    }
+}
+
+/// An OpenAI-compatible chat completions endpoint. Read from
+/// `SUGGEST_LLM_*` variables.
+#[derive(Debug, Clone)]
+pub struct LlmSettings {
+    /// Full URL, e.g. `https://api.openai.com/v1/chat/completions`.
+    pub url: String,
+    /// Sent as a bearer token (`SUGGEST_LLM_API_KEY`); local servers such
+    /// as Ollama need none.
+    pub api_key: Option<String>,
+    /// `SUGGEST_LLM_MODEL`; defaults to `gpt-4o-mini`.
+    pub model: String,
+}
+
+/// VAPID identity for Web Push. Read from `VAPID_*` variables.
+#[derive(Debug, Clone)]
+pub struct WebPushSettings {
+    /// Base64url P-256 private key, as printed by `web-push
+    /// generate-vapid-keys`; the public key is derived from it.
+    pub private_key: String,
+    /// Contact for push services (`VAPID_SUBJECT`), a `mailto:` or
+    /// `https:` URL; required.
+    pub subject: Option<String>,
+}
+
+impl MatrixSettings {
+    fn from_env(homeserver: String) -> Self {
+        MatrixSettings {
+            homeserver,
+            access_token: non_empty_var("MATRIX_ACCESS_TOKEN"),
+            room_id: non_empty_var("MATRIX_ROOM_ID"),
+            daily_digest: bool_var("MATRIX_DAILY_DIGEST", true),
+        }
+    }
+}
+
+impl TelegramSettings {
+    fn from_env(token: String) -> Self {
+        TelegramSettings {
+            api_url: non_empty_var("TELEGRAM_API_URL")
+                .unwrap_or_else(|| "https://api.telegram.org".to_string()),
+            token,
+            allowed_chats: non_empty_var("TELEGRAM_ALLOWED_CHATS"),
+        }
+    }
+}
+
+impl ImapSettings {
+    fn from_env(host: String) -> Self {
+        let tls = bool_var("IMAP_TLS", true);
+        let default_port = if tls { 993 } else { 143 };
+        ImapSettings {
+            host,
+            port: usize_var("IMAP_PORT", default_port)
+                .try_into()
+                .unwrap_or(default_port as u16),
+            tls,
+            username: non_empty_var("IMAP_USERNAME"),
+            password: non_empty_var("IMAP_PASSWORD"),
+            mailbox: non_empty_var("IMAP_MAILBOX").unwrap_or_else(|| "INBOX".to_string()),
+            interval: Duration::from_secs(
+                usize_var("IMAP_POLL_SECS", DEFAULT_IMAP_POLL_SECS).max(1) as u64,
+            ),
+        }
+    }
+}
+
+impl SmsSettings {
+    fn from_env(account_sid: String) -> Self {
+        SmsSettings {
+            gateway_url: non_empty_var("SMS_GATEWAY_URL")
+                .unwrap_or_else(|| "https://api.twilio.com".to_string()),
+            account_sid,
+            auth_token: non_empty_var("SMS_AUTH_TOKEN"),
+            from: non_empty_var("SMS_FROM"),
+            limits: RateLimits {
+                per_number_per_hour: usize_var(
+                    "SMS_MAX_PER_NUMBER_PER_HOUR",
+                    DEFAULT_SMS_PER_NUMBER_PER_HOUR,
+                ),
+                per_day: usize_var("SMS_MAX_PER_DAY", DEFAULT_SMS_PER_DAY),
+            },
+        }
+    }
+}
+
+impl BackupSettings {
+    fn from_env(bucket: String) -> Self {
+        BackupSettings {
+            bucket,
+            endpoint: non_empty_var("BACKUP_S3_ENDPOINT"),
+            region: non_empty_var("BACKUP_S3_REGION").unwrap_or_else(|| "us-east-1".to_string()),
+            access_key: non_empty_var("BACKUP_S3_ACCESS_KEY"),
+            secret_key: non_empty_var("BACKUP_S3_SECRET_KEY"),
+            prefix: non_empty_var("BACKUP_PREFIX").unwrap_or_else(|| "spicy-todo/".to_string()),
+            interval: Duration::from_secs(
+                usize_var("BACKUP_INTERVAL_SECS", DEFAULT_BACKUP_INTERVAL_SECS).max(1) as u64,
+            ),
+            retention: usize_var("BACKUP_RETENTION", DEFAULT_BACKUP_RETENTION),
+            encryption_key: non_empty_var("BACKUP_ENCRYPTION_KEY"),
+        }
+    }
+}
+
+impl Config {
+    pub fn from_env() -> Self {
+        Config {
+            admin_token: non_empty_var("ADMIN_TOKEN"),
+            auth: AuthSettings::from_env(),
+            seed_sample_data: bool_var("SEED_SAMPLE_DATA", false),
+            seed_fixture_file: non_empty_var("SEED_FIXTURE_FILE").map(PathBuf::from),
+            seed_fixture: non_empty_var("SEED_FIXTURE").unwrap_or_else(|| "default".to_string()),
+            profiling_enabled: bool_var("ENABLE_PROFILING", false),
+            suggest_missing_ids: bool_var("SUGGEST_MISSING_IDS", false),
+            journal_dir: non_empty_var("JOURNAL_DIR").map(PathBuf::from),
+            journal_compact_every: usize_var("JOURNAL_COMPACT_EVERY", DEFAULT_COMPACT_EVERY),
+            snapshot_path: non_empty_var("SNAPSHOT_PATH").map(PathBuf::from),
+            snapshot_interval: Duration::from_secs(
+                usize_var("SNAPSHOT_INTERVAL_SECS", DEFAULT_SNAPSHOT_INTERVAL_SECS).max(1) as u64,
+            ),
+            backup: non_empty_var("BACKUP_S3_BUCKET").map(BackupSettings::from_env),
+            encryption_key: non_empty_var("TODO_ENCRYPTION_KEY"),
+            audit_retention: non_empty_var("AUDIT_RETENTION_DAYS")
+                .and_then(|days| {
+                    days.trim()
+                        .parse::<u64>()
+                        .map_err(|_| eprintln!("Ignoring AUDIT_RETENTION_DAYS: not a number"))
+                        .ok()
+                })
+                .map(|days| AuditRetentionSettings {
+                    max_age: Duration::from_secs(days.max(1) * 24 * 3600),
+                    archive_dir: non_empty_var("AUDIT_ARCHIVE_DIR").map(PathBuf::from),
+                }),
+            event_store_path: non_empty_var("EVENT_STORE_PATH").map(PathBuf::from),
+            rollover_mode: non_empty_var("ROLLOVER_MODE")
+                .and_then(|value| value.parse().ok())
+                .unwrap_or_default(),
+            overdue_rollover: OverdueRolloverSettings::from_env(),
+            delete_cascade: non_empty_var("DELETE_CASCADE")
+                .and_then(|value| value.parse().ok())
+                .unwrap_or_default(),
+            transfer_peers: non_empty_var("TRANSFER_PEERS")
+                .map(|value| {
+                    transfer::parse_peers(&value).unwrap_or_else(|e| {
+                        eprintln!("Ignoring TRANSFER_PEERS: {}", e);
+                        BTreeMap::new()
+                    })
+                })
+                .unwrap_or_default(),
+            sms: non_empty_var("SMS_ACCOUNT_SID").map(SmsSettings::from_env),
+            matrix: non_empty_var("MATRIX_HOMESERVER").map(MatrixSettings::from_env),
+            telegram: non_empty_var("TELEGRAM_BOT_TOKEN").map(TelegramSettings::from_env),
+            web_push: non_empty_var("VAPID_PRIVATE_KEY").map(|private_key| WebPushSettings {
+                private_key,
+                subject: non_empty_var("VAPID_SUBJECT"),
+            }),
+            imap: non_empty_var("IMAP_HOST").map(ImapSettings::from_env),
+            email_webhook_signing_key: non_empty_var("EMAIL_WEBHOOK_SIGNING_KEY"),
+            feed_token: non_empty_var("FEED_TOKEN"),
+            moderation: ModerationSettings {
+                mode: non_empty_var("MODERATION_MODE")
+                    .and_then(|value| {
+                        value
+                            .parse()
+                            .map_err(|e| eprintln!("Ignoring MODERATION_MODE: {}", e))
+                            .ok()
+                    })
+                    .unwrap_or_default(),
+                words: non_empty_var("MODERATION_WORDS")
+                    .map(|value| value.split(',').map(|word| word.trim().to_string()).collect())
+                    .unwrap_or_default(),
+                words_file: non_empty_var("MODERATION_WORDS_FILE").map(PathBuf::from),
+                url: non_empty_var("MODERATION_URL"),
+                api_key: non_empty_var("MODERATION_API_KEY"),
+            },
+            suggest_llm: non_empty_var("SUGGEST_LLM_URL").map(|url| LlmSettings {
+                url,
+                api_key: non_empty_var("SUGGEST_LLM_API_KEY"),
+                model: non_empty_var("SUGGEST_LLM_MODEL")
+                    .unwrap_or_else(|| "gpt-4o-mini".to_string()),
+            }),
+            geofence_radius_meters: usize_var(
+                "GEOFENCE_RADIUS_METERS",
+                DEFAULT_GEOFENCE_RADIUS_METERS,
+            )
+            .try_into()
+            .unwrap_or(u32::MAX),
+            geofence_cooldown: Duration::from_secs(
+                usize_var("GEOFENCE_COOLDOWN_SECS", DEFAULT_GEOFENCE_COOLDOWN_SECS) as u64,
+            ),
+            daily_capacity_minutes: usize_var(
+                "DAILY_CAPACITY_MINUTES",
+                DEFAULT_DAILY_CAPACITY_MINUTES,
+            )
+            .try_into()
+            .unwrap_or(u32::MAX),
+            scheduler_lease_path: non_empty_var("SCHEDULER_LEASE_PATH").map(PathBuf::from),
+            scheduler_lease_ttl: Duration::from_secs(
+                usize_var("SCHEDULER_LEASE_TTL_SECS", DEFAULT_SCHEDULER_LEASE_TTL_SECS).max(1)
+                    as u64,
+            ),
+            security_headers: SecurityHeaderSettings::from_env(),
+            demo: bool_var("DEMO_MODE", false).then(DemoSettings::from_env),
+            server: ServerSettings::from_env(),
+        }
+    }
+
+    /// Resolves the startup dataset: nothing unless seeding is enabled, then
+    /// the fixture file if one is set, else the named built-in set.
+    pub fn startup_fixtures(&self) -> Result<Vec<TodoCreate>, String> {
+        if !self.seed_sample_data {
+            return Ok(Vec::new());
+        }
+        match &self.seed_fixture_file {
+            Some(path) => fixtures::load_file(path),
+            None => fixtures::builtin(&self.seed_fixture)
+                .ok_or_else(|| format!("Unknown fixture set '{}'", self.seed_fixture)),
+        }
+    }
+
+    /// Builds the todo service on the configured persistence: the event
+    /// store, or else whatever storage backend `store` opens.
+    pub fn service(&self) -> std::io::Result<TodoService> {
+        let cipher = self.text_cipher()?;
+        let Some(path) = &self.event_store_path else {
+            return Ok(TodoService::with_store(self.store()?));
+        };
+        if self.journal_dir.is_some() || self.snapshot_path.is_some() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "EVENT_STORE_PATH cannot be combined with JOURNAL_DIR or SNAPSHOT_PATH",
+            ));
+        }
+        if self.audit_retention.is_some() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "EVENT_STORE_PATH cannot be combined with AUDIT_RETENTION_DAYS",
+            ));
+        }
+        match cipher {
+            Some(cipher) => TodoService::event_sourced_encrypted(path, cipher),
+            None => TodoService::event_sourced(path),
+        }
+    }
+
+    /// The cipher for todo text at rest, when `encryption_key` is set.
+    pub fn text_cipher(&self) -> std::io::Result<Option<TextCipher>> {
+        self.encryption_key
+            .as_deref()
+            .map(TextCipher::from_hex)
+            .transpose()
+            .map_err(|e| {
+                std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    format!("TODO_ENCRYPTION_KEY: {}", e),
+                )
+            })
+    }
+
+    /// Opens the configured storage backend, restoring the journal or
+    /// snapshot if one is configured.
+    pub fn store(&self) -> std::io::Result<Box<dyn TodoStore>> {
+        match (&self.journal_dir, &self.snapshot_path) {
+            (Some(_), Some(_)) => Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "JOURNAL_DIR and SNAPSHOT_PATH are mutually exclusive",
+            )),
+            (Some(dir), None) => {
+                let journaled = JournaledStore::open(dir, self.journal_compact_every)?;
+                match self.text_cipher()? {
+                    Some(cipher) => Ok(Box::new(EncryptedStore::new(Box::new(journaled), cipher)?)),
+                    None => Ok(Box::new(journaled)),
+                }
+            }
+            (None, Some(path)) => {
+                let cipher = self.text_cipher()?;
+                let store = InMemoryStore::new();
+                for todo in snapshot::load(path)? {
+                    let todo = match &cipher {
+                        Some(cipher) => cipher
+                            .open_todo(todo)
+                            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?,
+                        None => todo,
+                    };
+                    store.insert(todo);
+                }
+                Ok(Box::new(store))
+            }
+            (None, None) => Ok(Box::new(InMemoryStore::new())),
+        }
+    }
+}
+
+/// The storage backends `Config::service` picks from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StorageBackend {
+    Memory,
+    Journal,
+    Snapshot,
+    EventStore,
+}
+
+impl Plugin for StorageBackend {
+    fn name(&self) -> &'static str {
+        match self {
+            StorageBackend::Memory => "memory",
+            StorageBackend::Journal => "journal",
+            StorageBackend::Snapshot => "snapshot",
+            StorageBackend::EventStore => "event-store",
+        }
+    }
+
+    fn kind(&self) -> Kind {
+        Kind::Storage
+    }
+
+    fn description(&self) -> &'static str {
+        match self {
+            StorageBackend::Memory => "Keeps todos in memory",
+            StorageBackend::Journal => "Appends every change to a journal on disk",
+            StorageBackend::Snapshot => "Writes the whole list to a file periodically",
+            StorageBackend::EventStore => "Rebuilds todos from an append-only event log",
+        }
+    }
+
+    fn enabled(&self, config: &Config) -> bool {
+        match self {
+            // Snapshots are taken of the in-memory store.
+            StorageBackend::Memory => {
+                config.journal_dir.is_none() && config.event_store_path.is_none()
+            }
+            StorageBackend::Journal => config.journal_dir.is_some(),
+            StorageBackend::Snapshot => config.snapshot_path.is_some(),
+            StorageBackend::EventStore => config.event_store_path.is_some(),
+        }
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            admin_token: None,
+            auth: AuthSettings::default(),
+            seed_sample_data: false,
+            seed_fixture_file: None,
+            seed_fixture: "default".to_string(),
+            profiling_enabled: false,
+            suggest_missing_ids: false,
+            journal_dir: None,
+            journal_compact_every: DEFAULT_COMPACT_EVERY,
+            snapshot_path: None,
+            snapshot_interval: Duration::from_secs(DEFAULT_SNAPSHOT_INTERVAL_SECS as u64),
+            backup: None,
+            event_store_path: None,
+            encryption_key: None,
+            audit_retention: None,
+            rollover_mode: RolloverMode::default(),
+            overdue_rollover: None,
+            delete_cascade: CascadePolicy::default(),
+            transfer_peers: BTreeMap::new(),
+            sms: None,
+            matrix: None,
+            telegram: None,
+            web_push: None,
+            imap: None,
+            email_webhook_signing_key: None,
+            feed_token: None,
+            suggest_llm: None,
+            moderation: ModerationSettings::default(),
+            geofence_radius_meters: DEFAULT_GEOFENCE_RADIUS_METERS as u32,
+            geofence_cooldown: Duration::from_secs(DEFAULT_GEOFENCE_COOLDOWN_SECS as u64),
+            daily_capacity_minutes: DEFAULT_DAILY_CAPACITY_MINUTES as u32,
+            scheduler_lease_path: None,
+            scheduler_lease_ttl: Duration::from_secs(DEFAULT_SCHEDULER_LEASE_TTL_SECS as u64),
+            security_headers: SecurityHeaderSettings::default(),
+            demo: None,
+            server: ServerSettings::default(),
+        }
+    }
+}
+
+fn non_empty_var(name: &str) -> Option<String> {
+    env::var(name).ok().filter(|value| !value.trim().is_empty())
+}
+
+fn bool_var(name: &str, default: bool) -> bool {
+    match non_empty_var(name) {
+        Some(value) => parse_bool(&value).unwrap_or(default),
+        None => default,
+    }
+}
+
+fn usize_var(name: &str, default: usize) -> usize {
+    non_empty_var(name)
+        .and_then(|value| value.trim().parse().ok())
+        .unwrap_or(default)
+}
+
+fn parse_bool(value: &str) -> Option<bool> {
+    match value.trim().to_lowercase().as_str() {
+        "1" | "true" | "yes" | "on" => Some(true),
+        "0" | "false" | "no" | "off" => Some(false),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spicy_todo_core::TodoService;
+
+    #[test]
+    fn test_parse_bool() {
+        assert_eq!(parse_bool("TRUE"), Some(true));
+        assert_eq!(parse_bool(" on "), Some(true));
+        assert_eq!(parse_bool("0"), Some(false));
+        assert_eq!(parse_bool("maybe"), None);
+    }
+
+    #[test]
+    fn test_server_settings_summary() {
+        let settings = ServerSettings {
+            workers: 2,
+            keep_alive: Duration::ZERO,
+            ..Default::default()
+        };
+        assert_eq!(
+            settings.summary(),
+            "2 workers, keep-alive off, request timeout 5000ms, disconnect timeout 1000ms, \
+             backlog 1024"
+        );
+    }
+
+    #[test]
+    fn test_startup_fixtures_respects_switch() {
+        let mut config = Config {
+            seed_fixture: "demo".to_string(),
+            ..Default::default()
+        };
+        assert!(config.startup_fixtures().unwrap().is_empty());
+
+        config.seed_sample_data = true;
+        assert_eq!(
+            config.startup_fixtures().unwrap().len(),
+            fixtures::builtin("demo").unwrap().len()
+        );
+
+        config.seed_fixture = "missing".to_string();
+        assert!(config.startup_fixtures().is_err());
+    }
+
+    #[test]
+    fn test_store_uses_journal_dir() {
+        let dir = env::temp_dir().join(format!("spicy-config-{}", uuid::Uuid::new_v4()));
+        let config = Config {
+            journal_dir: Some(dir.clone()),
+            ..Default::default()
+        };
+        let service = TodoService::with_store(config.store().unwrap());
+        let seeded = service.seed(fixtures::builtin("default").unwrap()).len();
+        drop(service);
+        assert_eq!(config.store().unwrap().count(), seeded);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_store_loads_snapshot() {
+        let dir = env::temp_dir().join(format!("spicy-config-{}", uuid::Uuid::new_v4()));
+        let path = dir.join("todos.json");
+        let config = Config {
+            snapshot_path: Some(path.clone()),
+            ..Default::default()
+        };
+        let service = TodoService::with_store(config.store().unwrap());
+        service.seed(fixtures::builtin("default").unwrap());
+        snapshot::save(&path, &service.get_all(None, None, None)).unwrap();
+        assert_eq!(
+            config.store().unwrap().count(),
+            service.get_all(None, None, None).len()
+        );
+
+        let both = Config {
+            journal_dir: Some(dir.clone()),
+            ..config
+        };
+        assert!(both.store().is_err());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_encryption_key_seals_stored_text() {
+        let dir = env::temp_dir().join(format!("spicy-config-{}", uuid::Uuid::new_v4()));
+        let config = Config {
+            journal_dir: Some(dir.clone()),
+            encryption_key: Some("ab".repeat(32)),
+            ..Default::default()
+        };
+        let service = config.service().unwrap();
+        let seeded = service.seed(fixtures::builtin("default").unwrap());
+        drop(service);
+        let journal: String = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| std::fs::read_to_string(entry.unwrap().path()).unwrap_or_default())
+            .collect();
+        assert!(!journal.contains(&seeded[0].text));
+
+        let restored = config.service().unwrap();
+        assert_eq!(restored.get_by_id(seeded[0].id).unwrap().text, seeded[0].text);
+        let wrong = Config {
+            encryption_key: Some("cd".repeat(32)),
+            ..config
+        };
+        assert!(wrong.service().is_err());
+        let short = Config {
+            encryption_key: Some("abcd".to_string()),
+            ..Default::default()
+        };
+        assert!(short.service().is_err());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_service_uses_event_store() {
+        let dir = env::temp_dir().join(format!("spicy-config-{}", uuid::Uuid::new_v4()));
+        let config = Config {
+            event_store_path: Some(dir.join("events.jsonl")),
+            ..Default::default()
+        };
+        let service = config.service().unwrap();
+        let seeded = service.seed(fixtures::builtin("default").unwrap()).len();
+        drop(service);
+        let restored = config.service().unwrap();
+        assert_eq!(restored.get_all(None, None, None).len(), seeded);
+        assert_eq!(restored.sequence(), seeded as u64);
+
+        let both = Config {
+            journal_dir: Some(dir.clone()),
+            ..config
+        };
+        assert!(both.service().is_err());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
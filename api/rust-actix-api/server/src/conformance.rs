@@ -0,0 +1,866 @@
+use crate::auth::AuthMode;
+use crate::config::Config;
+use crate::deadlines::{DEADLINE_HEADER, TIMEOUT_HEADER};
+use crate::moderation::ModerationMode;
+use serde::Serialize;
+use serde_json::json;
+use spicy_todo_core::models::{Color, Icon, MAX_OCCURRENCES};
+use spicy_todo_core::rollover::RolloverMode;
+use std::collections::BTreeMap;
+
+/// Bumped when the shape of the report changes, not when features are added.
+pub const CONFORMANCE_VERSION: u32 = 1;
+
+/// Which optional features this build and configuration support, so the
+/// frontends can feature-detect instead of sniffing the implementation.
+#[derive(Debug, Serialize)]
+pub struct Conformance {
+    pub implementation: &'static str,
+    pub version: &'static str,
+    #[serde(rename = "conformanceVersion")]
+    pub conformance_version: u32,
+    pub features: BTreeMap<&'static str, Feature>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Feature {
+    pub supported: bool,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub endpoints: Vec<&'static str>,
+    /// Feature-specific parameters, e.g. header names or limits.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub details: Option<serde_json::Value>,
+}
+
+impl Feature {
+    fn supported(endpoints: &[&'static str]) -> Self {
+        Feature {
+            supported: true,
+            endpoints: endpoints.to_vec(),
+            details: None,
+        }
+    }
+
+    fn unsupported() -> Self {
+        Feature {
+            supported: false,
+            endpoints: Vec::new(),
+            details: None,
+        }
+    }
+
+    fn with_details(mut self, details: serde_json::Value) -> Self {
+        self.details = Some(details);
+        self
+    }
+}
+
+/// How this deployment is set up, in brief, so generic clients can
+/// configure themselves against it. The conformance report has the detail.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Capabilities {
+    pub implementation: &'static str,
+    pub version: &'static str,
+    pub api_versions: [&'static str; 2],
+    /// Methods the API answers to. `HEAD` works on the todo list and
+    /// detail endpoints and on this one.
+    pub methods: [&'static str; 7],
+    pub auth: AuthCapabilities,
+    pub realtime: RealtimeCapabilities,
+    pub backends: BackendCapabilities,
+    pub conformance: &'static str,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AuthCapabilities {
+    /// What reading and writing todos takes.
+    pub todos: &'static str,
+    /// How admin endpoints take their token; empty when they are disabled.
+    pub admin: Vec<&'static str>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RealtimeCapabilities {
+    pub websockets: bool,
+    /// Server-sent event streams.
+    pub sse: Vec<&'static str>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BackendCapabilities {
+    pub storage: &'static str,
+    pub backups: Option<&'static str>,
+    pub notifications: Vec<&'static str>,
+    pub suggestions: &'static str,
+}
+
+/// Where todos are kept, by the settings that choose it.
+fn storage_backend(config: &Config) -> &'static str {
+    if config.event_store_path.is_some() {
+        "events"
+    } else if config.journal_dir.is_some() {
+        "journal"
+    } else if config.snapshot_path.is_some() {
+        "snapshot"
+    } else {
+        "memory"
+    }
+}
+
+pub fn capabilities(config: &Config) -> Capabilities {
+    let notifications = [
+        ("sms", config.sms.is_some()),
+        ("webPush", config.web_push.is_some()),
+        ("matrix", config.matrix.is_some()),
+        ("telegram", config.telegram.is_some()),
+    ];
+    Capabilities {
+        implementation: "rust-actix",
+        version: env!("CARGO_PKG_VERSION"),
+        api_versions: crate::versioning::VERSIONS,
+        methods: ["GET", "HEAD", "POST", "PUT", "PATCH", "DELETE", "OPTIONS"],
+        auth: AuthCapabilities {
+            todos: config.auth.mode.scheme(),
+            admin: if config.admin_token.is_some() {
+                vec!["bearer", "x-admin-token"]
+            } else {
+                Vec::new()
+            },
+        },
+        realtime: RealtimeCapabilities {
+            websockets: false,
+            sse: vec!["/api/mcp/sse"],
+        },
+        backends: BackendCapabilities {
+            storage: storage_backend(config),
+            backups: (config.backup.is_some() && cfg!(feature = "backups")).then_some("s3"),
+            notifications: notifications
+                .iter()
+                .filter(|(_, enabled)| *enabled)
+                .map(|(name, _)| *name)
+                .collect(),
+            suggestions: if config.suggest_llm.is_some() {
+                "llm"
+            } else {
+                "heuristic"
+            },
+        },
+        conformance: "/api/conformance",
+    }
+}
+
+pub fn report(config: &Config) -> Conformance {
+    let admin_enabled = config.admin_token.is_some();
+    let profiling = cfg!(feature = "profiling") && config.profiling_enabled;
+    let sign_in_providers: Vec<&str> = config
+        .auth
+        .oidc
+        .iter()
+        .flat_map(|oidc| oidc.providers.keys().map(String::as_str))
+        .collect();
+
+    let features = BTreeMap::from([
+        (
+            "pagination",
+            Feature::supported(&["/api/todos", "/api/events/log"]).with_details(json!({
+                "params": ["limit", "offset"],
+                "defaultLimit": spicy_todo_core::models::DEFAULT_PAGE_LIMIT,
+                "maxLimit": spicy_todo_core::models::MAX_PAGE_LIMIT,
+                "listMeta": ["matching", "active", "completed", "overdue"],
+                "cursor": {
+                    "endpoints": ["/api/todos"],
+                    "param": "cursor",
+                    "next": "nextCursor",
+                    "defaultSort": "createdAt"
+                }
+            })),
+        ),
+        (
+            "capabilities",
+            Feature::supported(&["/api", "/api/capabilities"])
+                .with_details(json!({ "methods": ["GET", "HEAD", "OPTIONS"] })),
+        ),
+        (
+            "keyCase",
+            Feature::supported(&["/api"]).with_details(json!({
+                "header": crate::casing::CASE_HEADER,
+                "query": crate::casing::CASE_PARAM,
+                "cases": crate::casing::KeyCase::NAMES,
+                "default": crate::casing::KeyCase::NAMES[0]
+            })),
+        ),
+        (
+            "versioning",
+            Feature::supported(&["/api/v1", "/api/v2"]).with_details(json!({
+                "versions": crate::versioning::VERSIONS,
+                "unversioned": crate::versioning::VERSIONS[0],
+                "header": crate::versioning::VERSION_HEADER,
+                "v2": {
+                    "todoKeys": "snake_case",
+                    "reminder": "remind_at (RFC 3339, in the client's time zone)",
+                    "formats": ["application/json"]
+                }
+            })),
+        ),
+        (
+            "webhooks",
+            Feature::supported(&["/api/webhooks", "/api/webhooks/{id}/replay"]),
+        ),
+        (
+            "actions",
+            Feature::supported(&["/api/actions"]).with_details(json!({
+                "query": ["resource", "id"],
+                "resources": ["todos", "todo"],
+                "schemas": "JSON Schema"
+            })),
+        ),
+        (
+            "feed",
+            Feature::supported(&["/api/todos/feed.atom"]).with_details(json!({
+                "format": "atom",
+                "events": ["created", "completed"],
+                "tokenRequired": config.feed_token.is_some(),
+                "defaultLimit": crate::feed::DEFAULT_ENTRIES,
+                "maxLimit": crate::feed::MAX_ENTRIES
+            })),
+        ),
+        (
+            "mcp",
+            Feature::supported(&["/api/mcp/sse", "/api/mcp/messages"]).with_details(json!({
+                "transports": ["stdio", "sse"],
+                "protocolVersions": crate::mcp::PROTOCOL_VERSIONS,
+                "tools": crate::mcp::tools().iter().map(|tool| tool.name).collect::<Vec<_>>()
+            })),
+        ),
+        (
+            "suggestions",
+            Feature::supported(&["/api/todos/{id}/suggest"]).with_details(json!({
+                "fields": ["priority", "tags", "dueDate"],
+                "source": if config.suggest_llm.is_some() { "llm" } else { "heuristic" },
+                "applied": false
+            })),
+        ),
+        (
+            "moderation",
+            Feature {
+                supported: config.moderation.mode != ModerationMode::Off,
+                endpoints: Vec::new(),
+                details: Some(json!({
+                    "mode": config.moderation.mode.as_str(),
+                    "appliesTo": [
+                        "create", "update", "quickAdd", "bulkEdit", "caldav", "homeassistant", "mcp"
+                    ],
+                    "external": config.moderation.url.is_some()
+                })),
+            },
+        ),
+        (
+            "plan",
+            Feature::supported(&["/api/plan/today"]).with_details(json!({
+                "query": ["capacity", "lookahead"],
+                "dailyCapacityMinutes": config.daily_capacity_minutes,
+                "defaultEstimateMinutes": spicy_todo_core::plan::DEFAULT_ESTIMATE_MINUTES,
+                "warnings": ["overCapacity", "tooLong", "unestimated"]
+            })),
+        ),
+        (
+            "plugins",
+            Feature::supported(&["/api/plugins"]).with_details(json!({
+                "query": ["kind", "loaded"],
+                "kinds": crate::plugins::Kind::ALL
+            })),
+        ),
+        (
+            "caldav",
+            Feature::supported(&[
+                "/.well-known/caldav",
+                "/dav/",
+                "/dav/todos/",
+                "/dav/todos/{id}.ics",
+            ])
+            .with_details(json!({
+                "collection": crate::caldav::COLLECTION,
+                "components": ["VTODO"],
+                "reports": ["calendar-query", "calendar-multiget"],
+                "properties": ["SUMMARY", "PRIORITY", "DUE", "STATUS", "RRULE"]
+            })),
+        ),
+        (
+            "scripting",
+            Feature::supported(&[
+                "/api/scripts",
+                "/api/scripts/{id}",
+                "/api/scripts/{id}/executions",
+            ])
+            .with_details(json!({
+                "engine": "rhai",
+                "hooks": crate::scripts::Hook::ALL,
+                "functions": ["update", "create", "print", "debug"],
+                "maxOperations": crate::scripts::MAX_OPERATIONS,
+                "timeLimitMs": crate::scripts::TIME_LIMIT.as_millis() as u64
+            })),
+        ),
+        (
+            "bulkEdit",
+            Feature::supported(&["/api/todos/bulk-edit/preview", "/api/todos/bulk-edit/apply"])
+                .with_details(json!({
+                    "filter": ["filter", "search", "priority", "ids"],
+                    "tokenTtlSeconds": crate::bulk_edits::PREVIEW_TTL.as_secs(),
+                    "staleCheck": "updatedAt"
+                })),
+        ),
+        (
+            "chatNotifiers",
+            Feature::supported(&["/api/notifiers"]).with_details(json!({
+                "kinds": ["slack", "discord"],
+                "alerts": ["dailySummary", "overdue", "highPriority", "weeklySummary"],
+                "placeholders": {
+                    "created": crate::notifiers::TODO_PLACEHOLDERS,
+                    "overdue": crate::notifiers::TODO_PLACEHOLDERS,
+                    "summary": crate::notifiers::SUMMARY_PLACEHOLDERS,
+                    "weekly": crate::notifiers::WEEKLY_PLACEHOLDERS
+                },
+                "weeklySummary": {
+                    "day": "monday",
+                    "unsubscribe": "notifications.weeklySummary"
+                }
+            })),
+        ),
+        (
+            "demoMode",
+            match &config.demo {
+                Some(settings) => Feature::supported(&["/"]).with_details(json!({
+                    "resetEveryMinutes": settings.reset_interval.as_secs() / 60,
+                    "maxTodos": settings.max_todos,
+                    "writesPerMinute": settings.writes_per_minute,
+                    "disabled": [
+                        "webhooks",
+                        "notifiers",
+                        "notifications",
+                        "webPush",
+                        "reminders",
+                        "bulkImports"
+                    ]
+                })),
+                None => Feature::unsupported(),
+            },
+        ),
+        ("graphql", Feature::unsupported()),
+        (
+            // Every storage backend is local to one process, and events are
+            // broadcast in-process only, so replicas would diverge. Needs a
+            // shared backend before events can go over one. Scheduled jobs
+            // can already be shared through a lease file.
+            "multiInstance",
+            Feature::unsupported().with_details(json!({
+                "storage": storage_backend(config),
+                "eventBroadcast": "in-process",
+                "schedulerLease": config.scheduler_lease_path.as_ref().map(|_| "file")
+            })),
+        ),
+        (
+            "sync",
+            Feature::supported(&["/api/sync"])
+                .with_details(json!({ "conflictResolution": "last-writer-wins" })),
+        ),
+        (
+            "changesFeed",
+            Feature::supported(&["/api/todos/changes"])
+                .with_details(json!({ "cursor": "sequence", "tombstones": true })),
+        ),
+        (
+            "auth",
+            Feature {
+                supported: admin_enabled || config.auth.mode != AuthMode::Off,
+                endpoints: match config.auth.mode {
+                    AuthMode::Session if !sign_in_providers.is_empty() => vec![
+                        "/api/auth/login",
+                        "/api/auth/login/{provider}",
+                        "/api/auth/callback",
+                        "/api/auth/logout",
+                        "/api/auth/session",
+                    ],
+                    AuthMode::Session => {
+                        vec!["/api/auth/login", "/api/auth/logout", "/api/auth/session"]
+                    }
+                    _ => Vec::new(),
+                },
+                details: Some(json!({
+                    "todos": config.auth.mode.scheme(),
+                    "csrfHeader": (config.auth.mode == AuthMode::Session)
+                        .then_some(crate::auth::CSRF_HEADER),
+                    "signInProviders": sign_in_providers,
+                    "admin": if admin_enabled {
+                        json!(["bearer", "x-admin-token"])
+                    } else {
+                        json!([])
+                    }
+                })),
+            },
+        ),
+        (
+            "errorCodes",
+            Feature::supported(&[]).with_details(json!({
+                "field": "code",
+                "codes": spicy_todo_core::models::ErrorCode::ALL
+            })),
+        ),
+        (
+            "i18n",
+            Feature::supported(&[]).with_details(json!({
+                "negotiation": "Accept-Language",
+                "locales": crate::i18n::LOCALES
+                    .iter()
+                    .map(|(locale, _)| *locale)
+                    .collect::<Vec<_>>(),
+                "localizedFields": ["error", "message"]
+            })),
+        ),
+        (
+            "contentNegotiation",
+            Feature::supported(&["/api/todos", "/api/todos/{id}"]).with_details(json!({
+                "types": ["application/json", "application/msgpack"]
+            })),
+        ),
+        (
+            "conditionalRequests",
+            Feature::supported(&["/api/todos"])
+                .with_details(json!({ "headers": ["If-None-Match", "If-Modified-Since"] })),
+        ),
+        (
+            "requestDeadlines",
+            Feature::supported(&[]).with_details(json!({
+                "headers": [TIMEOUT_HEADER, DEADLINE_HEADER]
+            })),
+        ),
+        (
+            "idSuggestions",
+            Feature {
+                supported: config.suggest_missing_ids,
+                endpoints: Vec::new(),
+                details: None,
+            },
+        ),
+        (
+            "generate",
+            if admin_enabled {
+                Feature::supported(&["/api/admin/generate"]).with_details(json!({
+                    "maxCount": spicy_todo_core::fixtures::MAX_GENERATED
+                }))
+            } else {
+                Feature::unsupported()
+            },
+        ),
+        (
+            "import",
+            if admin_enabled {
+                Feature::supported(&["/api/import/spicy"])
+            } else {
+                Feature::unsupported()
+            },
+        ),
+        (
+            "sorting",
+            Feature::supported(&["/api/todos"]).with_details(json!({
+                "fields": ["createdAt", "updatedAt", "dueDate", "priority", "text"],
+                "orders": ["asc", "desc"]
+            })),
+        ),
+        (
+            "recurrence",
+            Feature::supported(&["/api/todos", "/api/todos/{id}/missed"]).with_details(json!({
+                "values": ["daily", "weekly", "monthly"],
+                "endConditions": ["afterOccurrences", "until"],
+                "maxOccurrences": MAX_OCCURRENCES,
+                "rolloverMode": match config.rollover_mode {
+                    RolloverMode::CarryOver => "carry-over",
+                    RolloverMode::MarkMissed => "mark-missed",
+                }
+            })),
+        ),
+        (
+            "overdueRollover",
+            match &config.overdue_rollover {
+                Some(settings) => Feature::supported(&["/api/todos"]).with_details(json!({
+                    "mode": settings.mode.as_str(),
+                    "timezone": settings.timezone.name(),
+                    "fields": ["rolloverCount", "rolledOverTo"],
+                    "statsField": "rolledOverCount",
+                })),
+                None => Feature::unsupported(),
+            },
+        ),
+        (
+            "cascadeDelete",
+            Feature::supported(&["/api/todos/{id}"]).with_details(json!({
+                "children": ["missedOccurrences", "history"],
+                "missedOccurrences": config.delete_cascade.missed_occurrences,
+                "history": if config.delete_cascade.history { "redact" } else { "keep" }
+            })),
+        ),
+        (
+            "todoBundles",
+            Feature::supported(&["/api/todos/{id}/export", "/api/todos/import"]).with_details(
+                json!({
+                    "format": spicy_todo_core::bundle::BUNDLE_FORMAT,
+                    "includes": ["history", "missedOccurrences"]
+                }),
+            ),
+        ),
+        (
+            "transfer",
+            if config.transfer_peers.is_empty() {
+                Feature::unsupported()
+            } else {
+                Feature::supported(&["/api/todos/{id}/transfer"]).with_details(
+                    json!({ "peers": config.transfer_peers.keys().collect::<Vec<_>>() }),
+                )
+            },
+        ),
+        (
+            "quickAdd",
+            Feature::supported(&["/api/todos/quick"]).with_details(json!({
+                "priorities": ["!low", "!medium", "!high"],
+                "dates": ["YYYY-MM-DD", "today", "tomorrow", "<weekday>", "next <weekday>",
+                    "next week", "next month", "in N days|weeks|months"],
+                "tags": "kept in text"
+            })),
+        ),
+        (
+            "digest",
+            Feature::supported(&["/api/todos/digest"]).with_details(json!({
+                "formats": ["json", "text"],
+                "locales": spicy_todo_core::locale::Locale::tags(),
+                "textOptions": ["locale", "width", "priority"]
+            })),
+        ),
+        (
+            "pushReminders",
+            Feature::supported(&["/api/notifications/push", "/api/notifications/push/test"])
+                .with_details(json!({
+                    "targets": ["ntfy", "gotify"],
+                    "clientHeader": crate::preferences::CLIENT_HEADER
+                })),
+        ),
+        (
+            "smsReminders",
+            match &config.sms {
+                Some(settings) => Feature::supported(&[
+                    "/api/notifications/sms",
+                    "/api/notifications/sms/verify",
+                ])
+                .with_details(json!({
+                    "reminders": "high priority",
+                    "clientHeader": crate::preferences::CLIENT_HEADER,
+                    "maxPerNumberPerHour": settings.limits.per_number_per_hour,
+                    "maxPerDay": settings.limits.per_day
+                })),
+                None => Feature::unsupported(),
+            },
+        ),
+        (
+            "webPush",
+            match &config.web_push {
+                Some(_) => Feature::supported(&[
+                    "/api/push/vapid-public-key",
+                    "/api/push/subscribe",
+                ])
+                .with_details(json!({
+                    "encoding": "aes128gcm",
+                    "notifications": ["reminders", "overdue"]
+                })),
+                None => Feature::unsupported(),
+            },
+        ),
+        (
+            "matrix",
+            match &config.matrix {
+                Some(settings) => Feature::supported(&[]).with_details(json!({
+                    "commands": ["!add", "!list", "!done", "!help"],
+                    "reminders": true,
+                    "dailyDigest": settings.daily_digest
+                })),
+                None => Feature::unsupported(),
+            },
+        ),
+        (
+            "telegram",
+            match &config.telegram {
+                Some(_) => Feature::supported(&[]).with_details(json!({
+                    "commands": ["/add", "/today", "/done", "/help"]
+                })),
+                None => Feature::unsupported(),
+            },
+        ),
+        (
+            "emailIngest",
+            match (&config.imap, &config.email_webhook_signing_key) {
+                (None, None) => Feature::unsupported(),
+                (imap, signing_key) => {
+                    let endpoints: &[&str] = match signing_key {
+                        Some(_) => &["/api/ingest/email"],
+                        None => &[],
+                    };
+                    Feature::supported(endpoints).with_details(json!({
+                        "imap": imap.is_some(),
+                        "webhook": signing_key.as_ref().map(|_| "mailgun"),
+                        "priorityFrom": ["subject", "\\Flagged", "X-Priority", "Importance"],
+                        "dueDateFrom": "subject"
+                    }))
+                }
+            },
+        ),
+        (
+            "locations",
+            Feature::supported(&["/api/todos", "/api/todos/nearby"]).with_details(json!({
+                "field": "location",
+                "units": "meters",
+                "defaultRadius": 1000,
+                "maxRadius": 100000
+            })),
+        ),
+        (
+            "geofenceReminders",
+            Feature::supported(&["/api/todos/{id}/reminders/geo-trigger"]).with_details(json!({
+                "radiusMeters": config.geofence_radius_meters,
+                "cooldownSeconds": config.geofence_cooldown.as_secs(),
+                "channels": ["push", "sms", "matrix"]
+            })),
+        ),
+        (
+            "homeAssistant",
+            Feature::supported(&[
+                "/api/homeassistant/sensor",
+                "/api/homeassistant/services/{service}",
+            ])
+            .with_details(json!({
+                "sensor": ["active", "dueToday", "overdue"],
+                "services": ["add_todo", "complete_todo"]
+            })),
+        ),
+        (
+            "dashboard",
+            Feature::supported(&["/api/dashboard"]).with_details(json!({
+                "sections": ["dueToday", "overdue", "topPriority", "stats", "recentActivity"],
+                "defaultLimit": spicy_todo_core::dashboard::DEFAULT_SECTION_LIMIT,
+                "maxLimit": spicy_todo_core::dashboard::MAX_SECTION_LIMIT
+            })),
+        ),
+        (
+            "statsReport",
+            Feature::supported(&["/api/todos/stats/report"]).with_details(json!({
+                "formats": ["csv", "prometheus"],
+                "periods": ["week", "month"],
+            })),
+        ),
+        (
+            "ndjsonStream",
+            Feature::supported(&["/api/todos/stream"]).with_details(json!({
+                "contentType": crate::ndjson::CONTENT_TYPE,
+                "order": "id",
+                "pageSize": crate::ndjson::PAGE_SIZE
+            })),
+        ),
+        (
+            "effortEstimates",
+            Feature::supported(&["/api/todos", "/api/todos/stats/summary"]).with_details(json!({
+                "field": "estimateMinutes",
+                "maxMinutes": spicy_todo_core::models::MAX_ESTIMATE_MINUTES,
+                "dailyCapacityMinutes": config.daily_capacity_minutes
+            })),
+        ),
+        (
+            "appearance",
+            Feature::supported(&["/api/todos", "/api/views"]).with_details(json!({
+                "fields": ["color", "icon"],
+                "filterParams": ["color", "icon"],
+                "colors": Color::ALL.iter().map(Color::as_str).collect::<Vec<_>>(),
+                "icons": Icon::ALL
+                    .iter()
+                    .map(|icon| (icon.as_str(), icon.emoji()))
+                    .collect::<BTreeMap<_, _>>()
+            })),
+        ),
+        (
+            "pinning",
+            Feature::supported(&["/api/todos/{id}/pin"])
+                .with_details(json!({ "field": "pinned", "listedFirst": true })),
+        ),
+        (
+            "snooze",
+            Feature::supported(&["/api/todos/{id}/snooze"]).with_details(json!({
+                "presets": ["tomorrow", "next-week", "+Nd"],
+                "maxDays": spicy_todo_core::snooze::MAX_SNOOZE_DAYS,
+                "event": "todo.snoozed",
+            })),
+        ),
+        (
+            "myDay",
+            Feature::supported(&["/api/myday/{date}", "/api/myday/{date}/todos/{id}"])
+                .with_details(json!({ "rollover": "previousDay" })),
+        ),
+        (
+            "listPreferences",
+            Feature::supported(&["/api/todos/preferences"])
+                .with_details(json!({ "clientHeader": crate::preferences::CLIENT_HEADER })),
+        ),
+        (
+            "completeMatching",
+            Feature::supported(&["/api/todos/complete"]).with_details(json!({
+                "params": ["filter", "search", "priority", "q", "tag"]
+            })),
+        ),
+        (
+            "policies",
+            Feature::supported(&["/api/policies", "/api/policies/{id}"]).with_details(json!({
+                "actions": ["deleteCompleted", "escalatePriority"],
+                "escalationEvent": "todo.escalated",
+                "schedule": "daily",
+                "maxAfterDays": spicy_todo_core::policy::MAX_AFTER_DAYS
+            })),
+        ),
+        (
+            "queryLanguage",
+            Feature::supported(&["/api/todos"]).with_details(json!({
+                "param": "q",
+                "fields": ["text", "priority", "completed", "overdue", "tag", "due"],
+                "operators": [":", "~", "<", "<=", ">", ">="],
+                "keywords": ["AND", "OR", "NOT"],
+                "maxLength": spicy_todo_core::query::MAX_QUERY_LEN,
+                "maxNesting": spicy_todo_core::query::MAX_NESTING
+            })),
+        ),
+        (
+            "savedViews",
+            Feature::supported(&["/api/views", "/api/views/{id}", "/api/views/{id}/todos"])
+                .with_details(json!({
+                    "fields": ["filter", "search", "tags", "priority", "sort", "order"],
+                    "filters": ["all", "active", "completed", "overdue"],
+                    "tags": "hashtags in the text"
+                })),
+        ),
+        (
+            "accountData",
+            Feature::supported(&["/api/account/export", "/api/account"]).with_details(json!({
+                "identifiedBy": ["session", crate::preferences::CLIENT_HEADER],
+                "export": ["todos", "history", "preferences", "subscriptions", "linkedAccounts"],
+                "delete": {
+                    "ownTodos": "delete",
+                    "history": "anonymize",
+                    "backups": "excludeOnRestore"
+                }
+            })),
+        ),
+        (
+            "userPreferences",
+            Feature::supported(&["/api/preferences"]).with_details(json!({
+                "clientHeader": crate::preferences::CLIENT_HEADER,
+                "fields": [
+                    "defaultPriority",
+                    "reminderLeadMinutes",
+                    "timezone",
+                    "weekStart",
+                    "notifications"
+                ],
+                "weekStarts": ["monday", "sunday", "saturday"],
+                "notificationChannels": ["push", "sms"]
+            })),
+        ),
+        (
+            "persistence",
+            Feature::supported(&[]).with_details(json!({
+                "mode": storage_backend(config),
+                "encryptedAtRest": config.encryption_key.is_some()
+            })),
+        ),
+        (
+            "backups",
+            if config.backup.is_some() && cfg!(feature = "backups") && admin_enabled {
+                Feature::supported(&["/api/admin/backups", "/api/admin/restore"])
+                    .with_details(json!({ "encryption": "aes-256-gcm" }))
+            } else {
+                Feature::unsupported()
+            },
+        ),
+        (
+            "audit",
+            if admin_enabled {
+                let retention = config.audit_retention.as_ref();
+                Feature::supported(&["/api/audit"]).with_details(json!({
+                    "retentionDays": retention.map(|settings| settings.max_age.as_secs() / 86400),
+                    "archive": retention.is_some_and(|settings| settings.archive_dir.is_some())
+                }))
+            } else {
+                Feature::unsupported()
+            },
+        ),
+        ("activity", Feature::supported(&["/api/activity"])),
+        (
+            "metrics",
+            Feature::supported(&["/metrics", "/api/admin/grafana-dashboard"]),
+        ),
+        (
+            "profiling",
+            if profiling && admin_enabled {
+                Feature::supported(&["/debug/pprof/profile"])
+            } else {
+                Feature::unsupported()
+            },
+        ),
+    ]);
+
+    Conformance {
+        implementation: "rust-actix",
+        version: env!("CARGO_PKG_VERSION"),
+        conformance_version: CONFORMANCE_VERSION,
+        features,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report_reflects_config() {
+        let defaults = report(&Config::default());
+        assert!(!defaults.features["graphql"].supported);
+        assert!(!defaults.features["multiInstance"].supported);
+        assert!(defaults.features["sync"].supported);
+        assert!(!defaults.features["auth"].supported);
+        assert!(!defaults.features["import"].supported);
+        assert!(!defaults.features["profiling"].supported);
+
+        let config = Config {
+            admin_token: Some("secret".to_string()),
+            profiling_enabled: true,
+            ..Config::default()
+        };
+        let admin = report(&config);
+        assert!(admin.features["auth"].supported);
+        assert!(admin.features["import"].supported);
+        assert_eq!(
+            admin.features["profiling"].supported,
+            cfg!(feature = "profiling")
+        );
+    }
+
+    #[test]
+    fn test_capabilities_reflect_config() {
+        let defaults = capabilities(&Config::default());
+        assert!(defaults.auth.admin.is_empty());
+        assert_eq!(defaults.backends.storage, "memory");
+        assert!(defaults.backends.notifications.is_empty());
+        assert!(!defaults.realtime.websockets);
+
+        let config = Config {
+            admin_token: Some("secret".to_string()),
+            snapshot_path: Some("todos.json".into()),
+            ..Config::default()
+        };
+        let configured = capabilities(&config);
+        assert_eq!(configured.auth.admin, ["bearer", "x-admin-token"]);
+        assert_eq!(configured.backends.storage, "snapshot");
+    }
+}
@@ -0,0 +1,85 @@
+use crate::preferences;
+use actix_web::body::MessageBody;
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::header::{HeaderName, HeaderValue};
+use actix_web::middleware::Next;
+use actix_web::HttpMessage;
+use spicy_todo_core::RequestContext;
+use uuid::Uuid;
+
+/// Request ID: taken from the client when it sends a usable one, so a
+/// trace can start upstream, and echoed on every response.
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+/// Workspace the client is working in. Recorded on events only; todos are
+/// not yet split by workspace.
+pub const WORKSPACE_HEADER: &str = "x-workspace-id";
+const MAX_ID_LEN: usize = 128;
+
+/// A header value fit to record: 1 to `MAX_ID_LEN` visible ASCII
+/// characters. Anything else is ignored rather than rejected.
+fn id_header(req: &ServiceRequest, name: &str) -> Option<String> {
+    let value = req.headers().get(name)?.to_str().ok()?.trim();
+    let usable = !value.is_empty()
+        && value.len() <= MAX_ID_LEN
+        && value.bytes().all(|b| b.is_ascii_graphic());
+    usable.then(|| value.to_string())
+}
+
+/// The context of a request: its ID, the client (`x-client-id`) as the
+/// user, and the workspace.
+pub fn from_request(req: &ServiceRequest) -> RequestContext {
+    RequestContext {
+        request_id: Some(
+            id_header(req, REQUEST_ID_HEADER).unwrap_or_else(|| Uuid::new_v4().to_string()),
+        ),
+        user: preferences::client_id(req.request()).ok().flatten(),
+        workspace: id_header(req, WORKSPACE_HEADER),
+    }
+}
+
+/// Puts the request's context in scope for everything that handles it, so
+/// the events its mutations append carry it, and sets the request ID on the
+/// response. Handlers read it back with `RequestContext::current` when they
+/// hand work to another thread.
+pub async fn with_request_context(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, actix_web::Error> {
+    let context = from_request(&req);
+    let request_id = context.request_id.clone().unwrap_or_default();
+    req.extensions_mut().insert(context.clone());
+
+    let mut response = context.scope(next.call(req)).await?;
+    if let Ok(value) = HeaderValue::from_str(&request_id) {
+        response
+            .headers_mut()
+            .insert(HeaderName::from_static(REQUEST_ID_HEADER), value);
+    }
+    Ok(response)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::test::TestRequest;
+
+    #[test]
+    fn test_reads_context_from_headers() {
+        let req = TestRequest::default()
+            .insert_header((REQUEST_ID_HEADER, "abc-123"))
+            .insert_header((preferences::CLIENT_HEADER, "phone"))
+            .insert_header((WORKSPACE_HEADER, "home"))
+            .to_srv_request();
+        let context = from_request(&req);
+        assert_eq!(context.request_id.as_deref(), Some("abc-123"));
+        assert_eq!(context.user.as_deref(), Some("phone"));
+        assert_eq!(context.workspace.as_deref(), Some("home"));
+
+        let req = TestRequest::default()
+            .insert_header((REQUEST_ID_HEADER, "has spaces"))
+            .to_srv_request();
+        let context = from_request(&req);
+        assert!(Uuid::parse_str(context.request_id.as_deref().unwrap()).is_ok());
+        assert_eq!(context.user, None);
+    }
+}
@@ -0,0 +1,358 @@
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::future::Future;
+use std::path::Path;
+use std::time::Duration;
+
+/// Where `--contract-check` reads the spec unless `CONTRACT_SPEC` says
+/// otherwise: the one shared by every implementation, next to this one.
+pub const DEFAULT_SPEC_PATH: &str = "../contract/todos.json";
+/// Checked when `--contract-check` is given no base URL.
+pub const DEFAULT_BASE_URL: &str = "http://localhost:8000";
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+/// Larger response bodies fail the step rather than being read.
+const MAX_BODY_BYTES: usize = 4 * 1024 * 1024;
+
+/// The shared contract: requests every implementation of the API must
+/// answer alike. See the spec file's `description` for how it matches.
+#[derive(Debug, Deserialize)]
+pub struct Spec {
+    pub cases: Vec<Case>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Case {
+    pub name: String,
+    pub steps: Vec<Step>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Step {
+    pub request: Request,
+    pub expect: Expect,
+    /// Variable name to JSON pointer into the response body.
+    #[serde(default)]
+    pub capture: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Request {
+    pub method: String,
+    pub path: String,
+    #[serde(default)]
+    pub body: Option<Value>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Expect {
+    pub status: u16,
+    #[serde(default)]
+    pub body: Option<Value>,
+}
+
+/// What came back for a request: its status and its body, `null` when
+/// empty and a string when not JSON.
+#[derive(Debug)]
+pub struct Response {
+    pub status: u16,
+    pub body: Value,
+}
+
+impl Response {
+    pub fn new(status: u16, bytes: &[u8]) -> Self {
+        let body = if bytes.is_empty() {
+            Value::Null
+        } else {
+            serde_json::from_slice(bytes)
+                .unwrap_or_else(|_| Value::String(String::from_utf8_lossy(bytes).into_owned()))
+        };
+        Response { status, body }
+    }
+}
+
+/// How one case went: `failure` says which step failed first and why.
+#[derive(Debug)]
+pub struct CaseResult {
+    pub name: String,
+    pub failure: Option<String>,
+}
+
+impl Spec {
+    pub fn load(path: &Path) -> Result<Spec, String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("Cannot read contract spec {}: {}", path.display(), e))?;
+        serde_json::from_str(&text)
+            .map_err(|e| format!("Invalid contract spec {}: {}", path.display(), e))
+    }
+}
+
+/// Replaces every `{{name}}` in `text`.
+fn substitute(text: &str, vars: &HashMap<String, String>) -> String {
+    vars.iter().fold(text.to_string(), |text, (name, value)| {
+        text.replace(&format!("{{{{{}}}}}", name), value)
+    })
+}
+
+fn substitute_value(value: &Value, vars: &HashMap<String, String>) -> Value {
+    match value {
+        Value::String(text) => Value::String(substitute(text, vars)),
+        Value::Array(items) => Value::Array(
+            items
+                .iter()
+                .map(|item| substitute_value(item, vars))
+                .collect(),
+        ),
+        Value::Object(object) => Value::Object(
+            object
+                .iter()
+                .map(|(key, value)| (key.clone(), substitute_value(value, vars)))
+                .collect(),
+        ),
+        value => value.clone(),
+    }
+}
+
+/// Whether `actual` matches `expected`: objects by the keys `expected`
+/// lists, arrays item by item, `"$type"` strings by type, anything else
+/// exactly. The error names the JSON pointer that differed.
+pub fn check(expected: &Value, actual: &Value, at: &str) -> Result<(), String> {
+    let pointer = if at.is_empty() { "/" } else { at };
+    let mismatch = || format!("{}: expected {}, got {}", pointer, expected, actual);
+    match expected {
+        Value::String(matcher) if matcher.starts_with('$') => {
+            let matches = match matcher.as_str() {
+                "$string" => actual.is_string(),
+                "$number" => actual.is_number(),
+                "$boolean" => actual.is_boolean(),
+                "$array" => actual.is_array(),
+                "$object" => actual.is_object(),
+                _ => expected == actual,
+            };
+            matches.then_some(()).ok_or_else(mismatch)
+        }
+        Value::Object(fields) => {
+            let actual_fields = actual.as_object().ok_or_else(mismatch)?;
+            fields.iter().try_for_each(|(key, expected)| {
+                let at = format!("{}/{}", at, key);
+                match actual_fields.get(key) {
+                    Some(actual) => check(expected, actual, &at),
+                    None => Err(format!("{}: missing", at)),
+                }
+            })
+        }
+        Value::Array(items) => {
+            let actual_items = actual.as_array().ok_or_else(mismatch)?;
+            if items.len() != actual_items.len() {
+                return Err(format!(
+                    "{}: expected {} items, got {}",
+                    pointer,
+                    items.len(),
+                    actual_items.len()
+                ));
+            }
+            items
+                .iter()
+                .zip(actual_items)
+                .enumerate()
+                .try_for_each(|(i, (expected, actual))| {
+                    check(expected, actual, &format!("{}/{}", at, i))
+                })
+        }
+        _ => (expected == actual).then_some(()).ok_or_else(mismatch),
+    }
+}
+
+/// Runs every case in order, sending its requests through `send`. A case
+/// stops at its first failing step, since later steps may use what that
+/// one would have captured. `run_id` is what `{{run}}` stands for.
+pub async fn run<F, Fut>(spec: &Spec, run_id: &str, mut send: F) -> Vec<CaseResult>
+where
+    F: FnMut(Request) -> Fut,
+    Fut: Future<Output = Result<Response, String>>,
+{
+    let mut vars = HashMap::from([("run".to_string(), run_id.to_string())]);
+    let mut results = Vec::with_capacity(spec.cases.len());
+    for case in &spec.cases {
+        let mut failure = None;
+        for (i, step) in case.steps.iter().enumerate() {
+            let request = Request {
+                method: step.request.method.clone(),
+                path: substitute(&step.request.path, &vars),
+                body: step
+                    .request
+                    .body
+                    .as_ref()
+                    .map(|b| substitute_value(b, &vars)),
+            };
+            let label = format!("step {} ({} {})", i + 1, request.method, request.path);
+            let outcome = send(request).await.and_then(|response| {
+                if response.status != step.expect.status {
+                    return Err(format!(
+                        "expected status {}, got {}: {}",
+                        step.expect.status, response.status, response.body
+                    ));
+                }
+                if let Some(expected) = &step.expect.body {
+                    check(&substitute_value(expected, &vars), &response.body, "")?;
+                }
+                step.capture.iter().try_for_each(|(name, pointer)| {
+                    let value = response
+                        .body
+                        .pointer(pointer)
+                        .ok_or_else(|| format!("nothing at {} to capture as {}", pointer, name))?;
+                    let value = match value {
+                        Value::String(text) => text.clone(),
+                        value => value.to_string(),
+                    };
+                    vars.insert(name.clone(), value);
+                    Ok(())
+                })
+            });
+            if let Err(e) = outcome {
+                failure = Some(format!("{}: {}", label, e));
+                break;
+            }
+        }
+        results.push(CaseResult {
+            name: case.name.clone(),
+            failure,
+        });
+    }
+    results
+}
+
+/// `spicy-todo-rust-api --contract-check [base-url]`: runs the spec in
+/// `CONTRACT_SPEC` against a running server, of any implementation, and
+/// fails unless every case passes. The cases create and delete todos and
+/// clear completed ones, so point it at a scratch instance.
+pub async fn check_remote(base_url: &str) -> std::io::Result<()> {
+    let spec_path = std::env::var("CONTRACT_SPEC").unwrap_or_else(|_| DEFAULT_SPEC_PATH.into());
+    let spec = Spec::load(Path::new(&spec_path))
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))?;
+    let base_url = base_url.trim_end_matches('/');
+    let client = awc::Client::builder().timeout(REQUEST_TIMEOUT).finish();
+    let run_id = uuid::Uuid::new_v4().simple().to_string();
+
+    let results = run(&spec, &run_id, |request| {
+        let client = &client;
+        async move {
+            let method = awc::http::Method::from_bytes(request.method.as_bytes())
+                .map_err(|e| format!("Invalid method {}: {}", request.method, e))?;
+            let sent = client.request(method, format!("{}{}", base_url, request.path));
+            let mut response = match &request.body {
+                Some(body) => sent.send_json(body).await,
+                None => sent.send().await,
+            }
+            .map_err(|e| format!("Request failed: {}", e))?;
+            let bytes = response
+                .body()
+                .limit(MAX_BODY_BYTES)
+                .await
+                .map_err(|e| format!("Cannot read response: {}", e))?;
+            Ok(Response::new(response.status().as_u16(), &bytes))
+        }
+    })
+    .await;
+
+    let mut failed = 0;
+    for result in &results {
+        match &result.failure {
+            None => println!("✅ {}", result.name),
+            Some(failure) => {
+                failed += 1;
+                println!("❌ {}: {}", result.name, failure);
+            }
+        }
+    }
+    println!(
+        "{} of {} contract cases passed against {}",
+        results.len() - failed,
+        results.len(),
+        base_url
+    );
+    if failed > 0 {
+        return Err(std::io::Error::other(format!(
+            "{} contract cases failed",
+            failed
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use actix_web::{web, App};
+    use serde_json::json;
+    use spicy_todo_core::service::TodoService;
+
+    #[test]
+    fn test_check_matches_subsets_and_types() {
+        let actual = json!({ "id": "1", "tags": [1, 2], "done": false, "extra": null });
+        assert!(check(
+            &json!({ "id": "$string", "tags": [1, "$number"] }),
+            &actual,
+            ""
+        )
+        .is_ok());
+        assert_eq!(
+            check(&json!({ "done": true }), &actual, ""),
+            Err("/done: expected true, got false".to_string())
+        );
+        assert_eq!(
+            check(&json!({ "tags": [1] }), &actual, ""),
+            Err("/tags: expected 1 items, got 2".to_string())
+        );
+        assert_eq!(
+            check(&json!({ "missing": 1 }), &actual, ""),
+            Err("/missing: missing".to_string())
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_server_satisfies_shared_spec() {
+        let spec_path = concat!(env!("CARGO_MANIFEST_DIR"), "/../../contract/todos.json");
+        let spec = Spec::load(Path::new(spec_path)).unwrap();
+        let service = web::Data::new(TodoService::new_empty());
+        let app = actix_web::test::init_service(
+            App::new()
+                .app_data(service.clone())
+                .app_data(web::Data::new(Config::default()))
+                .configure(crate::routes::configure_routes),
+        )
+        .await;
+
+        let results = run(&spec, "0a1b2c3d4e5f40718293a4b5c6d7e8f9", |request| {
+            let app = &app;
+            async move {
+                let method = actix_web::http::Method::from_bytes(request.method.as_bytes())
+                    .map_err(|e| e.to_string())?;
+                let mut req = actix_web::test::TestRequest::default()
+                    .method(method)
+                    .uri(&request.path);
+                if let Some(body) = &request.body {
+                    req = req.set_json(body);
+                }
+                let resp = actix_web::test::call_service(app, req.to_request()).await;
+                let status = resp.status().as_u16();
+                Ok(Response::new(
+                    status,
+                    &actix_web::test::read_body(resp).await,
+                ))
+            }
+        })
+        .await;
+
+        assert_eq!(results.len(), spec.cases.len());
+        for result in results {
+            assert!(
+                result.failure.is_none(),
+                "{}: {:?}",
+                result.name,
+                result.failure
+            );
+        }
+    }
+}
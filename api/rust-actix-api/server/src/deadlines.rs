@@ -0,0 +1,129 @@
+use crate::errors::ApiError;
+use crate::i18n;
+use actix_web::http::StatusCode;
+use actix_web::{HttpRequest, HttpResponse};
+use chrono::{DateTime, TimeZone, Utc};
+use spicy_todo_core::models::ErrorCode;
+use spicy_todo_core::{Deadline, DeadlineExceeded};
+use std::time::Duration;
+
+/// Relative budget: milliseconds, optionally suffixed with `ms` or `s`.
+pub const TIMEOUT_HEADER: &str = "x-request-timeout";
+/// Absolute deadline: an RFC 3339 timestamp or Unix epoch milliseconds.
+pub const DEADLINE_HEADER: &str = "x-request-deadline";
+
+/// Builds the deadline for a request from its headers. Without either header
+/// the request is unbounded; with both, the earlier one wins.
+pub fn from_request(req: &HttpRequest) -> Result<Deadline, String> {
+    let timeout = match header(req, TIMEOUT_HEADER)? {
+        Some(value) => Some(
+            parse_timeout(value)
+                .ok_or_else(|| format!("Invalid {} header '{}'", TIMEOUT_HEADER, value))?,
+        ),
+        None => None,
+    };
+    let until_deadline = match header(req, DEADLINE_HEADER)? {
+        Some(value) => {
+            let at = parse_deadline(value)
+                .ok_or_else(|| format!("Invalid {} header '{}'", DEADLINE_HEADER, value))?;
+            Some((at - Utc::now()).to_std().unwrap_or(Duration::ZERO))
+        }
+        None => None,
+    };
+
+    Ok(match (timeout, until_deadline) {
+        (Some(a), Some(b)) => Deadline::within(a.min(b)),
+        (Some(budget), None) | (None, Some(budget)) => Deadline::within(budget),
+        (None, None) => Deadline::unbounded(),
+    })
+}
+
+/// 504 carrying where the budget ran out and what had completed by then.
+pub fn exceeded_response(exceeded: DeadlineExceeded) -> HttpResponse {
+    ApiError::new(
+        StatusCode::GATEWAY_TIMEOUT,
+        ErrorCode::DeadlineExceeded,
+        i18n::message("deadline-exceeded"),
+    )
+    .with("diagnostics", exceeded)
+    .into_response()
+}
+
+fn header<'a>(req: &'a HttpRequest, name: &str) -> Result<Option<&'a str>, String> {
+    match req.headers().get(name) {
+        Some(value) => value
+            .to_str()
+            .map(|value| Some(value.trim()))
+            .map_err(|_| format!("Invalid {} header", name)),
+        None => Ok(None),
+    }
+}
+
+fn parse_timeout(value: &str) -> Option<Duration> {
+    if let Some(seconds) = value.strip_suffix('s').filter(|v| !v.ends_with('m')) {
+        return seconds.trim().parse::<f64>().ok().and_then(|seconds| {
+            (seconds.is_finite() && seconds >= 0.0).then(|| Duration::from_secs_f64(seconds))
+        });
+    }
+    let millis = value.strip_suffix("ms").unwrap_or(value);
+    millis.trim().parse::<u64>().ok().map(Duration::from_millis)
+}
+
+fn parse_deadline(value: &str) -> Option<DateTime<Utc>> {
+    if let Ok(epoch_millis) = value.parse::<i64>() {
+        return Utc.timestamp_millis_opt(epoch_millis).single();
+    }
+    DateTime::parse_from_rfc3339(value)
+        .ok()
+        .map(|at| at.with_timezone(&Utc))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::test::TestRequest;
+
+    #[test]
+    fn test_parse_timeout() {
+        assert_eq!(parse_timeout("250"), Some(Duration::from_millis(250)));
+        assert_eq!(parse_timeout("250ms"), Some(Duration::from_millis(250)));
+        assert_eq!(parse_timeout("1.5s"), Some(Duration::from_millis(1500)));
+        assert_eq!(parse_timeout("soon"), None);
+        assert_eq!(parse_timeout("-1s"), None);
+    }
+
+    #[test]
+    fn test_parse_deadline() {
+        let at = parse_deadline("2030-01-01T00:00:00Z").unwrap();
+        assert_eq!(parse_deadline(&at.timestamp_millis().to_string()), Some(at));
+        assert_eq!(parse_deadline("tomorrow"), None);
+    }
+
+    #[test]
+    fn test_from_request_takes_earliest() {
+        let req = TestRequest::default().to_http_request();
+        assert!(!from_request(&req).unwrap().is_bounded());
+
+        let later = (Utc::now() + chrono::Duration::hours(1)).to_rfc3339();
+        let req = TestRequest::default()
+            .insert_header((TIMEOUT_HEADER, "100"))
+            .insert_header((DEADLINE_HEADER, later))
+            .to_http_request();
+        let remaining = from_request(&req).unwrap().remaining().unwrap();
+        assert!(remaining <= Duration::from_millis(100));
+
+        let past = (Utc::now() - chrono::Duration::seconds(5)).to_rfc3339();
+        let req = TestRequest::default()
+            .insert_header((DEADLINE_HEADER, past))
+            .to_http_request();
+        assert_eq!(
+            from_request(&req).unwrap().remaining(),
+            Some(Duration::ZERO)
+        );
+
+        let req = TestRequest::default()
+            .insert_header((TIMEOUT_HEADER, "whenever"))
+            .to_http_request();
+        assert!(from_request(&req).is_err());
+    }
+}
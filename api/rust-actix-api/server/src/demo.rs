@@ -0,0 +1,248 @@
+use crate::config::DemoSettings;
+use crate::errors::ApiError;
+use crate::scheduler::{Outcome, Schedule, Scheduler};
+use actix_web::body::{BoxBody, MessageBody};
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::header::{self, HeaderValue};
+use actix_web::http::{Method, StatusCode};
+use actix_web::middleware::Next;
+use actix_web::{web, HttpResponse};
+use spicy_todo_core::models::TodoCreate;
+use spicy_todo_core::TodoService;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// The window `writes_per_minute` is counted over.
+const RATE_WINDOW: Duration = Duration::from_secs(60);
+/// Routes that can add a todo when posted or put, refused once the demo is
+/// full.
+const ADDING_ROUTES: [&str; 6] = [
+    "/api/todos",
+    "/api/todos/import",
+    "/api/todos/quick",
+    "/api/ingest/email",
+    "/api/mcp/messages",
+    "/dav/todos/{name}",
+];
+/// Routes that add any number of todos in one request, which would carry
+/// the demo past its cap since that is checked before the request.
+const BULK_ROUTES: [&str; 4] = [
+    "/api/sync",
+    "/api/import/spicy",
+    "/api/admin/seed",
+    "/api/admin/generate",
+];
+/// Why an MCP batch is refused: like a bulk route, it can add any number
+/// of todos at once.
+pub const BATCH_REFUSED: &str = "Bulk imports are disabled in the demo";
+/// Routes that make the server call out to addresses or numbers a client
+/// picks, which a public playground must not be made to do.
+const DISABLED_ROUTES: [&str; 5] = [
+    "/api/webhooks",
+    "/api/notifiers",
+    "/api/notifications",
+    "/api/push",
+    "/api/todos/{id}/reminders",
+];
+
+/// State of the public playground: the settings and, per client address,
+/// the writes of the last minute.
+pub struct Demo {
+    settings: DemoSettings,
+    /// Oldest first.
+    writes: Mutex<HashMap<String, VecDeque<Instant>>>,
+}
+
+impl Demo {
+    pub fn new(settings: DemoSettings) -> Self {
+        Demo {
+            settings,
+            writes: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Counts a write by `client` at `now`, or says how long until it may
+    /// write again. Clients quiet for a minute are forgotten.
+    pub fn allow_write(&self, client: &str, now: Instant) -> Result<(), Duration> {
+        let mut writes = self.writes.lock().unwrap();
+        writes.retain(|_, times| {
+            while times
+                .front()
+                .is_some_and(|at| now.duration_since(*at) >= RATE_WINDOW)
+            {
+                times.pop_front();
+            }
+            !times.is_empty()
+        });
+        let times = writes.entry(client.to_string()).or_default();
+        if let Some(oldest) = times
+            .front()
+            .filter(|_| times.len() >= self.settings.writes_per_minute)
+        {
+            return Err(RATE_WINDOW.saturating_sub(now.duration_since(*oldest)));
+        }
+        times.push_back(now);
+        Ok(())
+    }
+
+    /// Who sent `req`, for the rate limit: the address it connects from,
+    /// or the forwarded one when that is a trusted proxy. Any other client
+    /// could dodge the limit by sending a different `X-Forwarded-For` on
+    /// each request.
+    fn client(&self, req: &ServiceRequest) -> String {
+        let Some(peer) = req.peer_addr().map(|addr| addr.ip()) else {
+            return "unknown".to_string();
+        };
+        if !self.settings.trusted_proxies.contains(&peer) {
+            return peer.to_string();
+        }
+        req.connection_info()
+            .realip_remote_addr()
+            .map_or_else(|| peer.to_string(), str::to_string)
+    }
+
+    /// Why `req` is refused, if it is.
+    fn refusal(&self, req: &ServiceRequest) -> Option<HttpResponse> {
+        let route = unversioned(
+            &req.match_pattern()
+                .unwrap_or_else(|| req.path().to_string()),
+        );
+        if DISABLED_ROUTES
+            .iter()
+            .any(|prefix| route.starts_with(prefix))
+        {
+            return Some(refused(
+                "Notifications, webhooks and notifiers are disabled in the demo",
+            ));
+        }
+        if BULK_ROUTES.contains(&route.as_str()) {
+            return Some(refused(BATCH_REFUSED));
+        }
+        if matches!(*req.method(), Method::GET | Method::HEAD | Method::OPTIONS) {
+            return None;
+        }
+        let client = self.client(req);
+        if let Err(retry_after) = self.allow_write(&client, Instant::now()) {
+            let message = format!(
+                "The demo allows {} changes a minute",
+                self.settings.writes_per_minute
+            );
+            let mut response =
+                ApiError::status(StatusCode::TOO_MANY_REQUESTS, message).into_response();
+            response.headers_mut().insert(
+                header::RETRY_AFTER,
+                HeaderValue::from(retry_after.as_secs().max(1)),
+            );
+            return Some(response);
+        }
+        let full = req
+            .app_data::<web::Data<TodoService>>()
+            .is_some_and(|service| service.get_stats().total >= self.settings.max_todos);
+        let adding = matches!(*req.method(), Method::POST | Method::PUT)
+            && ADDING_ROUTES.contains(&route.as_str());
+        if full && adding {
+            return Some(refused(&format!(
+                "The demo holds at most {} todos until it resets",
+                self.settings.max_todos
+            )));
+        }
+        None
+    }
+}
+
+fn refused(message: &str) -> HttpResponse {
+    ApiError::status(StatusCode::FORBIDDEN, message).into_response()
+}
+
+/// `/api/v1/...` and `/api/v2/...` as `/api/...`.
+fn unversioned(path: &str) -> String {
+    ["/api/v1", "/api/v2"]
+        .iter()
+        .find_map(|prefix| path.strip_prefix(prefix))
+        .map_or(path.to_string(), |rest| format!("/api{}", rest))
+}
+
+/// Enforces the demo's limits when it runs as one; a pass-through
+/// otherwise.
+pub async fn guard_demo(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<BoxBody>, actix_web::Error> {
+    let refusal = req
+        .app_data::<web::Data<Demo>>()
+        .and_then(|demo| demo.refusal(&req));
+    match refusal {
+        Some(response) => Ok(req.into_response(response)),
+        None => Ok(next.call(req).await?.map_into_boxed_body()),
+    }
+}
+
+/// Wipes every todo and seeds `fixtures` again each `reset_interval`.
+pub fn schedule(
+    scheduler: &mut Scheduler,
+    service: web::Data<TodoService>,
+    settings: &DemoSettings,
+    fixtures: Vec<TodoCreate>,
+) {
+    let interval = Schedule::Every(settings.reset_interval);
+    scheduler.register_exclusive("demo-reset", interval, move || {
+        let removed = service.reset();
+        let seeded = service.seed(fixtures.clone()).len();
+        println!(
+            "🎪 Reset the demo: {} todos removed, {} seeded",
+            removed, seeded
+        );
+        async move { Ok(Outcome::Done) }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::test::TestRequest;
+
+    #[test]
+    fn test_limits_writes_per_client() {
+        let demo = Demo::new(DemoSettings {
+            writes_per_minute: 2,
+            ..Default::default()
+        });
+        let start = Instant::now();
+        assert!(demo.allow_write("a", start).is_ok());
+        assert!(demo
+            .allow_write("a", start + Duration::from_secs(10))
+            .is_ok());
+        assert_eq!(
+            demo.allow_write("a", start + Duration::from_secs(20)),
+            Err(Duration::from_secs(40))
+        );
+        assert!(demo
+            .allow_write("b", start + Duration::from_secs(20))
+            .is_ok());
+        assert!(demo
+            .allow_write("a", start + Duration::from_secs(60))
+            .is_ok());
+        assert_eq!(unversioned("/api/v2/todos"), "/api/todos");
+        assert_eq!(unversioned("/dav/todos/{name}"), "/dav/todos/{name}");
+    }
+
+    #[test]
+    fn test_counts_forwarded_clients_only_behind_trusted_proxies() {
+        let proxy = "10.0.0.1:4000".parse().unwrap();
+        let request = || {
+            TestRequest::default()
+                .peer_addr(proxy)
+                .insert_header(("x-forwarded-for", "203.0.113.9"))
+                .to_srv_request()
+        };
+        let untrusted = Demo::new(DemoSettings::default());
+        assert_eq!(untrusted.client(&request()), "10.0.0.1");
+
+        let trusted = Demo::new(DemoSettings {
+            trusted_proxies: vec![proxy.ip()],
+            ..Default::default()
+        });
+        assert_eq!(trusted.client(&request()), "203.0.113.9");
+    }
+}
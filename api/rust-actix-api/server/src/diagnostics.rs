@@ -0,0 +1,93 @@
+use serde::Serialize;
+use std::sync::Mutex;
+use tokio::runtime::Handle;
+
+/// Handles of the tokio runtimes serving this process. Actix runs one
+/// single-threaded runtime per HTTP worker plus the system runtime, so each
+/// registers itself on startup to be reported together.
+#[derive(Default)]
+pub struct RuntimeRegistry {
+    runtimes: Mutex<Vec<(String, Handle)>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct WorkerSnapshot {
+    #[serde(rename = "busyMs")]
+    pub busy_ms: f64,
+    #[serde(rename = "parkCount")]
+    pub park_count: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RuntimeSnapshot {
+    pub name: String,
+    /// Tasks started with `tokio::spawn`. Actix's `spawn_local` tasks live on
+    /// a `LocalSet` and are not included.
+    #[serde(rename = "aliveTasks")]
+    pub alive_tasks: usize,
+    #[serde(rename = "globalQueueDepth")]
+    pub global_queue_depth: usize,
+    pub workers: Vec<WorkerSnapshot>,
+}
+
+impl RuntimeRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers the runtime of the calling thread under the thread's name.
+    /// Registering the same thread twice is a no-op.
+    pub fn register_current(&self) {
+        let Ok(handle) = Handle::try_current() else {
+            return;
+        };
+        let name = std::thread::current()
+            .name()
+            .unwrap_or("unnamed")
+            .to_string();
+        let mut runtimes = self.runtimes.lock().unwrap();
+        if !runtimes.iter().any(|(existing, _)| *existing == name) {
+            runtimes.push((name, handle));
+        }
+    }
+
+    pub fn snapshot(&self) -> Vec<RuntimeSnapshot> {
+        let runtimes = self.runtimes.lock().unwrap();
+        runtimes
+            .iter()
+            .map(|(name, handle)| {
+                let metrics = handle.metrics();
+                RuntimeSnapshot {
+                    name: name.clone(),
+                    alive_tasks: metrics.num_alive_tasks(),
+                    global_queue_depth: metrics.global_queue_depth(),
+                    workers: (0..metrics.num_workers())
+                        .map(|worker| WorkerSnapshot {
+                            busy_ms: metrics.worker_total_busy_duration(worker).as_secs_f64()
+                                * 1000.0,
+                            park_count: metrics.worker_park_count(worker),
+                        })
+                        .collect(),
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[actix_web::test]
+    async fn test_registers_current_runtime_once() {
+        let registry = RuntimeRegistry::new();
+        registry.register_current();
+        registry.register_current();
+
+        let _task = tokio::spawn(std::future::pending::<()>());
+        let snapshot = registry.snapshot();
+        assert_eq!(snapshot.len(), 1);
+        assert_eq!(snapshot[0].workers.len(), 1);
+        assert!(snapshot[0].alive_tasks >= 1);
+    }
+}
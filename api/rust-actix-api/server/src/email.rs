@@ -0,0 +1,743 @@
+use crate::config::{Config, ImapSettings};
+use crate::moderation::{Moderation, ModerationError};
+use crate::plugins::{Kind, Plugin};
+use crate::scheduler::{Outcome, Schedule, Scheduler};
+use actix_web::web;
+use chrono::{DateTime, NaiveDate, Utc};
+use hmac::{Hmac, Mac};
+use mail_parser::MessageParser;
+use serde::Deserialize;
+use sha2::Sha256;
+use spicy_todo_core::deadline::{Deadline, DeadlineExceeded};
+use spicy_todo_core::models::{Priority, Todo, TodoCreate};
+use spicy_todo_core::{quick_add, TodoService};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+use tokio::time::timeout;
+use tokio_rustls::rustls::pki_types::ServerName;
+use tokio_rustls::{rustls, TlsConnector};
+
+/// Longest todo text; longer subjects are cut short.
+const MAX_TEXT_BYTES: usize = 500;
+/// Dropped from the start of subjects, so forwarding a mail to the inbox
+/// works as well as writing one.
+const SUBJECT_PREFIXES: [&str; 3] = ["re:", "fwd:", "fw:"];
+/// How many message ids are remembered to drop duplicate deliveries.
+const MAX_REMEMBERED: usize = 1000;
+/// Oldest signed webhook accepted, against replayed requests.
+const MAX_WEBHOOK_AGE: Duration = Duration::from_secs(15 * 60);
+/// Most emails read per poll; the rest wait for the next one.
+const MAX_PER_POLL: usize = 50;
+const IO_TIMEOUT: Duration = Duration::from_secs(30);
+/// Longest response line or literal taken from the IMAP server. Only
+/// headers are fetched, so anything bigger is a misbehaving server.
+const MAX_RESPONSE_BYTES: usize = 64 * 1024;
+const FETCH_HEADERS: &str = "SUBJECT MESSAGE-ID X-PRIORITY IMPORTANCE";
+
+/// The parts of an email a todo is made from.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct InboundEmail {
+    pub message_id: Option<String>,
+    pub subject: String,
+    /// From the IMAP `\Flagged` flag or the `X-Priority` and `Importance`
+    /// headers.
+    pub priority: Option<Priority>,
+}
+
+/// What became of one email.
+#[derive(Debug)]
+pub enum Ingested {
+    Created(Box<Todo>),
+    /// Its message id was seen before.
+    Duplicate,
+    /// Nothing was left of the subject to make a todo of.
+    Empty,
+    /// Moderation refused the subject.
+    Disallowed,
+}
+
+/// Why an email could not be taken in yet; it is worth delivering again.
+#[derive(Debug)]
+pub enum IngestError {
+    Deadline(DeadlineExceeded),
+    /// The moderation service gave no verdict.
+    Moderation(ModerationError),
+}
+
+impl std::fmt::Display for IngestError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            IngestError::Deadline(e) => write!(f, "{}", e),
+            IngestError::Moderation(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+/// Reads `X-Priority` (1 highest to 5 lowest), falling back to
+/// `Importance` (`high`, `normal` or `low`).
+pub fn header_priority(x_priority: Option<&str>, importance: Option<&str>) -> Option<Priority> {
+    let from_x_priority = x_priority.and_then(|value| match value.trim().chars().next()? {
+        '1' | '2' => Some(Priority::High),
+        '4' | '5' => Some(Priority::Low),
+        _ => None,
+    });
+    from_x_priority.or_else(|| match importance?.trim().to_ascii_lowercase().as_str() {
+        "high" => Some(Priority::High),
+        "low" => Some(Priority::Low),
+        _ => None,
+    })
+}
+
+/// The todo for `email`: its subject read like a quick-add line, so
+/// `Pay rent friday !high` sets a due date and priority. A priority marker
+/// in the subject beats the email's own priority.
+pub fn to_todo(email: &InboundEmail, today: NaiveDate) -> Option<TodoCreate> {
+    let mut subject = email.subject.trim();
+    while let Some(rest) = SUBJECT_PREFIXES.iter().find_map(|prefix| {
+        let head = subject.get(..prefix.len())?;
+        head.eq_ignore_ascii_case(prefix)
+            .then(|| &subject[prefix.len()..])
+    }) {
+        subject = rest.trim_start();
+    }
+    let mut parsed = quick_add::parse(subject, today);
+    if parsed.text.trim().is_empty() {
+        return None;
+    }
+    parsed.priority = parsed.priority.or_else(|| email.priority.clone());
+    let mut todo = parsed.into_create();
+    if todo.text.len() > MAX_TEXT_BYTES {
+        let mut end = MAX_TEXT_BYTES;
+        while !todo.text.is_char_boundary(end) {
+            end -= 1;
+        }
+        todo.text.truncate(end);
+    }
+    Some(todo)
+}
+
+/// Turns emails into todos, from the mailbox poller and the webhook alike,
+/// dropping ones whose message id was seen recently.
+pub struct EmailIngest {
+    /// Oldest first.
+    seen: Mutex<VecDeque<String>>,
+}
+
+impl EmailIngest {
+    pub fn new() -> Self {
+        EmailIngest {
+            seen: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    pub async fn ingest(
+        &self,
+        service: &TodoService,
+        moderation: &Moderation,
+        email: &InboundEmail,
+        today: NaiveDate,
+        deadline: &Deadline,
+    ) -> Result<Ingested, IngestError> {
+        let Some(mut todo) = to_todo(email, today) else {
+            return Ok(Ingested::Empty);
+        };
+        if self.seen(email) {
+            return Ok(Ingested::Duplicate);
+        }
+        match moderation.apply(&mut todo.text).await {
+            Ok(()) => {}
+            Err(ModerationError::Disallowed(_)) => return Ok(Ingested::Disallowed),
+            Err(e) => return Err(IngestError::Moderation(e)),
+        }
+        // Checked again and held while creating, so two deliveries of one
+        // email can't both pass the check.
+        let mut seen = self.seen.lock().unwrap();
+        if let Some(id) = &email.message_id {
+            if seen.contains(id) {
+                return Ok(Ingested::Duplicate);
+            }
+        }
+        let todo = service
+            .create_until(todo, deadline)
+            .map_err(IngestError::Deadline)?;
+        if let Some(id) = &email.message_id {
+            if seen.len() >= MAX_REMEMBERED {
+                seen.pop_front();
+            }
+            seen.push_back(id.clone());
+        }
+        Ok(Ingested::Created(Box::new(todo)))
+    }
+
+    fn seen(&self, email: &InboundEmail) -> bool {
+        email
+            .message_id
+            .as_ref()
+            .is_some_and(|id| self.seen.lock().unwrap().contains(id))
+    }
+}
+
+impl Default for EmailIngest {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Form fields Mailgun posts for an inbound email; the others are ignored.
+/// Messages with attachments arrive as multipart forms, which aren't
+/// accepted, so strip attachments in the Mailgun route.
+#[derive(Debug, Deserialize)]
+pub struct MailgunEmail {
+    #[serde(default)]
+    pub subject: String,
+    #[serde(rename = "Message-Id")]
+    pub message_id: Option<String>,
+    /// The original headers, as a JSON list of `[name, value]` pairs.
+    #[serde(rename = "message-headers")]
+    pub message_headers: Option<String>,
+    pub timestamp: String,
+    pub token: String,
+    pub signature: String,
+}
+
+impl MailgunEmail {
+    /// Checks that Mailgun sent this: `signature` is the hex HMAC-SHA256 of
+    /// `timestamp` and `token` under the signing key, and the timestamp is
+    /// recent.
+    pub fn verify(&self, signing_key: &str, now: DateTime<Utc>) -> Result<(), String> {
+        let timestamp: i64 = self
+            .timestamp
+            .parse()
+            .map_err(|_| "Invalid timestamp".to_string())?;
+        if (now.timestamp() - timestamp).unsigned_abs() > MAX_WEBHOOK_AGE.as_secs() {
+            return Err("Timestamp is too far from the current time".to_string());
+        }
+        let signature =
+            hex::decode(&self.signature).map_err(|_| "Invalid signature".to_string())?;
+        let mut mac =
+            Hmac::<Sha256>::new_from_slice(signing_key.as_bytes()).map_err(|e| e.to_string())?;
+        mac.update(self.timestamp.as_bytes());
+        mac.update(self.token.as_bytes());
+        mac.verify_slice(&signature)
+            .map_err(|_| "Invalid signature".to_string())
+    }
+
+    pub fn email(&self) -> InboundEmail {
+        let headers: Vec<(String, String)> = self
+            .message_headers
+            .as_deref()
+            .and_then(|headers| serde_json::from_str(headers).ok())
+            .unwrap_or_default();
+        let header = |name: &str| {
+            headers
+                .iter()
+                .find(|(header, _)| header.eq_ignore_ascii_case(name))
+                .map(|(_, value)| value.as_str())
+        };
+        let message_id = self.message_id.as_deref().or_else(|| header("Message-Id"));
+        InboundEmail {
+            // IMAP yields message ids without the angle brackets.
+            message_id: message_id.map(|id| id.trim().trim_matches(['<', '>']).to_string()),
+            subject: self.subject.clone(),
+            priority: header_priority(header("X-Priority"), header("Importance")),
+        }
+    }
+}
+
+/// An IMAP mailbox whose unread emails become todos.
+pub struct Mailbox {
+    host: String,
+    port: u16,
+    tls: bool,
+    username: String,
+    password: String,
+    mailbox: String,
+}
+
+impl Mailbox {
+    pub fn from_settings(settings: &ImapSettings) -> Result<Self, String> {
+        let username = settings
+            .username
+            .clone()
+            .ok_or("IMAP_USERNAME is required when IMAP_HOST is set")?;
+        let password = settings
+            .password
+            .clone()
+            .ok_or("IMAP_PASSWORD is required when IMAP_HOST is set")?;
+        let mailbox = Mailbox {
+            host: settings.host.clone(),
+            port: settings.port,
+            tls: settings.tls,
+            username,
+            password,
+            mailbox: settings.mailbox.clone(),
+        };
+        // Fail at startup rather than on every poll.
+        quote(&mailbox.username)
+            .and(quote(&mailbox.password))
+            .and(quote(&mailbox.mailbox))?;
+        Ok(mailbox)
+    }
+
+    /// Turns unread emails into todos and marks them read, returning how
+    /// many todos were created.
+    pub async fn poll(
+        &self,
+        ingest: &EmailIngest,
+        service: &TodoService,
+        moderation: &Moderation,
+    ) -> Result<usize, String> {
+        let tcp = timeout(
+            IO_TIMEOUT,
+            TcpStream::connect((self.host.as_str(), self.port)),
+        )
+        .await
+        .map_err(|_| format!("Timed out connecting to {}", self.host))?
+        .map_err(|e| format!("IMAP server unreachable: {}", e))?;
+        if !self.tls {
+            return self.poll_on(tcp, ingest, service, moderation).await;
+        }
+        let name = ServerName::try_from(self.host.clone()).map_err(|e| e.to_string())?;
+        let stream = timeout(IO_TIMEOUT, tls_connector()?.connect(name, tcp))
+            .await
+            .map_err(|_| format!("Timed out connecting to {}", self.host))?
+            .map_err(|e| format!("TLS handshake with {} failed: {}", self.host, e))?;
+        self.poll_on(stream, ingest, service, moderation).await
+    }
+
+    async fn poll_on<S: AsyncRead + AsyncWrite + Unpin>(
+        &self,
+        stream: S,
+        ingest: &EmailIngest,
+        service: &TodoService,
+        moderation: &Moderation,
+    ) -> Result<usize, String> {
+        let mut session = Session::new(stream);
+        session.greeting().await?;
+        session
+            .command(&format!(
+                "LOGIN {} {}",
+                quote(&self.username)?,
+                quote(&self.password)?
+            ))
+            .await?;
+        session
+            .command(&format!("SELECT {}", quote(&self.mailbox)?))
+            .await?;
+        let unseen = search_results(&session.command("UID SEARCH UNSEEN").await?);
+
+        let mut created = 0;
+        if !unseen.is_empty() {
+            let uids: Vec<String> = unseen
+                .iter()
+                .take(MAX_PER_POLL)
+                .map(u32::to_string)
+                .collect();
+            let fetched = session
+                .command(&format!(
+                    "UID FETCH {} (UID FLAGS BODY.PEEK[HEADER.FIELDS ({})])",
+                    uids.join(","),
+                    FETCH_HEADERS
+                ))
+                .await?;
+            let today = Utc::now().date_naive();
+            for (uid, email) in fetched.iter().filter_map(parse_fetch) {
+                let ingested = ingest
+                    .ingest(service, moderation, &email, today, &Deadline::unbounded())
+                    .await
+                    .map_err(|e| e.to_string())?;
+                if let Ingested::Created(_) = ingested {
+                    created += 1;
+                }
+                // Only once its todo exists, so a failed poll reads it again.
+                session
+                    .command(&format!("UID STORE {} +FLAGS.SILENT (\\Seen)", uid))
+                    .await?;
+            }
+        }
+        // The server closes the connection either way.
+        let _ = session.command("LOGOUT").await;
+        Ok(created)
+    }
+}
+
+fn tls_connector() -> Result<TlsConnector, String> {
+    let roots = rustls::RootCertStore {
+        roots: webpki_roots::TLS_SERVER_ROOTS.to_vec(),
+    };
+    let config = rustls::ClientConfig::builder_with_provider(Arc::new(
+        rustls::crypto::ring::default_provider(),
+    ))
+    .with_safe_default_protocol_versions()
+    .map_err(|e| e.to_string())?
+    .with_root_certificates(roots)
+    .with_no_client_auth();
+    Ok(TlsConnector::from(Arc::new(config)))
+}
+
+/// An IMAP quoted string.
+fn quote(value: &str) -> Result<String, String> {
+    if value.contains(['\r', '\n', '\0']) {
+        return Err("IMAP settings cannot contain line breaks".to_string());
+    }
+    Ok(format!(
+        "\"{}\"",
+        value.replace('\\', "\\\\").replace('"', "\\\"")
+    ))
+}
+
+/// One server response: its text, with the bytes of any literals set
+/// aside in order.
+#[derive(Debug, Default)]
+struct Response {
+    text: String,
+    literals: Vec<Vec<u8>>,
+}
+
+/// The size announced by a line ending in a literal, `{123}`.
+fn literal_size(line: &str) -> Option<usize> {
+    let open = line.strip_suffix('}')?.rfind('{')?;
+    line[open + 1..line.len() - 1]
+        .trim_end_matches('+')
+        .parse()
+        .ok()
+}
+
+/// The UIDs in a `UID SEARCH` response.
+fn search_results(responses: &[Response]) -> Vec<u32> {
+    responses
+        .iter()
+        .filter_map(|response| response.text.strip_prefix("* SEARCH"))
+        .flat_map(|uids| uids.split_whitespace().filter_map(|uid| uid.parse().ok()))
+        .collect()
+}
+
+/// The UID and email in one `FETCH` response.
+fn parse_fetch(response: &Response) -> Option<(u32, InboundEmail)> {
+    let (_, items) = response.text.split_once(" FETCH (")?;
+    let mut words = items.split_whitespace();
+    words.find(|word| word.eq_ignore_ascii_case("UID"))?;
+    let uid = words.next()?.trim_end_matches(')').parse().ok()?;
+    let message = MessageParser::default().parse_headers(response.literals.first()?)?;
+    let flagged = items.to_ascii_uppercase().contains("\\FLAGGED");
+    let priority = if flagged {
+        Some(Priority::High)
+    } else {
+        header_priority(
+            message.header_raw("X-Priority"),
+            message.header_raw("Importance"),
+        )
+    };
+    Some((
+        uid,
+        InboundEmail {
+            message_id: message.message_id().map(str::to_string),
+            subject: message.subject().unwrap_or_default().to_string(),
+            priority,
+        },
+    ))
+}
+
+/// An IMAP connection issuing one command at a time.
+struct Session<S> {
+    stream: BufReader<S>,
+    tag: u32,
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> Session<S> {
+    fn new(stream: S) -> Self {
+        Session {
+            stream: BufReader::new(stream),
+            tag: 0,
+        }
+    }
+
+    async fn read_response(&mut self) -> Result<Response, String> {
+        let mut response = Response::default();
+        loop {
+            let mut line = Vec::new();
+            let mut limited = (&mut self.stream).take(MAX_RESPONSE_BYTES as u64);
+            timeout(IO_TIMEOUT, limited.read_until(b'\n', &mut line))
+                .await
+                .map_err(|_| "IMAP server timed out".to_string())?
+                .map_err(|e| format!("IMAP connection failed: {}", e))?;
+            if line.is_empty() {
+                return Err("IMAP server closed the connection".to_string());
+            }
+            if !line.ends_with(b"\n") {
+                return Err("IMAP response line too long".to_string());
+            }
+            let line = String::from_utf8_lossy(&line);
+            let line = line.trim_end_matches(['\r', '\n']);
+            response.text.push_str(line);
+            let Some(size) = literal_size(line) else {
+                return Ok(response);
+            };
+            if size > MAX_RESPONSE_BYTES {
+                return Err("IMAP literal too long".to_string());
+            }
+            let mut literal = vec![0; size];
+            timeout(IO_TIMEOUT, self.stream.read_exact(&mut literal))
+                .await
+                .map_err(|_| "IMAP server timed out".to_string())?
+                .map_err(|e| format!("IMAP connection failed: {}", e))?;
+            response.literals.push(literal);
+        }
+    }
+
+    async fn greeting(&mut self) -> Result<(), String> {
+        let greeting = self.read_response().await?;
+        if greeting.text.starts_with("* OK") || greeting.text.starts_with("* PREAUTH") {
+            Ok(())
+        } else {
+            Err(format!("Unexpected IMAP greeting: {}", greeting.text))
+        }
+    }
+
+    /// Sends `command` and returns the untagged responses to it, or the
+    /// server's refusal.
+    async fn command(&mut self, command: &str) -> Result<Vec<Response>, String> {
+        self.tag += 1;
+        let tag = format!("a{}", self.tag);
+        let line = format!("{} {}\r\n", tag, command);
+        let sent = timeout(IO_TIMEOUT, async {
+            self.stream.write_all(line.as_bytes()).await?;
+            self.stream.flush().await
+        });
+        sent.await
+            .map_err(|_| "IMAP server timed out".to_string())?
+            .map_err(|e| format!("IMAP connection failed: {}", e))?;
+
+        let mut untagged = Vec::new();
+        loop {
+            let response = self.read_response().await?;
+            let Some(status) = response
+                .text
+                .strip_prefix(tag.as_str())
+                .and_then(|rest| rest.strip_prefix(' '))
+            else {
+                untagged.push(response);
+                continue;
+            };
+            if status
+                .get(..2)
+                .is_some_and(|ok| ok.eq_ignore_ascii_case("OK"))
+            {
+                return Ok(untagged);
+            }
+            // Never the whole command, which may hold the password.
+            let name = command.split(' ').next().unwrap_or_default();
+            return Err(format!("IMAP {} failed: {}", name, status));
+        }
+    }
+}
+
+/// Polls `mailbox` every `interval`.
+pub fn schedule(
+    scheduler: &mut Scheduler,
+    mailbox: web::Data<Mailbox>,
+    ingest: web::Data<EmailIngest>,
+    service: web::Data<TodoService>,
+    moderation: web::Data<Moderation>,
+    interval: Duration,
+) {
+    scheduler.register_exclusive("email-ingest", Schedule::Every(interval), move || {
+        let (mailbox, ingest, service) = (mailbox.clone(), ingest.clone(), service.clone());
+        let moderation = moderation.clone();
+        async move {
+            match mailbox.poll(&ingest, &service, &moderation).await? {
+                0 => Ok(Outcome::Skipped),
+                created => {
+                    println!("📧 Created {} todos from email", created);
+                    Ok(Outcome::Done)
+                }
+            }
+        }
+    });
+}
+
+pub struct EmailPlugin;
+
+impl Plugin for EmailPlugin {
+    fn name(&self) -> &'static str {
+        "email"
+    }
+
+    fn kind(&self) -> Kind {
+        Kind::Importer
+    }
+
+    fn description(&self) -> &'static str {
+        "Turns emails into todos, from an IMAP mailbox or a Mailgun webhook"
+    }
+
+    fn enabled(&self, config: &Config) -> bool {
+        config.imap.is_some() || config.email_webhook_signing_key.is_some()
+    }
+
+    fn details(&self, config: &Config) -> Option<serde_json::Value> {
+        Some(serde_json::json!({
+            "imap": config.imap.is_some(),
+            "webhook": config.email_webhook_signing_key.is_some()
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::moderation::{ModerationMode, WordList};
+
+    fn today() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 6, 10).unwrap()
+    }
+
+    fn email(subject: &str, priority: Option<Priority>) -> InboundEmail {
+        InboundEmail {
+            message_id: Some("abc@example.com".to_string()),
+            subject: subject.to_string(),
+            priority,
+        }
+    }
+
+    #[test]
+    fn test_subject_becomes_todo() {
+        let todo = to_todo(&email("Fwd: RE: Pay rent tomorrow", None), today()).unwrap();
+        assert_eq!(todo.text, "Pay rent");
+        assert_eq!(todo.due_date.as_deref(), Some("2024-06-11"));
+        assert_eq!(todo.priority, None);
+
+        let flagged = email("Renew passport !low", Some(Priority::High));
+        assert_eq!(
+            to_todo(&flagged, today()).unwrap().priority,
+            Some(Priority::Low)
+        );
+        let flagged = email("Renew passport", Some(Priority::High));
+        assert_eq!(
+            to_todo(&flagged, today()).unwrap().priority,
+            Some(Priority::High)
+        );
+
+        assert!(to_todo(&email("Re: ", None), today()).is_none());
+        let long = to_todo(&email(&"é".repeat(300), None), today()).unwrap();
+        assert_eq!(long.text.len(), 500);
+    }
+
+    #[test]
+    fn test_header_priority() {
+        assert_eq!(
+            header_priority(Some("1 (Highest)"), None),
+            Some(Priority::High)
+        );
+        assert_eq!(
+            header_priority(Some("5"), Some("high")),
+            Some(Priority::Low)
+        );
+        assert_eq!(header_priority(Some("3"), Some("Low")), Some(Priority::Low));
+        assert_eq!(header_priority(None, Some("normal")), None);
+    }
+
+    #[actix_web::test]
+    async fn test_duplicates_are_dropped() {
+        let service = TodoService::new_empty();
+        let moderation = Moderation::default();
+        let ingest = EmailIngest::new();
+        let deadline = Deadline::unbounded();
+        let rent = email("Pay rent", None);
+        assert!(matches!(
+            ingest
+                .ingest(&service, &moderation, &rent, today(), &deadline)
+                .await
+                .unwrap(),
+            Ingested::Created(_)
+        ));
+        assert!(matches!(
+            ingest
+                .ingest(&service, &moderation, &rent, today(), &deadline)
+                .await
+                .unwrap(),
+            Ingested::Duplicate
+        ));
+        assert_eq!(service.get_all(None, None, None).len(), 1);
+    }
+
+    #[actix_web::test]
+    async fn test_subjects_are_moderated() {
+        let service = TodoService::new_empty();
+        let ingest = EmailIngest::new();
+        let deadline = Deadline::unbounded();
+        let words = || vec![Box::new(WordList::new(&["spinach".to_string()])) as Box<_>];
+        let spinach = email("Buy spinach", None);
+
+        let reject = Moderation::new(ModerationMode::Reject, words());
+        assert!(matches!(
+            ingest
+                .ingest(&service, &reject, &spinach, today(), &deadline)
+                .await
+                .unwrap(),
+            Ingested::Disallowed
+        ));
+        assert!(service.get_all(None, None, None).is_empty());
+
+        let mask = Moderation::new(ModerationMode::Mask, words());
+        let Ingested::Created(todo) = ingest
+            .ingest(&service, &mask, &spinach, today(), &deadline)
+            .await
+            .unwrap()
+        else {
+            panic!("expected a todo");
+        };
+        assert_eq!(todo.text, "Buy *******");
+    }
+
+    #[test]
+    fn test_mailgun_signature() {
+        let mut mac = Hmac::<Sha256>::new_from_slice(b"key-secret").unwrap();
+        mac.update(b"1718000000token123");
+        let mut form = MailgunEmail {
+            subject: "Pay rent".to_string(),
+            message_id: Some("<abc@example.com>".to_string()),
+            message_headers: Some(r#"[["X-Priority", "1"], ["Subject", "Pay rent"]]"#.to_string()),
+            timestamp: "1718000000".to_string(),
+            token: "token123".to_string(),
+            signature: hex::encode(mac.finalize().into_bytes()),
+        };
+        let now = DateTime::from_timestamp(1718000060, 0).unwrap();
+        assert!(form.verify("key-secret", now).is_ok());
+        assert!(form.verify("other-key", now).is_err());
+        let later = DateTime::from_timestamp(1718000000 + 3600, 0).unwrap();
+        assert!(form.verify("key-secret", later).is_err());
+
+        let email = form.email();
+        assert_eq!(email.message_id.as_deref(), Some("abc@example.com"));
+        assert_eq!(email.priority, Some(Priority::High));
+
+        form.token = "token124".to_string();
+        assert!(form.verify("key-secret", now).is_err());
+    }
+
+    #[test]
+    fn test_parse_fetch_response() {
+        let header = "Subject: =?UTF-8?B?UGF5IHJlbnQgdG9kYXk=?=\r\n\
+                      Message-ID: <abc@example.com>\r\n\r\n";
+        let response = Response {
+            text: format!(
+                "* 3 FETCH (UID 42 FLAGS (\\Seen \\Flagged) BODY[HEADER.FIELDS ({})] {{{}}})",
+                FETCH_HEADERS,
+                header.len()
+            ),
+            literals: vec![header.as_bytes().to_vec()],
+        };
+        let (uid, email) = parse_fetch(&response).unwrap();
+        assert_eq!(uid, 42);
+        assert_eq!(email.subject, "Pay rent today");
+        assert_eq!(email.message_id.as_deref(), Some("abc@example.com"));
+        assert_eq!(email.priority, Some(Priority::High));
+
+        assert_eq!(literal_size("* 3 FETCH (BODY[] {123}"), Some(123));
+        assert_eq!(literal_size("* OK [UIDNEXT 5]"), None);
+    }
+}
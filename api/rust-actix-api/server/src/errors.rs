@@ -0,0 +1,168 @@
+use crate::casing;
+use crate::i18n;
+use actix_web::body::BoxBody;
+use actix_web::dev::ServiceResponse;
+use actix_web::error::PathError;
+use actix_web::http::{header, StatusCode};
+use actix_web::{error, HttpRequest, HttpResponse, ResponseError};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use spicy_todo_core::models::ErrorCode;
+use std::fmt;
+
+/// An error response, with its code fixed where the error is raised
+/// rather than worked out later from the message.
+#[derive(Debug)]
+pub struct ApiError {
+    status: StatusCode,
+    code: ErrorCode,
+    message: String,
+    /// Fields sent next to `error` and `code`, such as id suggestions.
+    details: Map<String, Value>,
+}
+
+impl ApiError {
+    pub fn new(status: StatusCode, code: ErrorCode, message: impl Into<String>) -> Self {
+        ApiError {
+            status,
+            code,
+            message: message.into(),
+            details: Map::new(),
+        }
+    }
+
+    /// An error with the code its status implies.
+    pub fn status(status: StatusCode, message: impl Into<String>) -> Self {
+        Self::new(status, ErrorCode::for_status(status.as_u16()), message)
+    }
+
+    /// A 400 for a missing or invalid field, header or parameter.
+    pub fn bad_request(message: impl Into<String>) -> Self {
+        Self::new(
+            StatusCode::BAD_REQUEST,
+            ErrorCode::ValidationFailed,
+            message,
+        )
+    }
+
+    pub fn not_found(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::NOT_FOUND, ErrorCode::NotFound, message)
+    }
+
+    /// A 404 for a feature this server has not been set up with.
+    pub fn not_configured(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::NOT_FOUND, ErrorCode::NotConfigured, message)
+    }
+
+    /// Adds `key` to the body next to the message, in the key case of the
+    /// request being handled.
+    pub fn with(mut self, key: &str, value: impl Serialize) -> Self {
+        let (key, value) = casing::field(key, value).expect("error details serialize");
+        self.details.insert(key, value);
+        self
+    }
+
+    pub fn code(&self) -> ErrorCode {
+        self.code
+    }
+
+    /// The response, keeping the error attached so middleware can tell it
+    /// apart from a plain body.
+    pub fn into_response(self) -> HttpResponse {
+        HttpResponse::from_error(self)
+    }
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl ResponseError for ApiError {
+    fn status_code(&self) -> StatusCode {
+        self.status
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        let mut body = Map::new();
+        body.insert("error".to_string(), Value::String(self.message.clone()));
+        body.insert("code".to_string(), serde_json::json!(self.code));
+        body.extend(self.details.clone());
+        HttpResponse::build(self.status).json(Value::Object(body))
+    }
+}
+
+/// Whether a response says it is JSON.
+pub fn is_json(response: &ServiceResponse<BoxBody>) -> bool {
+    response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("application/json"))
+}
+
+/// Runs `edit` over a JSON response body, re-serializing it if `edit`
+/// reports a change. Returns the response and whether it changed.
+pub async fn edit_json(
+    response: ServiceResponse<BoxBody>,
+    edit: impl FnOnce(&mut Value) -> bool,
+) -> Result<(ServiceResponse<BoxBody>, bool), actix_web::Error> {
+    let (req, response) = response.into_parts();
+    let (response, body) = response.into_parts();
+    let bytes = actix_web::body::to_bytes(body)
+        .await
+        .map_err(error::ErrorInternalServerError)?;
+    let mut value = serde_json::from_slice::<Value>(&bytes).unwrap_or_default();
+    let changed = edit(&mut value);
+    let mut response = if changed {
+        response.set_body(BoxBody::new(value.to_string()))
+    } else {
+        response.set_body(BoxBody::new(bytes))
+    };
+    response.headers_mut().remove(header::CONTENT_LENGTH);
+    Ok((ServiceResponse::new(req, response), changed))
+}
+
+/// Turns a body, query or path that failed to deserialize into the usual
+/// JSON error, rather than actix's plain text one.
+pub fn extractor_error<E: fmt::Display>(err: E, _req: &HttpRequest) -> actix_web::Error {
+    ApiError::bad_request(err.to_string()).into()
+}
+
+/// Turns a path that failed to parse into a 400. Only todo ids are parsed
+/// from API paths, as UUIDs, so one of those was malformed.
+pub fn path_error(_err: PathError, _req: &HttpRequest) -> actix_web::Error {
+    ApiError::new(
+        StatusCode::BAD_REQUEST,
+        ErrorCode::InvalidId,
+        i18n::message("todo-id-invalid"),
+    )
+    .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::body::to_bytes;
+
+    #[actix_web::test]
+    async fn test_renders_code_and_details() {
+        let response = ApiError::not_found("Webhook not found").into_response();
+        assert_eq!(response.status(), 404);
+        let attached = response.error().and_then(|e| e.as_error::<ApiError>());
+        assert_eq!(attached.map(ApiError::code), Some(ErrorCode::NotFound));
+        let body = to_bytes(response.into_body()).await.unwrap();
+        let body: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["error"], "Webhook not found");
+        assert_eq!(body["code"], "NOT_FOUND");
+
+        let response = ApiError::new(StatusCode::NOT_FOUND, ErrorCode::TodoNotFound, "gone")
+            .with("suggestions", ["a"])
+            .into_response();
+        let body = to_bytes(response.into_body()).await.unwrap();
+        let body: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["code"], "TODO_NOT_FOUND");
+        assert_eq!(body["suggestions"][0], "a");
+    }
+}
@@ -0,0 +1,122 @@
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::Deserialize;
+use spicy_todo_core::events::{Event, EventFilter, EventType};
+use spicy_todo_core::models::{Priority, Todo};
+use spicy_todo_core::TodoService;
+
+pub const CONTENT_TYPE: &str = "application/atom+xml; charset=utf-8";
+pub const DEFAULT_ENTRIES: usize = 50;
+pub const MAX_ENTRIES: usize = 200;
+
+/// Query of `GET /api/todos/feed.atom`.
+#[derive(Debug, Default, Deserialize)]
+pub struct FeedQuery {
+    /// The `FEED_TOKEN`, in the URL since feed readers can't send headers.
+    pub token: Option<String>,
+    pub limit: Option<usize>,
+}
+
+/// The most recent additions and completions, newest first.
+pub fn activity(service: &TodoService, limit: usize) -> Vec<Event> {
+    let filter = EventFilter {
+        event_types: vec![EventType::Created, EventType::Completed],
+        ..EventFilter::default()
+    };
+    let mut events = service.events().search(&filter);
+    events.reverse();
+    events.truncate(limit);
+    events
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn timestamp(timestamp: DateTime<Utc>) -> String {
+    timestamp.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+/// What the entry says about the todo besides its text.
+fn summary(todo: &Todo) -> String {
+    let mut parts = vec![format!(
+        "Priority {}",
+        match todo.priority {
+            Priority::Low => "low",
+            Priority::Medium => "medium",
+            Priority::High => "high",
+        }
+    )];
+    if let Some(due_date) = &todo.due_date {
+        match &todo.reminder_time {
+            Some(time) => parts.push(format!("due {} {}", due_date, time)),
+            None => parts.push(format!("due {}", due_date)),
+        }
+    }
+    parts.join(", ")
+}
+
+fn entry(event: &Event) -> String {
+    let (verb, term) = match event.event_type {
+        EventType::Completed => ("Completed", "completed"),
+        _ => ("Added", "created"),
+    };
+    format!(
+        "<entry><id>urn:uuid:{}</id><title>{}: {}</title><updated>{}</updated>\
+         <author><name>{}</name></author><category term=\"{}\"/>\
+         <content type=\"text\">{}</content></entry>",
+        escape(&event.id),
+        verb,
+        escape(event.todo.text.trim()),
+        timestamp(event.timestamp),
+        escape(event.actor.as_deref().unwrap_or("Spicy Todo")),
+        term,
+        escape(&summary(&event.todo))
+    )
+}
+
+/// An Atom feed of `events`, served from `url`.
+pub fn atom(events: &[Event], url: &str, updated: DateTime<Utc>) -> String {
+    let entries: String = events.iter().map(entry).collect();
+    format!(
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n\
+         <feed xmlns=\"http://www.w3.org/2005/Atom\"><id>{url}</id>\
+         <title>Spicy Todo activity</title><updated>{}</updated>\
+         <link rel=\"self\" href=\"{url}\"/><generator>Spicy Todo</generator>{}</feed>\n",
+        timestamp(updated),
+        entries,
+        url = escape(url)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spicy_todo_core::models::TodoCreate;
+
+    #[test]
+    fn test_feed_lists_additions_and_completions() {
+        let service = TodoService::new_empty();
+        let rent = service.create(TodoCreate {
+            text: "Pay rent & bills".to_string(),
+            priority: Some(Priority::High),
+            due_date: Some("2024-06-10".to_string()),
+            ..Default::default()
+        });
+        service.toggle(rent.id);
+        service.toggle(rent.id);
+
+        let events = activity(&service, 10);
+        let types: Vec<EventType> = events.iter().map(|event| event.event_type).collect();
+        assert_eq!(types, vec![EventType::Completed, EventType::Created]);
+        assert_eq!(activity(&service, 1).len(), 1);
+
+        let feed = atom(&events, "http://localhost/api/todos/feed.atom", Utc::now());
+        assert!(roxmltree::Document::parse(&feed).is_ok());
+        assert!(feed.contains("<title>Completed: Pay rent &amp; bills</title>"));
+        assert!(feed.contains("<content type=\"text\">Priority high, due 2024-06-10</content>"));
+    }
+}
@@ -0,0 +1,193 @@
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use spicy_todo_core::geo;
+use spicy_todo_core::models::{self, Todo};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+use uuid::Uuid;
+
+/// Most of a reported `accuracy` that counts towards the geofence radius,
+/// so a vague fix can't trigger reminders from across town.
+const MAX_ACCURACY_METERS: f64 = 500.0;
+
+/// Body of `POST /api/todos/{id}/reminders/geo-trigger`: where the device
+/// was when it entered the todo's geofence.
+#[derive(Debug, Deserialize)]
+pub struct GeoTrigger {
+    pub lat: f64,
+    pub lng: f64,
+    /// Horizontal accuracy of the fix in meters, as reported by the device.
+    pub accuracy: Option<f64>,
+}
+
+/// Why a geo-trigger was refused.
+#[derive(Debug, PartialEq)]
+pub enum TriggerError {
+    Invalid(String),
+    Completed,
+    NoCoordinates,
+    /// The reported position is this many meters from the todo, farther
+    /// than the geofence allows.
+    Outside(f64),
+}
+
+impl std::fmt::Display for TriggerError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TriggerError::Invalid(e) => write!(f, "{}", e),
+            TriggerError::Completed => write!(f, "Todo is already completed"),
+            TriggerError::NoCoordinates => write!(f, "Todo has no location coordinates"),
+            TriggerError::Outside(distance) => write!(
+                f,
+                "Reported position is {:.0} meters from the todo, outside its geofence",
+                distance
+            ),
+        }
+    }
+}
+
+/// Checks that `trigger` places the device inside `todo`'s geofence of
+/// `radius_meters`, returning the distance.
+pub fn check(todo: &Todo, trigger: &GeoTrigger, radius_meters: f64) -> Result<f64, TriggerError> {
+    models::validate_coordinates(trigger.lat, trigger.lng).map_err(TriggerError::Invalid)?;
+    let accuracy = trigger.accuracy.unwrap_or(0.0);
+    if accuracy < 0.0 {
+        return Err(TriggerError::Invalid(
+            "accuracy must not be negative".to_string(),
+        ));
+    }
+    if todo.completed {
+        return Err(TriggerError::Completed);
+    }
+    let point = todo
+        .location
+        .as_ref()
+        .and_then(|location| location.coordinates())
+        .ok_or(TriggerError::NoCoordinates)?;
+    let distance = geo::distance_meters((trigger.lat, trigger.lng), point);
+    if distance > radius_meters + accuracy.min(MAX_ACCURACY_METERS) {
+        return Err(TriggerError::Outside(distance));
+    }
+    Ok(distance)
+}
+
+/// When each todo's location reminder last went out, so a device
+/// bouncing on a geofence edge doesn't send it over and over.
+pub struct GeofenceLog {
+    cooldown: Duration,
+    sent: Mutex<HashMap<Uuid, DateTime<Utc>>>,
+}
+
+impl GeofenceLog {
+    pub fn new(cooldown: Duration) -> Self {
+        GeofenceLog {
+            cooldown,
+            sent: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Records a reminder for `id` at `now` unless one went out within the
+    /// cooldown, in which case returns when the next may be sent.
+    pub fn claim(&self, id: Uuid, now: DateTime<Utc>) -> Result<(), DateTime<Utc>> {
+        let mut sent = self.sent.lock().unwrap();
+        let cooldown = chrono::Duration::from_std(self.cooldown).unwrap_or(chrono::Duration::MAX);
+        if let Some(last) = sent.get(&id) {
+            let next = last
+                .checked_add_signed(cooldown)
+                .unwrap_or(DateTime::<Utc>::MAX_UTC);
+            if now < next {
+                return Err(next);
+            }
+        }
+        sent.insert(id, now);
+        Ok(())
+    }
+
+    /// Gives back the claim made on `id` at `claimed_at`, when the reminder
+    /// it was for reached no one, so the next trigger may try again.
+    pub fn release(&self, id: Uuid, claimed_at: DateTime<Utc>) {
+        let mut sent = self.sent.lock().unwrap();
+        if sent.get(&id) == Some(&claimed_at) {
+            sent.remove(&id);
+        }
+    }
+}
+
+/// The reminder text for a todo whose geofence was entered.
+pub fn message(todo: &Todo) -> String {
+    match todo
+        .location
+        .as_ref()
+        .and_then(|location| location.name.as_deref())
+    {
+        Some(name) => format!("Reminder: {} (you are near {})", todo.text.trim(), name),
+        None => format!("Reminder: {} (you are nearby)", todo.text.trim()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spicy_todo_core::models::{Location, TodoCreate};
+    use spicy_todo_core::TodoService;
+
+    fn trigger(lat: f64, lng: f64, accuracy: Option<f64>) -> GeoTrigger {
+        GeoTrigger { lat, lng, accuracy }
+    }
+
+    #[test]
+    fn test_check_geofence() {
+        let service = TodoService::new_empty();
+        let todo = service.create(TodoCreate {
+            text: "Buy bread".to_string(),
+            location: Some(Location {
+                name: Some("Bakery".to_string()),
+                lat: Some(48.8570),
+                lng: Some(2.3530),
+            }),
+            ..Default::default()
+        });
+        assert_eq!(message(&todo), "Reminder: Buy bread (you are near Bakery)");
+
+        assert!(check(&todo, &trigger(48.8571, 2.3531, None), 150.0).unwrap() < 20.0);
+        // About 250 m away: outside, unless the fix is vague enough.
+        let far = trigger(48.8570, 2.3564, None);
+        assert!(matches!(
+            check(&todo, &far, 150.0),
+            Err(TriggerError::Outside(_))
+        ));
+        assert!(check(&todo, &trigger(48.8570, 2.3564, Some(120.0)), 150.0).is_ok());
+        assert!(matches!(
+            check(&todo, &trigger(48.8570, 2.3530, Some(-1.0)), 150.0),
+            Err(TriggerError::Invalid(_))
+        ));
+
+        let done = service.toggle(todo.id).unwrap();
+        assert_eq!(
+            check(&done, &trigger(48.8570, 2.3530, None), 150.0),
+            Err(TriggerError::Completed)
+        );
+    }
+
+    #[test]
+    fn test_claim_respects_cooldown() {
+        let log = GeofenceLog::new(Duration::from_secs(3600));
+        let (a, b) = (models::todo_id("a"), models::todo_id("b"));
+        let now = Utc::now();
+        assert!(log.claim(a, now).is_ok());
+        assert!(log.claim(b, now).is_ok());
+        let next = log
+            .claim(a, now + chrono::Duration::minutes(30))
+            .unwrap_err();
+        assert_eq!(next, now + chrono::Duration::hours(1));
+        assert!(log.claim(a, next).is_ok());
+
+        // A released claim frees the todo, unless another took its place.
+        log.release(a, next);
+        assert!(log.claim(a, next).is_ok());
+        let later = now + chrono::Duration::minutes(30);
+        log.release(b, later);
+        assert!(log.claim(b, later).is_err());
+    }
+}
@@ -0,0 +1,242 @@
+use crate::metrics::MetricNames;
+use serde_json::{json, Value};
+
+/// Name of the datasource input Grafana prompts for on import.
+const DATASOURCE_INPUT: &str = "DS_PROMETHEUS";
+
+const PANEL_WIDTH: u32 = 12;
+const PANEL_HEIGHT: u32 = 8;
+
+/// A timeseries panel: a title, a unit and the PromQL queries it plots as
+/// `(expr, legend)` pairs.
+struct Panel {
+    title: &'static str,
+    unit: &'static str,
+    targets: Vec<(String, &'static str)>,
+}
+
+/// Builds an importable Grafana dashboard whose queries use the metric names
+/// this build actually exports.
+pub fn dashboard(names: &MetricNames) -> Value {
+    let panels: Vec<Value> = panels(names)
+        .into_iter()
+        .enumerate()
+        .map(|(index, panel)| render_panel(index, panel))
+        .collect();
+
+    json!({
+        "__inputs": [{
+            "name": DATASOURCE_INPUT,
+            "label": "Prometheus",
+            "type": "datasource",
+            "pluginId": "prometheus",
+            "pluginName": "Prometheus"
+        }],
+        "uid": "spicy-todo",
+        "title": "Spicy Todo API",
+        "tags": ["spicy-todo"],
+        "timezone": "browser",
+        "schemaVersion": 39,
+        "version": 1,
+        "refresh": "30s",
+        "time": { "from": "now-6h", "to": "now" },
+        "templating": { "list": [] },
+        "panels": panels
+    })
+}
+
+fn panels(names: &MetricNames) -> Vec<Panel> {
+    vec![
+        Panel {
+            title: "Request rate by route",
+            unit: "reqps",
+            targets: vec![(
+                format!("sum by (route) (rate({}[5m]))", names.http_requests),
+                "{{route}}",
+            )],
+        },
+        Panel {
+            title: "Error rate (5xx)",
+            unit: "reqps",
+            targets: vec![(
+                format!(
+                    "sum by (route) (rate({}{{status=~\"5..\"}}[5m]))",
+                    names.http_requests
+                ),
+                "{{route}}",
+            )],
+        },
+        Panel {
+            title: "Request latency p95",
+            unit: "s",
+            targets: vec![(
+                format!(
+                    "histogram_quantile(0.95, sum by (le, route) (rate({}_bucket[5m])))",
+                    names.http_request_duration
+                ),
+                "{{route}}",
+            )],
+        },
+        Panel {
+            title: "Todos by state",
+            unit: "short",
+            targets: vec![(format!("sum by (state) ({})", names.todos), "{{state}}")],
+        },
+        Panel {
+            title: "Domain events by type",
+            unit: "ops",
+            targets: vec![(
+                format!("sum by (type) (rate({}[5m]))", names.domain_events),
+                "{{type}}",
+            )],
+        },
+        Panel {
+            title: "Todos created vs completed (per hour)",
+            unit: "short",
+            targets: vec![
+                (format!("increase({}[1h])", names.todos_created), "created"),
+                (
+                    format!("increase({}[1h])", names.todos_completed),
+                    "completed",
+                ),
+            ],
+        },
+        Panel {
+            title: "Completion latency",
+            unit: "s",
+            targets: vec![
+                (
+                    format!(
+                        "histogram_quantile(0.5, sum by (le) (rate({}_bucket[1h])))",
+                        names.completion_latency
+                    ),
+                    "p50",
+                ),
+                (
+                    format!(
+                        "histogram_quantile(0.9, sum by (le) (rate({}_bucket[1h])))",
+                        names.completion_latency
+                    ),
+                    "p90",
+                ),
+            ],
+        },
+        Panel {
+            title: "Webhook deliveries by outcome",
+            unit: "ops",
+            targets: vec![(
+                format!("sum by (outcome) (rate({}[5m]))", names.webhook_deliveries),
+                "{{outcome}}",
+            )],
+        },
+        Panel {
+            title: "Background job runs by outcome",
+            unit: "ops",
+            targets: vec![(
+                format!("sum by (job, outcome) (rate({}[1h]))", names.job_runs),
+                "{{job}} {{outcome}}",
+            )],
+        },
+        Panel {
+            title: "Background job duration p95",
+            unit: "s",
+            targets: vec![(
+                format!(
+                    "histogram_quantile(0.95, sum by (le, job) (rate({}_bucket[1h])))",
+                    names.job_duration
+                ),
+                "{{job}}",
+            )],
+        },
+    ]
+}
+
+fn render_panel(index: usize, panel: Panel) -> Value {
+    let datasource = json!({ "type": "prometheus", "uid": format!("${{{}}}", DATASOURCE_INPUT) });
+    let targets: Vec<Value> = panel
+        .targets
+        .into_iter()
+        .enumerate()
+        .map(|(i, (expr, legend))| {
+            json!({
+                "refId": ((b'A' + i as u8) as char).to_string(),
+                "datasource": datasource,
+                "expr": expr,
+                "legendFormat": legend
+            })
+        })
+        .collect();
+
+    let index = index as u32;
+    json!({
+        "id": index + 1,
+        "type": "timeseries",
+        "title": panel.title,
+        "datasource": datasource,
+        "gridPos": {
+            "x": (index % 2) * PANEL_WIDTH,
+            "y": (index / 2) * PANEL_HEIGHT,
+            "w": PANEL_WIDTH,
+            "h": PANEL_HEIGHT
+        },
+        "fieldConfig": { "defaults": { "unit": panel.unit }, "overrides": [] },
+        "targets": targets
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::Metrics;
+    use spicy_todo_core::service::TodoService;
+
+    #[test]
+    fn test_queries_reference_exported_metrics() {
+        let service = TodoService::new_empty();
+        let mut events = service.events().subscribe();
+        service.seed(spicy_todo_core::fixtures::builtin("demo").unwrap());
+
+        let metrics = Metrics::new();
+        metrics.observe_event(&events.try_recv().unwrap());
+        metrics.observe_request("GET", "/api/todos", 200, 0.01);
+        metrics.observe_webhook_delivery(true);
+        metrics.observe_job("snapshot", "done", 0.01);
+        let exposition = metrics.render(&service.get_stats());
+
+        let names = metrics.names();
+        let dashboard = dashboard(&names);
+        let panels = dashboard["panels"].as_array().unwrap();
+        assert_eq!(panels.len(), 10);
+
+        let all_names = [
+            &names.http_requests,
+            &names.http_request_duration,
+            &names.domain_events,
+            &names.todos_created,
+            &names.todos_completed,
+            &names.completion_latency,
+            &names.webhook_deliveries,
+            &names.todos,
+            &names.job_runs,
+            &names.job_duration,
+        ];
+        for name in all_names {
+            assert!(name.starts_with("spicy_todo_"));
+            assert!(
+                exposition.contains(&format!("# TYPE {} ", name)),
+                "{} is not exported",
+                name
+            );
+            let referenced = panels.iter().any(|panel| {
+                panel["targets"].as_array().unwrap().iter().any(|target| {
+                    let expr = target["expr"].as_str().unwrap();
+                    expr.contains(&format!("({}[", name))
+                        || expr.contains(&format!("({}{{", name))
+                        || expr.contains(&format!("({}_bucket[", name))
+                        || expr.contains(&format!("({})", name))
+                })
+            });
+            assert!(referenced, "{} has no panel", name);
+        }
+    }
+}
@@ -0,0 +1,3331 @@
+use crate::actions::{self, ActionsQuery};
+use crate::auth::{self, Auth, AuthMode, LoginRequest};
+use crate::backups::{BackupError, Backups};
+use crate::bulk_edits::{ApplyRequest, BulkEditPreviews};
+use crate::caldav::{self, Multistatus, Report, Resource};
+use crate::casing::{self, Cased};
+use crate::config::Config;
+use crate::conformance;
+use crate::deadlines;
+use crate::demo::{self, Demo};
+use crate::diagnostics::RuntimeRegistry;
+use crate::email::{EmailIngest, IngestError, Ingested, MailgunEmail};
+use crate::errors::ApiError;
+use crate::feed::{self, FeedQuery};
+use crate::geofence::{self, GeoTrigger, GeofenceLog, TriggerError};
+use crate::grafana;
+use crate::health::{self, ComponentHealth};
+use crate::homeassistant::{self, AddTodoData, CompleteTodoData, LookupError, Sensor};
+use crate::i18n;
+use crate::importer::{self, ImportQuery, ImportReport, Rejected};
+use crate::mcp::{self, McpSessions};
+use crate::metrics::Metrics;
+use crate::moderation::{Moderation, ModerationError};
+use crate::my_day::MyDayStore;
+use crate::ndjson;
+use crate::notifiers::{NotifierCreate, NotifierService};
+use crate::oidc::{self, LoginError, PendingLogin};
+use crate::plugins::{PluginRegistry, PluginsQuery};
+use crate::policies::{PolicyCreate, PolicyStore};
+use crate::preferences::{self, ListPreference, PreferenceStore, UserPreferences};
+use crate::profiling::{self, CaptureError, ProfileFormat, ProfileQuery};
+use crate::push::{self, Notification, PushService, PushSubscription};
+use crate::reminders::Channels;
+use crate::scripts::{ScriptCreate, ScriptService};
+use crate::sms::{SmsError, SmsService, SubscribeRequest, VerifyRequest};
+use crate::suggestions::Suggester;
+use crate::transfer::{self, TransferRequest};
+use crate::views::{ViewCreate, ViewStore};
+use crate::webhooks::{WebhookCreate, WebhookService};
+use crate::webpush::{WebPushService, WebPushSubscription};
+use spicy_todo_core::bulk_edit::{self, BulkEditOutcome, BulkEditRequest};
+use spicy_todo_core::bundle::{BundleError, TodoBundle};
+use spicy_todo_core::context::RequestContext;
+use spicy_todo_core::dashboard::{self, DashboardQuery};
+use spicy_todo_core::dates;
+use spicy_todo_core::digest::{self, Agenda, PlainTextOptions};
+use spicy_todo_core::events::{EventCursor, EventFilter, EventType};
+use spicy_todo_core::fixtures;
+use spicy_todo_core::ical::{self, Precondition, PutOutcome};
+use spicy_todo_core::locale::Locale;
+use spicy_todo_core::models::{
+    self, ActivityQuery, AuditQuery, ChangesQuery, Color, CompleteQuery, Cursor, DigestQuery,
+    ErrorCode, EventLogQuery, GenerateQuery, Icon, ListMeta, NearbyQuery, Page, QuickAddRequest,
+    ReplayQuery, SeedRequest, SnoozeRequest, SortField, StatsQuery, TodoCreate, TodoPage,
+    TodoQuery, TodoUpdate,
+};
+use spicy_todo_core::plan::{self, PlanOptions, PlanQuery};
+use spicy_todo_core::query::Query;
+use spicy_todo_core::quick_add;
+use spicy_todo_core::report::{ReportFormat, ReportPeriod, ReportQuery};
+use spicy_todo_core::service::TodoService;
+use spicy_todo_core::snooze::Snooze;
+use spicy_todo_core::sync::{SyncError, SyncErrorCode, SyncRequest};
+use actix_session::Session;
+use actix_web::http::header::{
+    self, ETag, EntityTag, HeaderValue, HttpDate, IfModifiedSince, IfNoneMatch, LastModified,
+};
+use actix_web::http::StatusCode;
+use actix_web::{web, HttpMessage, HttpRequest, HttpResponse, HttpResponseBuilder, Responder};
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::Serialize;
+use std::time::{Duration, SystemTime};
+use uuid::Uuid;
+
+pub async fn root(req: HttpRequest) -> impl Responder {
+    let mut body = serde_json::json!({
+        "message": "🌶️ Spicy Todo API - Rust/Actix Implementation",
+        "version": "1.0.0",
+        "docs": "/api/todos"
+    });
+    // Clients show the banner so playground visitors know what they're on.
+    let demo = req.app_data::<web::Data<Config>>().and_then(|config| config.demo.clone());
+    if let Some(demo) = demo {
+        body["banner"] = serde_json::json!(demo.banner);
+        body["demo"] = serde_json::json!({
+            "resetEveryMinutes": demo.reset_interval.as_secs() / 60,
+            "maxTodos": demo.max_todos,
+            "writesPerMinute": demo.writes_per_minute
+        });
+    }
+    HttpResponse::Ok().json(Cased(body))
+}
+
+/// Budget for each readiness check before the component counts as down.
+const READINESS_CHECK_TIMEOUT: Duration = Duration::from_millis(500);
+
+pub async fn health() -> impl Responder {
+    HttpResponse::Ok().json(Cased(serde_json::json!({
+        "status": "healthy",
+        "service": "spicy-todo-rust-api",
+        "startedAt": health::started_at().to_rfc3339(),
+        "uptime": health::uptime().as_secs()
+    })))
+}
+
+/// Liveness: the process is up and serving requests. Deliberately checks no
+/// dependencies so a slow backend doesn't get the container restarted.
+pub async fn health_live() -> impl Responder {
+    health().await
+}
+
+/// Readiness: verifies the storage backend and event log are usable and
+/// returns 503 with per-component details when any of them is not.
+pub async fn health_ready(service: web::Data<TodoService>) -> impl Responder {
+    let storage = ComponentHealth::probe(|| service.check_storage(READINESS_CHECK_TIMEOUT));
+    let event_log = ComponentHealth::probe(|| service.events().check(READINESS_CHECK_TIMEOUT));
+    let ready = storage.is_up() && event_log.is_up();
+
+    let body = serde_json::json!({
+        "status": if ready { "ready" } else { "degraded" },
+        "service": "spicy-todo-rust-api",
+        "startedAt": health::started_at().to_rfc3339(),
+        "uptime": health::uptime().as_secs(),
+        "components": {
+            "storage": storage,
+            "eventLog": event_log
+        }
+    });
+
+    if ready {
+        HttpResponse::Ok().json(Cased(body))
+    } else {
+        HttpResponse::ServiceUnavailable().json(Cased(body))
+    }
+}
+
+pub async fn get_todos(
+    req: HttpRequest,
+    service: web::Data<TodoService>,
+    query: web::Query<TodoQuery>,
+) -> impl Responder {
+    let mut query = query.into_inner();
+    let preference_applied = match saved_preference(&req) {
+        Ok(Some(preference)) if !query.has_view_params() => {
+            preference.apply_to(&mut query);
+            true
+        }
+        Ok(_) => false,
+        Err(e) => return ApiError::bad_request(e).into_response(),
+    };
+    let cursor = match list_cursor(&mut query) {
+        Ok(cursor) => cursor,
+        Err(e) => return ApiError::bad_request(e).into_response(),
+    };
+
+    let q = match query.q.as_deref().map(Query::parse).transpose() {
+        Ok(q) => q,
+        Err(e) => return ApiError::bad_request(e).into_response(),
+    };
+    let color = match query.color.as_deref().map(str::parse::<Color>).transpose() {
+        Ok(color) => color,
+        Err(e) => return ApiError::bad_request(e).into_response(),
+    };
+    let icon = match query.icon.as_deref().map(str::parse::<Icon>).transpose() {
+        Ok(icon) => icon,
+        Err(e) => return ApiError::bad_request(e).into_response(),
+    };
+
+    let today = client_today(user_preferences(&req).as_ref());
+    let version = service.collection_version();
+    // The same view differs by format and key case, and from day to day
+    // when it asks for overdue todos or counts them in its meta.
+    let date_relative = query.is_paginated() || q.as_ref().is_some_and(Query::is_date_relative);
+    let variant = (
+        (&query.filter, &query.search, &query.priority, &query.q),
+        (color, icon),
+        (query.sort, query.order, query.limit, query.offset),
+        &query.cursor,
+        (accepts_msgpack(&req), casing::requested(&req).ok()),
+        date_relative.then_some(today),
+    );
+    let etag = EntityTag::new_strong(version.etag(&variant));
+    let last_modified = http_date(version.last_modified);
+    let list_headers = |builder: &mut HttpResponseBuilder| {
+        builder
+            .insert_header(ETag(etag.clone()))
+            .insert_header(LastModified(last_modified));
+        if preference_applied {
+            builder.insert_header((preferences::APPLIED_HEADER, "applied"));
+        }
+    };
+
+    if is_not_modified(&req, &etag, last_modified) {
+        let mut builder = HttpResponse::NotModified();
+        list_headers(&mut builder);
+        return builder.finish();
+    }
+
+    let deadline = match deadlines::from_request(&req) {
+        Ok(deadline) => deadline,
+        Err(e) => return ApiError::bad_request(e).into_response(),
+    };
+    // Shared with the store: the list is only sorted, paged and serialized.
+    let todos = match &q {
+        Some(q) => service.get_matching_shared_until(
+            query.filter.clone(),
+            query.search.clone(),
+            query.priority.clone(),
+            q,
+            today,
+            &deadline,
+        ),
+        None => service.get_all_shared_until(
+            query.filter.clone(),
+            query.search.clone(),
+            query.priority.clone(),
+            &deadline,
+        ),
+    };
+    let mut todos = match todos {
+        Ok(todos) => todos,
+        Err(exceeded) => return deadlines::exceeded_response(exceeded),
+    };
+    todos.retain(|todo| {
+        color.is_none_or(|color| todo.color == Some(color))
+            && icon.is_none_or(|icon| todo.icon == Some(icon))
+    });
+    if let Some(sort) = query.sort {
+        sort.sort(&mut todos, query.order.unwrap_or_default());
+    }
+    let mut builder = HttpResponse::Ok();
+    list_headers(&mut builder);
+    if !query.is_paginated() {
+        return negotiated(&req, builder, &todos);
+    }
+
+    let meta = ListMeta::from_todos(&todos, today);
+    let page = match (query.cursor.is_some(), query.sort) {
+        (true, Some(sort)) => Page::after_cursor(
+            todos,
+            sort,
+            query.order.unwrap_or_default(),
+            cursor.as_ref(),
+            query.limit,
+        ),
+        _ => Page::from_vec(todos, query.limit, query.offset),
+    };
+    negotiated(&req, builder, &TodoPage { page, meta })
+}
+
+/// Checks a list query's `cursor` against the rest of it. Paging by cursor
+/// needs a fixed order, so it sorts by creation unless told otherwise, and
+/// a cursor keeps the sort it was issued for.
+fn list_cursor(query: &mut TodoQuery) -> Result<Option<Cursor>, String> {
+    let Some(encoded) = query.cursor.as_deref() else {
+        return Ok(None);
+    };
+    if query.offset.is_some() {
+        return Err(i18n::message("cursor-with-offset").to_string());
+    }
+    let cursor = match encoded {
+        "" => None,
+        encoded => Some(Cursor::decode(encoded).map_err(|_| i18n::message("cursor-invalid"))?),
+    };
+    let sort = query.sort.or(cursor.as_ref().map(|c| c.sort));
+    let order = query.order.or(cursor.as_ref().map(|c| c.order));
+    query.sort = Some(sort.unwrap_or(SortField::CreatedAt));
+    query.order = Some(order.unwrap_or_default());
+    if let Some(cursor) = &cursor {
+        if query.sort != Some(cursor.sort) || query.order != Some(cursor.order) {
+            return Err(i18n::message("cursor-sort-mismatch").to_string());
+        }
+    }
+    Ok(cursor)
+}
+
+/// The requesting client's saved list preference, if it sent `X-Client-Id`
+/// and preferences are enabled.
+fn saved_preference(req: &HttpRequest) -> Result<Option<ListPreference>, String> {
+    let store = match req.app_data::<web::Data<PreferenceStore>>() {
+        Some(store) => store,
+        None => return Ok(None),
+    };
+    Ok(preferences::client_id(req)?
+        .and_then(|client| store.get(&client))
+        .map(|saved| saved.preference))
+}
+
+/// The requesting client's saved defaults, if it sent `X-Client-Id` and
+/// saved any.
+fn user_preferences(req: &HttpRequest) -> Option<UserPreferences> {
+    let store = req.app_data::<web::Data<PreferenceStore>>()?;
+    let client = preferences::client_id(req).ok()??;
+    let saved = store.user(&client);
+    saved.updated_at.map(|_| saved.preferences)
+}
+
+/// Today in the client's time zone, or the server's without preferences.
+fn client_today(preferences: Option<&UserPreferences>) -> NaiveDate {
+    let now = Utc::now();
+    preferences.map_or_else(|| now.date_naive(), |preferences| preferences.today(now))
+}
+
+fn required_client_id(req: &HttpRequest) -> Result<String, ApiError> {
+    preferences::client_id(req)
+        .map_err(ApiError::bad_request)?
+        .ok_or_else(client_id_required)
+}
+
+fn client_id_required() -> ApiError {
+    ApiError::new(
+        StatusCode::BAD_REQUEST,
+        ErrorCode::ClientIdRequired,
+        i18n::message_with(
+            "client-header-required",
+            [("header", preferences::CLIENT_HEADER.into())],
+        ),
+    )
+}
+
+pub async fn get_preference(
+    req: HttpRequest,
+    preferences: web::Data<PreferenceStore>,
+) -> impl Responder {
+    let client = match required_client_id(&req) {
+        Ok(client) => client,
+        Err(e) => return e.into_response(),
+    };
+    match preferences.get(&client) {
+        Some(saved) => HttpResponse::Ok().json(Cased(saved)),
+        None => ApiError::not_found(i18n::message("preference-not-found")).into_response(),
+    }
+}
+
+pub async fn put_preference(
+    req: HttpRequest,
+    preferences: web::Data<PreferenceStore>,
+    preference: casing::Json<ListPreference>,
+) -> impl Responder {
+    let client = match required_client_id(&req) {
+        Ok(client) => client,
+        Err(e) => return e.into_response(),
+    };
+    let preference = preference.into_inner();
+    if let Err(e) = preference.validate() {
+        return ApiError::bad_request(e).into_response();
+    }
+    HttpResponse::Ok().json(Cased(preferences.set(&client, preference)))
+}
+
+pub async fn delete_preference(
+    req: HttpRequest,
+    preferences: web::Data<PreferenceStore>,
+) -> impl Responder {
+    let client = match required_client_id(&req) {
+        Ok(client) => client,
+        Err(e) => return e.into_response(),
+    };
+    if preferences.remove(&client) {
+        HttpResponse::NoContent().finish()
+    } else {
+        ApiError::not_found(i18n::message("preference-not-found")).into_response()
+    }
+}
+
+pub async fn get_user_preferences(
+    req: HttpRequest,
+    preferences: web::Data<PreferenceStore>,
+) -> impl Responder {
+    match required_client_id(&req) {
+        Ok(client) => HttpResponse::Ok().json(Cased(preferences.user(&client))),
+        Err(e) => e.into_response(),
+    }
+}
+
+pub async fn put_user_preferences(
+    req: HttpRequest,
+    preferences: web::Data<PreferenceStore>,
+    user_preferences: casing::Json<UserPreferences>,
+) -> impl Responder {
+    let client = match required_client_id(&req) {
+        Ok(client) => client,
+        Err(e) => return e.into_response(),
+    };
+    let user_preferences = user_preferences.into_inner();
+    if let Err(e) = user_preferences.validate() {
+        return ApiError::bad_request(e).into_response();
+    }
+    HttpResponse::Ok().json(Cased(preferences.set_user(&client, user_preferences)))
+}
+
+pub async fn get_push_subscription(
+    req: HttpRequest,
+    push: web::Data<PushService>,
+) -> impl Responder {
+    let client = match required_client_id(&req) {
+        Ok(client) => client,
+        Err(e) => return e.into_response(),
+    };
+    match push.get(&client) {
+        Some(subscription) => HttpResponse::Ok().json(Cased(subscription)),
+        None => ApiError::not_found(i18n::message("push-subscription-not-found")).into_response(),
+    }
+}
+
+pub async fn put_push_subscription(
+    req: HttpRequest,
+    push: web::Data<PushService>,
+    subscription: casing::Json<PushSubscription>,
+) -> impl Responder {
+    let client = match required_client_id(&req) {
+        Ok(client) => client,
+        Err(e) => return e.into_response(),
+    };
+    let subscription = subscription.into_inner();
+    if let Err(e) = subscription.validate() {
+        return ApiError::bad_request(e).into_response();
+    }
+    push.set(&client, subscription.clone());
+    HttpResponse::Ok().json(Cased(subscription))
+}
+
+pub async fn delete_push_subscription(
+    req: HttpRequest,
+    push: web::Data<PushService>,
+) -> impl Responder {
+    let client = match required_client_id(&req) {
+        Ok(client) => client,
+        Err(e) => return e.into_response(),
+    };
+    if push.remove(&client) {
+        HttpResponse::NoContent().finish()
+    } else {
+        ApiError::not_found(i18n::message("push-subscription-not-found")).into_response()
+    }
+}
+
+/// Sends a test notification to each of the client's targets, reporting
+/// per target whether it was accepted.
+pub async fn test_push_subscription(
+    req: HttpRequest,
+    push: web::Data<PushService>,
+) -> impl Responder {
+    let client = match required_client_id(&req) {
+        Ok(client) => client,
+        Err(e) => return e.into_response(),
+    };
+    let Some(subscription) = push.get(&client) else {
+        return ApiError::not_found(i18n::message("push-subscription-not-found")).into_response();
+    };
+    let notification = Notification {
+        title: "Spicy Todo".to_string(),
+        message: "Test notification: reminders will arrive here".to_string(),
+        priority: subscription.min_priority.clone(),
+    };
+    let mut results = Vec::new();
+    for target in &subscription.targets {
+        let result = push::deliver(target, &notification).await;
+        results.push(serde_json::json!({
+            "target": target,
+            "delivered": result.is_ok(),
+            "error": result.err()
+        }));
+    }
+    HttpResponse::Ok().json(Cased(serde_json::json!({ "results": results })))
+}
+
+fn sms_not_configured() -> HttpResponse {
+    ApiError::not_configured(i18n::message("sms-not-configured")).into_response()
+}
+
+fn sms_error_response(error: SmsError) -> HttpResponse {
+    let status = match error {
+        SmsError::InvalidPhone(_) | SmsError::WrongCode => StatusCode::BAD_REQUEST,
+        SmsError::NoPendingVerification => StatusCode::NOT_FOUND,
+        SmsError::CodeExpired => StatusCode::GONE,
+        SmsError::TooManyAttempts | SmsError::RateLimited { .. } => StatusCode::TOO_MANY_REQUESTS,
+        SmsError::Gateway(_) => StatusCode::BAD_GATEWAY,
+    };
+    let mut response = ApiError::status(status, error.to_string()).into_response();
+    if let SmsError::RateLimited { retry_after } = error {
+        let seconds = retry_after.num_seconds().max(1).to_string();
+        if let Ok(value) = HeaderValue::from_str(&seconds) {
+            response.headers_mut().insert(header::RETRY_AFTER, value);
+        }
+    }
+    response
+}
+
+pub async fn get_sms_subscription(req: HttpRequest) -> impl Responder {
+    let sms = match req.app_data::<web::Data<SmsService>>() {
+        Some(sms) => sms,
+        None => return sms_not_configured(),
+    };
+    let client = match required_client_id(&req) {
+        Ok(client) => client,
+        Err(e) => return e.into_response(),
+    };
+    match sms.subscription(&client) {
+        Some(subscription) => HttpResponse::Ok().json(Cased(subscription)),
+        None => ApiError::not_found(i18n::message("sms-subscription-not-found")).into_response(),
+    }
+}
+
+/// Registers the client's phone number and texts it a verification code.
+pub async fn put_sms_subscription(
+    req: HttpRequest,
+    body: casing::Json<SubscribeRequest>,
+) -> impl Responder {
+    let sms = match req.app_data::<web::Data<SmsService>>() {
+        Some(sms) => sms,
+        None => return sms_not_configured(),
+    };
+    let client = match required_client_id(&req) {
+        Ok(client) => client,
+        Err(e) => return e.into_response(),
+    };
+    match sms.subscribe(&client, &body.phone, Utc::now()).await {
+        Ok(()) => HttpResponse::Accepted().json(Cased(serde_json::json!({
+            "message": i18n::message("sms-code-sent"),
+            "subscription": sms.subscription(&client)
+        }))),
+        Err(e) => sms_error_response(e),
+    }
+}
+
+pub async fn verify_sms_subscription(
+    req: HttpRequest,
+    body: casing::Json<VerifyRequest>,
+) -> impl Responder {
+    let sms = match req.app_data::<web::Data<SmsService>>() {
+        Some(sms) => sms,
+        None => return sms_not_configured(),
+    };
+    let client = match required_client_id(&req) {
+        Ok(client) => client,
+        Err(e) => return e.into_response(),
+    };
+    match sms.verify(&client, &body.code, Utc::now()) {
+        Ok(()) => HttpResponse::Ok().json(Cased(sms.subscription(&client))),
+        Err(e) => sms_error_response(e),
+    }
+}
+
+pub async fn delete_sms_subscription(req: HttpRequest) -> impl Responder {
+    let sms = match req.app_data::<web::Data<SmsService>>() {
+        Some(sms) => sms,
+        None => return sms_not_configured(),
+    };
+    let client = match required_client_id(&req) {
+        Ok(client) => client,
+        Err(e) => return e.into_response(),
+    };
+    if sms.unsubscribe(&client) {
+        HttpResponse::NoContent().finish()
+    } else {
+        ApiError::not_found(i18n::message("sms-subscription-not-found")).into_response()
+    }
+}
+
+fn web_push_not_configured() -> HttpResponse {
+    ApiError::not_configured(i18n::message("web-push-not-configured")).into_response()
+}
+
+/// The key browsers pass as `applicationServerKey` when subscribing.
+pub async fn get_web_push_key(req: HttpRequest) -> impl Responder {
+    match req.app_data::<web::Data<WebPushService>>() {
+        Some(web_push) => HttpResponse::Ok().json(Cased(serde_json::json!({
+            "publicKey": web_push.public_key()
+        }))),
+        None => web_push_not_configured(),
+    }
+}
+
+/// Stores a browser's `PushSubscription` so reminders reach it while the
+/// tab is closed.
+pub async fn web_push_subscribe(
+    req: HttpRequest,
+    subscription: casing::Json<WebPushSubscription>,
+) -> impl Responder {
+    let web_push = match req.app_data::<web::Data<WebPushService>>() {
+        Some(web_push) => web_push,
+        None => return web_push_not_configured(),
+    };
+    let subscription = subscription.into_inner();
+    let endpoint = subscription.endpoint.clone();
+    match web_push.subscribe(subscription) {
+        Ok(true) => {
+            HttpResponse::Created().json(Cased(serde_json::json!({ "endpoint": endpoint })))
+        }
+        Ok(false) => HttpResponse::Ok().json(Cased(serde_json::json!({ "endpoint": endpoint }))),
+        Err(e) => ApiError::bad_request(e).into_response(),
+    }
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct UnsubscribeRequest {
+    pub endpoint: String,
+}
+
+pub async fn web_push_unsubscribe(
+    req: HttpRequest,
+    body: casing::Json<UnsubscribeRequest>,
+) -> impl Responder {
+    let web_push = match req.app_data::<web::Data<WebPushService>>() {
+        Some(web_push) => web_push,
+        None => return web_push_not_configured(),
+    };
+    if web_push.unsubscribe(&body.endpoint) {
+        HttpResponse::NoContent().finish()
+    } else {
+        ApiError::not_found(i18n::message("web-push-subscription-not-found")).into_response()
+    }
+}
+
+const MSGPACK_CONTENT_TYPE: &str = "application/msgpack";
+
+/// Serializes `body` as MessagePack when the client asks for it via
+/// `Accept: application/msgpack`, and as JSON otherwise.
+fn negotiated<T: Serialize>(
+    req: &HttpRequest,
+    mut builder: HttpResponseBuilder,
+    body: &T,
+) -> HttpResponse {
+    builder.insert_header((header::VARY, "Accept"));
+
+    if !accepts_msgpack(req) {
+        return builder.json(Cased(body));
+    }
+
+    match rmp_serde::to_vec_named(&Cased(body)) {
+        Ok(bytes) => builder.content_type(MSGPACK_CONTENT_TYPE).body(bytes),
+        Err(e) => ApiError::status(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            i18n::message_with("response-encode-failed", [("error", e.to_string().into())]),
+        )
+        .into_response(),
+    }
+}
+
+fn accepts_msgpack(req: &HttpRequest) -> bool {
+    req.get_header::<header::Accept>()
+        .map(|accept| {
+            accept.iter().any(|item| {
+                item.quality > header::Quality::ZERO
+                    && matches!(
+                        item.item.essence_str(),
+                        "application/msgpack" | "application/x-msgpack"
+                    )
+            })
+        })
+        .unwrap_or(false)
+}
+
+/// HTTP dates only carry whole seconds, so truncate before comparing.
+fn http_date(timestamp: DateTime<Utc>) -> HttpDate {
+    let secs = timestamp.timestamp().max(0) as u64;
+    HttpDate::from(SystemTime::UNIX_EPOCH + Duration::from_secs(secs))
+}
+
+/// Evaluates conditional request headers per RFC 9110: If-None-Match takes
+/// precedence, and If-Modified-Since is only consulted when it is absent.
+fn is_not_modified(req: &HttpRequest, etag: &EntityTag, last_modified: HttpDate) -> bool {
+    if let Some(if_none_match) = req.get_header::<IfNoneMatch>() {
+        return match if_none_match {
+            IfNoneMatch::Any => true,
+            IfNoneMatch::Items(tags) => tags.iter().any(|tag| tag.weak_eq(etag)),
+        };
+    }
+
+    match req.get_header::<IfModifiedSince>() {
+        Some(IfModifiedSince(since)) => {
+            SystemTime::from(last_modified) <= SystemTime::from(since)
+        }
+        None => false,
+    }
+}
+
+/// Recently added and completed todos as an Atom feed, for following a
+/// shared list in a feed reader. Requires `?token=` when `FEED_TOKEN` is set.
+pub async fn get_feed(
+    req: HttpRequest,
+    service: web::Data<TodoService>,
+    config: web::Data<Config>,
+    query: web::Query<FeedQuery>,
+) -> impl Responder {
+    if let Some(expected) = &config.feed_token {
+        let provided = query.token.as_deref().or_else(|| {
+            req.headers()
+                .get(header::AUTHORIZATION)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.strip_prefix("Bearer "))
+        });
+        if !provided.is_some_and(|token| constant_time_eq(token.as_bytes(), expected.as_bytes())) {
+            return ApiError::status(
+                StatusCode::UNAUTHORIZED,
+                i18n::message("feed-token-invalid"),
+            )
+            .into_response();
+        }
+    }
+    let limit = query.limit.unwrap_or(feed::DEFAULT_ENTRIES);
+    if !(1..=feed::MAX_ENTRIES).contains(&limit) {
+        return ApiError::bad_request(i18n::message_with(
+            "feed-limit-range",
+            [("max", feed::MAX_ENTRIES.into())],
+        ))
+        .into_response();
+    }
+
+    let version = service.collection_version();
+    let etag = EntityTag::new_strong(version.etag(&("feed", limit)));
+    let last_modified = http_date(version.last_modified);
+    if is_not_modified(&req, &etag, last_modified) {
+        return HttpResponse::NotModified()
+            .insert_header(ETag(etag))
+            .insert_header(LastModified(last_modified))
+            .finish();
+    }
+
+    // The token stays out of the feed's id and self link.
+    let info = req.connection_info();
+    let url = format!("{}://{}{}", info.scheme(), info.host(), req.path());
+    let events = feed::activity(&service, limit);
+    HttpResponse::Ok()
+        .content_type(feed::CONTENT_TYPE)
+        .insert_header(ETag(etag))
+        .insert_header(LastModified(last_modified))
+        .body(feed::atom(&events, &url, version.last_modified))
+}
+
+/// Largest radius `GET /api/todos/nearby` accepts: 100 km.
+const MAX_NEARBY_RADIUS_METERS: f64 = 100_000.0;
+
+/// Active todos with coordinates near a point, nearest first, each with its
+/// `distanceMeters`.
+pub async fn get_nearby_todos(
+    req: HttpRequest,
+    service: web::Data<TodoService>,
+    query: web::Query<NearbyQuery>,
+) -> impl Responder {
+    let radius = query.radius.unwrap_or(1_000.0);
+    if let Err(e) = models::validate_coordinates(query.lat, query.lng) {
+        return ApiError::bad_request(e).into_response();
+    }
+    if !(radius > 0.0 && radius <= MAX_NEARBY_RADIUS_METERS) {
+        return ApiError::bad_request(i18n::message_with(
+            "nearby-radius-range",
+            [("max", MAX_NEARBY_RADIUS_METERS.into())],
+        ))
+        .into_response();
+    }
+
+    let deadline = match deadlines::from_request(&req) {
+        Ok(deadline) => deadline,
+        Err(e) => return ApiError::bad_request(e).into_response(),
+    };
+    match service.nearby_until(query.lat, query.lng, radius, &deadline) {
+        Ok(todos) => HttpResponse::Ok().json(Cased(serde_json::json!({
+            "count": todos.len(),
+            "radiusMeters": radius,
+            "todos": todos
+        }))),
+        Err(exceeded) => deadlines::exceeded_response(exceeded),
+    }
+}
+
+pub async fn get_todo(
+    req: HttpRequest,
+    service: web::Data<TodoService>,
+    path: web::Path<Uuid>,
+) -> impl Responder {
+    let id = path.into_inner();
+    let deadline = match deadlines::from_request(&req) {
+        Ok(deadline) => deadline,
+        Err(e) => return ApiError::bad_request(e).into_response(),
+    };
+
+    match service.get_by_id_until(id, &deadline) {
+        Ok(Some(todo)) => negotiated(&req, HttpResponse::Ok(), &todo),
+        Ok(None) => todo_not_found(&req, &service, id),
+        Err(exceeded) => deadlines::exceeded_response(exceeded),
+    }
+}
+
+/// 404 for a missing todo. With `SUGGEST_MISSING_IDS` on, also lists close
+/// matches among existing and recently deleted ids.
+fn todo_not_found(req: &HttpRequest, service: &TodoService, id: Uuid) -> HttpResponse {
+    let mut error = ApiError::new(
+        StatusCode::NOT_FOUND,
+        ErrorCode::TodoNotFound,
+        i18n::message("todo-not-found"),
+    );
+    let suggest = req
+        .app_data::<web::Data<Config>>()
+        .is_some_and(|config| config.suggest_missing_ids);
+    if suggest {
+        let suggestions = service.suggest_ids(&id.to_string());
+        if !suggestions.is_empty() {
+            error = error.with("suggestions", suggestions);
+        }
+    }
+    error.into_response()
+}
+
+/// Checks a new todo's fields and normalizes its due date in place, reading
+/// relative dates against `today`. Shared with the MCP tools so both accept
+/// exactly the same todos.
+pub fn validate_create(todo_create: &mut TodoCreate, today: NaiveDate) -> Result<(), ApiError> {
+    validate_text(&todo_create.text)?;
+    dates::normalize_due_date(&mut todo_create.due_date, today)
+        .and_then(|()| models::validate_estimate(todo_create.estimate_minutes))
+        .and_then(|()| models::validate_location(todo_create.location.as_ref()))
+        .and_then(|()| models::validate_recurrence_end(todo_create.recurrence_end.as_ref()))
+        .map_err(ApiError::bad_request)
+}
+
+/// Checks todo text is given and fits, however the todo arrives.
+fn validate_text(text: &str) -> Result<(), ApiError> {
+    if text.trim().is_empty() {
+        return Err(ApiError::new(
+            StatusCode::BAD_REQUEST,
+            ErrorCode::TextRequired,
+            i18n::message("todo-text-required"),
+        ));
+    }
+    if text.len() > 500 {
+        return Err(ApiError::new(
+            StatusCode::BAD_REQUEST,
+            ErrorCode::TextTooLong,
+            i18n::message("todo-text-too-long"),
+        ));
+    }
+    Ok(())
+}
+
+/// The deployment's moderation, or none if the app has not set one up.
+fn moderation(req: &HttpRequest) -> web::Data<Moderation> {
+    req.app_data::<web::Data<Moderation>>()
+        .cloned()
+        .unwrap_or_default()
+}
+
+/// Runs `text` past the deployment's moderation, masking it in place if
+/// so configured. Returns the error response when it can't be saved.
+async fn moderate(req: &HttpRequest, text: &mut String) -> Option<HttpResponse> {
+    let moderation = req.app_data::<web::Data<Moderation>>()?;
+    let error = moderation.apply(text).await.err()?;
+    Some(moderation_error_response(error))
+}
+
+fn moderation_error_response(error: ModerationError) -> HttpResponse {
+    match error {
+        ModerationError::Disallowed(terms) => ApiError::new(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            ErrorCode::ContentRejected,
+            i18n::message("todo-text-disallowed"),
+        )
+        .with("terms", terms)
+        .into_response(),
+        ModerationError::Unavailable(_) => {
+            ApiError::status(StatusCode::SERVICE_UNAVAILABLE, error.to_string()).into_response()
+        }
+    }
+}
+
+pub async fn create_todo(
+    req: HttpRequest,
+    service: web::Data<TodoService>,
+    todo_create: casing::Json<TodoCreate>,
+) -> impl Responder {
+    let mut todo_create = todo_create.into_inner();
+    let preferences = user_preferences(&req);
+    if let Err(e) = validate_create(&mut todo_create, client_today(preferences.as_ref())) {
+        return e.into_response();
+    }
+    if let Some(preferences) = &preferences {
+        preferences.apply_defaults(&mut todo_create);
+    }
+    if let Some(response) = moderate(&req, &mut todo_create.text).await {
+        return response;
+    }
+
+    let deadline = match deadlines::from_request(&req) {
+        Ok(deadline) => deadline,
+        Err(e) => return ApiError::bad_request(e).into_response(),
+    };
+    match service.create_until(todo_create, &deadline) {
+        Ok(todo) => HttpResponse::Created().json(Cased(todo)),
+        Err(exceeded) => deadlines::exceeded_response(exceeded),
+    }
+}
+
+/// Creates a todo from a single line such as `Pay rent tomorrow 9am !high`,
+/// returning it along with what was parsed.
+pub async fn quick_add_todo(
+    req: HttpRequest,
+    service: web::Data<TodoService>,
+    body: casing::Json<QuickAddRequest>,
+) -> impl Responder {
+    let preferences = user_preferences(&req);
+    let mut parsed = quick_add::parse(&body.text, client_today(preferences.as_ref()));
+    if let Err(e) = validate_text(&parsed.text) {
+        return e.into_response();
+    }
+    if let Some(response) = moderate(&req, &mut parsed.text).await {
+        return response;
+    }
+
+    let deadline = match deadlines::from_request(&req) {
+        Ok(deadline) => deadline,
+        Err(e) => return ApiError::bad_request(e).into_response(),
+    };
+    let mut todo_create = parsed.clone().into_create();
+    if let Some(preferences) = &preferences {
+        preferences.apply_defaults(&mut todo_create);
+    }
+    match service.create_until(todo_create, &deadline) {
+        Ok(todo) => HttpResponse::Created().json(Cased(serde_json::json!({
+            "todo": todo,
+            "parsed": parsed
+        }))),
+        Err(exceeded) => deadlines::exceeded_response(exceeded),
+    }
+}
+
+/// Checks an update's fields and normalizes its due date in place.
+pub fn validate_update(todo_update: &mut TodoUpdate, today: NaiveDate) -> Result<(), ApiError> {
+    dates::normalize_due_date(&mut todo_update.due_date, today)
+        .and_then(|()| models::validate_estimate(todo_update.estimate_minutes))
+        .and_then(|()| models::validate_location(todo_update.location.as_ref()))
+        .and_then(|()| models::validate_recurrence_end(todo_update.recurrence_end.as_ref()))
+        .map_err(ApiError::bad_request)
+}
+
+pub async fn update_todo(
+    req: HttpRequest,
+    service: web::Data<TodoService>,
+    path: web::Path<Uuid>,
+    todo_update: casing::Json<TodoUpdate>,
+) -> impl Responder {
+    let id = path.into_inner();
+    let mut todo_update = todo_update.into_inner();
+    let today = client_today(user_preferences(&req).as_ref());
+    if let Err(e) = validate_update(&mut todo_update, today) {
+        return e.into_response();
+    }
+    if let Some(text) = &mut todo_update.text {
+        if let Some(response) = moderate(&req, text).await {
+            return response;
+        }
+    }
+    let deadline = match deadlines::from_request(&req) {
+        Ok(deadline) => deadline,
+        Err(e) => return ApiError::bad_request(e).into_response(),
+    };
+
+    match service.update_until(id, todo_update, &deadline) {
+        Ok(Some(todo)) => HttpResponse::Ok().json(Cased(todo)),
+        Ok(None) => todo_not_found(&req, &service, id),
+        Err(exceeded) => deadlines::exceeded_response(exceeded),
+    }
+}
+
+pub async fn bulk_edit_preview(
+    req: HttpRequest,
+    service: web::Data<TodoService>,
+    previews: web::Data<BulkEditPreviews>,
+    request: casing::Json<BulkEditRequest>,
+) -> impl Responder {
+    let mut request = request.into_inner();
+    if bulk_edit::is_empty(&request.changes) {
+        return ApiError::bad_request(i18n::message("bulk-edit-empty")).into_response();
+    }
+    let today = client_today(user_preferences(&req).as_ref());
+    if let Err(e) = validate_update(&mut request.changes, today) {
+        return e.into_response();
+    }
+    if let Some(text) = &mut request.changes.text {
+        if let Some(response) = moderate(&req, text).await {
+            return response;
+        }
+    }
+    let deadline = match deadlines::from_request(&req) {
+        Ok(deadline) => deadline,
+        Err(e) => return ApiError::bad_request(e).into_response(),
+    };
+
+    match service.bulk_edit_preview_until(&request, &deadline) {
+        Ok(todos) => {
+            HttpResponse::Ok().json(Cased(previews.insert(request.changes, todos, Utc::now())))
+        }
+        Err(exceeded) => deadlines::exceeded_response(exceeded),
+    }
+}
+
+pub async fn bulk_edit_apply(
+    req: HttpRequest,
+    service: web::Data<TodoService>,
+    previews: web::Data<BulkEditPreviews>,
+    request: casing::Json<ApplyRequest>,
+) -> impl Responder {
+    let Some(preview) = previews.take(&request.token, Utc::now()) else {
+        return ApiError::new(
+            StatusCode::NOT_FOUND,
+            ErrorCode::PreviewExpired,
+            i18n::message("bulk-edit-preview-missing"),
+        )
+        .into_response();
+    };
+    let deadline = match deadlines::from_request(&req) {
+        Ok(deadline) => deadline,
+        Err(e) => return ApiError::bad_request(e).into_response(),
+    };
+
+    match service.bulk_edit_apply_until(&preview.todos, &preview.changes, &deadline) {
+        Ok(BulkEditOutcome::Applied(todos)) => HttpResponse::Ok().json(Cased(serde_json::json!({
+            "applied": todos.len(),
+            "todos": todos
+        }))),
+        Ok(BulkEditOutcome::Stale(ids)) => ApiError::new(
+            StatusCode::CONFLICT,
+            ErrorCode::PreviewExpired,
+            i18n::message("bulk-edit-stale"),
+        )
+        .with("staleIds", ids)
+        .into_response(),
+        Err(exceeded) => deadlines::exceeded_response(exceeded),
+    }
+}
+
+pub async fn delete_todo(
+    req: HttpRequest,
+    service: web::Data<TodoService>,
+    path: web::Path<Uuid>,
+) -> impl Responder {
+    let id = path.into_inner();
+    let deadline = match deadlines::from_request(&req) {
+        Ok(deadline) => deadline,
+        Err(e) => return ApiError::bad_request(e).into_response(),
+    };
+
+    let policy = req
+        .app_data::<web::Data<Config>>()
+        .map(|config| config.delete_cascade)
+        .unwrap_or_default();
+    match service.delete_cascading_until(id, &policy, &deadline) {
+        Ok(Some(removed)) => HttpResponse::Ok().json(Cased(serde_json::json!({
+            "message": i18n::message("todo-deleted"),
+            "removed": removed
+        }))),
+        Ok(None) => todo_not_found(&req, &service, id),
+        Err(exceeded) => deadlines::exceeded_response(exceeded),
+    }
+}
+
+pub async fn toggle_todo(
+    req: HttpRequest,
+    service: web::Data<TodoService>,
+    path: web::Path<Uuid>,
+) -> impl Responder {
+    let id = path.into_inner();
+    let deadline = match deadlines::from_request(&req) {
+        Ok(deadline) => deadline,
+        Err(e) => return ApiError::bad_request(e).into_response(),
+    };
+
+    match service.toggle_until(id, &deadline) {
+        Ok(Some(todo)) => HttpResponse::Ok().json(Cased(todo)),
+        Ok(None) => todo_not_found(&req, &service, id),
+        Err(exceeded) => deadlines::exceeded_response(exceeded),
+    }
+}
+
+/// Pins or unpins a todo; pinned todos list first unless a sort is asked
+/// for.
+pub async fn pin_todo(
+    req: HttpRequest,
+    service: web::Data<TodoService>,
+    path: web::Path<Uuid>,
+) -> impl Responder {
+    let id = path.into_inner();
+    let deadline = match deadlines::from_request(&req) {
+        Ok(deadline) => deadline,
+        Err(e) => return ApiError::bad_request(e).into_response(),
+    };
+
+    match service.toggle_pin_until(id, &deadline) {
+        Ok(Some(todo)) => HttpResponse::Ok().json(Cased(todo)),
+        Ok(None) => todo_not_found(&req, &service, id),
+        Err(exceeded) => deadlines::exceeded_response(exceeded),
+    }
+}
+
+/// Puts a todo off to a preset or a given date, counted from the client's
+/// today. The snooze is kept in the todo's history as `todo.snoozed`.
+pub async fn snooze_todo(
+    req: HttpRequest,
+    service: web::Data<TodoService>,
+    path: web::Path<Uuid>,
+    body: casing::Json<SnoozeRequest>,
+) -> impl Responder {
+    let id = path.into_inner();
+    let today = client_today(user_preferences(&req).as_ref());
+    let due = match Snooze::from_request(&body).and_then(|snooze| snooze.due_date(today)) {
+        Ok(due) => due,
+        Err(e) => return ApiError::bad_request(e).into_response(),
+    };
+    let deadline = match deadlines::from_request(&req) {
+        Ok(deadline) => deadline,
+        Err(e) => return ApiError::bad_request(e).into_response(),
+    };
+
+    match service.snooze_until(id, due, &deadline) {
+        Ok(Some(todo)) => HttpResponse::Ok().json(Cased(todo)),
+        Ok(None) => todo_not_found(&req, &service, id),
+        Err(exceeded) => deadlines::exceeded_response(exceeded),
+    }
+}
+
+/// Occurrences of a recurring todo that the rollover job recorded as missed.
+pub async fn get_missed_occurrences(
+    req: HttpRequest,
+    service: web::Data<TodoService>,
+    path: web::Path<Uuid>,
+) -> impl Responder {
+    let id = path.into_inner();
+    if service.get_by_id(id).is_none() {
+        return todo_not_found(&req, &service, id);
+    }
+    let missed = service.missed_occurrences(id);
+    HttpResponse::Ok().json(Cased(serde_json::json!({
+        "todoId": id,
+        "count": missed.len(),
+        "missed": missed,
+    })))
+}
+
+/// A mobile client reports entering a todo's geofence; the todo's location
+/// reminder goes out through every configured channel, at most once per
+/// `GEOFENCE_COOLDOWN_SECS`.
+pub async fn geo_trigger_reminder(
+    req: HttpRequest,
+    config: web::Data<Config>,
+    service: web::Data<TodoService>,
+    channels: web::Data<Channels>,
+    geofences: web::Data<GeofenceLog>,
+    path: web::Path<Uuid>,
+    trigger: casing::Json<GeoTrigger>,
+) -> impl Responder {
+    let id = path.into_inner();
+    let Some(todo) = service.get_by_id(id) else {
+        return todo_not_found(&req, &service, id);
+    };
+    let radius = f64::from(config.geofence_radius_meters);
+    let distance = match geofence::check(&todo, &trigger, radius) {
+        Ok(distance) => distance,
+        Err(e) => {
+            let status = match e {
+                TriggerError::Invalid(_) => StatusCode::BAD_REQUEST,
+                TriggerError::Completed => StatusCode::CONFLICT,
+                TriggerError::NoCoordinates | TriggerError::Outside(_) => {
+                    StatusCode::UNPROCESSABLE_ENTITY
+                }
+            };
+            return ApiError::status(status, e.to_string()).into_response();
+        }
+    };
+
+    // Claimed before sending so two triggers at once send one reminder,
+    // and given back if it reached no one.
+    let now = Utc::now();
+    if let Err(next) = geofences.claim(id, now) {
+        return HttpResponse::Ok().json(Cased(serde_json::json!({
+            "dispatched": false,
+            "reason": i18n::message("reminder-sent-recently"),
+            "nextAllowedAt": next,
+            "distanceMeters": distance
+        })));
+    }
+    let delivery = channels.dispatch(&todo, geofence::message(&todo), now).await;
+    if !delivery.delivered() {
+        geofences.release(id, now);
+        return HttpResponse::Ok().json(Cased(serde_json::json!({
+            "dispatched": false,
+            "reason": i18n::message("reminder-undelivered"),
+            "distanceMeters": distance,
+            "delivery": delivery
+        })));
+    }
+    HttpResponse::Ok().json(Cased(serde_json::json!({
+        "dispatched": true,
+        "distanceMeters": distance,
+        "delivery": delivery
+    })))
+}
+
+/// The todo with its history and missed occurrences, as a file another
+/// instance can import.
+pub async fn export_todo(
+    req: HttpRequest,
+    service: web::Data<TodoService>,
+    path: web::Path<Uuid>,
+) -> impl Responder {
+    let id = path.into_inner();
+    match service.export_todo(id) {
+        Some(bundle) => HttpResponse::Ok()
+            .insert_header((
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"todo-{}.json\"", id),
+            ))
+            .json(Cased(bundle)),
+        None => todo_not_found(&req, &service, id),
+    }
+}
+
+/// Restores a todo exported by `export_todo`, keeping its id.
+pub async fn import_todo(
+    req: HttpRequest,
+    service: web::Data<TodoService>,
+    bundle: casing::Json<TodoBundle>,
+) -> impl Responder {
+    let mut bundle = bundle.into_inner();
+    if let Some(response) = moderate(&req, &mut bundle.todo.text).await {
+        return response;
+    }
+    match service.import_todo(bundle) {
+        Ok(todo) => HttpResponse::Created().json(Cased(todo)),
+        Err(e @ BundleError::Exists(_)) => {
+            ApiError::status(StatusCode::CONFLICT, e.to_string()).into_response()
+        }
+        Err(e) => ApiError::bad_request(e.to_string()).into_response(),
+    }
+}
+
+/// Hands a todo to a peer instance (`TRANSFER_PEERS`) and deletes it here
+/// once the peer has imported it.
+pub async fn transfer_todo(
+    req: HttpRequest,
+    config: web::Data<Config>,
+    service: web::Data<TodoService>,
+    path: web::Path<Uuid>,
+    body: casing::Json<TransferRequest>,
+) -> impl Responder {
+    let id = path.into_inner();
+    let Some(url) = config.transfer_peers.get(&body.peer) else {
+        return ApiError::bad_request(i18n::message_with(
+            "transfer-peer-unknown",
+            [("peer", body.peer.as_str().into())],
+        ))
+        .with("peers", config.transfer_peers.keys().collect::<Vec<_>>())
+        .into_response();
+    };
+    let Some(bundle) = service.export_todo(id) else {
+        return todo_not_found(&req, &service, id);
+    };
+
+    let remote = match transfer::push(url, &bundle).await {
+        Ok(todo) => todo,
+        Err(failure) => {
+            let mut error = ApiError::status(StatusCode::BAD_GATEWAY, failure.error);
+            if let Some(remote) = failure.remote {
+                error = error.with("remote", remote);
+            }
+            return error.into_response();
+        }
+    };
+    match service.hand_off(&bundle, &config.delete_cascade) {
+        Some(removed) => HttpResponse::Ok().json(Cased(serde_json::json!({
+            "message": i18n::message("todo-transferred"),
+            "peer": body.peer,
+            "todo": remote,
+            "removed": removed
+        }))),
+        None => ApiError::new(
+            StatusCode::CONFLICT,
+            ErrorCode::TodoChanged,
+            i18n::message("todo-transfer-conflict"),
+        )
+        .with("peer", &body.peer)
+        .with("todo", remote)
+        .into_response(),
+    }
+}
+
+/// Suggests a priority, tags and due date for a todo without changing it;
+/// clients apply what they accept with `PUT /api/todos/{id}`.
+pub async fn suggest_for_todo(
+    req: HttpRequest,
+    service: web::Data<TodoService>,
+    path: web::Path<Uuid>,
+) -> impl Responder {
+    let id = path.into_inner();
+    let Some(todo) = service.get_by_id(id) else {
+        return todo_not_found(&req, &service, id);
+    };
+    let today = client_today(user_preferences(&req).as_ref());
+    let response = match req.app_data::<web::Data<Suggester>>() {
+        Some(suggester) => suggester.suggest(&todo, today).await,
+        None => Suggester::default().suggest(&todo, today).await,
+    };
+    HttpResponse::Ok().json(Cased(response))
+}
+
+pub async fn get_stats(
+    req: HttpRequest,
+    service: web::Data<TodoService>,
+    query: web::Query<StatsQuery>,
+) -> impl Responder {
+    let deadline = match deadlines::from_request(&req) {
+        Ok(deadline) => deadline,
+        Err(e) => return ApiError::bad_request(e).into_response(),
+    };
+    let capacity = query.capacity.unwrap_or_else(|| {
+        req.app_data::<web::Data<Config>>()
+            .map_or(Config::default().daily_capacity_minutes, |config| {
+                config.daily_capacity_minutes
+            })
+    });
+    let preferences = user_preferences(&req).unwrap_or_default();
+    let today = preferences.today(Utc::now());
+    match service.get_stats_on_until(
+        today,
+        preferences.week_start,
+        &deadline,
+        query.include_hidden,
+    ) {
+        Ok(stats) => negotiated(&req, HttpResponse::Ok(), &stats.with_capacity(capacity)),
+        Err(exceeded) => deadlines::exceeded_response(exceeded),
+    }
+}
+
+/// Completions per day, priority and tag over a week or month, as a file
+/// to download: CSV for spreadsheets or a Prometheus textfile. Unlike
+/// `/stats/summary` it reports on the history, not the current todos.
+pub async fn get_stats_report(
+    req: HttpRequest,
+    service: web::Data<TodoService>,
+    query: web::Query<ReportQuery>,
+) -> impl Responder {
+    let format = query.format.as_deref().map_or(Ok(ReportFormat::default()), str::parse);
+    let period = query.period.as_deref().map_or(Ok(ReportPeriod::default()), str::parse);
+    let (format, period) = match (format, period) {
+        (Ok(format), Ok(period)) => (format, period),
+        (Err(e), _) | (_, Err(e)) => return ApiError::bad_request(e).into_response(),
+    };
+    let preferences = user_preferences(&req).unwrap_or_default();
+    let date = query.date.unwrap_or_else(|| preferences.today(Utc::now()));
+    let report = service.stats_report(period, date, preferences.week_start);
+
+    let (body, content_type, extension) = match format {
+        ReportFormat::Csv => (report.to_csv(), "text/csv; charset=utf-8", "csv"),
+        ReportFormat::Prometheus => (report.to_prometheus(), "text/plain; version=0.0.4", "prom"),
+    };
+    let filename = format!(
+        "spicy-todo-{}-{}.{}",
+        period.as_str(),
+        report.from,
+        extension
+    );
+    HttpResponse::Ok()
+        .content_type(content_type)
+        .insert_header((
+            header::CONTENT_DISPOSITION,
+            format!("attachment; filename=\"{}\"", filename),
+        ))
+        .body(body)
+}
+
+/// Every todo as newline-delimited JSON, streamed a page at a time, for
+/// exports too large to build as one JSON array.
+pub async fn stream_todos(service: web::Data<TodoService>) -> impl Responder {
+    HttpResponse::Ok()
+        .content_type(ndjson::CONTENT_TYPE)
+        .streaming(ndjson::todo_stream(service))
+}
+
+/// Longest look-ahead `GET /api/todos/digest` accepts, in days.
+const MAX_DIGEST_DAYS: u32 = 366;
+
+pub async fn get_digest(
+    req: HttpRequest,
+    service: web::Data<TodoService>,
+    query: web::Query<DigestQuery>,
+) -> impl Responder {
+    let days = query.days.unwrap_or(digest::DEFAULT_DAYS);
+    if days > MAX_DIGEST_DAYS {
+        return ApiError::bad_request(i18n::message_with(
+            "digest-days-range",
+            [("max", MAX_DIGEST_DAYS.into())],
+        ))
+        .into_response();
+    }
+    let text = match query.format.as_deref() {
+        None | Some("json") => false,
+        Some("text") => true,
+        Some(other) => {
+            return ApiError::bad_request(i18n::message_with(
+                "digest-format-invalid",
+                [("format", other.into())],
+            ))
+            .into_response()
+        }
+    };
+    let locale = match query.locale.as_deref() {
+        Some(tag) => match tag.parse::<Locale>() {
+            Ok(locale) => locale,
+            Err(e) => return ApiError::bad_request(e).into_response(),
+        },
+        None => req
+            .headers()
+            .get(header::ACCEPT_LANGUAGE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(Locale::negotiate)
+            .unwrap_or_default(),
+    };
+
+    let deadline = match deadlines::from_request(&req) {
+        Ok(deadline) => deadline,
+        Err(e) => return ApiError::bad_request(e).into_response(),
+    };
+    let todos = match service.get_all_until(None, None, None, &deadline) {
+        Ok(todos) => todos,
+        Err(exceeded) => return deadlines::exceeded_response(exceeded),
+    };
+    let agenda = Agenda::build(&todos, client_today(user_preferences(&req).as_ref()), days);
+    if !text {
+        return HttpResponse::Ok().json(Cased(agenda));
+    }
+    let options = PlainTextOptions {
+        locale,
+        width: query.width.unwrap_or(0),
+        show_priority: query.priority.unwrap_or(true),
+    };
+    HttpResponse::Ok()
+        .content_type("text/plain; charset=utf-8")
+        .insert_header((header::CONTENT_LANGUAGE, locale.tag))
+        .body(agenda.render_plain_text(&options))
+}
+
+/// Today's agenda: due work first, spare time filled with what is coming
+/// up, within the daily capacity.
+pub async fn get_plan_today(
+    req: HttpRequest,
+    service: web::Data<TodoService>,
+    query: web::Query<PlanQuery>,
+) -> impl Responder {
+    let capacity = query.capacity.unwrap_or_else(|| {
+        req.app_data::<web::Data<Config>>()
+            .map_or(Config::default().daily_capacity_minutes, |config| {
+                config.daily_capacity_minutes
+            })
+    });
+    if capacity > plan::MAX_CAPACITY_MINUTES {
+        return ApiError::bad_request(i18n::message_with(
+            "plan-capacity-range",
+            [("max", plan::MAX_CAPACITY_MINUTES.into())],
+        ))
+        .into_response();
+    }
+    let lookahead = query.lookahead.unwrap_or(plan::DEFAULT_LOOKAHEAD_DAYS);
+    if lookahead > MAX_DIGEST_DAYS {
+        return ApiError::bad_request(i18n::message_with(
+            "plan-lookahead-range",
+            [("max", MAX_DIGEST_DAYS.into())],
+        ))
+        .into_response();
+    }
+    let options = PlanOptions {
+        lookahead_days: lookahead,
+        ..PlanOptions::new(capacity)
+    };
+
+    let deadline = match deadlines::from_request(&req) {
+        Ok(deadline) => deadline,
+        Err(e) => return ApiError::bad_request(e).into_response(),
+    };
+    let today = client_today(user_preferences(&req).as_ref());
+    match service.plan_day_until(today, &options, &deadline) {
+        Ok(plan) => negotiated(&req, HttpResponse::Ok(), &plan),
+        Err(exceeded) => deadlines::exceeded_response(exceeded),
+    }
+}
+
+/// Today's todos, overdue and top-priority ones, stats and recent activity
+/// in one response, for the frontend's first load. Dates follow the
+/// client's time zone and week start when it has them saved.
+pub async fn get_dashboard(
+    req: HttpRequest,
+    service: web::Data<TodoService>,
+    query: web::Query<DashboardQuery>,
+) -> impl Responder {
+    let limit = query.limit.unwrap_or(dashboard::DEFAULT_SECTION_LIMIT);
+    if limit > dashboard::MAX_SECTION_LIMIT {
+        return ApiError::bad_request(i18n::message_with(
+            "dashboard-limit-range",
+            [("max", dashboard::MAX_SECTION_LIMIT.into())],
+        ))
+        .into_response();
+    }
+    let deadline = match deadlines::from_request(&req) {
+        Ok(deadline) => deadline,
+        Err(e) => return ApiError::bad_request(e).into_response(),
+    };
+    let preferences = user_preferences(&req).unwrap_or_default();
+    let today = preferences.today(Utc::now());
+    match service.dashboard_until(today, preferences.week_start, limit, &deadline) {
+        Ok(dashboard) => negotiated(&req, HttpResponse::Ok(), &dashboard),
+        Err(exceeded) => deadlines::exceeded_response(exceeded),
+    }
+}
+
+/// Todo counts shaped for a Home Assistant RESTful sensor.
+pub async fn get_homeassistant_sensor(
+    req: HttpRequest,
+    service: web::Data<TodoService>,
+) -> impl Responder {
+    let deadline = match deadlines::from_request(&req) {
+        Ok(deadline) => deadline,
+        Err(e) => return ApiError::bad_request(e).into_response(),
+    };
+    let today = client_today(user_preferences(&req).as_ref());
+    match service.get_all_until(None, None, None, &deadline) {
+        Ok(todos) => HttpResponse::Ok().json(Cased(Sensor::count(&todos, today))),
+        Err(exceeded) => deadlines::exceeded_response(exceeded),
+    }
+}
+
+fn homeassistant_data_invalid(service: &str, error: serde_json::Error) -> String {
+    i18n::message_with(
+        "homeassistant-data-invalid",
+        [
+            ("service", service.into()),
+            ("error", error.to_string().into()),
+        ],
+    )
+}
+
+/// Home Assistant service calls, as sent by a `rest_command`: `add_todo`
+/// and `complete_todo`. Answers with the todo and the updated sensor so
+/// dashboards can refresh without polling.
+pub async fn call_homeassistant_service(
+    req: HttpRequest,
+    service: web::Data<TodoService>,
+    path: web::Path<String>,
+    body: web::Json<serde_json::Value>,
+) -> impl Responder {
+    let today = client_today(user_preferences(&req).as_ref());
+    let deadline = match deadlines::from_request(&req) {
+        Ok(deadline) => deadline,
+        Err(e) => return ApiError::bad_request(e).into_response(),
+    };
+    let result = match path.as_str() {
+        "add_todo" => {
+            let data: AddTodoData = match serde_json::from_value(body.into_inner()) {
+                Ok(data) => data,
+                Err(e) => {
+                    return ApiError::bad_request(homeassistant_data_invalid("add_todo", e))
+                        .into_response()
+                }
+            };
+            let mut parsed = quick_add::parse(&data.text, today);
+            if parsed.text.trim().is_empty() || parsed.text.len() > 500 {
+                return ApiError::bad_request(i18n::message("todo-text-invalid")).into_response();
+            }
+            if let Some(response) = moderate(&req, &mut parsed.text).await {
+                return response;
+            }
+            service.create_until(parsed.into_create(), &deadline)
+        }
+        "complete_todo" => {
+            let data: CompleteTodoData = match serde_json::from_value(body.into_inner()) {
+                Ok(data) => data,
+                Err(e) => {
+                    return ApiError::bad_request(homeassistant_data_invalid("complete_todo", e))
+                        .into_response()
+                }
+            };
+            let todos = match service.get_all_until(None, None, None, &deadline) {
+                Ok(todos) => todos,
+                Err(exceeded) => return deadlines::exceeded_response(exceeded),
+            };
+            let id = match homeassistant::find_active(&todos, &data) {
+                Ok(todo) => todo.id,
+                Err(e) => {
+                    let status = match e {
+                        LookupError::Missing => StatusCode::BAD_REQUEST,
+                        LookupError::NotFound => StatusCode::NOT_FOUND,
+                        LookupError::Ambiguous(_) => StatusCode::CONFLICT,
+                    };
+                    return ApiError::status(status, e.to_string()).into_response();
+                }
+            };
+            let update = TodoUpdate {
+                completed: Some(true),
+                ..Default::default()
+            };
+            match service.update_until(id, update, &deadline) {
+                Ok(Some(todo)) => Ok(todo),
+                Ok(None) => return todo_not_found(&req, &service, id),
+                Err(exceeded) => Err(exceeded),
+            }
+        }
+        other => {
+            return ApiError::not_found(i18n::message_with(
+                "homeassistant-service-unknown",
+                [("service", other.into())],
+            ))
+            .with("services", ["add_todo", "complete_todo"])
+            .into_response()
+        }
+    };
+    match result {
+        Ok(todo) => HttpResponse::Ok().json(Cased(serde_json::json!({
+            "todo": todo,
+            "sensor": Sensor::count(&service.get_all(None, None, None), today)
+        }))),
+        Err(exceeded) => deadlines::exceeded_response(exceeded),
+    }
+}
+
+pub async fn clear_completed(req: HttpRequest, service: web::Data<TodoService>) -> impl Responder {
+    let deadline = match deadlines::from_request(&req) {
+        Ok(deadline) => deadline,
+        Err(e) => return ApiError::bad_request(e).into_response(),
+    };
+    match service.clear_completed_until(&deadline) {
+        Ok(()) => HttpResponse::Ok().json(Cased(serde_json::json!({
+            "message": i18n::message("completed-cleared")
+        }))),
+        Err(exceeded) => deadlines::exceeded_response(exceeded),
+    }
+}
+
+/// Completes every todo the list filters select, and `tag` on top, like
+/// `clear_completed` but for any selection. Answers with how many changed.
+pub async fn complete_todos(
+    req: HttpRequest,
+    service: web::Data<TodoService>,
+    query: web::Query<CompleteQuery>,
+) -> impl Responder {
+    let query = query.into_inner();
+    let q = match query.q.as_deref().map(Query::parse).transpose() {
+        Ok(q) => q,
+        Err(e) => return ApiError::bad_request(e).into_response(),
+    };
+    let tag = match query.tag.as_deref().map(Query::tag).transpose() {
+        Ok(tag) => tag,
+        Err(e) => return ApiError::bad_request(e).into_response(),
+    };
+    let selection = match (q, tag) {
+        (Some(q), Some(tag)) => Some(q.and(tag)),
+        (q, tag) => q.or(tag),
+    };
+    let deadline = match deadlines::from_request(&req) {
+        Ok(deadline) => deadline,
+        Err(e) => return ApiError::bad_request(e).into_response(),
+    };
+    match service.complete_matching_until(
+        query.filter,
+        query.search,
+        query.priority,
+        selection.as_ref(),
+        client_today(user_preferences(&req).as_ref()),
+        &deadline,
+    ) {
+        Ok(completed) => {
+            HttpResponse::Ok().json(Cased(serde_json::json!({ "completed": completed })))
+        }
+        Err(exceeded) => deadlines::exceeded_response(exceeded),
+    }
+}
+
+pub async fn get_webhooks(webhooks: web::Data<WebhookService>) -> impl Responder {
+    HttpResponse::Ok().json(Cased(webhooks.get_all()))
+}
+
+pub async fn create_webhook(
+    webhooks: web::Data<WebhookService>,
+    webhook_create: casing::Json<WebhookCreate>,
+) -> impl Responder {
+    match webhooks.create(webhook_create.into_inner()) {
+        Ok(subscription) => HttpResponse::Created().json(Cased(subscription)),
+        Err(e) => ApiError::bad_request(e).into_response(),
+    }
+}
+
+pub async fn get_webhook(
+    webhooks: web::Data<WebhookService>,
+    path: web::Path<String>,
+) -> impl Responder {
+    let id = path.into_inner();
+
+    match webhooks.get_by_id(&id) {
+        Some(subscription) => HttpResponse::Ok().json(Cased(subscription)),
+        None => ApiError::not_found(i18n::message("webhook-not-found")).into_response(),
+    }
+}
+
+pub async fn delete_webhook(
+    webhooks: web::Data<WebhookService>,
+    path: web::Path<String>,
+) -> impl Responder {
+    let id = path.into_inner();
+
+    if webhooks.delete(&id) {
+        HttpResponse::Ok().json(Cased(serde_json::json!({
+            "message": i18n::message("webhook-deleted")
+        })))
+    } else {
+        ApiError::not_found(i18n::message("webhook-not-found")).into_response()
+    }
+}
+
+pub async fn replay_webhook(
+    service: web::Data<TodoService>,
+    webhooks: web::Data<WebhookService>,
+    metrics: web::Data<Metrics>,
+    path: web::Path<String>,
+    query: web::Query<ReplayQuery>,
+) -> impl Responder {
+    let id = path.into_inner();
+
+    let subscription = match webhooks.get_by_id(&id) {
+        Some(subscription) => subscription,
+        None => return ApiError::not_found(i18n::message("webhook-not-found")).into_response(),
+    };
+
+    let cursor = match query.since.as_deref().unwrap_or("0").parse::<EventCursor>() {
+        Ok(cursor) => cursor,
+        Err(e) => return ApiError::bad_request(e).into_response(),
+    };
+
+    let events: Vec<_> = service
+        .events()
+        .since(&cursor)
+        .into_iter()
+        .filter(|event| subscription.matches(event))
+        .collect();
+    let count = events.len();
+
+    actix_web::rt::spawn(crate::webhooks::replay(metrics, subscription, events));
+
+    HttpResponse::Accepted().json(Cased(serde_json::json!({
+        "message": i18n::message("replay-started"),
+        "events": count
+    })))
+}
+
+pub async fn get_notifiers(notifiers: web::Data<NotifierService>) -> impl Responder {
+    HttpResponse::Ok().json(Cased(notifiers.get_all()))
+}
+
+pub async fn create_notifier(
+    notifiers: web::Data<NotifierService>,
+    notifier_create: casing::Json<NotifierCreate>,
+) -> impl Responder {
+    match notifiers.create(notifier_create.into_inner()) {
+        Ok(notifier) => HttpResponse::Created().json(Cased(notifier)),
+        Err(e) => ApiError::bad_request(e).into_response(),
+    }
+}
+
+pub async fn get_notifier(
+    notifiers: web::Data<NotifierService>,
+    path: web::Path<String>,
+) -> impl Responder {
+    let id = path.into_inner();
+
+    match notifiers.get_by_id(&id) {
+        Some(notifier) => HttpResponse::Ok().json(Cased(notifier)),
+        None => ApiError::not_found(i18n::message("notifier-not-found")).into_response(),
+    }
+}
+
+pub async fn delete_notifier(
+    notifiers: web::Data<NotifierService>,
+    path: web::Path<String>,
+) -> impl Responder {
+    let id = path.into_inner();
+
+    if notifiers.delete(&id) {
+        HttpResponse::Ok().json(Cased(serde_json::json!({
+            "message": i18n::message("notifier-deleted")
+        })))
+    } else {
+        ApiError::not_found(i18n::message("notifier-not-found")).into_response()
+    }
+}
+
+pub async fn get_scripts(scripts: web::Data<ScriptService>) -> impl Responder {
+    HttpResponse::Ok().json(Cased(scripts.get_all()))
+}
+
+pub async fn create_script(
+    scripts: web::Data<ScriptService>,
+    script_create: casing::Json<ScriptCreate>,
+) -> impl Responder {
+    match scripts.create(script_create.into_inner()) {
+        Ok(script) => HttpResponse::Created().json(Cased(script)),
+        Err(e) => ApiError::bad_request(e).into_response(),
+    }
+}
+
+pub async fn get_script(
+    scripts: web::Data<ScriptService>,
+    path: web::Path<String>,
+) -> impl Responder {
+    let id = path.into_inner();
+
+    match scripts.get_by_id(&id) {
+        Some(script) => HttpResponse::Ok().json(Cased(script)),
+        None => ApiError::not_found(i18n::message("script-not-found")).into_response(),
+    }
+}
+
+pub async fn delete_script(
+    scripts: web::Data<ScriptService>,
+    path: web::Path<String>,
+) -> impl Responder {
+    let id = path.into_inner();
+
+    if scripts.delete(&id) {
+        HttpResponse::Ok().json(Cased(serde_json::json!({
+            "message": i18n::message("script-deleted")
+        })))
+    } else {
+        ApiError::not_found(i18n::message("script-not-found")).into_response()
+    }
+}
+
+/// The audit log of a script's runs, newest first. Kept after the script
+/// is deleted.
+pub async fn get_script_executions(
+    scripts: web::Data<ScriptService>,
+    path: web::Path<String>,
+) -> impl Responder {
+    let id = path.into_inner();
+
+    let executions = scripts.executions(&id);
+    if executions.is_empty() && scripts.get_by_id(&id).is_none() {
+        return ApiError::not_found(i18n::message("script-not-found")).into_response();
+    }
+    HttpResponse::Ok().json(Cased(executions))
+}
+
+pub async fn get_views(views: web::Data<ViewStore>) -> impl Responder {
+    HttpResponse::Ok().json(Cased(views.get_all()))
+}
+
+pub async fn create_view(
+    views: web::Data<ViewStore>,
+    view_create: casing::Json<ViewCreate>,
+) -> impl Responder {
+    match views.create(view_create.into_inner()) {
+        Ok(view) => HttpResponse::Created().json(Cased(view)),
+        Err(e) => ApiError::bad_request(e).into_response(),
+    }
+}
+
+pub async fn get_view(views: web::Data<ViewStore>, path: web::Path<String>) -> impl Responder {
+    match views.get_by_id(&path.into_inner()) {
+        Some(view) => HttpResponse::Ok().json(Cased(view)),
+        None => ApiError::not_found(i18n::message("view-not-found")).into_response(),
+    }
+}
+
+pub async fn delete_view(views: web::Data<ViewStore>, path: web::Path<String>) -> impl Responder {
+    if views.delete(&path.into_inner()) {
+        HttpResponse::Ok().json(Cased(serde_json::json!({
+            "message": i18n::message("view-deleted")
+        })))
+    } else {
+        ApiError::not_found(i18n::message("view-not-found")).into_response()
+    }
+}
+
+/// Runs a saved view: the todos it selects, in its order. "Overdue" is
+/// judged by the client's date when it has a time zone saved.
+pub async fn get_view_todos(
+    req: HttpRequest,
+    service: web::Data<TodoService>,
+    views: web::Data<ViewStore>,
+    path: web::Path<String>,
+) -> impl Responder {
+    let Some(view) = views.get_by_id(&path.into_inner()) else {
+        return ApiError::not_found(i18n::message("view-not-found")).into_response();
+    };
+    let deadline = match deadlines::from_request(&req) {
+        Ok(deadline) => deadline,
+        Err(e) => return ApiError::bad_request(e).into_response(),
+    };
+    let query = view.query;
+    let mut todos = match service.get_all_until(
+        query.service_filter(),
+        query.search.clone(),
+        query.priority.clone(),
+        &deadline,
+    ) {
+        Ok(todos) => todos,
+        Err(exceeded) => return deadlines::exceeded_response(exceeded),
+    };
+    let today = client_today(user_preferences(&req).as_ref());
+    todos.retain(|todo| query.matches(todo, today));
+    if let Some(sort) = query.sort {
+        sort.sort(&mut todos, query.order.unwrap_or_default());
+    }
+    negotiated(&req, HttpResponse::Ok(), &todos)
+}
+
+/// A `{date}` path segment: `YYYY-MM-DD`, or a phrase like `today` read
+/// against the client's date.
+fn plan_date(req: &HttpRequest, value: &str) -> Result<NaiveDate, String> {
+    let today = client_today(user_preferences(req).as_ref());
+    dates::parse_date(value, today)
+        .ok_or_else(|| i18n::message_with("plan-date-invalid", [("date", value.into())]))
+}
+
+/// The todos planned for a day, whatever their due dates, with the day
+/// before's unfinished ones offered for rollover.
+pub async fn get_my_day(
+    req: HttpRequest,
+    service: web::Data<TodoService>,
+    my_day: web::Data<MyDayStore>,
+    path: web::Path<String>,
+) -> impl Responder {
+    match plan_date(&req, &path.into_inner()) {
+        Ok(date) => HttpResponse::Ok().json(Cased(my_day.day(date, &service))),
+        Err(e) => ApiError::bad_request(e).into_response(),
+    }
+}
+
+pub async fn add_to_my_day(
+    req: HttpRequest,
+    service: web::Data<TodoService>,
+    my_day: web::Data<MyDayStore>,
+    path: web::Path<(String, Uuid)>,
+) -> impl Responder {
+    let (date, id) = path.into_inner();
+    let date = match plan_date(&req, &date) {
+        Ok(date) => date,
+        Err(e) => return ApiError::bad_request(e).into_response(),
+    };
+    if service.get_by_id(id).is_none() {
+        return todo_not_found(&req, &service, id);
+    }
+    let today = client_today(user_preferences(&req).as_ref());
+    match my_day.add(date, id, today) {
+        Ok(_) => HttpResponse::Ok().json(Cased(my_day.day(date, &service))),
+        Err(e) => ApiError::bad_request(e).into_response(),
+    }
+}
+
+/// Takes a todo off a day's plan; the todo itself is left alone.
+pub async fn remove_from_my_day(
+    req: HttpRequest,
+    service: web::Data<TodoService>,
+    my_day: web::Data<MyDayStore>,
+    path: web::Path<(String, Uuid)>,
+) -> impl Responder {
+    let (date, id) = path.into_inner();
+    let date = match plan_date(&req, &date) {
+        Ok(date) => date,
+        Err(e) => return ApiError::bad_request(e).into_response(),
+    };
+    if !my_day.remove(date, id) {
+        return ApiError::not_found(i18n::message("plan-todo-not-planned")).into_response();
+    }
+    HttpResponse::Ok().json(Cased(my_day.day(date, &service)))
+}
+
+pub async fn get_policies(policies: web::Data<PolicyStore>) -> impl Responder {
+    HttpResponse::Ok().json(Cased(policies.get_all()))
+}
+
+pub async fn create_policy(
+    policies: web::Data<PolicyStore>,
+    policy_create: casing::Json<PolicyCreate>,
+) -> impl Responder {
+    match policies.create(policy_create.into_inner()) {
+        Ok(policy) => HttpResponse::Created().json(Cased(policy)),
+        Err(e) => ApiError::bad_request(e).into_response(),
+    }
+}
+
+pub async fn get_policy(
+    policies: web::Data<PolicyStore>,
+    path: web::Path<String>,
+) -> impl Responder {
+    match policies.get_by_id(&path.into_inner()) {
+        Some(policy) => HttpResponse::Ok().json(Cased(policy)),
+        None => ApiError::not_found(i18n::message("policy-not-found")).into_response(),
+    }
+}
+
+pub async fn update_policy(
+    policies: web::Data<PolicyStore>,
+    path: web::Path<String>,
+    policy_update: casing::Json<PolicyCreate>,
+) -> impl Responder {
+    match policies.replace(&path.into_inner(), policy_update.into_inner()) {
+        Ok(Some(policy)) => HttpResponse::Ok().json(Cased(policy)),
+        Ok(None) => ApiError::not_found(i18n::message("policy-not-found")).into_response(),
+        Err(e) => ApiError::bad_request(e).into_response(),
+    }
+}
+
+pub async fn delete_policy(
+    policies: web::Data<PolicyStore>,
+    path: web::Path<String>,
+) -> impl Responder {
+    if policies.delete(&path.into_inner()) {
+        HttpResponse::Ok().json(Cased(serde_json::json!({
+            "message": i18n::message("policy-deleted")
+        })))
+    } else {
+        ApiError::not_found(i18n::message("policy-not-found")).into_response()
+    }
+}
+
+pub async fn get_metrics(
+    service: web::Data<TodoService>,
+    metrics: web::Data<Metrics>,
+) -> impl Responder {
+    let body = metrics.render(&service.get_stats());
+    HttpResponse::Ok()
+        .content_type("text/plain; version=0.0.4")
+        .body(body)
+}
+
+pub async fn get_event_log(
+    service: web::Data<TodoService>,
+    query: web::Query<EventLogQuery>,
+) -> impl Responder {
+    let query = query.into_inner();
+    let event_types = match parse_event_types(query.event_type.as_deref()) {
+        Ok(event_types) => event_types,
+        Err(e) => return ApiError::bad_request(e).into_response(),
+    };
+
+    let filter = EventFilter {
+        event_types,
+        todo_id: query.todo_id,
+        actor: query.actor,
+        request_id: query.request_id,
+        from: query.from,
+        to: query.to,
+    };
+
+    let events = service.events().search(&filter);
+    HttpResponse::Ok().json(Cased(Page::from_vec(events, query.limit, query.offset)))
+}
+
+/// Comma-separated event types, e.g. `todo.created,todo.completed`; none
+/// when unset.
+fn parse_event_types(names: Option<&str>) -> Result<Vec<EventType>, String> {
+    names
+        .iter()
+        .flat_map(|names| names.split(','))
+        .map(|name| {
+            let name = name.trim();
+            serde_json::from_value::<EventType>(serde_json::Value::from(name))
+                .map_err(|_| i18n::message_with("event-type-unknown", [("name", name.into())]))
+        })
+        .collect()
+}
+
+/// The audit trail, for admins: who did what to which todo, and when.
+pub async fn get_audit(
+    req: HttpRequest,
+    config: web::Data<Config>,
+    service: web::Data<TodoService>,
+    query: web::Query<AuditQuery>,
+) -> impl Responder {
+    if let Some(resp) = reject_non_admin(&req, &config) {
+        return resp;
+    }
+    let query = query.into_inner();
+    let event_types = match parse_event_types(query.action.as_deref()) {
+        Ok(event_types) => event_types,
+        Err(e) => return ApiError::bad_request(e).into_response(),
+    };
+    let filter = EventFilter {
+        event_types,
+        todo_id: query.todo_id,
+        actor: query.actor,
+        from: query.from,
+        to: query.to,
+        ..Default::default()
+    };
+    let entries = service.audit(&filter);
+    HttpResponse::Ok().json(Cased(Page::from_vec(entries, query.limit, query.offset)))
+}
+
+/// What has been happening, newest first, told as sentences for people
+/// rather than programs. The signed-in user reads their own events as
+/// "You".
+pub async fn get_activity(
+    service: web::Data<TodoService>,
+    query: web::Query<ActivityQuery>,
+) -> impl Responder {
+    let viewer = RequestContext::current().and_then(|context| context.user);
+    let items = service.activity(viewer.as_deref());
+    HttpResponse::Ok().json(Cased(Page::from_vec(items, query.limit, query.offset)))
+}
+
+/// Mutations after a sequence cursor, for incremental mirroring. A cursor from
+/// before a restart is ahead of the new history and gets 410 Gone.
+pub async fn get_changes(
+    service: web::Data<TodoService>,
+    query: web::Query<ChangesQuery>,
+) -> impl Responder {
+    match service.changes_since(query.since.unwrap_or(0), query.limit) {
+        Ok(feed) => HttpResponse::Ok().json(Cased(feed)),
+        Err(e) => ApiError::status(StatusCode::GONE, e)
+            .with("latest", service.sequence())
+            .into_response(),
+    }
+}
+
+/// Checks the admin token from `Authorization: Bearer <token>` or
+/// `X-Admin-Token`, returning the error response to send when it doesn't
+/// match. Admin endpoints are disabled when no token is configured.
+fn reject_non_admin(req: &HttpRequest, config: &Config) -> Option<HttpResponse> {
+    let expected = match &config.admin_token {
+        Some(token) => token,
+        None => {
+            return Some(
+                ApiError::new(
+                    StatusCode::FORBIDDEN,
+                    ErrorCode::NotConfigured,
+                    i18n::message("admin-disabled"),
+                )
+                .into_response(),
+            )
+        }
+    };
+
+    let provided = req
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .or_else(|| {
+            req.headers()
+                .get("X-Admin-Token")
+                .and_then(|value| value.to_str().ok())
+        });
+
+    match provided {
+        Some(token) if constant_time_eq(token.as_bytes(), expected.as_bytes()) => None,
+        _ => Some(
+            ApiError::status(
+                StatusCode::UNAUTHORIZED,
+                i18n::message("admin-token-invalid"),
+            )
+            .into_response(),
+        ),
+    }
+}
+
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+fn session_auth_disabled() -> HttpResponse {
+    ApiError::not_found(i18n::message("auth-session-disabled")).into_response()
+}
+
+fn session_start_failed() -> HttpResponse {
+    ApiError::status(
+        StatusCode::INTERNAL_SERVER_ERROR,
+        i18n::message("session-start-failed"),
+    )
+    .into_response()
+}
+
+fn session_body(session: &Session) -> Option<serde_json::Value> {
+    let user = session.get::<String>(auth::USER_KEY).ok()??;
+    let csrf = session.get::<String>(auth::CSRF_KEY).ok()??;
+    Some(serde_json::json!({ "user": user, "csrfToken": csrf }))
+}
+
+/// Starts a browser session in session auth mode. The response carries
+/// the CSRF token that mutating requests must send back.
+pub async fn auth_login(
+    auth: web::Data<Auth>,
+    session: Session,
+    body: casing::Json<LoginRequest>,
+) -> impl Responder {
+    if auth.mode() != AuthMode::Session {
+        return session_auth_disabled();
+    }
+    let LoginRequest { username, password } = body.into_inner();
+    let checked = {
+        let (auth, username) = (auth.clone(), username.clone());
+        web::block(move || auth.check_password(&username, &password)).await
+    };
+    if !checked.unwrap_or(false) {
+        return ApiError::status(StatusCode::UNAUTHORIZED, i18n::message("auth-login-failed"))
+            .into_response();
+    }
+
+    // A fresh session id, so one planted before login is of no use.
+    session.renew();
+    let stored = session
+        .insert(auth::USER_KEY, &username)
+        .and_then(|()| session.insert(auth::CSRF_KEY, auth::new_csrf_token()));
+    match stored.ok().and_then(|()| session_body(&session)) {
+        Some(body) => HttpResponse::Ok().json(Cased(body)),
+        None => session_start_failed(),
+    }
+}
+
+/// Sends the browser to `provider` to sign in. It comes back to
+/// [`auth_callback`], which checks it is the same sign-in.
+pub async fn auth_login_with_provider(
+    auth: web::Data<Auth>,
+    session: Session,
+    path: web::Path<String>,
+) -> impl Responder {
+    if auth.mode() != AuthMode::Session {
+        return session_auth_disabled();
+    }
+    let Some((pending, url)) = auth.oidc().start(&path) else {
+        return ApiError::not_found(i18n::message("auth-provider-unknown")).into_response();
+    };
+    if session.insert(oidc::PENDING_KEY, pending).is_err() {
+        return session_start_failed();
+    }
+    HttpResponse::Found()
+        .insert_header((header::LOCATION, url))
+        .finish()
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct CallbackQuery {
+    pub code: Option<String>,
+    pub state: Option<String>,
+    pub error: Option<String>,
+}
+
+/// Where providers send the browser back. Signs in as the user the
+/// identity is linked to, linking or creating one first if needed, and
+/// redirects to the post-login URL.
+pub async fn auth_callback(
+    auth: web::Data<Auth>,
+    session: Session,
+    query: web::Query<CallbackQuery>,
+) -> impl Responder {
+    if auth.mode() != AuthMode::Session {
+        return session_auth_disabled();
+    }
+    // Taken out either way: a state is good for one callback.
+    let pending = session
+        .remove_as::<PendingLogin>(oidc::PENDING_KEY)
+        .and_then(Result::ok)
+        .filter(|pending| {
+            query.state.as_deref().is_some_and(|state| {
+                constant_time_eq(state.as_bytes(), pending.state.as_bytes())
+            })
+        });
+    let Some(pending) = pending else {
+        return ApiError::bad_request(i18n::message("auth-state-invalid")).into_response();
+    };
+    let Some(code) = query.code.as_deref().filter(|_| query.error.is_none()) else {
+        return ApiError::status(
+            StatusCode::UNAUTHORIZED,
+            i18n::message("auth-sign-in-cancelled"),
+        )
+        .into_response();
+    };
+
+    let signed_in = session.get::<String>(auth::USER_KEY).ok().flatten();
+    let user = match auth.oidc().identify(&pending, code).await.and_then(|identity| {
+        auth.oidc().account_for(
+            &pending.provider,
+            &identity,
+            signed_in.as_deref(),
+            |name| auth.has_user(name),
+        )
+    }) {
+        Ok(user) => user,
+        Err(LoginError::Refused) => {
+            return ApiError::status(StatusCode::FORBIDDEN, i18n::message("auth-sign-in-refused"))
+                .into_response()
+        }
+        Err(LoginError::Conflict) => {
+            return ApiError::status(StatusCode::CONFLICT, i18n::message("auth-account-exists"))
+                .into_response()
+        }
+        Err(LoginError::Provider(e)) => {
+            eprintln!("⚠️ Sign-in failed: {}", e);
+            return ApiError::status(
+                StatusCode::BAD_GATEWAY,
+                i18n::message("auth-provider-failed"),
+            )
+            .into_response();
+        }
+    };
+
+    session.renew();
+    let stored = session
+        .insert(auth::USER_KEY, &user)
+        .and_then(|()| session.insert(auth::CSRF_KEY, auth::new_csrf_token()));
+    if stored.is_err() {
+        return session_start_failed();
+    }
+    HttpResponse::SeeOther()
+        .insert_header((header::LOCATION, auth.oidc().post_login_url()))
+        .finish()
+}
+
+pub async fn auth_logout(auth: web::Data<Auth>, session: Session) -> impl Responder {
+    if auth.mode() != AuthMode::Session {
+        return session_auth_disabled();
+    }
+    session.purge();
+    HttpResponse::Ok().json(Cased(
+        serde_json::json!({ "message": i18n::message("auth-logged-out") }),
+    ))
+}
+
+/// The signed-in user and the session's CSRF token, for a page loaded
+/// after login.
+pub async fn auth_session(auth: web::Data<Auth>, session: Session) -> impl Responder {
+    if auth.mode() != AuthMode::Session {
+        return session_auth_disabled();
+    }
+    match session_body(&session) {
+        Some(body) => HttpResponse::Ok().json(Cased(body)),
+        None => ApiError::status(StatusCode::UNAUTHORIZED, i18n::message("auth-required"))
+            .into_response(),
+    }
+}
+
+/// Whose account `/api/account` is about: the signed-in user, or else the
+/// client named in `X-Client-Id`, as the events record them.
+fn account_user() -> Result<String, ApiError> {
+    RequestContext::current()
+        .and_then(|context| context.user)
+        .ok_or_else(client_id_required)
+}
+
+/// Everything kept about the user, as one JSON download: the todos they
+/// created, the events they caused, and their settings and subscriptions.
+/// Phone numbers come masked, as everywhere else.
+pub async fn account_export(
+    req: HttpRequest,
+    service: web::Data<TodoService>,
+    preferences: web::Data<PreferenceStore>,
+    push: web::Data<PushService>,
+) -> impl Responder {
+    let user = match account_user() {
+        Ok(user) => user,
+        Err(e) => return e.into_response(),
+    };
+    let data = service.export_account(&user);
+    let sms = req
+        .app_data::<web::Data<SmsService>>()
+        .and_then(|sms| sms.subscription(&user));
+    let linked_accounts = req
+        .app_data::<web::Data<Auth>>()
+        .map(|auth| auth.oidc().linked_to(&user))
+        .unwrap_or_default();
+    let saved = preferences.user(&user);
+    HttpResponse::Ok()
+        .insert_header((
+            header::CONTENT_DISPOSITION,
+            "attachment; filename=\"spicy-todo-account.json\"",
+        ))
+        .json(Cased(serde_json::json!({
+            "account": data,
+            "listPreference": preferences.get(&user),
+            "preferences": saved.updated_at.map(|_| saved),
+            "pushSubscription": push.get(&user),
+            "smsSubscription": sms,
+            "linkedAccounts": linked_accounts
+        })))
+}
+
+/// Erases the user everywhere: deletes the todos they created, redacting
+/// their history, takes their name off every other event, drops their
+/// settings, subscriptions and linked sign-ins, records their todos as
+/// erased for backups, and ends the session. A users file entry stays;
+/// that is the operator's to remove.
+pub async fn delete_account(
+    req: HttpRequest,
+    service: web::Data<TodoService>,
+    preferences: web::Data<PreferenceStore>,
+    push: web::Data<PushService>,
+    session: Session,
+) -> impl Responder {
+    let user = match account_user() {
+        Ok(user) => user,
+        Err(e) => return e.into_response(),
+    };
+    let deadline = match deadlines::from_request(&req) {
+        Ok(deadline) => deadline,
+        Err(e) => return ApiError::bad_request(e).into_response(),
+    };
+    let deletion = match service.delete_account_until(&user, &deadline) {
+        Ok(deletion) => deletion,
+        Err(exceeded) => return deadlines::exceeded_response(exceeded),
+    };
+
+    let preferences_removed = preferences.forget(&user);
+    let push_removed = push.remove(&user);
+    let sms_removed = req
+        .app_data::<web::Data<SmsService>>()
+        .is_some_and(|sms| sms.unsubscribe(&user));
+    let auth = req.app_data::<web::Data<Auth>>();
+    let unlinked = auth.map_or(0, |auth| auth.oidc().unlink(&user));
+    let backups = req.app_data::<web::Data<Backups>>();
+    let erased_from_backups = match backups {
+        Some(backups) if !deletion.deleted_todos.is_empty() => {
+            if let Err(e) = backups.erase(&deletion.deleted_todos).await {
+                eprintln!("⚠️ Could not record erased todos for backups: {}", e);
+                false
+            } else {
+                true
+            }
+        }
+        _ => false,
+    };
+    if auth.is_some_and(|auth| auth.mode() == AuthMode::Session) {
+        session.purge();
+    }
+
+    HttpResponse::Ok().json(Cased(serde_json::json!({
+        "message": i18n::message("account-deleted"),
+        "removed": {
+            "deletedTodos": deletion.deleted_todos,
+            "anonymizedEvents": deletion.anonymized_events,
+            "preferences": preferences_removed,
+            "pushSubscription": push_removed,
+            "smsSubscription": sms_removed,
+            "linkedAccounts": unlinked,
+            "erasedFromBackups": erased_from_backups
+        }
+    })))
+}
+
+pub async fn admin_seed(
+    req: HttpRequest,
+    config: web::Data<Config>,
+    service: web::Data<TodoService>,
+    seed_request: Option<casing::Json<SeedRequest>>,
+) -> impl Responder {
+    if let Some(resp) = reject_non_admin(&req, &config) {
+        return resp;
+    }
+
+    let seed_request = seed_request.map(|json| json.into_inner()).unwrap_or_default();
+    let (fixture, todos) = match seed_request.todos {
+        Some(todos) => ("custom".to_string(), todos),
+        None => {
+            let name = seed_request.fixture.unwrap_or_else(|| "default".to_string());
+            match fixtures::builtin(&name) {
+                Some(todos) => (name, todos),
+                None => {
+                    return ApiError::bad_request(i18n::message_with(
+                        "fixture-unknown",
+                        [("name", name.as_str().into())],
+                    ))
+                    .with("available", fixtures::FIXTURE_SETS)
+                    .into_response()
+                }
+            }
+        }
+    };
+
+    if todos.iter().any(|todo| todo.text.trim().is_empty()) {
+        return ApiError::new(
+            StatusCode::BAD_REQUEST,
+            ErrorCode::TextRequired,
+            i18n::message("todo-text-required"),
+        )
+        .into_response();
+    }
+
+    let removed = if seed_request.replace.unwrap_or(false) {
+        service.reset()
+    } else {
+        0
+    };
+    let created = service.seed(todos);
+
+    HttpResponse::Created().json(Cased(serde_json::json!({
+        "message": i18n::message_with("todos-seeded", [("count", created.len().into())]),
+        "fixture": fixture,
+        "created": created.len(),
+        "removed": removed
+    })))
+}
+
+/// Bulk-creates synthetic todos for load testing and profiling a real
+/// deployment; see `fixtures::generate`.
+pub async fn admin_generate(
+    req: HttpRequest,
+    config: web::Data<Config>,
+    service: web::Data<TodoService>,
+    query: web::Query<GenerateQuery>,
+) -> impl Responder {
+    if let Some(resp) = reject_non_admin(&req, &config) {
+        return resp;
+    }
+
+    let count = query.count.unwrap_or(1000);
+    if count == 0 || count > fixtures::MAX_GENERATED {
+        return ApiError::bad_request(format!("count must be 1 to {}", fixtures::MAX_GENERATED))
+            .into_response();
+    }
+    let seed = query.seed.unwrap_or_else(rand::random);
+    // Slow on the journal, which syncs every todo, so kept off the workers.
+    let context = RequestContext::current().unwrap_or_default();
+    let generate =
+        move || context.sync_scope(|| service.seed(fixtures::generate(count, seed)).len());
+    let created = match web::block(generate).await {
+        Ok(created) => created,
+        Err(e) => {
+            return ApiError::status(StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+                .into_response()
+        }
+    };
+
+    HttpResponse::Created().json(Cased(serde_json::json!({
+        "message": i18n::message_with("todos-generated", [("count", created.into())]),
+        "created": created,
+        "seed": seed
+    })))
+}
+
+pub async fn admin_reset(
+    req: HttpRequest,
+    config: web::Data<Config>,
+    service: web::Data<TodoService>,
+) -> impl Responder {
+    if let Some(resp) = reject_non_admin(&req, &config) {
+        return resp;
+    }
+
+    let removed = service.reset();
+    HttpResponse::Ok().json(Cased(serde_json::json!({
+        "message": i18n::message("state-reset"),
+        "removed": removed
+    })))
+}
+
+/// Serves a Grafana dashboard generated from the metric names this build
+/// exports, ready for import.
+pub async fn admin_grafana_dashboard(
+    req: HttpRequest,
+    config: web::Data<Config>,
+    metrics: web::Data<Metrics>,
+) -> impl Responder {
+    if let Some(resp) = reject_non_admin(&req, &config) {
+        return resp;
+    }
+
+    HttpResponse::Ok().json(Cased(grafana::dashboard(&metrics.names())))
+}
+
+/// Captures a CPU or heap profile of the live process. Requires the admin
+/// token, `ENABLE_PROFILING`, and a build with the `profiling` feature.
+pub async fn debug_pprof_profile(
+    req: HttpRequest,
+    config: web::Data<Config>,
+    query: web::Query<ProfileQuery>,
+) -> impl Responder {
+    if let Some(resp) = reject_non_admin(&req, &config) {
+        return resp;
+    }
+    if !config.profiling_enabled {
+        return ApiError::not_configured(i18n::message("profiling-disabled")).into_response();
+    }
+
+    let format = match ProfileFormat::parse(query.format.as_deref()) {
+        Ok(format) => format,
+        Err(e) => return ApiError::bad_request(e).into_response(),
+    };
+    let seconds = query.seconds.unwrap_or(profiling::DEFAULT_SECONDS);
+    if seconds == 0 || seconds > profiling::MAX_SECONDS {
+        return ApiError::bad_request(i18n::message_with(
+            "profiling-seconds-range",
+            [("max", profiling::MAX_SECONDS.into())],
+        ))
+        .into_response();
+    }
+    let frequency = query.frequency.unwrap_or(profiling::DEFAULT_FREQUENCY);
+    if frequency <= 0 || frequency > profiling::MAX_FREQUENCY {
+        return ApiError::bad_request(i18n::message_with(
+            "profiling-frequency-range",
+            [("max", profiling::MAX_FREQUENCY.into())],
+        ))
+        .into_response();
+    }
+
+    let captured = match format {
+        ProfileFormat::Heap => profiling::capture_heap(),
+        _ => profiling::capture_cpu(seconds, frequency, format).await,
+    };
+    match captured {
+        Ok(body) => HttpResponse::Ok().content_type(format.content_type()).body(body),
+        Err(CaptureError::Unsupported) => ApiError::new(
+            StatusCode::NOT_IMPLEMENTED,
+            ErrorCode::NotConfigured,
+            i18n::message("profiling-not-built"),
+        )
+        .into_response(),
+        Err(CaptureError::Busy) => {
+            ApiError::status(StatusCode::CONFLICT, i18n::message("profiling-busy")).into_response()
+        }
+        Err(CaptureError::Failed(e)) => {
+            ApiError::status(StatusCode::INTERNAL_SERVER_ERROR, e).into_response()
+        }
+    }
+}
+
+/// Reports tokio task and queue counts for every runtime plus lock wait
+/// statistics, to explain latency spikes on live instances.
+pub async fn debug_runtime(
+    req: HttpRequest,
+    config: web::Data<Config>,
+    service: web::Data<TodoService>,
+    runtimes: web::Data<RuntimeRegistry>,
+) -> impl Responder {
+    if let Some(resp) = reject_non_admin(&req, &config) {
+        return resp;
+    }
+
+    let runtimes = runtimes.snapshot();
+    let alive_tasks: usize = runtimes.iter().map(|runtime| runtime.alive_tasks).sum();
+    let queue_depth: usize = runtimes.iter().map(|runtime| runtime.global_queue_depth).sum();
+    HttpResponse::Ok().json(Cased(serde_json::json!({
+        "totals": {
+            "runtimes": runtimes.len(),
+            "aliveTasks": alive_tasks,
+            "globalQueueDepth": queue_depth
+        },
+        "runtimes": runtimes,
+        "locks": service.lock_diagnostics()
+    })))
+}
+
+/// Migrates todos from a running instance of one of the sibling
+/// implementations. Requires the admin token since it fetches an arbitrary
+/// URL and bulk-writes the store.
+pub async fn import_spicy(
+    req: HttpRequest,
+    config: web::Data<Config>,
+    service: web::Data<TodoService>,
+    query: web::Query<ImportQuery>,
+) -> impl Responder {
+    if let Some(resp) = reject_non_admin(&req, &config) {
+        return resp;
+    }
+
+    let url = match importer::todos_url(&query.source_url) {
+        Ok(url) => url,
+        Err(e) => return ApiError::bad_request(e).into_response(),
+    };
+    let sources = match importer::fetch(&url).await {
+        Ok(sources) => sources,
+        Err(e) => return ApiError::status(StatusCode::BAD_GATEWAY, e).into_response(),
+    };
+
+    let fetched = sources.len();
+    let now = Utc::now();
+    let moderation = moderation(&req);
+    let mut todos = Vec::new();
+    let mut rejected = Vec::new();
+    for source in sources {
+        let source_id = source.id.as_ref().map(|id| match id {
+            serde_json::Value::String(id) => id.clone(),
+            other => other.to_string(),
+        });
+        let mut todo = match importer::convert(source, now) {
+            Ok(todo) => todo,
+            Err(error) => {
+                rejected.push(Rejected { source_id, error });
+                continue;
+            }
+        };
+        match moderation.apply(&mut todo.text).await {
+            Ok(()) => todos.push(todo),
+            Err(ModerationError::Disallowed(_)) => rejected.push(Rejected {
+                source_id,
+                error: i18n::message("todo-text-disallowed"),
+            }),
+            Err(error) => return moderation_error_response(error),
+        }
+    }
+    let converted = todos.len();
+    let imported = service.import(todos).len();
+
+    HttpResponse::Ok().json(Cased(ImportReport {
+        source: url,
+        fetched,
+        imported,
+        skipped: converted - imported,
+        rejected,
+    }))
+}
+
+/// This deployment's setup, for `GET`, `HEAD` and `OPTIONS` alike.
+pub async fn get_capabilities(config: web::Data<Config>) -> impl Responder {
+    HttpResponse::Ok()
+        .insert_header((header::ALLOW, "GET, HEAD, OPTIONS"))
+        .json(Cased(conformance::capabilities(&config)))
+}
+
+/// Optional features this build supports, for client feature detection.
+pub async fn get_conformance(config: web::Data<Config>) -> impl Responder {
+    HttpResponse::Ok().json(Cased(conformance::report(&config)))
+}
+
+/// The command palette catalog, optionally narrowed to one kind of
+/// resource or to the actions that apply to one todo.
+pub async fn get_actions(
+    req: HttpRequest,
+    service: web::Data<TodoService>,
+    config: web::Data<Config>,
+    query: web::Query<ActionsQuery>,
+) -> impl Responder {
+    let query = query.into_inner();
+    let mut catalog = actions::catalog(&config);
+    if let Some(resource) = query.resource {
+        catalog.retain(|action| action.resource == resource);
+    }
+    if let Some(id) = query.id {
+        let Some(todo) = service.get_by_id(id) else {
+            return todo_not_found(&req, &service, id);
+        };
+        catalog.retain(|action| action.applies_to(&todo));
+    }
+    HttpResponse::Ok().json(Cased(catalog))
+}
+
+/// Opens an MCP session over server-sent events. The first event names
+/// the URL to post messages to; answers arrive on this stream.
+pub async fn mcp_sse(sessions: web::Data<McpSessions>) -> impl Responder {
+    let Some((id, receiver)) = sessions.open() else {
+        return ApiError::status(
+            StatusCode::SERVICE_UNAVAILABLE,
+            i18n::message("mcp-too-many-sessions"),
+        )
+        .into_response();
+    };
+    HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header((header::CACHE_CONTROL, "no-cache"))
+        // Keeps the compression middleware from buffering events.
+        .insert_header(header::ContentEncoding::Identity)
+        .streaming(mcp::event_stream(&id, receiver))
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct McpSessionQuery {
+    #[serde(rename = "sessionId")]
+    pub session_id: String,
+}
+
+/// A JSON-RPC message for an MCP session; the answer goes out on the
+/// session's event stream.
+pub async fn mcp_message(
+    req: HttpRequest,
+    service: web::Data<TodoService>,
+    sessions: web::Data<McpSessions>,
+    query: web::Query<McpSessionQuery>,
+    body: String,
+) -> impl Responder {
+    if !sessions.is_open(&query.session_id) {
+        return ApiError::not_found(i18n::message("mcp-session-not-found")).into_response();
+    }
+    if req.app_data::<web::Data<Demo>>().is_some() && body.trim_start().starts_with('[') {
+        return ApiError::status(StatusCode::FORBIDDEN, demo::BATCH_REFUSED).into_response();
+    }
+    let moderation = req.app_data::<web::Data<Moderation>>();
+    let response = match moderation {
+        Some(moderation) => mcp::handle_message(&service, moderation, &body).await,
+        None => mcp::handle_message(&service, &Moderation::default(), &body).await,
+    };
+    if let Some(response) = response {
+        if !sessions.send(&query.session_id, response) {
+            return ApiError::not_found(i18n::message("mcp-session-not-found")).into_response();
+        }
+    }
+    HttpResponse::Accepted().finish()
+}
+
+/// The integrations this server knows, and which of them are loaded.
+pub async fn get_plugins(
+    registry: web::Data<PluginRegistry>,
+    config: web::Data<Config>,
+    query: web::Query<PluginsQuery>,
+) -> impl Responder {
+    let plugins: Vec<_> = registry
+        .describe(&config)
+        .into_iter()
+        .filter(|plugin| query.kind.is_none_or(|kind| plugin.kind == kind))
+        .filter(|plugin| query.loaded.is_none_or(|loaded| plugin.loaded == loaded))
+        .collect();
+    HttpResponse::Ok().json(Cased(plugins))
+}
+
+/// Inbound email webhook in Mailgun's format. Duplicates and empty
+/// subjects are acknowledged without a todo, so they aren't retried.
+pub async fn ingest_email(
+    req: HttpRequest,
+    service: web::Data<TodoService>,
+    config: web::Data<Config>,
+    ingest: web::Data<EmailIngest>,
+    form: web::Form<MailgunEmail>,
+) -> impl Responder {
+    let Some(signing_key) = &config.email_webhook_signing_key else {
+        return ApiError::not_configured(i18n::message("email-not-configured")).into_response();
+    };
+    if let Err(e) = form.verify(signing_key, Utc::now()) {
+        return ApiError::status(StatusCode::UNAUTHORIZED, e).into_response();
+    }
+
+    let deadline = match deadlines::from_request(&req) {
+        Ok(deadline) => deadline,
+        Err(e) => return ApiError::bad_request(e).into_response(),
+    };
+    let today = client_today(user_preferences(&req).as_ref());
+    let ingested = ingest
+        .ingest(&service, &moderation(&req), &form.email(), today, &deadline)
+        .await;
+    match ingested {
+        Ok(Ingested::Created(todo)) => HttpResponse::Created().json(Cased(todo)),
+        Ok(Ingested::Duplicate) => HttpResponse::Ok().json(Cased(
+            serde_json::json!({"ignored": i18n::message("email-duplicate")}),
+        )),
+        Ok(Ingested::Empty) => HttpResponse::Ok().json(Cased(
+            serde_json::json!({"ignored": i18n::message("email-subject-empty")}),
+        )),
+        Ok(Ingested::Disallowed) => HttpResponse::Ok().json(Cased(
+            serde_json::json!({"ignored": i18n::message("todo-text-disallowed")}),
+        )),
+        Err(IngestError::Deadline(exceeded)) => deadlines::exceeded_response(exceeded),
+        Err(IngestError::Moderation(error)) => moderation_error_response(error),
+    }
+}
+
+/// CalDAV account discovery (RFC 6764) lands on the principal.
+pub async fn caldav_well_known() -> impl Responder {
+    HttpResponse::MovedPermanently()
+        .insert_header((header::LOCATION, caldav::ROOT))
+        .finish()
+}
+
+pub async fn caldav_options() -> impl Responder {
+    HttpResponse::Ok()
+        .insert_header(("DAV", caldav::COMPLIANCE))
+        .insert_header((header::ALLOW, caldav::ALLOW))
+        .finish()
+}
+
+fn multistatus(body: Multistatus) -> HttpResponse {
+    HttpResponse::MultiStatus()
+        .content_type(caldav::XML_CONTENT_TYPE)
+        .insert_header(("DAV", caldav::COMPLIANCE))
+        .body(body.finish())
+}
+
+fn depth(req: &HttpRequest) -> Option<&str> {
+    req.headers().get("Depth").and_then(|depth| depth.to_str().ok())
+}
+
+fn not_a_calendar_resource() -> HttpResponse {
+    ApiError::not_found(i18n::message("caldav-resource-invalid")).into_response()
+}
+
+/// The principal, which is also the calendar home.
+pub async fn caldav_propfind_root(
+    req: HttpRequest,
+    service: web::Data<TodoService>,
+    body: String,
+) -> impl Responder {
+    let props = match caldav::parse_propfind(&body) {
+        Ok(props) => props,
+        Err(e) => return ApiError::bad_request(e).into_response(),
+    };
+    let mut response = Multistatus::new();
+    response.push(caldav::ROOT, &Resource::Root, &props);
+    if caldav::lists_children(depth(&req)) {
+        let version = service.collection_version();
+        response.push(caldav::COLLECTION, &Resource::Collection(&version), &props);
+    }
+    multistatus(response)
+}
+
+pub async fn caldav_propfind_collection(
+    req: HttpRequest,
+    service: web::Data<TodoService>,
+    body: String,
+) -> impl Responder {
+    let props = match caldav::parse_propfind(&body) {
+        Ok(props) => props,
+        Err(e) => return ApiError::bad_request(e).into_response(),
+    };
+    let deadline = match deadlines::from_request(&req) {
+        Ok(deadline) => deadline,
+        Err(e) => return ApiError::bad_request(e).into_response(),
+    };
+    // Read the version first, so a change made meanwhile shows as a newer
+    // ctag on the next sync rather than being missed.
+    let version = service.collection_version();
+    let mut response = Multistatus::new();
+    response.push(caldav::COLLECTION, &Resource::Collection(&version), &props);
+    if caldav::lists_children(depth(&req)) {
+        let todos = match service.get_all_until(None, None, None, &deadline) {
+            Ok(todos) => todos,
+            Err(exceeded) => return deadlines::exceeded_response(exceeded),
+        };
+        for todo in &todos {
+            response.push(
+                &caldav::href(&todo.id.to_string()),
+                &Resource::Todo(todo),
+                &props,
+            );
+        }
+    }
+    multistatus(response)
+}
+
+/// calendar-query lists every todo; calendar-multiget the ones asked for.
+pub async fn caldav_report(
+    req: HttpRequest,
+    service: web::Data<TodoService>,
+    body: String,
+) -> impl Responder {
+    let report = match caldav::parse_report(&body) {
+        Ok(report) => report,
+        Err(e) => return ApiError::bad_request(e).into_response(),
+    };
+    let deadline = match deadlines::from_request(&req) {
+        Ok(deadline) => deadline,
+        Err(e) => return ApiError::bad_request(e).into_response(),
+    };
+    let mut response = Multistatus::new();
+    match report {
+        Report::CalendarQuery { props, todos } => {
+            if todos {
+                let todos = match service.get_all_until(None, None, None, &deadline) {
+                    Ok(todos) => todos,
+                    Err(exceeded) => return deadlines::exceeded_response(exceeded),
+                };
+                for todo in &todos {
+                    response.push(
+                        &caldav::href(&todo.id.to_string()),
+                        &Resource::Todo(todo),
+                        &props,
+                    );
+                }
+            }
+        }
+        Report::CalendarMultiget { props, hrefs } => {
+            for href in hrefs {
+                let todo = match caldav::todo_id(&href) {
+                    Some(id) => match service.get_by_id_until(id, &deadline) {
+                        Ok(todo) => todo,
+                        Err(exceeded) => return deadlines::exceeded_response(exceeded),
+                    },
+                    None => None,
+                };
+                match todo {
+                    Some(todo) => response.push(&href, &Resource::Todo(&todo), &props),
+                    None => response.push_not_found(&href),
+                }
+            }
+        }
+    }
+    multistatus(response)
+}
+
+pub async fn caldav_propfind_todo(
+    req: HttpRequest,
+    service: web::Data<TodoService>,
+    path: web::Path<String>,
+    body: String,
+) -> impl Responder {
+    let Some(id) = caldav::todo_id(&path) else {
+        return not_a_calendar_resource();
+    };
+    let props = match caldav::parse_propfind(&body) {
+        Ok(props) => props,
+        Err(e) => return ApiError::bad_request(e).into_response(),
+    };
+    let Some(todo) = service.get_by_id(id) else {
+        return todo_not_found(&req, &service, id);
+    };
+    let mut response = Multistatus::new();
+    response.push(
+        &caldav::href(&todo.id.to_string()),
+        &Resource::Todo(&todo),
+        &props,
+    );
+    multistatus(response)
+}
+
+pub async fn caldav_get_todo(
+    req: HttpRequest,
+    service: web::Data<TodoService>,
+    path: web::Path<String>,
+) -> impl Responder {
+    let Some(id) = caldav::todo_id(&path) else {
+        return not_a_calendar_resource();
+    };
+    match service.get_by_id(id) {
+        Some(todo) => HttpResponse::Ok()
+            .content_type(caldav::ICS_CONTENT_TYPE)
+            .insert_header((header::ETAG, ical::etag(&todo)))
+            .body(ical::to_ics(&todo)),
+        None => todo_not_found(&req, &service, id),
+    }
+}
+
+/// Creates or replaces a todo from a VTODO. A new resource's name becomes
+/// the todo's id, or the UUID derived from it, so the client finds it
+/// where it put it.
+pub async fn caldav_put_todo(
+    req: HttpRequest,
+    service: web::Data<TodoService>,
+    path: web::Path<String>,
+    body: String,
+) -> impl Responder {
+    let Some(id) = caldav::todo_id(&path) else {
+        return not_a_calendar_resource();
+    };
+    let mut vtodo = match ical::parse(&body) {
+        Ok(vtodo) => vtodo,
+        Err(e) => return ApiError::bad_request(e).into_response(),
+    };
+    if let Err(e) = validate_text(&vtodo.summary) {
+        return e.into_response();
+    }
+    if let Err(e) = models::validate_recurrence_end(vtodo.recurrence_end.as_ref()) {
+        return ApiError::bad_request(e).into_response();
+    }
+    if let Some(response) = moderate(&req, &mut vtodo.summary).await {
+        return response;
+    }
+
+    let header = |name| {
+        req.headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(str::trim)
+    };
+    let precondition = match (header(header::IF_MATCH), header(header::IF_NONE_MATCH)) {
+        (Some(etag), _) if etag != "*" => Precondition::Matches(etag.to_string()),
+        (_, Some("*")) => Precondition::Absent,
+        _ => Precondition::None,
+    };
+    let deadline = match deadlines::from_request(&req) {
+        Ok(deadline) => deadline,
+        Err(e) => return ApiError::bad_request(e).into_response(),
+    };
+    match service.put_vtodo_until(id, vtodo, &precondition, &deadline) {
+        Ok(PutOutcome::Created(todo)) => HttpResponse::Created()
+            .insert_header((header::ETAG, ical::etag(&todo)))
+            .finish(),
+        Ok(PutOutcome::Updated(todo)) => HttpResponse::NoContent()
+            .insert_header((header::ETAG, ical::etag(&todo)))
+            .finish(),
+        Ok(PutOutcome::PreconditionFailed) => todo_changed(),
+        Err(exceeded) => deadlines::exceeded_response(exceeded),
+    }
+}
+
+pub async fn caldav_delete_todo(
+    req: HttpRequest,
+    service: web::Data<TodoService>,
+    path: web::Path<String>,
+) -> impl Responder {
+    let Some(id) = caldav::todo_id(&path) else {
+        return not_a_calendar_resource();
+    };
+    let Some(todo) = service.get_by_id(id) else {
+        return todo_not_found(&req, &service, id);
+    };
+    let if_match = req
+        .headers()
+        .get(header::IF_MATCH)
+        .and_then(|value| value.to_str().ok())
+        .map(str::trim);
+    if if_match.is_some_and(|etag| etag != "*" && etag != ical::etag(&todo)) {
+        return todo_changed();
+    }
+
+    let deadline = match deadlines::from_request(&req) {
+        Ok(deadline) => deadline,
+        Err(e) => return ApiError::bad_request(e).into_response(),
+    };
+    match service.delete_until(id, &deadline) {
+        Ok(true) => HttpResponse::NoContent().finish(),
+        Ok(false) => todo_not_found(&req, &service, id),
+        Err(exceeded) => deadlines::exceeded_response(exceeded),
+    }
+}
+
+pub async fn sync_todos(
+    req: HttpRequest,
+    service: web::Data<TodoService>,
+    request: casing::Json<SyncRequest>,
+) -> impl Responder {
+    let mut request = request.into_inner();
+    let moderation = moderation(&req);
+    let mut disallowed = Vec::new();
+    let mut changes = Vec::with_capacity(request.changes.len());
+    for mut change in request.changes {
+        if let Some(text) = &mut change.fields.text {
+            match moderation.apply(text).await {
+                Ok(()) => {}
+                Err(ModerationError::Disallowed(_)) => {
+                    disallowed.push(SyncError {
+                        id: change.id,
+                        code: SyncErrorCode::Disallowed,
+                        error: None,
+                    });
+                    continue;
+                }
+                Err(error) => return moderation_error_response(error),
+            }
+        }
+        changes.push(change);
+    }
+    request.changes = changes;
+
+    let mut response = service.sync(request);
+    response.errors.extend(disallowed);
+    for error in &mut response.errors {
+        let id = match error.code {
+            SyncErrorCode::IdRequired => "sync-id-required",
+            SyncErrorCode::TextRequired => "todo-text-required",
+            SyncErrorCode::TextTooLong => "todo-text-too-long",
+            SyncErrorCode::NotFound => "todo-not-found",
+            SyncErrorCode::Disallowed => "todo-text-disallowed",
+            SyncErrorCode::Invalid => continue,
+        };
+        error.error = Some(i18n::message(id));
+    }
+    HttpResponse::Ok().json(Cased(response))
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct RestoreQuery {
+    pub backup: String,
+}
+
+fn todo_changed() -> HttpResponse {
+    ApiError::new(
+        StatusCode::PRECONDITION_FAILED,
+        ErrorCode::TodoChanged,
+        i18n::message("todo-changed"),
+    )
+    .into_response()
+}
+
+fn backups_not_configured() -> HttpResponse {
+    ApiError::not_configured(i18n::message("backups-not-configured")).into_response()
+}
+
+fn backup_error_response(error: BackupError) -> HttpResponse {
+    match error {
+        BackupError::NotFound => {
+            ApiError::not_found(i18n::message("backup-not-found")).into_response()
+        }
+        BackupError::Failed(e) => ApiError::status(StatusCode::BAD_GATEWAY, e).into_response(),
+    }
+}
+
+pub async fn admin_list_backups(req: HttpRequest, config: web::Data<Config>) -> impl Responder {
+    if let Some(resp) = reject_non_admin(&req, &config) {
+        return resp;
+    }
+    let backups = match req.app_data::<web::Data<Backups>>() {
+        Some(backups) => backups,
+        None => return backups_not_configured(),
+    };
+    match backups.list().await {
+        Ok(list) => HttpResponse::Ok().json(Cased(list)),
+        Err(e) => backup_error_response(e),
+    }
+}
+
+pub async fn admin_create_backup(
+    req: HttpRequest,
+    config: web::Data<Config>,
+    service: web::Data<TodoService>,
+) -> impl Responder {
+    if let Some(resp) = reject_non_admin(&req, &config) {
+        return resp;
+    }
+    let backups = match req.app_data::<web::Data<Backups>>() {
+        Some(backups) => backups,
+        None => return backups_not_configured(),
+    };
+    match backups.backup_now(&service).await {
+        Ok(key) => HttpResponse::Created().json(Cased(serde_json::json!({ "backup": key }))),
+        Err(e) => backup_error_response(e),
+    }
+}
+
+/// Rolls the collection back to a backup. The current state is backed up
+/// first, so a restore can itself be undone.
+pub async fn admin_restore(
+    req: HttpRequest,
+    config: web::Data<Config>,
+    service: web::Data<TodoService>,
+    query: web::Query<RestoreQuery>,
+) -> impl Responder {
+    if let Some(resp) = reject_non_admin(&req, &config) {
+        return resp;
+    }
+    let backups = match req.app_data::<web::Data<Backups>>() {
+        Some(backups) => backups,
+        None => return backups_not_configured(),
+    };
+
+    let todos = match backups.fetch(&query.backup).await {
+        Ok(todos) => todos,
+        Err(e) => return backup_error_response(e),
+    };
+    let pre_restore = match backups.backup_now(&service).await {
+        Ok(key) => key,
+        Err(e) => return backup_error_response(e),
+    };
+    let (removed, restored) = service.replace_all(todos);
+
+    HttpResponse::Ok().json(Cased(serde_json::json!({
+        "backup": query.backup,
+        "preRestoreBackup": pre_restore,
+        "removed": removed,
+        "restored": restored
+    })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{test, App};
+
+    #[actix_web::test]
+    async fn test_root() {
+        let result = test::call_service(
+            &test::init_service(App::new().route("/", web::get().to(root))).await,
+            test::TestRequest::get().uri("/").to_request(),
+        )
+        .await;
+
+        assert!(result.status().is_success());
+    }
+
+    #[actix_web::test]
+    async fn test_health() {
+        let result = test::call_service(
+            &test::init_service(App::new().route("/health", web::get().to(health))).await,
+            test::TestRequest::get().uri("/health").to_request(),
+        )
+        .await;
+
+        assert!(result.status().is_success());
+    }
+
+    #[actix_web::test]
+    async fn test_get_todos() {
+        let service = web::Data::new(TodoService::new_empty());
+        
+        let app = test::init_service(
+            App::new()
+                .app_data(service.clone())
+                .route("/api/todos", web::get().to(get_todos)),
+        )
+        .await;
+
+        let req = test::TestRequest::get().uri("/api/todos").to_request();
+        let resp = test::call_service(&app, req).await;
+
+        assert!(resp.status().is_success());
+    }
+
+    #[actix_web::test]
+    async fn test_create_todo() {
+        let service = web::Data::new(TodoService::new_empty());
+        
+        let app = test::init_service(
+            App::new()
+                .app_data(service.clone())
+                .route("/api/todos", web::post().to(create_todo)),
+        )
+        .await;
+
+        let req = test::TestRequest::post()
+            .uri("/api/todos")
+            .set_json(serde_json::json!({
+                "text": "Test Todo",
+                "priority": "high"
+            }))
+            .to_request();
+
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 201);
+    }
+
+    #[actix_web::test]
+    async fn test_create_todo_empty_text() {
+        let service = web::Data::new(TodoService::new_empty());
+        
+        let app = test::init_service(
+            App::new()
+                .app_data(service.clone())
+                .route("/api/todos", web::post().to(create_todo)),
+        )
+        .await;
+
+        let req = test::TestRequest::post()
+            .uri("/api/todos")
+            .set_json(serde_json::json!({
+                "text": ""
+            }))
+            .to_request();
+
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 400);
+    }
+}
+
@@ -0,0 +1,3528 @@
+#[cfg(test)]
+mod handlers_tests {
+    use crate::config::Config;
+    use crate::handlers::*;
+    use crate::metrics::Metrics;
+    use crate::preferences::PreferenceStore;
+    use crate::webhooks::WebhookService;
+    use spicy_todo_core::models::{todo_id, Priority, Recurrence, TodoCreate};
+    use spicy_todo_core::rollover::RolloverMode;
+    use spicy_todo_core::service::TodoService;
+    use actix_web::{test, web, App};
+
+    #[actix_web::test]
+    async fn test_root_endpoint() {
+        let app = test::init_service(App::new().route("/", web::get().to(root))).await;
+
+        let req = test::TestRequest::get().uri("/").to_request();
+        let resp = test::call_service(&app, req).await;
+
+        assert!(resp.status().is_success());
+        
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["message"], "🌶️ Spicy Todo API - Rust/Actix Implementation");
+        assert_eq!(body["version"], "1.0.0");
+    }
+
+    #[actix_web::test]
+    async fn test_health_endpoint() {
+        let app = test::init_service(App::new().route("/health", web::get().to(health))).await;
+
+        let req = test::TestRequest::get().uri("/health").to_request();
+        let resp = test::call_service(&app, req).await;
+
+        assert!(resp.status().is_success());
+        
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["status"], "healthy");
+        assert_eq!(body["service"], "spicy-todo-rust-api");
+        assert!(body["uptime"].is_u64());
+    }
+
+    #[actix_web::test]
+    async fn test_health_ready_endpoint() {
+        let service = web::Data::new(TodoService::new_empty());
+        let app = test::init_service(
+            App::new()
+                .app_data(service.clone())
+                .route("/health/ready", web::get().to(health_ready)),
+        )
+        .await;
+
+        let req = test::TestRequest::get().uri("/health/ready").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 200);
+
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["status"], "ready");
+        assert_eq!(body["components"]["storage"]["status"], "up");
+        assert_eq!(body["components"]["eventLog"]["status"], "up");
+    }
+
+    #[actix_web::test]
+    async fn test_get_todos_empty() {
+        let service = web::Data::new(TodoService::new_empty());
+        let app = test::init_service(
+            App::new()
+                .app_data(service.clone())
+                .route("/api/todos", web::get().to(get_todos)),
+        )
+        .await;
+
+        let req = test::TestRequest::get().uri("/api/todos").to_request();
+        let resp = test::call_service(&app, req).await;
+
+        assert!(resp.status().is_success());
+        
+        let body: Vec<serde_json::Value> = test::read_body_json(resp).await;
+        assert_eq!(body.len(), 0);
+    }
+
+    #[actix_web::test]
+    async fn test_get_todos_with_data() {
+        let service = web::Data::new(TodoService::new());
+        let app = test::init_service(
+            App::new()
+                .app_data(service.clone())
+                .route("/api/todos", web::get().to(get_todos)),
+        )
+        .await;
+
+        let req = test::TestRequest::get().uri("/api/todos").to_request();
+        let resp = test::call_service(&app, req).await;
+
+        assert!(resp.status().is_success());
+        
+        let body: Vec<serde_json::Value> = test::read_body_json(resp).await;
+        assert!(!body.is_empty());
+    }
+
+    #[actix_web::test]
+    async fn test_get_todos_with_filter() {
+        let service = web::Data::new(TodoService::new());
+        let app = test::init_service(
+            App::new()
+                .app_data(service.clone())
+                .route("/api/todos", web::get().to(get_todos)),
+        )
+        .await;
+
+        let req = test::TestRequest::get()
+            .uri("/api/todos?filter=active")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+
+        assert!(resp.status().is_success());
+    }
+
+    #[actix_web::test]
+    async fn test_get_todos_with_search() {
+        let service = web::Data::new(TodoService::new());
+        let app = test::init_service(
+            App::new()
+                .app_data(service.clone())
+                .route("/api/todos", web::get().to(get_todos)),
+        )
+        .await;
+
+        let req = test::TestRequest::get()
+            .uri("/api/todos?search=Rust")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+
+        assert!(resp.status().is_success());
+    }
+
+    #[actix_web::test]
+    async fn test_get_todos_with_priority() {
+        let service = web::Data::new(TodoService::new());
+        let app = test::init_service(
+            App::new()
+                .app_data(service.clone())
+                .route("/api/todos", web::get().to(get_todos)),
+        )
+        .await;
+
+        let req = test::TestRequest::get()
+            .uri("/api/todos?priority=high")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+
+        assert!(resp.status().is_success());
+    }
+
+    #[actix_web::test]
+    async fn test_create_todo_success() {
+        let service = web::Data::new(TodoService::new_empty());
+        let app = test::init_service(
+            App::new()
+                .app_data(service.clone())
+                .route("/api/todos", web::post().to(create_todo)),
+        )
+        .await;
+
+        let req = test::TestRequest::post()
+            .uri("/api/todos")
+            .set_json(serde_json::json!({
+                "text": "Test Todo",
+                "priority": "high",
+                "completed": false,
+                "dueDate": "2024-12-31",
+                "reminderTime": "10:00"
+            }))
+            .to_request();
+
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 201);
+
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["text"], "Test Todo");
+        assert_eq!(body["priority"], "high");
+    }
+
+    #[actix_web::test]
+    async fn test_create_todo_with_defaults() {
+        let service = web::Data::new(TodoService::new_empty());
+        let app = test::init_service(
+            App::new()
+                .app_data(service.clone())
+                .route("/api/todos", web::post().to(create_todo)),
+        )
+        .await;
+
+        let req = test::TestRequest::post()
+            .uri("/api/todos")
+            .set_json(serde_json::json!({
+                "text": "Test Todo"
+            }))
+            .to_request();
+
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 201);
+
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["text"], "Test Todo");
+        assert_eq!(body["priority"], "medium"); // default
+        assert_eq!(body["completed"], false); // default
+    }
+
+    #[actix_web::test]
+    async fn test_create_todo_empty_text() {
+        let service = web::Data::new(TodoService::new_empty());
+        let app = test::init_service(
+            App::new()
+                .app_data(service.clone())
+                .route("/api/todos", web::post().to(create_todo)),
+        )
+        .await;
+
+        let req = test::TestRequest::post()
+            .uri("/api/todos")
+            .set_json(serde_json::json!({
+                "text": ""
+            }))
+            .to_request();
+
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 400);
+    }
+
+    #[actix_web::test]
+    async fn test_create_todo_whitespace_text() {
+        let service = web::Data::new(TodoService::new_empty());
+        let app = test::init_service(
+            App::new()
+                .app_data(service.clone())
+                .route("/api/todos", web::post().to(create_todo)),
+        )
+        .await;
+
+        let req = test::TestRequest::post()
+            .uri("/api/todos")
+            .set_json(serde_json::json!({
+                "text": "   "
+            }))
+            .to_request();
+
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 400);
+    }
+
+    #[actix_web::test]
+    async fn test_create_todo_too_long() {
+        let service = web::Data::new(TodoService::new_empty());
+        let app = test::init_service(
+            App::new()
+                .app_data(service.clone())
+                .route("/api/todos", web::post().to(create_todo)),
+        )
+        .await;
+
+        let long_text = "a".repeat(501);
+        let req = test::TestRequest::post()
+            .uri("/api/todos")
+            .set_json(serde_json::json!({
+                "text": long_text
+            }))
+            .to_request();
+
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 400);
+    }
+
+    #[actix_web::test]
+    async fn test_get_todo_by_id_success() {
+        let service = web::Data::new(TodoService::new_empty());
+        
+        // Create a todo first
+        let created = service.create(TodoCreate {
+            text: "Test Todo".to_string(),
+            priority: Some(Priority::High),
+            completed: Some(false),
+            ..Default::default()
+        });
+
+        let app = test::init_service(
+            App::new()
+                .app_data(service.clone())
+                .route("/api/todos/{id}", web::get().to(get_todo)),
+        )
+        .await;
+
+        let req = test::TestRequest::get()
+            .uri(&format!("/api/todos/{}", created.id))
+            .to_request();
+
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 200);
+
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["id"], created.id.to_string());
+        assert_eq!(body["text"], "Test Todo");
+    }
+
+    #[actix_web::test]
+    async fn test_get_todo_by_id_not_found() {
+        let service = web::Data::new(TodoService::new_empty());
+        let app = test::init_service(
+            App::new()
+                .app_data(service.clone())
+                .route("/api/todos/{id}", web::get().to(get_todo)),
+        )
+        .await;
+
+        let req = test::TestRequest::get()
+            .uri("/api/todos/non-existent-id")
+            .to_request();
+
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 404);
+    }
+
+    #[actix_web::test]
+    async fn test_update_todo_success() {
+        let service = web::Data::new(TodoService::new_empty());
+        
+        // Create a todo first
+        let created = service.create(TodoCreate {
+            text: "Original".to_string(),
+            priority: Some(Priority::Low),
+            completed: Some(false),
+            ..Default::default()
+        });
+
+        let app = test::init_service(
+            App::new()
+                .app_data(service.clone())
+                .route("/api/todos/{id}", web::put().to(update_todo)),
+        )
+        .await;
+
+        let req = test::TestRequest::put()
+            .uri(&format!("/api/todos/{}", created.id))
+            .set_json(serde_json::json!({
+                "text": "Updated",
+                "priority": "high",
+                "completed": true
+            }))
+            .to_request();
+
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 200);
+
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["text"], "Updated");
+        assert_eq!(body["priority"], "high");
+        assert_eq!(body["completed"], true);
+    }
+
+    #[actix_web::test]
+    async fn test_update_todo_partial() {
+        let service = web::Data::new(TodoService::new_empty());
+        
+        let created = service.create(TodoCreate {
+            text: "Original".to_string(),
+            priority: Some(Priority::Medium),
+            completed: Some(false),
+            ..Default::default()
+        });
+
+        let app = test::init_service(
+            App::new()
+                .app_data(service.clone())
+                .route("/api/todos/{id}", web::put().to(update_todo)),
+        )
+        .await;
+
+        let req = test::TestRequest::put()
+            .uri(&format!("/api/todos/{}", created.id))
+            .set_json(serde_json::json!({
+                "text": "Only text updated"
+            }))
+            .to_request();
+
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 200);
+
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["text"], "Only text updated");
+        assert_eq!(body["priority"], "medium"); // unchanged
+    }
+
+    #[actix_web::test]
+    async fn test_update_todo_not_found() {
+        let service = web::Data::new(TodoService::new_empty());
+        let app = test::init_service(
+            App::new()
+                .app_data(service.clone())
+                .route("/api/todos/{id}", web::put().to(update_todo)),
+        )
+        .await;
+
+        let req = test::TestRequest::put()
+            .uri("/api/todos/non-existent-id")
+            .set_json(serde_json::json!({
+                "text": "Updated"
+            }))
+            .to_request();
+
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 404);
+    }
+
+    #[actix_web::test]
+    async fn test_delete_todo_success() {
+        let service = web::Data::new(TodoService::new_empty());
+        
+        let created = service.create(TodoCreate {
+            text: "To Delete".to_string(),
+            ..Default::default()
+        });
+
+        let app = test::init_service(
+            App::new()
+                .app_data(service.clone())
+                .route("/api/todos/{id}", web::delete().to(delete_todo)),
+        )
+        .await;
+
+        let req = test::TestRequest::delete()
+            .uri(&format!("/api/todos/{}", created.id))
+            .to_request();
+
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 200);
+
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["message"], "Todo deleted successfully");
+    }
+
+    #[actix_web::test]
+    async fn test_delete_todo_applies_configured_cascade() {
+        let service = web::Data::new(TodoService::new_empty());
+        let created = service.create(TodoCreate {
+            text: "Private".to_string(),
+            due_date: Some("2024-06-09".to_string()),
+            recurrence: Some(Recurrence::Daily),
+            ..Default::default()
+        });
+        let today = chrono::NaiveDate::from_ymd_opt(2024, 6, 10).unwrap();
+        service.roll_over(today, RolloverMode::MarkMissed);
+        let config = web::Data::new(Config {
+            delete_cascade: "history".parse().unwrap(),
+            ..Config::default()
+        });
+
+        let app = test::init_service(
+            App::new()
+                .app_data(service.clone())
+                .app_data(config)
+                .route("/api/todos/{id}", web::delete().to(delete_todo)),
+        )
+        .await;
+
+        let req = test::TestRequest::delete()
+            .uri(&format!("/api/todos/{}", created.id))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 200);
+
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["removed"]["missedOccurrences"], 0);
+        assert_eq!(body["removed"]["history"], 2);
+        assert_eq!(service.missed_occurrences(created.id).len(), 1);
+    }
+
+    #[actix_web::test]
+    async fn test_delete_todo_not_found() {
+        let service = web::Data::new(TodoService::new_empty());
+        let app = test::init_service(
+            App::new()
+                .app_data(service.clone())
+                .route("/api/todos/{id}", web::delete().to(delete_todo)),
+        )
+        .await;
+
+        let req = test::TestRequest::delete()
+            .uri("/api/todos/non-existent-id")
+            .to_request();
+
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 404);
+    }
+
+    #[actix_web::test]
+    async fn test_toggle_todo_success() {
+        let service = web::Data::new(TodoService::new_empty());
+        
+        let created = service.create(TodoCreate {
+            text: "To Toggle".to_string(),
+            completed: Some(false),
+            ..Default::default()
+        });
+
+        let app = test::init_service(
+            App::new()
+                .app_data(service.clone())
+                .route("/api/todos/{id}/toggle", web::patch().to(toggle_todo)),
+        )
+        .await;
+
+        // First toggle
+        let req = test::TestRequest::patch()
+            .uri(&format!("/api/todos/{}/toggle", created.id))
+            .to_request();
+
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 200);
+
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["completed"], true);
+
+        // Second toggle
+        let req = test::TestRequest::patch()
+            .uri(&format!("/api/todos/{}/toggle", created.id))
+            .to_request();
+
+        let resp = test::call_service(&app, req).await;
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["completed"], false);
+    }
+
+    #[actix_web::test]
+    async fn test_toggle_todo_not_found() {
+        let service = web::Data::new(TodoService::new_empty());
+        let app = test::init_service(
+            App::new()
+                .app_data(service.clone())
+                .route("/api/todos/{id}/toggle", web::patch().to(toggle_todo)),
+        )
+        .await;
+
+        let req = test::TestRequest::patch()
+            .uri("/api/todos/non-existent-id/toggle")
+            .to_request();
+
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 404);
+    }
+
+    #[actix_web::test]
+    async fn test_get_stats() {
+        let service = web::Data::new(TodoService::new_empty());
+        let app = test::init_service(
+            App::new()
+                .app_data(service.clone())
+                .route("/api/todos/stats/summary", web::get().to(get_stats)),
+        )
+        .await;
+
+        let req = test::TestRequest::get()
+            .uri("/api/todos/stats/summary")
+            .to_request();
+
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 200);
+
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert!(body["total"].is_number());
+        assert!(body["active"].is_number());
+        assert!(body["completed"].is_number());
+    }
+
+    #[actix_web::test]
+    async fn test_get_stats_counts_hidden_states() {
+        let service = web::Data::new(TodoService::new_empty());
+        service.create(TodoCreate {
+            text: "Visible".to_string(),
+            ..Default::default()
+        });
+        let app = test::init_service(
+            App::new()
+                .app_data(service.clone())
+                .route("/api/todos/stats/summary", web::get().to(get_stats)),
+        )
+        .await;
+
+        for uri in ["/api/todos/stats/summary", "/api/todos/stats/summary?include_hidden=true"] {
+            let req = test::TestRequest::get().uri(uri).to_request();
+            let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+            assert_eq!(body["total"], 1);
+            assert_eq!(body["archived"], 0);
+            assert_eq!(body["trashed"], 0);
+            assert_eq!(body["deferred"], 0);
+        }
+
+        let req = test::TestRequest::get()
+            .uri("/api/todos/stats/summary?include_hidden=maybe")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 400);
+    }
+
+    #[actix_web::test]
+    async fn test_clear_completed() {
+        let service = web::Data::new(TodoService::new_empty());
+        let app = test::init_service(
+            App::new()
+                .app_data(service.clone())
+                .route("/api/todos/completed", web::delete().to(clear_completed)),
+        )
+        .await;
+
+        let req = test::TestRequest::delete()
+            .uri("/api/todos/completed")
+            .to_request();
+
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 200);
+
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["message"], "Completed todos cleared");
+    }
+
+    #[actix_web::test]
+    async fn test_get_todos_conditional_etag() {
+        let service = web::Data::new(TodoService::new());
+        let app = test::init_service(
+            App::new()
+                .app_data(service.clone())
+                .route("/api/todos", web::get().to(get_todos)),
+        )
+        .await;
+
+        let req = test::TestRequest::get().uri("/api/todos").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 200);
+        let etag = resp.headers().get("etag").unwrap().to_str().unwrap().to_string();
+        assert!(resp.headers().contains_key("last-modified"));
+
+        // Same version: 304 with no body
+        let req = test::TestRequest::get()
+            .uri("/api/todos")
+            .insert_header(("If-None-Match", etag.clone()))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 304);
+        let body = test::read_body(resp).await;
+        assert!(body.is_empty());
+
+        // A different filter is a different representation
+        let req = test::TestRequest::get()
+            .uri("/api/todos?filter=active")
+            .insert_header(("If-None-Match", etag.clone()))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 200);
+
+        // So are MessagePack and snake_case
+        for (name, value) in [("Accept", "application/msgpack"), ("X-Json-Case", "snake")] {
+            let req = test::TestRequest::get()
+                .uri("/api/todos")
+                .insert_header(("If-None-Match", etag.clone()))
+                .insert_header((name, value))
+                .to_request();
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(resp.status(), 200, "{}", name);
+        }
+
+        // A mutation invalidates the tag
+        service.create(TodoCreate {
+            text: "New".to_string(),
+            ..Default::default()
+        });
+        let req = test::TestRequest::get()
+            .uri("/api/todos")
+            .insert_header(("If-None-Match", etag))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 200);
+    }
+
+    #[actix_web::test]
+    async fn test_get_todos_conditional_last_modified() {
+        let service = web::Data::new(TodoService::new());
+        let app = test::init_service(
+            App::new()
+                .app_data(service.clone())
+                .route("/api/todos", web::get().to(get_todos)),
+        )
+        .await;
+
+        let req = test::TestRequest::get().uri("/api/todos").to_request();
+        let resp = test::call_service(&app, req).await;
+        let last_modified = resp.headers().get("last-modified").unwrap().clone();
+
+        let req = test::TestRequest::get()
+            .uri("/api/todos")
+            .insert_header(("If-Modified-Since", last_modified))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 304);
+
+        let req = test::TestRequest::get()
+            .uri("/api/todos")
+            .insert_header(("If-Modified-Since", "Thu, 01 Jan 2015 00:00:00 GMT"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 200);
+    }
+
+    #[actix_web::test]
+    async fn test_webhook_lifecycle() {
+        let webhooks = web::Data::new(WebhookService::new());
+        let app = test::init_service(
+            App::new()
+                .app_data(webhooks.clone())
+                .route("/api/webhooks", web::get().to(get_webhooks))
+                .route("/api/webhooks", web::post().to(create_webhook))
+                .route("/api/webhooks/{id}", web::delete().to(delete_webhook)),
+        )
+        .await;
+
+        let req = test::TestRequest::post()
+            .uri("/api/webhooks")
+            .set_json(serde_json::json!({
+                "url": "http://localhost:9000/hook",
+                "events": ["todo.completed"],
+                "filter": "todo.priority == 'high'"
+            }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 201);
+
+        let created: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(created["filter"], "todo.priority == 'high'");
+        assert_eq!(created["events"][0], "todo.completed");
+
+        let req = test::TestRequest::get().uri("/api/webhooks").to_request();
+        let body: Vec<serde_json::Value> = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body.len(), 1);
+
+        let req = test::TestRequest::delete()
+            .uri(&format!("/api/webhooks/{}", created["id"].as_str().unwrap()))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 200);
+    }
+
+    #[actix_web::test]
+    async fn test_create_webhook_invalid_filter() {
+        let webhooks = web::Data::new(WebhookService::new());
+        let app = test::init_service(
+            App::new()
+                .app_data(webhooks.clone())
+                .route("/api/webhooks", web::post().to(create_webhook)),
+        )
+        .await;
+
+        let req = test::TestRequest::post()
+            .uri("/api/webhooks")
+            .set_json(serde_json::json!({
+                "url": "http://localhost:9000/hook",
+                "filter": "todo.priority =="
+            }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 400);
+    }
+
+    #[actix_web::test]
+    async fn test_get_todos_msgpack() {
+        let service = web::Data::new(TodoService::new());
+        let app = test::init_service(
+            App::new()
+                .app_data(service.clone())
+                .route("/api/todos", web::get().to(get_todos)),
+        )
+        .await;
+
+        let req = test::TestRequest::get()
+            .uri("/api/todos")
+            .insert_header(("Accept", "application/msgpack"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 200);
+        assert_eq!(
+            resp.headers().get("content-type").unwrap(),
+            "application/msgpack"
+        );
+
+        let body = test::read_body(resp).await;
+        let todos: Vec<serde_json::Value> = rmp_serde::from_slice(&body).unwrap();
+        assert_eq!(todos.len(), 3);
+        assert!(todos[0]["createdAt"].is_string());
+    }
+
+    #[actix_web::test]
+    async fn test_get_todos_compressed() {
+        let service = web::Data::new(TodoService::new());
+        let app = test::init_service(
+            App::new()
+                .wrap(actix_web::middleware::Compress::default())
+                .app_data(service.clone())
+                .route("/api/todos", web::get().to(get_todos)),
+        )
+        .await;
+
+        let req = test::TestRequest::get()
+            .uri("/api/todos")
+            .insert_header(("Accept-Encoding", "gzip"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 200);
+        assert_eq!(resp.headers().get("content-encoding").unwrap(), "gzip");
+    }
+
+    #[actix_web::test]
+    async fn test_replay_webhook() {
+        let service = web::Data::new(TodoService::new_empty());
+        let webhooks = web::Data::new(WebhookService::new());
+        let app = test::init_service(
+            App::new()
+                .app_data(service.clone())
+                .app_data(webhooks.clone())
+                .app_data(web::Data::new(Metrics::new()))
+                .route("/api/webhooks", web::post().to(create_webhook))
+                .route("/api/webhooks/{id}/replay", web::post().to(replay_webhook)),
+        )
+        .await;
+
+        for text in ["One", "Two", "Three"] {
+            service.create(TodoCreate {
+                text: text.to_string(),
+                ..Default::default()
+            });
+        }
+
+        let req = test::TestRequest::post()
+            .uri("/api/webhooks")
+            .set_json(serde_json::json!({
+                "url": "http://127.0.0.1:9/hook",
+                "events": ["todo.created"]
+            }))
+            .to_request();
+        let created: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        let id = created["id"].as_str().unwrap();
+
+        let req = test::TestRequest::post()
+            .uri(&format!("/api/webhooks/{}/replay?since=1", id))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 202);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["events"], 2);
+
+        let req = test::TestRequest::post()
+            .uri(&format!("/api/webhooks/{}/replay?since=last-tuesday", id))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 400);
+
+        let req = test::TestRequest::post()
+            .uri("/api/webhooks/missing/replay")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 404);
+    }
+
+    fn admin_config() -> web::Data<Config> {
+        web::Data::new(Config {
+            admin_token: Some("secret-token".to_string()),
+            ..Default::default()
+        })
+    }
+
+    #[actix_web::test]
+    async fn test_admin_seed_and_reset() {
+        let service = web::Data::new(TodoService::new_empty());
+        let app = test::init_service(
+            App::new()
+                .app_data(admin_config())
+                .app_data(service.clone())
+                .route("/api/admin/seed", web::post().to(admin_seed))
+                .route("/api/admin/reset", web::post().to(admin_reset)),
+        )
+        .await;
+
+        let req = test::TestRequest::post()
+            .uri("/api/admin/seed")
+            .insert_header(("Authorization", "Bearer secret-token"))
+            .set_json(serde_json::json!({ "fixture": "demo" }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 201);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["fixture"], "demo");
+        let seeded = body["created"].as_u64().unwrap() as usize;
+        assert_eq!(service.get_all(None, None, None).len(), seeded);
+
+        // Custom todos, replacing what's there
+        let req = test::TestRequest::post()
+            .uri("/api/admin/seed")
+            .insert_header(("X-Admin-Token", "secret-token"))
+            .set_json(serde_json::json!({
+                "todos": [{ "text": "Only me", "priority": "low" }],
+                "replace": true
+            }))
+            .to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["removed"].as_u64().unwrap() as usize, seeded);
+        assert_eq!(service.get_all(None, None, None).len(), 1);
+
+        let req = test::TestRequest::post()
+            .uri("/api/admin/reset")
+            .insert_header(("Authorization", "Bearer secret-token"))
+            .to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["removed"], 1);
+        assert!(service.get_all(None, None, None).is_empty());
+    }
+
+    #[actix_web::test]
+    async fn test_admin_endpoints_require_token() {
+        let service = web::Data::new(TodoService::new());
+        let app = test::init_service(
+            App::new()
+                .app_data(admin_config())
+                .app_data(service.clone())
+                .route("/api/admin/reset", web::post().to(admin_reset)),
+        )
+        .await;
+
+        let req = test::TestRequest::post().uri("/api/admin/reset").to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 401);
+
+        let req = test::TestRequest::post()
+            .uri("/api/admin/reset")
+            .insert_header(("Authorization", "Bearer wrong"))
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 401);
+        assert!(!service.get_all(None, None, None).is_empty());
+
+        let disabled = test::init_service(
+            App::new()
+                .app_data(web::Data::new(Config::default()))
+                .app_data(service.clone())
+                .route("/api/admin/reset", web::post().to(admin_reset)),
+        )
+        .await;
+        let req = test::TestRequest::post()
+            .uri("/api/admin/reset")
+            .insert_header(("Authorization", "Bearer anything"))
+            .to_request();
+        assert_eq!(test::call_service(&disabled, req).await.status(), 403);
+    }
+
+    #[actix_web::test]
+    async fn test_metrics_endpoint() {
+        let service = web::Data::new(TodoService::new());
+        let metrics = web::Data::new(Metrics::new());
+        let app = test::init_service(
+            App::new()
+                .wrap(actix_web::middleware::from_fn(crate::metrics::track_requests))
+                .app_data(service.clone())
+                .app_data(metrics.clone())
+                .route("/api/todos", web::get().to(get_todos))
+                .route("/metrics", web::get().to(get_metrics)),
+        )
+        .await;
+
+        let req = test::TestRequest::get().uri("/api/todos").to_request();
+        test::call_service(&app, req).await;
+
+        let req = test::TestRequest::get().uri("/metrics").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 200);
+        let body = String::from_utf8(test::read_body(resp).await.to_vec()).unwrap();
+        assert!(body.contains(
+            r#"spicy_todo_http_requests_total{method="GET",route="/api/todos",status="200"} 1"#
+        ));
+        assert!(body.contains(r#"spicy_todo_todos{state="completed"} 1"#));
+    }
+
+    #[actix_web::test]
+    async fn test_admin_grafana_dashboard() {
+        let app = test::init_service(
+            App::new()
+                .app_data(admin_config())
+                .app_data(web::Data::new(Metrics::new()))
+                .route("/api/admin/grafana-dashboard", web::get().to(admin_grafana_dashboard)),
+        )
+        .await;
+
+        let req = test::TestRequest::get().uri("/api/admin/grafana-dashboard").to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 401);
+
+        let req = test::TestRequest::get()
+            .uri("/api/admin/grafana-dashboard")
+            .insert_header(("Authorization", "Bearer secret-token"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 200);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["uid"], "spicy-todo");
+        assert_eq!(body["__inputs"][0]["name"], "DS_PROMETHEUS");
+        let expr = body["panels"][0]["targets"][0]["expr"].as_str().unwrap();
+        assert!(expr.contains("spicy_todo_http_requests_total"));
+    }
+
+    #[actix_web::test]
+    async fn test_pprof_profile_guards() {
+        let profiling_config = web::Data::new(Config {
+            admin_token: Some("secret-token".to_string()),
+            profiling_enabled: true,
+            ..Default::default()
+        });
+        let app = test::init_service(
+            App::new()
+                .app_data(profiling_config)
+                .route("/debug/pprof/profile", web::get().to(debug_pprof_profile)),
+        )
+        .await;
+
+        let req = test::TestRequest::get().uri("/debug/pprof/profile").to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 401);
+
+        let req = test::TestRequest::get()
+            .uri("/debug/pprof/profile?seconds=600")
+            .insert_header(("Authorization", "Bearer secret-token"))
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 400);
+
+        let req = test::TestRequest::get()
+            .uri("/debug/pprof/profile?format=gif")
+            .insert_header(("Authorization", "Bearer secret-token"))
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 400);
+
+        let disabled = test::init_service(
+            App::new()
+                .app_data(admin_config())
+                .route("/debug/pprof/profile", web::get().to(debug_pprof_profile)),
+        )
+        .await;
+        let req = test::TestRequest::get()
+            .uri("/debug/pprof/profile")
+            .insert_header(("Authorization", "Bearer secret-token"))
+            .to_request();
+        assert_eq!(test::call_service(&disabled, req).await.status(), 404);
+    }
+
+    #[actix_web::test]
+    async fn test_pprof_profile_capture() {
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(Config {
+                    admin_token: Some("secret-token".to_string()),
+                    profiling_enabled: true,
+                    ..Default::default()
+                }))
+                .route("/debug/pprof/profile", web::get().to(debug_pprof_profile)),
+        )
+        .await;
+
+        let req = test::TestRequest::get()
+            .uri("/debug/pprof/profile?seconds=1")
+            .insert_header(("Authorization", "Bearer secret-token"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        if cfg!(feature = "profiling") {
+            assert_eq!(resp.status(), 200);
+            assert_eq!(
+                resp.headers().get("content-type").unwrap(),
+                "application/octet-stream"
+            );
+        } else {
+            assert_eq!(resp.status(), 501);
+        }
+
+        let req = test::TestRequest::get()
+            .uri("/debug/pprof/profile?format=heap")
+            .insert_header(("Authorization", "Bearer secret-token"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        if cfg!(feature = "profiling") {
+            assert_eq!(resp.status(), 200);
+            assert!(test::read_body(resp).await.starts_with(b"heap_v2/"));
+        } else {
+            assert_eq!(resp.status(), 501);
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_debug_runtime() {
+        let service = web::Data::new(TodoService::new());
+        let runtimes = web::Data::new(crate::diagnostics::RuntimeRegistry::new());
+        runtimes.register_current();
+        let app = test::init_service(
+            App::new()
+                .app_data(admin_config())
+                .app_data(service.clone())
+                .app_data(runtimes)
+                .route("/debug/runtime", web::get().to(debug_runtime)),
+        )
+        .await;
+
+        let req = test::TestRequest::get().uri("/debug/runtime").to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 401);
+
+        let req = test::TestRequest::get()
+            .uri("/debug/runtime")
+            .insert_header(("Authorization", "Bearer secret-token"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 200);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["totals"]["runtimes"], 1);
+        assert_eq!(body["runtimes"][0]["workers"].as_array().unwrap().len(), 1);
+        assert!(body["locks"]["writes"]["acquisitions"].as_u64().unwrap() > 0);
+        assert!(body["locks"]["store"]["maxWaitMs"].is_number());
+    }
+
+    #[actix_web::test]
+    async fn test_request_deadline_exceeded() {
+        let service = web::Data::new(TodoService::new());
+        let app = test::init_service(
+            App::new()
+                .app_data(service.clone())
+                .route("/api/todos", web::get().to(get_todos))
+                .route("/api/todos", web::post().to(create_todo)),
+        )
+        .await;
+
+        let req = test::TestRequest::get()
+            .uri("/api/todos")
+            .insert_header(("X-Request-Timeout", "5s"))
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 200);
+
+        let past = (chrono::Utc::now() - chrono::Duration::seconds(1)).to_rfc3339();
+        let req = test::TestRequest::get()
+            .uri("/api/todos")
+            .insert_header(("X-Request-Deadline", past.clone()))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 504);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["diagnostics"]["stage"], "store lock");
+        assert!(body["diagnostics"]["completed"].as_array().unwrap().is_empty());
+
+        let before = service.get_all(None, None, None).len();
+        let req = test::TestRequest::post()
+            .uri("/api/todos")
+            .insert_header(("X-Request-Deadline", past))
+            .set_json(serde_json::json!({ "text": "Too late" }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 504);
+        assert_eq!(service.get_all(None, None, None).len(), before);
+
+        let req = test::TestRequest::get()
+            .uri("/api/todos")
+            .insert_header(("X-Request-Timeout", "eventually"))
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 400);
+    }
+
+    #[actix_web::test]
+    async fn test_sync_todos() {
+        let service = web::Data::new(TodoService::new_empty());
+        let app = test::init_service(
+            App::new()
+                .app_data(service.clone())
+                .route("/api/sync", web::post().to(sync_todos)),
+        )
+        .await;
+
+        let req = test::TestRequest::post()
+            .uri("/api/sync")
+            .set_json(serde_json::json!({
+                "changes": [{
+                    "op": "create",
+                    "id": "offline-1",
+                    "fields": { "text": "Written on a plane", "priority": "high" },
+                    "clientTimestamp": chrono::Utc::now().to_rfc3339()
+                }]
+            }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 200);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["fullResync"], true);
+        assert_eq!(body["applied"], serde_json::json!(["offline-1"]));
+        assert_eq!(body["upserted"][0]["priority"], "high");
+        let cursor = body["cursor"].as_u64().unwrap();
+
+        let req = test::TestRequest::post()
+            .uri("/api/sync")
+            .set_json(serde_json::json!({
+                "cursor": cursor,
+                "changes": [{
+                    "op": "create",
+                    "id": "offline-1",
+                    "fields": { "text": "Duplicate" },
+                    "clientTimestamp": "2020-01-01T00:00:00Z"
+                }]
+            }))
+            .to_request();
+        let body: serde_json::Value =
+            test::read_body_json(test::call_service(&app, req).await).await;
+        assert_eq!(body["conflicts"][0]["resolution"], "serverWins");
+        assert_eq!(body["cursor"], cursor);
+        assert!(body["upserted"].as_array().unwrap().is_empty());
+        assert_eq!(
+            service.get_by_id(todo_id("offline-1")).unwrap().text,
+            "Written on a plane"
+        );
+
+        let req = test::TestRequest::post()
+            .uri("/api/sync")
+            .set_json(serde_json::json!({ "changes": [{ "op": "rename" }] }))
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 400);
+    }
+
+    #[actix_web::test]
+    async fn test_sync_errors_are_worded_in_the_request_language() {
+        use crate::i18n;
+
+        let service = web::Data::new(TodoService::new_empty());
+        let app = test::init_service(
+            App::new()
+                .wrap(actix_web::middleware::from_fn(i18n::localize_responses))
+                .app_data(service.clone())
+                .route("/api/sync", web::post().to(sync_todos)),
+        )
+        .await;
+
+        let req = test::TestRequest::post()
+            .uri("/api/sync")
+            .insert_header(("Accept-Language", "de"))
+            .set_json(serde_json::json!({
+                "changes": [
+                    { "op": "create", "id": "", "fields": { "text": "x" },
+                      "clientTimestamp": "2020-01-01T00:00:00Z" },
+                    { "op": "create", "id": "no-text",
+                      "clientTimestamp": "2020-01-01T00:00:00Z" },
+                    { "op": "create", "id": "slow", "fields": { "text": "x", "estimateMinutes": 0 },
+                      "clientTimestamp": "2020-01-01T00:00:00Z" }
+                ]
+            }))
+            .to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["errors"][0]["code"], "idRequired");
+        assert_eq!(
+            body["errors"][0]["error"],
+            "Die Änderungs-ID ist erforderlich"
+        );
+        assert_eq!(body["errors"][1]["code"], "textRequired");
+        assert_eq!(
+            body["errors"][1]["error"],
+            "Der Aufgabentext ist erforderlich"
+        );
+        assert_eq!(body["errors"][2]["code"], "invalid");
+        assert!(body["errors"][2]["error"]
+            .as_str()
+            .unwrap()
+            .contains("estimateMinutes"));
+    }
+
+    #[actix_web::test]
+    async fn test_get_changes() {
+        let service = web::Data::new(TodoService::new_empty());
+        let app = test::init_service(
+            App::new()
+                .app_data(service.clone())
+                .route("/api/todos/changes", web::get().to(get_changes)),
+        )
+        .await;
+        let todo = service.create(TodoCreate {
+            text: "Mirrored".to_string(),
+            ..Default::default()
+        });
+        service.delete(todo.id);
+
+        let req = test::TestRequest::get()
+            .uri("/api/todos/changes?since=1")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 200);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["cursor"], 2);
+        assert_eq!(body["changes"][0]["id"], todo.id.to_string());
+        assert_eq!(body["changes"][0]["deleted"], true);
+        assert!(body["changes"][0].get("todo").is_none());
+
+        let req = test::TestRequest::get()
+            .uri("/api/todos/changes?since=99")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 410);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["latest"], 2);
+    }
+
+    #[actix_web::test]
+    async fn test_get_conformance() {
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(Config::default()))
+                .route("/api/conformance", web::get().to(get_conformance)),
+        )
+        .await;
+
+        let req = test::TestRequest::get().uri("/api/conformance").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 200);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["implementation"], "rust-actix");
+        assert_eq!(body["features"]["graphql"]["supported"], false);
+        assert_eq!(body["features"]["sync"]["endpoints"][0], "/api/sync");
+    }
+
+    #[actix_web::test]
+    async fn test_not_found_suggestions() {
+        let service = web::Data::new(TodoService::new_empty());
+        let kept = service.create(TodoCreate {
+            text: "Kept".to_string(),
+            ..Default::default()
+        });
+        let config = Config {
+            suggest_missing_ids: true,
+            ..Config::default()
+        };
+        let app = test::init_service(
+            App::new()
+                .app_data(service.clone())
+                .app_data(web::Data::new(config))
+                .route("/api/todos/{id}", web::get().to(get_todo)),
+        )
+        .await;
+
+        let mut typo = kept.id.to_string();
+        let last = if typo.ends_with('0') { "1" } else { "0" };
+        typo.replace_range(typo.len() - 1.., last);
+        let req = test::TestRequest::get()
+            .uri(&format!("/api/todos/{}", typo))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 404);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["error"], "Todo not found");
+        assert_eq!(body["suggestions"]["similar"][0], kept.id.to_string());
+
+        let req = test::TestRequest::get()
+            .uri(&format!("/api/todos/{}", uuid::Uuid::new_v4()))
+            .to_request();
+        let body: serde_json::Value =
+            test::read_body_json(test::call_service(&app, req).await).await;
+        assert!(body.get("suggestions").is_none());
+    }
+
+    #[actix_web::test]
+    async fn test_get_todos_paginated_with_meta() {
+        let service = web::Data::new(TodoService::new_empty());
+        for (text, completed, due) in [
+            ("Late report", false, Some("2000-01-01")),
+            ("Late taxes", true, Some("2000-01-01")),
+            ("Report draft", false, None),
+            ("Groceries", false, None),
+        ] {
+            service.create(TodoCreate {
+                text: text.to_string(),
+                completed: Some(completed),
+                due_date: due.map(str::to_string),
+                ..Default::default()
+            });
+        }
+        let app = test::init_service(
+            App::new()
+                .app_data(service.clone())
+                .route("/api/todos", web::get().to(get_todos)),
+        )
+        .await;
+
+        let req = test::TestRequest::get()
+            .uri("/api/todos?search=late&limit=1")
+            .to_request();
+        let body: serde_json::Value =
+            test::read_body_json(test::call_service(&app, req).await).await;
+        assert_eq!(body["items"].as_array().unwrap().len(), 1);
+        assert_eq!(body["total"], 2);
+        assert_eq!(body["hasMore"], true);
+        assert_eq!(body["meta"]["matching"], 2);
+        assert_eq!(body["meta"]["completed"], 1);
+        assert_eq!(body["meta"]["overdue"], 1);
+
+        let req = test::TestRequest::get().uri("/api/todos").to_request();
+        let body: serde_json::Value =
+            test::read_body_json(test::call_service(&app, req).await).await;
+        assert_eq!(body.as_array().unwrap().len(), 4);
+    }
+
+    #[actix_web::test]
+    async fn test_saved_list_preference() {
+        let service = web::Data::new(TodoService::new_empty());
+        let seeded = [("B", Priority::Low), ("A", Priority::High), ("C", Priority::High)];
+        for (text, priority) in seeded {
+            service.create(TodoCreate {
+                text: text.to_string(),
+                priority: Some(priority),
+                ..Default::default()
+            });
+        }
+        let app = test::init_service(
+            App::new()
+                .app_data(service.clone())
+                .app_data(web::Data::new(PreferenceStore::new()))
+                .route("/api/todos", web::get().to(get_todos))
+                .route("/api/todos/preferences", web::get().to(get_preference))
+                .route("/api/todos/preferences", web::put().to(put_preference))
+                .route("/api/todos/preferences", web::delete().to(delete_preference)),
+        )
+        .await;
+        let texts = |body: &serde_json::Value| -> Vec<String> {
+            body.as_array()
+                .unwrap()
+                .iter()
+                .map(|todo| todo["text"].as_str().unwrap().to_string())
+                .collect()
+        };
+
+        let req = test::TestRequest::put()
+            .uri("/api/todos/preferences")
+            .set_json(serde_json::json!({ "priority": "high" }))
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 400);
+
+        let req = test::TestRequest::put()
+            .uri("/api/todos/preferences")
+            .insert_header(("X-Client-Id", "phone"))
+            .set_json(serde_json::json!({ "priority": "high", "sort": "text", "order": "desc" }))
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 200);
+
+        let req = test::TestRequest::get()
+            .uri("/api/todos")
+            .insert_header(("X-Client-Id", "phone"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.headers().get("X-List-Preference").unwrap(), "applied");
+        let etag = resp.headers().get("etag").unwrap().clone();
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(texts(&body), vec!["C", "A"]);
+
+        // A revalidated list still says the preference shaped it
+        let req = test::TestRequest::get()
+            .uri("/api/todos")
+            .insert_header(("X-Client-Id", "phone"))
+            .insert_header(("If-None-Match", etag))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 304);
+        assert_eq!(resp.headers().get("X-List-Preference").unwrap(), "applied");
+
+        // Explicit parameters replace the saved preference entirely
+        let req = test::TestRequest::get()
+            .uri("/api/todos?sort=text")
+            .insert_header(("X-Client-Id", "phone"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.headers().get("X-List-Preference").is_none());
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(texts(&body), vec!["A", "B", "C"]);
+
+        let req = test::TestRequest::get()
+            .uri("/api/todos/preferences")
+            .insert_header(("X-Client-Id", "phone"))
+            .to_request();
+        let body: serde_json::Value =
+            test::read_body_json(test::call_service(&app, req).await).await;
+        assert_eq!(body["sort"], "text");
+
+        let req = test::TestRequest::delete()
+            .uri("/api/todos/preferences")
+            .insert_header(("X-Client-Id", "phone"))
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 204);
+        let req = test::TestRequest::get()
+            .uri("/api/todos/preferences")
+            .insert_header(("X-Client-Id", "phone"))
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 404);
+    }
+
+    #[actix_web::test]
+    async fn test_admin_restore_backup() {
+        let service = web::Data::new(TodoService::new_empty());
+        let backups = web::Data::new(crate::backups::Backups::in_memory(10));
+        let config = Config {
+            admin_token: Some("secret".to_string()),
+            ..Config::default()
+        };
+        service.create(TodoCreate {
+            text: "Before".to_string(),
+            ..Default::default()
+        });
+        let key = backups.backup_now(&service).await.unwrap();
+        service.reset();
+        service.create(TodoCreate {
+            text: "After".to_string(),
+            ..Default::default()
+        });
+
+        let app = test::init_service(
+            App::new()
+                .app_data(service.clone())
+                .app_data(backups.clone())
+                .app_data(web::Data::new(config))
+                .route("/api/admin/restore", web::post().to(admin_restore)),
+        )
+        .await;
+
+        let req = test::TestRequest::post()
+            .uri(&format!("/api/admin/restore?backup={}", key))
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 401);
+
+        let req = test::TestRequest::post()
+            .uri("/api/admin/restore?backup=backups/todos-missing.json.enc")
+            .insert_header(("Authorization", "Bearer secret"))
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 404);
+
+        let req = test::TestRequest::post()
+            .uri(&format!("/api/admin/restore?backup={}", key))
+            .insert_header(("Authorization", "Bearer secret"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 200);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["removed"], 1);
+        assert_eq!(body["restored"], 1);
+        let todos = service.get_all(None, None, None);
+        assert_eq!(todos[0].text, "Before");
+
+        // The pre-restore backup holds the state that was replaced
+        let pre_restore = body["preRestoreBackup"].as_str().unwrap();
+        assert_eq!(backups.fetch(pre_restore).await.unwrap()[0].text, "After");
+    }
+
+    #[actix_web::test]
+    async fn test_get_missed_occurrences() {
+        let service = web::Data::new(TodoService::new_empty());
+        let todo = service.create(TodoCreate {
+            text: "Stretch".to_string(),
+            due_date: Some("2024-06-08".to_string()),
+            recurrence: Some(Recurrence::Daily),
+            ..Default::default()
+        });
+        service.roll_over(
+            chrono::NaiveDate::from_ymd_opt(2024, 6, 10).unwrap(),
+            RolloverMode::MarkMissed,
+        );
+        let app = test::init_service(
+            App::new()
+                .app_data(service.clone())
+                .route("/api/todos/{id}/missed", web::get().to(get_missed_occurrences)),
+        )
+        .await;
+
+        let req = test::TestRequest::get()
+            .uri(&format!("/api/todos/{}/missed", todo.id))
+            .to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["count"], 2);
+        assert_eq!(body["missed"][0]["dueDate"], "2024-06-08");
+        assert_eq!(body["missed"][1]["dueDate"], "2024-06-09");
+
+        let req = test::TestRequest::get()
+            .uri("/api/todos/00000000-0000-0000-0000-000000000000/missed")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 404);
+    }
+
+    #[actix_web::test]
+    async fn test_export_and_import_todo_bundle() {
+        let source = web::Data::new(TodoService::new_empty());
+        let created = source.create(TodoCreate {
+            text: "Moving house".to_string(),
+            priority: Some(Priority::High),
+            ..Default::default()
+        });
+        source.toggle(created.id);
+        let app = test::init_service(
+            App::new()
+                .app_data(source.clone())
+                .route("/api/todos/{id}/export", web::get().to(export_todo)),
+        )
+        .await;
+
+        let req = test::TestRequest::get()
+            .uri(&format!("/api/todos/{}/export", created.id))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 200);
+        assert!(resp
+            .headers()
+            .get("content-disposition")
+            .is_some_and(|value| value.to_str().unwrap().contains("attachment")));
+        let bundle: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(bundle["history"].as_array().unwrap().len(), 2);
+
+        let req = test::TestRequest::get()
+            .uri("/api/todos/00000000-0000-0000-0000-000000000000/export")
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 404);
+
+        let target = web::Data::new(TodoService::new_empty());
+        let app = test::init_service(
+            App::new()
+                .app_data(target.clone())
+                .route("/api/todos/import", web::post().to(import_todo)),
+        )
+        .await;
+        let req = test::TestRequest::post()
+            .uri("/api/todos/import")
+            .set_json(&bundle)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 201);
+        let imported = target.get_by_id(created.id).unwrap();
+        assert!(imported.completed);
+        assert_eq!(imported.text, "Moving house");
+
+        let req = test::TestRequest::post()
+            .uri("/api/todos/import")
+            .set_json(&bundle)
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 409);
+    }
+
+    #[actix_web::test]
+    async fn test_quick_add_todo() {
+        let service = web::Data::new(TodoService::new_empty());
+        let app = test::init_service(
+            App::new()
+                .app_data(service.clone())
+                .route("/api/todos/quick", web::post().to(quick_add_todo)),
+        )
+        .await;
+
+        let req = test::TestRequest::post()
+            .uri("/api/todos/quick")
+            .set_json(serde_json::json!({"text": "Pay rent 2030-01-01 9am !high #finance"}))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 201);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["todo"]["text"], "Pay rent #finance");
+        assert_eq!(body["todo"]["priority"], "high");
+        assert_eq!(body["todo"]["dueDate"], "2030-01-01");
+        assert_eq!(body["todo"]["reminderTime"], "09:00");
+        assert_eq!(body["parsed"]["tags"], serde_json::json!(["finance"]));
+
+        let req = test::TestRequest::post()
+            .uri("/api/todos/quick")
+            .set_json(serde_json::json!({"text": "tomorrow !low"}))
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 400);
+        assert_eq!(service.get_all(None, None, None).len(), 1);
+    }
+
+    #[actix_web::test]
+    async fn test_due_date_phrases_are_normalized() {
+        let service = web::Data::new(TodoService::new_empty());
+        let app = test::init_service(
+            App::new()
+                .app_data(service.clone())
+                .route("/api/todos", web::post().to(create_todo))
+                .route("/api/todos/{id}", web::put().to(update_todo)),
+        )
+        .await;
+        let today = chrono::Utc::now().date_naive();
+
+        let req = test::TestRequest::post()
+            .uri("/api/todos")
+            .set_json(serde_json::json!({"text": "Renew passport", "dueDate": "in 3 days"}))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 201);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        let expected = today + chrono::Duration::days(3);
+        assert_eq!(body["dueDate"], expected.format("%Y-%m-%d").to_string());
+        let id = body["id"].as_str().unwrap().to_string();
+
+        let req = test::TestRequest::put()
+            .uri(&format!("/api/todos/{}", id))
+            .set_json(serde_json::json!({"dueDate": "Tomorrow"}))
+            .to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        let expected = today + chrono::Duration::days(1);
+        assert_eq!(body["dueDate"], expected.format("%Y-%m-%d").to_string());
+
+        let req = test::TestRequest::put()
+            .uri(&format!("/api/todos/{}", id))
+            .set_json(serde_json::json!({"dueDate": "when pigs fly"}))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 400);
+        assert_eq!(
+            service.get_by_id(id.parse().unwrap()).unwrap().due_date,
+            Some(expected.format("%Y-%m-%d").to_string())
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_nearby_todos() {
+        let service = web::Data::new(TodoService::new_empty());
+        let app = test::init_service(
+            App::new()
+                .app_data(service.clone())
+                .route("/api/todos", web::post().to(create_todo))
+                .route("/api/todos/nearby", web::get().to(get_nearby_todos)),
+        )
+        .await;
+        let create = |text: &str, location: serde_json::Value| {
+            test::TestRequest::post()
+                .uri("/api/todos")
+                .set_json(serde_json::json!({"text": text, "location": location}))
+                .to_request()
+        };
+
+        let req = create(
+            "Buy bread",
+            serde_json::json!({"name": "Bakery", "lat": 48.8570, "lng": 2.3530}),
+        );
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["location"]["name"], "Bakery");
+        let req = create("Pick up parcel", serde_json::json!({"name": "Post office"}));
+        assert_eq!(test::call_service(&app, req).await.status(), 201);
+        let req = create("Bad", serde_json::json!({"lat": 48.0}));
+        assert_eq!(test::call_service(&app, req).await.status(), 400);
+
+        let req = test::TestRequest::get()
+            .uri("/api/todos/nearby?lat=48.8566&lng=2.3522")
+            .to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["count"], 1);
+        assert_eq!(body["radiusMeters"], 1000.0);
+        assert_eq!(body["todos"][0]["text"], "Buy bread");
+        assert!(body["todos"][0]["distanceMeters"].as_f64().unwrap() < 100.0);
+
+        let invalid = [
+            "lat=95&lng=0",
+            "lat=0&lng=0&radius=0",
+            "lat=0&lng=0&radius=1e9",
+            "lat=0",
+        ];
+        for query in invalid {
+            let req = test::TestRequest::get()
+                .uri(&format!("/api/todos/nearby?{}", query))
+                .to_request();
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(resp.status(), 400, "{}", query);
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_geo_trigger_dispatches_once_per_cooldown() {
+        use crate::geofence::GeofenceLog;
+        use crate::push::{PushService, PushSubscription, PushTarget};
+        use crate::reminders::Channels;
+        use actix_web::{HttpResponse, HttpServer};
+        use spicy_todo_core::models::Location;
+        use std::time::Duration;
+
+        // Stand-in for an ntfy server, taking every message.
+        let ntfy = HttpServer::new(|| {
+            App::new().default_service(web::to(|| async { HttpResponse::Ok().finish() }))
+        })
+        .workers(1)
+        .bind(("127.0.0.1", 0))
+        .unwrap();
+        let ntfy_addr = ntfy.addrs()[0];
+        actix_rt::spawn(ntfy.run());
+
+        let service = web::Data::new(TodoService::new_empty());
+        let create = |text: &str, location: Option<Location>| {
+            service.create(TodoCreate {
+                text: text.to_string(),
+                location,
+                ..Default::default()
+            })
+        };
+        let bread = create(
+            "Buy bread",
+            Some(Location {
+                name: Some("Bakery".to_string()),
+                lat: Some(48.8570),
+                lng: Some(2.3530),
+            }),
+        );
+        let plain = create("Call bank", None);
+        let push = web::Data::new(PushService::new());
+        let subscribe = |server: String| {
+            push.set(
+                "phone",
+                PushSubscription {
+                    targets: vec![PushTarget::Ntfy {
+                        server,
+                        topic: "errands".to_string(),
+                        token: None,
+                    }],
+                    min_priority: Priority::Low,
+                },
+            )
+        };
+        let channels = web::Data::new(Channels {
+            preferences: web::Data::new(PreferenceStore::new()),
+            push: push.clone(),
+            sms: None,
+            matrix: None,
+            web_push: None,
+        });
+        let app = test::init_service(
+            App::new()
+                .app_data(service.clone())
+                .app_data(web::Data::new(Config::default()))
+                .app_data(channels)
+                .app_data(web::Data::new(GeofenceLog::new(Duration::from_secs(3600))))
+                .route(
+                    "/api/todos/{id}/reminders/geo-trigger",
+                    web::post().to(geo_trigger_reminder),
+                ),
+        )
+        .await;
+        let trigger = |id: &str, lat: f64, lng: f64| {
+            test::TestRequest::post()
+                .uri(&format!("/api/todos/{}/reminders/geo-trigger", id))
+                .set_json(serde_json::json!({"lat": lat, "lng": lng}))
+                .to_request()
+        };
+
+        // A reminder no channel took doesn't use up the cooldown.
+        subscribe("http://127.0.0.1:1".to_string());
+        for _ in 0..2 {
+            let req = trigger(&bread.id.to_string(), 48.8571, 2.3531);
+            let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+            assert_eq!(body["dispatched"], false);
+            assert_eq!(body["reason"], "No channel delivered the reminder");
+            assert_eq!(body["delivery"]["pushTargets"], 0);
+        }
+
+        subscribe(format!("http://{}", ntfy_addr));
+        let req = trigger(&bread.id.to_string(), 48.8571, 2.3531);
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["dispatched"], true);
+        assert_eq!(body["delivery"]["pushTargets"], 1);
+        assert_eq!(body["delivery"]["matrix"], false);
+        assert_eq!(body["delivery"]["webPush"], 0);
+
+        let req = trigger(&bread.id.to_string(), 48.8570, 2.3530);
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["dispatched"], false);
+        assert!(body["nextAllowedAt"].is_string());
+
+        let cases = [
+            (bread.id.to_string(), 48.9, 2.3530, 422),
+            (plain.id.to_string(), 48.8570, 2.3530, 422),
+            (bread.id.to_string(), 91.0, 2.3530, 400),
+            (uuid::Uuid::new_v4().to_string(), 48.8570, 2.3530, 404),
+        ];
+        for (id, lat, lng, status) in cases {
+            let resp = test::call_service(&app, trigger(&id, lat, lng)).await;
+            assert_eq!(resp.status(), status, "{} {}", id, lat);
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_digest_renders_plain_text() {
+        let service = web::Data::new(TodoService::new_empty());
+        let today = chrono::Utc::now().date_naive();
+        service.create(TodoCreate {
+            text: "Pay rent".to_string(),
+            priority: Some(Priority::High),
+            due_date: Some(today.format("%Y-%m-%d").to_string()),
+            ..Default::default()
+        });
+        let app = test::init_service(
+            App::new()
+                .app_data(service.clone())
+                .route("/api/todos/digest", web::get().to(get_digest)),
+        )
+        .await;
+
+        let req = test::TestRequest::get().uri("/api/todos/digest").to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["dueToday"][0]["text"], "Pay rent");
+        assert_eq!(body["days"], 7);
+
+        let req = test::TestRequest::get()
+            .uri("/api/todos/digest?format=text&priority=false")
+            .insert_header(("Accept-Language", "de-CH, en;q=0.5"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 200);
+        assert_eq!(resp.headers().get("content-language").unwrap(), "de-DE");
+        let body = test::read_body(resp).await;
+        let text = std::str::from_utf8(&body).unwrap();
+        let date = today.format("%d.%m.%Y");
+        assert!(text.starts_with(&format!("Agenda for {}\n", date)));
+        assert!(text.contains(&format!("1. Pay rent. Due {}.\n", date)));
+        assert!(!text.contains("priority"));
+
+        for query in ["format=html", "locale=xx", "days=1000"] {
+            let req = test::TestRequest::get()
+                .uri(&format!("/api/todos/digest?{}", query))
+                .to_request();
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(resp.status(), 400, "{}", query);
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_homeassistant_sensor_and_services() {
+        let service = web::Data::new(TodoService::new_empty());
+        let app = test::init_service(
+            App::new()
+                .app_data(service.clone())
+                .route("/api/homeassistant/sensor", web::get().to(get_homeassistant_sensor))
+                .route(
+                    "/api/homeassistant/services/{service}",
+                    web::post().to(call_homeassistant_service),
+                ),
+        )
+        .await;
+        let call = |name: &str, data: serde_json::Value| {
+            test::TestRequest::post()
+                .uri(&format!("/api/homeassistant/services/{}", name))
+                .set_json(data)
+                .to_request()
+        };
+
+        let req = call("add_todo", serde_json::json!({"text": "Pay rent today !high"}));
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["todo"]["text"], "Pay rent");
+        assert_eq!(body["todo"]["priority"], "high");
+        assert_eq!(body["sensor"]["state"], 1);
+        assert_eq!(body["sensor"]["attributes"]["dueToday"], 1);
+        let req = call("add_todo", serde_json::json!({"text": "Water plants"}));
+        test::call_service(&app, req).await;
+
+        let req = call("complete_todo", serde_json::json!({"text": "pay rent"}));
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["todo"]["completed"], true);
+
+        let req = test::TestRequest::get()
+            .uri("/api/homeassistant/sensor")
+            .to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["state"], 1);
+        assert_eq!(body["attributes"]["dueToday"], 0);
+        assert_eq!(body["attributes"]["unit_of_measurement"], "todos");
+
+        let cases = [
+            ("complete_todo", serde_json::json!({"text": "pay rent"}), 404),
+            ("complete_todo", serde_json::json!({}), 400),
+            ("add_todo", serde_json::json!({"text": " "}), 400),
+            ("turn_on", serde_json::json!({}), 404),
+        ];
+        for (name, data, status) in cases {
+            let resp = test::call_service(&app, call(name, data)).await;
+            assert_eq!(resp.status(), status, "{}", name);
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_homeassistant_sensor_counts_in_the_client_time_zone() {
+        use crate::preferences::UserPreferences;
+
+        let service = web::Data::new(TodoService::new_empty());
+        let preferences = web::Data::new(PreferenceStore::new());
+        preferences.set_user(
+            "kiritimati",
+            UserPreferences {
+                timezone: "Pacific/Kiritimati".to_string(),
+                ..Default::default()
+            },
+        );
+        let today = chrono::Utc::now()
+            .with_timezone(&chrono_tz::Pacific::Kiritimati)
+            .date_naive();
+        service.create(TodoCreate {
+            text: "Feed the cat".to_string(),
+            due_date: Some(today.to_string()),
+            ..Default::default()
+        });
+        let app = test::init_service(
+            App::new()
+                .app_data(service.clone())
+                .app_data(preferences)
+                .route("/api/homeassistant/sensor", web::get().to(get_homeassistant_sensor)),
+        )
+        .await;
+
+        let req = test::TestRequest::get()
+            .uri("/api/homeassistant/sensor")
+            .insert_header(("X-Client-Id", "kiritimati"))
+            .to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["attributes"]["dueToday"], 1);
+        assert_eq!(body["attributes"]["overdue"], 0);
+    }
+
+    #[actix_web::test]
+    async fn test_bulk_edit_preview_then_apply() {
+        use crate::bulk_edits::{BulkEditPreviews, PREVIEW_TTL};
+
+        let service = web::Data::new(TodoService::new_empty());
+        let app = test::init_service(
+            App::new()
+                .app_data(service.clone())
+                .app_data(web::Data::new(BulkEditPreviews::new(PREVIEW_TTL)))
+                .route(
+                    "/api/todos/bulk-edit/preview",
+                    web::post().to(bulk_edit_preview),
+                )
+                .route(
+                    "/api/todos/bulk-edit/apply",
+                    web::post().to(bulk_edit_apply),
+                ),
+        )
+        .await;
+        let create = |text: &str, priority: Priority| {
+            service.create(TodoCreate {
+                text: text.to_string(),
+                priority: Some(priority),
+                ..Default::default()
+            })
+        };
+        let rent = create("Pay rent", Priority::Low);
+        let bins = create("Take out bins", Priority::Low);
+        create("Water plants", Priority::Medium);
+        let preview = |body: serde_json::Value| {
+            test::TestRequest::post()
+                .uri("/api/todos/bulk-edit/preview")
+                .set_json(body)
+                .to_request()
+        };
+        let apply = |token: &serde_json::Value| {
+            test::TestRequest::post()
+                .uri("/api/todos/bulk-edit/apply")
+                .set_json(serde_json::json!({ "token": token }))
+                .to_request()
+        };
+
+        let resp = test::call_service(
+            &app,
+            preview(serde_json::json!({"filter": {"priority": "low"}, "changes": {}})),
+        )
+        .await;
+        assert_eq!(resp.status(), 400);
+
+        let edit = serde_json::json!({
+            "filter": {"priority": "low"},
+            "changes": {"priority": "high", "dueDate": "2024-06-30"}
+        });
+        let body: serde_json::Value =
+            test::call_and_read_body_json(&app, preview(edit.clone())).await;
+        assert_eq!(body["count"], 2);
+        assert_eq!(body["todos"][0]["changes"]["priority"]["from"], "low");
+        assert_eq!(body["todos"][0]["changes"]["priority"]["to"], "high");
+        // Previewing changes nothing.
+        assert_eq!(service.get_by_id(rent.id).unwrap().priority, Priority::Low);
+
+        // A todo edited after the preview makes the token unusable.
+        service.toggle(bins.id);
+        let resp = test::call_service(&app, apply(&body["token"])).await;
+        assert_eq!(resp.status(), 409);
+        let conflict: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(conflict["staleIds"], serde_json::json!([bins.id]));
+        assert_eq!(service.get_by_id(rent.id).unwrap().priority, Priority::Low);
+        let resp = test::call_service(&app, apply(&body["token"])).await;
+        assert_eq!(resp.status(), 404);
+
+        let body: serde_json::Value = test::call_and_read_body_json(&app, preview(edit)).await;
+        let applied: serde_json::Value =
+            test::call_and_read_body_json(&app, apply(&body["token"])).await;
+        assert_eq!(applied["applied"], 2);
+        let rent = service.get_by_id(rent.id).unwrap();
+        assert_eq!(rent.priority, Priority::High);
+        assert_eq!(rent.due_date.as_deref(), Some("2024-06-30"));
+    }
+
+    #[actix_web::test]
+    async fn test_sms_subscription_requires_verification() {
+        use crate::sms::testing::RecordingGateway;
+        use crate::sms::{RateLimits, SmsService};
+
+        let gateway = RecordingGateway::default();
+        let limits = RateLimits {
+            per_number_per_hour: 2,
+            per_day: 10,
+        };
+        let sms = web::Data::new(SmsService::new(Box::new(gateway.clone()), limits));
+        let app = test::init_service(
+            App::new()
+                .app_data(sms.clone())
+                .route("/api/notifications/sms", web::get().to(get_sms_subscription))
+                .route("/api/notifications/sms", web::put().to(put_sms_subscription))
+                .route("/api/notifications/sms/verify", web::post().to(verify_sms_subscription)),
+        )
+        .await;
+
+        let req = test::TestRequest::put()
+            .uri("/api/notifications/sms")
+            .set_json(serde_json::json!({"phone": "+1 415 555 0100"}))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 400, "client id is required");
+
+        let req = test::TestRequest::put()
+            .uri("/api/notifications/sms")
+            .insert_header(("X-Client-Id", "phone"))
+            .set_json(serde_json::json!({"phone": "+1 415 555 0100"}))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 202);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["subscription"]["phone"], "********0100");
+        assert_eq!(body["subscription"]["verified"], false);
+
+        let req = test::TestRequest::post()
+            .uri("/api/notifications/sms/verify")
+            .insert_header(("X-Client-Id", "phone"))
+            .set_json(serde_json::json!({"code": "not-it"}))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 400);
+
+        let code = gateway.last_code("+14155550100").unwrap();
+        let req = test::TestRequest::post()
+            .uri("/api/notifications/sms/verify")
+            .insert_header(("X-Client-Id", "phone"))
+            .set_json(serde_json::json!({"code": code}))
+            .to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["verified"], true);
+
+        for expected in [202, 429] {
+            let req = test::TestRequest::put()
+                .uri("/api/notifications/sms")
+                .insert_header(("X-Client-Id", "phone"))
+                .set_json(serde_json::json!({"phone": "+14155550100"}))
+                .to_request();
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(resp.status(), expected);
+            if expected == 429 {
+                assert!(resp.headers().contains_key("retry-after"));
+            }
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_sms_routes_without_gateway() {
+        let app = test::init_service(
+            App::new().route("/api/notifications/sms", web::get().to(get_sms_subscription)),
+        )
+        .await;
+        let req = test::TestRequest::get()
+            .uri("/api/notifications/sms")
+            .insert_header(("X-Client-Id", "phone"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 404);
+    }
+
+    #[actix_web::test]
+    async fn test_estimates_feed_workload_stats() {
+        let service = web::Data::new(TodoService::new_empty());
+        let config = Config {
+            daily_capacity_minutes: 120,
+            ..Default::default()
+        };
+        let app = test::init_service(
+            App::new()
+                .app_data(service.clone())
+                .app_data(web::Data::new(config))
+                .route("/api/todos", web::post().to(create_todo))
+                .route("/api/todos/stats/summary", web::get().to(get_stats)),
+        )
+        .await;
+
+        for minutes in [90, 60] {
+            let req = test::TestRequest::post()
+                .uri("/api/todos")
+                .set_json(serde_json::json!({
+                    "text": "Deep work",
+                    "dueDate": "today",
+                    "estimateMinutes": minutes
+                }))
+                .to_request();
+            let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+            assert_eq!(body["estimateMinutes"], minutes);
+        }
+        let req = test::TestRequest::post()
+            .uri("/api/todos")
+            .set_json(serde_json::json!({"text": "Forever", "estimateMinutes": 0}))
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 400);
+
+        let req = test::TestRequest::get()
+            .uri("/api/todos/stats/summary")
+            .to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        let today = chrono::Utc::now().date_naive().format("%Y-%m-%d").to_string();
+        assert_eq!(body["estimatedOutstandingMinutes"], 150);
+        assert_eq!(body["workload"][0]["minutes"], 150);
+        assert_eq!(body["dailyCapacityMinutes"], 120);
+        assert_eq!(body["overCommittedDays"], serde_json::json!([today]));
+
+        let req = test::TestRequest::get()
+            .uri("/api/todos/stats/summary?capacity=240")
+            .to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["overCommittedDays"], serde_json::json!([]));
+    }
+
+    #[actix_web::test]
+    async fn test_actions_for_one_todo() {
+        let service = web::Data::new(TodoService::new_empty());
+        let app = test::init_service(
+            App::new()
+                .app_data(service.clone())
+                .app_data(web::Data::new(Config::default()))
+                .route("/api/actions", web::get().to(get_actions)),
+        )
+        .await;
+        let todo = service.create(TodoCreate {
+            text: "Pay rent".to_string(),
+            completed: Some(true),
+            ..Default::default()
+        });
+        let ids = |body: &serde_json::Value| -> Vec<String> {
+            body.as_array()
+                .unwrap()
+                .iter()
+                .map(|action| action["id"].as_str().unwrap().to_string())
+                .collect()
+        };
+
+        let req = test::TestRequest::get().uri("/api/actions").to_request();
+        let all: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert!(ids(&all).contains(&"todo.create".to_string()));
+        assert!(!ids(&all).contains(&"todo.transfer".to_string()));
+        let create = all
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|action| action["id"] == "todo.create")
+            .unwrap();
+        assert_eq!(create["method"], "POST");
+        assert_eq!(create["body"]["required"], serde_json::json!(["text"]));
+
+        let req = test::TestRequest::get()
+            .uri("/api/actions?resource=todos")
+            .to_request();
+        let list: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert!(list
+            .as_array()
+            .unwrap()
+            .iter()
+            .all(|action| action["resource"] == "todos"));
+
+        let req = test::TestRequest::get()
+            .uri(&format!("/api/actions?id={}", todo.id))
+            .to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        let ids = ids(&body);
+        assert!(ids.contains(&"todo.reopen".to_string()));
+        assert!(!ids.contains(&"todo.complete".to_string()));
+        assert!(!ids.contains(&"todo.create".to_string()));
+
+        let req = test::TestRequest::get()
+            .uri(&format!("/api/actions?id={}", uuid::Uuid::new_v4()))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 404);
+    }
+
+    #[actix_web::test]
+    async fn test_actions_match_routes() {
+        let mut config = Config::default();
+        config
+            .transfer_peers
+            .insert("work".to_string(), "http://127.0.0.1:1".to_string());
+        let service = web::Data::new(TodoService::new_empty());
+        let app = test::init_service(
+            App::new()
+                .app_data(service.clone())
+                .app_data(web::Data::new(config.clone()))
+                .configure(crate::routes::configure_routes),
+        )
+        .await;
+
+        for action in crate::actions::catalog(&config) {
+            // A fresh todo each time, since some actions delete theirs.
+            let todo = service.create(TodoCreate {
+                text: "Pay rent".to_string(),
+                ..Default::default()
+            });
+            let uri = action.path.replace("{id}", &todo.id.to_string());
+            let method = actix_web::http::Method::from_bytes(action.method.as_bytes()).unwrap();
+            let req = test::TestRequest::default()
+                .method(method)
+                .uri(&uri)
+                .to_request();
+            let resp = test::call_service(&app, req).await;
+            // A mistyped path is either unrouted or taken for a missing
+            // todo's id; a wrong method gets a 405.
+            assert!(
+                resp.status() != 404 && resp.status() != 405,
+                "{} {} answered {}",
+                action.method,
+                action.path,
+                resp.status()
+            );
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_ingest_email_webhook() {
+        use crate::email::EmailIngest;
+        use hmac::{Hmac, Mac};
+
+        let service = web::Data::new(TodoService::new_empty());
+        let app = test::init_service(
+            App::new()
+                .app_data(service.clone())
+                .app_data(web::Data::new(Config {
+                    email_webhook_signing_key: Some("key-secret".to_string()),
+                    ..Config::default()
+                }))
+                .app_data(web::Data::new(EmailIngest::new()))
+                .route("/api/ingest/email", web::post().to(ingest_email)),
+        )
+        .await;
+        let timestamp = chrono::Utc::now().timestamp().to_string();
+        let sign = |key: &[u8]| {
+            let mut mac = Hmac::<sha2::Sha256>::new_from_slice(key).unwrap();
+            mac.update(format!("{}token123", timestamp).as_bytes());
+            hex::encode(mac.finalize().into_bytes())
+        };
+        let post = |signature: String| {
+            test::TestRequest::post()
+                .uri("/api/ingest/email")
+                .set_form([
+                    ("subject", "Book dentist !high"),
+                    ("Message-Id", "<dentist@example.com>"),
+                    ("timestamp", timestamp.as_str()),
+                    ("token", "token123"),
+                    ("signature", signature.as_str()),
+                ])
+                .to_request()
+        };
+
+        let resp = test::call_service(&app, post(sign(b"wrong-key"))).await;
+        assert_eq!(resp.status(), 401);
+        assert!(service.get_all(None, None, None).is_empty());
+
+        let resp = test::call_service(&app, post(sign(b"key-secret"))).await;
+        assert_eq!(resp.status(), 201);
+        let todo: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(todo["text"], "Book dentist");
+        assert_eq!(todo["priority"], "high");
+
+        // Mailgun retrying the same email creates nothing.
+        let resp = test::call_service(&app, post(sign(b"key-secret"))).await;
+        assert_eq!(resp.status(), 200);
+        assert_eq!(service.get_all(None, None, None).len(), 1);
+    }
+
+    #[actix_web::test]
+    async fn test_scripts_crud_and_audit() {
+        use crate::scripts::ScriptService;
+
+        let service = web::Data::new(TodoService::new_empty());
+        let scripts = web::Data::new(ScriptService::new());
+        let app = test::init_service(
+            App::new()
+                .app_data(scripts.clone())
+                .route("/api/scripts", web::get().to(get_scripts))
+                .route("/api/scripts", web::post().to(create_script))
+                .route("/api/scripts/{id}", web::get().to(get_script))
+                .route("/api/scripts/{id}", web::delete().to(delete_script))
+                .route(
+                    "/api/scripts/{id}/executions",
+                    web::get().to(get_script_executions),
+                ),
+        )
+        .await;
+        let create = |source: &str| {
+            test::TestRequest::post()
+                .uri("/api/scripts")
+                .set_json(serde_json::json!({ "name": "Escalate", "source": source }))
+                .to_request()
+        };
+
+        let resp = test::call_service(&app, create("fn on_create(todo) {")).await;
+        assert_eq!(resp.status(), 400);
+        let resp = test::call_service(
+            &app,
+            create(r#"fn on_complete(todo) { create("Review " + todo.text); }"#),
+        )
+        .await;
+        assert_eq!(resp.status(), 201);
+        let script: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(script["hooks"], serde_json::json!(["on_complete"]));
+        let id = script["id"].as_str().unwrap().to_string();
+
+        let mut receiver = service.events().subscribe();
+        let todo = service.create(TodoCreate {
+            text: "Ship release".to_string(),
+            ..Default::default()
+        });
+        service.toggle(todo.id);
+        let moderation = crate::moderation::Moderation::default();
+        while let Ok(event) = receiver.try_recv() {
+            scripts.handle(&service, &moderation, &event).await;
+        }
+        assert_eq!(service.get_all(None, None, None).len(), 2);
+
+        let req = test::TestRequest::get()
+            .uri(&format!("/api/scripts/{}/executions", id))
+            .to_request();
+        let executions: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(executions.as_array().unwrap().len(), 1);
+        assert_eq!(executions[0]["hook"], "on_complete");
+        assert_eq!(executions[0]["status"], "ok");
+        assert_eq!(executions[0]["todoId"], todo.id.to_string());
+
+        let req = test::TestRequest::delete()
+            .uri(&format!("/api/scripts/{}", id))
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 200);
+        // The audit log outlives the script.
+        let req = test::TestRequest::get()
+            .uri(&format!("/api/scripts/{}/executions", id))
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 200);
+        let req = test::TestRequest::get()
+            .uri("/api/scripts/unknown/executions")
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 404);
+    }
+
+    #[actix_web::test]
+    async fn test_caldav_sync() {
+        let service = web::Data::new(TodoService::new_empty());
+        let app = test::init_service(
+            App::new()
+                .app_data(service.clone())
+                .configure(crate::routes::configure_routes),
+        )
+        .await;
+        let dav = |method: &[u8], uri: &str| {
+            test::TestRequest::default()
+                .method(actix_web::http::Method::from_bytes(method).unwrap())
+                .uri(uri)
+        };
+
+        let req = test::TestRequest::get().uri("/.well-known/caldav").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 301);
+        assert_eq!(resp.headers().get("location").unwrap(), "/dav/");
+
+        let ics = "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nBEGIN:VTODO\r\nUID:abc@tasks.org\r\n\
+                   SUMMARY:Pay rent\r\nPRIORITY:1\r\nDUE;VALUE=DATE:20240610\r\n\
+                   END:VTODO\r\nEND:VCALENDAR\r\n";
+        let req = test::TestRequest::put()
+            .uri("/dav/todos/abc@tasks.org.ics")
+            .insert_header(("If-None-Match", "*"))
+            .set_payload(ics)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 201);
+        let etag = resp.headers().get("etag").unwrap().to_str().unwrap().to_string();
+        let todo = service.get_by_id(todo_id("abc@tasks.org")).unwrap();
+        assert_eq!(todo.priority, Priority::High);
+        assert_eq!(todo.due_date.as_deref(), Some("2024-06-10"));
+
+        let req = dav(b"PROPFIND", "/dav/todos/")
+            .insert_header(("Depth", "1"))
+            .set_payload(
+                r#"<d:propfind xmlns:d="DAV:" xmlns:cs="http://calendarserver.org/ns/">
+                     <d:prop><d:getetag/><cs:getctag/><d:quota-used-bytes/></d:prop>
+                   </d:propfind>"#,
+            )
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 207);
+        let body = String::from_utf8(test::read_body(resp).await.to_vec()).unwrap();
+        assert!(body.contains(&format!("<d:href>/dav/todos/{}.ics</d:href>", todo.id)));
+        assert!(body.contains(&format!("<d:getetag>{}</d:getetag>", etag)));
+        assert!(body.contains("<cs:getctag>"));
+        assert!(body.contains("<d:quota-used-bytes/></d:prop><d:status>HTTP/1.1 404 Not Found"));
+
+        let req = dav(b"REPORT", "/dav/todos/")
+            .set_payload(
+                r#"<c:calendar-multiget xmlns:d="DAV:" xmlns:c="urn:ietf:params:xml:ns:caldav">
+                     <d:prop><d:getetag/><c:calendar-data/></d:prop>
+                     <d:href>/dav/todos/abc@tasks.org.ics</d:href>
+                     <d:href>/dav/todos/gone.ics</d:href>
+                   </c:calendar-multiget>"#,
+            )
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 207);
+        let body = String::from_utf8(test::read_body(resp).await.to_vec()).unwrap();
+        assert!(body.contains("SUMMARY:Pay rent"));
+        assert!(body.contains(
+            "<d:href>/dav/todos/gone.ics</d:href><d:status>HTTP/1.1 404 Not Found"
+        ));
+
+        // A client editing an old copy must fetch the new one first.
+        let done = ics.replace("PRIORITY:1", "STATUS:COMPLETED");
+        let req = test::TestRequest::put()
+            .uri("/dav/todos/abc@tasks.org.ics")
+            .insert_header(("If-Match", "\"1\""))
+            .set_payload(done.clone())
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 412);
+        let req = test::TestRequest::put()
+            .uri("/dav/todos/abc@tasks.org.ics")
+            .insert_header(("If-Match", etag.as_str()))
+            .set_payload(done)
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 204);
+        let todo = service.get_by_id(todo_id("abc@tasks.org")).unwrap();
+        assert!(todo.completed);
+        assert_eq!(todo.priority, Priority::Medium);
+
+        let req = test::TestRequest::get()
+            .uri("/dav/todos/abc@tasks.org.ics")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 200);
+        let body = String::from_utf8(test::read_body(resp).await.to_vec()).unwrap();
+        assert!(body.contains("STATUS:COMPLETED\r\n"));
+
+        let req = test::TestRequest::delete()
+            .uri("/dav/todos/abc@tasks.org.ics")
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 204);
+        assert!(service.get_by_id(todo_id("abc@tasks.org")).is_none());
+    }
+
+    #[actix_web::test]
+    async fn test_get_plugins() {
+        use crate::plugins::PluginRegistry;
+
+        let mut config = Config::default();
+        config
+            .transfer_peers
+            .insert("work".to_string(), "http://127.0.0.1:1".to_string());
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(PluginRegistry::builtin()))
+                .app_data(web::Data::new(config))
+                .route("/api/plugins", web::get().to(get_plugins)),
+        )
+        .await;
+
+        let req = test::TestRequest::get()
+            .uri("/api/plugins?kind=sync")
+            .to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        let names: Vec<&str> = body
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|plugin| plugin["name"].as_str().unwrap())
+            .collect();
+        assert_eq!(names, vec!["caldav", "transfer"]);
+        assert_eq!(body[1]["loaded"], true);
+        assert_eq!(body[1]["details"]["peers"][0], "work");
+
+        let req = test::TestRequest::get()
+            .uri("/api/plugins?loaded=false")
+            .to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        let backups = body
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|plugin| plugin["name"] == "backups")
+            .unwrap();
+        assert_eq!(backups["feature"], "backups");
+        assert_eq!(backups["compiled"], cfg!(feature = "backups"));
+    }
+
+    #[actix_web::test]
+    async fn test_feed_requires_configured_token() {
+        let config = Config {
+            feed_token: Some("s3cret".to_string()),
+            ..Config::default()
+        };
+        let service = web::Data::new(TodoService::new_empty());
+        service.create(TodoCreate {
+            text: "Buy milk".to_string(),
+            ..Default::default()
+        });
+        let app = test::init_service(
+            App::new()
+                .app_data(service.clone())
+                .app_data(web::Data::new(config))
+                .route("/api/todos/feed.atom", web::get().to(get_feed)),
+        )
+        .await;
+
+        let req = test::TestRequest::get()
+            .uri("/api/todos/feed.atom?token=wrong")
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 401);
+
+        let req = test::TestRequest::get()
+            .uri("/api/todos/feed.atom?token=s3cret")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 200);
+        assert_eq!(
+            resp.headers().get("content-type").unwrap(),
+            "application/atom+xml; charset=utf-8"
+        );
+        let etag = resp.headers().get("etag").unwrap().clone();
+        let body = String::from_utf8(test::read_body(resp).await.to_vec()).unwrap();
+        assert!(body.contains("<title>Added: Buy milk</title>"));
+        assert!(!body.contains("s3cret"));
+
+        let req = test::TestRequest::get()
+            .uri("/api/todos/feed.atom")
+            .insert_header(("Authorization", "Bearer s3cret"))
+            .insert_header(("If-None-Match", etag))
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 304);
+    }
+
+    #[actix_web::test]
+    async fn test_mcp_over_sse() {
+        use crate::mcp::McpSessions;
+        use actix_web::body::MessageBody;
+
+        let service = web::Data::new(TodoService::new_empty());
+        let app = test::init_service(
+            App::new()
+                .app_data(service.clone())
+                .app_data(web::Data::new(McpSessions::new()))
+                .route("/api/mcp/sse", web::get().to(mcp_sse))
+                .route("/api/mcp/messages", web::post().to(mcp_message)),
+        )
+        .await;
+
+        let req = test::TestRequest::get().uri("/api/mcp/sse").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 200);
+        let mut body = Box::pin(resp.into_body());
+        async fn next_event<B: MessageBody>(body: &mut std::pin::Pin<Box<B>>) -> String
+        where
+            B::Error: std::fmt::Debug,
+        {
+            let chunk = std::future::poll_fn(|cx| body.as_mut().poll_next(cx)).await;
+            String::from_utf8(chunk.unwrap().unwrap().to_vec()).unwrap()
+        }
+        let endpoint = next_event(&mut body).await;
+        let endpoint = endpoint
+            .strip_prefix("event: endpoint\ndata: ")
+            .unwrap()
+            .trim()
+            .to_string();
+        assert!(endpoint.starts_with("/api/mcp/messages?sessionId="));
+
+        let req = test::TestRequest::post()
+            .uri(&endpoint)
+            .set_payload(
+                r#"{"jsonrpc":"2.0","id":7,"method":"tools/call",
+                    "params":{"name":"create_todo","arguments":{"text":"Buy milk"}}}"#,
+            )
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 202);
+        let event = next_event(&mut body).await;
+        let message: serde_json::Value =
+            serde_json::from_str(event.strip_prefix("event: message\ndata: ").unwrap().trim())
+                .unwrap();
+        assert_eq!(message["id"], 7);
+        assert_eq!(message["result"]["isError"], false);
+        assert_eq!(service.get_stats().total, 1);
+
+        let req = test::TestRequest::post()
+            .uri("/api/mcp/messages?sessionId=unknown")
+            .set_payload(r#"{"jsonrpc":"2.0","id":1,"method":"ping"}"#)
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 404);
+    }
+
+    #[actix_web::test]
+    async fn test_suggest_for_todo_does_not_apply() {
+        let service = web::Data::new(TodoService::new_empty());
+        let todo = service.create(TodoCreate {
+            text: "Pay the rent tomorrow asap".to_string(),
+            ..Default::default()
+        });
+        let app = test::init_service(
+            App::new()
+                .app_data(service.clone())
+                .route("/api/todos/{id}/suggest", web::post().to(suggest_for_todo)),
+        )
+        .await;
+
+        let req = test::TestRequest::post()
+            .uri(&format!("/api/todos/{}/suggest", todo.id))
+            .to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["source"], "heuristic");
+        assert_eq!(body["priority"], "high");
+        assert_eq!(body["tags"], serde_json::json!(["finance"]));
+        assert!(body["dueDate"].is_string());
+        let unchanged = service.get_by_id(todo.id).unwrap();
+        assert_eq!(unchanged.priority, Priority::Medium);
+        assert_eq!(unchanged.due_date, None);
+
+        let req = test::TestRequest::post()
+            .uri("/api/todos/00000000-0000-0000-0000-000000000000/suggest")
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 404);
+    }
+
+    #[actix_web::test]
+    async fn test_plan_today() {
+        let service = web::Data::new(TodoService::new_empty());
+        let today = chrono::Utc::now().date_naive().format("%Y-%m-%d").to_string();
+        let due_today = |text: &str, minutes: u32| TodoCreate {
+            text: text.to_string(),
+            priority: Some(Priority::High),
+            due_date: Some(today.clone()),
+            estimate_minutes: Some(minutes),
+            ..Default::default()
+        };
+        let report = service.create(due_today("Write report", 90));
+        let slides = service.create(due_today("Make slides", 60));
+        let app = test::init_service(
+            App::new()
+                .app_data(service.clone())
+                .app_data(web::Data::new(Config {
+                    daily_capacity_minutes: 120,
+                    ..Config::default()
+                }))
+                .route("/api/plan/today", web::get().to(get_plan_today)),
+        )
+        .await;
+
+        let req = test::TestRequest::get().uri("/api/plan/today").to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["capacityMinutes"], 120);
+        assert_eq!(body["agenda"][0]["todo"]["id"], report.id.to_string());
+        assert_eq!(body["overflow"][0]["todo"]["id"], slides.id.to_string());
+        assert_eq!(body["warnings"][0]["code"], "overCapacity");
+
+        let req = test::TestRequest::get()
+            .uri("/api/plan/today?capacity=240")
+            .to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["plannedMinutes"], 150);
+        assert!(body["warnings"].as_array().unwrap().is_empty());
+
+        let req = test::TestRequest::get()
+            .uri("/api/plan/today?capacity=5000")
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 400);
+    }
+
+    #[actix_web::test]
+    async fn test_moderation_rejects_or_masks_text() {
+        use crate::moderation::{Moderation, ModerationMode, WordList};
+
+        let service = web::Data::new(TodoService::new_empty());
+        let word_list = || Box::new(WordList::new(&["spinach".to_string()])) as Box<_>;
+        let app = test::init_service(
+            App::new()
+                .app_data(service.clone())
+                .app_data(web::Data::new(Moderation::new(
+                    ModerationMode::Reject,
+                    vec![word_list()],
+                )))
+                .route("/api/todos", web::post().to(create_todo)),
+        )
+        .await;
+        let req = test::TestRequest::post()
+            .uri("/api/todos")
+            .set_json(serde_json::json!({ "text": "Eat the Spinach" }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 422);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["terms"], serde_json::json!(["Spinach"]));
+        assert_eq!(service.get_stats().total, 0);
+
+        let app = test::init_service(
+            App::new()
+                .app_data(service.clone())
+                .app_data(web::Data::new(Moderation::new(
+                    ModerationMode::Mask,
+                    vec![word_list()],
+                )))
+                .route("/api/todos", web::post().to(create_todo))
+                .route("/api/todos/{id}", web::put().to(update_todo)),
+        )
+        .await;
+        let req = test::TestRequest::post()
+            .uri("/api/todos")
+            .set_json(serde_json::json!({ "text": "Buy carrots" }))
+            .to_request();
+        let created: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        let req = test::TestRequest::put()
+            .uri(&format!("/api/todos/{}", created["id"].as_str().unwrap()))
+            .set_json(serde_json::json!({ "text": "Buy spinach and carrots" }))
+            .to_request();
+        let updated: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(updated["text"], "Buy ******* and carrots");
+    }
+
+    #[actix_web::test]
+    async fn test_moderation_covers_sync_and_bundle_import() {
+        use crate::moderation::{Moderation, ModerationMode, WordList};
+
+        let service = web::Data::new(TodoService::new_empty());
+        let app = test::init_service(
+            App::new()
+                .app_data(service.clone())
+                .app_data(web::Data::new(Moderation::new(
+                    ModerationMode::Reject,
+                    vec![Box::new(WordList::new(&["spinach".to_string()]))],
+                )))
+                .route("/api/sync", web::post().to(sync_todos))
+                .route("/api/todos/import", web::post().to(import_todo)),
+        )
+        .await;
+
+        let change = |id: &str, text: &str| {
+            serde_json::json!({
+                "op": "create",
+                "id": id,
+                "fields": { "text": text },
+                "clientTimestamp": "2020-01-01T00:00:00Z"
+            })
+        };
+        let req = test::TestRequest::post()
+            .uri("/api/sync")
+            .set_json(serde_json::json!({
+                "changes": [change("greens", "Eat spinach"), change("bread", "Buy bread")]
+            }))
+            .to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["applied"], serde_json::json!(["bread"]));
+        assert_eq!(body["errors"][0]["id"], "greens");
+        assert_eq!(body["errors"][0]["code"], "disallowed");
+        assert_eq!(service.get_stats().total, 1);
+
+        let source = TodoService::new_empty();
+        let todo = source.create(TodoCreate {
+            text: "Plant spinach".to_string(),
+            ..Default::default()
+        });
+        let req = test::TestRequest::post()
+            .uri("/api/todos/import")
+            .set_json(source.export_todo(todo.id).unwrap())
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 422);
+        assert!(service.get_by_id(todo.id).is_none());
+    }
+
+    #[actix_web::test]
+    async fn test_user_preferences_set_creation_defaults() {
+        let service = web::Data::new(TodoService::new_empty());
+        let app = test::init_service(
+            App::new()
+                .app_data(service.clone())
+                .app_data(web::Data::new(PreferenceStore::new()))
+                .route("/api/todos", web::post().to(create_todo))
+                .route("/api/todos/stats", web::get().to(get_stats))
+                .route("/api/preferences", web::get().to(get_user_preferences))
+                .route("/api/preferences", web::put().to(put_user_preferences)),
+        )
+        .await;
+
+        let req = test::TestRequest::get()
+            .uri("/api/preferences")
+            .insert_header(("X-Client-Id", "phone"))
+            .to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["defaultPriority"], "medium");
+        assert_eq!(body["timezone"], "UTC");
+        assert!(body.get("updatedAt").is_none());
+
+        let req = test::TestRequest::put()
+            .uri("/api/preferences")
+            .insert_header(("X-Client-Id", "phone"))
+            .set_json(serde_json::json!({ "timezone": "Nowhere/Special" }))
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 400);
+
+        let req = test::TestRequest::put()
+            .uri("/api/preferences")
+            .insert_header(("X-Client-Id", "phone"))
+            .set_json(serde_json::json!({
+                "defaultPriority": "low",
+                "reminderLeadMinutes": 60,
+                "weekStart": "sunday"
+            }))
+            .to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["weekStart"], "sunday");
+        assert_eq!(body["notifications"]["push"], true);
+
+        let req = test::TestRequest::post()
+            .uri("/api/todos")
+            .insert_header(("X-Client-Id", "phone"))
+            .set_json(serde_json::json!({ "text": "Water plants", "dueDate": "2030-01-01" }))
+            .to_request();
+        let todo: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(todo["priority"], "low");
+        assert_eq!(todo["reminderTime"], "23:00");
+
+        // Other clients keep the server's defaults.
+        let req = test::TestRequest::post()
+            .uri("/api/todos")
+            .set_json(serde_json::json!({ "text": "Feed cat", "dueDate": "2030-01-01" }))
+            .to_request();
+        let todo: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(todo["priority"], "medium");
+        assert!(todo["reminderTime"].is_null());
+
+        let req = test::TestRequest::get()
+            .uri("/api/todos/stats")
+            .insert_header(("X-Client-Id", "phone"))
+            .to_request();
+        let stats: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(stats["weekStart"], "sunday");
+        assert_eq!(stats["dueThisWeekCount"], 0);
+    }
+
+    #[actix_web::test]
+    async fn test_messages_follow_accept_language() {
+        use crate::i18n;
+
+        let service = web::Data::new(TodoService::new_empty());
+        let app = test::init_service(
+            App::new()
+                .wrap(actix_web::middleware::from_fn(i18n::localize_responses))
+                .app_data(service.clone())
+                .route("/api/todos/{id}", web::get().to(get_todo)),
+        )
+        .await;
+
+        let req = test::TestRequest::get()
+            .uri("/api/todos/00000000-0000-0000-0000-000000000000")
+            .insert_header(("Accept-Language", "fr-CA, en;q=0.3"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 404);
+        assert_eq!(resp.headers().get("Content-Language").unwrap(), "fr");
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["error"], "Tâche introuvable");
+
+        let req = test::TestRequest::get()
+            .uri("/api/todos/00000000-0000-0000-0000-000000000000")
+            .insert_header(("Accept-Language", "ja"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.headers().get("Content-Language").is_none());
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["error"], "Todo not found");
+    }
+
+    #[actix_web::test]
+    async fn test_error_responses_carry_codes() {
+        use crate::errors;
+        use crate::i18n;
+
+        let service = web::Data::new(TodoService::new_empty());
+        let app = test::init_service(
+            App::new()
+                .wrap(actix_web::middleware::from_fn(i18n::localize_responses))
+                .app_data(service.clone())
+                .app_data(web::JsonConfig::default().error_handler(errors::extractor_error))
+                .app_data(web::PathConfig::default().error_handler(errors::path_error))
+                .route("/api/todos", web::post().to(create_todo))
+                .route("/api/todos/{id}", web::get().to(get_todo)),
+        )
+        .await;
+
+        // The code stays the same whatever language the message is in.
+        let req = test::TestRequest::get()
+            .uri("/api/todos/00000000-0000-0000-0000-000000000000")
+            .insert_header(("Accept-Language", "de"))
+            .to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["error"], "Aufgabe nicht gefunden");
+        assert_eq!(body["code"], "TODO_NOT_FOUND");
+
+        let req = test::TestRequest::get().uri("/api/todos/%00").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 400);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["error"], "Todo id must be a UUID");
+        assert_eq!(body["code"], "INVALID_ID");
+
+        let req = test::TestRequest::post()
+            .uri("/api/todos")
+            .set_json(serde_json::json!({ "text": "x".repeat(501) }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 400);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["code"], "TEXT_TOO_LONG");
+
+        let req = test::TestRequest::post()
+            .uri("/api/todos")
+            .set_json(serde_json::json!({ "text": "Milk", "priority": "urgent" }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 400);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["code"], "VALIDATION_FAILED");
+        assert!(body["error"].as_str().unwrap().contains("urgent"));
+    }
+
+    #[actix_web::test]
+    async fn test_v2_todo_shape_beside_v1() {
+        use crate::versioning;
+
+        let service = web::Data::new(TodoService::new_empty());
+        let app = test::init_service(
+            App::new()
+                .app_data(service.clone())
+                .service(web::scope("/api/v1").route("/todos/{id}", web::get().to(get_todo)))
+                .service(
+                    web::scope("/api/v2")
+                        .wrap(actix_web::middleware::from_fn(versioning::v2_compat))
+                        .route("/todos", web::post().to(create_todo))
+                        .route("/todos/{id}", web::get().to(get_todo)),
+                ),
+        )
+        .await;
+
+        let req = test::TestRequest::post()
+            .uri("/api/v2/todos")
+            .set_json(serde_json::json!({
+                "text": "Pay rent",
+                "estimate_minutes": 15,
+                "remind_at": "2024-12-31T10:00:00Z"
+            }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 201);
+        assert_eq!(resp.headers().get(versioning::VERSION_HEADER).unwrap(), "v2");
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["due_date"], "2024-12-31");
+        assert_eq!(body["remind_at"], "2024-12-31T10:00:00Z");
+        assert_eq!(body["estimate_minutes"], 15);
+        assert!(body.get("createdAt").is_none());
+
+        let id = body["id"].as_str().unwrap();
+        let req = test::TestRequest::get().uri(&format!("/api/v1/todos/{}", id)).to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.headers().get(versioning::VERSION_HEADER).is_none());
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["dueDate"], "2024-12-31");
+        assert_eq!(body["reminderTime"], "10:00");
+        assert!(body.get("remind_at").is_none());
+
+        let req = test::TestRequest::post()
+            .uri("/api/v2/todos")
+            .set_json(serde_json::json!({ "text": "Pay rent", "remind_at": "soon" }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 400);
+    }
+
+    #[actix_web::test]
+    async fn test_snake_case_negotiation() {
+        use crate::casing;
+
+        let service = web::Data::new(TodoService::new_empty());
+        let app = test::init_service(
+            App::new()
+                .wrap(actix_web::middleware::from_fn(casing::negotiate_case))
+                .app_data(service.clone())
+                .route("/api/todos", web::post().to(create_todo))
+                .route("/api/todos/{id}", web::get().to(get_todo)),
+        )
+        .await;
+
+        let req = test::TestRequest::post()
+            .uri("/api/todos")
+            .insert_header((casing::CASE_HEADER, "snake_case"))
+            .set_json(serde_json::json!({ "text": "Pay rent", "due_date": "2024-12-31" }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 201);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["due_date"], "2024-12-31");
+        assert!(body.get("created_at").is_some());
+        assert!(body.get("createdAt").is_none());
+
+        let id = body["id"].as_str().unwrap();
+        let req = test::TestRequest::get().uri(&format!("/api/todos/{}", id)).to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["dueDate"], "2024-12-31");
+
+        let req = test::TestRequest::get()
+            .uri(&format!("/api/todos/{}?case=snake", id))
+            .to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["due_date"], "2024-12-31");
+
+        let req = test::TestRequest::get()
+            .uri(&format!("/api/todos/{}?case=snake", id))
+            .insert_header(("Accept", "application/msgpack"))
+            .to_request();
+        let body = test::call_and_read_body(&app, req).await;
+        let body: serde_json::Value = rmp_serde::from_slice(&body).unwrap();
+        assert_eq!(body["due_date"], "2024-12-31");
+
+        let req = test::TestRequest::get()
+            .uri(&format!("/api/todos/{}?case=kebab", id))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 400);
+
+        let app = test::init_service(
+            App::new()
+                .wrap(crate::routes::configure_cors())
+                .route("/api/todos", web::get().to(get_todos)),
+        )
+        .await;
+        let req = test::TestRequest::default()
+            .method(actix_web::http::Method::OPTIONS)
+            .uri("/api/todos")
+            .insert_header(("Origin", "http://localhost:3000"))
+            .insert_header(("Access-Control-Request-Method", "GET"))
+            .insert_header(("Access-Control-Request-Headers", casing::CASE_HEADER))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 200);
+    }
+
+    #[actix_web::test]
+    async fn test_head_and_capabilities() {
+        let service = web::Data::new(TodoService::new_empty());
+        let app = test::init_service(
+            App::new()
+                .app_data(service.clone())
+                .app_data(web::Data::new(Config::default()))
+                .configure(crate::routes::configure_routes),
+        )
+        .await;
+
+        let req = test::TestRequest::default()
+            .method(actix_web::http::Method::HEAD)
+            .uri("/api/todos")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 200);
+        assert!(resp.headers().contains_key("etag"));
+
+        let req = test::TestRequest::default()
+            .method(actix_web::http::Method::HEAD)
+            .uri("/api/v1/todos/00000000-0000-0000-0000-000000000000")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 404);
+
+        for method in [actix_web::http::Method::GET, actix_web::http::Method::OPTIONS] {
+            let req = test::TestRequest::default()
+                .method(method)
+                .uri("/api/capabilities")
+                .to_request();
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(resp.status(), 200);
+            assert_eq!(resp.headers().get("allow").unwrap(), "GET, HEAD, OPTIONS");
+            let body: serde_json::Value = test::read_body_json(resp).await;
+            assert_eq!(body["backends"]["storage"], "memory");
+            assert_eq!(body["realtime"]["websockets"], false);
+        }
+
+        let req = test::TestRequest::default()
+            .method(actix_web::http::Method::OPTIONS)
+            .uri("/api")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 200);
+    }
+
+    #[actix_web::test]
+    async fn test_get_todos_by_cursor() {
+        let service = web::Data::new(TodoService::new_empty());
+        let create = |text: &str| TodoCreate {
+            text: text.to_string(),
+            ..Default::default()
+        };
+        for text in ["One", "Two", "Three"] {
+            service.create(create(text));
+        }
+        let app = test::init_service(
+            App::new()
+                .app_data(service.clone())
+                .route("/api/todos", web::get().to(get_todos)),
+        )
+        .await;
+
+        let req = test::TestRequest::get()
+            .uri("/api/todos?cursor=&limit=2&sort=text")
+            .to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        let texts = |body: &serde_json::Value| -> Vec<String> {
+            body["items"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|t| t["text"].as_str().unwrap().to_string())
+                .collect()
+        };
+        assert_eq!(texts(&body), ["One", "Three"]);
+        assert_eq!(body["hasMore"], true);
+        let next = body["nextCursor"].as_str().unwrap().to_string();
+
+        // Added before the cursor; the next page is unaffected.
+        service.create(create("Apples"));
+        let req = test::TestRequest::get()
+            .uri(&format!("/api/todos?cursor={}&limit=2", next))
+            .to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(texts(&body), ["Two"]);
+        assert_eq!(body["hasMore"], false);
+        assert!(body.get("nextCursor").is_none());
+
+        for uri in [
+            format!("/api/todos?cursor={}&sort=dueDate", next),
+            format!("/api/todos?cursor={}&offset=2", next),
+            "/api/todos?cursor=garbage".to_string(),
+        ] {
+            let req = test::TestRequest::get().uri(&uri).to_request();
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(resp.status(), 400, "{}", uri);
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_saved_view_runs() {
+        use crate::views::ViewStore;
+
+        let service = web::Data::new(TodoService::new_empty());
+        let app = test::init_service(
+            App::new()
+                .app_data(service.clone())
+                .app_data(web::Data::new(ViewStore::new()))
+                .route("/api/todos", web::post().to(create_todo))
+                .route("/api/views", web::post().to(create_view))
+                .route("/api/views/{id}/todos", web::get().to(get_view_todos)),
+        )
+        .await;
+
+        for (text, priority, due) in [
+            ("Send report #work", "high", "2020-01-01"),
+            ("Book dentist", "high", "2020-01-01"),
+            ("Plan offsite #work", "low", "2020-01-01"),
+            ("Review budget #work", "high", "2999-01-01"),
+        ] {
+            let req = test::TestRequest::post()
+                .uri("/api/todos")
+                .set_json(serde_json::json!({ "text": text, "priority": priority, "dueDate": due }))
+                .to_request();
+            assert_eq!(test::call_service(&app, req).await.status(), 201);
+        }
+
+        let req = test::TestRequest::post()
+            .uri("/api/views")
+            .set_json(serde_json::json!({
+                "name": "Overdue high-priority work",
+                "filter": "overdue",
+                "priority": "high",
+                "tags": ["work"],
+                "sort": "dueDate"
+            }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 201);
+        let view: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(view["tags"][0], "work");
+
+        let req = test::TestRequest::get()
+            .uri(&format!("/api/views/{}/todos", view["id"].as_str().unwrap()))
+            .to_request();
+        let todos: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        let todos = todos.as_array().unwrap();
+        assert_eq!(todos.len(), 1);
+        assert_eq!(todos[0]["text"], "Send report #work");
+
+        let req = test::TestRequest::get().uri("/api/views/missing/todos").to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 404);
+    }
+
+    #[actix_web::test]
+    async fn test_get_todos_with_query_language() {
+        let service = web::Data::new(TodoService::new_empty());
+        let app = test::init_service(
+            App::new()
+                .app_data(service.clone())
+                .route("/api/todos", web::post().to(create_todo))
+                .route("/api/todos", web::get().to(get_todos)),
+        )
+        .await;
+        for (text, priority, due) in [
+            ("Quarterly report #work", "high", "2999-01-01"),
+            ("Expense report", "high", "2020-01-01"),
+            ("Weekly report #work", "low", "2999-01-01"),
+            ("Expense report draft", "high", "2999-01-01"),
+        ] {
+            let req = test::TestRequest::post()
+                .uri("/api/todos")
+                .set_json(serde_json::json!({ "text": text, "priority": priority, "dueDate": due }))
+                .to_request();
+            assert_eq!(test::call_service(&app, req).await.status(), 201);
+        }
+
+        let q = "priority:high AND (tag:work OR overdue:true) AND text~\"report\"";
+        let req = test::TestRequest::get()
+            .uri(&format!(
+                "/api/todos?sort=text&q={}",
+                percent_encoding::utf8_percent_encode(q, percent_encoding::NON_ALPHANUMERIC)
+            ))
+            .to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        let texts: Vec<&str> = body
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|t| t["text"].as_str().unwrap())
+            .collect();
+        assert_eq!(texts, ["Expense report", "Quarterly report #work"]);
+
+        let req = test::TestRequest::get()
+            .uri("/api/todos?q=priority:urgent")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 400);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["error"], "Unknown priority 'urgent'");
+    }
+
+    #[actix_web::test]
+    async fn test_get_dashboard() {
+        let service = web::Data::new(TodoService::new_empty());
+        let app = test::init_service(
+            App::new()
+                .app_data(service.clone())
+                .route("/api/todos", web::post().to(create_todo))
+                .route("/api/dashboard", web::get().to(get_dashboard)),
+        )
+        .await;
+        let today = chrono::Utc::now().date_naive();
+        for (text, due) in [("Today", today), ("Late", today - chrono::Duration::days(3))] {
+            let req = test::TestRequest::post()
+                .uri("/api/todos")
+                .set_json(serde_json::json!({ "text": text, "dueDate": due.to_string() }))
+                .to_request();
+            assert_eq!(test::call_service(&app, req).await.status(), 201);
+        }
+
+        let req = test::TestRequest::get().uri("/api/dashboard").to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["dueToday"][0]["text"], "Today");
+        assert_eq!(body["overdue"][0]["text"], "Late");
+        assert_eq!(body["topPriority"].as_array().unwrap().len(), 2);
+        assert_eq!(body["stats"]["total"], 2);
+        assert_eq!(body["recentActivity"][0]["type"], "todo.created");
+
+        let req = test::TestRequest::get().uri("/api/dashboard?limit=1000").to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 400);
+    }
+
+    #[actix_web::test]
+    async fn test_complete_todos_matching_filters() {
+        let service = web::Data::new(TodoService::new_empty());
+        let app = test::init_service(
+            App::new()
+                .app_data(service.clone())
+                .route("/api/todos", web::post().to(create_todo))
+                .route("/api/todos/complete", web::post().to(complete_todos)),
+        )
+        .await;
+        for (text, priority) in [
+            ("Send report #work", "high"),
+            ("Book flights #work", "low"),
+            ("Water plants #home", "high"),
+        ] {
+            let req = test::TestRequest::post()
+                .uri("/api/todos")
+                .set_json(serde_json::json!({ "text": text, "priority": priority }))
+                .to_request();
+            assert_eq!(test::call_service(&app, req).await.status(), 201);
+        }
+
+        let req = test::TestRequest::post()
+            .uri("/api/todos/complete?filter=active&tag=work")
+            .to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["completed"], 2);
+        let active = service.get_all(Some("active".to_string()), None, None);
+        assert_eq!(active.len(), 1);
+        assert_eq!(active[0].text, "Water plants #home");
+
+        let req = test::TestRequest::post()
+            .uri("/api/todos/complete?tag=%23")
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 400);
+        assert_eq!(service.get_all(Some("active".to_string()), None, None).len(), 1);
+    }
+
+    #[actix_web::test]
+    async fn test_policy_crud() {
+        use crate::policies::PolicyStore;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(PolicyStore::new()))
+                .route("/api/policies", web::get().to(get_policies))
+                .route("/api/policies", web::post().to(create_policy))
+                .route("/api/policies/{id}", web::get().to(get_policy))
+                .route("/api/policies/{id}", web::put().to(update_policy))
+                .route("/api/policies/{id}", web::delete().to(delete_policy)),
+        )
+        .await;
+
+        let req = test::TestRequest::post()
+            .uri("/api/policies")
+            .set_json(serde_json::json!({
+                "name": "Tidy up",
+                "action": "deleteCompleted",
+                "afterDays": 30
+            }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 201);
+        let policy: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(policy["enabled"], true);
+        assert!(policy["lastRun"].is_null());
+        let uri = format!("/api/policies/{}", policy["id"].as_str().unwrap());
+
+        let req = test::TestRequest::put()
+            .uri(&uri)
+            .set_json(serde_json::json!({
+                "name": "Escalate",
+                "action": "escalatePriority",
+                "afterDays": 3,
+                "enabled": false
+            }))
+            .to_request();
+        let policy: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(policy["action"], "escalatePriority");
+        assert_eq!(policy["enabled"], false);
+
+        let req = test::TestRequest::put()
+            .uri(&uri)
+            .set_json(serde_json::json!({
+                "name": "Escalate",
+                "action": "escalatePriority",
+                "afterDays": 0
+            }))
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 400);
+
+        let req = test::TestRequest::get().uri("/api/policies").to_request();
+        let policies: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(policies.as_array().unwrap().len(), 1);
+
+        let req = test::TestRequest::delete().uri(&uri).to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 200);
+        let req = test::TestRequest::get().uri(&uri).to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 404);
+    }
+
+    #[actix_web::test]
+    async fn test_admin_generate() {
+        let service = web::Data::new(TodoService::new_empty());
+        let app = test::init_service(
+            App::new()
+                .app_data(admin_config())
+                .app_data(service.clone())
+                .route("/api/admin/generate", web::post().to(admin_generate)),
+        )
+        .await;
+
+        let req = test::TestRequest::post()
+            .uri("/api/admin/generate?count=250&seed=7")
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 401);
+
+        let req = test::TestRequest::post()
+            .uri("/api/admin/generate?count=250&seed=7")
+            .insert_header(("Authorization", "Bearer secret-token"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 201);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["created"], 250);
+        assert_eq!(body["seed"], 7);
+        assert_eq!(service.get_all(None, None, None).len(), 250);
+
+        let req = test::TestRequest::post()
+            .uri("/api/admin/generate?count=0")
+            .insert_header(("Authorization", "Bearer secret-token"))
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 400);
+    }
+}
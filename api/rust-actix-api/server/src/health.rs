@@ -0,0 +1,89 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+static STARTED: OnceLock<(Instant, DateTime<Utc>)> = OnceLock::new();
+
+/// Records the process start time. Called once from `main`; later calls are
+/// no-ops, and reading the start time before this falls back to first use.
+pub fn mark_started() {
+    STARTED.get_or_init(|| (Instant::now(), Utc::now()));
+}
+
+pub fn started_at() -> DateTime<Utc> {
+    STARTED.get_or_init(|| (Instant::now(), Utc::now())).1
+}
+
+pub fn uptime() -> Duration {
+    STARTED
+        .get_or_init(|| (Instant::now(), Utc::now()))
+        .0
+        .elapsed()
+}
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ComponentStatus {
+    Up,
+    Down,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ComponentHealth {
+    pub status: ComponentStatus,
+    #[serde(rename = "latencyMs")]
+    pub latency_ms: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl ComponentHealth {
+    /// Times `check` and converts its outcome into a component report.
+    pub fn probe<F: FnOnce() -> Result<(), String>>(check: F) -> Self {
+        let start = Instant::now();
+        let result = check();
+        let latency_ms = start.elapsed().as_secs_f64() * 1000.0;
+        match result {
+            Ok(()) => ComponentHealth {
+                status: ComponentStatus::Up,
+                latency_ms,
+                error: None,
+            },
+            Err(e) => ComponentHealth {
+                status: ComponentStatus::Down,
+                latency_ms,
+                error: Some(e),
+            },
+        }
+    }
+
+    pub fn is_up(&self) -> bool {
+        self.status == ComponentStatus::Up
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_probe_reports_status() {
+        let up = ComponentHealth::probe(|| Ok(()));
+        assert!(up.is_up());
+        assert!(up.error.is_none());
+
+        let down = ComponentHealth::probe(|| Err("lock timeout".to_string()));
+        assert!(!down.is_up());
+        assert_eq!(down.error.as_deref(), Some("lock timeout"));
+    }
+
+    #[test]
+    fn test_uptime_is_monotonic() {
+        mark_started();
+        let first = uptime();
+        let second = uptime();
+        assert!(second >= first);
+        assert!(started_at() <= Utc::now());
+    }
+}
@@ -0,0 +1,199 @@
+use crate::config::Config;
+use crate::plugins::{Kind, Plugin};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use spicy_todo_core::digest::Agenda;
+use spicy_todo_core::models::{self, Todo};
+
+/// The todo list as a Home Assistant RESTful sensor: the state is the
+/// number of active todos and the other counts are attributes, so a
+/// `rest` sensor can use `value_json.state` and `json_attributes`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Sensor {
+    pub state: usize,
+    pub attributes: SensorAttributes,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SensorAttributes {
+    pub active: usize,
+    #[serde(rename = "dueToday")]
+    pub due_today: usize,
+    pub overdue: usize,
+    pub unit_of_measurement: &'static str,
+    pub friendly_name: &'static str,
+    pub icon: &'static str,
+}
+
+impl Sensor {
+    /// Counts `todos` as of `today`. Completed and hidden todos are not
+    /// counted anywhere.
+    pub fn count(todos: &[Todo], today: NaiveDate) -> Self {
+        let active = todos
+            .iter()
+            .filter(|todo| !todo.completed && todo.hidden_state().is_none())
+            .count();
+        let agenda = Agenda::build(todos, today, 0);
+        Sensor {
+            state: active,
+            attributes: SensorAttributes {
+                active,
+                due_today: agenda.due_today.len(),
+                overdue: agenda.overdue.len(),
+                unit_of_measurement: "todos",
+                friendly_name: "Spicy todos",
+                icon: "mdi:format-list-checks",
+            },
+        }
+    }
+}
+
+/// Data for the `add_todo` service. `text` is parsed like quick-add, so
+/// `Buy milk tomorrow !high` works from a dashboard or voice assistant.
+#[derive(Debug, Deserialize)]
+pub struct AddTodoData {
+    pub text: String,
+}
+
+/// Data for the `complete_todo` service: the todo's id, or its text when
+/// the caller only knows what it says.
+#[derive(Debug, Deserialize)]
+pub struct CompleteTodoData {
+    pub id: Option<String>,
+    pub text: Option<String>,
+}
+
+/// Why `find_active` found no single todo to complete.
+#[derive(Debug, PartialEq)]
+pub enum LookupError {
+    Missing,
+    NotFound,
+    Ambiguous(usize),
+}
+
+impl std::fmt::Display for LookupError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LookupError::Missing => write!(f, "Either id or text is required"),
+            LookupError::NotFound => write!(f, "No active todo matches"),
+            LookupError::Ambiguous(count) => {
+                write!(f, "{} active todos match; complete it by id", count)
+            }
+        }
+    }
+}
+
+/// The active todo `data` names: by exact id, else by text ignoring case
+/// and surrounding whitespace.
+pub fn find_active<'a>(
+    todos: &'a [Todo],
+    data: &CompleteTodoData,
+) -> Result<&'a Todo, LookupError> {
+    let active = todos.iter().filter(|todo| !todo.completed);
+    let mut matches: Vec<&Todo> = match (&data.id, &data.text) {
+        (Some(id), _) => active
+            .filter(|todo| todo.id == models::todo_id(id))
+            .collect(),
+        (None, Some(text)) => {
+            let text = text.trim().to_lowercase();
+            active
+                .filter(|todo| todo.text.trim().to_lowercase() == text)
+                .collect()
+        }
+        (None, None) => return Err(LookupError::Missing),
+    };
+    match matches.len() {
+        0 => Err(LookupError::NotFound),
+        1 => Ok(matches.remove(0)),
+        count => Err(LookupError::Ambiguous(count)),
+    }
+}
+
+pub struct HomeAssistantPlugin;
+
+impl Plugin for HomeAssistantPlugin {
+    fn name(&self) -> &'static str {
+        "home-assistant"
+    }
+
+    fn kind(&self) -> Kind {
+        Kind::Automation
+    }
+
+    fn description(&self) -> &'static str {
+        "Home Assistant sensor and services for the todo list"
+    }
+
+    fn enabled(&self, _config: &Config) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spicy_todo_core::models::TodoCreate;
+    use spicy_todo_core::TodoService;
+
+    fn create(service: &TodoService, text: &str, due_date: Option<&str>) -> Todo {
+        service.create(TodoCreate {
+            text: text.to_string(),
+            due_date: due_date.map(str::to_string),
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_sensor_counts() {
+        let service = TodoService::new_empty();
+        create(&service, "Pay rent", Some("2024-06-09"));
+        create(&service, "Water plants", Some("2024-06-10"));
+        create(&service, "Call bank", Some("2024-06-12"));
+        let done = create(&service, "Take out bins", Some("2024-06-10"));
+        service.toggle(done.id);
+
+        let today = NaiveDate::from_ymd_opt(2024, 6, 10).unwrap();
+        let sensor = Sensor::count(&service.get_all(None, None, None), today);
+        assert_eq!(sensor.state, 3);
+        assert_eq!(sensor.attributes.due_today, 1);
+        assert_eq!(sensor.attributes.overdue, 1);
+    }
+
+    #[test]
+    fn test_find_active_by_id_or_text() {
+        let service = TodoService::new_empty();
+        let rent = create(&service, "Pay rent", None);
+        create(&service, "Water plants", None);
+        create(&service, "water plants", None);
+        let todos = service.get_all(None, None, None);
+
+        let by = |id: Option<&str>, text: Option<&str>| CompleteTodoData {
+            id: id.map(str::to_string),
+            text: text.map(str::to_string),
+        };
+        assert_eq!(
+            find_active(&todos, &by(Some(&rent.id.to_string()), None))
+                .unwrap()
+                .id,
+            rent.id
+        );
+        assert_eq!(
+            find_active(&todos, &by(None, Some(" PAY RENT ")))
+                .unwrap()
+                .id,
+            rent.id
+        );
+        assert_eq!(
+            find_active(&todos, &by(None, Some("Water plants"))).unwrap_err(),
+            LookupError::Ambiguous(2)
+        );
+        assert_eq!(
+            find_active(&todos, &by(None, Some("Feed cat"))).unwrap_err(),
+            LookupError::NotFound
+        );
+        assert_eq!(
+            find_active(&todos, &by(None, None)).unwrap_err(),
+            LookupError::Missing
+        );
+    }
+}
@@ -0,0 +1,235 @@
+use actix_web::body::{BoxBody, MessageBody};
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::header::{self, HeaderValue};
+use actix_web::middleware::Next;
+use fluent_bundle::concurrent::FluentBundle;
+use fluent_bundle::{FluentArgs, FluentResource, FluentValue};
+use fluent_langneg::{negotiate_languages, NegotiationStrategy};
+use std::cell::Cell;
+use std::rc::Rc;
+use std::sync::OnceLock;
+use unic_langid::LanguageIdentifier;
+
+/// The catalogs, the first being the source language, used for anything
+/// another lacks and outside requests.
+pub const LOCALES: [(&str, &str); 4] = [
+    ("en", include_str!("../locales/en.ftl")),
+    ("de", include_str!("../locales/de.ftl")),
+    ("es", include_str!("../locales/es.ftl")),
+    ("fr", include_str!("../locales/fr.ftl")),
+];
+
+tokio::task_local! {
+    /// The catalog negotiated for the request being handled.
+    static LANGUAGE: Language;
+}
+
+struct Language {
+    index: usize,
+    /// Set once a message is written in the language, so the response
+    /// can say which it is in.
+    used: Rc<Cell<bool>>,
+}
+
+/// The languages in an `Accept-Language` header, most wanted first. Those
+/// with `q=0` are left out.
+fn requested_languages(header: &str) -> Vec<LanguageIdentifier> {
+    let mut weighted: Vec<(f32, LanguageIdentifier)> = header
+        .split(',')
+        .filter_map(|part| {
+            let mut params = part.split(';');
+            let language = params.next()?.trim().parse().ok()?;
+            let weight = params
+                .find_map(|param| param.trim().strip_prefix("q="))
+                .map_or(Some(1.0), |q| q.trim().parse::<f32>().ok())?;
+            (weight > 0.0).then_some((weight, language))
+        })
+        .collect();
+    weighted.sort_by(|a, b| b.0.total_cmp(&a.0));
+    weighted.into_iter().map(|(_, language)| language).collect()
+}
+
+/// The message catalogs.
+pub struct Catalog {
+    locales: Vec<LanguageIdentifier>,
+    bundles: Vec<FluentBundle<FluentResource>>,
+}
+
+impl Catalog {
+    /// The catalogs in `LOCALES`.
+    pub fn builtin() -> Self {
+        let mut locales = Vec::new();
+        let mut bundles = Vec::new();
+        for (locale, source) in LOCALES {
+            let locale: LanguageIdentifier = locale.parse().expect("valid locale");
+            let resource =
+                FluentResource::try_new(source.to_string()).expect("valid message catalog");
+            let mut bundle = FluentBundle::new_concurrent(vec![locale.clone()]);
+            bundle.set_use_isolating(false);
+            bundle
+                .add_resource(resource)
+                .expect("message ids are unique");
+            locales.push(locale);
+            bundles.push(bundle);
+        }
+        Catalog { locales, bundles }
+    }
+
+    /// Message `id` from the catalog at `index`, if it has one.
+    pub fn format(&self, index: usize, id: &str, args: Option<&FluentArgs>) -> Option<String> {
+        let bundle = &self.bundles[index];
+        let pattern = bundle.get_message(id)?.value()?;
+        let mut errors = Vec::new();
+        Some(
+            bundle
+                .format_pattern(pattern, args, &mut errors)
+                .to_string(),
+        )
+    }
+
+    /// The best catalog for an `Accept-Language` header, by index; the
+    /// source language when nothing requested is available.
+    pub fn negotiate(&self, accept_language: Option<&str>) -> usize {
+        let requested = requested_languages(accept_language.unwrap_or(""));
+        let chosen = negotiate_languages(
+            &requested,
+            &self.locales,
+            Some(&self.locales[0]),
+            NegotiationStrategy::Lookup,
+        );
+        self.locales
+            .iter()
+            .position(|locale| Some(&locale) == chosen.first())
+            .unwrap_or(0)
+    }
+
+    pub fn locale(&self, index: usize) -> &LanguageIdentifier {
+        &self.locales[index]
+    }
+}
+
+/// The built-in catalogs, parsed on first use.
+pub fn catalog() -> &'static Catalog {
+    static CATALOG: OnceLock<Catalog> = OnceLock::new();
+    CATALOG.get_or_init(Catalog::builtin)
+}
+
+/// Message `id` in the language negotiated for the current request.
+pub fn message(id: &str) -> String {
+    localized(id, None)
+}
+
+/// Message `id` with its `{ $name }` placeholders filled from `args`.
+pub fn message_with<'a>(
+    id: &str,
+    args: impl IntoIterator<Item = (&'a str, FluentValue<'a>)>,
+) -> String {
+    localized(id, Some(&args.into_iter().collect()))
+}
+
+fn localized(id: &str, args: Option<&FluentArgs>) -> String {
+    let catalog = catalog();
+    let translated = LANGUAGE
+        .try_with(|language| {
+            let message = catalog.format(language.index, id, args)?;
+            language.used.set(true);
+            Some(message)
+        })
+        .ok()
+        .flatten();
+    translated
+        .or_else(|| catalog.format(0, id, args))
+        .unwrap_or_else(|| id.to_string())
+}
+
+/// Negotiates the client's `Accept-Language` before the request is
+/// handled, so the messages handlers write come out in that language, and
+/// says which it got in `Content-Language`.
+pub async fn localize_responses(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<BoxBody>, actix_web::Error> {
+    let index = catalog().negotiate(
+        req.headers()
+            .get(header::ACCEPT_LANGUAGE)
+            .and_then(|value| value.to_str().ok()),
+    );
+    let used = Rc::new(Cell::new(false));
+    let language = Language {
+        index,
+        used: used.clone(),
+    };
+    let mut response = LANGUAGE
+        .scope(language, next.call(req))
+        .await?
+        .map_into_boxed_body();
+    response
+        .headers_mut()
+        .append(header::VARY, HeaderValue::from_static("accept-language"));
+    if index != 0 && used.get() {
+        let locale = catalog().locale(index).to_string();
+        if let Ok(locale) = HeaderValue::from_str(&locale) {
+            response
+                .headers_mut()
+                .insert(header::CONTENT_LANGUAGE, locale);
+        }
+    }
+    Ok(response)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fluent_syntax::ast::Entry;
+
+    #[test]
+    fn test_every_locale_has_every_message() {
+        let source = FluentResource::try_new(LOCALES[0].1.to_string()).unwrap();
+        let ids: Vec<&str> = source
+            .entries()
+            .filter_map(|entry| match entry {
+                Entry::Message(message) => Some(message.id.name),
+                _ => None,
+            })
+            .collect();
+        assert!(ids.len() > 30);
+        for index in 1..LOCALES.len() {
+            for id in &ids {
+                assert!(
+                    catalog().format(index, id, None).is_some(),
+                    "{} has no '{}'",
+                    catalog().locale(index),
+                    id
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_negotiates_and_formats_in_the_request_language() {
+        let catalog = catalog();
+        assert_eq!(catalog.negotiate(None), 0);
+        assert_eq!(catalog.negotiate(Some("pt-BR, ja;q=0.5")), 0);
+        let german = catalog.negotiate(Some("de-AT, en;q=0.5"));
+        assert_eq!(catalog.locale(german).to_string(), "de");
+        let spanish = catalog.negotiate(Some("fr;q=0.4, es-MX;q=0.9"));
+        assert_eq!(catalog.locale(spanish).to_string(), "es");
+        assert_eq!(catalog.negotiate(Some("de;q=0, *")), 0);
+
+        assert_eq!(message("todo-not-found"), "Todo not found");
+        let used = Rc::new(Cell::new(false));
+        let language = Language {
+            index: german,
+            used: used.clone(),
+        };
+        let (plain, with_args) = LANGUAGE.sync_scope(language, || {
+            (
+                message("todo-not-found"),
+                message_with("client-header-required", [("header", "x-client-id".into())]),
+            )
+        });
+        assert!(used.get());
+        assert_eq!(plain, "Aufgabe nicht gefunden");
+        assert_eq!(with_args, "Der Header x-client-id ist erforderlich");
+    }
+}
@@ -0,0 +1,287 @@
+use crate::config::Config;
+use crate::plugins::{Kind, Plugin};
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use serde::{Deserialize, Serialize};
+use spicy_todo_core::models::{todo_id, Priority, Todo};
+use std::time::Duration;
+use uuid::Uuid;
+
+const FETCH_TIMEOUT: Duration = Duration::from_secs(30);
+const MAX_RESPONSE_BYTES: usize = 16 * 1024 * 1024;
+
+/// A todo as served by any of the sibling implementations. The Python API
+/// uses snake_case and `HH:MM:SS` reminder times, the Node one camelCase.
+#[derive(Debug, Deserialize)]
+pub struct SourceTodo {
+    #[serde(default)]
+    pub id: Option<serde_json::Value>,
+    #[serde(default)]
+    pub text: Option<String>,
+    #[serde(default)]
+    pub priority: Option<String>,
+    #[serde(default)]
+    pub completed: Option<bool>,
+    #[serde(default, rename = "dueDate", alias = "due_date")]
+    pub due_date: Option<String>,
+    #[serde(default, rename = "reminderTime", alias = "reminder_time")]
+    pub reminder_time: Option<String>,
+    #[serde(default, rename = "createdAt", alias = "created_at")]
+    pub created_at: Option<String>,
+    #[serde(default, rename = "updatedAt", alias = "updated_at")]
+    pub updated_at: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ImportQuery {
+    pub source_url: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Rejected {
+    #[serde(rename = "sourceId")]
+    pub source_id: Option<String>,
+    pub error: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImportReport {
+    pub source: String,
+    pub fetched: usize,
+    pub imported: usize,
+    /// Already present here, usually from an earlier run of the same import.
+    pub skipped: usize,
+    pub rejected: Vec<Rejected>,
+}
+
+/// Resolves the list endpoint for a source instance. Accepts either the
+/// instance's base URL or its `/api/todos` URL.
+pub fn todos_url(source_url: &str) -> Result<String, String> {
+    let trimmed = source_url.trim().trim_end_matches('/');
+    let uri: awc::http::Uri = trimmed
+        .parse()
+        .map_err(|_| format!("Invalid source_url '{}'", source_url))?;
+    if !matches!(uri.scheme_str(), Some("http") | Some("https")) || uri.host().is_none() {
+        return Err("source_url must be an absolute http(s) URL".to_string());
+    }
+    if uri.query().is_some() {
+        return Err("source_url must not contain a query string".to_string());
+    }
+    if trimmed.ends_with("/api/todos") {
+        Ok(trimmed.to_string())
+    } else {
+        Ok(format!("{}/api/todos", trimmed))
+    }
+}
+
+/// Fetches every todo from a running source instance.
+pub async fn fetch(url: &str) -> Result<Vec<SourceTodo>, String> {
+    let client = awc::Client::builder().timeout(FETCH_TIMEOUT).finish();
+    let mut response = client
+        .get(url)
+        .insert_header(("Accept", "application/json"))
+        .send()
+        .await
+        .map_err(|e| format!("Failed to reach {}: {}", url, e))?;
+    if !response.status().is_success() {
+        return Err(format!("{} responded with {}", url, response.status()));
+    }
+    response
+        .json::<Vec<SourceTodo>>()
+        .limit(MAX_RESPONSE_BYTES)
+        .await
+        .map_err(|e| format!("Unexpected response from {}: {}", url, e))
+}
+
+/// Converts a source todo, keeping its id and timestamps where present.
+pub fn convert(source: SourceTodo, now: DateTime<Utc>) -> Result<Todo, String> {
+    let text = source.text.unwrap_or_default().trim().to_string();
+    if text.is_empty() {
+        return Err("Todo text is required".to_string());
+    }
+    if text.len() > 500 {
+        return Err("Todo text must be less than 500 characters".to_string());
+    }
+
+    let priority = match source.priority.as_deref().map(str::to_lowercase) {
+        None => Priority::default(),
+        Some(p) if p == "low" => Priority::Low,
+        Some(p) if p == "medium" => Priority::Medium,
+        Some(p) if p == "high" => Priority::High,
+        Some(p) => return Err(format!("Unknown priority '{}'", p)),
+    };
+
+    let due_date = match source.due_date.filter(|d| !d.is_empty()) {
+        Some(due) => {
+            let day = due.get(..10).unwrap_or(&due);
+            NaiveDate::parse_from_str(day, "%Y-%m-%d")
+                .map_err(|_| format!("Invalid due date '{}'", due))?;
+            Some(day.to_string())
+        }
+        None => None,
+    };
+
+    let reminder_time = match source.reminder_time.filter(|t| !t.is_empty()) {
+        Some(reminder) => {
+            let parsed = NaiveTime::parse_from_str(&reminder, "%H:%M")
+                .or_else(|_| NaiveTime::parse_from_str(&reminder, "%H:%M:%S%.f"))
+                .map_err(|_| format!("Invalid reminder time '{}'", reminder))?;
+            Some(parsed.format("%H:%M").to_string())
+        }
+        None => None,
+    };
+
+    let id = match source.id {
+        Some(serde_json::Value::String(id)) if !id.is_empty() => todo_id(&id),
+        Some(serde_json::Value::Number(id)) => todo_id(&id.to_string()),
+        _ => Uuid::new_v4(),
+    };
+    let created_at = source
+        .created_at
+        .as_deref()
+        .and_then(timestamp)
+        .unwrap_or(now);
+    let updated_at = source
+        .updated_at
+        .as_deref()
+        .and_then(timestamp)
+        .unwrap_or(created_at);
+
+    Ok(Todo {
+        id,
+        text,
+        priority,
+        completed: source.completed.unwrap_or(false),
+        due_date,
+        reminder_time,
+        recurrence: None,
+        recurrence_end: None,
+        remaining_occurrences: None,
+        estimate_minutes: None,
+        location: None,
+        color: None,
+        icon: None,
+        pinned: false,
+        rollover_count: 0,
+        rolled_over_to: None,
+        created_at,
+        updated_at,
+    })
+}
+
+/// RFC 3339, or a naive ISO timestamp (as Python emits) taken to be UTC.
+fn timestamp(value: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|at| at.with_timezone(&Utc))
+        .ok()
+        .or_else(|| {
+            NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S%.f")
+                .ok()
+                .map(|naive| naive.and_utc())
+        })
+}
+
+pub struct SpicyImportPlugin;
+
+impl Plugin for SpicyImportPlugin {
+    fn name(&self) -> &'static str {
+        "spicy-import"
+    }
+
+    fn kind(&self) -> Kind {
+        Kind::Importer
+    }
+
+    fn description(&self) -> &'static str {
+        "Imports todos from another Spicy Todo implementation"
+    }
+
+    fn enabled(&self, _config: &Config) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(json: serde_json::Value) -> SourceTodo {
+        serde_json::from_value(json).unwrap()
+    }
+
+    #[test]
+    fn test_todos_url() {
+        assert_eq!(
+            todos_url("http://localhost:8001/").unwrap(),
+            "http://localhost:8001/api/todos"
+        );
+        assert_eq!(
+            todos_url("http://localhost:3001/api/todos").unwrap(),
+            "http://localhost:3001/api/todos"
+        );
+        assert!(todos_url("file:///etc/passwd").is_err());
+        assert!(todos_url("localhost:8001").is_err());
+    }
+
+    #[test]
+    fn test_convert_python_shape() {
+        let todo = convert(
+            parse(serde_json::json!({
+                "id": "py-1",
+                "text": "From Python",
+                "priority": "high",
+                "completed": true,
+                "due_date": "2024-03-01",
+                "reminder_time": "09:30:00",
+                "created_at": "2024-02-01T10:00:00.123456",
+                "updated_at": "2024-02-02T10:00:00"
+            })),
+            Utc::now(),
+        )
+        .unwrap();
+        assert_eq!(todo.id, todo_id("py-1"));
+        assert_eq!(todo.priority, Priority::High);
+        assert!(todo.completed);
+        assert_eq!(todo.due_date.as_deref(), Some("2024-03-01"));
+        assert_eq!(todo.reminder_time.as_deref(), Some("09:30"));
+        assert_eq!(
+            todo.created_at.to_rfc3339(),
+            "2024-02-01T10:00:00.123456+00:00"
+        );
+    }
+
+    #[test]
+    fn test_convert_node_shape() {
+        let now = Utc::now();
+        let todo = convert(
+            parse(serde_json::json!({
+                "id": 42,
+                "text": "From Node",
+                "dueDate": "2024-03-01T00:00:00.000Z",
+                "reminderTime": "18:00",
+                "createdAt": "2024-02-01T10:00:00.000Z"
+            })),
+            now,
+        )
+        .unwrap();
+        assert_eq!(todo.id, todo_id("42"));
+        assert_eq!(todo.priority, Priority::Medium);
+        assert_eq!(todo.due_date.as_deref(), Some("2024-03-01"));
+        assert_eq!(todo.updated_at, todo.created_at);
+    }
+
+    #[test]
+    fn test_convert_rejects_invalid() {
+        let now = Utc::now();
+        assert!(convert(parse(serde_json::json!({ "text": " " })), now).is_err());
+        assert!(convert(
+            parse(serde_json::json!({ "text": "x", "priority": "urgent" })),
+            now
+        )
+        .is_err());
+        assert!(convert(
+            parse(serde_json::json!({ "text": "x", "due_date": "soon" })),
+            now
+        )
+        .is_err());
+    }
+}
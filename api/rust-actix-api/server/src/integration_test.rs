@@ -0,0 +1,1131 @@
+#[cfg(test)]
+mod integration_tests {
+    use crate::config::Config;
+    use crate::handlers::*;
+    use spicy_todo_core::service::TodoService;
+    use actix_web::{test, web, App, HttpResponse, HttpServer};
+
+    #[actix_web::test]
+    async fn test_full_crud_lifecycle() {
+        let service = web::Data::new(TodoService::new_empty());
+        let app = test::init_service(
+            App::new()
+                .app_data(service.clone())
+                .route("/api/todos", web::get().to(get_todos))
+                .route("/api/todos", web::post().to(create_todo))
+                .route("/api/todos/{id}", web::get().to(get_todo))
+                .route("/api/todos/{id}", web::put().to(update_todo))
+                .route("/api/todos/{id}", web::delete().to(delete_todo))
+                .route("/api/todos/{id}/toggle", web::patch().to(toggle_todo)),
+        )
+        .await;
+
+        // 1. Create a todo
+        let create_req = test::TestRequest::post()
+            .uri("/api/todos")
+            .set_json(serde_json::json!({
+                "text": "Integration Test Todo",
+                "priority": "high",
+                "completed": false,
+                "dueDate": "2024-12-31",
+                "reminderTime": "10:00"
+            }))
+            .to_request();
+
+        let create_resp = test::call_service(&app, create_req).await;
+        assert_eq!(create_resp.status(), 201);
+
+        let created: serde_json::Value = test::read_body_json(create_resp).await;
+        let todo_id = created["id"].as_str().unwrap();
+
+        // 2. Get the todo by ID
+        let get_req = test::TestRequest::get()
+            .uri(&format!("/api/todos/{}", todo_id))
+            .to_request();
+
+        let get_resp = test::call_service(&app, get_req).await;
+        assert_eq!(get_resp.status(), 200);
+
+        let get_body: serde_json::Value = test::read_body_json(get_resp).await;
+        assert_eq!(get_body["text"], "Integration Test Todo");
+        assert_eq!(get_body["priority"], "high");
+
+        // 3. Update the todo
+        let update_req = test::TestRequest::put()
+            .uri(&format!("/api/todos/{}", todo_id))
+            .set_json(serde_json::json!({
+                "text": "Updated Integration Test Todo",
+                "priority": "medium",
+                "completed": true
+            }))
+            .to_request();
+
+        let update_resp = test::call_service(&app, update_req).await;
+        assert_eq!(update_resp.status(), 200);
+
+        let update_body: serde_json::Value = test::read_body_json(update_resp).await;
+        assert_eq!(update_body["text"], "Updated Integration Test Todo");
+        assert_eq!(update_body["priority"], "medium");
+        assert_eq!(update_body["completed"], true);
+
+        // 4. Toggle the todo
+        let toggle_req = test::TestRequest::patch()
+            .uri(&format!("/api/todos/{}/toggle", todo_id))
+            .to_request();
+
+        let toggle_resp = test::call_service(&app, toggle_req).await;
+        assert_eq!(toggle_resp.status(), 200);
+
+        let toggle_body: serde_json::Value = test::read_body_json(toggle_resp).await;
+        assert_eq!(toggle_body["completed"], false);
+
+        // 5. Get all todos
+        let get_all_req = test::TestRequest::get()
+            .uri("/api/todos")
+            .to_request();
+
+        let get_all_resp = test::call_service(&app, get_all_req).await;
+        assert_eq!(get_all_resp.status(), 200);
+
+        // 6. Delete the todo
+        let delete_req = test::TestRequest::delete()
+            .uri(&format!("/api/todos/{}", todo_id))
+            .to_request();
+
+        let delete_resp = test::call_service(&app, delete_req).await;
+        assert_eq!(delete_resp.status(), 200);
+
+        // 7. Verify deletion
+        let verify_req = test::TestRequest::get()
+            .uri(&format!("/api/todos/{}", todo_id))
+            .to_request();
+
+        let verify_resp = test::call_service(&app, verify_req).await;
+        assert_eq!(verify_resp.status(), 404);
+    }
+
+    #[actix_web::test]
+    async fn test_create_and_filter_todos() {
+        let service = web::Data::new(TodoService::new_empty());
+        let app = test::init_service(
+            App::new()
+                .app_data(service.clone())
+                .route("/api/todos", web::get().to(get_todos))
+                .route("/api/todos", web::post().to(create_todo)),
+        )
+        .await;
+
+        // Create multiple todos
+        for i in 0..3 {
+            let req = test::TestRequest::post()
+                .uri("/api/todos")
+                .set_json(serde_json::json!({
+                    "text": format!("Test Todo {}", i),
+                    "priority": if i % 2 == 0 { "high" } else { "low" },
+                    "completed": i % 2 == 0
+                }))
+                .to_request();
+
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(resp.status(), 201);
+        }
+
+        // Test filtering by active
+        let req = test::TestRequest::get()
+            .uri("/api/todos?filter=active")
+            .to_request();
+
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 200);
+
+        // Test filtering by completed
+        let req = test::TestRequest::get()
+            .uri("/api/todos?filter=completed")
+            .to_request();
+
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 200);
+
+        // Test filtering by priority
+        let req = test::TestRequest::get()
+            .uri("/api/todos?priority=high")
+            .to_request();
+
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 200);
+
+        // Test search
+        let req = test::TestRequest::get()
+            .uri("/api/todos?search=Test")
+            .to_request();
+
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 200);
+    }
+
+    #[actix_web::test]
+    async fn test_clear_completed_functionality() {
+        let service = web::Data::new(TodoService::new_empty());
+        let app = test::init_service(
+            App::new()
+                .app_data(service.clone())
+                .route("/api/todos", web::get().to(get_todos))
+                .route("/api/todos", web::post().to(create_todo))
+                .route("/api/todos/completed", web::delete().to(clear_completed)),
+        )
+        .await;
+
+        // Create completed todos
+        for i in 0..2 {
+            let req = test::TestRequest::post()
+                .uri("/api/todos")
+                .set_json(serde_json::json!({
+                    "text": format!("Completed Todo {}", i),
+                    "completed": true
+                }))
+                .to_request();
+
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(resp.status(), 201);
+        }
+
+        // Create active todo
+        let req = test::TestRequest::post()
+            .uri("/api/todos")
+            .set_json(serde_json::json!({
+                "text": "Active Todo",
+                "completed": false
+            }))
+            .to_request();
+
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 201);
+
+        // Clear completed
+        let req = test::TestRequest::delete()
+            .uri("/api/todos/completed")
+            .to_request();
+
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 200);
+
+        // Verify only active todos remain
+        let req = test::TestRequest::get()
+            .uri("/api/todos?filter=active")
+            .to_request();
+
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 200);
+
+        let body: Vec<serde_json::Value> = test::read_body_json(resp).await;
+        assert_eq!(body.len(), 1);
+        assert_eq!(body[0]["text"], "Active Todo");
+    }
+
+    #[actix_web::test]
+    async fn test_stats_with_due_dates() {
+        let service = web::Data::new(TodoService::new_empty());
+        let app = test::init_service(
+            App::new()
+                .app_data(service.clone())
+                .route("/api/todos/stats/summary", web::get().to(get_stats)),
+        )
+        .await;
+
+        let req = test::TestRequest::get()
+            .uri("/api/todos/stats/summary")
+            .to_request();
+
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 200);
+
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert!(body["total"].is_number());
+        assert!(body["active"].is_number());
+        assert!(body["completed"].is_number());
+        assert!(body["completionRate"].is_number());
+        assert!(body["weightedCompletionRate"].is_number());
+        assert!(body["priorityBreakdown"].is_object());
+    }
+
+    #[actix_web::test]
+    async fn test_multiple_concurrent_requests() {
+        let service = web::Data::new(TodoService::new_empty());
+        let app = test::init_service(
+            App::new()
+                .app_data(service.clone())
+                .route("/api/todos", web::post().to(create_todo)),
+        )
+        .await;
+
+        // Create multiple todos concurrently
+        let app = std::rc::Rc::new(app);
+        let mut handles = vec![];
+
+        for i in 0..5 {
+            let app = app.clone();
+            let handle = actix_rt::spawn(async move {
+                let req = test::TestRequest::post()
+                    .uri("/api/todos")
+                    .set_json(serde_json::json!({
+                        "text": format!("Concurrent Todo {}", i),
+                        "priority": "medium"
+                    }))
+                    .to_request();
+
+                let resp = test::call_service(&*app, req).await;
+                resp.status().is_success()
+            });
+            handles.push(handle);
+        }
+
+        // Wait for all requests to complete
+        for handle in handles {
+            let result = handle.await.unwrap();
+            assert!(result);
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_validation_errors() {
+        let service = web::Data::new(TodoService::new_empty());
+        let app = test::init_service(
+            App::new()
+                .app_data(service.clone())
+                .route("/api/todos", web::post().to(create_todo)),
+        )
+        .await;
+
+        // Test empty text
+        let req = test::TestRequest::post()
+            .uri("/api/todos")
+            .set_json(serde_json::json!({
+                "text": ""
+            }))
+            .to_request();
+
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 400);
+
+        // Test text too long
+        let long_text = "a".repeat(501);
+        let req = test::TestRequest::post()
+            .uri("/api/todos")
+            .set_json(serde_json::json!({
+                "text": long_text
+            }))
+            .to_request();
+
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 400);
+    }
+
+    #[actix_web::test]
+    async fn test_not_found_scenarios() {
+        let service = web::Data::new(TodoService::new_empty());
+        let app = test::init_service(
+            App::new()
+                .app_data(service.clone())
+                .route("/api/todos/{id}", web::get().to(get_todo))
+                .route("/api/todos/{id}", web::put().to(update_todo))
+                .route("/api/todos/{id}", web::delete().to(delete_todo))
+                .route("/api/todos/{id}/toggle", web::patch().to(toggle_todo)),
+        )
+        .await;
+
+        let non_existent_id = "non-existent-id";
+
+        // Get non-existent todo
+        let req = test::TestRequest::get()
+            .uri(&format!("/api/todos/{}", non_existent_id))
+            .to_request();
+
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 404);
+
+        // Update non-existent todo
+        let req = test::TestRequest::put()
+            .uri(&format!("/api/todos/{}", non_existent_id))
+            .set_json(serde_json::json!({
+                "text": "Updated"
+            }))
+            .to_request();
+
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 404);
+
+        // Delete non-existent todo
+        let req = test::TestRequest::delete()
+            .uri(&format!("/api/todos/{}", non_existent_id))
+            .to_request();
+
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 404);
+
+        // Toggle non-existent todo
+        let req = test::TestRequest::patch()
+            .uri(&format!("/api/todos/{}/toggle", non_existent_id))
+            .to_request();
+
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 404);
+    }
+
+    #[actix_web::test]
+    async fn test_event_log_search() {
+        let service = web::Data::new(TodoService::new_empty());
+        let app = test::init_service(
+            App::new()
+                .app_data(service.clone())
+                .route("/api/todos", web::post().to(create_todo))
+                .route("/api/todos/{id}/toggle", web::patch().to(toggle_todo))
+                .route("/api/events/log", web::get().to(get_event_log)),
+        )
+        .await;
+
+        let mut ids = vec![];
+        for text in ["First", "Second", "Third"] {
+            let req = test::TestRequest::post()
+                .uri("/api/todos")
+                .set_json(serde_json::json!({ "text": text }))
+                .to_request();
+            let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+            ids.push(body["id"].as_str().unwrap().to_string());
+        }
+        let req = test::TestRequest::patch()
+            .uri(&format!("/api/todos/{}/toggle", ids[0]))
+            .to_request();
+        test::call_service(&app, req).await;
+
+        // Everything, paginated
+        let req = test::TestRequest::get()
+            .uri("/api/events/log?limit=2")
+            .to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["total"], 4);
+        assert_eq!(body["items"].as_array().unwrap().len(), 2);
+        assert_eq!(body["hasMore"], true);
+
+        // By todo and type
+        let req = test::TestRequest::get()
+            .uri(&format!("/api/events/log?todoId={}&type=todo.completed", ids[0]))
+            .to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["total"], 1);
+        assert_eq!(body["items"][0]["type"], "todo.completed");
+
+        // Unknown type
+        let req = test::TestRequest::get()
+            .uri("/api/events/log?type=todo.exploded")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 400);
+    }
+
+    #[actix_web::test]
+    async fn test_import_from_python_instance() {
+        use crate::moderation::{Moderation, ModerationMode, WordList};
+        use spicy_todo_core::models::todo_id;
+
+        // Stand-in for the Python API: snake_case fields, naive timestamps
+        let source = HttpServer::new(|| {
+            App::new().route(
+                "/api/todos",
+                web::get().to(|| async {
+                    HttpResponse::Ok().json(serde_json::json!([
+                        {
+                            "id": "py-1",
+                            "text": "Imported",
+                            "priority": "high",
+                            "completed": false,
+                            "due_date": "2024-03-01",
+                            "reminder_time": "09:00:00",
+                            "created_at": "2024-02-01T10:00:00",
+                            "updated_at": "2024-02-01T10:00:00"
+                        },
+                        { "id": "py-2", "text": "" },
+                        { "id": "py-3", "text": "Eat spinach" }
+                    ]))
+                }),
+            )
+        })
+        .workers(1)
+        .bind(("127.0.0.1", 0))
+        .unwrap();
+        let addr = source.addrs()[0];
+        let handle = source.run();
+        actix_rt::spawn(handle);
+
+        let service = web::Data::new(TodoService::new_empty());
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(Config {
+                    admin_token: Some("secret-token".to_string()),
+                    ..Default::default()
+                }))
+                .app_data(web::Data::new(Moderation::new(
+                    ModerationMode::Reject,
+                    vec![Box::new(WordList::new(&["spinach".to_string()]))],
+                )))
+                .app_data(service.clone())
+                .route("/api/import/spicy", web::post().to(import_spicy)),
+        )
+        .await;
+
+        let uri = format!("/api/import/spicy?source_url=http://{}", addr);
+        let req = test::TestRequest::post()
+            .uri(&uri)
+            .insert_header(("Authorization", "Bearer secret-token"))
+            .to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["fetched"], 3);
+        assert_eq!(body["imported"], 1);
+        assert_eq!(body["rejected"][0]["sourceId"], "py-2");
+        assert_eq!(body["rejected"][1]["sourceId"], "py-3");
+        assert_eq!(
+            body["rejected"][1]["error"],
+            "Todo text contains disallowed content"
+        );
+        let todo = service.get_by_id(todo_id("py-1")).unwrap();
+        assert_eq!(todo.reminder_time.as_deref(), Some("09:00"));
+
+        // Re-running is harmless
+        let req = test::TestRequest::post()
+            .uri(&uri)
+            .insert_header(("Authorization", "Bearer secret-token"))
+            .to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["imported"], 0);
+        assert_eq!(body["skipped"], 1);
+
+        let req = test::TestRequest::post()
+            .uri("/api/import/spicy?source_url=http://127.0.0.1:1")
+            .insert_header(("Authorization", "Bearer secret-token"))
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 502);
+    }
+
+    #[actix_web::test]
+    async fn test_transfer_to_peer_instance() {
+        let peer_service = web::Data::new(TodoService::new_empty());
+        let peer_data = peer_service.clone();
+        let peer = HttpServer::new(move || {
+            App::new()
+                .app_data(peer_data.clone())
+                .route("/api/todos/import", web::post().to(import_todo))
+        })
+        .workers(1)
+        .bind(("127.0.0.1", 0))
+        .unwrap();
+        let addr = peer.addrs()[0];
+        actix_rt::spawn(peer.run());
+
+        let service = web::Data::new(TodoService::new_empty());
+        let config = Config {
+            transfer_peers: crate::transfer::parse_peers(&format!(
+                "work=http://{},gone=http://127.0.0.1:1",
+                addr
+            ))
+            .unwrap(),
+            ..Default::default()
+        };
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(config))
+                .app_data(service.clone())
+                .route("/api/todos/{id}/transfer", web::post().to(transfer_todo)),
+        )
+        .await;
+        let todo = service.create(spicy_todo_core::models::TodoCreate {
+            text: "Quarterly report".to_string(),
+            ..Default::default()
+        });
+        let transfer = |peer: &str| {
+            test::TestRequest::post()
+                .uri(&format!("/api/todos/{}/transfer", todo.id))
+                .set_json(serde_json::json!({ "peer": peer }))
+                .to_request()
+        };
+
+        let resp = test::call_service(&app, transfer("nowhere")).await;
+        assert_eq!(resp.status(), 400);
+        let resp = test::call_service(&app, transfer("gone")).await;
+        assert_eq!(resp.status(), 502);
+        assert!(service.get_by_id(todo.id).is_some());
+
+        let resp = test::call_service(&app, transfer("work")).await;
+        assert_eq!(resp.status(), 200);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["todo"]["id"], todo.id.to_string());
+        assert!(service.get_by_id(todo.id).is_none());
+        assert_eq!(peer_service.get_by_id(todo.id).unwrap().text, "Quarterly report");
+
+        let resp = test::call_service(&app, transfer("work")).await;
+        assert_eq!(resp.status(), 404);
+    }
+
+    #[actix_web::test]
+    async fn test_push_to_ntfy_and_gotify() {
+        use crate::push::{Notification, PushService, PushTarget};
+        use actix_web::HttpRequest;
+        use spicy_todo_core::models::Priority;
+        use std::sync::{Arc, Mutex};
+
+        type Received = Arc<Mutex<Vec<(String, String, String)>>>;
+        let received: Received = Arc::default();
+        let log = received.clone();
+        let fake = HttpServer::new(move || {
+            let log = log.clone();
+            App::new().default_service(web::to(move |req: HttpRequest, body: String| {
+                let header = |name: &str| {
+                    req.headers()
+                        .get(name)
+                        .and_then(|value| value.to_str().ok())
+                        .unwrap_or_default()
+                        .to_string()
+                };
+                let auth = format!("{}{}", header("authorization"), header("x-gotify-key"));
+                let priority = header("priority");
+                log.lock()
+                    .unwrap()
+                    .push((req.path().to_string(), format!("{}|{}", auth, priority), body));
+                async { HttpResponse::Ok().finish() }
+            }))
+        })
+        .workers(1)
+        .bind(("127.0.0.1", 0))
+        .unwrap();
+        let server = format!("http://{}", fake.addrs()[0]);
+        actix_rt::spawn(fake.run());
+
+        let push = web::Data::new(PushService::new());
+        let app = test::init_service(
+            App::new()
+                .app_data(push.clone())
+                .route("/api/notifications/push", web::put().to(put_push_subscription))
+                .route("/api/notifications/push/test", web::post().to(test_push_subscription)),
+        )
+        .await;
+        let req = test::TestRequest::put()
+            .uri("/api/notifications/push")
+            .insert_header(("X-Client-Id", "laptop"))
+            .set_json(serde_json::json!({
+                "targets": [
+                    {"kind": "ntfy", "server": server, "topic": "chores", "token": "tk"},
+                    {"kind": "gotify", "server": server, "token": "app-token"},
+                    {"kind": "gotify", "server": "http://127.0.0.1:1", "token": "gone"}
+                ],
+                "minPriority": "medium"
+            }))
+            .to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert!(body["targets"][0].get("token").is_none());
+
+        let req = test::TestRequest::post()
+            .uri("/api/notifications/push/test")
+            .insert_header(("X-Client-Id", "laptop"))
+            .to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        let delivered: Vec<bool> = body["results"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|result| result["delivered"].as_bool().unwrap())
+            .collect();
+        assert_eq!(delivered, vec![true, true, false]);
+
+        received.lock().unwrap().clear();
+        let urgent = Notification {
+            title: "Urgent reminder".to_string(),
+            message: "Urgent: Pay rent".to_string(),
+            priority: Priority::High,
+        };
+        assert_eq!(push.broadcast(&urgent, &Default::default()).await, 2);
+        let low = Notification {
+            priority: Priority::Low,
+            ..urgent
+        };
+        assert_eq!(push.broadcast(&low, &Default::default()).await, 0);
+
+        let received = received.lock().unwrap().clone();
+        assert_eq!(
+            received[0],
+            (
+                "/chores".to_string(),
+                "Bearer tk|high".to_string(),
+                "Urgent: Pay rent".to_string()
+            )
+        );
+        assert_eq!(received[1].0, "/message");
+        assert_eq!(received[1].1, "app-token|");
+        let gotify: serde_json::Value = serde_json::from_str(&received[1].2).unwrap();
+        assert_eq!(gotify["priority"], 8);
+        assert!(matches!(
+            push.get("laptop").unwrap().targets[0],
+            PushTarget::Ntfy { .. }
+        ));
+        assert!(push.get("nobody").is_none());
+    }
+
+    #[actix_web::test]
+    async fn test_matrix_bot_answers_commands() {
+        use crate::matrix::{Bot, MatrixRoom};
+        use actix_web::HttpRequest;
+        use spicy_todo_core::TodoService;
+        use std::sync::{Arc, Mutex};
+        use std::time::Duration;
+
+        let sent: Arc<Mutex<Vec<String>>> = Arc::default();
+        let log = sent.clone();
+        let homeserver = HttpServer::new(move || {
+            let log = log.clone();
+            App::new().default_service(web::to(move |req: HttpRequest, body: String| {
+                let path = req.path().to_string();
+                let response = if path.ends_with("/account/whoami") {
+                    serde_json::json!({ "user_id": "@todo-bot:example" })
+                } else if path.ends_with("/sync") && !req.query_string().contains("since=") {
+                    serde_json::json!({ "next_batch": "s1" })
+                } else if path.ends_with("/sync") {
+                    let message = |sender: &str, body: &str| {
+                        serde_json::json!({
+                            "type": "m.room.message",
+                            "sender": sender,
+                            "content": { "msgtype": "m.text", "body": body }
+                        })
+                    };
+                    serde_json::json!({
+                        "next_batch": "s2",
+                        "rooms": { "join": { "!room:example": { "timeline": { "events": [
+                            message("@alice:example", "!add Pay rent !high"),
+                            message("@alice:example", "just chatting"),
+                            message("@todo-bot:example", "!list")
+                        ]}}}}
+                    })
+                } else {
+                    let content: serde_json::Value = serde_json::from_str(&body).unwrap();
+                    log.lock()
+                        .unwrap()
+                        .push(content["body"].as_str().unwrap().to_string());
+                    serde_json::json!({ "event_id": "$sent" })
+                };
+                async move { HttpResponse::Ok().json(response) }
+            }))
+        })
+        .workers(1)
+        .bind(("127.0.0.1", 0))
+        .unwrap();
+        let url = format!("http://{}", homeserver.addrs()[0]);
+        actix_rt::spawn(homeserver.run());
+
+        let room = MatrixRoom::new(&url, "token", "!room:example")
+            .with_poll_timeout(Duration::from_millis(10));
+        let service = web::Data::new(TodoService::new_empty());
+        let mut bot = Bot::start(web::Data::new(room), service.clone(), Default::default())
+            .await
+            .unwrap();
+        assert_eq!(bot.poll().await.unwrap(), 1);
+
+        let todos = service.get_all(None, None, None);
+        assert_eq!(todos.len(), 1);
+        assert_eq!(todos[0].text, "Pay rent");
+        let sent = sent.lock().unwrap().clone();
+        assert_eq!(
+            sent,
+            vec![format!(
+                "Added: Pay rent [{}]",
+                &todos[0].id.to_string()[..8]
+            )]
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_telegram_bot_answers_allowed_chats() {
+        use crate::telegram::{Bot, TelegramClient};
+        use actix_web::HttpRequest;
+        use spicy_todo_core::TodoService;
+        use std::sync::{Arc, Mutex};
+        use std::time::Duration;
+
+        let sent: Arc<Mutex<Vec<serde_json::Value>>> = Arc::default();
+        let log = sent.clone();
+        let api = HttpServer::new(move || {
+            let log = log.clone();
+            App::new().default_service(web::to(move |req: HttpRequest, body: String| {
+                let message = |update_id: i64, chat_id: i64, text: &str| {
+                    serde_json::json!({
+                        "update_id": update_id,
+                        "message": { "chat": { "id": chat_id }, "text": text }
+                    })
+                };
+                let response = if req.path() == "/bottoken/getUpdates" {
+                    if req.query_string().contains("offset=-1") {
+                        // Left over from before the bot started.
+                        serde_json::json!({ "ok": true, "result": [message(7, 42, "/add Old")] })
+                    } else {
+                        assert!(req.query_string().contains("offset=8"));
+                        serde_json::json!({ "ok": true, "result": [
+                            message(8, 42, "/add Pay rent today !high"),
+                            message(9, 42, "thanks"),
+                            message(10, 99, "/add Not yours"),
+                            message(11, 42, "/today")
+                        ]})
+                    }
+                } else {
+                    assert_eq!(req.path(), "/bottoken/sendMessage");
+                    log.lock().unwrap().push(serde_json::from_str(&body).unwrap());
+                    serde_json::json!({ "ok": true, "result": {} })
+                };
+                async move { HttpResponse::Ok().json(response) }
+            }))
+        })
+        .workers(1)
+        .bind(("127.0.0.1", 0))
+        .unwrap();
+        let url = format!("http://{}", api.addrs()[0]);
+        actix_rt::spawn(api.run());
+
+        let client = TelegramClient::new(&url, "token", vec![42]).with_poll_timeout(Duration::ZERO);
+        let service = web::Data::new(TodoService::new_empty());
+        let mut bot = Bot::start(web::Data::new(client), service.clone(), Default::default())
+            .await
+            .unwrap();
+        assert_eq!(bot.poll().await.unwrap(), 2);
+
+        let todos = service.get_all(None, None, None);
+        assert_eq!(todos.len(), 1);
+        assert_eq!(todos[0].text, "Pay rent");
+        let sent = sent.lock().unwrap().clone();
+        assert_eq!(sent.len(), 2);
+        assert_eq!(sent[0]["chat_id"], 42);
+        assert!(sent[1]["text"]
+            .as_str()
+            .unwrap()
+            .starts_with("- Pay rent, due "));
+    }
+
+    #[actix_web::test]
+    async fn test_chat_notifiers_post_to_slack_and_discord() {
+        use crate::notifiers::{
+            self, Alerts, NotifierCreate, NotifierKind, NotifierService, Templates,
+        };
+        use actix_web::HttpRequest;
+        use chrono::NaiveDate;
+        use spicy_todo_core::models::{Priority, TodoCreate};
+        use spicy_todo_core::weekly::WeeklySummary;
+        use std::sync::{Arc, Mutex};
+        use std::time::Duration;
+
+        let posted: Arc<Mutex<Vec<(String, serde_json::Value)>>> = Arc::default();
+        let log = posted.clone();
+        let chat = HttpServer::new(move || {
+            let log = log.clone();
+            App::new().default_service(web::to(
+                move |req: HttpRequest, body: web::Json<serde_json::Value>| {
+                    log.lock()
+                        .unwrap()
+                        .push((req.path().to_string(), body.into_inner()));
+                    async { HttpResponse::NoContent().finish() }
+                },
+            ))
+        })
+        .workers(1)
+        .bind(("127.0.0.1", 0))
+        .unwrap();
+        let url = format!("http://{}", chat.addrs()[0]);
+        actix_rt::spawn(chat.run());
+
+        let notifiers = web::Data::new(NotifierService::new());
+        notifiers
+            .create(NotifierCreate {
+                kind: NotifierKind::Slack,
+                workspace: "acme".to_string(),
+                url: format!("{}/slack", url),
+                alerts: Some(Alerts {
+                    daily_summary: false,
+                    ..Alerts::default()
+                }),
+                templates: None,
+            })
+            .unwrap();
+        notifiers
+            .create(NotifierCreate {
+                kind: NotifierKind::Discord,
+                workspace: "friends".to_string(),
+                url: format!("{}/discord", url),
+                alerts: Some(Alerts {
+                    high_priority: false,
+                    ..Alerts::default()
+                }),
+                templates: Some(Templates {
+                    summary: "{dueToday} due today, {overdue} overdue".to_string(),
+                    weekly: "{user}: {completed} done, {upcoming} coming up".to_string(),
+                    ..Templates::default()
+                }),
+            })
+            .unwrap();
+
+        let service = web::Data::new(TodoService::new_empty());
+        actix_rt::spawn(notifiers::run_listener(
+            notifiers.clone(),
+            service.events().subscribe(),
+        ));
+        let create = |text: &str, priority: Priority| {
+            service.create(TodoCreate {
+                text: text.to_string(),
+                priority: Some(priority),
+                due_date: Some("2024-06-10".to_string()),
+                ..Default::default()
+            })
+        };
+        create("Water plants", Priority::Low);
+        let urgent = create("Fix <prod> & deploy", Priority::High);
+        for _ in 0..250 {
+            if !posted.lock().unwrap().is_empty() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        assert_eq!(
+            posted.lock().unwrap().clone(),
+            vec![(
+                "/slack".to_string(),
+                serde_json::json!({
+                    "text": "New high priority todo: Fix &lt;prod&gt; &amp; deploy"
+                })
+            )]
+        );
+        posted.lock().unwrap().clear();
+
+        let todos = service.get_all(None, None, None);
+        let today = NaiveDate::from_ymd_opt(2024, 6, 10).unwrap();
+        assert_eq!(notifiers.post_summary(&todos, today).await, 1);
+        assert_eq!(notifiers.alert_overdue(&[&urgent]).await, 2);
+        let sent = posted.lock().unwrap().clone();
+        assert_eq!(sent.len(), 3);
+        assert_eq!(sent[0].0, "/discord");
+        assert_eq!(sent[0].1["content"], "2 due today, 0 overdue");
+        assert_eq!(
+            sent[0].1["allowed_mentions"]["parse"],
+            serde_json::json!([])
+        );
+        let overdue: Vec<&serde_json::Value> = sent[1..].iter().map(|(_, body)| body).collect();
+        assert_eq!(
+            overdue[0]["text"],
+            "Overdue: Fix &lt;prod&gt; &amp; deploy (was due 2024-06-10)"
+        );
+        assert_eq!(
+            overdue[1]["content"],
+            "Overdue: Fix <prod> & deploy (was due 2024-06-10)"
+        );
+
+        posted.lock().unwrap().clear();
+        let summary = WeeklySummary {
+            user: "alice & bob".to_string(),
+            from: NaiveDate::from_ymd_opt(2024, 6, 3).unwrap(),
+            to: NaiveDate::from_ymd_opt(2024, 6, 9).unwrap(),
+            completed: 4,
+            carried_over: Vec::new(),
+            upcoming: vec![urgent.clone()],
+        };
+        assert_eq!(notifiers.post_weekly(&[summary]).await, 2);
+        let sent = posted.lock().unwrap().clone();
+        assert!(sent[0].1["text"]
+            .as_str()
+            .unwrap()
+            .starts_with("Week of 2024-06-03 to 2024-06-09 for alice &amp; bob\n"));
+        assert_eq!(sent[1].1["content"], "alice & bob: 4 done, 1 coming up");
+    }
+
+    #[actix_web::test]
+    async fn test_web_push_subscribe_and_deliver() {
+        use crate::config::WebPushSettings;
+        use crate::webpush::testing::Browser;
+        use crate::webpush::{Vapid, WebPushMessage, WebPushService};
+        use actix_web::HttpRequest;
+        use spicy_todo_core::models::Priority;
+        use std::sync::{Arc, Mutex};
+
+        type Received = Arc<Mutex<Vec<(String, String, Vec<u8>)>>>;
+        let received: Received = Arc::default();
+        let log = received.clone();
+        let push_service = HttpServer::new(move || {
+            let log = log.clone();
+            App::new().default_service(web::to(move |req: HttpRequest, body: web::Bytes| {
+                let header = |name: &str| {
+                    req.headers()
+                        .get(name)
+                        .and_then(|value| value.to_str().ok())
+                        .unwrap_or_default()
+                        .to_string()
+                };
+                log.lock().unwrap().push((
+                    req.path().to_string(),
+                    format!("{}|{}", header("content-encoding"), header("urgency")),
+                    body.to_vec(),
+                ));
+                let gone = req.path().ends_with("/expired");
+                async move {
+                    if gone {
+                        HttpResponse::Gone().finish()
+                    } else {
+                        HttpResponse::Created().finish()
+                    }
+                }
+            }))
+        })
+        .workers(1)
+        .bind(("127.0.0.1", 0))
+        .unwrap();
+        let url = format!("http://{}", push_service.addrs()[0]);
+        actix_rt::spawn(push_service.run());
+
+        let vapid = Vapid::from_settings(&WebPushSettings {
+            private_key: "AQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQE".to_string(),
+            subject: Some("mailto:ops@example.com".to_string()),
+        })
+        .unwrap();
+        let web_push = web::Data::new(WebPushService::new(vapid));
+        let app = test::init_service(
+            App::new()
+                .app_data(web_push.clone())
+                .route("/api/push/vapid-public-key", web::get().to(get_web_push_key))
+                .route("/api/push/subscribe", web::post().to(web_push_subscribe))
+                .route("/api/push/subscribe", web::delete().to(web_push_unsubscribe)),
+        )
+        .await;
+
+        let req = test::TestRequest::get()
+            .uri("/api/push/vapid-public-key")
+            .to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["publicKey"], web_push.public_key());
+
+        let browser = Browser::new();
+        for path in ["/send/laptop", "/send/expired"] {
+            let req = test::TestRequest::post()
+                .uri("/api/push/subscribe")
+                .set_json(browser.subscription(&format!("{}{}", url, path)))
+                .to_request();
+            assert_eq!(test::call_service(&app, req).await.status(), 201);
+        }
+        let req = test::TestRequest::post()
+            .uri("/api/push/subscribe")
+            .set_json(serde_json::json!({"endpoint": url, "keys": {"p256dh": "x", "auth": "y"}}))
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 400);
+
+        let message = WebPushMessage {
+            title: "Reminder".to_string(),
+            body: "Reminder: Pay rent".to_string(),
+            tag: "todo-1".to_string(),
+            priority: Priority::High,
+        };
+        assert_eq!(web_push.broadcast(&message, chrono::Utc::now()).await, 1);
+        {
+            let received = received.lock().unwrap();
+            let (_, headers, body) = received
+                .iter()
+                .find(|(path, _, _)| path == "/send/laptop")
+                .unwrap();
+            assert_eq!(headers, "aes128gcm|high");
+            let payload: serde_json::Value =
+                serde_json::from_slice(&browser.decrypt(body)).unwrap();
+            assert_eq!(payload["body"], "Reminder: Pay rent");
+            assert_eq!(payload["tag"], "todo-1");
+        }
+
+        // The expired subscription was dropped after its 410.
+        let unsubscribe = |path: &str| {
+            test::TestRequest::delete()
+                .uri("/api/push/subscribe")
+                .set_json(serde_json::json!({ "endpoint": format!("{}{}", url, path) }))
+                .to_request()
+        };
+        let resp = test::call_service(&app, unsubscribe("/send/expired")).await;
+        assert_eq!(resp.status(), 404);
+        let resp = test::call_service(&app, unsubscribe("/send/laptop")).await;
+        assert_eq!(resp.status(), 204);
+    }
+
+    #[actix_web::test]
+    async fn test_mailbox_poll_turns_unread_emails_into_todos() {
+        use crate::config::ImapSettings;
+        use crate::email::{EmailIngest, Mailbox};
+        use spicy_todo_core::models::Priority;
+        use std::sync::{Arc, Mutex};
+        use std::time::Duration;
+        use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+        let listener = tokio::net::TcpListener::bind(("127.0.0.1", 0)).await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let commands = Arc::new(Mutex::new(Vec::<String>::new()));
+        let received = commands.clone();
+        actix_rt::spawn(async move {
+            let (socket, _) = listener.accept().await.unwrap();
+            let (read, mut write) = socket.into_split();
+            let mut lines = BufReader::new(read).lines();
+            write.write_all(b"* OK IMAP4rev1 ready\r\n").await.unwrap();
+            // The second email is the first one forwarded, and is dropped.
+            let header = |subject: &str| {
+                format!(
+                    "Subject: {}\r\nMessage-ID: <rent@example.com>\r\n\r\n",
+                    subject
+                )
+            };
+            let (rent, forwarded) = (header("Pay rent 2030-01-04"), header("Fwd: Pay rent"));
+            while let Some(line) = lines.next_line().await.unwrap() {
+                let (tag, command) = line.split_once(' ').unwrap();
+                received.lock().unwrap().push(command.to_string());
+                let reply = if command.starts_with("UID SEARCH") {
+                    "* SEARCH 7 9\r\n".to_string()
+                } else if command.starts_with("UID FETCH") {
+                    format!(
+                        "* 1 FETCH (UID 7 FLAGS (\\Flagged) BODY[HEADER] {{{}}}\r\n{})\r\n\
+                         * 2 FETCH (UID 9 FLAGS () BODY[HEADER] {{{}}}\r\n{})\r\n",
+                        rent.len(),
+                        rent,
+                        forwarded.len(),
+                        forwarded
+                    )
+                } else if command.starts_with("LOGOUT") {
+                    "* BYE\r\n".to_string()
+                } else {
+                    String::new()
+                };
+                let reply = format!("{}{} OK done\r\n", reply, tag);
+                write.write_all(reply.as_bytes()).await.unwrap();
+            }
+        });
+
+        let mailbox = Mailbox::from_settings(&ImapSettings {
+            host: "127.0.0.1".to_string(),
+            port,
+            tls: false,
+            username: Some("me@example.com".to_string()),
+            password: Some("pa\"ss".to_string()),
+            mailbox: "INBOX".to_string(),
+            interval: Duration::from_secs(60),
+        })
+        .unwrap();
+        let service = TodoService::new_empty();
+        let created = mailbox
+            .poll(&EmailIngest::new(), &service, &Default::default())
+            .await
+            .unwrap();
+        assert_eq!(created, 1);
+
+        let todos = service.get_all(None, None, None);
+        assert_eq!(todos.len(), 1);
+        assert_eq!(todos[0].text, "Pay rent");
+        assert_eq!(todos[0].priority, Priority::High);
+        assert_eq!(todos[0].due_date.as_deref(), Some("2030-01-04"));
+
+        let commands = commands.lock().unwrap();
+        assert_eq!(commands[0], r#"LOGIN "me@example.com" "pa\"ss""#);
+        assert_eq!(commands[1], r#"SELECT "INBOX""#);
+        assert!(commands[3].starts_with("UID FETCH 7,9 "));
+        assert_eq!(commands[4], "UID STORE 7 +FLAGS.SILENT (\\Seen)");
+        assert_eq!(commands[5], "UID STORE 9 +FLAGS.SILENT (\\Seen)");
+        assert_eq!(commands[6], "LOGOUT");
+    }
+}
@@ -0,0 +1,165 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, OpenOptions};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use uuid::Uuid;
+
+/// Who holds the lease, as written to the lease file.
+#[derive(Debug, Serialize, Deserialize)]
+struct LeaseRecord {
+    holder: String,
+    #[serde(rename = "expiresAt")]
+    expires_at: DateTime<Utc>,
+}
+
+/// Leadership among replicas sharing a lease file, so jobs that must run
+/// once per deployment run on one of them. The leader renews the lease each
+/// time it checks it; when it stops, another replica takes over once the
+/// lease expires.
+///
+/// Each check and write happens under an exclusive lock on a file beside
+/// the lease, so two replicas finding it expired at the same moment can't
+/// both take it. The lock goes with the process if a replica dies holding
+/// it.
+pub struct FileLease {
+    path: PathBuf,
+    holder: String,
+    ttl: Duration,
+}
+
+impl FileLease {
+    pub fn new(path: impl Into<PathBuf>, ttl: Duration) -> Self {
+        FileLease {
+            path: path.into(),
+            holder: Uuid::new_v4().to_string(),
+            ttl,
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn read(&self) -> io::Result<Option<LeaseRecord>> {
+        match fs::read(&self.path) {
+            // A torn or foreign file counts as no lease, and is overwritten.
+            Ok(bytes) => Ok(serde_json::from_slice(&bytes).ok()),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Blocks until this replica holds the lock beside the lease; dropping
+    /// the file lets it go.
+    fn lock(&self) -> io::Result<File> {
+        let mut path = self.path.clone().into_os_string();
+        path.push(".lock");
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(path)?;
+        file.lock()?;
+        Ok(file)
+    }
+
+    fn held_by_me(&self) -> io::Result<bool> {
+        Ok(self
+            .read()?
+            .is_some_and(|record| record.holder == self.holder))
+    }
+
+    /// Takes or renews the lease at `now`, unless another replica holds
+    /// one that has not expired. Returns whether this replica leads.
+    pub fn acquire(&self, now: DateTime<Utc>) -> io::Result<bool> {
+        let _lock = self.lock()?;
+        if let Some(record) = self.read()? {
+            if record.holder != self.holder && record.expires_at > now {
+                return Ok(false);
+            }
+        }
+        let ttl = chrono::Duration::from_std(self.ttl).unwrap_or(chrono::Duration::MAX);
+        let record = LeaseRecord {
+            holder: self.holder.clone(),
+            expires_at: now
+                .checked_add_signed(ttl)
+                .unwrap_or(DateTime::<Utc>::MAX_UTC),
+        };
+        let temp = self.path.with_extension(format!("{}.tmp", self.holder));
+        fs::write(&temp, serde_json::to_vec(&record)?)?;
+        fs::rename(&temp, &self.path)?;
+        Ok(true)
+    }
+
+    /// Gives the lease up, on shutdown, so another replica need not wait
+    /// for it to expire.
+    pub fn release(&self) -> io::Result<()> {
+        let _lock = self.lock()?;
+        if self.held_by_me()? {
+            fs::remove_file(&self.path)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_one_replica_leads_until_its_lease_expires() {
+        let dir = std::env::temp_dir().join(format!("spicy-lease-{}", Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("scheduler.lease");
+        let ttl = Duration::from_secs(60);
+        let (first, second) = (FileLease::new(&path, ttl), FileLease::new(&path, ttl));
+        let now = Utc::now();
+
+        assert!(first.acquire(now).unwrap());
+        assert!(!second.acquire(now).unwrap());
+        // Renewing keeps it.
+        assert!(first.acquire(now + chrono::Duration::seconds(30)).unwrap());
+        assert!(!second.acquire(now + chrono::Duration::seconds(80)).unwrap());
+        // Once it lapses, another replica takes over.
+        assert!(second.acquire(now + chrono::Duration::seconds(91)).unwrap());
+        assert!(!first.acquire(now + chrono::Duration::seconds(92)).unwrap());
+
+        first.release().unwrap();
+        assert!(path.exists(), "only the holder releases");
+        second.release().unwrap();
+        assert!(!path.exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_replicas_racing_for_a_free_lease_elect_one() {
+        let dir = std::env::temp_dir().join(format!("spicy-lease-{}", Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        for round in 0..20 {
+            let path = dir.join(format!("race-{}.lease", round));
+            let barrier = std::sync::Barrier::new(2);
+            let now = Utc::now();
+            let won = std::thread::scope(|scope| {
+                let racers: Vec<_> = (0..2)
+                    .map(|_| {
+                        let lease = FileLease::new(&path, Duration::from_secs(60));
+                        let barrier = &barrier;
+                        scope.spawn(move || {
+                            barrier.wait();
+                            lease.acquire(now).unwrap()
+                        })
+                    })
+                    .collect();
+                racers
+                    .into_iter()
+                    .map(|racer| racer.join().unwrap())
+                    .filter(|won| *won)
+                    .count()
+            });
+            assert_eq!(won, 1, "round {}", round);
+        }
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
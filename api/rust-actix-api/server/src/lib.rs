@@ -0,0 +1,60 @@
+pub mod actions;
+pub mod audit;
+pub mod auth;
+pub mod backups;
+pub mod bulk_edits;
+pub mod caldav;
+pub mod casing;
+pub mod config;
+pub mod conformance;
+pub mod context;
+pub mod contract;
+pub mod deadlines;
+pub mod demo;
+pub mod diagnostics;
+pub mod email;
+pub mod errors;
+pub mod feed;
+pub mod geofence;
+pub mod grafana;
+pub mod handlers;
+pub mod health;
+pub mod homeassistant;
+pub mod i18n;
+pub mod importer;
+pub mod mcp;
+pub mod metrics;
+pub mod moderation;
+pub mod my_day;
+pub mod ndjson;
+pub mod notifiers;
+pub mod oidc;
+pub mod plugins;
+pub mod policies;
+pub mod preferences;
+pub mod profiling;
+pub mod push;
+#[cfg(test)]
+pub mod handlers_test;
+#[cfg(test)]
+pub mod integration_test;
+pub mod leader;
+pub mod logging;
+pub mod matrix;
+pub mod reminders;
+pub mod rollover;
+pub mod routes;
+pub mod scheduler;
+pub mod scripts;
+pub mod security_headers;
+pub mod sms;
+pub mod snapshots;
+pub mod suggestions;
+pub mod telegram;
+#[cfg(any(test, feature = "test-util"))]
+pub mod test_util;
+pub mod transfer;
+pub mod versioning;
+pub mod views;
+pub mod webhooks;
+pub mod webpush;
@@ -0,0 +1,26 @@
+use log::{Level, LevelFilter, Log, Metadata, Record};
+
+/// Prints what the core crate reports through `log` to stderr, as the
+/// server prints its own warnings.
+struct StderrLogger;
+
+impl Log for StderrLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= Level::Info
+    }
+
+    fn log(&self, record: &Record) {
+        if self.enabled(record.metadata()) {
+            eprintln!("{}", record.args());
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+/// Installs the logger, once at startup.
+pub fn init() {
+    if log::set_logger(&StderrLogger).is_ok() {
+        log::set_max_level(LevelFilter::Info);
+    }
+}
@@ -0,0 +1,372 @@
+use actix_web::http::KeepAlive;
+use actix_web::{middleware, web, App, HttpServer};
+use auth::{Auth, AuthMode};
+use backups::Backups;
+use bulk_edits::BulkEditPreviews;
+use config::Config;
+use demo::Demo;
+use diagnostics::RuntimeRegistry;
+use email::{EmailIngest, Mailbox};
+use geofence::GeofenceLog;
+use leader::FileLease;
+use matrix::MatrixRoom;
+use mcp::McpSessions;
+use metrics::Metrics;
+use moderation::{Moderation, ModerationMode};
+use my_day::MyDayStore;
+use notifiers::NotifierService;
+use plugins::PluginRegistry;
+use policies::PolicyStore;
+use preferences::PreferenceStore;
+use push::PushService;
+use reminders::Channels;
+use scheduler::Scheduler;
+use scripts::ScriptService;
+use sms::SmsService;
+use spicy_todo_server::{
+    audit, auth, backups, bulk_edits, casing, config, context, contract, demo, diagnostics, email,
+    geofence, health, i18n, leader, logging, matrix, mcp, metrics, moderation, my_day, notifiers,
+    plugins, policies, preferences, push, reminders, rollover, routes, scheduler, scripts,
+    security_headers, sms, snapshots, suggestions, telegram, views, webhooks, webpush,
+};
+use suggestions::Suggester;
+use telegram::TelegramClient;
+use views::ViewStore;
+use webhooks::WebhookService;
+use webpush::{Vapid, WebPushService};
+
+#[actix_web::main]
+async fn main() -> std::io::Result<()> {
+    logging::init();
+    // `spicy-todo-rust-api --contract-check [base-url]` checks a running
+    // server against the shared contract spec instead of serving.
+    if std::env::args().nth(1).as_deref() == Some("--contract-check") {
+        let base_url = std::env::args().nth(2);
+        return contract::check_remote(base_url.as_deref().unwrap_or(contract::DEFAULT_BASE_URL))
+            .await;
+    }
+    // `spicy-todo-rust-api hash-password` prints the hash of the password on
+    // stdin, for a line of AUTH_USERS_FILE.
+    if std::env::args().nth(1).as_deref() == Some("hash-password") {
+        let mut password = String::new();
+        std::io::stdin().read_line(&mut password)?;
+        let hash = auth::hash_password(password.trim_end_matches(['\r', '\n']))
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))?;
+        println!("{}", hash);
+        return Ok(());
+    }
+    health::mark_started();
+
+    let config = web::Data::new(Config::from_env());
+
+    // Initialize the service. Sample data is only loaded when SEED_SAMPLE_DATA
+    // is set, and never on top of restored todos; otherwise seed on demand
+    // via /api/admin/seed.
+    let todo_service = web::Data::new(config.service()?);
+    let moderation = Moderation::from_settings(&config.moderation)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))?;
+    let moderation = web::Data::new(moderation);
+    let auth = Auth::from_settings(&config.auth)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))?;
+    let auth = web::Data::new(auth);
+    // `spicy-todo-rust-api mcp` answers MCP on stdin and stdout instead of
+    // serving HTTP, so nothing else may print to stdout.
+    if std::env::args().nth(1).as_deref() == Some("mcp") {
+        eprintln!("🌶️  Spicy Todo MCP server on stdio");
+        return mcp::serve_stdio(&todo_service, &moderation).await;
+    }
+    let restored = todo_service.get_all(None, None, None).len();
+    if let Some(path) = &config.event_store_path {
+        println!(
+            "📒 Rebuilt {} todos from {} events in {}",
+            restored,
+            todo_service.sequence(),
+            path.display()
+        );
+    }
+    if let Some(dir) = &config.journal_dir {
+        println!("📒 Restored {} todos from journal in {}", restored, dir.display());
+    }
+    if let Some(path) = &config.snapshot_path {
+        println!("📒 Restored {} todos from snapshot {}", restored, path.display());
+    }
+    let fixtures = config
+        .startup_fixtures()
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))?;
+    if !fixtures.is_empty() && restored == 0 {
+        let seeded = todo_service.seed(fixtures.clone());
+        println!("🌱 Seeded {} sample todos", seeded.len());
+    }
+    let webhook_service = web::Data::new(WebhookService::new());
+    let notifier_service = web::Data::new(NotifierService::new());
+    let script_service = web::Data::new(ScriptService::new());
+    let bulk_edits = web::Data::new(BulkEditPreviews::new(bulk_edits::PREVIEW_TTL));
+    let preferences = web::Data::new(PreferenceStore::new());
+    let views = web::Data::new(ViewStore::new());
+    let my_day = web::Data::new(MyDayStore::new());
+    let policies = web::Data::new(PolicyStore::new());
+    let push = web::Data::new(PushService::new());
+    let email_ingest = web::Data::new(EmailIngest::new());
+    let mcp_sessions = web::Data::new(McpSessions::new());
+    let metrics = web::Data::new(Metrics::new());
+    let runtimes = web::Data::new(RuntimeRegistry::new());
+    runtimes.register_current();
+    let demo = config
+        .demo
+        .clone()
+        .map(|settings| web::Data::new(Demo::new(settings)));
+
+    // The demo refuses to set up webhooks and notifiers, so it runs
+    // nothing that would send to them.
+    if demo.is_none() {
+        actix_web::rt::spawn(webhooks::run_dispatcher(
+            webhook_service.clone(),
+            metrics.clone(),
+            todo_service.events().subscribe(),
+        ));
+        actix_web::rt::spawn(notifiers::run_listener(
+            notifier_service.clone(),
+            todo_service.events().subscribe(),
+        ));
+    }
+    actix_web::rt::spawn(scripts::run_listener(
+        script_service.clone(),
+        todo_service.clone(),
+        moderation.clone(),
+        todo_service.events().subscribe(),
+    ));
+    actix_web::rt::spawn(metrics::run_event_recorder(
+        metrics.clone(),
+        todo_service.events().subscribe(),
+    ));
+
+    // Periodic jobs; stopped, and their shutdown hooks run, once the server exits.
+    let mut scheduler = Scheduler::new(Some(metrics.clone()));
+    if let Some(path) = &config.scheduler_lease_path {
+        scheduler.lead_with(FileLease::new(path, config.scheduler_lease_ttl));
+        println!("👑 Sharing scheduled jobs through lease {}", path.display());
+    }
+    rollover::schedule(&mut scheduler, todo_service.clone(), config.rollover_mode);
+    if let Some(settings) = config.overdue_rollover {
+        rollover::schedule_overdue(&mut scheduler, todo_service.clone(), settings);
+        println!(
+            "🔁 Carrying overdue todos over at midnight {} ({})",
+            settings.timezone,
+            settings.mode.as_str()
+        );
+    }
+    if let Some(settings) = &config.demo {
+        demo::schedule(&mut scheduler, todo_service.clone(), settings, fixtures);
+        println!(
+            "🎪 Demo mode: resetting every {} minutes, at most {} todos",
+            settings.reset_interval.as_secs() / 60,
+            settings.max_todos
+        );
+    } else {
+        notifiers::schedule(
+            &mut scheduler,
+            notifier_service.clone(),
+            todo_service.clone(),
+            preferences.clone(),
+        );
+    }
+    policies::schedule(&mut scheduler, policies.clone(), todo_service.clone());
+    if let Some(path) = &config.snapshot_path {
+        snapshots::schedule(
+            &mut scheduler,
+            todo_service.clone(),
+            path.clone(),
+            config.snapshot_interval,
+            config.text_cipher()?,
+        );
+    }
+    if let Some(settings) = &config.audit_retention {
+        audit::schedule(&mut scheduler, todo_service.clone(), settings.clone());
+        println!(
+            "🧾 Keeping {} days of audit trail",
+            settings.max_age.as_secs() / 86400
+        );
+    }
+    let backups = match &config.backup {
+        Some(settings) => {
+            let backups = Backups::from_settings(settings)
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))?;
+            let backups = web::Data::new(backups);
+            backups::schedule(
+                &mut scheduler,
+                backups.clone(),
+                todo_service.clone(),
+                settings.interval,
+            );
+            println!("💾 Backing up to s3://{}/{}", settings.bucket, settings.prefix);
+            Some(backups)
+        }
+        None => None,
+    };
+    let sms = match &config.sms {
+        Some(settings) => {
+            let sms = SmsService::from_settings(settings)
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))?;
+            let sms = web::Data::new(sms);
+            println!("📱 Texting urgent reminders via {}", settings.gateway_url);
+            Some(sms)
+        }
+        None => None,
+    };
+    let matrix = match &config.matrix {
+        Some(settings) => {
+            let room = MatrixRoom::from_settings(settings)
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))?;
+            let room = web::Data::new(room);
+            if settings.daily_digest {
+                matrix::schedule_digest(&mut scheduler, room.clone(), todo_service.clone());
+            }
+            actix_web::rt::spawn(matrix::run_bot(
+                room.clone(),
+                todo_service.clone(),
+                moderation.clone(),
+            ));
+            println!("💬 Matrix bot listening via {}", settings.homeserver);
+            Some(room)
+        }
+        None => None,
+    };
+    if let Some(settings) = &config.telegram {
+        let client = TelegramClient::from_settings(settings)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))?;
+        actix_web::rt::spawn(telegram::run_bot(
+            web::Data::new(client),
+            todo_service.clone(),
+            moderation.clone(),
+        ));
+        println!("✈️  Telegram bot listening via {}", settings.api_url);
+    }
+    if let Some(settings) = &config.imap {
+        let mailbox = Mailbox::from_settings(settings)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))?;
+        email::schedule(
+            &mut scheduler,
+            web::Data::new(mailbox),
+            email_ingest.clone(),
+            todo_service.clone(),
+            moderation.clone(),
+            settings.interval,
+        );
+        println!(
+            "📧 Reading todos from {} on {}",
+            settings.mailbox, settings.host
+        );
+    }
+    let web_push = match &config.web_push {
+        Some(settings) => {
+            let vapid = Vapid::from_settings(settings)
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))?;
+            let web_push = web::Data::new(WebPushService::new(vapid));
+            println!("🌐 Web Push enabled, VAPID public key {}", web_push.public_key());
+            Some(web_push)
+        }
+        None => None,
+    };
+    let channels = web::Data::new(Channels {
+        preferences: preferences.clone(),
+        push: push.clone(),
+        sms: sms.clone(),
+        matrix,
+        web_push: web_push.clone(),
+    });
+    let geofences = web::Data::new(GeofenceLog::new(config.geofence_cooldown));
+    // Nor does it send reminders, whatever channels are configured.
+    if demo.is_none() {
+        reminders::schedule(&mut scheduler, todo_service.clone(), (**channels).clone());
+    }
+    let scheduler = scheduler.start();
+
+    let suggester = web::Data::new(Suggester::from_config(&config));
+    if config.moderation.mode != ModerationMode::Off {
+        println!(
+            "🧼 Moderating todo text ({} mode)",
+            config.moderation.mode.as_str()
+        );
+    }
+    if auth.mode() != AuthMode::Off {
+        println!("🔐 API requires auth ({} mode)", auth.mode().as_str());
+    }
+    let plugins = web::Data::new(PluginRegistry::builtin());
+    println!("🧩 Plugins loaded: {}", plugins.loaded(&config).join(", "));
+
+    let server = config.server.clone();
+    println!("⚙️  HTTP server: {}", server.summary());
+    println!("🌶️  Spicy Todo API (Rust/Actix) running on http://localhost:8000");
+
+    HttpServer::new(move || {
+        // Runs once on each worker thread, inside that worker's runtime
+        runtimes.register_current();
+        let app = App::new()
+            .wrap(middleware::from_fn(demo::guard_demo))
+            .wrap(middleware::from_fn(auth::require_auth))
+            .wrap(middleware::from_fn(casing::negotiate_case))
+            .wrap(middleware::from_fn(i18n::localize_responses))
+            .wrap(middleware::Compress::default())
+            .wrap(middleware::Condition::new(
+                auth.mode() == AuthMode::Session,
+                auth.session_middleware(),
+            ))
+            .wrap(routes::configure_cors())
+            .wrap(middleware::from_fn(metrics::track_requests))
+            .wrap(middleware::from_fn(context::with_request_context))
+            .wrap(middleware::from_fn(security_headers::add_security_headers))
+            .app_data(config.clone())
+            .app_data(todo_service.clone())
+            .app_data(webhook_service.clone())
+            .app_data(notifier_service.clone())
+            .app_data(script_service.clone())
+            .app_data(bulk_edits.clone())
+            .app_data(preferences.clone())
+            .app_data(views.clone())
+            .app_data(my_day.clone())
+            .app_data(policies.clone())
+            .app_data(push.clone())
+            .app_data(email_ingest.clone())
+            .app_data(mcp_sessions.clone())
+            .app_data(channels.clone())
+            .app_data(geofences.clone())
+            .app_data(metrics.clone())
+            .app_data(runtimes.clone())
+            .app_data(plugins.clone())
+            .app_data(suggester.clone())
+            .app_data(moderation.clone())
+            .app_data(auth.clone())
+            .configure(routes::configure_routes);
+        let app = match &sms {
+            Some(sms) => app.app_data(sms.clone()),
+            None => app,
+        };
+        let app = match &web_push {
+            Some(web_push) => app.app_data(web_push.clone()),
+            None => app,
+        };
+        let app = match &demo {
+            Some(demo) => app.app_data(demo.clone()),
+            None => app,
+        };
+        match &backups {
+            Some(backups) => app.app_data(backups.clone()),
+            None => app,
+        }
+    })
+    .workers(server.workers)
+    .keep_alive(if server.keep_alive.is_zero() {
+        KeepAlive::Disabled
+    } else {
+        KeepAlive::Timeout(server.keep_alive)
+    })
+    .client_request_timeout(server.client_request_timeout)
+    .client_disconnect_timeout(server.client_disconnect_timeout)
+    .backlog(server.backlog)
+    .bind("0.0.0.0:8000")?
+    .run()
+    .await?;
+
+    scheduler.shutdown().await;
+    Ok(())
+}
@@ -0,0 +1,522 @@
+use crate::config::{Config, MatrixSettings};
+use crate::moderation::Moderation;
+use crate::plugins::{Kind, Plugin};
+use crate::scheduler::{Outcome, Schedule, Scheduler};
+use actix_web::web;
+use chrono::{NaiveDate, Utc};
+use serde::Deserialize;
+use spicy_todo_core::digest::{self, Agenda, PlainTextOptions};
+use spicy_todo_core::models::{Todo, TodoUpdate};
+use spicy_todo_core::{quick_add, TodoService};
+use std::cell::Cell;
+use std::rc::Rc;
+use std::time::Duration;
+use uuid::Uuid;
+
+const SEND_TIMEOUT: Duration = Duration::from_secs(10);
+/// How long a `/sync` request waits for new messages before returning.
+const POLL_TIMEOUT: Duration = Duration::from_secs(30);
+const RETRY_DELAY: Duration = Duration::from_secs(10);
+const MAX_SYNC_BYTES: usize = 8 * 1024 * 1024;
+/// Most todos `!list` shows.
+const LIST_LIMIT: usize = 20;
+/// Shortest id prefix `!done` accepts.
+const MIN_ID_PREFIX: usize = 4;
+const SHORT_ID_LEN: usize = 8;
+
+const HELP: &str = "Commands: !add <todo, e.g. Pay rent tomorrow 9am !high>, !list, \
+                    !done <id from !list>, !help";
+
+/// One room on a Matrix homeserver, reached through the client-server API
+/// with a bot account's access token.
+pub struct MatrixRoom {
+    homeserver: String,
+    access_token: String,
+    room_id: String,
+    poll_timeout: Duration,
+}
+
+/// A text message someone posted in the room.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RoomMessage {
+    pub sender: String,
+    pub body: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct WhoAmI {
+    user_id: String,
+}
+
+impl MatrixRoom {
+    pub fn new(homeserver: &str, access_token: &str, room_id: &str) -> Self {
+        MatrixRoom {
+            homeserver: homeserver.trim_end_matches('/').to_string(),
+            access_token: access_token.to_string(),
+            room_id: room_id.to_string(),
+            poll_timeout: POLL_TIMEOUT,
+        }
+    }
+
+    pub fn from_settings(settings: &MatrixSettings) -> Result<Self, String> {
+        let access_token = settings
+            .access_token
+            .as_deref()
+            .ok_or("MATRIX_ACCESS_TOKEN is required when Matrix is enabled")?;
+        let room_id = settings
+            .room_id
+            .as_deref()
+            .ok_or("MATRIX_ROOM_ID is required when Matrix is enabled")?;
+        if !room_id.starts_with('!') || !room_id.contains(':') {
+            return Err(format!(
+                "MATRIX_ROOM_ID '{}' must be a room id like !abc123:example.org",
+                room_id
+            ));
+        }
+        Ok(MatrixRoom::new(&settings.homeserver, access_token, room_id))
+    }
+
+    #[cfg(test)]
+    pub fn with_poll_timeout(mut self, poll_timeout: Duration) -> Self {
+        self.poll_timeout = poll_timeout;
+        self
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}/_matrix/client/v3{}", self.homeserver, path)
+    }
+
+    /// Posts `text` to the room as a plain text message.
+    pub async fn send(&self, text: &str) -> Result<(), String> {
+        let url = self.url(&format!(
+            "/rooms/{}/send/m.room.message/{}",
+            self.room_id,
+            Uuid::new_v4()
+        ));
+        let client = awc::Client::builder().timeout(SEND_TIMEOUT).finish();
+        let response = client
+            .put(&url)
+            .bearer_auth(&self.access_token)
+            .send_json(&serde_json::json!({ "msgtype": "m.text", "body": text }))
+            .await
+            .map_err(|e| format!("Matrix homeserver unreachable: {}", e))?;
+        if !response.status().is_success() {
+            return Err(format!("Matrix send responded with {}", response.status()));
+        }
+        Ok(())
+    }
+
+    /// The bot account's user id, so its own messages can be ignored.
+    async fn whoami(&self) -> Result<String, String> {
+        let client = awc::Client::builder().timeout(SEND_TIMEOUT).finish();
+        let mut response = client
+            .get(self.url("/account/whoami"))
+            .bearer_auth(&self.access_token)
+            .send()
+            .await
+            .map_err(|e| format!("Matrix homeserver unreachable: {}", e))?;
+        if !response.status().is_success() {
+            return Err(format!(
+                "Matrix whoami responded with {}",
+                response.status()
+            ));
+        }
+        let whoami: WhoAmI = response
+            .json()
+            .await
+            .map_err(|e| format!("Unexpected whoami response: {}", e))?;
+        Ok(whoami.user_id)
+    }
+
+    /// Messages posted in the room after `since`, and the token to pass next
+    /// time. Without `since`, returns at once with just the token.
+    async fn sync(&self, since: Option<&str>) -> Result<(String, Vec<RoomMessage>), String> {
+        let filter = serde_json::json!({
+            "room": {
+                "rooms": [self.room_id],
+                "timeline": { "types": ["m.room.message"] },
+                "state": { "types": [] },
+                "ephemeral": { "types": [] }
+            },
+            "presence": { "types": [] },
+            "account_data": { "types": [] }
+        })
+        .to_string();
+        let timeout = match since {
+            Some(_) => self.poll_timeout,
+            None => Duration::ZERO,
+        };
+        let mut query = vec![
+            ("filter", filter),
+            ("timeout", timeout.as_millis().to_string()),
+        ];
+        if let Some(since) = since {
+            query.push(("since", since.to_string()));
+        }
+        let client = awc::Client::builder()
+            .timeout(timeout + SEND_TIMEOUT)
+            .finish();
+        let mut response = client
+            .get(self.url("/sync"))
+            .query(&query)
+            .map_err(|e| e.to_string())?
+            .bearer_auth(&self.access_token)
+            .send()
+            .await
+            .map_err(|e| format!("Matrix homeserver unreachable: {}", e))?;
+        if !response.status().is_success() {
+            return Err(format!("Matrix sync responded with {}", response.status()));
+        }
+        let body: serde_json::Value = response
+            .json()
+            .limit(MAX_SYNC_BYTES)
+            .await
+            .map_err(|e| format!("Unexpected sync response: {}", e))?;
+        let next_batch = body["next_batch"]
+            .as_str()
+            .ok_or("Sync response has no next_batch")?
+            .to_string();
+        let messages = body["rooms"]["join"][&self.room_id]["timeline"]["events"]
+            .as_array()
+            .map(|events| {
+                events
+                    .iter()
+                    .filter(|event| event["type"] == "m.room.message")
+                    .filter(|event| event["content"]["msgtype"] == "m.text")
+                    .filter_map(|event| {
+                        Some(RoomMessage {
+                            sender: event["sender"].as_str()?.to_string(),
+                            body: event["content"]["body"].as_str()?.to_string(),
+                        })
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        Ok((next_batch, messages))
+    }
+}
+
+/// Answers one bot command, or `None` for messages that aren't commands.
+pub async fn command(
+    service: &TodoService,
+    moderation: &Moderation,
+    message: &str,
+    today: NaiveDate,
+) -> Option<String> {
+    let message = message.trim();
+    let (name, argument) = message.split_once(' ').unwrap_or((message, ""));
+    let argument = argument.trim();
+    let reply = match name.strip_prefix('!')? {
+        "add" => add(service, moderation, argument, today, "!add").await,
+        "list" => {
+            let mut todos = service.get_all(Some("active".to_string()), None, None);
+            if todos.is_empty() {
+                return Some("Nothing to do.".to_string());
+            }
+            todos.sort_by(|a, b| {
+                let due = |todo: &Todo| todo.due_date.clone().unwrap_or_else(|| "~".to_string());
+                due(a).cmp(&due(b)).then(a.created_at.cmp(&b.created_at))
+            });
+            let mut lines: Vec<String> = todos
+                .iter()
+                .take(LIST_LIMIT)
+                .map(|todo| format!("- {}", describe(todo)))
+                .collect();
+            if todos.len() > LIST_LIMIT {
+                lines.push(format!("...and {} more", todos.len() - LIST_LIMIT));
+            }
+            lines.join("\n")
+        }
+        "done" => match find_active(service, argument, "!done", "!list") {
+            Ok(todo) => {
+                let update = TodoUpdate {
+                    completed: Some(true),
+                    ..Default::default()
+                };
+                match service.update(todo.id, update) {
+                    Some(todo) => format!("Done: {}", todo.text),
+                    None => "That todo was just deleted.".to_string(),
+                }
+            }
+            Err(e) => e,
+        },
+        "help" => HELP.to_string(),
+        _ => format!("Unknown command. {}", HELP),
+    };
+    Some(reply)
+}
+
+/// Adds `line`, read like a quick-add line, once moderation lets its text
+/// through. The usage reply names the bot's own `add` command.
+pub(crate) async fn add(
+    service: &TodoService,
+    moderation: &Moderation,
+    line: &str,
+    today: NaiveDate,
+    usage: &str,
+) -> String {
+    let mut parsed = quick_add::parse(line, today);
+    if parsed.text.trim().is_empty() {
+        return format!("Usage: {} <todo>", usage);
+    }
+    if let Err(e) = moderation.apply(&mut parsed.text).await {
+        return format!("Not added: {}", e);
+    }
+    let todo = service.create(parsed.into_create());
+    format!("Added: {}", describe(&todo))
+}
+
+/// A todo as the chat bots show it, with the short id `done` commands take.
+pub(crate) fn describe(todo: &Todo) -> String {
+    let mut text = todo.text.clone();
+    if let Some(due) = todo.due_date.as_deref() {
+        text.push_str(&format!(", due {}", due));
+    }
+    format!("{} [{}]", text, short_id(&todo.id.to_string()))
+}
+
+fn short_id(id: &str) -> &str {
+    &id[..id.len().min(SHORT_ID_LEN)]
+}
+
+/// The one active todo whose id starts with `prefix`. The errors are
+/// replies that name the bot's own `done` and `list` commands.
+pub(crate) fn find_active(
+    service: &TodoService,
+    prefix: &str,
+    done: &str,
+    list: &str,
+) -> Result<Todo, String> {
+    if prefix.len() < MIN_ID_PREFIX {
+        return Err(format!(
+            "Usage: {} <id>, with at least {} characters of the id shown by {}",
+            done, MIN_ID_PREFIX, list
+        ));
+    }
+    let mut matches: Vec<Todo> = service
+        .get_all(Some("active".to_string()), None, None)
+        .into_iter()
+        .filter(|todo| todo.id.to_string().starts_with(prefix))
+        .collect();
+    match matches.len() {
+        0 => Err(format!("No active todo with id {}", prefix)),
+        1 => Ok(matches.remove(0)),
+        _ => Err(format!(
+            "{} matches several todos; use more of the id",
+            prefix
+        )),
+    }
+}
+
+/// The command side of the bot: reads the room and answers commands.
+pub struct Bot {
+    room: web::Data<MatrixRoom>,
+    service: web::Data<TodoService>,
+    moderation: web::Data<Moderation>,
+    user_id: String,
+    since: String,
+}
+
+impl Bot {
+    /// Logs in and skips everything already in the room, so old commands
+    /// are not run again after a restart.
+    pub async fn start(
+        room: web::Data<MatrixRoom>,
+        service: web::Data<TodoService>,
+        moderation: web::Data<Moderation>,
+    ) -> Result<Self, String> {
+        let user_id = room.whoami().await?;
+        let (since, _) = room.sync(None).await?;
+        Ok(Bot {
+            room,
+            service,
+            moderation,
+            user_id,
+            since,
+        })
+    }
+
+    /// Waits for new messages and answers the commands among them,
+    /// returning how many were answered.
+    pub async fn poll(&mut self) -> Result<usize, String> {
+        let (next, messages) = self.room.sync(Some(&self.since)).await?;
+        self.since = next;
+        let mut answered = 0;
+        for message in messages {
+            if message.sender == self.user_id {
+                continue;
+            }
+            let today = Utc::now().date_naive();
+            let reply = command(&self.service, &self.moderation, &message.body, today).await;
+            if let Some(reply) = reply {
+                self.room.send(&reply).await?;
+                answered += 1;
+            }
+        }
+        Ok(answered)
+    }
+}
+
+/// Runs the bot until the server exits, restarting it after errors.
+pub async fn run_bot(
+    room: web::Data<MatrixRoom>,
+    service: web::Data<TodoService>,
+    moderation: web::Data<Moderation>,
+) {
+    loop {
+        let started = Bot::start(room.clone(), service.clone(), moderation.clone()).await;
+        let mut bot = match started {
+            Ok(bot) => bot,
+            Err(e) => {
+                eprintln!("Matrix bot failed to start: {}", e);
+                tokio::time::sleep(RETRY_DELAY).await;
+                continue;
+            }
+        };
+        while let Ok(answered) = bot.poll().await.inspect_err(|e| {
+            eprintln!("Matrix bot error: {}", e);
+        }) {
+            if answered > 0 {
+                println!("💬 Answered {} Matrix commands", answered);
+            }
+        }
+        tokio::time::sleep(RETRY_DELAY).await;
+    }
+}
+
+/// Posts the plain-text agenda to the room after every UTC midnight. The
+/// run at startup is skipped, so restarts don't repeat the day's digest.
+pub fn schedule_digest(
+    scheduler: &mut Scheduler,
+    room: web::Data<MatrixRoom>,
+    service: web::Data<TodoService>,
+) {
+    let posted_on = Rc::new(Cell::new(Utc::now().date_naive()));
+    scheduler.register_exclusive("matrix-digest", Schedule::Daily, move || {
+        let (room, service) = (room.clone(), service.clone());
+        let posted_on = posted_on.clone();
+        async move {
+            let today = Utc::now().date_naive();
+            if posted_on.get() == today {
+                return Ok(Outcome::Skipped);
+            }
+            let todos = service.get_all(None, None, None);
+            let agenda = Agenda::build(&todos, today, digest::DEFAULT_DAYS);
+            room.send(&agenda.render_plain_text(&PlainTextOptions::default()))
+                .await?;
+            posted_on.set(today);
+            Ok(Outcome::Done)
+        }
+    });
+}
+
+pub struct MatrixPlugin;
+
+impl Plugin for MatrixPlugin {
+    fn name(&self) -> &'static str {
+        "matrix"
+    }
+
+    fn kind(&self) -> Kind {
+        Kind::Chat
+    }
+
+    fn description(&self) -> &'static str {
+        "Matrix room bot with reminders and a daily digest"
+    }
+
+    fn enabled(&self, config: &Config) -> bool {
+        config.matrix.is_some()
+    }
+
+    fn details(&self, config: &Config) -> Option<serde_json::Value> {
+        config
+            .matrix
+            .as_ref()
+            .map(|settings| serde_json::json!({ "homeserver": settings.homeserver }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::moderation::{ModerationMode, WordList};
+    use spicy_todo_core::models::Priority;
+
+    fn today() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 6, 10).unwrap()
+    }
+
+    /// Answers `message` with nothing moderated.
+    async fn ask(service: &TodoService, message: &str) -> Option<String> {
+        command(service, &Moderation::default(), message, today()).await
+    }
+
+    #[actix_web::test]
+    async fn test_add_list_and_done() {
+        let service = TodoService::new_empty();
+        assert_eq!(ask(&service, "hello there").await, None);
+        assert_eq!(ask(&service, "!list").await.unwrap(), "Nothing to do.");
+
+        let reply = ask(&service, "!add Pay rent tomorrow !high").await.unwrap();
+        let todo = service.get_all(None, None, None).remove(0);
+        assert_eq!(todo.priority, Priority::High);
+        assert_eq!(todo.due_date.as_deref(), Some("2024-06-11"));
+        assert_eq!(
+            reply,
+            format!(
+                "Added: Pay rent, due 2024-06-11 [{}]",
+                &todo.id.to_string()[..8]
+            )
+        );
+        ask(&service, "!add Water plants").await;
+
+        let list = ask(&service, "!list").await.unwrap();
+        let lines: Vec<&str> = list.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("- Pay rent, due 2024-06-11"));
+
+        let done = format!("!done {}", &todo.id.to_string()[..6]);
+        assert_eq!(ask(&service, &done).await.unwrap(), "Done: Pay rent");
+        assert!(service.get_by_id(todo.id).unwrap().completed);
+        assert!(ask(&service, &done)
+            .await
+            .unwrap()
+            .starts_with("No active todo"));
+        assert!(ask(&service, "!done ab")
+            .await
+            .unwrap()
+            .starts_with("Usage"));
+        assert!(ask(&service, "!frobnicate")
+            .await
+            .unwrap()
+            .starts_with("Unknown command"));
+    }
+
+    #[actix_web::test]
+    async fn test_add_is_moderated() {
+        let service = TodoService::new_empty();
+        let words = vec![Box::new(WordList::new(&["spinach".to_string()])) as Box<_>];
+        let moderation = Moderation::new(ModerationMode::Reject, words);
+
+        let reply = command(&service, &moderation, "!add Buy spinach", today()).await;
+        assert_eq!(
+            reply.unwrap(),
+            "Not added: Todo text contains disallowed content"
+        );
+        assert!(service.get_all(None, None, None).is_empty());
+    }
+
+    #[test]
+    fn test_settings_require_room_id() {
+        let settings = |room: Option<&str>| MatrixSettings {
+            homeserver: "https://matrix.example".to_string(),
+            access_token: Some("token".to_string()),
+            room_id: room.map(str::to_string),
+            daily_digest: true,
+        };
+        assert!(MatrixRoom::from_settings(&settings(None)).is_err());
+        assert!(MatrixRoom::from_settings(&settings(Some("#todos:example"))).is_err());
+        assert!(MatrixRoom::from_settings(&settings(Some("!abc:example"))).is_ok());
+    }
+}
@@ -0,0 +1,496 @@
+use crate::actions::{object, todo_fields};
+use crate::handlers::{validate_create, validate_update};
+use crate::moderation::Moderation;
+use actix_web::web::Bytes;
+use chrono::Utc;
+use futures_util::{stream, Stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use spicy_todo_core::models::{TodoCreate, TodoUpdate};
+use spicy_todo_core::TodoService;
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::sync::Mutex;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::sync::mpsc;
+use uuid::Uuid;
+
+/// Protocol revisions this server speaks, newest last. The HTTP+SSE
+/// transport is the one from 2024-11-05.
+pub const PROTOCOL_VERSIONS: [&str; 3] = ["2024-11-05", "2025-03-26", "2025-06-18"];
+/// Where SSE clients post their messages.
+pub const MESSAGES_PATH: &str = "/api/mcp/messages";
+/// Most SSE sessions open at once.
+const MAX_SESSIONS: usize = 64;
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+/// A tool as listed by `tools/list`.
+#[derive(Debug, Clone, Serialize)]
+pub struct Tool {
+    pub name: &'static str,
+    pub description: &'static str,
+    #[serde(rename = "inputSchema")]
+    pub input_schema: Value,
+}
+
+#[derive(Debug, Deserialize)]
+struct CallParams {
+    name: String,
+    #[serde(default)]
+    arguments: Value,
+}
+
+#[derive(Debug, Deserialize)]
+struct IdArgs {
+    id: Uuid,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ListArgs {
+    filter: Option<String>,
+    search: Option<String>,
+    priority: Option<String>,
+    query: Option<String>,
+}
+
+fn list_fields() -> Value {
+    json!({
+        "filter": { "enum": ["all", "active", "completed"] },
+        "search": { "type": "string", "description": "Text the todos must contain" },
+        "priority": { "enum": ["low", "medium", "high"] }
+    })
+}
+
+fn id_field() -> Value {
+    json!({ "id": { "type": "string" } })
+}
+
+/// The tools, with the same field schemas as `GET /api/actions`.
+pub fn tools() -> Vec<Tool> {
+    let mut update_fields = todo_fields();
+    update_fields["id"] = json!({ "type": "string" });
+    vec![
+        Tool {
+            name: "list_todos",
+            description: "List todos, optionally filtered by status, text or priority.",
+            input_schema: object(list_fields(), &[]),
+        },
+        Tool {
+            name: "search_todos",
+            description: "Find todos whose text contains the query.",
+            input_schema: object(
+                json!({
+                    "query": { "type": "string", "minLength": 1 },
+                    "filter": { "enum": ["all", "active", "completed"] }
+                }),
+                &["query"],
+            ),
+        },
+        Tool {
+            name: "get_todo",
+            description: "Get one todo by id.",
+            input_schema: object(id_field(), &["id"]),
+        },
+        Tool {
+            name: "create_todo",
+            description: "Create a todo. Due dates may be phrases such as 'next friday'.",
+            input_schema: object(todo_fields(), &["text"]),
+        },
+        Tool {
+            name: "update_todo",
+            description: "Change some fields of a todo; set completed to finish it.",
+            input_schema: object(update_fields, &["id"]),
+        },
+        Tool {
+            name: "delete_todo",
+            description: "Delete a todo.",
+            input_schema: object(id_field(), &["id"]),
+        },
+        Tool {
+            name: "get_stats",
+            description: "Counts of total, active, completed and overdue todos.",
+            input_schema: object(json!({}), &[]),
+        },
+    ]
+}
+
+fn arguments<T: for<'de> Deserialize<'de>>(arguments: &Value) -> Result<T, String> {
+    serde_json::from_value(arguments.clone()).map_err(|e| format!("Invalid arguments: {}", e))
+}
+
+fn not_found(id: Uuid) -> String {
+    format!("Todo {} not found", id)
+}
+
+async fn create(
+    service: &TodoService,
+    moderation: &Moderation,
+    args: &Value,
+) -> Result<Value, String> {
+    let mut todo_create = arguments::<TodoCreate>(args)?;
+    validate_create(&mut todo_create, Utc::now().date_naive()).map_err(|e| e.to_string())?;
+    moderation
+        .apply(&mut todo_create.text)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(json!(service.create(todo_create)))
+}
+
+async fn update(
+    service: &TodoService,
+    moderation: &Moderation,
+    args: &Value,
+) -> Result<Value, String> {
+    let IdArgs { id } = arguments(args)?;
+    let mut todo_update = arguments::<TodoUpdate>(args)?;
+    validate_update(&mut todo_update, Utc::now().date_naive()).map_err(|e| e.to_string())?;
+    if let Some(text) = &mut todo_update.text {
+        moderation.apply(text).await.map_err(|e| e.to_string())?;
+    }
+    service
+        .update(id, todo_update)
+        .map(|todo| json!(todo))
+        .ok_or_else(|| not_found(id))
+}
+
+/// Runs a tool. `None` means there is no tool by that name; `Err` is a
+/// failure the model should see, such as a validation error. Text is
+/// moderated as on the REST API.
+pub async fn call_tool(
+    service: &TodoService,
+    moderation: &Moderation,
+    name: &str,
+    args: &Value,
+) -> Option<Result<Value, String>> {
+    let args = if args.is_null() { &json!({}) } else { args };
+    let result = match name {
+        "list_todos" | "search_todos" => arguments::<ListArgs>(args).map(|list| {
+            let search = list.query.or(list.search);
+            json!(service.get_all(list.filter, search, list.priority))
+        }),
+        "get_todo" => arguments::<IdArgs>(args).and_then(|IdArgs { id }| {
+            service
+                .get_by_id(id)
+                .map(|todo| json!(todo))
+                .ok_or_else(|| not_found(id))
+        }),
+        "create_todo" => create(service, moderation, args).await,
+        "update_todo" => update(service, moderation, args).await,
+        "delete_todo" => arguments::<IdArgs>(args).and_then(|IdArgs { id }| {
+            if service.delete(id) {
+                Ok(json!({ "deleted": id }))
+            } else {
+                Err(not_found(id))
+            }
+        }),
+        "get_stats" => Ok(json!(service.get_stats())),
+        _ => return None,
+    };
+    Some(result)
+}
+
+fn initialize(params: &Value) -> Value {
+    let requested = params["protocolVersion"].as_str();
+    let version = PROTOCOL_VERSIONS
+        .into_iter()
+        .find(|version| Some(*version) == requested)
+        .unwrap_or(PROTOCOL_VERSIONS[PROTOCOL_VERSIONS.len() - 1]);
+    json!({
+        "protocolVersion": version,
+        "capabilities": { "tools": {} },
+        "serverInfo": { "name": "spicy-todo", "version": env!("CARGO_PKG_VERSION") }
+    })
+}
+
+fn error(id: Value, code: i64, message: &str) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } })
+}
+
+/// Answers one JSON-RPC message, or `None` for notifications and for
+/// responses from the client.
+async fn handle_request(
+    service: &TodoService,
+    moderation: &Moderation,
+    request: Value,
+) -> Option<Value> {
+    let id = request.get("id").cloned();
+    let Some(method) = request.get("method").and_then(Value::as_str) else {
+        let is_response = request.get("result").is_some() || request.get("error").is_some();
+        return (!is_response).then(|| {
+            error(
+                id.unwrap_or(Value::Null),
+                INVALID_REQUEST,
+                "Invalid request",
+            )
+        });
+    };
+    let params = request.get("params").cloned().unwrap_or(Value::Null);
+    let result = match method {
+        "initialize" => Ok(initialize(&params)),
+        "ping" => Ok(json!({})),
+        "tools/list" => Ok(json!({ "tools": tools() })),
+        "tools/call" => match serde_json::from_value::<CallParams>(params) {
+            Ok(call) => match call_tool(service, moderation, &call.name, &call.arguments).await {
+                Some(Ok(value)) => Ok(json!({
+                    "content": [{
+                        "type": "text",
+                        "text": serde_json::to_string_pretty(&value).unwrap_or_default()
+                    }],
+                    "isError": false
+                })),
+                Some(Err(e)) => Ok(json!({
+                    "content": [{ "type": "text", "text": e }],
+                    "isError": true
+                })),
+                None => Err((INVALID_PARAMS, format!("Unknown tool: {}", call.name))),
+            },
+            Err(e) => Err((INVALID_PARAMS, e.to_string())),
+        },
+        _ => Err((METHOD_NOT_FOUND, format!("Method not found: {}", method))),
+    };
+    // Notifications, such as notifications/initialized, get no answer.
+    let id = id?;
+    Some(match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err((code, message)) => error(id, code, &message),
+    })
+}
+
+/// Answers one transport message: a JSON-RPC request, notification or
+/// batch.
+pub async fn handle_message(
+    service: &TodoService,
+    moderation: &Moderation,
+    message: &str,
+) -> Option<String> {
+    let response = match serde_json::from_str::<Value>(message) {
+        Ok(Value::Array(batch)) => {
+            let mut responses = Vec::new();
+            for request in batch {
+                responses.extend(handle_request(service, moderation, request).await);
+            }
+            (!responses.is_empty()).then_some(Value::Array(responses))
+        }
+        Ok(request) => handle_request(service, moderation, request).await,
+        Err(_) => Some(error(Value::Null, PARSE_ERROR, "Parse error")),
+    };
+    response.map(|response| response.to_string())
+}
+
+/// The stdio transport: one message per line on stdin, answers on stdout.
+/// Returns when stdin closes.
+pub async fn serve_stdio(service: &TodoService, moderation: &Moderation) -> std::io::Result<()> {
+    let mut lines = BufReader::new(tokio::io::stdin()).lines();
+    let mut stdout = tokio::io::stdout();
+    while let Some(line) = lines.next_line().await? {
+        if line.trim().is_empty() {
+            continue;
+        }
+        if let Some(response) = handle_message(service, moderation, &line).await {
+            stdout.write_all(response.as_bytes()).await?;
+            stdout.write_all(b"\n").await?;
+            stdout.flush().await?;
+        }
+    }
+    Ok(())
+}
+
+/// Open SSE streams, by session id, for the HTTP+SSE transport.
+#[derive(Default)]
+pub struct McpSessions {
+    sessions: Mutex<HashMap<String, mpsc::UnboundedSender<String>>>,
+}
+
+impl McpSessions {
+    pub fn new() -> Self {
+        McpSessions::default()
+    }
+
+    /// Starts a session, or `None` when too many are open.
+    pub fn open(&self) -> Option<(String, mpsc::UnboundedReceiver<String>)> {
+        let mut sessions = self.sessions.lock().unwrap();
+        sessions.retain(|_, sender| !sender.is_closed());
+        if sessions.len() >= MAX_SESSIONS {
+            return None;
+        }
+        let id = Uuid::new_v4().to_string();
+        let (sender, receiver) = mpsc::unbounded_channel();
+        sessions.insert(id.clone(), sender);
+        Some((id, receiver))
+    }
+
+    /// Whether the session exists and its client is still listening.
+    pub fn is_open(&self, id: &str) -> bool {
+        let mut sessions = self.sessions.lock().unwrap();
+        match sessions.get(id) {
+            Some(sender) if !sender.is_closed() => true,
+            Some(_) => {
+                sessions.remove(id);
+                false
+            }
+            None => false,
+        }
+    }
+
+    /// Queues `message` on the session's stream.
+    pub fn send(&self, id: &str, message: String) -> bool {
+        let sessions = self.sessions.lock().unwrap();
+        sessions
+            .get(id)
+            .is_some_and(|sender| sender.send(message).is_ok())
+    }
+}
+
+/// The SSE body of a session: first the endpoint to post messages to,
+/// then each answer as a `message` event.
+pub fn event_stream(
+    id: &str,
+    receiver: mpsc::UnboundedReceiver<String>,
+) -> impl Stream<Item = Result<Bytes, Infallible>> {
+    let endpoint = format!(
+        "event: endpoint\ndata: {}?sessionId={}\n\n",
+        MESSAGES_PATH, id
+    );
+    let messages = stream::unfold(receiver, |mut receiver| async move {
+        let message = receiver.recv().await?;
+        Some((format!("event: message\ndata: {}\n\n", message), receiver))
+    });
+    stream::once(async move { endpoint })
+        .chain(messages)
+        .map(|event| Ok(Bytes::from(event)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn call(service: &TodoService, request: Value) -> Value {
+        let response = handle_message(service, &Moderation::default(), &request.to_string())
+            .await
+            .unwrap();
+        serde_json::from_str(&response).unwrap()
+    }
+
+    async fn call_raw(service: &TodoService, message: &str) -> Value {
+        let response = handle_message(service, &Moderation::default(), message)
+            .await
+            .unwrap();
+        serde_json::from_str(&response).unwrap()
+    }
+
+    async fn create(service: &TodoService, arguments: Value) -> Value {
+        call(
+            service,
+            json!({ "jsonrpc": "2.0", "id": 1, "method": "tools/call",
+                    "params": { "name": "create_todo", "arguments": arguments } }),
+        )
+        .await
+    }
+
+    #[actix_web::test]
+    async fn test_initialize_and_list_tools() {
+        let service = TodoService::new_empty();
+        let response = call(
+            &service,
+            json!({ "jsonrpc": "2.0", "id": 1, "method": "initialize",
+                    "params": { "protocolVersion": "2024-11-05" } }),
+        )
+        .await;
+        assert_eq!(response["result"]["protocolVersion"], "2024-11-05");
+        let notification = r#"{"jsonrpc":"2.0","method":"notifications/initialized"}"#;
+        assert!(
+            handle_message(&service, &Moderation::default(), notification)
+                .await
+                .is_none()
+        );
+
+        let response = call(
+            &service,
+            json!({ "jsonrpc": "2.0", "id": 2, "method": "tools/list" }),
+        )
+        .await;
+        let names: Vec<&str> = response["result"]["tools"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|tool| tool["name"].as_str().unwrap())
+            .collect();
+        assert!(names.contains(&"create_todo"));
+        assert!(names.contains(&"get_stats"));
+
+        let response = call(
+            &service,
+            json!({ "jsonrpc": "2.0", "id": 3, "method": "resources/list" }),
+        )
+        .await;
+        assert_eq!(response["error"]["code"], METHOD_NOT_FOUND);
+        assert_eq!(
+            call(&service, json!("not a request")).await["error"]["code"],
+            INVALID_REQUEST
+        );
+        assert_eq!(call_raw(&service, "{").await["error"]["code"], PARSE_ERROR);
+    }
+
+    #[actix_web::test]
+    async fn test_tools_validate_like_the_api() {
+        let service = TodoService::new_empty();
+        let response = create(&service, json!({ "text": "  " })).await;
+        assert_eq!(response["result"]["isError"], true);
+        assert_eq!(
+            response["result"]["content"][0]["text"],
+            "Todo text is required"
+        );
+        let response = create(
+            &service,
+            json!({ "text": "Pay rent", "estimateMinutes": 0 }),
+        )
+        .await;
+        assert_eq!(response["result"]["isError"], true);
+
+        let response = create(&service, json!({ "text": "Pay rent", "priority": "high" })).await;
+        assert_eq!(response["result"]["isError"], false);
+        let todo: Value =
+            serde_json::from_str(response["result"]["content"][0]["text"].as_str().unwrap())
+                .unwrap();
+        assert_eq!(todo["priority"], "high");
+
+        let id = todo["id"].as_str().unwrap();
+        let moderation = Moderation::default();
+        let updated = call_tool(
+            &service,
+            &moderation,
+            "update_todo",
+            &json!({ "id": id, "completed": true }),
+        )
+        .await
+        .unwrap()
+        .unwrap();
+        assert_eq!(updated["completed"], true);
+        let found = call_tool(
+            &service,
+            &moderation,
+            "search_todos",
+            &json!({ "query": "rent" }),
+        )
+        .await
+        .unwrap()
+        .unwrap();
+        assert_eq!(found.as_array().unwrap().len(), 1);
+        assert!(call_tool(
+            &service,
+            &moderation,
+            "get_todo",
+            &json!({ "id": "missing" })
+        )
+        .await
+        .unwrap()
+        .is_err());
+        assert!(call_tool(&service, &moderation, "drop_tables", &json!({}))
+            .await
+            .is_none());
+    }
+}
@@ -0,0 +1,351 @@
+use actix_web::body::MessageBody;
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::middleware::Next;
+use actix_web::web;
+use prometheus::core::Collector;
+use prometheus::{
+    Encoder, Histogram, HistogramOpts, HistogramVec, IntCounter, IntCounterVec, IntGaugeVec, Opts,
+    Registry, TextEncoder,
+};
+use spicy_todo_core::events::{Event, EventType};
+use spicy_todo_core::models::TodoStats;
+use std::time::Instant;
+use tokio::sync::broadcast;
+
+const NAMESPACE: &str = "spicy_todo";
+
+/// Completion latency buckets, from a minute up to a month.
+const COMPLETION_LATENCY_BUCKETS: &[f64] = &[
+    60.0, 600.0, 3600.0, 21600.0, 86400.0, 259200.0, 604800.0, 2592000.0,
+];
+
+/// Fully-qualified names of the exported metric families, read back from the
+/// collectors so anything built on them matches the exposition output.
+#[derive(Debug, Clone)]
+pub struct MetricNames {
+    pub http_requests: String,
+    pub http_request_duration: String,
+    pub domain_events: String,
+    pub todos_created: String,
+    pub todos_completed: String,
+    pub completion_latency: String,
+    pub webhook_deliveries: String,
+    pub todos: String,
+    pub job_runs: String,
+    pub job_duration: String,
+}
+
+/// Prometheus registry with HTTP- and domain-level metrics.
+pub struct Metrics {
+    registry: Registry,
+    http_requests: IntCounterVec,
+    http_request_duration: HistogramVec,
+    domain_events: IntCounterVec,
+    todos_created: IntCounter,
+    todos_completed: IntCounter,
+    completion_latency: Histogram,
+    webhook_deliveries: IntCounterVec,
+    todos: IntGaugeVec,
+    job_runs: IntCounterVec,
+    job_duration: HistogramVec,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        let registry = Registry::new();
+
+        let http_requests = IntCounterVec::new(
+            Opts::new("http_requests_total", "HTTP requests handled").namespace(NAMESPACE),
+            &["method", "route", "status"],
+        )
+        .unwrap();
+        let http_request_duration = HistogramVec::new(
+            HistogramOpts::new("http_request_duration_seconds", "HTTP request latency")
+                .namespace(NAMESPACE),
+            &["method", "route"],
+        )
+        .unwrap();
+        let domain_events = IntCounterVec::new(
+            Opts::new("domain_events_total", "Domain events emitted, by type").namespace(NAMESPACE),
+            &["type"],
+        )
+        .unwrap();
+        let todos_created = IntCounter::with_opts(
+            Opts::new("todos_created_total", "Todos created").namespace(NAMESPACE),
+        )
+        .unwrap();
+        let todos_completed = IntCounter::with_opts(
+            Opts::new("todos_completed_total", "Todos marked completed").namespace(NAMESPACE),
+        )
+        .unwrap();
+        let completion_latency = Histogram::with_opts(
+            HistogramOpts::new(
+                "completion_latency_seconds",
+                "Time from a todo's creation to its completion",
+            )
+            .namespace(NAMESPACE)
+            .buckets(COMPLETION_LATENCY_BUCKETS.to_vec()),
+        )
+        .unwrap();
+        let webhook_deliveries = IntCounterVec::new(
+            Opts::new(
+                "webhook_deliveries_total",
+                "Webhook delivery attempts, by outcome",
+            )
+            .namespace(NAMESPACE),
+            &["outcome"],
+        )
+        .unwrap();
+        let todos = IntGaugeVec::new(
+            Opts::new("todos", "Current number of todos, by state").namespace(NAMESPACE),
+            &["state"],
+        )
+        .unwrap();
+        let job_runs = IntCounterVec::new(
+            Opts::new("job_runs_total", "Background job runs, by job and outcome")
+                .namespace(NAMESPACE),
+            &["job", "outcome"],
+        )
+        .unwrap();
+        let job_duration = HistogramVec::new(
+            HistogramOpts::new("job_duration_seconds", "Background job run time")
+                .namespace(NAMESPACE),
+            &["job"],
+        )
+        .unwrap();
+
+        registry.register(Box::new(http_requests.clone())).unwrap();
+        registry
+            .register(Box::new(http_request_duration.clone()))
+            .unwrap();
+        registry.register(Box::new(domain_events.clone())).unwrap();
+        registry.register(Box::new(todos_created.clone())).unwrap();
+        registry
+            .register(Box::new(todos_completed.clone()))
+            .unwrap();
+        registry
+            .register(Box::new(completion_latency.clone()))
+            .unwrap();
+        registry
+            .register(Box::new(webhook_deliveries.clone()))
+            .unwrap();
+        registry.register(Box::new(todos.clone())).unwrap();
+        registry.register(Box::new(job_runs.clone())).unwrap();
+        registry.register(Box::new(job_duration.clone())).unwrap();
+
+        Metrics {
+            registry,
+            http_requests,
+            http_request_duration,
+            domain_events,
+            todos_created,
+            todos_completed,
+            completion_latency,
+            webhook_deliveries,
+            todos,
+            job_runs,
+            job_duration,
+        }
+    }
+
+    pub fn names(&self) -> MetricNames {
+        fn name(collector: &dyn Collector) -> String {
+            collector.desc()[0].fq_name.clone()
+        }
+        MetricNames {
+            http_requests: name(&self.http_requests),
+            http_request_duration: name(&self.http_request_duration),
+            domain_events: name(&self.domain_events),
+            todos_created: name(&self.todos_created),
+            todos_completed: name(&self.todos_completed),
+            completion_latency: name(&self.completion_latency),
+            webhook_deliveries: name(&self.webhook_deliveries),
+            todos: name(&self.todos),
+            job_runs: name(&self.job_runs),
+            job_duration: name(&self.job_duration),
+        }
+    }
+
+    pub fn observe_event(&self, event: &Event) {
+        let label = serde_json::to_value(event.event_type)
+            .ok()
+            .and_then(|value| value.as_str().map(str::to_string))
+            .unwrap_or_default();
+        self.domain_events.with_label_values(&[&label]).inc();
+
+        match event.event_type {
+            EventType::Created => self.todos_created.inc(),
+            EventType::Completed => {
+                self.todos_completed.inc();
+                let latency = event.timestamp - event.todo.created_at;
+                self.completion_latency
+                    .observe(latency.num_milliseconds().max(0) as f64 / 1000.0);
+            }
+            _ => {}
+        }
+    }
+
+    pub fn observe_webhook_delivery(&self, success: bool) {
+        let outcome = if success { "success" } else { "failure" };
+        self.webhook_deliveries.with_label_values(&[outcome]).inc();
+    }
+
+    /// `outcome` is one of `done`, `skipped` or `failed`.
+    pub fn observe_job(&self, job: &str, outcome: &str, seconds: f64) {
+        self.job_runs.with_label_values(&[job, outcome]).inc();
+        self.job_duration.with_label_values(&[job]).observe(seconds);
+    }
+
+    pub fn observe_request(&self, method: &str, route: &str, status: u16, seconds: f64) {
+        self.http_requests
+            .with_label_values(&[method, route, &status.to_string()])
+            .inc();
+        self.http_request_duration
+            .with_label_values(&[method, route])
+            .observe(seconds);
+    }
+
+    /// Encodes every metric in the Prometheus text exposition format,
+    /// refreshing the point-in-time gauges from `stats` first.
+    pub fn render(&self, stats: &TodoStats) -> String {
+        self.todos
+            .with_label_values(&["active"])
+            .set(stats.active as i64);
+        self.todos
+            .with_label_values(&["completed"])
+            .set(stats.completed as i64);
+        self.todos
+            .with_label_values(&["overdue"])
+            .set(stats.overdue_count as i64);
+
+        let mut buffer = Vec::new();
+        TextEncoder::new()
+            .encode(&self.registry.gather(), &mut buffer)
+            .unwrap();
+        String::from_utf8(buffer).unwrap_or_default()
+    }
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Feeds domain events from the service's event stream into the registry.
+pub async fn run_event_recorder(
+    metrics: web::Data<Metrics>,
+    mut receiver: broadcast::Receiver<Event>,
+) {
+    loop {
+        match receiver.recv().await {
+            Ok(event) => metrics.observe_event(&event),
+            Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                eprintln!("Metrics recorder lagged, skipped {} events", skipped);
+            }
+            Err(broadcast::error::RecvError::Closed) => break,
+        }
+    }
+}
+
+/// Middleware recording request counts and latency per matched route. Route
+/// patterns (not raw paths) keep label cardinality bounded.
+pub async fn track_requests(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, actix_web::Error> {
+    let metrics = req.app_data::<web::Data<Metrics>>().cloned();
+    let method = req.method().to_string();
+    let route = req
+        .match_pattern()
+        .unwrap_or_else(|| "unmatched".to_string());
+    let start = Instant::now();
+
+    let response = next.call(req).await?;
+
+    if let Some(metrics) = metrics {
+        metrics.observe_request(
+            &method,
+            &route,
+            response.status().as_u16(),
+            start.elapsed().as_secs_f64(),
+        );
+    }
+    Ok(response)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, Utc};
+    use spicy_todo_core::models::{todo_id, Priority, Todo};
+
+    fn event(event_type: EventType) -> Event {
+        let now = Utc::now();
+        Event {
+            id: "event-1".to_string(),
+            sequence: 1,
+            event_type,
+            todo_id: "todo-1".to_string(),
+            actor: None,
+            request_id: None,
+            workspace: None,
+            timestamp: now,
+            todo: Todo {
+                id: todo_id("todo-1"),
+                text: "Test".to_string(),
+                priority: Priority::Medium,
+                completed: event_type == EventType::Completed,
+                due_date: None,
+                reminder_time: None,
+                recurrence: None,
+                recurrence_end: None,
+                remaining_occurrences: None,
+                estimate_minutes: None,
+                location: None,
+                color: None,
+                icon: None,
+                pinned: false,
+                rollover_count: 0,
+                rolled_over_to: None,
+                created_at: now - Duration::hours(2),
+                updated_at: now,
+            },
+            child: None,
+        }
+    }
+
+    #[test]
+    fn test_observe_event_updates_domain_metrics() {
+        let metrics = Metrics::new();
+        metrics.observe_event(&event(EventType::Created));
+        metrics.observe_event(&event(EventType::Completed));
+
+        assert_eq!(metrics.todos_created.get(), 1);
+        assert_eq!(metrics.todos_completed.get(), 1);
+        assert_eq!(metrics.completion_latency.get_sample_count(), 1);
+        assert!(metrics.completion_latency.get_sample_sum() >= 7200.0);
+        assert_eq!(
+            metrics
+                .domain_events
+                .with_label_values(&["todo.completed"])
+                .get(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_render_exposes_metric_names() {
+        let metrics = Metrics::new();
+        metrics.observe_event(&event(EventType::Created));
+        metrics.observe_webhook_delivery(false);
+        metrics.observe_request("GET", "/api/todos", 200, 0.01);
+
+        let stats = spicy_todo_core::service::TodoService::new().get_stats();
+        let output = metrics.render(&stats);
+        assert!(output.contains("spicy_todo_todos_created_total 1"));
+        assert!(output.contains("spicy_todo_webhook_deliveries_total{outcome=\"failure\"} 1"));
+        assert!(output.contains("spicy_todo_todos{state=\"active\"} 2"));
+        assert!(output.contains("spicy_todo_http_requests_total"));
+    }
+}
@@ -0,0 +1,397 @@
+use crate::config::{Config, ModerationSettings};
+use crate::plugins::{Kind, Plugin};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::HashSet;
+use std::future::Future;
+use std::pin::Pin;
+use std::str::FromStr;
+
+const CHECK_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Words the built-in list screens out, matched as whole words after
+/// undoing common letter-for-digit swaps (`sh1t`).
+const DEFAULT_WORDS: [&str; 24] = [
+    "arse",
+    "arsehole",
+    "ass",
+    "asshole",
+    "bastard",
+    "bitch",
+    "bollocks",
+    "bullshit",
+    "crap",
+    "cunt",
+    "damn",
+    "dick",
+    "dickhead",
+    "fuck",
+    "fucked",
+    "fucker",
+    "fucking",
+    "motherfucker",
+    "piss",
+    "shit",
+    "shitty",
+    "slut",
+    "twat",
+    "whore",
+];
+
+/// What happens to todo text with disallowed content.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ModerationMode {
+    #[default]
+    Off,
+    /// The create or update fails with 422.
+    Reject,
+    /// The offending words are replaced with asterisks and the write goes
+    /// ahead.
+    Mask,
+}
+
+impl FromStr for ModerationMode {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_lowercase().as_str() {
+            "off" => Ok(ModerationMode::Off),
+            "reject" => Ok(ModerationMode::Reject),
+            "mask" => Ok(ModerationMode::Mask),
+            other => Err(format!(
+                "Invalid moderation mode '{}': expected off, reject or mask",
+                other
+            )),
+        }
+    }
+}
+
+impl ModerationMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ModerationMode::Off => "off",
+            ModerationMode::Reject => "reject",
+            ModerationMode::Mask => "mask",
+        }
+    }
+}
+
+/// A moderator's finding. `terms` are the words to mask; a flagged
+/// verdict without any can only be rejected.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct Verdict {
+    pub flagged: bool,
+    #[serde(default)]
+    pub terms: Vec<String>,
+}
+
+pub type CheckFuture<'a> = Pin<Box<dyn Future<Output = Result<Verdict, String>> + 'a>>;
+
+/// Screens todo text before it is saved.
+pub trait ContentModerator: Send + Sync {
+    fn check<'a>(&'a self, text: &'a str) -> CheckFuture<'a>;
+}
+
+fn normalize(word: &str) -> String {
+    word.chars()
+        .map(|c| match c {
+            '0' => 'o',
+            '1' => 'i',
+            '3' => 'e',
+            '4' | '@' => 'a',
+            '5' | '$' => 's',
+            '7' => 't',
+            c => c,
+        })
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '@' || c == '$'
+}
+
+/// The words of `text` with their byte offsets.
+fn words(text: &str) -> Vec<(usize, &str)> {
+    let mut words = Vec::new();
+    let mut start = None;
+    for (i, c) in text.char_indices() {
+        match (is_word_char(c), start) {
+            (true, None) => start = Some(i),
+            (false, Some(from)) => {
+                words.push((from, &text[from..i]));
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    if let Some(from) = start {
+        words.push((from, &text[from..]));
+    }
+    words
+}
+
+/// The default moderator: a fixed list of words.
+pub struct WordList {
+    words: HashSet<String>,
+}
+
+impl WordList {
+    /// The built-in words plus `extra`.
+    pub fn new(extra: &[String]) -> Self {
+        WordList {
+            words: DEFAULT_WORDS
+                .iter()
+                .map(|word| word.to_string())
+                .chain(extra.iter().map(|word| normalize(word.trim())))
+                .filter(|word| !word.is_empty())
+                .collect(),
+        }
+    }
+
+    fn verdict(&self, text: &str) -> Verdict {
+        let mut terms: Vec<String> = Vec::new();
+        for (_, word) in words(text) {
+            if self.words.contains(&normalize(word)) && !terms.iter().any(|term| term == word) {
+                terms.push(word.to_string());
+            }
+        }
+        Verdict {
+            flagged: !terms.is_empty(),
+            terms,
+        }
+    }
+}
+
+impl ContentModerator for WordList {
+    fn check<'a>(&'a self, text: &'a str) -> CheckFuture<'a> {
+        Box::pin(async move { Ok(self.verdict(text)) })
+    }
+}
+
+/// A moderation service over HTTP. Gets `{"text": ...}` posted and
+/// answers with a `Verdict`: `{"flagged": true, "terms": ["..."]}`.
+pub struct ExternalModerator {
+    url: String,
+    api_key: Option<String>,
+}
+
+impl ExternalModerator {
+    pub fn new(url: &str, api_key: Option<&str>) -> Self {
+        ExternalModerator {
+            url: url.to_string(),
+            api_key: api_key.map(str::to_string),
+        }
+    }
+}
+
+impl ContentModerator for ExternalModerator {
+    fn check<'a>(&'a self, text: &'a str) -> CheckFuture<'a> {
+        Box::pin(async move {
+            let client = awc::Client::builder().timeout(CHECK_TIMEOUT).finish();
+            let mut request = client.post(&self.url);
+            if let Some(api_key) = &self.api_key {
+                request = request.bearer_auth(api_key);
+            }
+            let mut response = request
+                .send_json(&json!({ "text": text }))
+                .await
+                .map_err(|e| format!("Moderation service unreachable: {}", e))?;
+            if !response.status().is_success() {
+                return Err(format!(
+                    "Moderation service responded with {}",
+                    response.status()
+                ));
+            }
+            response
+                .json::<Verdict>()
+                .await
+                .map_err(|e| format!("Unreadable moderation verdict: {}", e))
+        })
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ModerationError {
+    /// The text may not be saved; carries the offending words, if known.
+    Disallowed(Vec<String>),
+    /// The external service couldn't give a verdict. Text is not let
+    /// through unchecked.
+    Unavailable(String),
+}
+
+impl std::fmt::Display for ModerationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ModerationError::Disallowed(_) => write!(f, "Todo text contains disallowed content"),
+            ModerationError::Unavailable(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+/// The deployment's moderation policy: a mode and the moderators that
+/// must all pass the text.
+#[derive(Default)]
+pub struct Moderation {
+    mode: ModerationMode,
+    moderators: Vec<Box<dyn ContentModerator>>,
+}
+
+impl Moderation {
+    pub fn new(mode: ModerationMode, moderators: Vec<Box<dyn ContentModerator>>) -> Self {
+        Moderation { mode, moderators }
+    }
+
+    /// The word list, with `MODERATION_WORDS` and the words in
+    /// `MODERATION_WORDS_FILE`, then the external service if configured.
+    pub fn from_settings(settings: &ModerationSettings) -> Result<Self, String> {
+        let mut extra = settings.words.clone();
+        if let Some(path) = &settings.words_file {
+            let words = std::fs::read_to_string(path)
+                .map_err(|e| format!("Cannot read {}: {}", path.display(), e))?;
+            extra.extend(
+                words
+                    .lines()
+                    .map(str::trim)
+                    .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                    .map(str::to_string),
+            );
+        }
+        let mut moderators: Vec<Box<dyn ContentModerator>> = vec![Box::new(WordList::new(&extra))];
+        if let Some(url) = &settings.url {
+            moderators.push(Box::new(ExternalModerator::new(
+                url,
+                settings.api_key.as_deref(),
+            )));
+        }
+        Ok(Moderation::new(settings.mode, moderators))
+    }
+
+    /// Checks `text`. Returns the masked text when masking changed it, or
+    /// `None` when it can be saved as is.
+    pub async fn review(&self, text: &str) -> Result<Option<String>, ModerationError> {
+        if self.mode == ModerationMode::Off {
+            return Ok(None);
+        }
+        let mut current = text.to_string();
+        for moderator in &self.moderators {
+            let verdict = moderator
+                .check(&current)
+                .await
+                .map_err(ModerationError::Unavailable)?;
+            if !verdict.flagged {
+                continue;
+            }
+            let masked = mask(&current, &verdict.terms);
+            if self.mode == ModerationMode::Reject || masked == current {
+                return Err(ModerationError::Disallowed(verdict.terms));
+            }
+            current = masked;
+        }
+        Ok((current != text).then_some(current))
+    }
+
+    /// Reviews `text` in place.
+    pub async fn apply(&self, text: &mut String) -> Result<(), ModerationError> {
+        if let Some(masked) = self.review(text).await? {
+            *text = masked;
+        }
+        Ok(())
+    }
+}
+
+/// Replaces each word of `text` matching one of `terms` with asterisks.
+fn mask(text: &str, terms: &[String]) -> String {
+    let terms: HashSet<String> = terms.iter().map(|term| normalize(term)).collect();
+    let mut masked = String::with_capacity(text.len());
+    let mut copied = 0;
+    for (start, word) in words(text) {
+        if terms.contains(&normalize(word)) {
+            masked.push_str(&text[copied..start]);
+            masked.push_str(&"*".repeat(word.chars().count()));
+            copied = start + word.len();
+        }
+    }
+    masked.push_str(&text[copied..]);
+    masked
+}
+
+pub struct ModerationPlugin;
+
+impl Plugin for ModerationPlugin {
+    fn name(&self) -> &'static str {
+        "moderation"
+    }
+    fn kind(&self) -> Kind {
+        Kind::Moderation
+    }
+    fn description(&self) -> &'static str {
+        "Rejects or masks disallowed words in todo text"
+    }
+    fn enabled(&self, config: &Config) -> bool {
+        config.moderation.mode != ModerationMode::Off
+    }
+    fn details(&self, config: &Config) -> Option<Value> {
+        Some(json!({
+            "mode": config.moderation.mode.as_str(),
+            "external": config.moderation.url.is_some()
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn moderation(mode: ModerationMode) -> Moderation {
+        Moderation::new(
+            mode,
+            vec![Box::new(WordList::new(&["Broccoli".to_string()]))],
+        )
+    }
+
+    #[actix_web::test]
+    async fn test_word_list_rejects_or_masks() {
+        let reject = moderation(ModerationMode::Reject);
+        assert_eq!(reject.review("Buy milk, then the dishes").await, Ok(None));
+        assert_eq!(
+            reject.review("Clean this sh1t up").await,
+            Err(ModerationError::Disallowed(vec!["sh1t".to_string()]))
+        );
+        // Whole words only.
+        assert_eq!(
+            reject.review("Pass the class, assess Scunthorpe").await,
+            Ok(None)
+        );
+
+        let mask = moderation(ModerationMode::Mask);
+        assert_eq!(
+            mask.review("Eat the BROCCOLI, damn it").await,
+            Ok(Some("Eat the ********, **** it".to_string()))
+        );
+        let off = moderation(ModerationMode::Off);
+        assert_eq!(off.review("damn").await, Ok(None));
+    }
+
+    struct FlagsEverything;
+
+    impl ContentModerator for FlagsEverything {
+        fn check<'a>(&'a self, _text: &'a str) -> CheckFuture<'a> {
+            Box::pin(async {
+                Ok(Verdict {
+                    flagged: true,
+                    terms: Vec::new(),
+                })
+            })
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_flagged_without_terms_cannot_be_masked() {
+        let mask = Moderation::new(ModerationMode::Mask, vec![Box::new(FlagsEverything)]);
+        assert_eq!(
+            mask.review("Anything").await,
+            Err(ModerationError::Disallowed(Vec::new()))
+        );
+    }
+}
@@ -0,0 +1,142 @@
+use chrono::NaiveDate;
+use serde::Serialize;
+use spicy_todo_core::models::Todo;
+use spicy_todo_core::service::TodoService;
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use uuid::Uuid;
+
+const MAX_TODOS_PER_DAY: usize = 100;
+
+/// One day's plan as `GET /api/myday/{date}` shows it.
+#[derive(Debug, Serialize)]
+pub struct MyDay {
+    pub date: NaiveDate,
+    /// Planned todos, in the order they were added.
+    pub todos: Vec<Todo>,
+    /// The day before's planned todos that are still open and not planned
+    /// again, offered to carry over.
+    pub rollover: Vec<Todo>,
+}
+
+/// "My day": todos picked for a given day, whatever their due date. Only
+/// today and yesterday are kept once a day has passed; yesterday's plan is
+/// where the rollover comes from.
+pub struct MyDayStore {
+    days: Mutex<BTreeMap<NaiveDate, Vec<Uuid>>>,
+}
+
+impl MyDayStore {
+    pub fn new() -> Self {
+        MyDayStore {
+            days: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// Plans todo `id` for `date`, forgetting plans from before yesterday.
+    /// `Ok(false)` when it was already planned.
+    pub fn add(&self, date: NaiveDate, id: Uuid, today: NaiveDate) -> Result<bool, String> {
+        let yesterday = today.pred_opt().unwrap_or(today);
+        if date < yesterday {
+            return Err("Only days from yesterday on can be planned".to_string());
+        }
+        let mut days = self.days.lock().unwrap();
+        days.retain(|day, _| *day >= yesterday);
+        let ids = days.entry(date).or_default();
+        if ids.contains(&id) {
+            return Ok(false);
+        }
+        if ids.len() >= MAX_TODOS_PER_DAY {
+            return Err(format!(
+                "At most {} todos can be planned for a day",
+                MAX_TODOS_PER_DAY
+            ));
+        }
+        ids.push(id);
+        Ok(true)
+    }
+
+    /// Whether todo `id` was planned for `date`.
+    pub fn remove(&self, date: NaiveDate, id: Uuid) -> bool {
+        let mut days = self.days.lock().unwrap();
+        let Some(ids) = days.get_mut(&date) else {
+            return false;
+        };
+        let before = ids.len();
+        ids.retain(|planned| *planned != id);
+        let removed = ids.len() < before;
+        if ids.is_empty() {
+            days.remove(&date);
+        }
+        removed
+    }
+
+    pub fn ids(&self, date: NaiveDate) -> Vec<Uuid> {
+        self.days
+            .lock()
+            .unwrap()
+            .get(&date)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// The plan for `date` with its rollover. Todos deleted since they
+    /// were planned are left out.
+    pub fn day(&self, date: NaiveDate, service: &TodoService) -> MyDay {
+        let planned = self.ids(date);
+        let todos = planned
+            .iter()
+            .filter_map(|id| service.get_by_id(*id))
+            .collect();
+        let rollover = date
+            .pred_opt()
+            .map(|before| self.ids(before))
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|id| !planned.contains(id))
+            .filter_map(|id| service.get_by_id(id))
+            .filter(|todo| !todo.completed)
+            .collect();
+        MyDay {
+            date,
+            todos,
+            rollover,
+        }
+    }
+}
+
+impl Default for MyDayStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spicy_todo_core::models::todo_id;
+
+    fn date(value: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(value, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn test_keeps_days_from_yesterday_on() {
+        let store = MyDayStore::new();
+        let (a, b, c) = (todo_id("a"), todo_id("b"), todo_id("c"));
+        let today = date("2026-10-16");
+        assert_eq!(
+            store.add(date("2026-10-14"), a, today),
+            Err("Only days from yesterday on can be planned".to_string())
+        );
+        assert_eq!(store.add(date("2026-10-15"), a, today), Ok(true));
+        assert_eq!(store.add(date("2026-10-15"), a, today), Ok(false));
+        assert_eq!(store.add(date("2026-10-20"), b, today), Ok(true));
+
+        store.add(today, c, date("2026-10-17")).unwrap();
+        assert!(store.ids(date("2026-10-15")).is_empty());
+        assert_eq!(store.ids(date("2026-10-20")), vec![b]);
+        assert!(store.remove(date("2026-10-20"), b));
+        assert!(!store.remove(date("2026-10-20"), b));
+    }
+}
@@ -0,0 +1,60 @@
+use actix_web::web::{self, Bytes};
+use futures_util::{stream, Stream};
+use spicy_todo_core::TodoService;
+use std::convert::Infallible;
+
+pub const CONTENT_TYPE: &str = "application/x-ndjson";
+/// Todos read from the store, and written out, per chunk of the body.
+pub const PAGE_SIZE: usize = 500;
+
+/// The body of `GET /api/todos/stream`: every todo as one JSON line, by id.
+/// Only the ids are collected up front; the todos themselves are read a
+/// page at a time as the client consumes the body, so memory stays flat
+/// however many there are. A todo deleted before its page is read is left
+/// out.
+pub fn todo_stream(
+    service: web::Data<TodoService>,
+) -> impl Stream<Item = Result<Bytes, Infallible>> {
+    let ids = service.todo_ids();
+    stream::unfold((service, ids, 0), |(service, ids, start)| async move {
+        if start >= ids.len() {
+            return None;
+        }
+        let end = (start + PAGE_SIZE).min(ids.len());
+        let mut page = Vec::new();
+        for todo in service.get_many(&ids[start..end]) {
+            serde_json::to_writer(&mut page, &todo).expect("todos serialize to JSON");
+            page.push(b'\n');
+        }
+        Some((Ok(Bytes::from(page)), (service, ids, end)))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures_util::StreamExt;
+    use spicy_todo_core::models::TodoCreate;
+
+    #[actix_web::test]
+    async fn test_streams_every_todo_in_pages() {
+        let service = web::Data::new(TodoService::new_empty());
+        for i in 0..PAGE_SIZE + 1 {
+            service.create(TodoCreate {
+                text: format!("Todo {}", i),
+                ..Default::default()
+            });
+        }
+
+        let pages: Vec<Bytes> = todo_stream(service)
+            .map(|page| page.unwrap())
+            .collect()
+            .await;
+        assert_eq!(pages.len(), 2);
+        let lines: usize = pages
+            .iter()
+            .map(|page| page.split(|b| *b == b'\n').count() - 1)
+            .sum();
+        assert_eq!(lines, PAGE_SIZE + 1);
+    }
+}
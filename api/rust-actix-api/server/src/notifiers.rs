@@ -0,0 +1,638 @@
+use crate::config::Config;
+use crate::plugins::{Kind, Plugin};
+use crate::preferences::{Channel, PreferenceStore};
+use crate::reminders;
+use crate::scheduler::{Outcome, Schedule, Scheduler};
+use actix_web::web;
+use chrono::{DateTime, Datelike, NaiveDate, NaiveDateTime, Utc, Weekday};
+use serde::{Deserialize, Serialize};
+use spicy_todo_core::digest::{self, Agenda, PlainTextOptions};
+use spicy_todo_core::events::{Event, EventType};
+use spicy_todo_core::models::{Priority, Todo};
+use spicy_todo_core::weekly::WeeklySummary;
+use spicy_todo_core::TodoService;
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+const SEND_TIMEOUT: Duration = Duration::from_secs(10);
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+/// Longest message Discord accepts.
+const DISCORD_MAX_CHARS: usize = 2000;
+/// Slack truncates anything longer than this itself, mid-word.
+const SLACK_MAX_CHARS: usize = 40000;
+
+/// Placeholders available to the `created` and `overdue` templates.
+pub const TODO_PLACEHOLDERS: [&str; 4] = ["text", "priority", "dueDate", "id"];
+/// Placeholders available to the `summary` template.
+pub const SUMMARY_PLACEHOLDERS: [&str; 5] = ["date", "overdue", "dueToday", "upcoming", "agenda"];
+/// Placeholders available to the `weekly` template.
+pub const WEEKLY_PLACEHOLDERS: [&str; 7] = [
+    "user",
+    "from",
+    "to",
+    "completed",
+    "carriedOver",
+    "upcoming",
+    "summary",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NotifierKind {
+    Slack,
+    Discord,
+}
+
+fn enabled() -> bool {
+    true
+}
+
+/// Which messages a notifier posts; all of them unless turned off.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Alerts {
+    #[serde(rename = "dailySummary", default = "enabled")]
+    pub daily_summary: bool,
+    #[serde(default = "enabled")]
+    pub overdue: bool,
+    /// A message as soon as a high priority todo is created.
+    #[serde(rename = "highPriority", default = "enabled")]
+    pub high_priority: bool,
+    /// Each user's week, posted on Mondays.
+    #[serde(rename = "weeklySummary", default = "enabled")]
+    pub weekly_summary: bool,
+}
+
+impl Default for Alerts {
+    fn default() -> Self {
+        Alerts {
+            daily_summary: true,
+            overdue: true,
+            high_priority: true,
+            weekly_summary: true,
+        }
+    }
+}
+
+fn default_created() -> String {
+    "New high priority todo: {text}".to_string()
+}
+
+fn default_overdue() -> String {
+    "Overdue: {text} (was due {dueDate})".to_string()
+}
+
+fn default_summary() -> String {
+    "{agenda}".to_string()
+}
+
+fn default_weekly() -> String {
+    "{summary}".to_string()
+}
+
+/// Message templates. `{name}` is replaced by the named value, see
+/// `TODO_PLACEHOLDERS`, `SUMMARY_PLACEHOLDERS` and `WEEKLY_PLACEHOLDERS`;
+/// templates left out use the defaults.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Templates {
+    #[serde(default = "default_created")]
+    pub created: String,
+    #[serde(default = "default_overdue")]
+    pub overdue: String,
+    #[serde(default = "default_summary")]
+    pub summary: String,
+    #[serde(default = "default_weekly")]
+    pub weekly: String,
+}
+
+impl Default for Templates {
+    fn default() -> Self {
+        Templates {
+            created: default_created(),
+            overdue: default_overdue(),
+            summary: default_summary(),
+            weekly: default_weekly(),
+        }
+    }
+}
+
+/// A Slack or Discord incoming webhook, one per workspace or server that
+/// wants to hear about the todo list.
+#[derive(Debug, Clone, Serialize)]
+pub struct Notifier {
+    pub id: String,
+    pub kind: NotifierKind,
+    /// Which Slack workspace or Discord server the webhook posts to.
+    pub workspace: String,
+    pub url: String,
+    pub alerts: Alerts,
+    pub templates: Templates,
+    #[serde(rename = "createdAt")]
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct NotifierCreate {
+    pub kind: NotifierKind,
+    pub workspace: String,
+    pub url: String,
+    pub alerts: Option<Alerts>,
+    pub templates: Option<Templates>,
+}
+
+/// The kinds of message a notifier can post.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Alert {
+    DailySummary,
+    Overdue,
+    HighPriority,
+    WeeklySummary,
+}
+
+impl Alerts {
+    fn wants(&self, alert: Alert) -> bool {
+        match alert {
+            Alert::DailySummary => self.daily_summary,
+            Alert::Overdue => self.overdue,
+            Alert::HighPriority => self.high_priority,
+            Alert::WeeklySummary => self.weekly_summary,
+        }
+    }
+}
+
+/// Replaces each `{name}` in `template` with its value, failing on names
+/// not in `values` so typos surface when the template is saved.
+fn render(template: &str, values: &[(&str, String)]) -> Result<String, String> {
+    let mut rendered = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        rendered.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let Some(end) = after.find('}') else {
+            rendered.push_str(&rest[start..]);
+            return Ok(rendered);
+        };
+        let name = &after[..end];
+        let (_, value) = values
+            .iter()
+            .find(|(key, _)| *key == name)
+            .ok_or_else(|| format!("unknown placeholder {{{}}}", name))?;
+        rendered.push_str(value);
+        rest = &after[end + 1..];
+    }
+    rendered.push_str(rest);
+    Ok(rendered)
+}
+
+impl Templates {
+    fn validate(&self) -> Result<(), String> {
+        let todo: Vec<(&str, String)> = TODO_PLACEHOLDERS
+            .iter()
+            .map(|name| (*name, String::new()))
+            .collect();
+        let summary: Vec<(&str, String)> = SUMMARY_PLACEHOLDERS
+            .iter()
+            .map(|name| (*name, String::new()))
+            .collect();
+        let weekly: Vec<(&str, String)> = WEEKLY_PLACEHOLDERS
+            .iter()
+            .map(|name| (*name, String::new()))
+            .collect();
+        for (field, template, values) in [
+            ("created", &self.created, &todo),
+            ("overdue", &self.overdue, &todo),
+            ("summary", &self.summary, &summary),
+            ("weekly", &self.weekly, &weekly),
+        ] {
+            if template.trim().is_empty() {
+                return Err(format!("templates.{} must not be empty", field));
+            }
+            render(template, values).map_err(|e| format!("templates.{}: {}", field, e))?;
+        }
+        Ok(())
+    }
+}
+
+impl NotifierKind {
+    /// Slack reads `&`, `<` and `>` as markup, so todo text containing
+    /// them must be escaped. Discord mentions are disabled per message.
+    fn escape(self, value: &str) -> String {
+        match self {
+            NotifierKind::Slack => value
+                .replace('&', "&amp;")
+                .replace('<', "&lt;")
+                .replace('>', "&gt;"),
+            NotifierKind::Discord => value.to_string(),
+        }
+    }
+
+    /// The webhook body for `text`, cut to what the service accepts.
+    fn payload(self, text: &str) -> serde_json::Value {
+        match self {
+            NotifierKind::Slack => serde_json::json!({ "text": truncate(text, SLACK_MAX_CHARS) }),
+            NotifierKind::Discord => serde_json::json!({
+                "content": truncate(text, DISCORD_MAX_CHARS),
+                "allowed_mentions": { "parse": [] }
+            }),
+        }
+    }
+}
+
+fn truncate(text: &str, max_chars: usize) -> String {
+    if text.chars().count() <= max_chars {
+        return text.to_string();
+    }
+    let mut truncated: String = text.chars().take(max_chars - 1).collect();
+    truncated.push('…');
+    truncated
+}
+
+fn priority_label(priority: &Priority) -> &'static str {
+    match priority {
+        Priority::Low => "low",
+        Priority::Medium => "medium",
+        Priority::High => "high",
+    }
+}
+
+impl Notifier {
+    fn todo_message(&self, template: &str, todo: &Todo) -> String {
+        let values = [
+            ("text", self.kind.escape(todo.text.trim())),
+            ("priority", priority_label(&todo.priority).to_string()),
+            ("dueDate", todo.due_date.clone().unwrap_or_default()),
+            ("id", todo.id.to_string()),
+        ];
+        render(template, &values).unwrap_or_else(|_| template.to_string())
+    }
+
+    pub fn created_message(&self, todo: &Todo) -> String {
+        self.todo_message(&self.templates.created, todo)
+    }
+
+    pub fn overdue_message(&self, todo: &Todo) -> String {
+        self.todo_message(&self.templates.overdue, todo)
+    }
+
+    pub fn summary_message(&self, agenda: &Agenda) -> String {
+        let text = agenda.render_plain_text(&PlainTextOptions::default());
+        let values = [
+            ("date", agenda.date.format("%Y-%m-%d").to_string()),
+            ("overdue", agenda.overdue.len().to_string()),
+            ("dueToday", agenda.due_today.len().to_string()),
+            ("upcoming", agenda.upcoming.len().to_string()),
+            ("agenda", self.kind.escape(text.trim_end())),
+        ];
+        let template = &self.templates.summary;
+        render(template, &values).unwrap_or_else(|_| template.to_string())
+    }
+
+    pub fn weekly_message(&self, summary: &WeeklySummary) -> String {
+        let text = summary.render_plain_text();
+        let values = [
+            ("user", self.kind.escape(&summary.user)),
+            ("from", summary.from.format("%Y-%m-%d").to_string()),
+            ("to", summary.to.format("%Y-%m-%d").to_string()),
+            ("completed", summary.completed.to_string()),
+            ("carriedOver", summary.carried_over.len().to_string()),
+            ("upcoming", summary.upcoming.len().to_string()),
+            ("summary", self.kind.escape(text.trim_end())),
+        ];
+        let template = &self.templates.weekly;
+        render(template, &values).unwrap_or_else(|_| template.to_string())
+    }
+
+    /// Posts `text` to the webhook.
+    pub async fn post(&self, text: &str) -> Result<(), String> {
+        let client = awc::Client::builder().timeout(SEND_TIMEOUT).finish();
+        let response = client
+            .post(&self.url)
+            .send_json(&self.kind.payload(text))
+            .await
+            .map_err(|e| format!("{:?} webhook unreachable: {}", self.kind, e))?;
+        if !response.status().is_success() {
+            return Err(format!(
+                "{:?} webhook responded with {}",
+                self.kind,
+                response.status()
+            ));
+        }
+        Ok(())
+    }
+}
+
+pub struct NotifierService {
+    notifiers: Mutex<HashMap<String, Notifier>>,
+}
+
+impl NotifierService {
+    pub fn new() -> Self {
+        NotifierService {
+            notifiers: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn create(&self, input: NotifierCreate) -> Result<Notifier, String> {
+        let url = input.url.trim();
+        if !(url.starts_with("http://") || url.starts_with("https://")) {
+            return Err("Notifier url must be an http(s) URL".to_string());
+        }
+        let workspace = input.workspace.trim();
+        if workspace.is_empty() {
+            return Err("Notifier workspace must not be empty".to_string());
+        }
+        let templates = input.templates.unwrap_or_default();
+        templates.validate()?;
+
+        let notifier = Notifier {
+            id: Uuid::new_v4().to_string(),
+            kind: input.kind,
+            workspace: workspace.to_string(),
+            url: url.to_string(),
+            alerts: input.alerts.unwrap_or_default(),
+            templates,
+            created_at: Utc::now(),
+        };
+        self.notifiers
+            .lock()
+            .unwrap()
+            .insert(notifier.id.clone(), notifier.clone());
+        Ok(notifier)
+    }
+
+    pub fn get_all(&self) -> Vec<Notifier> {
+        let mut notifiers: Vec<Notifier> =
+            self.notifiers.lock().unwrap().values().cloned().collect();
+        notifiers.sort_by_key(|n| n.created_at);
+        notifiers
+    }
+
+    pub fn get_by_id(&self, id: &str) -> Option<Notifier> {
+        self.notifiers.lock().unwrap().get(id).cloned()
+    }
+
+    pub fn delete(&self, id: &str) -> bool {
+        self.notifiers.lock().unwrap().remove(id).is_some()
+    }
+
+    fn wanting(&self, alert: Alert) -> Vec<Notifier> {
+        self.get_all()
+            .into_iter()
+            .filter(|notifier| notifier.alerts.wants(alert))
+            .collect()
+    }
+
+    /// Posts the message `compose` writes for each notifier that wants
+    /// `alert`, returning how many were delivered. Failures are logged,
+    /// not retried.
+    async fn broadcast(&self, alert: Alert, compose: impl Fn(&Notifier) -> String) -> usize {
+        let mut delivered = 0;
+        for notifier in self.wanting(alert) {
+            match notifier.post(&compose(&notifier)).await {
+                Ok(()) => delivered += 1,
+                Err(e) => eprintln!("Notifier {} failed: {}", notifier.id, e),
+            }
+        }
+        delivered
+    }
+
+    /// Announces a newly created high priority todo.
+    pub async fn announce(&self, todo: &Todo) -> usize {
+        self.broadcast(Alert::HighPriority, |notifier| {
+            notifier.created_message(todo)
+        })
+        .await
+    }
+
+    /// Posts one alert per todo that became overdue.
+    pub async fn alert_overdue(&self, todos: &[&Todo]) -> usize {
+        let mut delivered = 0;
+        for todo in todos {
+            delivered += self
+                .broadcast(Alert::Overdue, |notifier| notifier.overdue_message(todo))
+                .await;
+        }
+        delivered
+    }
+
+    /// Posts the agenda for `today`.
+    pub async fn post_summary(&self, todos: &[Todo], today: NaiveDate) -> usize {
+        let agenda = Agenda::build(todos, today, digest::DEFAULT_DAYS);
+        self.broadcast(Alert::DailySummary, |notifier| {
+            notifier.summary_message(&agenda)
+        })
+        .await
+    }
+
+    /// Posts each user's week as a message of its own.
+    pub async fn post_weekly(&self, summaries: &[WeeklySummary]) -> usize {
+        let mut delivered = 0;
+        for summary in summaries {
+            delivered += self
+                .broadcast(Alert::WeeklySummary, |notifier| {
+                    notifier.weekly_message(summary)
+                })
+                .await;
+        }
+        delivered
+    }
+}
+
+impl Default for NotifierService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Announces high priority todos as they are created. Runs on the actix
+/// runtime for the lifetime of the server.
+pub async fn run_listener(
+    notifiers: web::Data<NotifierService>,
+    mut receiver: broadcast::Receiver<Event>,
+) {
+    loop {
+        let event = match receiver.recv().await {
+            Ok(event) => event,
+            Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                eprintln!("Notifier listener lagged, skipped {} events", skipped);
+                continue;
+            }
+            Err(broadcast::error::RecvError::Closed) => break,
+        };
+        if event.event_type == EventType::Created && event.todo.priority == Priority::High {
+            notifiers.announce(&event.todo).await;
+        }
+    }
+}
+
+/// Posts overdue alerts every minute, for todos that became overdue since
+/// the last check, the summary after every UTC midnight and the weekly
+/// summaries after Monday's. As with reminders, nothing is sent late for
+/// time the server was down, and summaries are not repeated on restart.
+pub fn schedule(
+    scheduler: &mut Scheduler,
+    notifiers: web::Data<NotifierService>,
+    service: web::Data<TodoService>,
+    preferences: web::Data<PreferenceStore>,
+) {
+    let checked_until: Rc<Cell<NaiveDateTime>> = Rc::new(Cell::new(Utc::now().naive_utc()));
+    let (overdue_notifiers, overdue_service) = (notifiers.clone(), service.clone());
+    scheduler.register_exclusive("chat-overdue", Schedule::Every(CHECK_INTERVAL), move || {
+        let (notifiers, service) = (overdue_notifiers.clone(), overdue_service.clone());
+        let checked_until = checked_until.clone();
+        async move {
+            let now = Utc::now().naive_utc();
+            let since = checked_until.replace(now);
+            if notifiers.wanting(Alert::Overdue).is_empty() {
+                return Ok(Outcome::Skipped);
+            }
+            let todos = service.get_all(None, None, None);
+            let overdue = reminders::overdue_between(&todos, since, now);
+            if overdue.is_empty() {
+                return Ok(Outcome::Skipped);
+            }
+            notifiers.alert_overdue(&overdue).await;
+            Ok(Outcome::Done)
+        }
+    });
+
+    let posted_on = Rc::new(Cell::new(Utc::now().date_naive()));
+    let (summary_notifiers, summary_service) = (notifiers.clone(), service.clone());
+    scheduler.register_exclusive("chat-summary", Schedule::Daily, move || {
+        let (notifiers, service) = (summary_notifiers.clone(), summary_service.clone());
+        let posted_on = posted_on.clone();
+        async move {
+            let today = Utc::now().date_naive();
+            if posted_on.replace(today) == today {
+                return Ok(Outcome::Skipped);
+            }
+            let todos = service.get_all(None, None, None);
+            notifiers.post_summary(&todos, today).await;
+            Ok(Outcome::Done)
+        }
+    });
+
+    let posted_on = Rc::new(Cell::new(Utc::now().date_naive()));
+    scheduler.register_exclusive("chat-weekly", Schedule::Daily, move || {
+        let (notifiers, service) = (notifiers.clone(), service.clone());
+        let (preferences, posted_on) = (preferences.clone(), posted_on.clone());
+        async move {
+            let today = Utc::now().date_naive();
+            if posted_on.replace(today) == today || today.weekday() != Weekday::Mon {
+                return Ok(Outcome::Skipped);
+            }
+            if notifiers.wanting(Alert::WeeklySummary).is_empty() {
+                return Ok(Outcome::Skipped);
+            }
+            let unsubscribed = preferences.muted(Channel::WeeklySummary);
+            let summaries: Vec<WeeklySummary> = service
+                .weekly_recipients()
+                .into_iter()
+                .filter(|user| !unsubscribed.contains(user))
+                .map(|user| service.weekly_summary(&user, today))
+                .filter(|summary| !summary.is_empty())
+                .collect();
+            notifiers.post_weekly(&summaries).await;
+            Ok(Outcome::Done)
+        }
+    });
+}
+
+pub struct NotifiersPlugin;
+
+impl Plugin for NotifiersPlugin {
+    fn name(&self) -> &'static str {
+        "notifiers"
+    }
+
+    fn kind(&self) -> Kind {
+        Kind::Notifier
+    }
+
+    fn description(&self) -> &'static str {
+        "Posts new, overdue, daily and weekly summary messages to Slack or Discord"
+    }
+
+    fn enabled(&self, _config: &Config) -> bool {
+        true
+    }
+
+    fn details(&self, _config: &Config) -> Option<serde_json::Value> {
+        Some(serde_json::json!({ "services": ["slack", "discord"] }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create(
+        service: &NotifierService,
+        kind: NotifierKind,
+        templates: Option<Templates>,
+    ) -> Result<Notifier, String> {
+        service.create(NotifierCreate {
+            kind,
+            workspace: "acme".to_string(),
+            url: "https://hooks.example.com/T000/B000".to_string(),
+            alerts: None,
+            templates,
+        })
+    }
+
+    #[test]
+    fn test_render_templates() {
+        let values = [("text", "Pay rent".to_string()), ("dueDate", String::new())];
+        assert_eq!(
+            render("Due: {text} {dueDate}!", &values).unwrap(),
+            "Due: Pay rent !"
+        );
+        assert_eq!(render("{text} {", &values).unwrap(), "Pay rent {");
+        assert_eq!(
+            render("{txet}", &values).unwrap_err(),
+            "unknown placeholder {txet}"
+        );
+
+        let service = NotifierService::new();
+        let templates: Templates =
+            serde_json::from_value(serde_json::json!({ "overdue": "Late: {text} {when}" }))
+                .unwrap();
+        assert_eq!(templates.created, default_created());
+        assert_eq!(
+            create(&service, NotifierKind::Slack, Some(templates)).unwrap_err(),
+            "templates.overdue: unknown placeholder {when}"
+        );
+        let summary = Templates {
+            summary: "{dueToday} due, {overdue} overdue on {date}".to_string(),
+            ..Templates::default()
+        };
+        assert!(create(&service, NotifierKind::Slack, Some(summary)).is_ok());
+        let created = Templates {
+            created: "{agenda}".to_string(),
+            ..Templates::default()
+        };
+        assert!(create(&service, NotifierKind::Slack, Some(created)).is_err());
+    }
+
+    #[test]
+    fn test_payloads_escape_and_truncate() {
+        assert_eq!(
+            NotifierKind::Slack.escape("Fix <script> & deploy"),
+            "Fix &lt;script&gt; &amp; deploy"
+        );
+        assert_eq!(NotifierKind::Discord.escape("a & b"), "a & b");
+
+        let long = "x".repeat(DISCORD_MAX_CHARS + 10);
+        let content = NotifierKind::Discord.payload(&long)["content"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        assert_eq!(content.chars().count(), DISCORD_MAX_CHARS);
+        assert!(content.ends_with('…'));
+        assert_eq!(NotifierKind::Slack.payload("hi")["text"], "hi");
+    }
+}
@@ -0,0 +1,466 @@
+use crate::config::{OidcProviderSettings, OidcSettings};
+use actix_web::http::header;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Providers with built-in endpoints, configured by client id and secret.
+pub const PROVIDERS: &[&str] = &["google", "github"];
+/// Session key holding a sign-in between the redirect and the callback.
+pub const PENDING_KEY: &str = "oidc";
+const EXCHANGE_TIMEOUT: Duration = Duration::from_secs(10);
+/// Escaped in query values: everything but RFC 3986's unreserved characters.
+const QUERY_VALUE: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
+
+/// The registration of provider `name`, if it is one of [`PROVIDERS`].
+pub fn builtin(
+    name: &str,
+    client_id: String,
+    client_secret: String,
+) -> Option<OidcProviderSettings> {
+    let (authorize_url, token_url, userinfo_url, scope) = match name {
+        "google" => (
+            "https://accounts.google.com/o/oauth2/v2/auth",
+            "https://oauth2.googleapis.com/token",
+            "https://openidconnect.googleapis.com/v1/userinfo",
+            "openid email",
+        ),
+        "github" => (
+            "https://github.com/login/oauth/authorize",
+            "https://github.com/login/oauth/access_token",
+            "https://api.github.com/user",
+            "read:user user:email",
+        ),
+        _ => return None,
+    };
+    Some(OidcProviderSettings {
+        client_id,
+        client_secret,
+        authorize_url: authorize_url.to_string(),
+        token_url: token_url.to_string(),
+        userinfo_url: userinfo_url.to_string(),
+        scope: scope.to_string(),
+    })
+}
+
+/// A sign-in under way, kept in the session until the provider sends the
+/// browser back.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PendingLogin {
+    pub provider: String,
+    pub state: String,
+    /// The PKCE verifier; the provider was only sent its hash.
+    pub verifier: String,
+}
+
+/// Who the provider says signed in. `email` is only set when the provider
+/// has verified it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Identity {
+    pub subject: String,
+    pub email: Option<String>,
+}
+
+/// A provider identity and the user it signs in as.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LinkedAccount {
+    pub provider: String,
+    pub subject: String,
+    pub user: String,
+    #[serde(rename = "linkedAt")]
+    pub linked_at: DateTime<Utc>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum LoginError {
+    /// The identity is not linked and may not create an account.
+    Refused,
+    /// The account it would create is someone else's name.
+    Conflict,
+    /// The provider could not be reached or did not accept the code.
+    Provider(String),
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: Option<String>,
+    error: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct UserInfo {
+    sub: String,
+    email: Option<String>,
+    #[serde(default)]
+    email_verified: bool,
+}
+
+#[derive(Deserialize)]
+struct GitHubUser {
+    id: u64,
+}
+
+#[derive(Deserialize)]
+struct GitHubEmail {
+    email: String,
+    primary: bool,
+    verified: bool,
+}
+
+/// Sign-in with the configured providers, and the identities linked to
+/// users so far.
+pub struct Oidc {
+    settings: Option<OidcSettings>,
+    linked: Mutex<Vec<LinkedAccount>>,
+}
+
+impl Oidc {
+    pub fn from_settings(settings: Option<&OidcSettings>) -> Result<Self, String> {
+        let path = settings.and_then(|settings| settings.accounts_file.as_deref());
+        let linked = match path {
+            Some(path) if path.exists() => load_accounts(path)?,
+            _ => Vec::new(),
+        };
+        Ok(Oidc {
+            settings: settings.cloned(),
+            linked: Mutex::new(linked),
+        })
+    }
+
+    /// Whether any provider is configured.
+    pub fn is_enabled(&self) -> bool {
+        self.settings
+            .as_ref()
+            .is_some_and(|settings| !settings.providers.is_empty())
+    }
+
+    pub fn post_login_url(&self) -> &str {
+        self.settings
+            .as_ref()
+            .map_or("/", |settings| settings.post_login_url.as_str())
+    }
+
+    fn provider(&self, name: &str) -> Option<(&OidcSettings, &OidcProviderSettings)> {
+        let settings = self.settings.as_ref()?;
+        Some((settings, settings.providers.get(name)?))
+    }
+
+    /// Begins signing in with `provider`: the login to keep in the session
+    /// and the provider URL to send the browser to. `None` when no such
+    /// provider is configured.
+    pub fn start(&self, provider: &str) -> Option<(PendingLogin, String)> {
+        let (settings, registration) = self.provider(provider)?;
+        let pending = PendingLogin {
+            provider: provider.to_string(),
+            state: random_token(),
+            verifier: random_token(),
+        };
+        let challenge = URL_SAFE_NO_PAD.encode(Sha256::digest(pending.verifier.as_bytes()));
+        let query = [
+            ("response_type", "code"),
+            ("client_id", registration.client_id.as_str()),
+            ("redirect_uri", settings.redirect_url.as_str()),
+            ("scope", registration.scope.as_str()),
+            ("state", pending.state.as_str()),
+            ("code_challenge", challenge.as_str()),
+            ("code_challenge_method", "S256"),
+        ]
+        .iter()
+        .map(|(key, value)| format!("{}={}", key, utf8_percent_encode(value, QUERY_VALUE)))
+        .collect::<Vec<_>>()
+        .join("&");
+        let separator = if registration.authorize_url.contains('?') {
+            '&'
+        } else {
+            '?'
+        };
+        let url = format!("{}{}{}", registration.authorize_url, separator, query);
+        Some((pending, url))
+    }
+
+    /// Trades the callback's `code` for an access token and asks the
+    /// provider who it belongs to.
+    pub async fn identify(
+        &self,
+        pending: &PendingLogin,
+        code: &str,
+    ) -> Result<Identity, LoginError> {
+        let name = pending.provider.as_str();
+        let (settings, registration) = self
+            .provider(name)
+            .ok_or_else(|| LoginError::Provider(format!("{} is not configured", name)))?;
+        let failed = |e: &dyn std::fmt::Display| LoginError::Provider(format!("{}: {}", name, e));
+        let client = awc::Client::builder().timeout(EXCHANGE_TIMEOUT).finish();
+        let token: TokenResponse = client
+            .post(&registration.token_url)
+            .insert_header((header::ACCEPT, "application/json"))
+            .send_form(&[
+                ("grant_type", "authorization_code"),
+                ("code", code),
+                ("redirect_uri", settings.redirect_url.as_str()),
+                ("client_id", registration.client_id.as_str()),
+                ("client_secret", registration.client_secret.as_str()),
+                ("code_verifier", pending.verifier.as_str()),
+            ])
+            .await
+            .map_err(|e| failed(&e))?
+            .json()
+            .await
+            .map_err(|e| failed(&e))?;
+        let access_token = token.access_token.ok_or_else(|| {
+            let error = token.error.unwrap_or_else(|| "no access token".to_string());
+            failed(&error)
+        })?;
+
+        let get = |url: &str| {
+            client
+                .get(url)
+                .bearer_auth(&access_token)
+                .insert_header((header::ACCEPT, "application/json"))
+                .insert_header((header::USER_AGENT, "spicy-todo"))
+                .send()
+        };
+        if name != "github" {
+            let info: UserInfo = get(&registration.userinfo_url)
+                .await
+                .map_err(|e| failed(&e))?
+                .json()
+                .await
+                .map_err(|e| failed(&e))?;
+            return Ok(Identity {
+                subject: info.sub,
+                email: info.email.filter(|_| info.email_verified),
+            });
+        }
+        // GitHub is OAuth2 only: the user's id, then their verified
+        // primary address, which `/user` leaves out when it is private.
+        let user: GitHubUser = get(&registration.userinfo_url)
+            .await
+            .map_err(|e| failed(&e))?
+            .json()
+            .await
+            .map_err(|e| failed(&e))?;
+        let emails: Vec<GitHubEmail> = get(&format!("{}/emails", registration.userinfo_url))
+            .await
+            .map_err(|e| failed(&e))?
+            .json()
+            .await
+            .map_err(|e| failed(&e))?;
+        Ok(Identity {
+            subject: user.id.to_string(),
+            email: emails
+                .into_iter()
+                .find(|email| email.primary && email.verified)
+                .map(|email| email.email),
+        })
+    }
+
+    /// The user `identity` signs in as. An identity seen before signs in as
+    /// the user it was linked to; otherwise it is linked to `signed_in`, the
+    /// user already in the session, or else gets an account named after its
+    /// verified email if that is in an allowed domain and `is_user` does not
+    /// know the name.
+    pub fn account_for(
+        &self,
+        provider: &str,
+        identity: &Identity,
+        signed_in: Option<&str>,
+        is_user: impl Fn(&str) -> bool,
+    ) -> Result<String, LoginError> {
+        let mut linked = self.linked.lock().unwrap();
+        if let Some(account) = linked
+            .iter()
+            .find(|account| account.provider == provider && account.subject == identity.subject)
+        {
+            return Ok(account.user.clone());
+        }
+
+        let user = match signed_in {
+            Some(user) => user.to_string(),
+            None => {
+                let email = identity
+                    .email
+                    .as_deref()
+                    .map(str::to_lowercase)
+                    .ok_or(LoginError::Refused)?;
+                let domain = email.rsplit_once('@').map_or("", |(_, domain)| domain);
+                let allowed = self.settings.as_ref().is_some_and(|settings| {
+                    settings
+                        .allowed_domains
+                        .iter()
+                        .any(|allowed| allowed == domain)
+                });
+                if !allowed {
+                    return Err(LoginError::Refused);
+                }
+                if is_user(&email) || linked.iter().any(|account| account.user == email) {
+                    return Err(LoginError::Conflict);
+                }
+                email
+            }
+        };
+        linked.push(LinkedAccount {
+            provider: provider.to_string(),
+            subject: identity.subject.clone(),
+            user: user.clone(),
+            linked_at: Utc::now(),
+        });
+        self.save(&linked);
+        Ok(user)
+    }
+
+    /// The identities linked to `user`.
+    pub fn linked_to(&self, user: &str) -> Vec<LinkedAccount> {
+        let linked = self.linked.lock().unwrap();
+        linked
+            .iter()
+            .filter(|account| account.user == user)
+            .cloned()
+            .collect()
+    }
+
+    /// Unlinks every identity of `user`. Returns how many there were.
+    pub fn unlink(&self, user: &str) -> usize {
+        let mut linked = self.linked.lock().unwrap();
+        let before = linked.len();
+        linked.retain(|account| account.user != user);
+        let removed = before - linked.len();
+        if removed > 0 {
+            self.save(&linked);
+        }
+        removed
+    }
+
+    fn save(&self, linked: &[LinkedAccount]) {
+        let path = self
+            .settings
+            .as_ref()
+            .and_then(|settings| settings.accounts_file.as_deref());
+        if let Some(path) = path {
+            if let Err(e) = save_accounts(path, linked) {
+                eprintln!("⚠️ Could not save linked accounts: {}", e);
+            }
+        }
+    }
+}
+
+fn random_token() -> String {
+    URL_SAFE_NO_PAD.encode(rand::random::<[u8; 32]>())
+}
+
+fn load_accounts(path: &Path) -> Result<Vec<LinkedAccount>, String> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| format!("Cannot read {}: {}", path.display(), e))?;
+    serde_json::from_str(&text).map_err(|e| format!("Invalid {}: {}", path.display(), e))
+}
+
+fn save_accounts(path: &Path, linked: &[LinkedAccount]) -> std::io::Result<()> {
+    let tmp_path: PathBuf = path.with_extension("tmp");
+    std::fs::write(&tmp_path, serde_json::to_vec_pretty(linked)?)?;
+    std::fs::rename(tmp_path, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    fn oidc(allowed_domains: &[&str]) -> Oidc {
+        let google = builtin("google", "client".into(), "secret".into()).unwrap();
+        let settings = OidcSettings {
+            redirect_url: "https://todo.example.com/api/auth/callback".to_string(),
+            providers: BTreeMap::from([("google".to_string(), google)]),
+            allowed_domains: allowed_domains.iter().map(|d| d.to_string()).collect(),
+            accounts_file: None,
+            post_login_url: "/".to_string(),
+        };
+        Oidc::from_settings(Some(&settings)).unwrap()
+    }
+
+    fn identity(subject: &str, email: Option<&str>) -> Identity {
+        Identity {
+            subject: subject.to_string(),
+            email: email.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_start_sends_state_and_pkce_challenge() {
+        let oidc = oidc(&[]);
+        assert!(oidc.start("github").is_none());
+        let (pending, url) = oidc.start("google").unwrap();
+        assert!(url.starts_with("https://accounts.google.com/o/oauth2/v2/auth?response_type=code"));
+        assert!(url.contains("&client_id=client&"));
+        assert!(url.contains("redirect_uri=https%3A%2F%2Ftodo.example.com%2Fapi%2Fauth%2Fcallback"));
+        assert!(url.contains(&format!("&state={}&", pending.state)));
+        let challenge = URL_SAFE_NO_PAD.encode(Sha256::digest(pending.verifier.as_bytes()));
+        assert!(url.contains(&format!("code_challenge={}&", challenge)));
+        assert!(!url.contains(&pending.verifier));
+    }
+
+    #[test]
+    fn test_creates_or_links_accounts() {
+        let oidc = oidc(&["example.com"]);
+        let nobody = |_: &str| false;
+        assert_eq!(
+            oidc.account_for(
+                "google",
+                &identity("1", Some("Ann@Example.com")),
+                None,
+                nobody
+            ),
+            Ok("ann@example.com".to_string())
+        );
+        // Known identities keep their user, whoever is signed in.
+        assert_eq!(
+            oidc.account_for("google", &identity("1", None), Some("bob"), nobody),
+            Ok("ann@example.com".to_string())
+        );
+        assert_eq!(
+            oidc.account_for(
+                "google",
+                &identity("2", Some("eve@elsewhere.org")),
+                None,
+                nobody
+            ),
+            Err(LoginError::Refused)
+        );
+        assert_eq!(
+            oidc.account_for("google", &identity("3", None), None, nobody),
+            Err(LoginError::Refused)
+        );
+        assert_eq!(
+            oidc.account_for(
+                "google",
+                &identity("4", Some("al@example.com")),
+                None,
+                |name| { name == "al@example.com" }
+            ),
+            Err(LoginError::Conflict)
+        );
+        // Signed in, anyone can link their own identity.
+        assert_eq!(
+            oidc.account_for(
+                "github",
+                &identity("2", Some("eve@elsewhere.org")),
+                Some("eve"),
+                nobody
+            ),
+            Ok("eve".to_string())
+        );
+        assert_eq!(
+            oidc.account_for("github", &identity("2", None), None, nobody),
+            Ok("eve".to_string())
+        );
+    }
+}
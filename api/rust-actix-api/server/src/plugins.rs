@@ -0,0 +1,208 @@
+use crate::config::{Config, StorageBackend};
+use crate::{
+    backups, caldav, email, homeassistant, importer, matrix, moderation, notifiers, push, scripts,
+    sms, suggestions, telegram, transfer, webhooks, webpush,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// What a plugin extends.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Kind {
+    /// Where todos are kept.
+    Storage,
+    /// Sends reminders and alerts out.
+    Notifier,
+    /// Brings todos in from elsewhere.
+    Importer,
+    /// Manages todos from a chat app.
+    Chat,
+    /// Keeps todos in step with another app or instance.
+    Sync,
+    Backup,
+    /// Reacts to todo events with user-defined behaviour.
+    Automation,
+    /// Suggests changes to todos.
+    Assistant,
+    /// Screens todo text before it is saved.
+    Moderation,
+}
+
+impl Kind {
+    pub const ALL: [Kind; 9] = [
+        Kind::Storage,
+        Kind::Notifier,
+        Kind::Importer,
+        Kind::Chat,
+        Kind::Sync,
+        Kind::Backup,
+        Kind::Automation,
+        Kind::Assistant,
+        Kind::Moderation,
+    ];
+}
+
+/// An integration. Each one describes itself here and is registered in
+/// `PluginRegistry::builtin`, so `GET /api/plugins` lists everything the
+/// server can talk to and what this deployment turned on.
+pub trait Plugin: Send + Sync {
+    fn name(&self) -> &'static str;
+    fn kind(&self) -> Kind;
+    fn description(&self) -> &'static str;
+    /// The cargo feature the plugin needs, for ones left out of default
+    /// builds.
+    fn feature(&self) -> Option<&'static str> {
+        None
+    }
+    /// Whether this build includes the plugin's feature.
+    fn compiled(&self) -> bool {
+        true
+    }
+    /// Whether `config` turns the plugin on.
+    fn enabled(&self, config: &Config) -> bool;
+    /// Settings worth showing, never secrets.
+    fn details(&self, _config: &Config) -> Option<Value> {
+        None
+    }
+}
+
+/// One entry of `GET /api/plugins`.
+#[derive(Debug, Clone, Serialize)]
+pub struct PluginInfo {
+    pub name: &'static str,
+    pub kind: Kind,
+    pub description: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub feature: Option<&'static str>,
+    pub compiled: bool,
+    /// Compiled in and enabled by the configuration.
+    pub loaded: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub details: Option<Value>,
+}
+
+/// Query of `GET /api/plugins`.
+#[derive(Debug, Default, Deserialize)]
+pub struct PluginsQuery {
+    pub kind: Option<Kind>,
+    pub loaded: Option<bool>,
+}
+
+#[derive(Default)]
+pub struct PluginRegistry {
+    plugins: Vec<Box<dyn Plugin>>,
+}
+
+impl PluginRegistry {
+    pub fn new() -> Self {
+        PluginRegistry::default()
+    }
+
+    /// Every integration that ships with the server.
+    pub fn builtin() -> Self {
+        let mut registry = PluginRegistry::new();
+        let plugins: Vec<Box<dyn Plugin>> = vec![
+            Box::new(StorageBackend::Memory),
+            Box::new(StorageBackend::Journal),
+            Box::new(StorageBackend::Snapshot),
+            Box::new(StorageBackend::EventStore),
+            Box::new(backups::BackupsPlugin),
+            Box::new(push::PushPlugin),
+            Box::new(webpush::WebPushPlugin),
+            Box::new(sms::SmsPlugin),
+            Box::new(notifiers::NotifiersPlugin),
+            Box::new(webhooks::WebhooksPlugin),
+            Box::new(matrix::MatrixPlugin),
+            Box::new(telegram::TelegramPlugin),
+            Box::new(email::EmailPlugin),
+            Box::new(importer::SpicyImportPlugin),
+            Box::new(caldav::CaldavPlugin),
+            Box::new(transfer::TransferPlugin),
+            Box::new(homeassistant::HomeAssistantPlugin),
+            Box::new(scripts::ScriptsPlugin),
+            Box::new(suggestions::SuggestionsPlugin),
+            Box::new(moderation::ModerationPlugin),
+        ];
+        for plugin in plugins {
+            registry
+                .register(plugin)
+                .expect("built-in plugin names are unique");
+        }
+        registry
+    }
+
+    /// Adds `plugin`, unless one with its name is already registered.
+    pub fn register(&mut self, plugin: Box<dyn Plugin>) -> Result<(), String> {
+        if self
+            .plugins
+            .iter()
+            .any(|known| known.name() == plugin.name())
+        {
+            return Err(format!("Plugin '{}' is registered twice", plugin.name()));
+        }
+        self.plugins.push(plugin);
+        Ok(())
+    }
+
+    /// The registered plugins in registration order, as they stand with
+    /// `config`.
+    pub fn describe(&self, config: &Config) -> Vec<PluginInfo> {
+        self.plugins
+            .iter()
+            .map(|plugin| PluginInfo {
+                name: plugin.name(),
+                kind: plugin.kind(),
+                description: plugin.description(),
+                feature: plugin.feature(),
+                compiled: plugin.compiled(),
+                loaded: plugin.compiled() && plugin.enabled(config),
+                details: plugin.details(config),
+            })
+            .collect()
+    }
+
+    /// Names of the plugins `config` loads.
+    pub fn loaded(&self, config: &Config) -> Vec<&'static str> {
+        self.describe(config)
+            .into_iter()
+            .filter(|plugin| plugin.loaded)
+            .map(|plugin| plugin.name)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_plugins_follow_config() {
+        let registry = PluginRegistry::builtin();
+        let config = Config::default();
+        let loaded = registry.loaded(&config);
+        assert!(loaded.contains(&"memory"));
+        assert!(loaded.contains(&"caldav"));
+        assert!(!loaded.contains(&"journal"));
+        assert!(!loaded.contains(&"telegram"));
+        assert!(!loaded.contains(&"backups"));
+
+        let config = Config {
+            journal_dir: Some("/tmp/journal".into()),
+            ..Config::default()
+        };
+        let loaded = registry.loaded(&config);
+        assert!(loaded.contains(&"journal"));
+        assert!(!loaded.contains(&"memory"));
+
+        let kinds: Vec<Kind> = registry.describe(&config).iter().map(|p| p.kind).collect();
+        assert!(Kind::ALL.iter().all(|kind| kinds.contains(kind)));
+    }
+
+    #[test]
+    fn test_register_rejects_duplicates() {
+        let mut registry = PluginRegistry::new();
+        registry.register(Box::new(caldav::CaldavPlugin)).unwrap();
+        assert!(registry.register(Box::new(caldav::CaldavPlugin)).is_err());
+    }
+}
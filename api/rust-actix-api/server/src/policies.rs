@@ -0,0 +1,228 @@
+use crate::scheduler::{Outcome, Schedule, Scheduler};
+use actix_web::web;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use spicy_todo_core::policy::PolicyRule;
+use spicy_todo_core::TodoService;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use uuid::Uuid;
+
+const MAX_POLICIES: usize = 50;
+const MAX_NAME_LEN: usize = 100;
+
+/// The last time the policy job applied a policy.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct PolicyRun {
+    pub at: DateTime<Utc>,
+    /// Todos it changed or deleted.
+    pub affected: usize,
+}
+
+/// A named rule the policy job applies to every todo, such as "delete
+/// completed todos after 30 days".
+#[derive(Debug, Clone, Serialize)]
+pub struct Policy {
+    pub id: String,
+    pub name: String,
+    #[serde(flatten)]
+    pub rule: PolicyRule,
+    pub enabled: bool,
+    #[serde(rename = "createdAt")]
+    pub created_at: DateTime<Utc>,
+    #[serde(rename = "lastRun")]
+    pub last_run: Option<PolicyRun>,
+}
+
+/// Body of both creating and replacing a policy.
+#[derive(Debug, Deserialize)]
+pub struct PolicyCreate {
+    pub name: String,
+    #[serde(flatten)]
+    pub rule: PolicyRule,
+    #[serde(default = "enabled_by_default")]
+    pub enabled: bool,
+}
+
+fn enabled_by_default() -> bool {
+    true
+}
+
+impl PolicyCreate {
+    fn validate(&self) -> Result<String, String> {
+        let name = self.name.trim();
+        if name.is_empty() || name.chars().count() > MAX_NAME_LEN {
+            return Err(format!(
+                "Policy name must be 1 to {} characters",
+                MAX_NAME_LEN
+            ));
+        }
+        self.rule.validate()?;
+        Ok(name.to_string())
+    }
+}
+
+pub struct PolicyStore {
+    policies: Mutex<HashMap<String, Policy>>,
+}
+
+impl PolicyStore {
+    pub fn new() -> Self {
+        PolicyStore {
+            policies: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn create(&self, input: PolicyCreate) -> Result<Policy, String> {
+        let name = input.validate()?;
+        let mut policies = self.policies.lock().unwrap();
+        if policies.len() >= MAX_POLICIES {
+            return Err(format!("At most {} policies can be saved", MAX_POLICIES));
+        }
+        let policy = Policy {
+            id: Uuid::new_v4().to_string(),
+            name,
+            rule: input.rule,
+            enabled: input.enabled,
+            created_at: Utc::now(),
+            last_run: None,
+        };
+        policies.insert(policy.id.clone(), policy.clone());
+        Ok(policy)
+    }
+
+    /// Oldest first.
+    pub fn get_all(&self) -> Vec<Policy> {
+        let mut policies: Vec<Policy> = self.policies.lock().unwrap().values().cloned().collect();
+        policies.sort_by_key(|policy| policy.created_at);
+        policies
+    }
+
+    pub fn get_by_id(&self, id: &str) -> Option<Policy> {
+        self.policies.lock().unwrap().get(id).cloned()
+    }
+
+    /// Replaces a policy's name, rule and whether it is enabled; `Ok(None)`
+    /// when there is no such policy.
+    pub fn replace(&self, id: &str, input: PolicyCreate) -> Result<Option<Policy>, String> {
+        let name = input.validate()?;
+        let mut policies = self.policies.lock().unwrap();
+        let Some(policy) = policies.get_mut(id) else {
+            return Ok(None);
+        };
+        policy.name = name;
+        policy.rule = input.rule;
+        policy.enabled = input.enabled;
+        Ok(Some(policy.clone()))
+    }
+
+    pub fn delete(&self, id: &str) -> bool {
+        self.policies.lock().unwrap().remove(id).is_some()
+    }
+
+    fn record_run(&self, id: &str, run: PolicyRun) {
+        if let Some(policy) = self.policies.lock().unwrap().get_mut(id) {
+            policy.last_run = Some(run);
+        }
+    }
+}
+
+impl Default for PolicyStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Applies every enabled policy, oldest first, and notes each run on its
+/// policy. Returns how many todos the policies changed in all.
+pub fn run_policies(policies: &PolicyStore, service: &TodoService) -> usize {
+    let mut total = 0;
+    for policy in policies
+        .get_all()
+        .into_iter()
+        .filter(|policy| policy.enabled)
+    {
+        let at = Utc::now();
+        let affected = service.apply_policy(&policy.rule, at);
+        policies.record_run(&policy.id, PolicyRun { at, affected });
+        total += affected;
+    }
+    total
+}
+
+/// Applies the policies at startup and then after every UTC midnight; their
+/// waits are in days, so a finer schedule would find nothing new.
+pub fn schedule(
+    scheduler: &mut Scheduler,
+    policies: web::Data<PolicyStore>,
+    service: web::Data<TodoService>,
+) {
+    scheduler.register_exclusive("policies", Schedule::Daily, move || {
+        let affected = run_policies(&policies, &service);
+        if affected > 0 {
+            println!("🧹 Policies changed {} todos", affected);
+        }
+        let outcome = if affected > 0 {
+            Outcome::Done
+        } else {
+            Outcome::Skipped
+        };
+        async move { Ok(outcome) }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spicy_todo_core::models::TodoCreate;
+    use spicy_todo_core::policy::PolicyAction;
+
+    fn input(name: &str, after_days: u32, enabled: bool) -> PolicyCreate {
+        PolicyCreate {
+            name: name.to_string(),
+            rule: PolicyRule {
+                action: PolicyAction::DeleteCompleted,
+                after_days,
+            },
+            enabled,
+        }
+    }
+
+    #[test]
+    fn test_store_validates() {
+        let store = PolicyStore::new();
+        assert!(store.create(input(" ", 30, true)).is_err());
+        assert!(store.create(input("Tidy up", 0, true)).is_err());
+        let policy = store.create(input("Tidy up", 30, true)).unwrap();
+        let replaced = store
+            .replace(&policy.id, input("Tidy", 7, false))
+            .unwrap()
+            .unwrap();
+        assert_eq!(replaced.rule.after_days, 7);
+        assert!(!replaced.enabled);
+        assert!(store
+            .replace("missing", input("Tidy", 7, false))
+            .unwrap()
+            .is_none());
+        assert!(store.delete(&policy.id));
+        assert!(store.get_all().is_empty());
+    }
+
+    #[test]
+    fn test_runs_only_enabled_policies() {
+        let store = PolicyStore::new();
+        let service = TodoService::new_empty();
+        service.create(TodoCreate {
+            text: "Done".to_string(),
+            completed: Some(true),
+            ..Default::default()
+        });
+        let disabled = store.create(input("Off", 1, false)).unwrap();
+        let enabled = store.create(input("On", 1, true)).unwrap();
+
+        assert_eq!(run_policies(&store, &service), 0);
+        assert!(store.get_by_id(&disabled.id).unwrap().last_run.is_none());
+        let run = store.get_by_id(&enabled.id).unwrap().last_run.unwrap();
+        assert_eq!(run.affected, 0);
+    }
+}
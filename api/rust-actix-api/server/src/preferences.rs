@@ -0,0 +1,391 @@
+use actix_web::HttpRequest;
+use chrono::{DateTime, Duration, NaiveDate, NaiveTime, Utc};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use spicy_todo_core::models::{Priority, SortField, SortOrder, TodoCreate, TodoQuery, WeekStart};
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+
+/// Identifies the client (device, frontend, user) a preference belongs to.
+pub const CLIENT_HEADER: &str = "x-client-id";
+/// Set on list responses that used the client's saved preference.
+pub const APPLIED_HEADER: &str = "x-list-preference";
+const MAX_CLIENT_ID_LEN: usize = 128;
+/// Longest reminder lead time, in minutes: a day before the due date.
+const MAX_REMINDER_LEAD_MINUTES: u32 = 24 * 60;
+
+/// A saved list view: applied to `GET /api/todos` when the client sends none
+/// of these parameters itself.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ListPreference {
+    pub filter: Option<String>,
+    pub priority: Option<String>,
+    pub sort: Option<SortField>,
+    pub order: Option<SortOrder>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SavedPreference {
+    #[serde(flatten)]
+    pub preference: ListPreference,
+    #[serde(rename = "updatedAt")]
+    pub updated_at: DateTime<Utc>,
+}
+
+impl ListPreference {
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(filter) = &self.filter {
+            if !matches!(filter.as_str(), "all" | "active" | "completed") {
+                return Err(format!("Unknown filter '{}'", filter));
+            }
+        }
+        if let Some(priority) = &self.priority {
+            if !matches!(priority.to_lowercase().as_str(), "low" | "medium" | "high") {
+                return Err(format!("Unknown priority '{}'", priority));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn apply_to(&self, query: &mut TodoQuery) {
+        query.filter = self.filter.clone();
+        query.priority = self.priority.clone();
+        query.sort = self.sort;
+        query.order = self.order;
+    }
+}
+
+fn default_timezone() -> String {
+    "UTC".to_string()
+}
+
+fn enabled() -> bool {
+    true
+}
+
+/// Which channels deliver reminders to the client, and whether its user
+/// gets the weekly summary. All are on until turned off.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NotificationSettings {
+    #[serde(default = "enabled")]
+    pub push: bool,
+    #[serde(default = "enabled")]
+    pub sms: bool,
+    /// Off unsubscribes the user named by this client id from the weekly
+    /// summary the chat notifiers post.
+    #[serde(rename = "weeklySummary", default = "enabled")]
+    pub weekly_summary: bool,
+}
+
+impl Default for NotificationSettings {
+    fn default() -> Self {
+        NotificationSettings {
+            push: true,
+            sms: true,
+            weekly_summary: true,
+        }
+    }
+}
+
+/// A client's defaults, as stored by `PUT /api/preferences`. Every field
+/// is optional and falls back to the server's behaviour.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UserPreferences {
+    /// Priority of new todos that don't set one.
+    #[serde(rename = "defaultPriority", default)]
+    pub default_priority: Priority,
+    /// Minutes before the end of the due date at which new todos with a
+    /// due date but no reminder are reminded. 0 sets no reminder.
+    #[serde(rename = "reminderLeadMinutes", default)]
+    pub reminder_lead_minutes: u32,
+    /// IANA time zone name; decides what "today" is for due dates and stats.
+    #[serde(default = "default_timezone")]
+    pub timezone: String,
+    #[serde(rename = "weekStart", default)]
+    pub week_start: WeekStart,
+    #[serde(default)]
+    pub notifications: NotificationSettings,
+}
+
+impl Default for UserPreferences {
+    fn default() -> Self {
+        UserPreferences {
+            default_priority: Priority::default(),
+            reminder_lead_minutes: 0,
+            timezone: default_timezone(),
+            week_start: WeekStart::default(),
+            notifications: NotificationSettings::default(),
+        }
+    }
+}
+
+impl UserPreferences {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.reminder_lead_minutes > MAX_REMINDER_LEAD_MINUTES {
+            return Err(format!(
+                "reminderLeadMinutes must be at most {}",
+                MAX_REMINDER_LEAD_MINUTES
+            ));
+        }
+        self.timezone
+            .parse::<Tz>()
+            .map(|_| ())
+            .map_err(|_| format!("Unknown timezone '{}'", self.timezone))
+    }
+
+    /// The client's time zone, or UTC if it names none known.
+    pub fn tz(&self) -> Tz {
+        self.timezone.parse().unwrap_or(Tz::UTC)
+    }
+
+    /// The date it is at `now` in the client's time zone.
+    pub fn today(&self, now: DateTime<Utc>) -> NaiveDate {
+        now.with_timezone(&self.tz()).date_naive()
+    }
+
+    /// Fills in what a new todo leaves unset: the priority, and a reminder
+    /// `reminderLeadMinutes` before its due date ends. Expects the due date
+    /// to be normalized already.
+    pub fn apply_defaults(&self, todo_create: &mut TodoCreate) {
+        if todo_create.priority.is_none() {
+            todo_create.priority = Some(self.default_priority.clone());
+        }
+        if self.reminder_lead_minutes == 0 || todo_create.reminder_time.is_some() {
+            return;
+        }
+        let due = todo_create
+            .due_date
+            .as_deref()
+            .and_then(|due| NaiveDate::parse_from_str(due, "%Y-%m-%d").ok());
+        if let Some(due) = due {
+            let end_of_day = due.and_time(NaiveTime::MIN) + Duration::days(1);
+            let remind_at = end_of_day - Duration::minutes(self.reminder_lead_minutes.into());
+            todo_create.reminder_time = Some(remind_at.format("%H:%M").to_string());
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SavedUserPreferences {
+    #[serde(flatten)]
+    pub preferences: UserPreferences,
+    /// Unset until the client saves its preferences.
+    #[serde(rename = "updatedAt", skip_serializing_if = "Option::is_none")]
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+/// A kind of notification a client can turn off.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Channel {
+    Push,
+    Sms,
+    WeeklySummary,
+}
+
+/// The client id from `X-Client-Id`, if present and sane.
+pub fn client_id(req: &HttpRequest) -> Result<Option<String>, String> {
+    let value = match req.headers().get(CLIENT_HEADER) {
+        Some(value) => value,
+        None => return Ok(None),
+    };
+    let id = value
+        .to_str()
+        .map(str::trim)
+        .map_err(|_| format!("Invalid {} header", CLIENT_HEADER))?;
+    if id.is_empty() || id.len() > MAX_CLIENT_ID_LEN {
+        return Err(format!(
+            "{} must be 1 to {} characters",
+            CLIENT_HEADER, MAX_CLIENT_ID_LEN
+        ));
+    }
+    Ok(Some(id.to_string()))
+}
+
+pub struct PreferenceStore {
+    preferences: Mutex<HashMap<String, SavedPreference>>,
+    users: Mutex<HashMap<String, SavedUserPreferences>>,
+}
+
+impl PreferenceStore {
+    pub fn new() -> Self {
+        PreferenceStore {
+            preferences: Mutex::new(HashMap::new()),
+            users: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// The client's saved defaults, or the server's when it has none.
+    pub fn user(&self, client_id: &str) -> SavedUserPreferences {
+        self.users
+            .lock()
+            .unwrap()
+            .get(client_id)
+            .cloned()
+            .unwrap_or_else(|| SavedUserPreferences {
+                preferences: UserPreferences::default(),
+                updated_at: None,
+            })
+    }
+
+    pub fn set_user(&self, client_id: &str, preferences: UserPreferences) -> SavedUserPreferences {
+        let saved = SavedUserPreferences {
+            preferences,
+            updated_at: Some(Utc::now()),
+        };
+        self.users
+            .lock()
+            .unwrap()
+            .insert(client_id.to_string(), saved.clone());
+        saved
+    }
+
+    /// Clients that turned `channel` off.
+    pub fn muted(&self, channel: Channel) -> HashSet<String> {
+        self.users
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(_, saved)| {
+                let notifications = &saved.preferences.notifications;
+                match channel {
+                    Channel::Push => !notifications.push,
+                    Channel::Sms => !notifications.sms,
+                    Channel::WeeklySummary => !notifications.weekly_summary,
+                }
+            })
+            .map(|(client, _)| client.clone())
+            .collect()
+    }
+
+    pub fn get(&self, client_id: &str) -> Option<SavedPreference> {
+        self.preferences.lock().unwrap().get(client_id).cloned()
+    }
+
+    pub fn set(&self, client_id: &str, preference: ListPreference) -> SavedPreference {
+        let saved = SavedPreference {
+            preference,
+            updated_at: Utc::now(),
+        };
+        self.preferences
+            .lock()
+            .unwrap()
+            .insert(client_id.to_string(), saved.clone());
+        saved
+    }
+
+    pub fn remove(&self, client_id: &str) -> bool {
+        self.preferences.lock().unwrap().remove(client_id).is_some()
+    }
+
+    /// Drops the client's list preference and saved defaults alike.
+    pub fn forget(&self, client_id: &str) -> bool {
+        let list = self.remove(client_id);
+        let user = self.users.lock().unwrap().remove(client_id).is_some();
+        list || user
+    }
+}
+
+impl Default for PreferenceStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::test::TestRequest;
+
+    #[test]
+    fn test_validate() {
+        let preference = ListPreference {
+            filter: Some("active".to_string()),
+            priority: Some("HIGH".to_string()),
+            ..Default::default()
+        };
+        assert!(preference.validate().is_ok());
+
+        let preference = ListPreference {
+            filter: Some("archived".to_string()),
+            ..Default::default()
+        };
+        assert!(preference.validate().is_err());
+    }
+
+    #[test]
+    fn test_store_round_trip() {
+        let store = PreferenceStore::new();
+        assert!(store.get("phone").is_none());
+        let preference = ListPreference {
+            sort: Some(SortField::DueDate),
+            ..Default::default()
+        };
+        store.set("phone", preference.clone());
+        assert_eq!(store.get("phone").unwrap().preference, preference);
+        assert!(store.get("laptop").is_none());
+        assert!(store.remove("phone"));
+        assert!(!store.remove("phone"));
+    }
+
+    #[test]
+    fn test_user_preferences() {
+        let preferences: UserPreferences = serde_json::from_value(serde_json::json!({
+            "defaultPriority": "high",
+            "reminderLeadMinutes": 120,
+            "timezone": "Pacific/Auckland",
+            "notifications": { "sms": false, "weeklySummary": false }
+        }))
+        .unwrap();
+        assert!(preferences.validate().is_ok());
+        assert_eq!(preferences.week_start, WeekStart::Monday);
+        assert!(preferences.notifications.push);
+
+        // Already the next day in Auckland.
+        let now = DateTime::parse_from_rfc3339("2024-06-10T20:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        assert_eq!(
+            preferences.today(now),
+            NaiveDate::from_ymd_opt(2024, 6, 11).unwrap()
+        );
+
+        let mut todo_create: TodoCreate = serde_json::from_value(serde_json::json!({
+            "text": "Pay rent",
+            "dueDate": "2024-06-14"
+        }))
+        .unwrap();
+        preferences.apply_defaults(&mut todo_create);
+        assert_eq!(todo_create.priority, Some(Priority::High));
+        assert_eq!(todo_create.reminder_time.as_deref(), Some("22:00"));
+
+        let store = PreferenceStore::new();
+        assert!(store.user("phone").updated_at.is_none());
+        store.set_user("phone", preferences);
+        assert_eq!(
+            store.muted(Channel::Sms),
+            HashSet::from(["phone".to_string()])
+        );
+        assert!(store.muted(Channel::Push).is_empty());
+        assert_eq!(store.muted(Channel::WeeklySummary).len(), 1);
+
+        let invalid = UserPreferences {
+            timezone: "Mars/Olympus".to_string(),
+            ..Default::default()
+        };
+        assert!(invalid.validate().is_err());
+    }
+
+    #[test]
+    fn test_client_id() {
+        let req = TestRequest::default().to_http_request();
+        assert_eq!(client_id(&req), Ok(None));
+        let req = TestRequest::default()
+            .insert_header((CLIENT_HEADER, " web-app "))
+            .to_http_request();
+        assert_eq!(client_id(&req), Ok(Some("web-app".to_string())));
+        let req = TestRequest::default()
+            .insert_header((CLIENT_HEADER, "x".repeat(200)))
+            .to_http_request();
+        assert!(client_id(&req).is_err());
+    }
+}
@@ -0,0 +1,200 @@
+use serde::Deserialize;
+#[cfg(feature = "profiling")]
+use std::sync::atomic::{AtomicBool, Ordering};
+
+pub const DEFAULT_SECONDS: u64 = 10;
+pub const MAX_SECONDS: u64 = 60;
+pub const DEFAULT_FREQUENCY: i32 = 99;
+pub const MAX_FREQUENCY: i32 = 1000;
+
+/// Query for `GET /debug/pprof/profile`, mirroring Go's pprof parameters.
+#[derive(Debug, Default, Deserialize)]
+pub struct ProfileQuery {
+    pub seconds: Option<u64>,
+    /// Sampling frequency in Hz.
+    pub frequency: Option<i32>,
+    /// `pprof` (protobuf, default) or `flamegraph` (SVG) for CPU, or
+    /// `heap` for a jemalloc heap profile, read with `jeprof`.
+    pub format: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ProfileFormat {
+    Pprof,
+    Flamegraph,
+    Heap,
+}
+
+impl ProfileFormat {
+    pub fn parse(value: Option<&str>) -> Result<Self, String> {
+        match value.unwrap_or("pprof") {
+            "pprof" | "proto" => Ok(ProfileFormat::Pprof),
+            "flamegraph" | "svg" => Ok(ProfileFormat::Flamegraph),
+            "heap" => Ok(ProfileFormat::Heap),
+            other => Err(format!(
+                "Unknown profile format '{}', expected pprof, flamegraph or heap",
+                other
+            )),
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            ProfileFormat::Pprof | ProfileFormat::Heap => "application/octet-stream",
+            ProfileFormat::Flamegraph => "image/svg+xml",
+        }
+    }
+}
+
+/// Heap profiles need jemalloc, sampling allocations from the start.
+#[cfg(feature = "profiling")]
+#[global_allocator]
+static ALLOCATOR: tikv_jemallocator::Jemalloc = tikv_jemallocator::Jemalloc;
+
+/// Samples every 512 KiB allocated on average, cheap enough to leave on.
+#[cfg(feature = "profiling")]
+#[allow(non_upper_case_globals)]
+#[export_name = "_rjem_malloc_conf"]
+pub static malloc_conf: Option<&'static std::ffi::c_char> =
+    Some(unsafe { &*c"prof:true,prof_active:true,lg_prof_sample:19".as_ptr() });
+
+/// The sampler is process-global, so only one capture may run at a time.
+#[cfg(feature = "profiling")]
+static CAPTURE_RUNNING: AtomicBool = AtomicBool::new(false);
+
+/// Errors a capture can fail with, mapped to HTTP statuses by the handler.
+#[derive(Debug, PartialEq)]
+pub enum CaptureError {
+    #[cfg_attr(feature = "profiling", allow(dead_code))]
+    Unsupported,
+    #[cfg_attr(not(feature = "profiling"), allow(dead_code))]
+    Busy,
+    #[cfg_attr(not(feature = "profiling"), allow(dead_code))]
+    Failed(String),
+}
+
+#[cfg(feature = "profiling")]
+struct RunningCapture;
+
+#[cfg(feature = "profiling")]
+impl RunningCapture {
+    fn acquire() -> Result<Self, CaptureError> {
+        CAPTURE_RUNNING
+            .compare_exchange(false, true, Ordering::AcqRel, Ordering::Acquire)
+            .map(|_| RunningCapture)
+            .map_err(|_| CaptureError::Busy)
+    }
+}
+
+#[cfg(feature = "profiling")]
+impl Drop for RunningCapture {
+    fn drop(&mut self) {
+        CAPTURE_RUNNING.store(false, Ordering::Release);
+    }
+}
+
+/// Samples CPU stacks for `seconds` at `frequency` Hz and encodes the result.
+#[cfg(feature = "profiling")]
+pub async fn capture_cpu(
+    seconds: u64,
+    frequency: i32,
+    format: ProfileFormat,
+) -> Result<Vec<u8>, CaptureError> {
+    use pprof::protos::Message;
+
+    let _running = RunningCapture::acquire()?;
+    let guard = pprof::ProfilerGuardBuilder::default()
+        .frequency(frequency)
+        .blocklist(&["libc", "libgcc", "pthread", "vdso"])
+        .build()
+        .map_err(|e| CaptureError::Failed(e.to_string()))?;
+
+    tokio::time::sleep(std::time::Duration::from_secs(seconds)).await;
+
+    let report = guard
+        .report()
+        .build()
+        .map_err(|e| CaptureError::Failed(e.to_string()))?;
+    let mut body = Vec::new();
+    match format {
+        ProfileFormat::Pprof => {
+            let profile = report
+                .pprof()
+                .map_err(|e| CaptureError::Failed(e.to_string()))?;
+            profile
+                .encode(&mut body)
+                .map_err(|e| CaptureError::Failed(e.to_string()))?;
+        }
+        ProfileFormat::Flamegraph => {
+            report
+                .flamegraph(&mut body)
+                .map_err(|e| CaptureError::Failed(e.to_string()))?;
+        }
+        ProfileFormat::Heap => unreachable!("heap profiles are dumped, not sampled"),
+    }
+    Ok(body)
+}
+
+/// Dumps the sampled live allocations in jemalloc's heap profile format.
+#[cfg(feature = "profiling")]
+pub fn capture_heap() -> Result<Vec<u8>, CaptureError> {
+    let _running = RunningCapture::acquire()?;
+    if !tikv_jemalloc_ctl::profiling::prof::read()
+        .map_err(|e| CaptureError::Failed(e.to_string()))?
+    {
+        return Err(CaptureError::Failed(
+            "jemalloc heap profiling is off".to_string(),
+        ));
+    }
+
+    let path = std::env::temp_dir().join(format!("spicy-todo-{}.heap", std::process::id()));
+    let c_path = std::ffi::CString::new(path.to_string_lossy().into_owned())
+        .map_err(|e| CaptureError::Failed(e.to_string()))?;
+    // SAFETY: `prof.dump` takes a NUL-terminated path, which `c_path`
+    // outlives.
+    unsafe { tikv_jemalloc_ctl::raw::write(b"prof.dump\0", c_path.as_ptr()) }
+        .map_err(|e| CaptureError::Failed(e.to_string()))?;
+    let body = std::fs::read(&path).map_err(|e| CaptureError::Failed(e.to_string()));
+    let _ = std::fs::remove_file(&path);
+    body
+}
+
+/// Builds without the `profiling` feature carry no sampler.
+#[cfg(not(feature = "profiling"))]
+pub async fn capture_cpu(
+    _seconds: u64,
+    _frequency: i32,
+    _format: ProfileFormat,
+) -> Result<Vec<u8>, CaptureError> {
+    Err(CaptureError::Unsupported)
+}
+
+#[cfg(not(feature = "profiling"))]
+pub fn capture_heap() -> Result<Vec<u8>, CaptureError> {
+    Err(CaptureError::Unsupported)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_format() {
+        assert_eq!(ProfileFormat::parse(None), Ok(ProfileFormat::Pprof));
+        assert_eq!(
+            ProfileFormat::parse(Some("svg")),
+            Ok(ProfileFormat::Flamegraph)
+        );
+        assert_eq!(ProfileFormat::parse(Some("heap")), Ok(ProfileFormat::Heap));
+        assert!(ProfileFormat::parse(Some("gif")).is_err());
+    }
+
+    #[cfg(feature = "profiling")]
+    #[test]
+    fn test_only_one_capture_at_a_time() {
+        let first = RunningCapture::acquire().unwrap();
+        assert_eq!(RunningCapture::acquire().err(), Some(CaptureError::Busy));
+        drop(first);
+        assert!(RunningCapture::acquire().is_ok());
+    }
+}
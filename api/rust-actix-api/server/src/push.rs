@@ -0,0 +1,322 @@
+use crate::config::Config;
+use crate::plugins::{Kind, Plugin};
+use serde::{Deserialize, Serialize};
+use spicy_todo_core::models::Priority;
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+use std::time::Duration;
+
+const SEND_TIMEOUT: Duration = Duration::from_secs(10);
+const MAX_TARGETS: usize = 5;
+const MAX_TOPIC_LEN: usize = 64;
+
+fn default_ntfy_server() -> String {
+    "https://ntfy.sh".to_string()
+}
+
+fn default_min_priority() -> Priority {
+    Priority::Low
+}
+
+/// A self-hosted push service to deliver reminders to. Tokens are accepted
+/// but never echoed back.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum PushTarget {
+    /// A topic on ntfy.sh or a self-hosted ntfy server.
+    Ntfy {
+        #[serde(default = "default_ntfy_server")]
+        server: String,
+        topic: String,
+        /// Access token for protected topics.
+        #[serde(default, skip_serializing)]
+        token: Option<String>,
+    },
+    /// A Gotify server, with an application token.
+    Gotify {
+        server: String,
+        #[serde(skip_serializing)]
+        token: String,
+    },
+}
+
+/// A client's push settings.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PushSubscription {
+    pub targets: Vec<PushTarget>,
+    /// Reminders below this priority are not pushed. Defaults to `low`,
+    /// so every reminder is.
+    #[serde(rename = "minPriority", default = "default_min_priority")]
+    pub min_priority: Priority,
+}
+
+impl PushSubscription {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.targets.is_empty() || self.targets.len() > MAX_TARGETS {
+            return Err(format!("targets must list 1 to {} targets", MAX_TARGETS));
+        }
+        for target in &self.targets {
+            match target {
+                PushTarget::Ntfy { server, topic, .. } => {
+                    check_server_url(server)?;
+                    let valid = !topic.is_empty()
+                        && topic.len() <= MAX_TOPIC_LEN
+                        && topic
+                            .bytes()
+                            .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_');
+                    if !valid {
+                        return Err(format!(
+                            "Invalid ntfy topic '{}': use up to {} letters, digits, - or _",
+                            topic, MAX_TOPIC_LEN
+                        ));
+                    }
+                }
+                PushTarget::Gotify { server, token } => {
+                    check_server_url(server)?;
+                    if token.trim().is_empty() {
+                        return Err("Gotify targets need an application token".to_string());
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn check_server_url(server: &str) -> Result<(), String> {
+    let uri: awc::http::Uri = server
+        .trim_end_matches('/')
+        .parse()
+        .map_err(|_| format!("Invalid server URL '{}'", server))?;
+    if !matches!(uri.scheme_str(), Some("http") | Some("https")) || uri.host().is_none() {
+        return Err(format!(
+            "Server URL '{}' must be an absolute http(s) URL",
+            server
+        ));
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Notification {
+    pub title: String,
+    pub message: String,
+    pub priority: Priority,
+}
+
+/// Sends `notification` to one target.
+pub async fn deliver(target: &PushTarget, notification: &Notification) -> Result<(), String> {
+    let client = awc::Client::builder().timeout(SEND_TIMEOUT).finish();
+    let (url, result) = match target {
+        PushTarget::Ntfy {
+            server,
+            topic,
+            token,
+        } => {
+            let url = format!("{}/{}", server.trim_end_matches('/'), topic);
+            let priority = match notification.priority {
+                Priority::Low => "low",
+                Priority::Medium => "default",
+                Priority::High => "high",
+            };
+            let mut request = client
+                .post(&url)
+                .insert_header(("Title", notification.title.as_str()))
+                .insert_header(("Priority", priority));
+            if let Some(token) = token {
+                request = request.bearer_auth(token);
+            }
+            let result = request.send_body(notification.message.clone()).await;
+            (url, result)
+        }
+        PushTarget::Gotify { server, token } => {
+            let url = format!("{}/message", server.trim_end_matches('/'));
+            let priority = match notification.priority {
+                Priority::Low => 2,
+                Priority::Medium => 5,
+                Priority::High => 8,
+            };
+            let result = client
+                .post(&url)
+                .insert_header(("X-Gotify-Key", token.as_str()))
+                .send_json(&serde_json::json!({
+                    "title": notification.title,
+                    "message": notification.message,
+                    "priority": priority
+                }))
+                .await;
+            (url, result)
+        }
+    };
+    let response = result.map_err(|e| format!("Failed to reach {}: {}", url, e))?;
+    if !response.status().is_success() {
+        return Err(format!("{} responded with {}", url, response.status()));
+    }
+    Ok(())
+}
+
+/// Push settings by client id, as sent in `X-Client-Id`.
+pub struct PushService {
+    subscriptions: Mutex<HashMap<String, PushSubscription>>,
+}
+
+impl PushService {
+    pub fn new() -> Self {
+        PushService {
+            subscriptions: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn get(&self, client: &str) -> Option<PushSubscription> {
+        self.subscriptions.lock().unwrap().get(client).cloned()
+    }
+
+    pub fn set(&self, client: &str, subscription: PushSubscription) {
+        self.subscriptions
+            .lock()
+            .unwrap()
+            .insert(client.to_string(), subscription);
+    }
+
+    pub fn remove(&self, client: &str) -> bool {
+        self.subscriptions.lock().unwrap().remove(client).is_some()
+    }
+
+    /// Every target that wants notifications of `priority`, each once,
+    /// leaving out the subscriptions of `muted` clients.
+    pub fn targets_for(&self, priority: &Priority, muted: &HashSet<String>) -> Vec<PushTarget> {
+        let subscriptions = self.subscriptions.lock().unwrap();
+        let mut targets: Vec<PushTarget> = Vec::new();
+        for (client, subscription) in subscriptions.iter() {
+            if priority.weight() < subscription.min_priority.weight() || muted.contains(client) {
+                continue;
+            }
+            for target in &subscription.targets {
+                if !targets.contains(target) {
+                    targets.push(target.clone());
+                }
+            }
+        }
+        targets
+    }
+
+    /// Delivers `notification` to every interested target outside `muted`
+    /// clients, returning how many accepted it. Failures are logged, not
+    /// retried.
+    pub async fn broadcast(&self, notification: &Notification, muted: &HashSet<String>) -> usize {
+        let mut delivered = 0;
+        for target in self.targets_for(&notification.priority, muted) {
+            match deliver(&target, notification).await {
+                Ok(()) => delivered += 1,
+                Err(e) => eprintln!("Push notification failed: {}", e),
+            }
+        }
+        delivered
+    }
+}
+
+impl Default for PushService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub struct PushPlugin;
+
+impl Plugin for PushPlugin {
+    fn name(&self) -> &'static str {
+        "push"
+    }
+
+    fn kind(&self) -> Kind {
+        Kind::Notifier
+    }
+
+    fn description(&self) -> &'static str {
+        "Sends reminders to ntfy topics"
+    }
+
+    fn enabled(&self, _config: &Config) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ntfy(topic: &str) -> PushTarget {
+        PushTarget::Ntfy {
+            server: default_ntfy_server(),
+            topic: topic.to_string(),
+            token: None,
+        }
+    }
+
+    #[test]
+    fn test_validate() {
+        let subscription: PushSubscription = serde_json::from_value(serde_json::json!({
+            "targets": [
+                {"kind": "ntfy", "topic": "spicy-reminders"},
+                {"kind": "gotify", "server": "https://push.example", "token": "A1b2"}
+            ]
+        }))
+        .unwrap();
+        assert_eq!(subscription.min_priority, Priority::Low);
+        assert_eq!(subscription.targets[0], ntfy("spicy-reminders"));
+        assert!(subscription.validate().is_ok());
+
+        let json = serde_json::to_value(&subscription).unwrap();
+        assert!(json["targets"][1].get("token").is_none());
+
+        let invalid = |target: PushTarget| {
+            PushSubscription {
+                targets: vec![target],
+                min_priority: Priority::Low,
+            }
+            .validate()
+            .is_err()
+        };
+        assert!(invalid(ntfy("no spaces")));
+        assert!(invalid(ntfy("")));
+        assert!(invalid(PushTarget::Gotify {
+            server: "ftp://push.example".to_string(),
+            token: "t".to_string()
+        }));
+        assert!(invalid(PushTarget::Gotify {
+            server: "https://push.example".to_string(),
+            token: " ".to_string()
+        }));
+        let empty = PushSubscription {
+            targets: Vec::new(),
+            min_priority: Priority::Low,
+        };
+        assert!(empty.validate().is_err());
+    }
+
+    #[test]
+    fn test_targets_respect_min_priority() {
+        let push = PushService::new();
+        push.set(
+            "phone",
+            PushSubscription {
+                targets: vec![ntfy("all")],
+                min_priority: Priority::Low,
+            },
+        );
+        push.set(
+            "desktop",
+            PushSubscription {
+                targets: vec![ntfy("urgent"), ntfy("all")],
+                min_priority: Priority::High,
+            },
+        );
+        assert_eq!(
+            push.targets_for(&Priority::Medium, &HashSet::new()),
+            vec![ntfy("all")]
+        );
+        assert_eq!(push.targets_for(&Priority::High, &HashSet::new()).len(), 2);
+        assert!(push.remove("phone"));
+        assert!(push.targets_for(&Priority::Low, &HashSet::new()).is_empty());
+    }
+}
@@ -0,0 +1,256 @@
+use crate::matrix::MatrixRoom;
+use crate::preferences::{Channel, PreferenceStore};
+use crate::push::{Notification, PushService};
+use crate::scheduler::{Outcome, Schedule, Scheduler};
+use crate::sms::SmsService;
+use crate::webpush::{WebPushMessage, WebPushService};
+use actix_web::web;
+use chrono::{DateTime, Days, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use serde::Serialize;
+use spicy_todo_core::models::{Priority, Todo};
+use spicy_todo_core::TodoService;
+use std::cell::Cell;
+use std::rc::Rc;
+use std::time::Duration;
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Active todos whose reminder, `dueDate` at `reminderTime` in UTC, falls
+/// after `since` and no later than `until`.
+pub fn due_between(todos: &[Todo], since: NaiveDateTime, until: NaiveDateTime) -> Vec<&Todo> {
+    todos
+        .iter()
+        .filter(|todo| !todo.completed)
+        .filter(|todo| reminder_at(todo).is_some_and(|at| since < at && at <= until))
+        .collect()
+}
+
+/// Active todos that became overdue, at the midnight after their due date,
+/// after `since` and no later than `until`.
+pub fn overdue_between(todos: &[Todo], since: NaiveDateTime, until: NaiveDateTime) -> Vec<&Todo> {
+    todos
+        .iter()
+        .filter(|todo| !todo.completed)
+        .filter(|todo| overdue_at(todo).is_some_and(|at| since < at && at <= until))
+        .collect()
+}
+
+fn overdue_at(todo: &Todo) -> Option<NaiveDateTime> {
+    let date = NaiveDate::parse_from_str(todo.due_date.as_deref()?, "%Y-%m-%d").ok()?;
+    Some(date.checked_add_days(Days::new(1))?.and_time(NaiveTime::MIN))
+}
+
+fn reminder_at(todo: &Todo) -> Option<NaiveDateTime> {
+    let date = NaiveDate::parse_from_str(todo.due_date.as_deref()?, "%Y-%m-%d").ok()?;
+    let time = NaiveTime::parse_from_str(todo.reminder_time.as_deref()?, "%H:%M").ok()?;
+    Some(date.and_time(time))
+}
+
+/// Only high priority reminders are urgent enough to text.
+pub fn is_urgent(todo: &Todo) -> bool {
+    todo.priority == Priority::High
+}
+
+pub fn message(todo: &Todo) -> String {
+    let label = if is_urgent(todo) { "Urgent" } else { "Reminder" };
+    format!(
+        "{}: {} (due {} {})",
+        label,
+        todo.text.trim(),
+        todo.due_date.as_deref().unwrap_or_default(),
+        todo.reminder_time.as_deref().unwrap_or_default()
+    )
+}
+
+fn notification(todo: &Todo, message: String) -> Notification {
+    Notification {
+        title: if is_urgent(todo) {
+            "Urgent reminder".to_string()
+        } else {
+            "Reminder".to_string()
+        },
+        message,
+        priority: todo.priority.clone(),
+    }
+}
+
+/// Everywhere reminders can go. Push is always available; SMS, Matrix and
+/// Web Push only when configured. Clients can turn push and SMS off in
+/// their `preferences`.
+#[derive(Clone)]
+pub struct Channels {
+    pub preferences: web::Data<PreferenceStore>,
+    pub push: web::Data<PushService>,
+    pub sms: Option<web::Data<SmsService>>,
+    pub matrix: Option<web::Data<MatrixRoom>>,
+    pub web_push: Option<web::Data<WebPushService>>,
+}
+
+/// Where one reminder was delivered.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Delivery {
+    #[serde(rename = "pushTargets")]
+    pub push_targets: usize,
+    #[serde(rename = "smsNumbers")]
+    pub sms_numbers: usize,
+    pub matrix: bool,
+    #[serde(rename = "webPush")]
+    pub web_push: usize,
+}
+
+impl Delivery {
+    /// Whether any channel took the reminder.
+    pub fn delivered(&self) -> bool {
+        self.push_targets > 0 || self.sms_numbers > 0 || self.matrix || self.web_push > 0
+    }
+}
+
+impl Channels {
+    /// Sends `message` about `todo` to the push targets that want its
+    /// priority and to the Matrix room, and to verified SMS numbers when it
+    /// is urgent. Failures are logged, not retried.
+    pub async fn dispatch(&self, todo: &Todo, message: String, now: DateTime<Utc>) -> Delivery {
+        let notification = notification(todo, message.clone());
+        let web_push = self.notify_browsers(todo, &notification, now).await;
+        let push_targets = self
+            .push
+            .broadcast(&notification, &self.preferences.muted(Channel::Push))
+            .await;
+        let sms_numbers = match &self.sms {
+            Some(sms) if is_urgent(todo) => {
+                let muted = self.preferences.muted(Channel::Sms);
+                sms.broadcast(&message, now, &muted).await
+            }
+            _ => 0,
+        };
+        let matrix = match &self.matrix {
+            Some(matrix) => matrix
+                .send(&message)
+                .await
+                .inspect_err(|e| eprintln!("Matrix reminder failed: {}", e))
+                .is_ok(),
+            None => false,
+        };
+        Delivery {
+            push_targets,
+            sms_numbers,
+            matrix,
+            web_push,
+        }
+    }
+
+    /// Sends `notification` about `todo` to Web Push subscribers only.
+    pub async fn notify_browsers(
+        &self,
+        todo: &Todo,
+        notification: &Notification,
+        now: DateTime<Utc>,
+    ) -> usize {
+        let Some(web_push) = &self.web_push else {
+            return 0;
+        };
+        let message = WebPushMessage {
+            title: notification.title.clone(),
+            body: notification.message.clone(),
+            tag: todo.id.to_string(),
+            priority: notification.priority.clone(),
+        };
+        web_push.broadcast(&message, now).await
+    }
+}
+
+fn overdue_notification(todo: &Todo) -> Notification {
+    Notification {
+        title: "Overdue".to_string(),
+        message: format!(
+            "Overdue: {} (was due {})",
+            todo.text.trim(),
+            todo.due_date.as_deref().unwrap_or_default()
+        ),
+        priority: todo.priority.clone(),
+    }
+}
+
+/// The notification dispatcher: checks every minute for reminders that
+/// came due since the last check and dispatches each through `channels`.
+/// Browsers subscribed to Web Push also hear when a todo becomes overdue.
+/// Reminders that came due while the server was down are not sent late.
+pub fn schedule(scheduler: &mut Scheduler, service: web::Data<TodoService>, channels: Channels) {
+    let checked_until = Rc::new(Cell::new(Utc::now().naive_utc()));
+    scheduler.register_exclusive("reminders", Schedule::Every(CHECK_INTERVAL), move || {
+        let (service, channels) = (service.clone(), channels.clone());
+        let checked_until = checked_until.clone();
+        async move {
+            let now = Utc::now();
+            let todos = service.get_all(None, None, None);
+            let due = due_between(&todos, checked_until.get(), now.naive_utc());
+            let overdue = overdue_between(&todos, checked_until.get(), now.naive_utc());
+            checked_until.set(now.naive_utc());
+            if due.is_empty() && (overdue.is_empty() || channels.web_push.is_none()) {
+                return Ok(Outcome::Skipped);
+            }
+            for todo in overdue {
+                channels
+                    .notify_browsers(todo, &overdue_notification(todo), now)
+                    .await;
+            }
+            for todo in due {
+                let delivery = channels.dispatch(todo, message(todo), now).await;
+                println!(
+                    "🔔 Sent reminder for {} to {} push targets and {} numbers",
+                    todo.id, delivery.push_targets, delivery.sms_numbers
+                );
+            }
+            Ok(Outcome::Done)
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spicy_todo_core::models::TodoCreate;
+
+    fn create(service: &TodoService, text: &str, priority: Priority, time: &str) -> Todo {
+        service.create(TodoCreate {
+            text: text.to_string(),
+            priority: Some(priority),
+            due_date: Some("2024-06-10".to_string()),
+            reminder_time: Some(time.to_string()),
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_due_between_window() {
+        let service = TodoService::new_empty();
+        create(&service, "Pay rent", Priority::High, "09:00");
+        create(&service, "Water plants", Priority::Medium, "09:00");
+        create(&service, "Call bank", Priority::High, "09:05");
+        let todos = service.get_all(None, None, None);
+        let at = |time: &str| {
+            NaiveDate::from_ymd_opt(2024, 6, 10)
+                .unwrap()
+                .and_time(NaiveTime::parse_from_str(time, "%H:%M").unwrap())
+        };
+
+        let mut due = due_between(&todos, at("08:59"), at("09:00"));
+        due.sort_by_key(|todo| todo.text.clone());
+        assert_eq!(due.len(), 2);
+        assert_eq!(message(due[0]), "Urgent: Pay rent (due 2024-06-10 09:00)");
+        assert_eq!(notification(due[1], message(due[1])).title, "Reminder");
+        assert!(due_between(&todos, at("09:00"), at("09:04")).is_empty());
+        assert_eq!(due_between(&todos, at("08:00"), at("10:00")).len(), 3);
+
+        let midnight = NaiveDate::from_ymd_opt(2024, 6, 11)
+            .unwrap()
+            .and_time(NaiveTime::MIN);
+        assert!(overdue_between(&todos, at("10:00"), at("23:59")).is_empty());
+        let overdue = overdue_between(&todos, at("23:59"), midnight);
+        assert_eq!(overdue.len(), 3);
+        assert_eq!(
+            overdue_notification(overdue[0]).message,
+            format!("Overdue: {} (was due 2024-06-10)", overdue[0].text)
+        );
+    }
+}
@@ -0,0 +1,49 @@
+use crate::config::OverdueRolloverSettings;
+use crate::scheduler::{Outcome, Schedule, Scheduler};
+use actix_web::web;
+use chrono::Utc;
+use spicy_todo_core::rollover::RolloverMode;
+use spicy_todo_core::TodoService;
+
+/// Rolls recurring todos over at startup, to catch up on days the server
+/// was down, and then after every UTC midnight.
+pub fn schedule(scheduler: &mut Scheduler, service: web::Data<TodoService>, mode: RolloverMode) {
+    scheduler.register_exclusive("rollover", Schedule::Daily, move || {
+        let report = service.roll_over(Utc::now().date_naive(), mode);
+        if report.rolled > 0 {
+            println!(
+                "🔁 Rolled over {} recurring todos ({} occurrences missed)",
+                report.rolled, report.missed
+            );
+        }
+        let outcome = if report.rolled > 0 {
+            Outcome::Done
+        } else {
+            Outcome::Skipped
+        };
+        async move { Ok(outcome) }
+    });
+}
+
+/// Carries overdue one-off todos over to today at startup and then after
+/// every midnight in the configured time zone.
+pub fn schedule_overdue(
+    scheduler: &mut Scheduler,
+    service: web::Data<TodoService>,
+    settings: OverdueRolloverSettings,
+) {
+    let schedule = Schedule::DailyIn(settings.timezone);
+    scheduler.register_exclusive("overdue-rollover", schedule, move || {
+        let today = Utc::now().with_timezone(&settings.timezone).date_naive();
+        let rolled = service.roll_over_overdue(today, settings.mode);
+        if rolled > 0 {
+            println!("🔁 Carried {} overdue todos over to {}", rolled, today);
+        }
+        let outcome = if rolled > 0 {
+            Outcome::Done
+        } else {
+            Outcome::Skipped
+        };
+        async move { Ok(outcome) }
+    });
+}
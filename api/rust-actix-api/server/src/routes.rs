@@ -0,0 +1,231 @@
+use crate::auth;
+use crate::caldav;
+use crate::casing;
+use crate::context;
+use crate::deadlines;
+use crate::errors;
+use crate::handlers;
+use crate::preferences;
+use crate::versioning;
+use actix_cors::Cors;
+use actix_web::http::Method;
+use actix_web::{guard, middleware, web, Route, Scope};
+
+pub fn configure_routes(cfg: &mut web::ServiceConfig) {
+    cfg
+        // Root routes
+        .route("/", web::get().to(handlers::root))
+        .route("/health", web::get().to(handlers::health))
+        .route("/health/live", web::get().to(handlers::health_live))
+        .route("/health/ready", web::get().to(handlers::health_ready))
+        .route("/metrics", web::get().to(handlers::get_metrics))
+        .route("/debug/pprof/profile", web::get().to(handlers::debug_pprof_profile))
+        .route("/debug/runtime", web::get().to(handlers::debug_runtime))
+        // CalDAV routes
+        .route("/.well-known/caldav", web::route().to(handlers::caldav_well_known))
+        .service(
+            web::resource(["/dav", "/dav/"])
+                .route(web::method(caldav::propfind()).to(handlers::caldav_propfind_root))
+                .route(web::method(Method::OPTIONS).to(handlers::caldav_options)),
+        )
+        .service(
+            web::resource(["/dav/todos", "/dav/todos/"])
+                .route(web::method(caldav::propfind()).to(handlers::caldav_propfind_collection))
+                .route(web::method(caldav::report()).to(handlers::caldav_report))
+                .route(web::method(Method::OPTIONS).to(handlers::caldav_options)),
+        )
+        .service(
+            web::resource("/dav/todos/{name}")
+                .route(web::get().to(handlers::caldav_get_todo))
+                .route(web::put().to(handlers::caldav_put_todo))
+                .route(web::delete().to(handlers::caldav_delete_todo))
+                .route(web::method(caldav::propfind()).to(handlers::caldav_propfind_todo))
+                .route(web::method(Method::OPTIONS).to(handlers::caldav_options)),
+        )
+        // API routes. The versioned scopes go first, since "/api" would
+        // otherwise take "/api/v2/todos" for its own "/v2/todos".
+        .service(api_scope("/api/v1"))
+        .service(api_scope("/api/v2").wrap(middleware::from_fn(versioning::v2_compat)))
+        .service(api_scope("/api"));
+}
+
+/// A route for `GET` that answers `HEAD` too; the server leaves the body
+/// out of `HEAD` responses but keeps their headers.
+fn get_or_head() -> Route {
+    web::route().guard(guard::Any(guard::Get()).or(guard::Head()))
+}
+
+/// Every API route, under `path`. Unversioned `/api` is v1.
+fn api_scope(path: &str) -> Scope {
+    web::scope(path)
+        .app_data(web::JsonConfig::default().error_handler(errors::extractor_error))
+        .app_data(web::QueryConfig::default().error_handler(errors::extractor_error))
+        .app_data(web::PathConfig::default().error_handler(errors::path_error))
+        .route("", web::method(Method::OPTIONS).to(handlers::get_capabilities))
+        .route("/capabilities", get_or_head().to(handlers::get_capabilities))
+        .route(
+            "/capabilities",
+            web::method(Method::OPTIONS).to(handlers::get_capabilities),
+        )
+        .route("/auth/login", web::post().to(handlers::auth_login))
+        .route(
+            "/auth/login/{provider}",
+            web::get().to(handlers::auth_login_with_provider),
+        )
+        .route("/auth/callback", web::get().to(handlers::auth_callback))
+        .route("/auth/logout", web::post().to(handlers::auth_logout))
+        .route("/auth/session", web::get().to(handlers::auth_session))
+        .route("/account/export", web::get().to(handlers::account_export))
+        .route("/account", web::delete().to(handlers::delete_account))
+        .route("/todos", get_or_head().to(handlers::get_todos))
+        .route("/todos", web::post().to(handlers::create_todo))
+        .route("/todos/changes", web::get().to(handlers::get_changes))
+        .route("/todos/import", web::post().to(handlers::import_todo))
+        .route("/todos/quick", web::post().to(handlers::quick_add_todo))
+        .route("/todos/digest", web::get().to(handlers::get_digest))
+        .route("/todos/feed.atom", web::get().to(handlers::get_feed))
+        .route("/todos/stream", web::get().to(handlers::stream_todos))
+        .route("/todos/nearby", web::get().to(handlers::get_nearby_todos))
+        .route(
+            "/todos/bulk-edit/preview",
+            web::post().to(handlers::bulk_edit_preview),
+        )
+        .route("/todos/bulk-edit/apply", web::post().to(handlers::bulk_edit_apply))
+        .route("/todos/preferences", web::get().to(handlers::get_preference))
+        .route("/todos/preferences", web::put().to(handlers::put_preference))
+        .route("/todos/preferences", web::delete().to(handlers::delete_preference))
+        // Before /todos/{id}, which would otherwise take "completed" as an id.
+        .route("/todos/completed", web::delete().to(handlers::clear_completed))
+        .route("/todos/complete", web::post().to(handlers::complete_todos))
+        .route("/todos/{id}", get_or_head().to(handlers::get_todo))
+        .route("/todos/{id}", web::put().to(handlers::update_todo))
+        .route("/todos/{id}", web::delete().to(handlers::delete_todo))
+        .route("/todos/{id}/toggle", web::patch().to(handlers::toggle_todo))
+        .route("/todos/{id}/pin", web::patch().to(handlers::pin_todo))
+        .route("/todos/{id}/snooze", web::post().to(handlers::snooze_todo))
+        .route("/todos/{id}/missed", web::get().to(handlers::get_missed_occurrences))
+        .route(
+            "/todos/{id}/reminders/geo-trigger",
+            web::post().to(handlers::geo_trigger_reminder),
+        )
+        .route("/todos/{id}/export", web::get().to(handlers::export_todo))
+        .route("/todos/{id}/transfer", web::post().to(handlers::transfer_todo))
+        .route("/todos/{id}/suggest", web::post().to(handlers::suggest_for_todo))
+        .route("/todos/stats/summary", web::get().to(handlers::get_stats))
+        .route("/todos/stats/report", web::get().to(handlers::get_stats_report))
+        .route("/notifications/push", web::get().to(handlers::get_push_subscription))
+        .route("/notifications/push", web::put().to(handlers::put_push_subscription))
+        .route(
+            "/notifications/push",
+            web::delete().to(handlers::delete_push_subscription),
+        )
+        .route(
+            "/notifications/push/test",
+            web::post().to(handlers::test_push_subscription),
+        )
+        .route("/notifications/sms", web::get().to(handlers::get_sms_subscription))
+        .route("/notifications/sms", web::put().to(handlers::put_sms_subscription))
+        .route("/notifications/sms", web::delete().to(handlers::delete_sms_subscription))
+        .route(
+            "/notifications/sms/verify",
+            web::post().to(handlers::verify_sms_subscription),
+        )
+        .route(
+            "/homeassistant/sensor",
+            web::get().to(handlers::get_homeassistant_sensor),
+        )
+        .route(
+            "/homeassistant/services/{service}",
+            web::post().to(handlers::call_homeassistant_service),
+        )
+        .route("/push/vapid-public-key", web::get().to(handlers::get_web_push_key))
+        .route("/push/subscribe", web::post().to(handlers::web_push_subscribe))
+        .route("/push/subscribe", web::delete().to(handlers::web_push_unsubscribe))
+        .route("/webhooks", web::get().to(handlers::get_webhooks))
+        .route("/webhooks", web::post().to(handlers::create_webhook))
+        .route("/webhooks/{id}", web::get().to(handlers::get_webhook))
+        .route("/webhooks/{id}", web::delete().to(handlers::delete_webhook))
+        .route("/webhooks/{id}/replay", web::post().to(handlers::replay_webhook))
+        .route("/notifiers", web::get().to(handlers::get_notifiers))
+        .route("/notifiers", web::post().to(handlers::create_notifier))
+        .route("/notifiers/{id}", web::get().to(handlers::get_notifier))
+        .route("/notifiers/{id}", web::delete().to(handlers::delete_notifier))
+        .route("/scripts", web::get().to(handlers::get_scripts))
+        .route("/scripts", web::post().to(handlers::create_script))
+        .route("/scripts/{id}", web::get().to(handlers::get_script))
+        .route("/scripts/{id}", web::delete().to(handlers::delete_script))
+        .route(
+            "/scripts/{id}/executions",
+            web::get().to(handlers::get_script_executions),
+        )
+        .route("/views", web::get().to(handlers::get_views))
+        .route("/views", web::post().to(handlers::create_view))
+        .route("/views/{id}", web::get().to(handlers::get_view))
+        .route("/views/{id}", web::delete().to(handlers::delete_view))
+        .route("/views/{id}/todos", web::get().to(handlers::get_view_todos))
+        .route("/policies", web::get().to(handlers::get_policies))
+        .route("/policies", web::post().to(handlers::create_policy))
+        .route("/policies/{id}", web::get().to(handlers::get_policy))
+        .route("/policies/{id}", web::put().to(handlers::update_policy))
+        .route("/policies/{id}", web::delete().to(handlers::delete_policy))
+        .route("/actions", web::get().to(handlers::get_actions))
+        .route("/plugins", web::get().to(handlers::get_plugins))
+        .route("/plan/today", web::get().to(handlers::get_plan_today))
+        .route("/myday/{date}", web::get().to(handlers::get_my_day))
+        .route("/myday/{date}/todos/{id}", web::post().to(handlers::add_to_my_day))
+        .route(
+            "/myday/{date}/todos/{id}",
+            web::delete().to(handlers::remove_from_my_day),
+        )
+        .route("/dashboard", web::get().to(handlers::get_dashboard))
+        .route("/preferences", web::get().to(handlers::get_user_preferences))
+        .route("/preferences", web::put().to(handlers::put_user_preferences))
+        .route("/mcp/sse", web::get().to(handlers::mcp_sse))
+        .route("/mcp/messages", web::post().to(handlers::mcp_message))
+        .route("/conformance", web::get().to(handlers::get_conformance))
+        .route("/sync", web::post().to(handlers::sync_todos))
+        .route("/ingest/email", web::post().to(handlers::ingest_email))
+        .route("/events/log", web::get().to(handlers::get_event_log))
+        .route("/audit", web::get().to(handlers::get_audit))
+        .route("/activity", web::get().to(handlers::get_activity))
+        .route("/import/spicy", web::post().to(handlers::import_spicy))
+        .route("/admin/seed", web::post().to(handlers::admin_seed))
+        .route("/admin/generate", web::post().to(handlers::admin_generate))
+        .route("/admin/reset", web::post().to(handlers::admin_reset))
+        .route("/admin/backups", web::get().to(handlers::admin_list_backups))
+        .route("/admin/backups", web::post().to(handlers::admin_create_backup))
+        .route("/admin/restore", web::post().to(handlers::admin_restore))
+        .route(
+            "/admin/grafana-dashboard",
+            web::get().to(handlers::admin_grafana_dashboard),
+        )
+}
+
+pub fn configure_cors() -> Cors {
+    Cors::default()
+        .allowed_origin("http://localhost:3000")
+        .allowed_origin("http://127.0.0.1:3000")
+        .allowed_methods(vec!["GET", "HEAD", "POST", "PUT", "PATCH", "DELETE", "OPTIONS"])
+        .allowed_headers(vec![
+            actix_web::http::header::CONTENT_TYPE,
+            actix_web::http::header::ACCEPT,
+            actix_web::http::header::AUTHORIZATION,
+            actix_web::http::header::IF_NONE_MATCH,
+            actix_web::http::header::IF_MODIFIED_SINCE,
+            actix_web::http::header::HeaderName::from_static(deadlines::TIMEOUT_HEADER),
+            actix_web::http::header::HeaderName::from_static(deadlines::DEADLINE_HEADER),
+            actix_web::http::header::HeaderName::from_static(preferences::CLIENT_HEADER),
+            actix_web::http::header::HeaderName::from_static(auth::CSRF_HEADER),
+            actix_web::http::header::HeaderName::from_static(casing::CASE_HEADER),
+        ])
+        .expose_headers(vec![
+            actix_web::http::header::ETAG,
+            actix_web::http::header::LAST_MODIFIED,
+            actix_web::http::header::HeaderName::from_static(preferences::APPLIED_HEADER),
+            actix_web::http::header::HeaderName::from_static(context::REQUEST_ID_HEADER),
+        ])
+        // For the session cookie.
+        .supports_credentials()
+        .max_age(3600)
+}
+
@@ -0,0 +1,349 @@
+use crate::leader::FileLease;
+use crate::metrics::Metrics;
+use actix_web::web;
+use chrono::{NaiveDate, NaiveDateTime, Utc};
+use chrono_tz::Tz;
+use std::future::Future;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+
+/// Interval jobs wait up to this fraction of their interval on top of it, so
+/// jobs started together drift apart instead of always colliding.
+const INTERVAL_JITTER: f64 = 0.1;
+/// Daily jobs start up to this long after midnight.
+const DAILY_JITTER: Duration = Duration::from_secs(60);
+
+/// When a job runs.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Schedule {
+    /// Every interval, starting one interval after startup.
+    Every(Duration),
+    /// At startup, to catch up on downtime, then after every UTC midnight.
+    Daily,
+    /// As `Daily`, at midnight in the time zone.
+    DailyIn(Tz),
+}
+
+/// How a run went, for logs and metrics. Failures are `Err`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Outcome {
+    Done,
+    /// Nothing to do this time, e.g. no changes since the last snapshot.
+    Skipped,
+}
+
+type JobFuture = Pin<Box<dyn Future<Output = Result<Outcome, String>>>>;
+type HookFuture = Pin<Box<dyn Future<Output = Result<(), String>>>>;
+
+struct Job {
+    name: &'static str,
+    schedule: Schedule,
+    /// Runs only on the replica holding the lease, when there is one.
+    exclusive: bool,
+    run: Box<dyn Fn() -> JobFuture>,
+}
+
+struct Hook {
+    name: &'static str,
+    run: Box<dyn FnOnce() -> HookFuture>,
+}
+
+/// Periodic background work registered at startup. Each job runs on its own
+/// task, one run at a time; failures are logged and retried on the next
+/// tick. Runs are counted in the metrics registry when one is given.
+pub struct Scheduler {
+    jobs: Vec<Job>,
+    hooks: Vec<Hook>,
+    metrics: Option<web::Data<Metrics>>,
+    lease: Option<Rc<FileLease>>,
+}
+
+impl Scheduler {
+    pub fn new(metrics: Option<web::Data<Metrics>>) -> Self {
+        Scheduler {
+            jobs: Vec::new(),
+            hooks: Vec::new(),
+            metrics,
+            lease: None,
+        }
+    }
+
+    /// Shares exclusive jobs with the other replicas using `lease`: each
+    /// run goes ahead only on the replica leading at the time, and is
+    /// skipped on the others.
+    pub fn lead_with(&mut self, lease: FileLease) {
+        self.lease = Some(Rc::new(lease));
+    }
+
+    /// A job every replica runs, on its own state.
+    pub fn register<F, Fut>(&mut self, name: &'static str, schedule: Schedule, run: F)
+    where
+        F: Fn() -> Fut + 'static,
+        Fut: Future<Output = Result<Outcome, String>> + 'static,
+    {
+        self.push(name, schedule, false, run);
+    }
+
+    /// A job that must run once per deployment, such as sending reminders;
+    /// see `lead_with`. Without a lease it runs like any other.
+    pub fn register_exclusive<F, Fut>(&mut self, name: &'static str, schedule: Schedule, run: F)
+    where
+        F: Fn() -> Fut + 'static,
+        Fut: Future<Output = Result<Outcome, String>> + 'static,
+    {
+        self.push(name, schedule, true, run);
+    }
+
+    fn push<F, Fut>(&mut self, name: &'static str, schedule: Schedule, exclusive: bool, run: F)
+    where
+        F: Fn() -> Fut + 'static,
+        Fut: Future<Output = Result<Outcome, String>> + 'static,
+    {
+        self.jobs.push(Job {
+            name,
+            schedule,
+            exclusive,
+            run: Box::new(move || Box::pin(run())),
+        });
+    }
+
+    /// Runs `hook` during `shutdown`, after every job has stopped. Hooks run
+    /// in registration order.
+    pub fn on_shutdown<F, Fut>(&mut self, name: &'static str, hook: F)
+    where
+        F: FnOnce() -> Fut + 'static,
+        Fut: Future<Output = Result<(), String>> + 'static,
+    {
+        self.hooks.push(Hook {
+            name,
+            run: Box::new(move || Box::pin(hook())),
+        });
+    }
+
+    /// Spawns every job on the current runtime.
+    pub fn start(self) -> RunningScheduler {
+        let (stop, stopped) = watch::channel(false);
+        let tasks = self
+            .jobs
+            .into_iter()
+            .map(|job| {
+                let lease = self.lease.clone().filter(|_| job.exclusive);
+                actix_web::rt::spawn(run_job(job, self.metrics.clone(), lease, stopped.clone()))
+            })
+            .collect();
+        RunningScheduler {
+            stop,
+            tasks,
+            hooks: self.hooks,
+            lease: self.lease,
+        }
+    }
+}
+
+pub struct RunningScheduler {
+    stop: watch::Sender<bool>,
+    tasks: Vec<JoinHandle<()>>,
+    hooks: Vec<Hook>,
+    lease: Option<Rc<FileLease>>,
+}
+
+impl RunningScheduler {
+    /// Stops scheduling new runs, waits for in-flight ones to finish, then
+    /// runs the shutdown hooks and gives up the lease.
+    pub async fn shutdown(self) {
+        let _ = self.stop.send(true);
+        for task in self.tasks {
+            let _ = task.await;
+        }
+        for hook in self.hooks {
+            if let Err(e) = (hook.run)().await {
+                eprintln!("Shutdown hook {} failed: {}", hook.name, e);
+            }
+        }
+        if let Some(lease) = self.lease {
+            if let Err(e) = lease.release() {
+                eprintln!("Releasing lease {} failed: {}", lease.path().display(), e);
+            }
+        }
+    }
+}
+
+async fn run_job(
+    job: Job,
+    metrics: Option<web::Data<Metrics>>,
+    lease: Option<Rc<FileLease>>,
+    mut stopped: watch::Receiver<bool>,
+) {
+    let mut first = true;
+    loop {
+        let wait = match job.schedule {
+            Schedule::Every(interval) => interval + jitter(interval.mul_f64(INTERVAL_JITTER)),
+            Schedule::Daily | Schedule::DailyIn(_) if first => Duration::ZERO,
+            Schedule::Daily => {
+                let now = Utc::now().naive_utc();
+                until_next_day(now, now.date()) + jitter(DAILY_JITTER)
+            }
+            Schedule::DailyIn(tz) => {
+                let now = Utc::now().with_timezone(&tz).naive_local();
+                until_next_day(now, now.date()) + jitter(DAILY_JITTER)
+            }
+        };
+        first = false;
+        tokio::select! {
+            _ = tokio::time::sleep(wait) => {}
+            _ = stopped.wait_for(|stop| *stop) => return,
+        }
+
+        let start = Instant::now();
+        let result = match lease.as_ref().map(|lease| lease.acquire(Utc::now())) {
+            None | Some(Ok(true)) => (job.run)().await,
+            // Another replica leads; it runs this one.
+            Some(Ok(false)) => Ok(Outcome::Skipped),
+            // Unsure who leads, so better skip a run than run it twice.
+            Some(Err(e)) => Err(format!("checking the lease: {}", e)),
+        };
+        let outcome = match &result {
+            Ok(Outcome::Done) => "done",
+            Ok(Outcome::Skipped) => "skipped",
+            Err(e) => {
+                eprintln!("Job {} failed: {}", job.name, e);
+                "failed"
+            }
+        };
+        if let Some(metrics) = &metrics {
+            metrics.observe_job(job.name, outcome, start.elapsed().as_secs_f64());
+        }
+    }
+}
+
+fn jitter(max: Duration) -> Duration {
+    max.mul_f64(rand::random_range(0.0..=1.0))
+}
+
+/// Time from `now` until the start of the day after `today`.
+fn until_next_day(now: NaiveDateTime, today: NaiveDate) -> Duration {
+    let midnight = today
+        .succ_opt()
+        .and_then(|tomorrow| tomorrow.and_hms_opt(0, 0, 0))
+        .unwrap_or(now);
+    (midnight - now).to_std().unwrap_or(Duration::ZERO)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    #[test]
+    fn test_sleeps_until_midnight() {
+        let today = NaiveDate::from_ymd_opt(2024, 6, 10).unwrap();
+        let evening = today.and_hms_opt(23, 59, 0).unwrap();
+        assert_eq!(until_next_day(evening, today), Duration::from_secs(60));
+
+        // A slow run that finished after midnight goes again right away.
+        let late = today.succ_opt().unwrap().and_hms_opt(0, 0, 5).unwrap();
+        assert_eq!(until_next_day(late, today), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_jitter_stays_within_bound() {
+        for _ in 0..100 {
+            assert!(jitter(Duration::from_millis(10)) <= Duration::from_millis(10));
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_runs_jobs_and_hooks_until_shutdown() {
+        let metrics = web::Data::new(Metrics::new());
+        let mut scheduler = Scheduler::new(Some(metrics.clone()));
+        let runs = Rc::new(Cell::new(0));
+        let counter = runs.clone();
+        scheduler.register(
+            "count",
+            Schedule::Every(Duration::from_millis(5)),
+            move || {
+                counter.set(counter.get() + 1);
+                let outcome = if counter.get() % 2 == 0 {
+                    Ok(Outcome::Skipped)
+                } else {
+                    Err("odd run".to_string())
+                };
+                async move { outcome }
+            },
+        );
+        scheduler.register("daily", Schedule::Daily, || async { Ok(Outcome::Done) });
+        let hooked = Rc::new(Cell::new(false));
+        let hook = hooked.clone();
+        scheduler.on_shutdown("flag", move || async move {
+            hook.set(true);
+            Ok(())
+        });
+
+        let running = scheduler.start();
+        for _ in 0..100 {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+            if runs.get() >= 3 {
+                break;
+            }
+        }
+        running.shutdown().await;
+        let stopped_at = runs.get();
+        assert!(stopped_at >= 3);
+        assert!(hooked.get());
+
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert_eq!(runs.get(), stopped_at, "no runs after shutdown");
+
+        let exposition = metrics.render(&spicy_todo_core::TodoService::new_empty().get_stats());
+        assert!(exposition.contains(r#"spicy_todo_job_runs_total{job="count",outcome="failed"}"#));
+        assert!(exposition.contains(r#"spicy_todo_job_runs_total{job="count",outcome="skipped"}"#));
+        assert!(exposition.contains(r#"spicy_todo_job_runs_total{job="daily",outcome="done"} 1"#));
+    }
+
+    #[actix_web::test]
+    async fn test_exclusive_jobs_run_on_the_leader_only() {
+        let dir = std::env::temp_dir().join(format!("spicy-scheduler-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("scheduler.lease");
+        let interval = Schedule::Every(Duration::from_millis(5));
+        let runs = Rc::new(Cell::new([0, 0, 0]));
+
+        let mut replicas = Vec::new();
+        for replica in 0..2 {
+            let mut scheduler = Scheduler::new(None);
+            scheduler.lead_with(FileLease::new(&path, Duration::from_secs(60)));
+            let counter = runs.clone();
+            scheduler.register_exclusive("exclusive", interval, move || {
+                let mut counts = counter.get();
+                counts[replica] += 1;
+                counter.set(counts);
+                async { Ok(Outcome::Done) }
+            });
+            if replica == 0 {
+                let counter = runs.clone();
+                scheduler.register("local", interval, move || {
+                    let mut counts = counter.get();
+                    counts[2] += 1;
+                    counter.set(counts);
+                    async { Ok(Outcome::Done) }
+                });
+            }
+            replicas.push(scheduler.start());
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        for running in replicas {
+            running.shutdown().await;
+        }
+
+        let [first, second, local] = runs.get();
+        assert!(first + second > 0);
+        assert!(first == 0 || second == 0, "ran on both: {} and {}", first, second);
+        assert!(local > 0);
+        assert!(!path.exists(), "released on shutdown");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,657 @@
+use crate::config::Config;
+use crate::moderation::{Moderation, ModerationError};
+use crate::plugins::{Kind, Plugin};
+use actix_web::web;
+use chrono::{DateTime, Utc};
+use rhai::module_resolvers::DummyModuleResolver;
+use rhai::{CallFnOptions, Dynamic, Engine, EvalAltResult, Map, Scope, AST};
+use serde::{Deserialize, Serialize};
+use spicy_todo_core::dates;
+use spicy_todo_core::events::{Event, EventType};
+use spicy_todo_core::models::{self, Todo, TodoUpdate};
+use spicy_todo_core::{quick_add, TodoService};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+const MAX_SCRIPTS: usize = 50;
+const MAX_SOURCE_BYTES: usize = 16 * 1024;
+/// Rhai operations one handler run may take; its CPU budget.
+pub const MAX_OPERATIONS: u64 = 100_000;
+/// Wall-clock budget of one handler run.
+pub const TIME_LIMIT: Duration = Duration::from_millis(250);
+const MAX_STRING_BYTES: usize = 10_000;
+const MAX_COLLECTION_SIZE: usize = 1_000;
+const MAX_CALL_LEVELS: usize = 32;
+/// Todos one handler run may create.
+const MAX_CREATES: usize = 10;
+/// Lines of `print` and `debug` output kept per run.
+const MAX_OUTPUT_LINES: usize = 20;
+/// Runs kept in the audit log, across all scripts.
+const MAX_EXECUTIONS: usize = 500;
+
+/// The events a script can handle, each by defining a function of that
+/// name taking the todo.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Hook {
+    #[serde(rename = "on_create")]
+    OnCreate,
+    #[serde(rename = "on_complete")]
+    OnComplete,
+}
+
+impl Hook {
+    pub const ALL: [Hook; 2] = [Hook::OnCreate, Hook::OnComplete];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Hook::OnCreate => "on_create",
+            Hook::OnComplete => "on_complete",
+        }
+    }
+
+    fn for_event(event_type: EventType) -> Option<Hook> {
+        match event_type {
+            EventType::Created => Some(Hook::OnCreate),
+            EventType::Completed => Some(Hook::OnComplete),
+            _ => None,
+        }
+    }
+}
+
+/// A stored Rhai script.
+#[derive(Debug, Clone, Serialize)]
+pub struct Script {
+    pub id: String,
+    pub name: String,
+    pub source: String,
+    /// The hooks the script defines.
+    pub hooks: Vec<Hook>,
+    #[serde(rename = "createdAt")]
+    pub created_at: DateTime<Utc>,
+    #[serde(skip)]
+    ast: Arc<AST>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ScriptCreate {
+    pub name: String,
+    pub source: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Status {
+    Ok,
+    Error,
+    /// Ran past `TIME_LIMIT`.
+    Timeout,
+    /// Ran past the operation, size or nesting limits.
+    LimitExceeded,
+}
+
+/// Audit record of one handler run.
+#[derive(Debug, Clone, Serialize)]
+pub struct Execution {
+    #[serde(rename = "scriptId")]
+    pub script_id: String,
+    pub hook: Hook,
+    #[serde(rename = "todoId")]
+    pub todo_id: String,
+    #[serde(rename = "startedAt")]
+    pub started_at: DateTime<Utc>,
+    #[serde(rename = "durationMs")]
+    pub duration_ms: f64,
+    pub operations: u64,
+    pub status: Status,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// What the script printed.
+    pub output: Vec<String>,
+    /// Whether the script's `update` was applied. Nothing a failed run
+    /// asked for is.
+    pub updated: bool,
+    /// Ids of the todos it created.
+    pub created: Vec<Uuid>,
+    /// The request whose change set the run off. What the run changes is
+    /// recorded as part of that request too.
+    #[serde(rename = "requestId", skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+}
+
+/// What a run asked for, applied once it finishes without error.
+#[derive(Debug, Default)]
+struct Effects {
+    update: Option<TodoUpdate>,
+    creates: Vec<String>,
+    output: Vec<String>,
+}
+
+impl Effects {
+    fn print(&mut self, line: &str) {
+        if self.output.len() < MAX_OUTPUT_LINES {
+            self.output
+                .push(line.chars().take(MAX_STRING_BYTES).collect());
+        }
+    }
+
+    /// Runs the text the run would save past moderation, masking it in
+    /// place if so configured.
+    async fn moderate(&mut self, moderation: &Moderation) -> Result<(), ModerationError> {
+        if let Some(text) = self.update.as_mut().and_then(|update| update.text.as_mut()) {
+            moderation.apply(text).await?;
+        }
+        for line in &mut self.creates {
+            moderation.apply(line).await?;
+        }
+        Ok(())
+    }
+}
+
+/// An engine with no access to files or modules, and limits on nesting and
+/// data sizes.
+fn sandbox() -> Engine {
+    let mut engine = Engine::new();
+    engine
+        .set_max_operations(MAX_OPERATIONS)
+        .set_max_call_levels(MAX_CALL_LEVELS)
+        .set_max_expr_depths(64, 32)
+        .set_max_string_size(MAX_STRING_BYTES)
+        .set_max_array_size(MAX_COLLECTION_SIZE)
+        .set_max_map_size(MAX_COLLECTION_SIZE)
+        .set_max_modules(0)
+        .set_module_resolver(DummyModuleResolver::new())
+        .disable_symbol("eval");
+    engine
+}
+
+/// The sandbox plus the functions scripts act through:
+///
+/// - `update(#{ priority: "high" })` changes the todo, taking the fields
+///   of `PUT /api/todos/{id}`. The last call wins.
+/// - `create("Call back tomorrow !high")` adds a todo from a quick-add
+///   line.
+/// - `print` and `debug` write to the run's audit output.
+fn engine(effects: &Arc<Mutex<Effects>>, operations: &Arc<AtomicU64>, started: Instant) -> Engine {
+    let mut engine = sandbox();
+    let (progress, update, create, print, debug) = (
+        operations.clone(),
+        effects.clone(),
+        effects.clone(),
+        effects.clone(),
+        effects.clone(),
+    );
+    engine
+        .on_progress(move |count| {
+            progress.store(count, Ordering::Relaxed);
+            (started.elapsed() > TIME_LIMIT).then(|| Dynamic::from("time limit"))
+        })
+        .on_print(move |line| print.lock().unwrap().print(line))
+        .on_debug(move |line, _, _| debug.lock().unwrap().print(line))
+        .register_fn(
+            "update",
+            move |changes: Map| -> Result<(), Box<EvalAltResult>> {
+                let mut changes: TodoUpdate =
+                    rhai::serde::from_dynamic(&Dynamic::from_map(changes))?;
+                dates::normalize_due_date(&mut changes.due_date, Utc::now().date_naive())
+                    .and_then(|()| models::validate_estimate(changes.estimate_minutes))
+                    .and_then(|()| models::validate_location(changes.location.as_ref()))
+                    .and_then(|()| {
+                        models::validate_recurrence_end(changes.recurrence_end.as_ref())
+                    })?;
+                update.lock().unwrap().update = Some(changes);
+                Ok(())
+            },
+        )
+        .register_fn(
+            "create",
+            move |line: &str| -> Result<(), Box<EvalAltResult>> {
+                let mut effects = create.lock().unwrap();
+                if effects.creates.len() >= MAX_CREATES {
+                    return Err(format!("A script can create at most {} todos", MAX_CREATES).into());
+                }
+                if line.trim().is_empty() {
+                    return Err("create needs the todo's text".into());
+                }
+                effects.creates.push(line.to_string());
+                Ok(())
+            },
+        );
+    engine
+}
+
+/// Runs `hook` of `script` for `todo`, returning the audit record, not yet
+/// knowing the effects, and the effects if the run succeeded.
+fn run(script: &Script, hook: Hook, todo: &Todo) -> (Execution, Option<Effects>) {
+    let effects = Arc::new(Mutex::new(Effects::default()));
+    let operations = Arc::new(AtomicU64::new(0));
+    let started_at = Utc::now();
+    let started = Instant::now();
+    let engine = engine(&effects, &operations, started);
+    let result = rhai::serde::to_dynamic(todo).and_then(|todo| {
+        engine.call_fn_with_options::<Dynamic>(
+            CallFnOptions::new().eval_ast(false),
+            &mut Scope::new(),
+            &script.ast,
+            hook.name(),
+            (todo,),
+        )
+    });
+    // The engine holds clones of `effects` in its callbacks.
+    drop(engine);
+
+    let (status, error) = match &result {
+        Ok(_) => (Status::Ok, None),
+        Err(e) => {
+            let status = match e.unwrap_inner() {
+                EvalAltResult::ErrorTerminated(..) => Status::Timeout,
+                EvalAltResult::ErrorTooManyOperations(..)
+                | EvalAltResult::ErrorTooManyVariables(..)
+                | EvalAltResult::ErrorStackOverflow(..)
+                | EvalAltResult::ErrorDataTooLarge(..) => Status::LimitExceeded,
+                _ => Status::Error,
+            };
+            (status, Some(e.to_string()))
+        }
+    };
+    let mut effects = Arc::try_unwrap(effects)
+        .map(|effects| effects.into_inner().unwrap())
+        .unwrap_or_default();
+    let execution = Execution {
+        script_id: script.id.clone(),
+        hook,
+        todo_id: todo.id.to_string(),
+        started_at,
+        duration_ms: started.elapsed().as_secs_f64() * 1000.0,
+        operations: operations.load(Ordering::Relaxed),
+        status,
+        error,
+        output: std::mem::take(&mut effects.output),
+        updated: false,
+        created: Vec::new(),
+        request_id: None,
+    };
+    (execution, result.is_ok().then_some(effects))
+}
+
+/// Stored scripts, the audit log of their runs, and the listener that runs
+/// them.
+pub struct ScriptService {
+    scripts: Mutex<HashMap<String, Script>>,
+    /// Oldest first.
+    executions: Mutex<VecDeque<Execution>>,
+    /// Todo versions the scripts wrote themselves. Their events are not
+    /// handled, so scripts can't set each other off in a loop.
+    caused: Mutex<HashSet<(String, DateTime<Utc>)>>,
+}
+
+impl ScriptService {
+    pub fn new() -> Self {
+        ScriptService {
+            scripts: Mutex::new(HashMap::new()),
+            executions: Mutex::new(VecDeque::new()),
+            caused: Mutex::new(HashSet::new()),
+        }
+    }
+
+    /// Compiles and stores a script, which must define at least one hook.
+    pub fn create(&self, input: ScriptCreate) -> Result<Script, String> {
+        let name = input.name.trim();
+        if name.is_empty() {
+            return Err("Script name must not be empty".to_string());
+        }
+        if input.source.len() > MAX_SOURCE_BYTES {
+            return Err(format!(
+                "Script source must be at most {} bytes",
+                MAX_SOURCE_BYTES
+            ));
+        }
+        let ast = sandbox()
+            .compile(&input.source)
+            .map_err(|e| format!("Script does not compile: {}", e))?;
+        let hooks: Vec<Hook> = Hook::ALL
+            .into_iter()
+            .filter(|hook| {
+                ast.iter_functions()
+                    .any(|f| f.name == hook.name() && f.params.len() == 1)
+            })
+            .collect();
+        if hooks.is_empty() {
+            return Err("Script must define on_create(todo) or on_complete(todo)".to_string());
+        }
+
+        let mut scripts = self.scripts.lock().unwrap();
+        if scripts.len() >= MAX_SCRIPTS {
+            return Err(format!("At most {} scripts can be stored", MAX_SCRIPTS));
+        }
+        let script = Script {
+            id: Uuid::new_v4().to_string(),
+            name: name.to_string(),
+            source: input.source,
+            hooks,
+            created_at: Utc::now(),
+            ast: Arc::new(ast),
+        };
+        scripts.insert(script.id.clone(), script.clone());
+        Ok(script)
+    }
+
+    pub fn get_all(&self) -> Vec<Script> {
+        let mut scripts: Vec<Script> = self.scripts.lock().unwrap().values().cloned().collect();
+        scripts.sort_by_key(|script| script.created_at);
+        scripts
+    }
+
+    pub fn get_by_id(&self, id: &str) -> Option<Script> {
+        self.scripts.lock().unwrap().get(id).cloned()
+    }
+
+    /// Removes the script. Its runs stay in the audit log.
+    pub fn delete(&self, id: &str) -> bool {
+        self.scripts.lock().unwrap().remove(id).is_some()
+    }
+
+    /// The logged runs of one script, newest first.
+    pub fn executions(&self, script_id: &str) -> Vec<Execution> {
+        self.executions
+            .lock()
+            .unwrap()
+            .iter()
+            .rev()
+            .filter(|execution| execution.script_id == script_id)
+            .cloned()
+            .collect()
+    }
+
+    /// Runs the scripts hooked to `event`, oldest script first, each seeing
+    /// the todo as the ones before left it. Runs are off the async workers,
+    /// since one can take up to `TIME_LIMIT`. Returns how many ran.
+    pub async fn handle(
+        &self,
+        service: &TodoService,
+        moderation: &Moderation,
+        event: &Event,
+    ) -> usize {
+        let Some(hook) = Hook::for_event(event.event_type) else {
+            return 0;
+        };
+        let version = (event.todo_id.clone(), event.todo.updated_at);
+        if self.caused.lock().unwrap().remove(&version) {
+            return 0;
+        }
+
+        let mut ran = 0;
+        for script in self.get_all() {
+            if !script.hooks.contains(&hook) {
+                continue;
+            }
+            let Some(todo) = service.get_by_id(models::todo_id(&event.todo_id)) else {
+                break;
+            };
+            let ran_on = todo.clone();
+            let (mut execution, effects) =
+                match web::block(move || run(&script, hook, &ran_on)).await {
+                    Ok(outcome) => outcome,
+                    Err(e) => {
+                        eprintln!("Script run failed: {}", e);
+                        continue;
+                    }
+                };
+            execution.request_id = event.request_id.clone();
+            if let Some(mut effects) = effects {
+                // Like a failed run, one asking to save refused text
+                // changes nothing.
+                match effects.moderate(moderation).await {
+                    Ok(()) => event
+                        .context()
+                        .sync_scope(|| self.apply(service, &todo, effects, &mut execution)),
+                    Err(e) => {
+                        execution.status = Status::Error;
+                        execution.error = Some(e.to_string());
+                    }
+                }
+            }
+            let mut executions = self.executions.lock().unwrap();
+            if executions.len() >= MAX_EXECUTIONS {
+                executions.pop_front();
+            }
+            executions.push_back(execution);
+            ran += 1;
+        }
+        ran
+    }
+
+    fn apply(
+        &self,
+        service: &TodoService,
+        todo: &Todo,
+        effects: Effects,
+        execution: &mut Execution,
+    ) {
+        let mut written = Vec::new();
+        if let Some(update) = effects.update {
+            if let Some(updated) = service.update(todo.id, update) {
+                written.push((updated.id.to_string(), updated.updated_at));
+                execution.updated = true;
+            }
+        }
+        let today = Utc::now().date_naive();
+        for line in effects.creates {
+            let created = service.create(quick_add::parse(&line, today).into_create());
+            written.push((created.id.to_string(), created.updated_at));
+            execution.created.push(created.id);
+        }
+
+        let mut caused = self.caused.lock().unwrap();
+        // Entries only linger if their events were lost to lag.
+        if caused.len() > MAX_EXECUTIONS {
+            caused.clear();
+        }
+        caused.extend(written);
+    }
+}
+
+impl Default for ScriptService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Runs scripts for todo events.
+pub async fn run_listener(
+    scripts: web::Data<ScriptService>,
+    service: web::Data<TodoService>,
+    moderation: web::Data<Moderation>,
+    mut receiver: broadcast::Receiver<Event>,
+) {
+    loop {
+        let event = match receiver.recv().await {
+            Ok(event) => event,
+            Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                eprintln!("Script listener lagged, skipped {} events", skipped);
+                continue;
+            }
+            Err(broadcast::error::RecvError::Closed) => break,
+        };
+        scripts.handle(&service, &moderation, &event).await;
+    }
+}
+
+pub struct ScriptsPlugin;
+
+impl Plugin for ScriptsPlugin {
+    fn name(&self) -> &'static str {
+        "scripts"
+    }
+
+    fn kind(&self) -> Kind {
+        Kind::Automation
+    }
+
+    fn description(&self) -> &'static str {
+        "Runs sandboxed Rhai scripts when todos are created or completed"
+    }
+
+    fn enabled(&self, _config: &Config) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::moderation::{ModerationMode, WordList};
+    use spicy_todo_core::models::{Priority, TodoCreate};
+
+    fn create_todo(service: &TodoService, text: &str) -> Todo {
+        service.create(TodoCreate {
+            text: text.to_string(),
+            ..Default::default()
+        })
+    }
+
+    fn script(scripts: &ScriptService, source: &str) -> Script {
+        scripts
+            .create(ScriptCreate {
+                name: "test".to_string(),
+                source: source.to_string(),
+            })
+            .unwrap()
+    }
+
+    /// Handles every event waiting on `receiver`, as the listener would.
+    async fn drain(
+        scripts: &ScriptService,
+        service: &TodoService,
+        moderation: &Moderation,
+        receiver: &mut broadcast::Receiver<Event>,
+    ) {
+        while let Ok(event) = receiver.try_recv() {
+            scripts.handle(service, moderation, &event).await;
+        }
+    }
+
+    #[test]
+    fn test_create_requires_a_hook() {
+        let scripts = ScriptService::new();
+        let create = |source: &str| {
+            scripts.create(ScriptCreate {
+                name: "test".to_string(),
+                source: source.to_string(),
+            })
+        };
+        assert!(create("fn helper(x) { x }")
+            .unwrap_err()
+            .contains("on_create"));
+        assert!(create("fn on_create(todo) {")
+            .unwrap_err()
+            .contains("compile"));
+        let both = create("fn on_create(todo) {} fn on_complete(todo) {}").unwrap();
+        assert_eq!(both.hooks, vec![Hook::OnCreate, Hook::OnComplete]);
+    }
+
+    #[actix_web::test]
+    async fn test_hooks_update_and_create_without_looping() {
+        let service = TodoService::new_empty();
+        let scripts = ScriptService::new();
+        let mut receiver = service.events().subscribe();
+        let urgent = script(
+            &scripts,
+            r#"
+            fn on_create(todo) {
+                if todo.text.contains("urgent") {
+                    update(#{ priority: "high" });
+                    print("escalated " + todo.text);
+                }
+                create("Follow up on " + todo.text);
+            }
+            "#,
+        );
+
+        let todo = create_todo(&service, "urgent invoice");
+        drain(&scripts, &service, &Moderation::default(), &mut receiver).await;
+
+        assert_eq!(service.get_by_id(todo.id).unwrap().priority, Priority::High);
+        // The follow-up's own creation doesn't run the script again.
+        let todos = service.get_all(None, None, None);
+        assert_eq!(todos.len(), 2);
+        let executions = scripts.executions(&urgent.id);
+        assert_eq!(executions.len(), 1);
+        assert_eq!(executions[0].status, Status::Ok);
+        assert!(executions[0].updated);
+        assert_eq!(executions[0].created.len(), 1);
+        assert_eq!(executions[0].output, vec!["escalated urgent invoice"]);
+    }
+
+    #[actix_web::test]
+    async fn test_limits_stop_runaway_scripts() {
+        let service = TodoService::new_empty();
+        let scripts = ScriptService::new();
+        let mut receiver = service.events().subscribe();
+        let spin = script(
+            &scripts,
+            r#"fn on_create(todo) { update(#{ completed: true }); loop {} }"#,
+        );
+        let grow = script(
+            &scripts,
+            r#"fn on_create(todo) { let s = "x"; loop { s += s; } }"#,
+        );
+        let fail = script(
+            &scripts,
+            r#"fn on_create(todo) { update(#{ dueDate: "someday" }); }"#,
+        );
+        let import = script(
+            &scripts,
+            r#"fn on_create(todo) { import "/etc/passwd" as p; }"#,
+        );
+
+        let todo = create_todo(&service, "Pay rent");
+        drain(&scripts, &service, &Moderation::default(), &mut receiver).await;
+
+        let status = |script: &Script| scripts.executions(&script.id)[0].status;
+        assert_eq!(status(&spin), Status::LimitExceeded);
+        assert_eq!(status(&grow), Status::LimitExceeded);
+        assert_eq!(status(&fail), Status::Error);
+        assert_eq!(status(&import), Status::Error);
+        assert!(scripts.executions(&spin.id)[0].operations >= MAX_OPERATIONS);
+        // A failed run changes nothing.
+        assert!(!service.get_by_id(todo.id).unwrap().completed);
+    }
+
+    #[actix_web::test]
+    async fn test_moderation_refuses_script_text() {
+        let service = TodoService::new_empty();
+        let scripts = ScriptService::new();
+        let mut receiver = service.events().subscribe();
+        let words = || vec![Box::new(WordList::new(&["spinach".to_string()])) as Box<_>];
+        let rename = script(
+            &scripts,
+            r#"fn on_create(todo) { update(#{ text: "Eat spinach" }); }"#,
+        );
+        let follow_up = script(
+            &scripts,
+            r#"fn on_create(todo) { create("More spinach !high"); }"#,
+        );
+
+        let reject = Moderation::new(ModerationMode::Reject, words());
+        let todo = create_todo(&service, "Dinner");
+        drain(&scripts, &service, &reject, &mut receiver).await;
+        assert_eq!(service.get_by_id(todo.id).unwrap().text, "Dinner");
+        assert_eq!(service.get_all(None, None, None).len(), 1);
+        for script in [&rename, &follow_up] {
+            let execution = &scripts.executions(&script.id)[0];
+            assert_eq!(execution.status, Status::Error);
+            assert!(!execution.updated && execution.created.is_empty());
+        }
+
+        let mask = Moderation::new(ModerationMode::Mask, words());
+        let todo = create_todo(&service, "Lunch");
+        drain(&scripts, &service, &mask, &mut receiver).await;
+        assert_eq!(service.get_by_id(todo.id).unwrap().text, "Eat *******");
+        let created = &scripts.executions(&follow_up.id)[0].created;
+        assert_eq!(service.get_by_id(created[0]).unwrap().text, "More *******");
+    }
+}
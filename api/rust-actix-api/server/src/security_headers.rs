@@ -0,0 +1,55 @@
+use crate::config::Config;
+use actix_web::body::{BoxBody, MessageBody};
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::header::{self, HeaderName, HeaderValue};
+use actix_web::middleware::Next;
+use actix_web::web;
+
+/// Adds the configured security headers to every response, errors and
+/// preflights included. Headers a handler set itself are left alone.
+pub async fn add_security_headers(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<BoxBody>, actix_web::Error> {
+    let settings = req
+        .app_data::<web::Data<Config>>()
+        .map(|config| config.security_headers.clone())
+        .unwrap_or_default();
+    if !settings.enabled {
+        return Ok(next.call(req).await?.map_into_boxed_body());
+    }
+    let https = req.connection_info().scheme() == "https";
+    let mut res = next.call(req).await?.map_into_boxed_body();
+
+    let mut headers = vec![
+        (header::X_CONTENT_TYPE_OPTIONS, "nosniff".to_string()),
+        (header::X_FRAME_OPTIONS, "DENY".to_string()),
+        (header::REFERRER_POLICY, settings.referrer_policy),
+        (
+            header::CONTENT_SECURITY_POLICY,
+            settings.content_security_policy,
+        ),
+    ];
+    if https && !settings.hsts_max_age.is_zero() {
+        headers.push((
+            header::STRICT_TRANSPORT_SECURITY,
+            format!("max-age={}", settings.hsts_max_age.as_secs()),
+        ));
+    }
+    for (name, value) in headers {
+        insert_missing(res.headers_mut(), name, &value);
+    }
+    Ok(res)
+}
+
+fn insert_missing(headers: &mut header::HeaderMap, name: HeaderName, value: &str) {
+    if headers.contains_key(&name) {
+        return;
+    }
+    match HeaderValue::from_str(value) {
+        Ok(value) => {
+            headers.insert(name, value);
+        }
+        Err(_) => eprintln!("⚠️ Invalid {} header value: {}", name, value),
+    }
+}
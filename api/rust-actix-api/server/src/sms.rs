@@ -0,0 +1,529 @@
+use crate::config::{Config, SmsSettings};
+use crate::plugins::{Kind, Plugin};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Mutex;
+
+const SEND_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+/// How long a verification code can be used.
+const CODE_TTL_MINUTES: i64 = 10;
+/// Wrong guesses allowed per code before a new one must be requested.
+const MAX_CODE_ATTEMPTS: u32 = 5;
+/// One GSM-7 segment; longer bodies are cut so each message costs one send.
+const MAX_BODY_CHARS: usize = 160;
+
+pub type SendFuture<'a> = Pin<Box<dyn Future<Output = Result<(), String>> + 'a>>;
+
+/// Hands a text message to a provider. Implementations make one attempt;
+/// the caller decides whether a failure is worth another paid send.
+pub trait SmsGateway: Send + Sync {
+    fn send<'a>(&'a self, to: &'a str, body: &'a str) -> SendFuture<'a>;
+}
+
+/// The Twilio Messages API, or anything speaking the same protocol.
+pub struct TwilioGateway {
+    messages_url: String,
+    account_sid: String,
+    auth_token: String,
+    from: String,
+}
+
+impl TwilioGateway {
+    pub fn new(base_url: &str, account_sid: &str, auth_token: &str, from: &str) -> Self {
+        TwilioGateway {
+            messages_url: format!(
+                "{}/2010-04-01/Accounts/{}/Messages.json",
+                base_url.trim_end_matches('/'),
+                account_sid
+            ),
+            account_sid: account_sid.to_string(),
+            auth_token: auth_token.to_string(),
+            from: from.to_string(),
+        }
+    }
+}
+
+impl SmsGateway for TwilioGateway {
+    fn send<'a>(&'a self, to: &'a str, body: &'a str) -> SendFuture<'a> {
+        Box::pin(async move {
+            let client = awc::Client::builder().timeout(SEND_TIMEOUT).finish();
+            let mut response = client
+                .post(&self.messages_url)
+                .basic_auth(&self.account_sid, &self.auth_token)
+                .send_form(&[("To", to), ("From", self.from.as_str()), ("Body", body)])
+                .await
+                .map_err(|e| format!("SMS gateway unreachable: {}", e))?;
+            if response.status().is_success() {
+                return Ok(());
+            }
+            let detail = response.body().await.unwrap_or_default();
+            Err(format!(
+                "SMS gateway responded with {}: {}",
+                response.status(),
+                String::from_utf8_lossy(&detail)
+            ))
+        })
+    }
+}
+
+/// Caps on messages sent, counting every attempt since each one may be
+/// billed. Verification codes count too.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimits {
+    pub per_number_per_hour: usize,
+    pub per_day: usize,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum SmsError {
+    InvalidPhone(String),
+    NoPendingVerification,
+    CodeExpired,
+    WrongCode,
+    TooManyAttempts,
+    RateLimited { retry_after: Duration },
+    Gateway(String),
+}
+
+impl std::fmt::Display for SmsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SmsError::InvalidPhone(phone) => write!(
+                f,
+                "Invalid phone number '{}': expected E.164, e.g. +14155550100",
+                phone
+            ),
+            SmsError::NoPendingVerification => write!(f, "No verification is pending"),
+            SmsError::CodeExpired => write!(f, "Verification code expired; request a new one"),
+            SmsError::WrongCode => write!(f, "Wrong verification code"),
+            SmsError::TooManyAttempts => {
+                write!(f, "Too many wrong codes; request a new one")
+            }
+            SmsError::RateLimited { retry_after } => write!(
+                f,
+                "SMS rate limit reached; retry in {} seconds",
+                retry_after.num_seconds()
+            ),
+            SmsError::Gateway(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct PendingCode {
+    code: String,
+    expires_at: DateTime<Utc>,
+    attempts: u32,
+}
+
+#[derive(Debug, Clone)]
+struct Subscriber {
+    phone: String,
+    verified_at: Option<DateTime<Utc>>,
+    pending: Option<PendingCode>,
+}
+
+/// What a client sees of its own subscription.
+#[derive(Debug, Serialize, PartialEq)]
+pub struct SubscriptionView {
+    /// All but the last four digits masked.
+    pub phone: String,
+    pub verified: bool,
+    #[serde(rename = "verifiedAt")]
+    pub verified_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SubscribeRequest {
+    pub phone: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct VerifyRequest {
+    pub code: String,
+}
+
+#[derive(Default)]
+struct State {
+    /// By client id, as sent in `X-Client-Id`.
+    subscribers: HashMap<String, Subscriber>,
+    /// Every send in the last day, oldest first.
+    sent: VecDeque<(DateTime<Utc>, String)>,
+}
+
+/// SMS delivery for urgent reminders. Each client registers one phone
+/// number, which only receives reminders once it has confirmed a code sent
+/// to it.
+pub struct SmsService {
+    gateway: Box<dyn SmsGateway>,
+    limits: RateLimits,
+    state: Mutex<State>,
+}
+
+impl SmsService {
+    pub fn new(gateway: Box<dyn SmsGateway>, limits: RateLimits) -> Self {
+        SmsService {
+            gateway,
+            limits,
+            state: Mutex::new(State::default()),
+        }
+    }
+
+    pub fn from_settings(settings: &SmsSettings) -> Result<Self, String> {
+        let auth_token = settings
+            .auth_token
+            .as_deref()
+            .ok_or("SMS_AUTH_TOKEN is required when SMS is enabled")?;
+        let from = settings
+            .from
+            .as_deref()
+            .ok_or("SMS_FROM is required when SMS is enabled")?;
+        let from = normalize_phone(from).map_err(|e| format!("SMS_FROM: {}", e))?;
+        let gateway = TwilioGateway::new(
+            &settings.gateway_url,
+            &settings.account_sid,
+            auth_token,
+            &from,
+        );
+        Ok(SmsService::new(Box::new(gateway), settings.limits))
+    }
+
+    pub fn subscription(&self, client: &str) -> Option<SubscriptionView> {
+        let state = self.state.lock().unwrap();
+        state
+            .subscribers
+            .get(client)
+            .map(|subscriber| SubscriptionView {
+                phone: mask(&subscriber.phone),
+                verified: subscriber.verified_at.is_some(),
+                verified_at: subscriber.verified_at,
+            })
+    }
+
+    /// Registers `phone` for `client` and texts it a verification code.
+    /// Replaces any earlier number, which stops receiving reminders at once.
+    pub async fn subscribe(
+        &self,
+        client: &str,
+        phone: &str,
+        now: DateTime<Utc>,
+    ) -> Result<(), SmsError> {
+        let phone = normalize_phone(phone)?;
+        let code = format!("{:06}", rand::random_range(0..1_000_000));
+        {
+            let mut state = self.state.lock().unwrap();
+            self.reserve(&mut state, &phone, now)?;
+            state.subscribers.insert(
+                client.to_string(),
+                Subscriber {
+                    phone: phone.clone(),
+                    verified_at: None,
+                    pending: Some(PendingCode {
+                        code: code.clone(),
+                        expires_at: now + Duration::minutes(CODE_TTL_MINUTES),
+                        attempts: 0,
+                    }),
+                },
+            );
+        }
+        let body = format!("Your Spicy Todo verification code is {}", code);
+        self.gateway
+            .send(&phone, &body)
+            .await
+            .map_err(SmsError::Gateway)
+    }
+
+    /// Confirms the code sent by `subscribe`.
+    pub fn verify(&self, client: &str, code: &str, now: DateTime<Utc>) -> Result<(), SmsError> {
+        let mut state = self.state.lock().unwrap();
+        let subscriber = state
+            .subscribers
+            .get_mut(client)
+            .ok_or(SmsError::NoPendingVerification)?;
+        let pending = subscriber
+            .pending
+            .as_mut()
+            .ok_or(SmsError::NoPendingVerification)?;
+        if now >= pending.expires_at {
+            return Err(SmsError::CodeExpired);
+        }
+        if pending.attempts >= MAX_CODE_ATTEMPTS {
+            return Err(SmsError::TooManyAttempts);
+        }
+        if pending.code != code.trim() {
+            pending.attempts += 1;
+            return Err(SmsError::WrongCode);
+        }
+        subscriber.pending = None;
+        subscriber.verified_at = Some(now);
+        Ok(())
+    }
+
+    pub fn unsubscribe(&self, client: &str) -> bool {
+        self.state
+            .lock()
+            .unwrap()
+            .subscribers
+            .remove(client)
+            .is_some()
+    }
+
+    /// Texts `body` to every verified number, once per number even when
+    /// several clients registered it. Numbers over their limit, and those of
+    /// `muted` clients, are skipped.
+    /// Returns how many messages were handed to the gateway.
+    pub async fn broadcast(
+        &self,
+        body: &str,
+        now: DateTime<Utc>,
+        muted: &HashSet<String>,
+    ) -> usize {
+        let body: String = body.chars().take(MAX_BODY_CHARS).collect();
+        let mut phones: Vec<String> = {
+            let state = self.state.lock().unwrap();
+            state
+                .subscribers
+                .iter()
+                .filter(|(client, subscriber)| {
+                    subscriber.verified_at.is_some() && !muted.contains(*client)
+                })
+                .map(|(_, subscriber)| subscriber.phone.clone())
+                .collect()
+        };
+        phones.sort();
+        phones.dedup();
+
+        let mut sent = 0;
+        for phone in phones {
+            let reserved = self.reserve(&mut self.state.lock().unwrap(), &phone, now);
+            if let Err(e) = reserved {
+                eprintln!("Skipping SMS to {}: {}", mask(&phone), e);
+                continue;
+            }
+            match self.gateway.send(&phone, &body).await {
+                Ok(()) => sent += 1,
+                Err(e) => eprintln!("SMS to {} failed: {}", mask(&phone), e),
+            }
+        }
+        sent
+    }
+
+    /// Counts a send to `phone` against the limits, or says how long until
+    /// one is allowed.
+    fn reserve(&self, state: &mut State, phone: &str, now: DateTime<Utc>) -> Result<(), SmsError> {
+        let day_ago = now - Duration::days(1);
+        while state.sent.front().is_some_and(|(at, _)| *at <= day_ago) {
+            state.sent.pop_front();
+        }
+        if state.sent.len() >= self.limits.per_day {
+            let oldest = state.sent.front().map_or(now, |(at, _)| *at);
+            return Err(SmsError::RateLimited {
+                retry_after: oldest + Duration::days(1) - now,
+            });
+        }
+        let hour_ago = now - Duration::hours(1);
+        let recent: Vec<DateTime<Utc>> = state
+            .sent
+            .iter()
+            .filter(|(at, to)| *at > hour_ago && to == phone)
+            .map(|(at, _)| *at)
+            .collect();
+        if recent.len() >= self.limits.per_number_per_hour {
+            let oldest = recent.first().copied().unwrap_or(now);
+            return Err(SmsError::RateLimited {
+                retry_after: oldest + Duration::hours(1) - now,
+            });
+        }
+        state.sent.push_back((now, phone.to_string()));
+        Ok(())
+    }
+}
+
+/// Strips spaces, dashes, dots and parentheses, then requires E.164: a `+`
+/// and 8 to 15 digits.
+fn normalize_phone(phone: &str) -> Result<String, SmsError> {
+    let compact: String = phone
+        .chars()
+        .filter(|c| !matches!(c, ' ' | '-' | '.' | '(' | ')'))
+        .collect();
+    let valid = compact.strip_prefix('+').is_some_and(|digits| {
+        (8..=15).contains(&digits.len()) && digits.bytes().all(|b| b.is_ascii_digit())
+    });
+    if valid {
+        Ok(compact)
+    } else {
+        Err(SmsError::InvalidPhone(phone.to_string()))
+    }
+}
+
+fn mask(phone: &str) -> String {
+    let keep = phone.len().saturating_sub(4);
+    format!("{}{}", "*".repeat(keep), &phone[keep..])
+}
+
+#[cfg(test)]
+pub mod testing {
+    use super::*;
+    use std::sync::Arc;
+
+    /// Records messages instead of sending them.
+    #[derive(Clone, Default)]
+    pub struct RecordingGateway {
+        pub sent: Arc<Mutex<Vec<(String, String)>>>,
+    }
+
+    impl RecordingGateway {
+        /// The code in the last message to `phone`.
+        pub fn last_code(&self, phone: &str) -> Option<String> {
+            let sent = self.sent.lock().unwrap();
+            let (_, body) = sent.iter().rev().find(|(to, _)| to == phone)?;
+            body.rsplit(' ').next().map(str::to_string)
+        }
+    }
+
+    impl SmsGateway for RecordingGateway {
+        fn send<'a>(&'a self, to: &'a str, body: &'a str) -> SendFuture<'a> {
+            self.sent
+                .lock()
+                .unwrap()
+                .push((to.to_string(), body.to_string()));
+            Box::pin(async { Ok(()) })
+        }
+    }
+}
+
+pub struct SmsPlugin;
+
+impl Plugin for SmsPlugin {
+    fn name(&self) -> &'static str {
+        "sms"
+    }
+
+    fn kind(&self) -> Kind {
+        Kind::Notifier
+    }
+
+    fn description(&self) -> &'static str {
+        "Texts urgent reminders through an SMS gateway"
+    }
+
+    fn enabled(&self, config: &Config) -> bool {
+        config.sms.is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::testing::RecordingGateway;
+    use super::*;
+
+    const PHONE: &str = "+14155550100";
+
+    fn service(limits: RateLimits) -> (SmsService, RecordingGateway) {
+        let gateway = RecordingGateway::default();
+        (SmsService::new(Box::new(gateway.clone()), limits), gateway)
+    }
+
+    fn generous() -> RateLimits {
+        RateLimits {
+            per_number_per_hour: 10,
+            per_day: 100,
+        }
+    }
+
+    #[test]
+    fn test_normalize_phone() {
+        assert_eq!(normalize_phone("+1 (415) 555-0100"), Ok(PHONE.to_string()));
+        assert!(normalize_phone("4155550100").is_err());
+        assert!(normalize_phone("+1415abc0100").is_err());
+        assert!(normalize_phone("+123").is_err());
+        assert_eq!(mask(PHONE), "********0100");
+    }
+
+    #[actix_web::test]
+    async fn test_only_verified_numbers_get_reminders() {
+        let (sms, gateway) = service(generous());
+        let now = Utc::now();
+        sms.subscribe("phone-app", "+1 415 555 0100", now)
+            .await
+            .unwrap();
+        assert_eq!(sms.broadcast("Pay rent", now, &HashSet::new()).await, 0);
+
+        let code = gateway.last_code(PHONE).unwrap();
+        assert_eq!(
+            sms.verify("phone-app", "000000x", now),
+            Err(SmsError::WrongCode)
+        );
+        sms.verify("phone-app", &code, now).unwrap();
+        assert!(sms.subscription("phone-app").unwrap().verified);
+        assert_eq!(
+            sms.verify("phone-app", &code, now),
+            Err(SmsError::NoPendingVerification)
+        );
+
+        // A second client on the same number still gets one message.
+        sms.subscribe("web-app", PHONE, now).await.unwrap();
+        let code = gateway.last_code(PHONE).unwrap();
+        sms.verify("web-app", &code, now).unwrap();
+        assert_eq!(
+            sms.broadcast(&"x".repeat(300), now, &HashSet::new()).await,
+            1
+        );
+        let last = gateway.sent.lock().unwrap().last().cloned().unwrap();
+        assert_eq!(last.1.len(), MAX_BODY_CHARS);
+
+        assert!(sms.unsubscribe("web-app"));
+        assert!(sms.unsubscribe("phone-app"));
+        assert_eq!(sms.broadcast("Pay rent", now, &HashSet::new()).await, 0);
+    }
+
+    #[actix_web::test]
+    async fn test_codes_expire_and_lock_after_attempts() {
+        let (sms, gateway) = service(generous());
+        let now = Utc::now();
+        sms.subscribe("a", PHONE, now).await.unwrap();
+        let code = gateway.last_code(PHONE).unwrap();
+        let later = now + Duration::minutes(CODE_TTL_MINUTES);
+        assert_eq!(sms.verify("a", &code, later), Err(SmsError::CodeExpired));
+
+        sms.subscribe("a", PHONE, now).await.unwrap();
+        let code = gateway.last_code(PHONE).unwrap();
+        for _ in 0..MAX_CODE_ATTEMPTS {
+            assert_eq!(sms.verify("a", "wrong", now), Err(SmsError::WrongCode));
+        }
+        assert_eq!(sms.verify("a", &code, now), Err(SmsError::TooManyAttempts));
+    }
+
+    #[actix_web::test]
+    async fn test_rate_limits() {
+        let (sms, gateway) = service(RateLimits {
+            per_number_per_hour: 2,
+            per_day: 3,
+        });
+        let now = Utc::now();
+        sms.subscribe("a", PHONE, now).await.unwrap();
+        sms.subscribe("a", PHONE, now).await.unwrap();
+        let limited = sms.subscribe("a", PHONE, now).await;
+        assert_eq!(
+            limited,
+            Err(SmsError::RateLimited {
+                retry_after: Duration::hours(1)
+            })
+        );
+
+        // The hourly limit is per number; the daily one is for everyone.
+        let other = "+442071838750";
+        sms.subscribe("b", other, now).await.unwrap();
+        assert!(matches!(
+            sms.subscribe("c", "+33142685300", now).await,
+            Err(SmsError::RateLimited { .. })
+        ));
+        assert_eq!(gateway.sent.lock().unwrap().len(), 3);
+
+        let tomorrow = now + Duration::days(1);
+        sms.subscribe("c", "+33142685300", tomorrow).await.unwrap();
+    }
+}
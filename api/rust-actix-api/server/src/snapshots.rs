@@ -0,0 +1,106 @@
+use crate::scheduler::{Outcome, Schedule, Scheduler};
+use actix_web::web;
+use spicy_todo_core::encryption::TextCipher;
+use spicy_todo_core::snapshot;
+use spicy_todo_core::TodoService;
+use std::cell::Cell;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::time::Duration;
+
+/// Writes the collection to `path` every `interval`, skipping runs where
+/// nothing changed since the last write, and once more on shutdown. With
+/// `cipher`, todo text is sealed in the file.
+pub fn schedule(
+    scheduler: &mut Scheduler,
+    service: web::Data<TodoService>,
+    path: PathBuf,
+    interval: Duration,
+    cipher: Option<TextCipher>,
+) {
+    // The boot state was just loaded from `path`.
+    let saved_version = Rc::new(Cell::new(service.collection_version().version));
+    let (job_service, job_path, job_cipher) = (service.clone(), path.clone(), cipher.clone());
+    scheduler.register("snapshot", Schedule::Every(interval), move || {
+        let (service, path, cipher) = (job_service.clone(), job_path.clone(), job_cipher.clone());
+        let saved_version = saved_version.clone();
+        async move {
+            let version = service.collection_version().version;
+            if version == saved_version.get() {
+                return Ok(Outcome::Skipped);
+            }
+            let target = path.clone();
+            web::block(move || save(&service, &target, cipher.as_ref()))
+                .await
+                .map_err(io::Error::other)
+                .and_then(|saved| saved)
+                .map_err(|e| format!("snapshot to {}: {}", path.display(), e))?;
+            saved_version.set(version);
+            Ok(Outcome::Done)
+        }
+    });
+    scheduler.on_shutdown("snapshot", move || async move {
+        save(&service, &path, cipher.as_ref())
+            .map_err(|e| format!("snapshot to {}: {}", path.display(), e))?;
+        println!("💾 Saved snapshot to {}", path.display());
+        Ok(())
+    });
+}
+
+pub fn save(service: &TodoService, path: &Path, cipher: Option<&TextCipher>) -> io::Result<()> {
+    let mut todos = service.get_all(None, None, None);
+    if let Some(cipher) = cipher {
+        todos = todos.iter().map(|todo| cipher.seal_todo(todo)).collect();
+    }
+    snapshot::save(path, &todos)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spicy_todo_core::models::TodoCreate;
+
+    #[actix_web::test]
+    async fn test_snapshotter_writes_after_changes() {
+        let dir = std::env::temp_dir().join(format!("spicy-snapshots-{}", uuid::Uuid::new_v4()));
+        let path = dir.join("todos.json");
+        let service = web::Data::new(TodoService::new_empty());
+        let mut scheduler = Scheduler::new(None);
+        schedule(
+            &mut scheduler,
+            service.clone(),
+            path.clone(),
+            Duration::from_millis(10),
+            None,
+        );
+        let running = scheduler.start();
+
+        tokio::time::sleep(Duration::from_millis(30)).await;
+        assert!(!path.exists(), "unchanged collection should not be written");
+
+        service.create(TodoCreate {
+            text: "Survives reboot".to_string(),
+            ..Default::default()
+        });
+        let mut loaded = Vec::new();
+        for _ in 0..100 {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            loaded = snapshot::load(&path).unwrap();
+            if !loaded.is_empty() {
+                break;
+            }
+        }
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(loaded[0].text, "Survives reboot");
+
+        // Changes after the last periodic write are saved on shutdown.
+        service.create(TodoCreate {
+            text: "Written on the way out".to_string(),
+            ..Default::default()
+        });
+        running.shutdown().await;
+        assert_eq!(snapshot::load(&path).unwrap().len(), 2);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
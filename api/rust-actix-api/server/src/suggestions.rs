@@ -0,0 +1,263 @@
+use crate::config::{Config, LlmSettings};
+use crate::plugins::{Kind, Plugin};
+use chrono::NaiveDate;
+use serde::Serialize;
+use serde_json::{json, Value};
+use spicy_todo_core::dates::parse_date;
+use spicy_todo_core::models::{Priority, Todo};
+use spicy_todo_core::triage::{self, Suggestion};
+use std::future::Future;
+use std::pin::Pin;
+
+const LLM_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(20);
+/// Most tags taken from a model's answer.
+const MAX_TAGS: usize = 5;
+
+const SYSTEM_PROMPT: &str = "You triage todo items. Reply with a JSON object with the keys \
+    \"priority\" (\"low\", \"medium\" or \"high\"), \"tags\" (up to 5 short lowercase words), \
+    \"dueDate\" (YYYY-MM-DD, or null when the text implies no date) and \"reason\" (one \
+    sentence). Reply with the JSON object only.";
+
+pub type SuggestFuture<'a> = Pin<Box<dyn Future<Output = Result<Suggestion, String>> + 'a>>;
+
+/// Suggests changes to a todo with a language model.
+pub trait SuggestionBackend: Send + Sync {
+    fn name(&self) -> &'static str;
+    fn suggest<'a>(&'a self, todo: &'a Todo, today: NaiveDate) -> SuggestFuture<'a>;
+}
+
+/// An OpenAI-compatible chat completions endpoint: OpenAI itself, or a
+/// local server such as Ollama or llama.cpp.
+pub struct ChatCompletions {
+    settings: LlmSettings,
+}
+
+impl ChatCompletions {
+    pub fn new(settings: LlmSettings) -> Self {
+        ChatCompletions { settings }
+    }
+}
+
+impl SuggestionBackend for ChatCompletions {
+    fn name(&self) -> &'static str {
+        "llm"
+    }
+
+    fn suggest<'a>(&'a self, todo: &'a Todo, today: NaiveDate) -> SuggestFuture<'a> {
+        Box::pin(async move {
+            let request = json!({
+                "model": self.settings.model,
+                "temperature": 0,
+                "response_format": { "type": "json_object" },
+                "messages": [
+                    { "role": "system", "content": SYSTEM_PROMPT },
+                    {
+                        "role": "user",
+                        "content": format!(
+                            "Today is {}. The todo was written on {}.\nTodo: {}",
+                            today,
+                            todo.created_at.date_naive(),
+                            todo.text
+                        )
+                    }
+                ]
+            });
+            let client = awc::Client::builder().timeout(LLM_TIMEOUT).finish();
+            let mut builder = client.post(&self.settings.url);
+            if let Some(api_key) = &self.settings.api_key {
+                builder = builder.bearer_auth(api_key);
+            }
+            let mut response = builder
+                .send_json(&request)
+                .await
+                .map_err(|e| format!("Language model unreachable: {}", e))?;
+            if !response.status().is_success() {
+                let detail = response.body().await.unwrap_or_default();
+                return Err(format!(
+                    "Language model responded with {}: {}",
+                    response.status(),
+                    String::from_utf8_lossy(&detail)
+                ));
+            }
+            let completion: Value = response
+                .json()
+                .limit(1 << 20)
+                .await
+                .map_err(|e| format!("Unreadable language model response: {}", e))?;
+            from_completion(&completion, todo, today)
+        })
+    }
+}
+
+/// Reads a chat completion into a suggestion, keeping only what the todo
+/// lacks, as the heuristics do. Anything malformed is dropped rather than
+/// passed on.
+pub fn from_completion(
+    completion: &Value,
+    todo: &Todo,
+    today: NaiveDate,
+) -> Result<Suggestion, String> {
+    let content = completion["choices"][0]["message"]["content"]
+        .as_str()
+        .ok_or("Language model response has no message")?;
+    let content = content
+        .trim()
+        .trim_start_matches("```json")
+        .trim_matches('`')
+        .trim();
+    let answer: Value = serde_json::from_str(content)
+        .map_err(|e| format!("Language model did not answer in JSON: {}", e))?;
+
+    let mut suggestion = Suggestion::default();
+    let priority = match answer["priority"]
+        .as_str()
+        .map(str::to_lowercase)
+        .as_deref()
+    {
+        Some("low") => Some(Priority::Low),
+        Some("medium") => Some(Priority::Medium),
+        Some("high") => Some(Priority::High),
+        _ => None,
+    };
+    suggestion.priority = priority.filter(|priority| *priority != todo.priority);
+    if todo.due_date.is_none() {
+        suggestion.due_date = answer["dueDate"]
+            .as_str()
+            .and_then(|date| parse_date(date, today))
+            .map(|date| date.format("%Y-%m-%d").to_string());
+    }
+    for tag in answer["tags"].as_array().into_iter().flatten() {
+        let tag: String = tag
+            .as_str()
+            .unwrap_or_default()
+            .trim_start_matches('#')
+            .to_lowercase()
+            .chars()
+            .filter(|c| c.is_alphanumeric() || *c == '-')
+            .collect();
+        let in_text = todo.text.to_lowercase().contains(&format!("#{}", tag));
+        if !tag.is_empty() && !in_text && !suggestion.tags.contains(&tag) {
+            suggestion.tags.push(tag);
+        }
+    }
+    suggestion.tags.truncate(MAX_TAGS);
+    if let Some(reason) = answer["reason"]
+        .as_str()
+        .filter(|reason| !reason.is_empty())
+    {
+        suggestion.reasons.push(reason.to_string());
+    }
+    Ok(suggestion)
+}
+
+/// Body of `POST /api/todos/{id}/suggest`.
+#[derive(Debug, Serialize)]
+pub struct SuggestionResponse {
+    #[serde(rename = "todoId")]
+    pub todo_id: String,
+    /// `llm` or `heuristic`.
+    pub source: &'static str,
+    #[serde(flatten)]
+    pub suggestion: Suggestion,
+    /// Why the language model wasn't used, when it failed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub warning: Option<String>,
+}
+
+/// Produces suggestions from the configured backend, falling back to the
+/// local heuristics without one or when it fails.
+#[derive(Default)]
+pub struct Suggester {
+    backend: Option<Box<dyn SuggestionBackend>>,
+}
+
+impl Suggester {
+    pub fn from_config(config: &Config) -> Self {
+        Suggester {
+            backend: config.suggest_llm.clone().map(|settings| {
+                Box::new(ChatCompletions::new(settings)) as Box<dyn SuggestionBackend>
+            }),
+        }
+    }
+
+    pub async fn suggest(&self, todo: &Todo, today: NaiveDate) -> SuggestionResponse {
+        let mut warning = None;
+        if let Some(backend) = &self.backend {
+            match backend.suggest(todo, today).await {
+                Ok(suggestion) => {
+                    return SuggestionResponse {
+                        todo_id: todo.id.to_string(),
+                        source: backend.name(),
+                        suggestion,
+                        warning: None,
+                    }
+                }
+                Err(e) => warning = Some(e),
+            }
+        }
+        SuggestionResponse {
+            todo_id: todo.id.to_string(),
+            source: "heuristic",
+            suggestion: triage::suggest(todo, today),
+            warning,
+        }
+    }
+}
+
+pub struct SuggestionsPlugin;
+
+impl Plugin for SuggestionsPlugin {
+    fn name(&self) -> &'static str {
+        "llm-suggestions"
+    }
+    fn kind(&self) -> Kind {
+        Kind::Assistant
+    }
+    fn description(&self) -> &'static str {
+        "Suggests priorities, tags and due dates with a language model"
+    }
+    fn enabled(&self, config: &Config) -> bool {
+        config.suggest_llm.is_some()
+    }
+    fn details(&self, config: &Config) -> Option<Value> {
+        config
+            .suggest_llm
+            .as_ref()
+            .map(|settings| json!({ "model": settings.model }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spicy_todo_core::models::TodoCreate;
+    use spicy_todo_core::TodoService;
+
+    #[test]
+    fn test_reads_completion_and_drops_what_is_malformed() {
+        let service = TodoService::new_empty();
+        let todo = service.create(TodoCreate {
+            text: "Renew passport #travel".to_string(),
+            priority: Some(Priority::High),
+            ..Default::default()
+        });
+        let today = NaiveDate::from_ymd_opt(2024, 6, 10).unwrap();
+        let answer = json!({
+            "priority": "High",
+            "tags": ["#Travel", "admin", "admin", "gov stuff!"],
+            "dueDate": "2024-07-01",
+            "reason": "Passports take weeks to renew."
+        });
+        let completion = json!({
+            "choices": [{ "message": { "content": format!("```json\n{}\n```", answer) } }]
+        });
+        let suggestion = from_completion(&completion, &todo, today).unwrap();
+        assert_eq!(suggestion.priority, None);
+        assert_eq!(suggestion.tags, vec!["admin", "govstuff"]);
+        assert_eq!(suggestion.due_date.as_deref(), Some("2024-07-01"));
+        assert_eq!(suggestion.reasons, vec!["Passports take weeks to renew."]);
+
+        let completion = json!({ "choices": [{ "message": { "content": "Sure! High." } }] });
+        assert!(from_completion(&completion, &todo, today).is_err());
+    }
+}
@@ -0,0 +1,404 @@
+use crate::config::{Config, TelegramSettings};
+use crate::matrix::{add, describe, find_active};
+use crate::moderation::Moderation;
+use crate::plugins::{Kind, Plugin};
+use actix_web::web;
+use chrono::{NaiveDate, Utc};
+use serde::Deserialize;
+use spicy_todo_core::digest::Agenda;
+use spicy_todo_core::models::{Todo, TodoUpdate};
+use spicy_todo_core::TodoService;
+use std::time::Duration;
+
+const SEND_TIMEOUT: Duration = Duration::from_secs(10);
+/// How long a `getUpdates` request waits for new messages before returning.
+const POLL_TIMEOUT: Duration = Duration::from_secs(30);
+const RETRY_DELAY: Duration = Duration::from_secs(10);
+const MAX_UPDATES_BYTES: usize = 8 * 1024 * 1024;
+/// Most todos `/today` shows.
+const TODAY_LIMIT: usize = 20;
+
+const HELP: &str = "Commands: /add <todo, e.g. Pay rent tomorrow 9am !high>, /today, \
+                    /done <id from /today>, /help";
+
+/// The Telegram Bot API, reached with the token BotFather issued.
+pub struct TelegramClient {
+    api_url: String,
+    token: String,
+    /// Chats whose commands are answered; messages from anywhere else are
+    /// ignored, since anyone can find and message a bot.
+    allowed_chats: Vec<i64>,
+    poll_timeout: Duration,
+}
+
+/// A text message sent to the bot.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChatMessage {
+    pub chat_id: i64,
+    pub text: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Updates {
+    ok: bool,
+    #[serde(default)]
+    result: Vec<Update>,
+    description: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Update {
+    update_id: i64,
+    message: Option<Message>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Message {
+    chat: Chat,
+    text: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Chat {
+    id: i64,
+}
+
+/// Parses `TELEGRAM_ALLOWED_CHATS`: comma-separated chat ids, negative
+/// for groups.
+pub fn parse_chat_ids(value: &str) -> Result<Vec<i64>, String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|id| !id.is_empty())
+        .map(|id| {
+            id.parse()
+                .map_err(|_| format!("'{}' is not a Telegram chat id", id))
+        })
+        .collect()
+}
+
+impl TelegramClient {
+    pub fn new(api_url: &str, token: &str, allowed_chats: Vec<i64>) -> Self {
+        TelegramClient {
+            api_url: api_url.trim_end_matches('/').to_string(),
+            token: token.to_string(),
+            allowed_chats,
+            poll_timeout: POLL_TIMEOUT,
+        }
+    }
+
+    pub fn from_settings(settings: &TelegramSettings) -> Result<Self, String> {
+        let allowed_chats = settings
+            .allowed_chats
+            .as_deref()
+            .ok_or("TELEGRAM_ALLOWED_CHATS is required when Telegram is enabled")?;
+        let allowed_chats =
+            parse_chat_ids(allowed_chats).map_err(|e| format!("TELEGRAM_ALLOWED_CHATS: {}", e))?;
+        if allowed_chats.is_empty() {
+            return Err("TELEGRAM_ALLOWED_CHATS must list at least one chat id".to_string());
+        }
+        Ok(TelegramClient::new(
+            &settings.api_url,
+            &settings.token,
+            allowed_chats,
+        ))
+    }
+
+    #[cfg(test)]
+    pub fn with_poll_timeout(mut self, poll_timeout: Duration) -> Self {
+        self.poll_timeout = poll_timeout;
+        self
+    }
+
+    fn url(&self, method: &str) -> String {
+        format!("{}/bot{}/{}", self.api_url, self.token, method)
+    }
+
+    /// Sends `text` to `chat_id` as a plain text message.
+    pub async fn send(&self, chat_id: i64, text: &str) -> Result<(), String> {
+        let client = awc::Client::builder().timeout(SEND_TIMEOUT).finish();
+        let response = client
+            .post(self.url("sendMessage"))
+            .send_json(&serde_json::json!({ "chat_id": chat_id, "text": text }))
+            .await
+            .map_err(|e| format!("Telegram API unreachable: {}", e))?;
+        if !response.status().is_success() {
+            return Err(format!(
+                "Telegram sendMessage responded with {}",
+                response.status()
+            ));
+        }
+        Ok(())
+    }
+
+    /// Messages from allowed chats received from `offset` on, and the
+    /// offset to pass next time, which confirms them. Without `offset`,
+    /// returns at once, confirming everything already waiting.
+    async fn updates(
+        &self,
+        offset: Option<i64>,
+    ) -> Result<(Option<i64>, Vec<ChatMessage>), String> {
+        let timeout = match offset {
+            Some(_) => self.poll_timeout,
+            None => Duration::ZERO,
+        };
+        let query = [
+            ("offset", offset.unwrap_or(-1).to_string()),
+            ("timeout", timeout.as_secs().to_string()),
+            ("allowed_updates", r#"["message"]"#.to_string()),
+        ];
+        let client = awc::Client::builder()
+            .timeout(timeout + SEND_TIMEOUT)
+            .finish();
+        let mut response = client
+            .get(self.url("getUpdates"))
+            .query(&query)
+            .map_err(|e| e.to_string())?
+            .send()
+            .await
+            .map_err(|e| format!("Telegram API unreachable: {}", e))?;
+        let updates: Updates = response
+            .json()
+            .limit(MAX_UPDATES_BYTES)
+            .await
+            .map_err(|e| format!("Unexpected getUpdates response: {}", e))?;
+        if !updates.ok {
+            return Err(format!(
+                "Telegram getUpdates failed: {}",
+                updates.description.unwrap_or_default()
+            ));
+        }
+        let next = updates
+            .result
+            .iter()
+            .map(|update| update.update_id + 1)
+            .max()
+            .or(offset);
+        if offset.is_none() {
+            return Ok((next, Vec::new()));
+        }
+        let messages = updates
+            .result
+            .into_iter()
+            .filter_map(|update| update.message)
+            .filter(|message| self.allowed_chats.contains(&message.chat.id))
+            .filter_map(|message| {
+                Some(ChatMessage {
+                    chat_id: message.chat.id,
+                    text: message.text?,
+                })
+            })
+            .collect();
+        Ok((next, messages))
+    }
+}
+
+/// Answers one bot command, or `None` for messages that aren't commands.
+pub async fn command(
+    service: &TodoService,
+    moderation: &Moderation,
+    message: &str,
+    today: NaiveDate,
+) -> Option<String> {
+    let message = message.trim();
+    let (name, argument) = message.split_once(' ').unwrap_or((message, ""));
+    let argument = argument.trim();
+    // In groups, commands may be addressed as /today@SpicyTodoBot.
+    let name = name.strip_prefix('/')?;
+    let name = name.split_once('@').map_or(name, |(name, _)| name);
+    let reply = match name {
+        "add" => add(service, moderation, argument, today, "/add").await,
+        "today" => {
+            let todos = service.get_all(Some("active".to_string()), None, None);
+            let agenda = Agenda::build(&todos, today, 0);
+            let due: Vec<&Todo> = agenda.overdue.iter().chain(&agenda.due_today).collect();
+            if due.is_empty() {
+                return Some("Nothing due today.".to_string());
+            }
+            let mut lines: Vec<String> = due
+                .iter()
+                .take(TODAY_LIMIT)
+                .map(|todo| format!("- {}", describe(todo)))
+                .collect();
+            if due.len() > TODAY_LIMIT {
+                lines.push(format!("...and {} more", due.len() - TODAY_LIMIT));
+            }
+            lines.join("\n")
+        }
+        "done" => match find_active(service, argument, "/done", "/today") {
+            Ok(todo) => {
+                let update = TodoUpdate {
+                    completed: Some(true),
+                    ..Default::default()
+                };
+                match service.update(todo.id, update) {
+                    Some(todo) => format!("Done: {}", todo.text),
+                    None => "That todo was just deleted.".to_string(),
+                }
+            }
+            Err(e) => e,
+        },
+        "start" | "help" => HELP.to_string(),
+        _ => format!("Unknown command. {}", HELP),
+    };
+    Some(reply)
+}
+
+/// Reads the bot's messages and answers commands.
+pub struct Bot {
+    client: web::Data<TelegramClient>,
+    service: web::Data<TodoService>,
+    moderation: web::Data<Moderation>,
+    offset: Option<i64>,
+}
+
+impl Bot {
+    /// Confirms everything already sent to the bot, so old commands are
+    /// not run again after a restart.
+    pub async fn start(
+        client: web::Data<TelegramClient>,
+        service: web::Data<TodoService>,
+        moderation: web::Data<Moderation>,
+    ) -> Result<Self, String> {
+        let (offset, _) = client.updates(None).await?;
+        Ok(Bot {
+            client,
+            service,
+            moderation,
+            offset: Some(offset.unwrap_or(0)),
+        })
+    }
+
+    /// Waits for new messages and answers the commands among them,
+    /// returning how many were answered.
+    pub async fn poll(&mut self) -> Result<usize, String> {
+        let (next, messages) = self.client.updates(self.offset).await?;
+        self.offset = next;
+        let mut answered = 0;
+        for message in messages {
+            let today = Utc::now().date_naive();
+            let reply = command(&self.service, &self.moderation, &message.text, today).await;
+            if let Some(reply) = reply {
+                self.client.send(message.chat_id, &reply).await?;
+                answered += 1;
+            }
+        }
+        Ok(answered)
+    }
+}
+
+/// Runs the bot until the server exits, restarting it after errors.
+pub async fn run_bot(
+    client: web::Data<TelegramClient>,
+    service: web::Data<TodoService>,
+    moderation: web::Data<Moderation>,
+) {
+    loop {
+        let started = Bot::start(client.clone(), service.clone(), moderation.clone()).await;
+        let mut bot = match started {
+            Ok(bot) => bot,
+            Err(e) => {
+                eprintln!("Telegram bot failed to start: {}", e);
+                tokio::time::sleep(RETRY_DELAY).await;
+                continue;
+            }
+        };
+        while let Ok(answered) = bot.poll().await.inspect_err(|e| {
+            eprintln!("Telegram bot error: {}", e);
+        }) {
+            if answered > 0 {
+                println!("✈️  Answered {} Telegram commands", answered);
+            }
+        }
+        tokio::time::sleep(RETRY_DELAY).await;
+    }
+}
+
+pub struct TelegramPlugin;
+
+impl Plugin for TelegramPlugin {
+    fn name(&self) -> &'static str {
+        "telegram"
+    }
+
+    fn kind(&self) -> Kind {
+        Kind::Chat
+    }
+
+    fn description(&self) -> &'static str {
+        "Telegram bot with /add, /today and /done"
+    }
+
+    fn enabled(&self, config: &Config) -> bool {
+        config.telegram.is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::moderation::{ModerationMode, WordList};
+    use spicy_todo_core::models::TodoCreate;
+
+    fn today() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 6, 10).unwrap()
+    }
+
+    /// Answers `message` with nothing moderated.
+    async fn ask(service: &TodoService, message: &str) -> Option<String> {
+        command(service, &Moderation::default(), message, today()).await
+    }
+
+    #[actix_web::test]
+    async fn test_add_today_and_done() {
+        let service = TodoService::new_empty();
+        assert_eq!(ask(&service, "hello").await, None);
+        assert_eq!(ask(&service, "/today").await.unwrap(), "Nothing due today.");
+
+        ask(&service, "/add@SpicyTodoBot Pay rent today !high").await;
+        ask(&service, "/add Call bank tomorrow").await;
+        let late = service.create(TodoCreate {
+            text: "File taxes".to_string(),
+            due_date: Some("2024-06-01".to_string()),
+            ..Default::default()
+        });
+
+        let list = ask(&service, "/today").await.unwrap();
+        let lines: Vec<&str> = list.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("- File taxes, due 2024-06-01"));
+        assert!(lines[1].starts_with("- Pay rent, due 2024-06-10"));
+
+        let done = format!("/done {}", &late.id.to_string()[..6]);
+        assert_eq!(ask(&service, &done).await.unwrap(), "Done: File taxes");
+        assert!(ask(&service, "/done ab")
+            .await
+            .unwrap()
+            .starts_with("Usage: /done <id>"));
+        assert_eq!(ask(&service, "/start").await.unwrap(), HELP);
+    }
+
+    #[actix_web::test]
+    async fn test_add_is_moderated() {
+        let service = TodoService::new_empty();
+        let words = || vec![Box::new(WordList::new(&["spinach".to_string()])) as Box<_>];
+
+        let reject = Moderation::new(ModerationMode::Reject, words());
+        let reply = command(&service, &reject, "/add Buy spinach", today()).await;
+        assert!(reply.unwrap().starts_with("Not added"));
+        assert!(service.get_all(None, None, None).is_empty());
+
+        let mask = Moderation::new(ModerationMode::Mask, words());
+        command(&service, &mask, "/add Buy spinach", today()).await;
+        assert_eq!(service.get_all(None, None, None)[0].text, "Buy *******");
+    }
+
+    #[test]
+    fn test_parse_chat_ids() {
+        assert_eq!(
+            parse_chat_ids("12345, -100987").unwrap(),
+            vec![12345, -100987]
+        );
+        assert!(parse_chat_ids("12345,@channel").is_err());
+    }
+}
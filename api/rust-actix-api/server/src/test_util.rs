@@ -0,0 +1,294 @@
+use crate::auth::{self, Auth, AuthMode};
+use crate::bulk_edits::{self, BulkEditPreviews};
+use crate::config::{Config, StorageBackend};
+use crate::demo::{self, Demo};
+use crate::diagnostics::RuntimeRegistry;
+use crate::email::EmailIngest;
+use crate::geofence::GeofenceLog;
+use crate::mcp::McpSessions;
+use crate::metrics::Metrics;
+use crate::moderation::Moderation;
+use crate::my_day::MyDayStore;
+use crate::notifiers::NotifierService;
+use crate::plugins::PluginRegistry;
+use crate::policies::PolicyStore;
+use crate::preferences::PreferenceStore;
+use crate::push::PushService;
+use crate::reminders::Channels;
+use crate::scripts::ScriptService;
+use crate::suggestions::Suggester;
+use crate::views::ViewStore;
+use crate::webhooks::WebhookService;
+use crate::{casing, context, i18n, metrics, routes, security_headers};
+use actix_web::body::BoxBody;
+use actix_web::dev::ServiceResponse;
+use actix_web::http::StatusCode;
+use actix_web::test::{self, TestRequest};
+use actix_web::{middleware, web, App};
+use futures_util::future::LocalBoxFuture;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use spicy_todo_core::models::Todo;
+use spicy_todo_core::service::TodoService;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+type Call = Rc<dyn Fn(TestRequest) -> LocalBoxFuture<'static, ServiceResponse<BoxBody>>>;
+
+/// The body of an error response.
+#[derive(Debug, Deserialize)]
+pub struct ApiError {
+    pub error: String,
+    pub code: Option<String>,
+}
+
+/// The body of `DELETE /api/todos/{id}`.
+#[derive(Debug, Deserialize)]
+pub struct Deleted {
+    pub message: String,
+    pub removed: Value,
+}
+
+/// The counts of `GET /api/todos/stats/summary`; the rest is left out.
+#[derive(Debug, Deserialize)]
+pub struct Stats {
+    pub total: usize,
+    pub active: usize,
+    pub completed: usize,
+    #[serde(rename = "completionRate")]
+    pub completion_rate: f64,
+    #[serde(rename = "overdueCount")]
+    pub overdue_count: usize,
+    pub pinned: usize,
+}
+
+/// The whole app, middleware included, served in process for integration
+/// tests. The helpers send real requests and panic on any status other
+/// than the one the call expects, with the body in the message.
+pub struct TestApp {
+    call: Call,
+    service: web::Data<TodoService>,
+    dir: Option<PathBuf>,
+}
+
+pub struct TestAppBuilder {
+    backend: StorageBackend,
+    config: Config,
+}
+
+impl TestAppBuilder {
+    /// Stores todos in `backend`. The journal, snapshot and event log are
+    /// kept in a directory of their own, removed when the app is dropped.
+    pub fn backend(mut self, backend: StorageBackend) -> Self {
+        self.backend = backend;
+        self
+    }
+
+    /// Serves with `config`. Its storage paths are replaced by the
+    /// backend's.
+    pub fn config(mut self, config: Config) -> Self {
+        self.config = config;
+        self
+    }
+
+    pub async fn build(self) -> TestApp {
+        let mut config = self.config;
+        config.journal_dir = None;
+        config.snapshot_path = None;
+        config.event_store_path = None;
+        let dir = (self.backend != StorageBackend::Memory).then(|| {
+            let dir =
+                std::env::temp_dir().join(format!("spicy-todo-test-{}", uuid::Uuid::new_v4()));
+            std::fs::create_dir_all(&dir).expect("create test app directory");
+            dir
+        });
+        match (&dir, self.backend) {
+            (Some(dir), StorageBackend::Journal) => config.journal_dir = Some(dir.join("journal")),
+            (Some(dir), StorageBackend::Snapshot) => {
+                config.snapshot_path = Some(dir.join("snapshot.json"))
+            }
+            (Some(dir), StorageBackend::EventStore) => {
+                config.event_store_path = Some(dir.join("events.jsonl"))
+            }
+            _ => {}
+        }
+
+        let service = web::Data::new(config.service().expect("open test app storage"));
+        let moderation = Moderation::from_settings(&config.moderation).expect("moderation");
+        let preferences = web::Data::new(PreferenceStore::new());
+        let push = web::Data::new(PushService::new());
+        let channels = Channels {
+            preferences: preferences.clone(),
+            push: push.clone(),
+            sms: None,
+            matrix: None,
+            web_push: None,
+        };
+        let auth = Auth::from_settings(&config.auth).expect("auth");
+        let demo = config.demo.clone().map(Demo::new);
+        let app = App::new()
+            .wrap(middleware::from_fn(demo::guard_demo))
+            .wrap(middleware::from_fn(auth::require_auth))
+            .wrap(middleware::from_fn(casing::negotiate_case))
+            .wrap(middleware::from_fn(i18n::localize_responses))
+            .wrap(middleware::Condition::new(
+                auth.mode() == AuthMode::Session,
+                auth.session_middleware(),
+            ))
+            .wrap(middleware::from_fn(metrics::track_requests))
+            .wrap(middleware::from_fn(context::with_request_context))
+            .wrap(middleware::from_fn(security_headers::add_security_headers))
+            .app_data(web::Data::new(Suggester::from_config(&config)))
+            .app_data(web::Data::new(GeofenceLog::new(config.geofence_cooldown)))
+            .app_data(web::Data::new(config))
+            .app_data(service.clone())
+            .app_data(web::Data::new(WebhookService::new()))
+            .app_data(web::Data::new(NotifierService::new()))
+            .app_data(web::Data::new(ScriptService::new()))
+            .app_data(web::Data::new(BulkEditPreviews::new(
+                bulk_edits::PREVIEW_TTL,
+            )))
+            .app_data(preferences)
+            .app_data(web::Data::new(ViewStore::new()))
+            .app_data(web::Data::new(MyDayStore::new()))
+            .app_data(web::Data::new(PolicyStore::new()))
+            .app_data(push)
+            .app_data(web::Data::new(EmailIngest::new()))
+            .app_data(web::Data::new(McpSessions::new()))
+            .app_data(web::Data::new(channels))
+            .app_data(web::Data::new(Metrics::new()))
+            .app_data(web::Data::new(RuntimeRegistry::new()))
+            .app_data(web::Data::new(PluginRegistry::builtin()))
+            .app_data(web::Data::new(moderation))
+            .app_data(web::Data::new(auth))
+            .configure(routes::configure_routes);
+        let app = match demo {
+            Some(demo) => app.app_data(web::Data::new(demo)),
+            None => app,
+        };
+        let app = Rc::new(test::init_service(app).await);
+        let call: Call = Rc::new(move |req| {
+            let app = app.clone();
+            Box::pin(async move {
+                test::call_service(&*app, req.to_request())
+                    .await
+                    .map_into_boxed_body()
+            })
+        });
+        TestApp { call, service, dir }
+    }
+}
+
+impl TestApp {
+    pub fn builder() -> TestAppBuilder {
+        TestAppBuilder {
+            backend: StorageBackend::Memory,
+            config: Config::default(),
+        }
+    }
+
+    /// The app on the in-memory store, with the default config.
+    pub async fn new() -> TestApp {
+        TestApp::builder().build().await
+    }
+
+    /// The service behind the app, for setting up or checking state
+    /// without going through HTTP.
+    pub fn service(&self) -> &TodoService {
+        &self.service
+    }
+
+    pub async fn call(&self, req: TestRequest) -> ServiceResponse<BoxBody> {
+        (self.call)(req).await
+    }
+
+    /// Sends `req`, checks it was answered with `status` and reads the body
+    /// as `T`.
+    pub async fn send<T: DeserializeOwned>(&self, req: TestRequest, status: u16) -> T {
+        let resp = self.call(req).await;
+        let actual = resp.status();
+        let body = test::read_body(resp).await;
+        assert_eq!(
+            actual,
+            StatusCode::from_u16(status).unwrap(),
+            "unexpected status, body: {}",
+            String::from_utf8_lossy(&body)
+        );
+        serde_json::from_slice(&body).unwrap_or_else(|e| {
+            panic!(
+                "cannot read body ({}): {}",
+                e,
+                String::from_utf8_lossy(&body)
+            )
+        })
+    }
+
+    pub async fn create_todo(&self, text: &str) -> Todo {
+        self.create_todo_with(json!({ "text": text })).await
+    }
+
+    /// Creates a todo from a full `POST /api/todos` body.
+    pub async fn create_todo_with(&self, body: Value) -> Todo {
+        let req = TestRequest::post().uri("/api/todos").set_json(body);
+        self.send(req, 201).await
+    }
+
+    /// `None` when the todo is not found.
+    pub async fn get_todo(&self, id: &str) -> Option<Todo> {
+        let resp = self
+            .call(TestRequest::get().uri(&format!("/api/todos/{}", id)))
+            .await;
+        if resp.status() == StatusCode::NOT_FOUND {
+            return None;
+        }
+        assert_eq!(resp.status(), StatusCode::OK);
+        Some(test::read_body_json(resp).await)
+    }
+
+    /// `GET /api/todos?{query}`; `query` is sent as is, e.g. `filter=active`.
+    pub async fn list_todos(&self, query: &str) -> Vec<Todo> {
+        let req = TestRequest::get().uri(&format!("/api/todos?{}", query));
+        self.send(req, 200).await
+    }
+
+    pub async fn update_todo(&self, id: &str, body: Value) -> Todo {
+        let req = TestRequest::put()
+            .uri(&format!("/api/todos/{}", id))
+            .set_json(body);
+        self.send(req, 200).await
+    }
+
+    pub async fn toggle_todo(&self, id: &str) -> Todo {
+        let req = TestRequest::patch().uri(&format!("/api/todos/{}/toggle", id));
+        self.send(req, 200).await
+    }
+
+    pub async fn pin_todo(&self, id: &str) -> Todo {
+        let req = TestRequest::patch().uri(&format!("/api/todos/{}/pin", id));
+        self.send(req, 200).await
+    }
+
+    pub async fn delete_todo(&self, id: &str) -> Deleted {
+        let req = TestRequest::delete().uri(&format!("/api/todos/{}", id));
+        self.send(req, 200).await
+    }
+
+    pub async fn stats(&self) -> Stats {
+        let req = TestRequest::get().uri("/api/todos/stats/summary");
+        self.send(req, 200).await
+    }
+
+    /// Sends `req`, expecting it to fail with `status`.
+    pub async fn expect_error(&self, req: TestRequest, status: u16) -> ApiError {
+        self.send(req, status).await
+    }
+}
+
+impl Drop for TestApp {
+    fn drop(&mut self) {
+        if let Some(dir) = &self.dir {
+            let _ = std::fs::remove_dir_all(dir);
+        }
+    }
+}
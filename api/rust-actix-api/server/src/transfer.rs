@@ -0,0 +1,124 @@
+use crate::config::Config;
+use crate::plugins::{Kind, Plugin};
+use serde::Deserialize;
+use spicy_todo_core::bundle::TodoBundle;
+use spicy_todo_core::models::Todo;
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+const PUSH_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Deserialize)]
+pub struct TransferRequest {
+    /// Name of a configured peer.
+    pub peer: String,
+}
+
+#[derive(Debug)]
+pub struct TransferFailure {
+    pub error: String,
+    /// The peer's own response body, when it sent one.
+    pub remote: Option<serde_json::Value>,
+}
+
+/// Parses `TRANSFER_PEERS`: comma-separated `name=url` pairs naming the
+/// other instances todos can be handed to, e.g.
+/// `work=https://todo.work.example,home=http://10.0.0.2:8000`.
+pub fn parse_peers(value: &str) -> Result<BTreeMap<String, String>, String> {
+    let mut peers = BTreeMap::new();
+    for entry in value
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+    {
+        let (name, url) = entry
+            .split_once('=')
+            .ok_or_else(|| format!("Invalid peer '{}': expected name=url", entry))?;
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(format!("Invalid peer '{}': name is empty", entry));
+        }
+        peers.insert(name.to_string(), import_url(url)?);
+    }
+    Ok(peers)
+}
+
+/// The bundle import endpoint of the instance at `base_url`.
+fn import_url(base_url: &str) -> Result<String, String> {
+    let trimmed = base_url.trim().trim_end_matches('/');
+    let uri: awc::http::Uri = trimmed
+        .parse()
+        .map_err(|_| format!("Invalid peer URL '{}'", base_url))?;
+    if !matches!(uri.scheme_str(), Some("http") | Some("https")) || uri.host().is_none() {
+        return Err(format!(
+            "Peer URL '{}' must be an absolute http(s) URL",
+            base_url
+        ));
+    }
+    Ok(format!("{}/api/todos/import", trimmed))
+}
+
+/// Delivers `bundle` to the import endpoint at `url`, returning the todo as
+/// the peer stored it.
+pub async fn push(url: &str, bundle: &TodoBundle) -> Result<Todo, TransferFailure> {
+    let client = awc::Client::builder().timeout(PUSH_TIMEOUT).finish();
+    let mut response = client
+        .post(url)
+        .send_json(bundle)
+        .await
+        .map_err(|e| TransferFailure {
+            error: format!("Failed to reach {}: {}", url, e),
+            remote: None,
+        })?;
+    if !response.status().is_success() {
+        return Err(TransferFailure {
+            error: format!("{} responded with {}", url, response.status()),
+            remote: response.json().await.ok(),
+        });
+    }
+    response.json().await.map_err(|e| TransferFailure {
+        error: format!("Unexpected response from {}: {}", url, e),
+        remote: None,
+    })
+}
+
+pub struct TransferPlugin;
+
+impl Plugin for TransferPlugin {
+    fn name(&self) -> &'static str {
+        "transfer"
+    }
+
+    fn kind(&self) -> Kind {
+        Kind::Sync
+    }
+
+    fn description(&self) -> &'static str {
+        "Sends todos to peer instances"
+    }
+
+    fn enabled(&self, config: &Config) -> bool {
+        !config.transfer_peers.is_empty()
+    }
+
+    fn details(&self, config: &Config) -> Option<serde_json::Value> {
+        Some(serde_json::json!({ "peers": config.transfer_peers.keys().collect::<Vec<_>>() }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_peers() {
+        let peers =
+            parse_peers(" work=https://todo.work.example/ , home=http://10.0.0.2:8000").unwrap();
+        assert_eq!(peers["work"], "https://todo.work.example/api/todos/import");
+        assert_eq!(peers["home"], "http://10.0.0.2:8000/api/todos/import");
+        assert!(parse_peers("").unwrap().is_empty());
+        assert!(parse_peers("work").is_err());
+        assert!(parse_peers("=http://host").is_err());
+        assert!(parse_peers("work=file:///etc/passwd").is_err());
+    }
+}
@@ -0,0 +1,297 @@
+use crate::casing::{camel_case, snake_case};
+use crate::errors::{self, ApiError};
+use crate::preferences::{self, PreferenceStore};
+use actix_web::body::{BoxBody, MessageBody};
+use actix_web::dev::{Payload, ServiceRequest, ServiceResponse};
+use actix_web::error::PayloadError;
+use actix_web::http::header::{self, HeaderValue};
+use actix_web::middleware::Next;
+use actix_web::web::Bytes;
+use actix_web::{web, HttpRequest};
+use chrono::{DateTime, NaiveDate, NaiveTime, SecondsFormat, TimeZone};
+use chrono_tz::Tz;
+use futures_util::{stream, Stream};
+use serde_json::{Map, Value};
+use std::pin::Pin;
+
+/// Set on `/api/v2` responses, naming the version that produced them.
+pub const VERSION_HEADER: &str = "x-api-version";
+/// Served versions, oldest first. Unversioned `/api` is the first.
+pub const VERSIONS: [&str; 2] = ["v1", "v2"];
+
+fn rename_keys(value: &mut Value, rename: fn(&str) -> String) {
+    match value {
+        Value::Object(object) => {
+            let renamed: Map<String, Value> = std::mem::take(object)
+                .into_iter()
+                .map(|(key, mut value)| {
+                    rename_keys(&mut value, rename);
+                    (rename(&key), value)
+                })
+                .collect();
+            *object = renamed;
+        }
+        Value::Array(items) => items.iter_mut().for_each(|item| rename_keys(item, rename)),
+        _ => {}
+    }
+}
+
+/// Whether `object` is a todo as v1 writes it.
+fn is_todo(object: &Map<String, Value>) -> bool {
+    ["id", "text", "createdAt"]
+        .iter()
+        .all(|key| object.contains_key(*key))
+}
+
+/// When a v1 todo's reminder goes off, in `tz`: its `reminderTime`, with
+/// or without seconds, on its due date. A time skipped by a daylight saving
+/// change never comes, so has none.
+fn remind_at(object: &Map<String, Value>, tz: Tz) -> Option<DateTime<Tz>> {
+    let date = NaiveDate::parse_from_str(object.get("dueDate")?.as_str()?, "%Y-%m-%d").ok()?;
+    let time = object.get("reminderTime")?.as_str()?;
+    let time = NaiveTime::parse_from_str(time, "%H:%M:%S")
+        .or_else(|_| NaiveTime::parse_from_str(time, "%H:%M"))
+        .ok()?;
+    tz.from_local_datetime(&date.and_time(time)).earliest()
+}
+
+/// v2's todo: snake_case keys, and the reminder as one `remind_at`
+/// timestamp, in the client's time zone `tz`, rather than a `reminderTime`
+/// on the due date.
+fn todo_to_v2(object: &mut Map<String, Value>, tz: Tz) {
+    let remind_at = remind_at(object, tz).map_or(Value::Null, |at| {
+        Value::String(at.to_rfc3339_opts(SecondsFormat::Secs, true))
+    });
+    object.remove("reminderTime");
+    object.insert("remindAt".to_string(), remind_at);
+    let mut todo = Value::Object(std::mem::take(object));
+    rename_keys(&mut todo, snake_case);
+    if let Value::Object(todo) = todo {
+        *object = todo;
+    }
+}
+
+/// Rewrites every todo in a v1 response body into its v2 shape, wherever
+/// it sits: alone, in a list or page, or inside another object. Returns
+/// whether there were any. Reminders are given in `tz`.
+pub fn response_to_v2(value: &mut Value, tz: Tz) -> bool {
+    match value {
+        Value::Object(object) if is_todo(object) => {
+            todo_to_v2(object, tz);
+            true
+        }
+        Value::Object(object) => {
+            let mut found = false;
+            for value in object.values_mut() {
+                found |= response_to_v2(value, tz);
+            }
+            found
+        }
+        Value::Array(items) => {
+            let mut found = false;
+            for item in items {
+                found |= response_to_v2(item, tz);
+            }
+            found
+        }
+        _ => false,
+    }
+}
+
+/// Splits a `remindAt` back into the `dueDate` and `reminderTime` the
+/// handlers take, in every object of `value`, as they fall in `tz`.
+fn split_remind_at(value: &mut Value, tz: Tz) -> Result<(), String> {
+    match value {
+        Value::Object(object) => {
+            if let Some(remind_at) = object.remove("remindAt") {
+                let (date, time) = match &remind_at {
+                    Value::Null => (None, Value::Null),
+                    Value::String(at) => {
+                        let at = DateTime::parse_from_rfc3339(at)
+                            .map_err(|_| "remind_at must be an RFC 3339 date-time".to_string())?
+                            .with_timezone(&tz);
+                        let time = Value::String(at.format("%H:%M").to_string());
+                        (Some(at.format("%Y-%m-%d").to_string()), time)
+                    }
+                    _ => return Err("remind_at must be an RFC 3339 date-time".to_string()),
+                };
+                if let Some(date) = date {
+                    match object.get("dueDate").and_then(Value::as_str) {
+                        Some(due) if due != date => {
+                            return Err(
+                                "remind_at must fall on due_date, in your time zone".to_string()
+                            )
+                        }
+                        _ => {
+                            object.insert("dueDate".to_string(), Value::String(date));
+                        }
+                    }
+                }
+                object.insert("reminderTime".to_string(), time);
+            }
+            object
+                .values_mut()
+                .try_for_each(|value| split_remind_at(value, tz))
+        }
+        Value::Array(items) => items
+            .iter_mut()
+            .try_for_each(|item| split_remind_at(item, tz)),
+        _ => Ok(()),
+    }
+}
+
+/// Reads a v2 request body into the v1 shape the handlers take, from a
+/// client in `tz`.
+pub fn request_from_v2(value: &mut Value, tz: Tz) -> Result<(), String> {
+    rename_keys(value, camel_case);
+    split_remind_at(value, tz)
+}
+
+/// A request payload of `bytes`, for a body that was read and rewritten.
+fn payload(bytes: Bytes) -> Payload {
+    let chunks: Pin<Box<dyn Stream<Item = Result<Bytes, PayloadError>>>> =
+        Box::pin(stream::once(async move { Ok(bytes) }));
+    Payload::from(chunks)
+}
+
+/// The time zone of the client sending `req`, from its saved preferences;
+/// UTC for clients without any.
+fn client_timezone(req: &HttpRequest) -> Tz {
+    let store = req.app_data::<web::Data<PreferenceStore>>();
+    match (store, preferences::client_id(req).ok().flatten()) {
+        (Some(store), Some(client)) => store.user(&client).preferences.tz(),
+        _ => Tz::UTC,
+    }
+}
+
+/// The compatibility layer in front of `/api/v2`: the handlers only speak
+/// v1, so JSON request bodies are translated down to it and JSON
+/// responses up to v2. `/api/v1` bypasses this and stays byte for byte
+/// what it always was. Reminders are read and written in the client's
+/// time zone.
+pub async fn v2_compat(
+    mut req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<BoxBody>, actix_web::Error> {
+    let tz = client_timezone(req.request());
+    let json_request = req
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("application/json"));
+    if json_request {
+        let bytes = req.extract::<Bytes>().await?;
+        let body = match serde_json::from_slice::<Value>(&bytes) {
+            Ok(mut value) => match request_from_v2(&mut value, tz) {
+                Ok(()) => Bytes::from(value.to_string()),
+                Err(e) => {
+                    return Ok(req.into_response(ApiError::bad_request(e).into_response()));
+                }
+            },
+            // Left for the handler to reject as usual.
+            Err(_) => bytes,
+        };
+        req.headers_mut().remove(header::CONTENT_LENGTH);
+        req.set_payload(payload(body));
+    }
+
+    let response = next.call(req).await?.map_into_boxed_body();
+    let mut response = if errors::is_json(&response) {
+        errors::edit_json(response, |body| response_to_v2(body, tz))
+            .await?
+            .0
+    } else {
+        response
+    };
+    response.headers_mut().insert(
+        header::HeaderName::from_static(VERSION_HEADER),
+        HeaderValue::from_static("v2"),
+    );
+    Ok(response)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_todos_move_to_v2_shape() {
+        let mut body = json!({
+            "items": [{
+                "id": "1",
+                "text": "Pay rent",
+                "dueDate": "2024-06-10",
+                "reminderTime": "09:30",
+                "recurrenceEnd": { "afterOccurrences": 3 },
+                "createdAt": "2024-06-01T00:00:00Z"
+            }],
+            "hasMore": false
+        });
+        assert!(response_to_v2(&mut body, Tz::UTC));
+        let todo = &body["items"][0];
+        assert_eq!(todo["due_date"], "2024-06-10");
+        assert_eq!(todo["remind_at"], "2024-06-10T09:30:00Z");
+        assert_eq!(todo["recurrence_end"]["after_occurrences"], 3);
+        assert!(todo.get("reminderTime").is_none());
+        // Only todos change shape.
+        assert_eq!(body["hasMore"], false);
+        assert!(!response_to_v2(
+            &mut json!({ "error": "Todo not found" }),
+            Tz::UTC
+        ));
+    }
+
+    #[test]
+    fn test_reminders_are_given_in_the_client_time_zone() {
+        let todo = || {
+            json!({
+                "id": "1",
+                "text": "Pay rent",
+                "dueDate": "2024-06-10",
+                "reminderTime": "09:30:15",
+                "createdAt": "2024-06-01T00:00:00Z"
+            })
+        };
+        let mut body = todo();
+        response_to_v2(&mut body, Tz::UTC);
+        assert_eq!(body["remind_at"], "2024-06-10T09:30:15Z");
+        let mut body = todo();
+        response_to_v2(&mut body, Tz::Europe__Berlin);
+        assert_eq!(body["remind_at"], "2024-06-10T09:30:15+02:00");
+
+        let mut body = json!({ "remind_at": "2024-06-10T07:30:00Z" });
+        request_from_v2(&mut body, Tz::Europe__Berlin).unwrap();
+        assert_eq!(body["reminderTime"], "09:30");
+        // 02:30 never comes in Berlin on the day clocks go forward.
+        let mut body = todo();
+        body["dueDate"] = json!("2024-03-31");
+        body["reminderTime"] = json!("02:30");
+        response_to_v2(&mut body, Tz::Europe__Berlin);
+        assert_eq!(body["remind_at"], Value::Null);
+    }
+
+    #[test]
+    fn test_requests_move_back_to_v1_shape() {
+        let mut body = json!({
+            "text": "Pay rent",
+            "remind_at": "2024-06-10T11:30:00+02:00",
+            "estimate_minutes": 15
+        });
+        request_from_v2(&mut body, Tz::UTC).unwrap();
+        assert_eq!(
+            body,
+            json!({
+                "text": "Pay rent",
+                "dueDate": "2024-06-10",
+                "reminderTime": "09:30",
+                "estimateMinutes": 15
+            })
+        );
+
+        let mut body = json!({ "due_date": "2024-06-11", "remind_at": "2024-06-10T09:30:00Z" });
+        assert!(request_from_v2(&mut body, Tz::UTC).is_err());
+        let mut body = json!({ "remind_at": "tomorrow" });
+        assert!(request_from_v2(&mut body, Tz::UTC).is_err());
+    }
+}
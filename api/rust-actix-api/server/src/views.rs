@@ -0,0 +1,237 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use spicy_todo_core::models::{Color, Icon, SortField, SortOrder, Todo};
+use spicy_todo_core::quick_add;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use uuid::Uuid;
+
+const MAX_VIEWS: usize = 100;
+const MAX_NAME_LEN: usize = 100;
+
+/// What a view selects and how it orders it. Everything set must match;
+/// unset fields select everything.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ViewQuery {
+    /// `all`, `active`, `completed` or `overdue`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub filter: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub search: Option<String>,
+    /// Hashtags the text must have, all of them. Todos have no separate
+    /// tags, so these are matched as `#tag` words.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub priority: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sort: Option<SortField>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub order: Option<SortOrder>,
+}
+
+impl ViewQuery {
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(filter) = &self.filter {
+            if !matches!(filter.as_str(), "all" | "active" | "completed" | "overdue") {
+                return Err(format!("Unknown filter '{}'", filter));
+            }
+        }
+        if let Some(priority) = &self.priority {
+            if !matches!(priority.to_lowercase().as_str(), "low" | "medium" | "high") {
+                return Err(format!("Unknown priority '{}'", priority));
+            }
+        }
+        if self
+            .tags
+            .iter()
+            .any(|tag| tag.trim_start_matches('#').is_empty())
+        {
+            return Err("Tags must not be empty".to_string());
+        }
+        Ok(())
+    }
+
+    /// The `filter` for `TodoService::get_all`; overdue todos are the
+    /// active ones, narrowed further by `matches`.
+    pub fn service_filter(&self) -> Option<String> {
+        match self.filter.as_deref() {
+            Some("overdue") => Some("active".to_string()),
+            filter => filter.map(str::to_string),
+        }
+    }
+
+    /// What the service's filters leave to the view: being overdue on
+    /// `today`, and the tags.
+    pub fn matches(&self, todo: &Todo, today: NaiveDate) -> bool {
+        if self.filter.as_deref() == Some("overdue") {
+            let due = todo
+                .due_date
+                .as_deref()
+                .and_then(|due| NaiveDate::parse_from_str(due, "%Y-%m-%d").ok());
+            if due.is_none_or(|due| due >= today) {
+                return false;
+            }
+        }
+        if self.tags.is_empty() {
+            return true;
+        }
+        let tags = quick_add::parse(&todo.text, today).tags;
+        self.tags.iter().all(|wanted| {
+            let wanted = wanted.trim_start_matches('#').to_lowercase();
+            tags.contains(&wanted)
+        })
+    }
+}
+
+/// A named, saved list query: a smart list such as "Overdue high-priority
+/// work".
+#[derive(Debug, Clone, Serialize)]
+pub struct View {
+    pub id: String,
+    pub name: String,
+    /// How clients draw the list; the todos keep their own.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color: Option<Color>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub icon: Option<Icon>,
+    #[serde(flatten)]
+    pub query: ViewQuery,
+    #[serde(rename = "createdAt")]
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ViewCreate {
+    pub name: String,
+    #[serde(default)]
+    pub color: Option<Color>,
+    #[serde(default)]
+    pub icon: Option<Icon>,
+    #[serde(flatten)]
+    pub query: ViewQuery,
+}
+
+pub struct ViewStore {
+    views: Mutex<HashMap<String, View>>,
+}
+
+impl ViewStore {
+    pub fn new() -> Self {
+        ViewStore {
+            views: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn create(&self, input: ViewCreate) -> Result<View, String> {
+        let name = input.name.trim();
+        if name.is_empty() || name.chars().count() > MAX_NAME_LEN {
+            return Err(format!(
+                "View name must be 1 to {} characters",
+                MAX_NAME_LEN
+            ));
+        }
+        input.query.validate()?;
+
+        let mut views = self.views.lock().unwrap();
+        if views.len() >= MAX_VIEWS {
+            return Err(format!("At most {} views can be saved", MAX_VIEWS));
+        }
+        let view = View {
+            id: Uuid::new_v4().to_string(),
+            name: name.to_string(),
+            color: input.color,
+            icon: input.icon,
+            query: input.query,
+            created_at: Utc::now(),
+        };
+        views.insert(view.id.clone(), view.clone());
+        Ok(view)
+    }
+
+    /// Oldest first.
+    pub fn get_all(&self) -> Vec<View> {
+        let mut views: Vec<View> = self.views.lock().unwrap().values().cloned().collect();
+        views.sort_by_key(|view| view.created_at);
+        views
+    }
+
+    pub fn get_by_id(&self, id: &str) -> Option<View> {
+        self.views.lock().unwrap().get(id).cloned()
+    }
+
+    pub fn delete(&self, id: &str) -> bool {
+        self.views.lock().unwrap().remove(id).is_some()
+    }
+}
+
+impl Default for ViewStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spicy_todo_core::models::{todo_id, Priority};
+
+    fn todo(text: &str, due_date: Option<&str>) -> Todo {
+        Todo {
+            id: todo_id(text),
+            text: text.to_string(),
+            priority: Priority::High,
+            completed: false,
+            due_date: due_date.map(str::to_string),
+            reminder_time: None,
+            recurrence: None,
+            recurrence_end: None,
+            remaining_occurrences: None,
+            estimate_minutes: None,
+            location: None,
+            color: None,
+            icon: None,
+            pinned: false,
+            rollover_count: 0,
+            rolled_over_to: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_matches_overdue_and_tags() {
+        let today = NaiveDate::from_ymd_opt(2024, 6, 10).unwrap();
+        let query = ViewQuery {
+            filter: Some("overdue".to_string()),
+            tags: vec!["#Work".to_string()],
+            ..Default::default()
+        };
+        assert!(query.validate().is_ok());
+        assert_eq!(query.service_filter().as_deref(), Some("active"));
+        assert!(query.matches(&todo("Send report #work", Some("2024-06-09")), today));
+        assert!(!query.matches(&todo("Send report #work", Some("2024-06-10")), today));
+        assert!(!query.matches(&todo("Send report #home", Some("2024-06-09")), today));
+        assert!(!query.matches(&todo("Send report #work", None), today));
+    }
+
+    #[test]
+    fn test_store_validates() {
+        let store = ViewStore::new();
+        let create = |name: &str, filter: &str| ViewCreate {
+            name: name.to_string(),
+            color: None,
+            icon: None,
+            query: ViewQuery {
+                filter: Some(filter.to_string()),
+                ..Default::default()
+            },
+        };
+        assert!(store.create(create(" ", "active")).is_err());
+        assert!(store.create(create("Someday", "archived")).is_err());
+        let view = store.create(create("Open", "active")).unwrap();
+        assert_eq!(store.get_all().len(), 1);
+        assert!(store.delete(&view.id));
+        assert!(store.get_by_id(&view.id).is_none());
+    }
+}
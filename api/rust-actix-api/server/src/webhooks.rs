@@ -0,0 +1,569 @@
+use crate::config::Config;
+use crate::metrics::Metrics;
+use crate::plugins::{Kind, Plugin};
+use spicy_todo_core::events::{Event, EventType};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(10);
+/// How often the dispatcher checks for batches whose interval has elapsed.
+const BATCH_FLUSH_TICK: Duration = Duration::from_millis(250);
+const MAX_BATCH_SIZE: usize = 1000;
+const MAX_BATCH_INTERVAL_MS: u64 = 5 * 60 * 1000;
+
+fn default_batch_size() -> usize {
+    50
+}
+
+fn default_batch_interval_ms() -> u64 {
+    5000
+}
+
+/// Buffers events for a subscription and delivers them together once
+/// `max_size` events are pending or `max_interval_ms` has passed since the
+/// first one, whichever comes first.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct BatchConfig {
+    #[serde(rename = "maxSize", default = "default_batch_size")]
+    pub max_size: usize,
+    #[serde(rename = "maxIntervalMs", default = "default_batch_interval_ms")]
+    pub max_interval_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WebhookSubscription {
+    pub id: String,
+    pub url: String,
+    /// Event types to deliver; empty means every event.
+    pub events: Vec<EventType>,
+    /// Optional JMESPath expression evaluated against the event payload.
+    /// The event is delivered only when the result is truthy.
+    pub filter: Option<String>,
+    /// When set, events are delivered in batches instead of one request each.
+    pub batch: Option<BatchConfig>,
+    #[serde(rename = "createdAt")]
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct WebhookCreate {
+    pub url: String,
+    pub events: Option<Vec<EventType>>,
+    pub filter: Option<String>,
+    pub batch: Option<BatchConfig>,
+}
+
+impl WebhookSubscription {
+    pub fn matches(&self, event: &Event) -> bool {
+        if !self.events.is_empty() && !self.events.contains(&event.event_type) {
+            return false;
+        }
+
+        match &self.filter {
+            Some(expression) => evaluate_filter(expression, event).unwrap_or(false),
+            None => true,
+        }
+    }
+}
+
+/// Evaluates a JMESPath filter against the JSON form of an event, e.g.
+/// `type == 'todo.completed' && todo.priority == 'high'`.
+pub fn evaluate_filter(expression: &str, event: &Event) -> Result<bool, String> {
+    let compiled = jmespath::compile(expression).map_err(|e| e.to_string())?;
+    let payload = serde_json::to_value(event).map_err(|e| e.to_string())?;
+    let result = compiled.search(payload).map_err(|e| e.to_string())?;
+    Ok(result.is_truthy())
+}
+
+pub struct WebhookService {
+    subscriptions: Mutex<HashMap<String, WebhookSubscription>>,
+}
+
+impl WebhookService {
+    pub fn new() -> Self {
+        WebhookService {
+            subscriptions: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn create(&self, input: WebhookCreate) -> Result<WebhookSubscription, String> {
+        let url = input.url.trim();
+        if !(url.starts_with("http://") || url.starts_with("https://")) {
+            return Err("Webhook url must be an http(s) URL".to_string());
+        }
+
+        if let Some(expression) = &input.filter {
+            jmespath::compile(expression)
+                .map_err(|e| format!("Invalid filter expression: {}", e))?;
+        }
+
+        if let Some(batch) = &input.batch {
+            if batch.max_size == 0 || batch.max_size > MAX_BATCH_SIZE {
+                return Err(format!(
+                    "Batch maxSize must be between 1 and {}",
+                    MAX_BATCH_SIZE
+                ));
+            }
+            if batch.max_interval_ms == 0 || batch.max_interval_ms > MAX_BATCH_INTERVAL_MS {
+                return Err(format!(
+                    "Batch maxIntervalMs must be between 1 and {}",
+                    MAX_BATCH_INTERVAL_MS
+                ));
+            }
+        }
+
+        let subscription = WebhookSubscription {
+            id: Uuid::new_v4().to_string(),
+            url: url.to_string(),
+            events: input.events.unwrap_or_default(),
+            filter: input.filter,
+            batch: input.batch,
+            created_at: Utc::now(),
+        };
+
+        self.subscriptions
+            .lock()
+            .unwrap()
+            .insert(subscription.id.clone(), subscription.clone());
+        Ok(subscription)
+    }
+
+    pub fn get_all(&self) -> Vec<WebhookSubscription> {
+        let mut subscriptions: Vec<WebhookSubscription> = self
+            .subscriptions
+            .lock()
+            .unwrap()
+            .values()
+            .cloned()
+            .collect();
+        subscriptions.sort_by_key(|s| s.created_at);
+        subscriptions
+    }
+
+    pub fn get_by_id(&self, id: &str) -> Option<WebhookSubscription> {
+        self.subscriptions.lock().unwrap().get(id).cloned()
+    }
+
+    pub fn delete(&self, id: &str) -> bool {
+        self.subscriptions.lock().unwrap().remove(id).is_some()
+    }
+
+    pub fn matching(&self, event: &Event) -> Vec<WebhookSubscription> {
+        self.subscriptions
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|s| s.matches(event))
+            .cloned()
+            .collect()
+    }
+}
+
+impl Default for WebhookService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+struct PendingBatch {
+    subscription: WebhookSubscription,
+    events: Vec<Event>,
+    opened_at: Instant,
+}
+
+/// Per-subscription buffers for batched delivery.
+#[derive(Default)]
+pub struct BatchBuffer {
+    pending: HashMap<String, PendingBatch>,
+}
+
+impl BatchBuffer {
+    /// Buffers an event, returning the batch if it just reached its size limit.
+    pub fn push(
+        &mut self,
+        subscription: &WebhookSubscription,
+        config: BatchConfig,
+        event: Event,
+        now: Instant,
+    ) -> Option<(WebhookSubscription, Vec<Event>)> {
+        let batch = self
+            .pending
+            .entry(subscription.id.clone())
+            .or_insert_with(|| PendingBatch {
+                subscription: subscription.clone(),
+                events: Vec::new(),
+                opened_at: now,
+            });
+        batch.events.push(event);
+
+        if batch.events.len() >= config.max_size {
+            self.pending
+                .remove(&subscription.id)
+                .map(|batch| (batch.subscription, batch.events))
+        } else {
+            None
+        }
+    }
+
+    /// Removes and returns every batch whose interval has elapsed.
+    pub fn take_due(&mut self, now: Instant) -> Vec<(WebhookSubscription, Vec<Event>)> {
+        let due: Vec<String> = self
+            .pending
+            .iter()
+            .filter(|(_, batch)| {
+                let interval = batch
+                    .subscription
+                    .batch
+                    .map(|config| Duration::from_millis(config.max_interval_ms))
+                    .unwrap_or_default();
+                now.duration_since(batch.opened_at) >= interval
+            })
+            .map(|(id, _)| id.clone())
+            .collect();
+
+        due.iter()
+            .filter_map(|id| self.pending.remove(id))
+            .map(|batch| (batch.subscription, batch.events))
+            .collect()
+    }
+
+    pub fn take_all(&mut self) -> Vec<(WebhookSubscription, Vec<Event>)> {
+        self.pending
+            .drain()
+            .map(|(_, batch)| (batch.subscription, batch.events))
+            .collect()
+    }
+}
+
+/// Forwards events from the service's event stream to matching subscriptions.
+/// Runs on the actix runtime for the lifetime of the server.
+pub async fn run_dispatcher(
+    webhooks: actix_web::web::Data<WebhookService>,
+    metrics: actix_web::web::Data<Metrics>,
+    mut receiver: broadcast::Receiver<Event>,
+) {
+    let client = awc::Client::builder().timeout(DELIVERY_TIMEOUT).finish();
+    let mut batches = BatchBuffer::default();
+    let mut flush_tick = tokio::time::interval(BATCH_FLUSH_TICK);
+
+    loop {
+        tokio::select! {
+            received = receiver.recv() => {
+                let event = match received {
+                    Ok(event) => event,
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        eprintln!("Webhook dispatcher lagged, skipped {} events", skipped);
+                        continue;
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                };
+
+                for subscription in webhooks.matching(&event) {
+                    match subscription.batch {
+                        Some(config) => {
+                            let now = Instant::now();
+                            if let Some((subscription, events)) =
+                                batches.push(&subscription, config, event.clone(), now)
+                            {
+                                deliver_batch(&client, &metrics, &subscription, &events, false)
+                                    .await;
+                            }
+                        }
+                        None => deliver_single(&client, &metrics, &subscription, &event).await,
+                    }
+                }
+            }
+            _ = flush_tick.tick() => {
+                for (subscription, events) in batches.take_due(Instant::now()) {
+                    deliver_batch(&client, &metrics, &subscription, &events, false).await;
+                }
+            }
+        }
+    }
+
+    for (subscription, events) in batches.take_all() {
+        deliver_batch(&client, &metrics, &subscription, &events, false).await;
+    }
+}
+
+/// Re-delivers historical events to a single subscription, honouring its
+/// batching configuration. Replayed requests carry `X-Webhook-Replay: true`
+/// so receivers can tell them apart from live traffic.
+pub async fn replay(
+    metrics: actix_web::web::Data<Metrics>,
+    subscription: WebhookSubscription,
+    events: Vec<Event>,
+) {
+    let client = awc::Client::builder().timeout(DELIVERY_TIMEOUT).finish();
+
+    match subscription.batch {
+        Some(config) => {
+            for chunk in events.chunks(config.max_size) {
+                deliver_batch(&client, &metrics, &subscription, chunk, true).await;
+            }
+        }
+        None => {
+            let headers = [("X-Webhook-Replay", "true".to_string())];
+            for event in &events {
+                if let Err(e) = deliver(&client, &metrics, &subscription, event, &headers).await {
+                    eprintln!("Webhook replay to {} failed: {}", subscription.url, e);
+                }
+            }
+        }
+    }
+}
+
+async fn deliver_single(
+    client: &awc::Client,
+    metrics: &Metrics,
+    subscription: &WebhookSubscription,
+    event: &Event,
+) {
+    if let Err(e) = deliver(client, metrics, subscription, event, &[]).await {
+        eprintln!("Webhook delivery to {} failed: {}", subscription.url, e);
+    }
+}
+
+async fn deliver_batch(
+    client: &awc::Client,
+    metrics: &Metrics,
+    subscription: &WebhookSubscription,
+    events: &[Event],
+    replay: bool,
+) {
+    let body = serde_json::json!({
+        "count": events.len(),
+        "events": events,
+    });
+    let mut headers = vec![("X-Webhook-Batch-Size", events.len().to_string())];
+    if replay {
+        headers.push(("X-Webhook-Replay", "true".to_string()));
+    }
+    if let Err(e) = deliver(client, metrics, subscription, &body, &headers).await {
+        eprintln!(
+            "Webhook batch delivery of {} events to {} failed: {}",
+            events.len(),
+            subscription.url,
+            e
+        );
+    }
+}
+
+async fn deliver<T: Serialize>(
+    client: &awc::Client,
+    metrics: &Metrics,
+    subscription: &WebhookSubscription,
+    body: &T,
+    headers: &[(&'static str, String)],
+) -> Result<(), String> {
+    let mut request = client
+        .post(&subscription.url)
+        .insert_header(("X-Webhook-Id", subscription.id.as_str()));
+    for (name, value) in headers {
+        request = request.insert_header((*name, value.as_str()));
+    }
+
+    let result = match request.send_json(body).await {
+        Ok(response) if response.status().is_success() => Ok(()),
+        Ok(response) => Err(format!("receiver responded with {}", response.status())),
+        Err(e) => Err(e.to_string()),
+    };
+    metrics.observe_webhook_delivery(result.is_ok());
+    result
+}
+
+pub struct WebhooksPlugin;
+
+impl Plugin for WebhooksPlugin {
+    fn name(&self) -> &'static str {
+        "webhooks"
+    }
+
+    fn kind(&self) -> Kind {
+        Kind::Notifier
+    }
+
+    fn description(&self) -> &'static str {
+        "Delivers todo events to registered URLs"
+    }
+
+    fn enabled(&self, _config: &Config) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spicy_todo_core::models::{todo_id, Priority, Todo};
+
+    fn event(event_type: EventType, priority: Priority) -> Event {
+        Event {
+            id: "event-1".to_string(),
+            sequence: 1,
+            event_type,
+            todo_id: "todo-1".to_string(),
+            actor: None,
+            request_id: None,
+            workspace: None,
+            timestamp: Utc::now(),
+            todo: Todo {
+                id: todo_id("todo-1"),
+                text: "Ship it".to_string(),
+                priority,
+                completed: event_type == EventType::Completed,
+                due_date: None,
+                reminder_time: None,
+                recurrence: None,
+                recurrence_end: None,
+                remaining_occurrences: None,
+                estimate_minutes: None,
+                location: None,
+                color: None,
+                icon: None,
+                pinned: false,
+                rollover_count: 0,
+                rolled_over_to: None,
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+            },
+            child: None,
+        }
+    }
+
+    fn create(
+        service: &WebhookService,
+        events: Option<Vec<EventType>>,
+        filter: Option<&str>,
+    ) -> WebhookSubscription {
+        service
+            .create(WebhookCreate {
+                url: "http://localhost:9000/hook".to_string(),
+                events,
+                filter: filter.map(|f| f.to_string()),
+                batch: None,
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn test_matches_event_types() {
+        let service = WebhookService::new();
+        let all = create(&service, None, None);
+        let only_created = create(&service, Some(vec![EventType::Created]), None);
+
+        let completed = event(EventType::Completed, Priority::Low);
+        assert!(all.matches(&completed));
+        assert!(!only_created.matches(&completed));
+    }
+
+    #[test]
+    fn test_matches_filter_expression() {
+        let service = WebhookService::new();
+        let subscription = create(
+            &service,
+            None,
+            Some("type == 'todo.completed' && todo.priority == 'high'"),
+        );
+
+        assert!(subscription.matches(&event(EventType::Completed, Priority::High)));
+        assert!(!subscription.matches(&event(EventType::Completed, Priority::Low)));
+        assert!(!subscription.matches(&event(EventType::Created, Priority::High)));
+    }
+
+    #[test]
+    fn test_create_rejects_invalid_input() {
+        let service = WebhookService::new();
+
+        let bad_filter = service.create(WebhookCreate {
+            url: "http://localhost:9000/hook".to_string(),
+            events: None,
+            filter: Some("todo.priority ==".to_string()),
+            batch: None,
+        });
+        assert!(bad_filter.is_err());
+
+        let bad_url = service.create(WebhookCreate {
+            url: "ftp://example.com".to_string(),
+            events: None,
+            filter: None,
+            batch: None,
+        });
+        assert!(bad_url.is_err());
+
+        let bad_batch = service.create(WebhookCreate {
+            url: "http://localhost:9000/hook".to_string(),
+            events: None,
+            filter: None,
+            batch: Some(BatchConfig {
+                max_size: 0,
+                max_interval_ms: 1000,
+            }),
+        });
+        assert!(bad_batch.is_err());
+        assert!(service.get_all().is_empty());
+    }
+
+    #[test]
+    fn test_matching_returns_only_interested_subscriptions() {
+        let service = WebhookService::new();
+        create(&service, Some(vec![EventType::Deleted]), None);
+        let wanted = create(&service, None, Some("todo.priority == 'high'"));
+
+        let matched = service.matching(&event(EventType::Created, Priority::High));
+        assert_eq!(matched.len(), 1);
+        assert_eq!(matched[0].id, wanted.id);
+    }
+
+    #[test]
+    fn test_batch_buffer_flushes_on_size() {
+        let config = BatchConfig {
+            max_size: 2,
+            max_interval_ms: 60_000,
+        };
+        let mut subscription = create(&WebhookService::new(), None, None);
+        subscription.batch = Some(config);
+        let mut buffer = BatchBuffer::default();
+        let now = Instant::now();
+
+        let first = event(EventType::Created, Priority::Low);
+        assert!(buffer.push(&subscription, config, first, now).is_none());
+
+        let second = event(EventType::Completed, Priority::Low);
+        let (flushed_for, events) = buffer.push(&subscription, config, second, now).unwrap();
+        assert_eq!(flushed_for.id, subscription.id);
+        assert_eq!(events.len(), 2);
+        assert!(buffer.take_all().is_empty());
+    }
+
+    #[test]
+    fn test_batch_buffer_flushes_on_interval() {
+        let config = BatchConfig {
+            max_size: 100,
+            max_interval_ms: 1000,
+        };
+        let mut subscription = create(&WebhookService::new(), None, None);
+        subscription.batch = Some(config);
+        let mut buffer = BatchBuffer::default();
+        let opened = Instant::now();
+
+        buffer.push(
+            &subscription,
+            config,
+            event(EventType::Created, Priority::Low),
+            opened,
+        );
+        assert!(buffer
+            .take_due(opened + Duration::from_millis(500))
+            .is_empty());
+
+        let due = buffer.take_due(opened + Duration::from_millis(1000));
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].1.len(), 1);
+    }
+}
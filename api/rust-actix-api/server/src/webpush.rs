@@ -0,0 +1,459 @@
+use crate::config::{Config, WebPushSettings};
+use crate::plugins::{Kind, Plugin};
+use aes_gcm::aead::{Aead, KeyInit, OsRng};
+use aes_gcm::{Aes128Gcm, Nonce};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use hkdf::Hkdf;
+use p256::ecdh::EphemeralSecret;
+use p256::ecdsa::signature::Signer;
+use p256::ecdsa::{Signature, SigningKey};
+use p256::elliptic_curve::sec1::ToEncodedPoint;
+use p256::PublicKey;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use spicy_todo_core::models::Priority;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+const SEND_TIMEOUT: Duration = Duration::from_secs(10);
+/// How long the push service keeps an undelivered message, in seconds.
+const TTL_SECS: u32 = 24 * 60 * 60;
+/// VAPID tokens are valid for at most 24 hours; stay well inside that.
+const TOKEN_LIFETIME_SECS: i64 = 12 * 60 * 60;
+const MAX_SUBSCRIPTIONS: usize = 1000;
+/// Record size advertised in the aes128gcm header. Payloads are single
+/// records, so anything above the largest payload will do.
+const RECORD_SIZE: u32 = 4096;
+
+/// A browser's push subscription, exactly as `PushSubscription.toJSON()`
+/// returns it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WebPushSubscription {
+    pub endpoint: String,
+    pub keys: SubscriptionKeys,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SubscriptionKeys {
+    /// The browser's P-256 public key, base64url.
+    pub p256dh: String,
+    /// 16 byte authentication secret, base64url.
+    pub auth: String,
+}
+
+impl WebPushSubscription {
+    pub fn validate(&self) -> Result<(), String> {
+        let uri: awc::http::Uri = self
+            .endpoint
+            .parse()
+            .map_err(|_| format!("Invalid endpoint '{}'", self.endpoint))?;
+        if !matches!(uri.scheme_str(), Some("http") | Some("https")) || uri.host().is_none() {
+            return Err("endpoint must be an absolute http(s) URL".to_string());
+        }
+        self.client_keys().map(|_| ())
+    }
+
+    fn client_keys(&self) -> Result<(PublicKey, [u8; 16]), String> {
+        let p256dh = URL_SAFE_NO_PAD
+            .decode(self.keys.p256dh.trim_end_matches('='))
+            .ok()
+            .and_then(|bytes| PublicKey::from_sec1_bytes(&bytes).ok())
+            .ok_or("keys.p256dh must be a base64url P-256 public key")?;
+        let auth = URL_SAFE_NO_PAD
+            .decode(self.keys.auth.trim_end_matches('='))
+            .ok()
+            .and_then(|bytes| <[u8; 16]>::try_from(bytes).ok())
+            .ok_or("keys.auth must be 16 bytes, base64url")?;
+        Ok((p256dh, auth))
+    }
+}
+
+/// What the service worker receives, as JSON.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct WebPushMessage {
+    pub title: String,
+    pub body: String,
+    /// Lets the browser replace an older notification about the same todo.
+    pub tag: String,
+    #[serde(skip)]
+    pub priority: Priority,
+}
+
+/// The application server's VAPID identity.
+pub struct Vapid {
+    key: SigningKey,
+    /// `mailto:` or `https:` contact the push services can reach.
+    subject: String,
+}
+
+impl Vapid {
+    pub fn from_settings(settings: &WebPushSettings) -> Result<Self, String> {
+        let key = URL_SAFE_NO_PAD
+            .decode(settings.private_key.trim().trim_end_matches('='))
+            .ok()
+            .and_then(|bytes| SigningKey::from_slice(&bytes).ok())
+            .ok_or("VAPID_PRIVATE_KEY must be a base64url P-256 private key")?;
+        let subject = settings
+            .subject
+            .clone()
+            .ok_or("VAPID_SUBJECT is required when Web Push is enabled")?;
+        if !subject.starts_with("mailto:") && !subject.starts_with("https://") {
+            return Err(format!(
+                "VAPID_SUBJECT '{}' must be a mailto: or https: URL",
+                subject
+            ));
+        }
+        Ok(Vapid { key, subject })
+    }
+
+    /// The public key browsers pass as `applicationServerKey`, base64url.
+    pub fn public_key(&self) -> String {
+        let point = self.key.verifying_key().to_encoded_point(false);
+        URL_SAFE_NO_PAD.encode(point.as_bytes())
+    }
+
+    /// The `Authorization` header for a request to `endpoint`.
+    fn authorization(&self, endpoint: &str, now: DateTime<Utc>) -> Result<String, String> {
+        let uri: awc::http::Uri = endpoint.parse().map_err(|_| "Invalid endpoint")?;
+        let audience = format!(
+            "{}://{}",
+            uri.scheme_str().unwrap_or("https"),
+            uri.authority().ok_or("Endpoint has no host")?
+        );
+        let header = URL_SAFE_NO_PAD.encode(r#"{"typ":"JWT","alg":"ES256"}"#);
+        let claims = URL_SAFE_NO_PAD.encode(
+            serde_json::json!({
+                "aud": audience,
+                "exp": now.timestamp() + TOKEN_LIFETIME_SECS,
+                "sub": self.subject
+            })
+            .to_string(),
+        );
+        let unsigned = format!("{}.{}", header, claims);
+        let signature: Signature = self.key.sign(unsigned.as_bytes());
+        Ok(format!(
+            "vapid t={}.{}, k={}",
+            unsigned,
+            URL_SAFE_NO_PAD.encode(signature.to_bytes()),
+            self.public_key()
+        ))
+    }
+}
+
+/// Encrypts `payload` for `subscription` as a single aes128gcm record
+/// (RFC 8291), returning the request body.
+pub fn encrypt(subscription: &WebPushSubscription, payload: &[u8]) -> Result<Vec<u8>, String> {
+    let (client_key, auth) = subscription.client_keys()?;
+    let server_secret = EphemeralSecret::random(&mut OsRng);
+    let server_key = server_secret.public_key().to_encoded_point(false);
+    let client_point = client_key.to_encoded_point(false);
+    let shared = server_secret.diffie_hellman(&client_key);
+    let salt: [u8; 16] = rand::random();
+
+    let mut key_info = b"WebPush: info\0".to_vec();
+    key_info.extend_from_slice(client_point.as_bytes());
+    key_info.extend_from_slice(server_key.as_bytes());
+    let mut ikm = [0u8; 32];
+    Hkdf::<Sha256>::new(Some(&auth), shared.raw_secret_bytes())
+        .expand(&key_info, &mut ikm)
+        .map_err(|e| e.to_string())?;
+    let prk = Hkdf::<Sha256>::new(Some(&salt), &ikm);
+    let mut cek = [0u8; 16];
+    let mut nonce = [0u8; 12];
+    prk.expand(b"Content-Encoding: aes128gcm\0", &mut cek)
+        .and_then(|()| prk.expand(b"Content-Encoding: nonce\0", &mut nonce))
+        .map_err(|e| e.to_string())?;
+
+    // A single record ends with the 0x02 delimiter and no padding.
+    let mut plaintext = payload.to_vec();
+    plaintext.push(2);
+    let ciphertext = Aes128Gcm::new_from_slice(&cek)
+        .map_err(|e| e.to_string())?
+        .encrypt(Nonce::from_slice(&nonce), plaintext.as_slice())
+        .map_err(|e| e.to_string())?;
+
+    let mut body = salt.to_vec();
+    body.extend_from_slice(&RECORD_SIZE.to_be_bytes());
+    body.push(server_key.as_bytes().len() as u8);
+    body.extend_from_slice(server_key.as_bytes());
+    body.extend_from_slice(&ciphertext);
+    Ok(body)
+}
+
+/// How a delivery attempt ended.
+#[derive(Debug, PartialEq)]
+pub enum SendError {
+    /// The push service no longer knows the subscription (404 or 410).
+    Gone,
+    Failed(String),
+}
+
+/// Web Push subscriptions by endpoint, and the VAPID key to reach them.
+pub struct WebPushService {
+    vapid: Vapid,
+    subscriptions: Mutex<HashMap<String, WebPushSubscription>>,
+}
+
+impl WebPushService {
+    pub fn new(vapid: Vapid) -> Self {
+        WebPushService {
+            vapid,
+            subscriptions: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn public_key(&self) -> String {
+        self.vapid.public_key()
+    }
+
+    /// Stores `subscription`, replacing any with the same endpoint.
+    /// Returns whether it was new.
+    pub fn subscribe(&self, subscription: WebPushSubscription) -> Result<bool, String> {
+        subscription.validate()?;
+        let mut subscriptions = self.subscriptions.lock().unwrap();
+        if !subscriptions.contains_key(&subscription.endpoint)
+            && subscriptions.len() >= MAX_SUBSCRIPTIONS
+        {
+            return Err(format!(
+                "At most {} subscriptions are kept",
+                MAX_SUBSCRIPTIONS
+            ));
+        }
+        Ok(subscriptions
+            .insert(subscription.endpoint.clone(), subscription)
+            .is_none())
+    }
+
+    pub fn unsubscribe(&self, endpoint: &str) -> bool {
+        self.subscriptions
+            .lock()
+            .unwrap()
+            .remove(endpoint)
+            .is_some()
+    }
+
+    /// Sends `message` to one subscription.
+    pub async fn send(
+        &self,
+        subscription: &WebPushSubscription,
+        message: &WebPushMessage,
+        now: DateTime<Utc>,
+    ) -> Result<(), SendError> {
+        let payload = serde_json::to_vec(message).map_err(|e| SendError::Failed(e.to_string()))?;
+        let body = encrypt(subscription, &payload).map_err(SendError::Failed)?;
+        let authorization = self
+            .vapid
+            .authorization(&subscription.endpoint, now)
+            .map_err(SendError::Failed)?;
+        let urgency = match message.priority {
+            Priority::Low => "low",
+            Priority::Medium => "normal",
+            Priority::High => "high",
+        };
+        let client = awc::Client::builder().timeout(SEND_TIMEOUT).finish();
+        let response = client
+            .post(&subscription.endpoint)
+            .insert_header(("Authorization", authorization))
+            .insert_header(("Content-Encoding", "aes128gcm"))
+            .insert_header(("Content-Type", "application/octet-stream"))
+            .insert_header(("TTL", TTL_SECS.to_string()))
+            .insert_header(("Urgency", urgency))
+            .send_body(body)
+            .await
+            .map_err(|e| SendError::Failed(format!("Push service unreachable: {}", e)))?;
+        match response.status().as_u16() {
+            200..=299 => Ok(()),
+            404 | 410 => Err(SendError::Gone),
+            status => Err(SendError::Failed(format!(
+                "Push service responded with {}",
+                status
+            ))),
+        }
+    }
+
+    /// Sends `message` to every subscription, returning how many accepted
+    /// it. Subscriptions the push service reports gone are dropped.
+    pub async fn broadcast(&self, message: &WebPushMessage, now: DateTime<Utc>) -> usize {
+        let subscriptions: Vec<WebPushSubscription> = self
+            .subscriptions
+            .lock()
+            .unwrap()
+            .values()
+            .cloned()
+            .collect();
+        let mut delivered = 0;
+        for subscription in subscriptions {
+            match self.send(&subscription, message, now).await {
+                Ok(()) => delivered += 1,
+                Err(SendError::Gone) => {
+                    self.unsubscribe(&subscription.endpoint);
+                }
+                Err(SendError::Failed(e)) => eprintln!("Web Push failed: {}", e),
+            }
+        }
+        delivered
+    }
+}
+
+#[cfg(test)]
+pub mod testing {
+    use super::*;
+    use p256::SecretKey;
+
+    /// A browser's side of a subscription, able to read what it receives.
+    pub struct Browser {
+        secret: SecretKey,
+        auth: [u8; 16],
+    }
+
+    impl Browser {
+        pub fn new() -> Self {
+            Browser {
+                secret: SecretKey::random(&mut OsRng),
+                auth: rand::random(),
+            }
+        }
+
+        pub fn subscription(&self, endpoint: &str) -> WebPushSubscription {
+            WebPushSubscription {
+                endpoint: endpoint.to_string(),
+                keys: SubscriptionKeys {
+                    p256dh: URL_SAFE_NO_PAD
+                        .encode(self.secret.public_key().to_encoded_point(false).as_bytes()),
+                    auth: URL_SAFE_NO_PAD.encode(self.auth),
+                },
+            }
+        }
+
+        /// Decrypts an aes128gcm body, as the browser would.
+        pub fn decrypt(&self, body: &[u8]) -> Vec<u8> {
+            let salt = &body[..16];
+            let key_len = body[20] as usize;
+            let server_key = PublicKey::from_sec1_bytes(&body[21..21 + key_len]).unwrap();
+            let ciphertext = &body[21 + key_len..];
+            let shared =
+                p256::ecdh::diffie_hellman(self.secret.to_nonzero_scalar(), server_key.as_affine());
+
+            let mut key_info = b"WebPush: info\0".to_vec();
+            key_info.extend_from_slice(self.secret.public_key().to_encoded_point(false).as_bytes());
+            key_info.extend_from_slice(server_key.to_encoded_point(false).as_bytes());
+            let mut ikm = [0u8; 32];
+            Hkdf::<Sha256>::new(Some(&self.auth), shared.raw_secret_bytes())
+                .expand(&key_info, &mut ikm)
+                .unwrap();
+            let prk = Hkdf::<Sha256>::new(Some(salt), &ikm);
+            let mut cek = [0u8; 16];
+            let mut nonce = [0u8; 12];
+            prk.expand(b"Content-Encoding: aes128gcm\0", &mut cek)
+                .unwrap();
+            prk.expand(b"Content-Encoding: nonce\0", &mut nonce)
+                .unwrap();
+            let mut plaintext = Aes128Gcm::new_from_slice(&cek)
+                .unwrap()
+                .decrypt(Nonce::from_slice(&nonce), ciphertext)
+                .unwrap();
+            assert_eq!(plaintext.pop(), Some(2));
+            plaintext
+        }
+    }
+
+    impl Default for Browser {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+}
+
+pub struct WebPushPlugin;
+
+impl Plugin for WebPushPlugin {
+    fn name(&self) -> &'static str {
+        "web-push"
+    }
+
+    fn kind(&self) -> Kind {
+        Kind::Notifier
+    }
+
+    fn description(&self) -> &'static str {
+        "Sends reminders to browsers through Web Push"
+    }
+
+    fn enabled(&self, config: &Config) -> bool {
+        config.web_push.is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::testing::Browser;
+    use super::*;
+    use p256::ecdsa::signature::Verifier;
+
+    fn vapid() -> Vapid {
+        let settings = WebPushSettings {
+            private_key: URL_SAFE_NO_PAD.encode([7u8; 32]),
+            subject: Some("mailto:ops@example.com".to_string()),
+        };
+        Vapid::from_settings(&settings).unwrap()
+    }
+
+    #[test]
+    fn test_encrypt_round_trip() {
+        let browser = Browser::new();
+        let subscription = browser.subscription("https://push.example/send/abc");
+        assert!(subscription.validate().is_ok());
+        let body = encrypt(&subscription, b"{\"title\":\"Reminder\"}").unwrap();
+        assert_eq!(&body[16..20], &RECORD_SIZE.to_be_bytes());
+        assert_eq!(browser.decrypt(&body), b"{\"title\":\"Reminder\"}");
+    }
+
+    #[test]
+    fn test_vapid_token_verifies() {
+        let vapid = vapid();
+        let now = Utc::now();
+        let header = vapid
+            .authorization("https://push.example:8443/send/abc", now)
+            .unwrap();
+        let (token, key) = header
+            .strip_prefix("vapid t=")
+            .unwrap()
+            .split_once(", k=")
+            .unwrap();
+        assert_eq!(key, vapid.public_key());
+
+        let (unsigned, signature) = token.rsplit_once('.').unwrap();
+        let signature = Signature::from_slice(&URL_SAFE_NO_PAD.decode(signature).unwrap()).unwrap();
+        assert!(vapid
+            .key
+            .verifying_key()
+            .verify(unsigned.as_bytes(), &signature)
+            .is_ok());
+        let claims = unsigned.split('.').nth(1).unwrap();
+        let claims: serde_json::Value =
+            serde_json::from_slice(&URL_SAFE_NO_PAD.decode(claims).unwrap()).unwrap();
+        assert_eq!(claims["aud"], "https://push.example:8443");
+        assert_eq!(claims["sub"], "mailto:ops@example.com");
+    }
+
+    #[test]
+    fn test_subscribe_validates_keys() {
+        let service = WebPushService::new(vapid());
+        let browser = Browser::new();
+        let subscription = browser.subscription("https://push.example/send/abc");
+        assert_eq!(service.subscribe(subscription.clone()), Ok(true));
+        assert_eq!(service.subscribe(subscription.clone()), Ok(false));
+
+        let mut bad = subscription.clone();
+        bad.keys.auth = "c2hvcnQ".to_string();
+        assert!(service.subscribe(bad).is_err());
+        let mut bad = subscription;
+        bad.endpoint = "ftp://push.example".to_string();
+        assert!(service.subscribe(bad).is_err());
+        assert!(service.unsubscribe("https://push.example/send/abc"));
+        assert!(!service.unsubscribe("https://push.example/send/abc"));
+    }
+}
@@ -0,0 +1,626 @@
+use actix_web::test::TestRequest;
+use actix_web::{web, App, HttpResponse, HttpServer};
+use serde_json::json;
+use spicy_todo_server::auth::AuthMode;
+use spicy_todo_server::config::{
+    Config, DemoSettings, OidcSettings, SecurityHeaderSettings, StorageBackend,
+};
+use spicy_todo_server::oidc;
+use spicy_todo_server::test_util::TestApp;
+use std::collections::{BTreeMap, HashMap};
+
+#[actix_web::test]
+async fn test_crud_on_every_backend() {
+    for backend in [
+        StorageBackend::Memory,
+        StorageBackend::Journal,
+        StorageBackend::Snapshot,
+        StorageBackend::EventStore,
+    ] {
+        let app = TestApp::builder().backend(backend).build().await;
+
+        let todo = app.create_todo("Pay rent").await;
+        let updated = app
+            .update_todo(&todo.id.to_string(), json!({ "priority": "high" }))
+            .await;
+        assert_eq!(updated.text, "Pay rent");
+        assert!(app.toggle_todo(&todo.id.to_string()).await.completed);
+        assert_eq!(app.list_todos("filter=completed").await.len(), 1);
+        assert_eq!(app.stats().await.completed, 1);
+
+        app.delete_todo(&todo.id.to_string()).await;
+        assert!(
+            app.get_todo(&todo.id.to_string()).await.is_none(),
+            "{:?}",
+            backend
+        );
+        assert!(app.service().get_all(None, None, None).is_empty());
+    }
+}
+
+#[actix_web::test]
+async fn test_errors_carry_codes() {
+    let app = TestApp::new().await;
+    let error = app
+        .expect_error(
+            TestRequest::post()
+                .uri("/api/todos")
+                .set_json(json!({ "text": " " })),
+            400,
+        )
+        .await;
+    assert_eq!(error.error, "Todo text is required");
+    assert!(error.code.is_some());
+}
+
+#[actix_web::test]
+async fn test_malformed_todo_ids_are_rejected() {
+    let app = TestApp::new().await;
+    for req in [
+        TestRequest::get().uri("/api/todos/%00"),
+        TestRequest::patch().uri("/api/v1/todos/1234/toggle"),
+        TestRequest::post().uri("/api/myday/today/todos/missing"),
+    ] {
+        let error = app.expect_error(req, 400).await;
+        assert_eq!(error.error, "Todo id must be a UUID");
+        assert_eq!(error.code.as_deref(), Some("INVALID_ID"));
+    }
+    let missing = uuid::Uuid::new_v4().to_string().to_uppercase();
+    let error = app
+        .expect_error(
+            TestRequest::get().uri(&format!("/api/todos/{}", missing)),
+            404,
+        )
+        .await;
+    assert_eq!(error.code.as_deref(), Some("TODO_NOT_FOUND"));
+}
+
+#[actix_web::test]
+async fn test_events_trace_back_to_requests() {
+    let app = TestApp::new().await;
+    let req = TestRequest::post()
+        .uri("/api/todos")
+        .insert_header(("x-request-id", "trace-1"))
+        .insert_header(("x-client-id", "phone"))
+        .set_json(json!({ "text": "Pay rent" }));
+    let resp = app.call(req).await;
+    assert_eq!(resp.status(), 201);
+    assert_eq!(resp.headers().get("x-request-id").unwrap(), "trace-1");
+    app.create_todo("Call mum").await;
+
+    let page: serde_json::Value = app
+        .send(
+            TestRequest::get().uri("/api/events/log?requestId=trace-1"),
+            200,
+        )
+        .await;
+    let events = page["items"].as_array().unwrap();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0]["todo"]["text"], "Pay rent");
+    assert_eq!(events[0]["actor"], "phone");
+}
+
+#[actix_web::test]
+async fn test_session_login_guards_the_api() {
+    let users = std::env::temp_dir().join(format!("users-{}", uuid::Uuid::new_v4()));
+    let hash = spicy_todo_server::auth::hash_password("hunter2").unwrap();
+    std::fs::write(&users, format!("alice:{}\n", hash)).unwrap();
+    let mut config = Config::default();
+    config.auth.mode = AuthMode::Session;
+    config.auth.users_file = Some(users.clone());
+    let app = TestApp::builder().config(config).build().await;
+    std::fs::remove_file(&users).unwrap();
+
+    let resp = app.call(TestRequest::get().uri("/api/todos")).await;
+    assert_eq!(resp.status(), 401);
+    // Without a `FEED_TOKEN` the feed has nothing of its own to check.
+    let resp = app
+        .call(TestRequest::get().uri("/api/todos/feed.atom"))
+        .await;
+    assert_eq!(resp.status(), 401);
+    let login = |password: &str| {
+        TestRequest::post()
+            .uri("/api/auth/login")
+            .set_json(json!({ "username": "alice", "password": password }))
+    };
+    assert_eq!(app.call(login("hunter3")).await.status(), 401);
+
+    let resp = app.call(login("hunter2")).await;
+    assert_eq!(resp.status(), 200);
+    let cookie = resp.response().cookies().next().unwrap().into_owned();
+    let body: serde_json::Value = actix_web::test::read_body_json(resp).await;
+    let csrf = body["csrfToken"].as_str().unwrap().to_string();
+
+    let resp = app
+        .call(TestRequest::get().uri("/api/todos").cookie(cookie.clone()))
+        .await;
+    assert_eq!(resp.status(), 200);
+    let create = || {
+        TestRequest::post()
+            .uri("/api/todos")
+            .cookie(cookie.clone())
+            .set_json(json!({ "text": "Pay rent" }))
+    };
+    assert_eq!(app.call(create()).await.status(), 403);
+    let resp = app
+        .call(create().insert_header(("x-csrf-token", csrf)))
+        .await;
+    assert_eq!(resp.status(), 201);
+    let events = app.service().events().recent(1);
+    assert_eq!(events[0].actor.as_deref(), Some("alice"));
+}
+
+#[actix_web::test]
+async fn test_sign_in_with_provider_creates_account() {
+    let provider = HttpServer::new(|| {
+        App::new()
+            .route(
+                "/token",
+                web::post().to(|form: web::Form<HashMap<String, String>>| async move {
+                    assert_eq!(form["code"], "good-code");
+                    assert!(!form["code_verifier"].is_empty());
+                    HttpResponse::Ok().json(json!({ "access_token": "token-1" }))
+                }),
+            )
+            .route(
+                "/userinfo",
+                web::get().to(|| async {
+                    HttpResponse::Ok().json(json!({
+                        "sub": "g-1",
+                        "email": "ann@example.com",
+                        "email_verified": true
+                    }))
+                }),
+            )
+    })
+    .workers(1)
+    .bind(("127.0.0.1", 0))
+    .unwrap();
+    let url = format!("http://{}", provider.addrs()[0]);
+    actix_rt::spawn(provider.run());
+
+    let mut google = oidc::builtin("google", "client".into(), "secret".into()).unwrap();
+    google.token_url = format!("{}/token", url);
+    google.userinfo_url = format!("{}/userinfo", url);
+    let mut config = Config::default();
+    config.auth.mode = AuthMode::Session;
+    config.auth.oidc = Some(OidcSettings {
+        redirect_url: "http://localhost/api/auth/callback".to_string(),
+        providers: BTreeMap::from([("google".to_string(), google)]),
+        allowed_domains: vec!["example.com".to_string()],
+        accounts_file: None,
+        post_login_url: "/app".to_string(),
+    });
+    let app = TestApp::builder().config(config).build().await;
+
+    let resp = app
+        .call(TestRequest::get().uri("/api/auth/login/google"))
+        .await;
+    assert_eq!(resp.status(), 302);
+    let cookie = resp.response().cookies().next().unwrap().into_owned();
+    let location = resp.headers().get("location").unwrap().to_str().unwrap();
+    let state = location
+        .split('&')
+        .find_map(|param| param.strip_prefix("state="))
+        .unwrap()
+        .to_string();
+    let callback = |state: &str| {
+        TestRequest::get()
+            .uri(&format!(
+                "/api/auth/callback?code=good-code&state={}",
+                state
+            ))
+            .cookie(cookie.clone())
+    };
+    assert_eq!(app.call(callback("forged")).await.status(), 400);
+
+    let resp = app.call(callback(&state)).await;
+    assert_eq!(resp.status(), 303);
+    assert_eq!(resp.headers().get("location").unwrap(), "/app");
+    let cookie = resp.response().cookies().next().unwrap().into_owned();
+    let resp = app
+        .call(TestRequest::get().uri("/api/auth/session").cookie(cookie))
+        .await;
+    let body: serde_json::Value = actix_web::test::read_body_json(resp).await;
+    assert_eq!(body["user"], "ann@example.com");
+}
+
+#[actix_web::test]
+async fn test_account_export_and_deletion() {
+    let app = TestApp::new().await;
+    let as_client = |req: TestRequest, client: &str| req.insert_header(("x-client-id", client));
+    let create = |client: &str, text: &str| {
+        as_client(TestRequest::post().uri("/api/todos"), client).set_json(json!({ "text": text }))
+    };
+    let mine: serde_json::Value = app.send(create("alice", "See the doctor"), 201).await;
+    let theirs: serde_json::Value = app.send(create("bob", "Water the plants"), 201).await;
+    let toggle = TestRequest::patch().uri(&format!(
+        "/api/todos/{}/toggle",
+        theirs["id"].as_str().unwrap()
+    ));
+    let _: serde_json::Value = app.send(as_client(toggle, "alice"), 200).await;
+    let put = TestRequest::put()
+        .uri("/api/preferences")
+        .set_json(json!({ "timezone": "Europe/Berlin" }));
+    let _: serde_json::Value = app.send(as_client(put, "alice"), 200).await;
+
+    let export = TestRequest::get().uri("/api/account/export");
+    let data: serde_json::Value = app.send(as_client(export, "alice"), 200).await;
+    assert_eq!(data["account"]["todos"][0]["id"], mine["id"]);
+    assert_eq!(data["account"]["history"].as_array().unwrap().len(), 2);
+    assert_eq!(data["preferences"]["timezone"], "Europe/Berlin");
+
+    let delete = TestRequest::delete().uri("/api/account");
+    let deleted: serde_json::Value = app.send(as_client(delete, "alice"), 200).await;
+    assert_eq!(deleted["removed"]["deletedTodos"], json!([mine["id"]]));
+    assert_eq!(deleted["removed"]["preferences"], true);
+    assert!(app.get_todo(mine["id"].as_str().unwrap()).await.is_none());
+    assert!(
+        app.get_todo(theirs["id"].as_str().unwrap())
+            .await
+            .unwrap()
+            .completed
+    );
+
+    let export = TestRequest::get().uri("/api/account/export");
+    let data: serde_json::Value = app.send(as_client(export, "alice"), 200).await;
+    assert!(data["account"]["history"].as_array().unwrap().is_empty());
+    assert!(data["preferences"].is_null());
+    app.expect_error(TestRequest::delete().uri("/api/account"), 400)
+        .await;
+}
+
+#[actix_web::test]
+async fn test_responses_carry_security_headers() {
+    let app = TestApp::new().await;
+    let resp = app.call(TestRequest::get().uri("/api/todos")).await;
+    let headers = resp.headers();
+    assert_eq!(headers.get("x-content-type-options").unwrap(), "nosniff");
+    assert_eq!(headers.get("referrer-policy").unwrap(), "no-referrer");
+    assert!(headers.contains_key("content-security-policy"));
+    assert!(!headers.contains_key("strict-transport-security"));
+
+    let behind_tls = TestRequest::get()
+        .uri("/api/todos/00000000-0000-0000-0000-000000000000")
+        .insert_header(("x-forwarded-proto", "https"));
+    let resp = app.call(behind_tls).await;
+    assert_eq!(resp.status(), 404);
+    assert_eq!(
+        resp.headers().get("strict-transport-security").unwrap(),
+        "max-age=31536000"
+    );
+
+    let config = Config {
+        security_headers: SecurityHeaderSettings {
+            enabled: false,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let app = TestApp::builder().config(config).build().await;
+    let resp = app.call(TestRequest::get().uri("/api/todos")).await;
+    assert!(!resp.headers().contains_key("content-security-policy"));
+}
+
+#[actix_web::test]
+async fn test_audit_is_filtered_and_admin_only() {
+    let config = Config {
+        admin_token: Some("root".to_string()),
+        ..Default::default()
+    };
+    let app = TestApp::builder().config(config).build().await;
+    for client in ["alice", "bob"] {
+        let create = TestRequest::post()
+            .uri("/api/todos")
+            .insert_header(("x-client-id", client))
+            .set_json(json!({ "text": "Review access" }));
+        let todo: serde_json::Value = app.send(create, 201).await;
+        let toggle = TestRequest::patch()
+            .uri(&format!(
+                "/api/todos/{}/toggle",
+                todo["id"].as_str().unwrap()
+            ))
+            .insert_header(("x-client-id", client));
+        let _: serde_json::Value = app.send(toggle, 200).await;
+    }
+
+    app.expect_error(TestRequest::get().uri("/api/audit"), 401)
+        .await;
+    let audit = |query: &str| {
+        TestRequest::get()
+            .uri(&format!("/api/audit?{}", query))
+            .insert_header(("x-admin-token", "root"))
+    };
+    let page: serde_json::Value = app.send(audit("actor=alice"), 200).await;
+    assert_eq!(page["total"], 2);
+    assert!(page["items"][0].get("todo").is_none());
+    let page: serde_json::Value = app.send(audit("action=todo.completed&limit=1"), 200).await;
+    assert_eq!(page["total"], 2);
+    assert_eq!(page["items"][0]["actor"], "alice");
+    assert_eq!(page["hasMore"], true);
+    app.expect_error(audit("action=todo.renamed"), 400).await;
+}
+
+#[actix_web::test]
+async fn test_colors_and_icons() {
+    let app = TestApp::new().await;
+    let spicy = app
+        .create_todo_with(json!({ "text": "Hot sauce", "color": "red", "icon": "pepper" }))
+        .await;
+    app.create_todo("Plain rice").await;
+    let bad = TestRequest::post()
+        .uri("/api/todos")
+        .set_json(json!({ "text": "Paint", "color": "#ff0000" }));
+    app.expect_error(bad, 400).await;
+
+    let red = app.list_todos("color=red").await;
+    assert_eq!(red.len(), 1);
+    assert_eq!(red[0].id, spicy.id);
+    assert!(app.list_todos("icon=star").await.is_empty());
+    let bad = TestRequest::get().uri("/api/todos?icon=skull");
+    app.expect_error(bad, 400).await;
+
+    let recolored = app
+        .update_todo(&spicy.id.to_string(), json!({ "color": "green" }))
+        .await;
+    assert_eq!(recolored.icon.map(|icon| icon.as_str()), Some("pepper"));
+
+    let create_view = TestRequest::post()
+        .uri("/api/views")
+        .set_json(json!({ "name": "Kitchen", "color": "teal", "icon": "home" }));
+    let view: serde_json::Value = app.send(create_view, 201).await;
+    assert_eq!(
+        (&view["color"], &view["icon"]),
+        (&json!("teal"), &json!("home"))
+    );
+}
+
+#[actix_web::test]
+async fn test_pinned_todos_list_first() {
+    let app = TestApp::new().await;
+    let rent = app.create_todo("Pay rent").await;
+    let taxes = app.create_todo("File taxes").await;
+
+    assert!(app.pin_todo(&taxes.id.to_string()).await.pinned);
+    let ids: Vec<String> = app
+        .list_todos("")
+        .await
+        .into_iter()
+        .map(|t| t.id.to_string())
+        .collect();
+    assert_eq!(ids, vec![taxes.id.to_string(), rent.id.to_string()]);
+    assert_eq!(app.stats().await.pinned, 1);
+
+    assert!(!app.pin_todo(&taxes.id.to_string()).await.pinned);
+    assert_eq!(app.stats().await.pinned, 0);
+    let missing = TestRequest::patch().uri("/api/todos/00000000-0000-0000-0000-000000000000/pin");
+    app.expect_error(missing, 404).await;
+}
+
+#[actix_web::test]
+async fn test_snooze_puts_off_due_date() {
+    let app = TestApp::new().await;
+    let todo = app
+        .create_todo_with(
+            json!({ "text": "Call the bank", "dueDate": "today", "reminderTime": "09:00" }),
+        )
+        .await;
+    let snooze = |body: serde_json::Value| {
+        TestRequest::post()
+            .uri(&format!("/api/todos/{}/snooze", todo.id))
+            .set_json(body)
+    };
+
+    let snoozed: serde_json::Value = app.send(snooze(json!({ "preset": "+3d" })), 200).await;
+    let today = chrono::Utc::now().date_naive();
+    let expected = (today + chrono::Days::new(3))
+        .format("%Y-%m-%d")
+        .to_string();
+    assert_eq!(snoozed["dueDate"], json!(expected));
+    assert_eq!(snoozed["reminderTime"], "09:00");
+    let snoozed: serde_json::Value = app.send(snooze(json!({ "date": "2099-01-31" })), 200).await;
+    assert_eq!(snoozed["dueDate"], "2099-01-31");
+
+    app.expect_error(snooze(json!({ "preset": "someday" })), 400)
+        .await;
+    app.expect_error(snooze(json!({ "date": "2000-01-01" })), 400)
+        .await;
+    let missing = TestRequest::post()
+        .uri("/api/todos/00000000-0000-0000-0000-000000000000/snooze")
+        .set_json(json!({ "preset": "tomorrow" }));
+    app.expect_error(missing, 404).await;
+
+    let history: serde_json::Value = app
+        .send(
+            TestRequest::get().uri("/api/events/log?type=todo.snoozed"),
+            200,
+        )
+        .await;
+    assert_eq!(history["items"].as_array().unwrap().len(), 2);
+}
+
+#[actix_web::test]
+async fn test_my_day_is_planned_apart_from_due_dates() {
+    let app = TestApp::new().await;
+    let report = app
+        .create_todo_with(json!({ "text": "Write report", "dueDate": "2099-01-31" }))
+        .await;
+    let gym = app.create_todo("Go to the gym").await;
+    let yesterday = (chrono::Utc::now().date_naive() - chrono::Days::new(1)).to_string();
+    let plan = |date: &str, id: &str| format!("/api/myday/{}/todos/{}", date, id);
+
+    let day: serde_json::Value = app
+        .send(
+            TestRequest::post().uri(&plan("today", &report.id.to_string())),
+            200,
+        )
+        .await;
+    assert_eq!(day["todos"][0]["id"], json!(report.id));
+    assert_eq!(day["todos"][0]["dueDate"], "2099-01-31");
+    let req = TestRequest::post().uri(&plan(&yesterday, &gym.id.to_string()));
+    let _: serde_json::Value = app.send(req, 200).await;
+
+    let today: serde_json::Value = app
+        .send(TestRequest::get().uri("/api/myday/today"), 200)
+        .await;
+    assert_eq!(today["todos"].as_array().unwrap().len(), 1);
+    assert_eq!(today["rollover"][0]["id"], json!(gym.id));
+    app.toggle_todo(&gym.id.to_string()).await;
+    let today: serde_json::Value = app
+        .send(TestRequest::get().uri("/api/myday/today"), 200)
+        .await;
+    assert!(today["rollover"].as_array().unwrap().is_empty());
+
+    let remove = TestRequest::delete().uri(&plan("today", &report.id.to_string()));
+    let day: serde_json::Value = app.send(remove, 200).await;
+    assert!(day["todos"].as_array().unwrap().is_empty());
+    app.expect_error(
+        TestRequest::delete().uri(&plan("today", &report.id.to_string())),
+        404,
+    )
+    .await;
+    let missing = uuid::Uuid::new_v4().to_string();
+    app.expect_error(TestRequest::post().uri(&plan("today", &missing)), 404)
+        .await;
+    app.expect_error(TestRequest::get().uri("/api/myday/someday"), 400)
+        .await;
+}
+
+#[actix_web::test]
+async fn test_activity_feed_reads_as_sentences() {
+    let app = TestApp::new().await;
+    let as_client = |req: TestRequest, client: &str| req.insert_header(("x-client-id", client));
+    let create = |text: &str| {
+        TestRequest::post()
+            .uri("/api/todos")
+            .set_json(json!({ "text": text }))
+    };
+    let report: serde_json::Value = app
+        .send(as_client(create("Write report"), "bob"), 201)
+        .await;
+    let _: serde_json::Value = app.send(as_client(create("Deploy v2"), "Alice"), 201).await;
+    let toggle = TestRequest::patch().uri(&format!(
+        "/api/todos/{}/toggle",
+        report["id"].as_str().unwrap()
+    ));
+    let _: serde_json::Value = app.send(as_client(toggle, "bob"), 200).await;
+
+    let req = as_client(TestRequest::get().uri("/api/activity?limit=2"), "bob");
+    let feed: serde_json::Value = app.send(req, 200).await;
+    assert_eq!(feed["total"], 3);
+    assert_eq!(feed["items"][0]["message"], "You completed 'Write report'");
+    assert_eq!(feed["items"][1]["message"], "Alice added 'Deploy v2'");
+    assert_eq!(feed["hasMore"], true);
+}
+
+#[actix_web::test]
+async fn test_stats_report_downloads() {
+    let app = TestApp::new().await;
+    let invoices = app
+        .create_todo_with(json!({ "text": "Send invoices #work", "priority": "high" }))
+        .await;
+    app.toggle_todo(&invoices.id.to_string()).await;
+
+    let resp = app
+        .call(TestRequest::get().uri("/api/todos/stats/report?format=csv&period=month"))
+        .await;
+    assert_eq!(resp.status(), 200);
+    assert_eq!(
+        resp.headers().get("content-type").unwrap(),
+        "text/csv; charset=utf-8"
+    );
+    let disposition = resp
+        .headers()
+        .get("content-disposition")
+        .unwrap()
+        .to_str()
+        .unwrap();
+    assert!(disposition.starts_with("attachment; filename=\"spicy-todo-month-"));
+    let body = actix_web::test::read_body(resp).await;
+    let csv = String::from_utf8(body.to_vec()).unwrap();
+    let today = chrono::Utc::now().date_naive();
+    assert!(csv.contains(&format!("\n{},1\n", today)));
+    assert!(csv.contains("\nhigh,1\n"));
+    assert!(csv.ends_with("tag,completed\nwork,1\n"));
+
+    let resp = app
+        .call(TestRequest::get().uri("/api/todos/stats/report?format=prometheus&period=week"))
+        .await;
+    let body = actix_web::test::read_body(resp).await;
+    let text = String::from_utf8(body.to_vec()).unwrap();
+    assert!(text.contains("tag=\"work\"} 1\n"));
+
+    let bad = TestRequest::get().uri("/api/todos/stats/report?period=year");
+    app.expect_error(bad, 400).await;
+}
+
+#[actix_web::test]
+async fn test_todo_stream_is_ndjson() {
+    let app = TestApp::new().await;
+    let mut ids = Vec::new();
+    for text in ["Export me", "And me", "Me too"] {
+        ids.push(app.create_todo(text).await.id.to_string());
+    }
+    ids.sort();
+
+    let resp = app.call(TestRequest::get().uri("/api/todos/stream")).await;
+    assert_eq!(resp.status(), 200);
+    assert_eq!(
+        resp.headers().get("content-type").unwrap(),
+        "application/x-ndjson"
+    );
+    let body = actix_web::test::read_body(resp).await;
+    let streamed: Vec<String> = String::from_utf8(body.to_vec())
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap()["id"].to_string())
+        .map(|id| id.trim_matches('"').to_string())
+        .collect();
+    assert_eq!(streamed, ids);
+}
+
+#[actix_web::test]
+async fn test_demo_mode_caps_and_rate_limits() {
+    let config = Config {
+        demo: Some(DemoSettings {
+            max_todos: 2,
+            writes_per_minute: 4,
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+    let app = TestApp::builder().config(config).build().await;
+    let root: serde_json::Value = app.send(TestRequest::get().uri("/"), 200).await;
+    assert!(root["banner"].as_str().unwrap().starts_with("Public demo"));
+    assert_eq!(root["demo"]["maxTodos"], 2);
+
+    let first = app.create_todo("Try the demo").await;
+    app.create_todo("Share the link").await;
+    let third = TestRequest::post()
+        .uri("/api/v1/todos")
+        .set_json(json!({ "text": "One too many" }));
+    let error = app.expect_error(third, 403).await;
+    assert_eq!(
+        error.error,
+        "The demo holds at most 2 todos until it resets"
+    );
+    let webhooks = TestRequest::get().uri("/api/webhooks");
+    app.expect_error(webhooks, 403).await;
+    let push = TestRequest::put()
+        .uri("/api/notifications/push")
+        .set_json(json!({ "provider": "ntfy", "url": "http://169.254.169.254/" }));
+    app.expect_error(push, 403).await;
+    let sync = TestRequest::post()
+        .uri("/api/sync")
+        .set_json(json!({ "changes": [] }));
+    app.expect_error(sync, 403).await;
+
+    // The fourth write of the minute still goes through; the fifth doesn't.
+    app.toggle_todo(&first.id.to_string()).await;
+    let resp = app
+        .call(TestRequest::patch().uri(&format!("/api/todos/{}/toggle", first.id)))
+        .await;
+    assert_eq!(resp.status(), 429);
+    assert!(resp.headers().contains_key("retry-after"));
+    assert_eq!(app.list_todos("").await.len(), 2);
+}